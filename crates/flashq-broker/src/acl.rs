@@ -0,0 +1,505 @@
+//! ACL-based authorization.
+//!
+//! An [`AclBinding`] allows or denies one operation on topics, groups or the cluster to a
+//! principal. With ACLs enabled, a call goes through only if some binding allows it and none
+//! denies it; super users skip the check. Principal `*` matches every principal, and a literal
+//! resource name `*` matches every resource of its type.
+
+use parking_lot::RwLock;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tonic::Status;
+
+use crate::auth::Principal;
+
+/// Name of the single cluster resource.
+pub const CLUSTER_RESOURCE_NAME: &str = "flashq-cluster";
+
+pub const WILDCARD: &str = "*";
+
+const ACLS_FILE_NAME: &str = "acls.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceType {
+    Topic,
+    Group,
+    Cluster,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternType {
+    Literal,
+    Prefixed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AclOperation {
+    All,
+    Read,
+    Write,
+    Create,
+    Delete,
+    Describe,
+    Alter,
+    ClusterAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AclPermission {
+    Allow,
+    Deny,
+}
+
+impl fmt::Display for ResourceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Topic => "topic",
+            Self::Group => "group",
+            Self::Cluster => "cluster",
+        })
+    }
+}
+
+impl fmt::Display for AclOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::All => "All",
+            Self::Read => "Read",
+            Self::Write => "Write",
+            Self::Create => "Create",
+            Self::Delete => "Delete",
+            Self::Describe => "Describe",
+            Self::Alter => "Alter",
+            Self::ClusterAction => "ClusterAction",
+        })
+    }
+}
+
+impl AclOperation {
+    /// Whether a binding for `self` covers a request for `requested`. `All` covers every
+    /// operation, and anything that reads or changes a resource also lets the caller describe it.
+    fn covers(self, requested: AclOperation) -> bool {
+        self == requested
+            || self == AclOperation::All
+            || (requested == AclOperation::Describe
+                && matches!(
+                    self,
+                    AclOperation::Read
+                        | AclOperation::Write
+                        | AclOperation::Delete
+                        | AclOperation::Alter
+                ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AclBinding {
+    pub principal: String,
+    pub resource_type: ResourceType,
+    pub resource_name: String,
+    pub pattern_type: PatternType,
+    pub operation: AclOperation,
+    pub permission: AclPermission,
+}
+
+impl AclBinding {
+    fn applies_to(
+        &self,
+        principal: &str,
+        operation: AclOperation,
+        resource_type: ResourceType,
+        resource_name: &str,
+    ) -> bool {
+        (self.principal == WILDCARD || self.principal == principal)
+            && self.resource_type == resource_type
+            && self.operation.covers(operation)
+            && match self.pattern_type {
+                PatternType::Literal => {
+                    self.resource_name == WILDCARD || self.resource_name == resource_name
+                }
+                PatternType::Prefixed => resource_name.starts_with(&self.resource_name),
+            }
+    }
+}
+
+/// Selects bindings by exact field values; `None` fields match anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AclFilter {
+    pub principal: Option<String>,
+    pub resource_type: Option<ResourceType>,
+    pub resource_name: Option<String>,
+    pub pattern_type: Option<PatternType>,
+    pub operation: Option<AclOperation>,
+    pub permission: Option<AclPermission>,
+}
+
+impl AclFilter {
+    pub fn matches(&self, binding: &AclBinding) -> bool {
+        self.principal
+            .as_ref()
+            .is_none_or(|p| *p == binding.principal)
+            && self
+                .resource_type
+                .is_none_or(|t| t == binding.resource_type)
+            && self
+                .resource_name
+                .as_ref()
+                .is_none_or(|n| *n == binding.resource_name)
+            && self.pattern_type.is_none_or(|p| p == binding.pattern_type)
+            && self.operation.is_none_or(|o| o == binding.operation)
+            && self.permission.is_none_or(|p| p == binding.permission)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AclsFile {
+    acls: Vec<AclBinding>,
+}
+
+/// Holds the ACLs and answers authorization checks.
+#[derive(Debug, Default)]
+pub struct Authorizer {
+    enabled: bool,
+    super_users: HashSet<String>,
+    acls: RwLock<Vec<AclBinding>>,
+    /// File the ACLs are persisted to; `None` keeps them in memory only.
+    path: Option<PathBuf>,
+}
+
+impl Authorizer {
+    /// Allow every call; ACLs can still be managed but are not enforced.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Enforce ACLs kept in memory.
+    pub fn in_memory() -> Self {
+        Self {
+            enabled: true,
+            ..Self::default()
+        }
+    }
+
+    /// Enforce ACLs persisted in `acls.json` under `data_dir`, loading any saved there.
+    pub fn open(data_dir: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = data_dir.as_ref().join(ACLS_FILE_NAME);
+        let acls = match std::fs::read(&path) {
+            Ok(bytes) => {
+                serde_json::from_slice::<AclsFile>(&bytes)
+                    .map_err(|e| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!("invalid ACL file {}: {e}", path.display()),
+                        )
+                    })?
+                    .acls
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            enabled: true,
+            super_users: HashSet::new(),
+            acls: RwLock::new(acls),
+            path: Some(path),
+        })
+    }
+
+    /// Principals that pass every check regardless of ACLs.
+    pub fn with_super_users(mut self, super_users: impl IntoIterator<Item = String>) -> Self {
+        self.super_users.extend(super_users);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn is_allowed(
+        &self,
+        principal: &Principal,
+        operation: AclOperation,
+        resource_type: ResourceType,
+        resource_name: &str,
+    ) -> bool {
+        if !self.enabled || self.super_users.contains(&principal.name) {
+            return true;
+        }
+        let acls = self.acls.read();
+        let mut allowed = false;
+        for binding in acls.iter().filter(|binding| {
+            binding.applies_to(&principal.name, operation, resource_type, resource_name)
+        }) {
+            match binding.permission {
+                AclPermission::Deny => return false,
+                AclPermission::Allow => allowed = true,
+            }
+        }
+        allowed
+    }
+
+    /// `PermissionDenied` naming the principal, operation and resource unless allowed.
+    pub fn authorize(
+        &self,
+        principal: &Principal,
+        operation: AclOperation,
+        resource_type: ResourceType,
+        resource_name: &str,
+    ) -> Result<(), Box<Status>> {
+        if self.is_allowed(principal, operation, resource_type, resource_name) {
+            return Ok(());
+        }
+        Err(Box::new(Status::permission_denied(format!(
+            "principal '{}' is not authorized to {operation} {resource_type} '{resource_name}'",
+            principal.name
+        ))))
+    }
+
+    /// Add `bindings`, skipping ones already present. Returns how many were added.
+    pub fn create_acls(&self, bindings: Vec<AclBinding>) -> std::io::Result<usize> {
+        let mut acls = self.acls.write();
+        let mut updated = acls.clone();
+        for binding in bindings {
+            if !updated.contains(&binding) {
+                updated.push(binding);
+            }
+        }
+        let added = updated.len() - acls.len();
+        if added > 0 {
+            self.persist(&updated)?;
+            *acls = updated;
+        }
+        Ok(added)
+    }
+
+    pub fn list_acls(&self, filter: &AclFilter) -> Vec<AclBinding> {
+        self.acls
+            .read()
+            .iter()
+            .filter(|binding| filter.matches(binding))
+            .cloned()
+            .collect()
+    }
+
+    /// Remove and return the bindings `filter` matches.
+    pub fn delete_acls(&self, filter: &AclFilter) -> std::io::Result<Vec<AclBinding>> {
+        let mut acls = self.acls.write();
+        let (deleted, kept): (Vec<_>, Vec<_>) = acls
+            .iter()
+            .cloned()
+            .partition(|binding| filter.matches(binding));
+        if !deleted.is_empty() {
+            self.persist(&kept)?;
+            *acls = kept;
+        }
+        Ok(deleted)
+    }
+
+    fn persist(&self, acls: &[AclBinding]) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&AclsFile {
+            acls: acls.to_vec(),
+        })
+        .map_err(std::io::Error::other)?;
+        let staging = path.with_extension("json.tmp");
+        std::fs::write(&staging, json)?;
+        std::fs::rename(&staging, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AuthMechanism;
+
+    fn principal(name: &str) -> Principal {
+        Principal {
+            name: name.to_string(),
+            mechanism: AuthMechanism::Token,
+        }
+    }
+
+    fn binding(
+        principal: &str,
+        resource_type: ResourceType,
+        resource_name: &str,
+        pattern_type: PatternType,
+        operation: AclOperation,
+        permission: AclPermission,
+    ) -> AclBinding {
+        AclBinding {
+            principal: principal.to_string(),
+            resource_type,
+            resource_name: resource_name.to_string(),
+            pattern_type,
+            operation,
+            permission,
+        }
+    }
+
+    #[test]
+    fn test_allow_deny_and_pattern_matching() {
+        let authorizer = Authorizer::in_memory().with_super_users(["admin".to_string()]);
+        authorizer
+            .create_acls(vec![
+                binding(
+                    "alice",
+                    ResourceType::Topic,
+                    "pay",
+                    PatternType::Prefixed,
+                    AclOperation::Write,
+                    AclPermission::Allow,
+                ),
+                binding(
+                    "alice",
+                    ResourceType::Topic,
+                    "payments-audit",
+                    PatternType::Literal,
+                    AclOperation::All,
+                    AclPermission::Deny,
+                ),
+                binding(
+                    WILDCARD,
+                    ResourceType::Group,
+                    WILDCARD,
+                    PatternType::Literal,
+                    AclOperation::Read,
+                    AclPermission::Allow,
+                ),
+            ])
+            .unwrap();
+        let alice = principal("alice");
+        let bob = principal("bob");
+
+        assert!(authorizer.is_allowed(
+            &alice,
+            AclOperation::Write,
+            ResourceType::Topic,
+            "payments"
+        ));
+        assert!(authorizer.is_allowed(
+            &alice,
+            AclOperation::Describe,
+            ResourceType::Topic,
+            "payments"
+        ));
+        assert!(!authorizer.is_allowed(
+            &alice,
+            AclOperation::Read,
+            ResourceType::Topic,
+            "payments"
+        ));
+        assert!(!authorizer.is_allowed(
+            &alice,
+            AclOperation::Write,
+            ResourceType::Topic,
+            "payments-audit"
+        ));
+        assert!(!authorizer.is_allowed(&bob, AclOperation::Write, ResourceType::Topic, "payments"));
+        assert!(authorizer.is_allowed(&bob, AclOperation::Read, ResourceType::Group, "billing"));
+        assert!(authorizer.is_allowed(
+            &principal("admin"),
+            AclOperation::Alter,
+            ResourceType::Cluster,
+            CLUSTER_RESOURCE_NAME
+        ));
+
+        let denied = authorizer
+            .authorize(&bob, AclOperation::Write, ResourceType::Topic, "payments")
+            .unwrap_err();
+        assert_eq!(denied.code(), tonic::Code::PermissionDenied);
+        assert!(denied.message().contains("topic 'payments'"));
+    }
+
+    #[test]
+    fn test_disabled_authorizer_allows_everything() {
+        let authorizer = Authorizer::disabled();
+        assert!(authorizer.is_allowed(
+            &Principal::anonymous(),
+            AclOperation::Delete,
+            ResourceType::Group,
+            "billing"
+        ));
+    }
+
+    #[test]
+    fn test_acls_persist_across_reopen_and_delete_by_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        let authorizer = Authorizer::open(dir.path()).unwrap();
+        let read_billing = binding(
+            "alice",
+            ResourceType::Group,
+            "billing",
+            PatternType::Literal,
+            AclOperation::Read,
+            AclPermission::Allow,
+        );
+        let write_payments = binding(
+            "alice",
+            ResourceType::Topic,
+            "payments",
+            PatternType::Literal,
+            AclOperation::Write,
+            AclPermission::Allow,
+        );
+        assert_eq!(
+            authorizer
+                .create_acls(vec![read_billing.clone(), write_payments.clone()])
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            authorizer.create_acls(vec![read_billing.clone()]).unwrap(),
+            0
+        );
+
+        let reopened = Authorizer::open(dir.path()).unwrap();
+        assert_eq!(reopened.list_acls(&AclFilter::default()).len(), 2);
+
+        let deleted = reopened
+            .delete_acls(&AclFilter {
+                resource_type: Some(ResourceType::Topic),
+                ..AclFilter::default()
+            })
+            .unwrap();
+        assert_eq!(deleted, vec![write_payments]);
+        assert_eq!(
+            Authorizer::open(dir.path())
+                .unwrap()
+                .list_acls(&AclFilter::default()),
+            vec![read_billing]
+        );
+    }
+
+    #[test]
+    fn test_failed_persist_leaves_acls_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let authorizer = Authorizer::open(dir.path().join("missing")).unwrap();
+        let read_billing = binding(
+            "alice",
+            ResourceType::Group,
+            "billing",
+            PatternType::Literal,
+            AclOperation::Read,
+            AclPermission::Allow,
+        );
+
+        assert!(authorizer.create_acls(vec![read_billing]).is_err());
+        assert!(authorizer.list_acls(&AclFilter::default()).is_empty());
+        assert!(!authorizer.is_allowed(
+            &principal("alice"),
+            AclOperation::Read,
+            ResourceType::Group,
+            "billing"
+        ));
+    }
+}
@@ -0,0 +1,328 @@
+//! Authentication for the broker's gRPC services.
+//!
+//! [`AuthInterceptor`] wraps every service. It checks the call's credentials against a
+//! [`CredentialStore`] and stores the resulting [`Principal`] in the request extensions,
+//! where handlers read it back with [`Principal::from_request`]. Without a store every call
+//! runs as [`Principal::anonymous`].
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use flashq_proto::auth::{CallCredentials, ScramCredential, constant_time_eq};
+use parking_lot::Mutex;
+use serde::Deserialize;
+use tonic::{Request, Status};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMechanism {
+    Anonymous,
+    Token,
+    Password,
+}
+
+/// The identity a call was authenticated as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub name: String,
+    pub mechanism: AuthMechanism,
+}
+
+impl Principal {
+    pub const ANONYMOUS: &'static str = "ANONYMOUS";
+
+    pub fn anonymous() -> Self {
+        Self {
+            name: Self::ANONYMOUS.to_string(),
+            mechanism: AuthMechanism::Anonymous,
+        }
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        self.mechanism == AuthMechanism::Anonymous
+    }
+
+    /// The principal [`AuthInterceptor`] attached to `request`; anonymous when the request
+    /// did not pass through one, as in handler unit tests.
+    pub fn from_request<T>(request: &Request<T>) -> Self {
+        request
+            .extensions()
+            .get::<Principal>()
+            .cloned()
+            .unwrap_or_else(Self::anonymous)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CredentialsFile {
+    #[serde(default)]
+    tokens: Vec<TokenEntry>,
+    #[serde(default)]
+    users: Vec<UserEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TokenEntry {
+    principal: String,
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UserEntry {
+    username: String,
+    /// `iterations=<n>,salt=<base64>,stored_key=<base64>`, as printed by
+    /// `flashq-client hash-password`.
+    scram: String,
+}
+
+/// Static API tokens and password users loaded from a JSON credentials file:
+///
+/// ```json
+/// {
+///   "tokens": [{ "principal": "ingest-service", "token": "s3cr3t" }],
+///   "users": [{ "username": "alice", "scram": "iterations=4096,salt=...,stored_key=..." }]
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct CredentialStore {
+    tokens: Vec<(Vec<u8>, String)>,
+    users: HashMap<String, ScramCredential>,
+    /// SHA-256 of the password each user last authenticated with, so repeat calls skip the
+    /// PBKDF2 derivation.
+    verified: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl CredentialStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_token(mut self, principal: impl Into<String>, token: impl Into<String>) -> Self {
+        self.tokens
+            .push((token.into().into_bytes(), principal.into()));
+        self
+    }
+
+    pub fn with_user(mut self, username: impl Into<String>, credential: ScramCredential) -> Self {
+        self.users.insert(username.into(), credential);
+        self
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let invalid = |reason: String| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid credentials file {}: {reason}", path.display()),
+            )
+        };
+        let file: CredentialsFile =
+            serde_json::from_slice(&std::fs::read(path)?).map_err(|e| invalid(e.to_string()))?;
+
+        let mut store = Self::new();
+        for entry in file.tokens {
+            if entry.token.is_empty() {
+                return Err(invalid(format!("empty token for '{}'", entry.principal)));
+            }
+            store = store.with_token(entry.principal, entry.token);
+        }
+        for entry in file.users {
+            let credential = entry
+                .scram
+                .parse::<ScramCredential>()
+                .map_err(|e| invalid(format!("user '{}': {e}", entry.username)))?;
+            if store.users.contains_key(&entry.username) {
+                return Err(invalid(format!("duplicate user '{}'", entry.username)));
+            }
+            store = store.with_user(entry.username, credential);
+        }
+        Ok(store)
+    }
+
+    pub fn authenticate(&self, credentials: &CallCredentials) -> Result<Principal, Box<Status>> {
+        match credentials {
+            CallCredentials::None => Err(Box::new(Status::unauthenticated("credentials required"))),
+            CallCredentials::Token(token) => self
+                .tokens
+                .iter()
+                .find(|(known, _)| constant_time_eq(known, token.as_bytes()))
+                .map(|(_, principal)| Principal {
+                    name: principal.clone(),
+                    mechanism: AuthMechanism::Token,
+                })
+                .ok_or_else(|| Box::new(Status::unauthenticated("invalid token"))),
+            CallCredentials::Password { username, password } => {
+                if self.verify_password(username, password) {
+                    Ok(Principal {
+                        name: username.clone(),
+                        mechanism: AuthMechanism::Password,
+                    })
+                } else {
+                    Err(Box::new(Status::unauthenticated(
+                        "invalid username or password",
+                    )))
+                }
+            }
+        }
+    }
+
+    fn verify_password(&self, username: &str, password: &str) -> bool {
+        let Some(credential) = self.users.get(username) else {
+            return false;
+        };
+        let fingerprint = ring::digest::digest(&ring::digest::SHA256, password.as_bytes());
+        if self
+            .verified
+            .lock()
+            .get(username)
+            .is_some_and(|known| constant_time_eq(known, fingerprint.as_ref()))
+        {
+            return true;
+        }
+        // PBKDF2 is deliberately slow; run it without holding the cache lock
+        if !credential.verify(password) {
+            return false;
+        }
+        self.verified
+            .lock()
+            .insert(username.to_string(), fingerprint.as_ref().to_vec());
+        true
+    }
+}
+
+/// Server interceptor that authenticates each call and attaches its [`Principal`].
+#[derive(Debug, Clone, Default)]
+pub struct AuthInterceptor {
+    store: Option<Arc<CredentialStore>>,
+}
+
+impl AuthInterceptor {
+    /// Accept every call as anonymous.
+    pub fn disabled() -> Self {
+        Self { store: None }
+    }
+
+    /// Require every call to carry credentials known to `store`.
+    pub fn new(store: Arc<CredentialStore>) -> Self {
+        Self { store: Some(store) }
+    }
+}
+
+impl tonic::service::Interceptor for AuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let principal = match &self.store {
+            None => Principal::anonymous(),
+            Some(store) => {
+                let credentials =
+                    CallCredentials::from_metadata(request.metadata()).map_err(|e| *e)?;
+                store.authenticate(&credentials).map_err(|e| *e)?
+            }
+        };
+        request.extensions_mut().insert(principal);
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::service::Interceptor;
+
+    fn store() -> Arc<CredentialStore> {
+        Arc::new(
+            CredentialStore::new()
+                .with_token("ingest", "tok-1")
+                .with_user(
+                    "alice",
+                    ScramCredential::derive("wonderland", b"salt".to_vec(), 64).unwrap(),
+                ),
+        )
+    }
+
+    fn intercept(
+        interceptor: &mut AuthInterceptor,
+        credentials: CallCredentials,
+    ) -> Result<Principal, Box<Status>> {
+        let request = CallCredentials::call(&mut credentials.clone(), Request::new(()))?;
+        let request = interceptor.call(request).map_err(Box::new)?;
+        Ok(Principal::from_request(&request))
+    }
+
+    #[test]
+    fn test_interceptor_attaches_authenticated_principal() {
+        let mut interceptor = AuthInterceptor::new(store());
+
+        let by_token = intercept(&mut interceptor, CallCredentials::token("tok-1")).unwrap();
+        assert_eq!(by_token.name, "ingest");
+        assert_eq!(by_token.mechanism, AuthMechanism::Token);
+
+        for _ in 0..2 {
+            let by_password = intercept(
+                &mut interceptor,
+                CallCredentials::password("alice", "wonderland"),
+            )
+            .unwrap();
+            assert_eq!(by_password.name, "alice");
+            assert_eq!(by_password.mechanism, AuthMechanism::Password);
+        }
+    }
+
+    #[test]
+    fn test_interceptor_rejects_missing_and_wrong_credentials() {
+        let mut interceptor = AuthInterceptor::new(store());
+        for credentials in [
+            CallCredentials::None,
+            CallCredentials::token("tok-2"),
+            CallCredentials::password("alice", "looking-glass"),
+            CallCredentials::password("bob", "wonderland"),
+        ] {
+            let status = intercept(&mut interceptor, credentials).unwrap_err();
+            assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        }
+    }
+
+    #[test]
+    fn test_disabled_interceptor_runs_calls_as_anonymous() {
+        let principal = intercept(
+            &mut AuthInterceptor::disabled(),
+            CallCredentials::token("any"),
+        )
+        .unwrap();
+        assert!(principal.is_anonymous());
+    }
+
+    #[test]
+    fn test_credentials_file_round_trips_scram_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.json");
+        let scram = ScramCredential::generate("wonderland", 64).unwrap();
+        std::fs::write(
+            &path,
+            format!(
+                r#"{{"tokens":[{{"principal":"ingest","token":"tok-1"}}],
+                    "users":[{{"username":"alice","scram":"{scram}"}}]}}"#
+            ),
+        )
+        .unwrap();
+
+        let store = CredentialStore::from_path(&path).unwrap();
+        assert_eq!(
+            store
+                .authenticate(&CallCredentials::password("alice", "wonderland"))
+                .unwrap()
+                .name,
+            "alice"
+        );
+
+        std::fs::write(
+            &path,
+            r#"{"users":[{"username":"alice","scram":"salt=AA=="}]}"#,
+        )
+        .unwrap();
+        let err = CredentialStore::from_path(&path).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -1,12 +1,20 @@
 use std::{net::SocketAddr, path::PathBuf, sync::Arc};
 
 use clap::{Parser, ValueEnum};
+use flashq_broker::acl::Authorizer;
+use flashq_broker::auth::{AuthInterceptor, CredentialStore};
 use flashq_broker::broker::FlashQBroker;
+use flashq_broker::lifecycle::Lifecycle;
+use flashq_broker::quota::{QuotaEnforcement, QuotaManager};
 use flashq_cluster::{
     manifest::loader::ManifestLoader, metadata_store::MetadataBackend, service::ClusterServiceImpl,
     storage::StorageBackend, types::BrokerId,
 };
-use flashq_storage::SyncMode;
+use flashq_proto::auth::CallCredentials;
+use flashq_proto::tls::{ClientTlsOptions, ServerTlsOptions};
+use flashq_storage::{
+    CompressionCodec, FsRemoteSegmentStore, SyncMode, TieredStorageConfig, TimestampType,
+};
 
 #[derive(Copy, Clone, Debug, ValueEnum)]
 enum StorageKind {
@@ -31,6 +39,28 @@ impl From<FileSyncMode> for SyncMode {
     }
 }
 
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum OffsetStoreKind {
+    Json,
+    Sqlite,
+    Topic,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum QuotaEnforcementArg {
+    Throttle,
+    Reject,
+}
+
+impl From<QuotaEnforcementArg> for QuotaEnforcement {
+    fn from(v: QuotaEnforcementArg) -> Self {
+        match v {
+            QuotaEnforcementArg::Throttle => QuotaEnforcement::Throttle,
+            QuotaEnforcementArg::Reject => QuotaEnforcement::Reject,
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 #[command(name = "flashq-broker", version, author, about = "FlashQ broker")]
 struct Args {
@@ -54,6 +84,47 @@ struct Args {
     #[arg(long, value_enum, default_value_t = FileSyncMode::None)]
     sync: FileSyncMode,
 
+    /// Consumer offset store: one JSON file per group, a shared SQLite database that imports
+    /// existing JSON files on first start, or the internal `__consumer_offsets` topic
+    /// (file backend only)
+    #[arg(long, value_enum, default_value_t = OffsetStoreKind::Json)]
+    offset_store: OffsetStoreKind,
+
+    /// Default record batch compression: none, gzip, snappy, lz4 or zstd (file backend only)
+    #[arg(long, default_value_t = CompressionCodec::None)]
+    compression: CompressionCodec,
+
+    /// Roll segments once their first batch is this many milliseconds old (file backend only)
+    #[arg(long)]
+    segment_ms: Option<u64>,
+
+    /// Roll segments once their offset index holds this many entries (file backend only)
+    #[arg(long)]
+    segment_index_max_entries: Option<usize>,
+
+    /// Timestamp stored with each record: CreateTime keeps producer timestamps, LogAppendTime
+    /// always uses the broker clock (file backend only)
+    #[arg(long, default_value_t = TimestampType::CreateTime)]
+    timestamp_type: TimestampType,
+
+    /// Reject producer timestamps more than this many milliseconds from the broker clock
+    /// (file backend only)
+    #[arg(long)]
+    max_timestamp_skew_ms: Option<u64>,
+
+    /// Dedicated storage I/O threads (file backend only; defaults to available cores)
+    #[arg(long)]
+    io_threads: Option<usize>,
+
+    /// Directory acting as the remote object store for tiered storage (file backend only)
+    #[arg(long)]
+    remote_storage_dir: Option<PathBuf>,
+
+    /// How long closed segments stay on local disk before offload to remote storage; checked
+    /// whenever a segment rolls and by a background sweep (at most every minute)
+    #[arg(long, default_value_t = 24 * 60 * 60 * 1000)]
+    local_retention_ms: u64,
+
     /// Cluster manifest file path
     #[arg(long)]
     manifest: Option<PathBuf>,
@@ -69,24 +140,227 @@ struct Args {
     /// Connection timeout for cluster client in seconds
     #[arg(long, default_value_t = 10)]
     cluster_timeout: u64,
+
+    /// PEM certificate chain served to clients; enables TLS together with --tls-key
+    #[arg(long, requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key for --tls-cert
+    #[arg(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// PEM CA bundle that client certificates must chain to (enables mutual TLS)
+    #[arg(long, requires = "tls_cert")]
+    tls_client_ca: Option<PathBuf>,
+
+    /// PEM CA bundle for verifying the cluster controller; connects over TLS, presenting
+    /// --tls-cert/--tls-key as the client identity when they are set
+    #[arg(long)]
+    cluster_tls_ca: Option<PathBuf>,
+
+    /// JSON file of API tokens and SCRAM password users; every call must authenticate
+    #[arg(long)]
+    credentials_file: Option<PathBuf>,
+
+    /// API token this broker presents to the cluster controller
+    #[arg(
+        long,
+        env = "FLASHQ_CLUSTER_TOKEN",
+        conflicts_with = "cluster_username"
+    )]
+    cluster_token: Option<String>,
+
+    /// Username this broker presents to the cluster controller
+    #[arg(long, requires = "cluster_password")]
+    cluster_username: Option<String>,
+
+    /// Password for --cluster-username
+    #[arg(long, env = "FLASHQ_CLUSTER_PASSWORD", requires = "cluster_username")]
+    cluster_password: Option<String>,
+
+    /// Enforce ACLs; kept in <data-dir>/acls.json with file storage, in memory otherwise
+    #[arg(long)]
+    enable_acls: bool,
+
+    /// Principal that bypasses ACL checks (repeatable)
+    #[arg(long = "super-user", requires = "enable_acls")]
+    super_users: Vec<String>,
+
+    /// What to do with produce and fetch calls over a quota: delay them until the client is
+    /// back within it, or fail them with RESOURCE_EXHAUSTED (429 over REST). Quotas are set
+    /// with the SetQuota admin RPC and kept in <data-dir>/quotas.json with file storage
+    #[arg(long, value_enum, default_value_t = QuotaEnforcementArg::Throttle)]
+    quota_enforcement: QuotaEnforcementArg,
+
+    /// Export spans over OTLP/gRPC to this collector, e.g. http://127.0.0.1:4317
+    #[arg(long, env = "OTEL_EXPORTER_OTLP_ENDPOINT")]
+    otlp_endpoint: Option<String>,
+
+    /// Serve the HTTP/JSON REST API (docs/openapi.yaml) on this address. The REST API is
+    /// unauthenticated, so it cannot be combined with credentials or ACLs
+    #[arg(long, conflicts_with_all = ["credentials_file", "enable_acls"])]
+    http_addr: Option<SocketAddr>,
+
+    /// Serve a subset of the Kafka protocol on this address. Like the REST API it is
+    /// unauthenticated, so it cannot be combined with credentials or ACLs
+    #[arg(long, conflicts_with_all = ["credentials_file", "enable_acls"])]
+    kafka_addr: Option<SocketAddr>,
+
+    /// Host Kafka clients are told to connect to; defaults to the --kafka-addr IP, or
+    /// localhost when that is unspecified
+    #[arg(long, requires = "kafka_addr")]
+    kafka_advertised_host: Option<String>,
+
+    /// Serve Prometheus metrics over HTTP at /metrics on this address
+    #[cfg(feature = "metrics")]
+    #[arg(long)]
+    metrics_addr: Option<SocketAddr>,
+}
+
+impl Args {
+    fn server_tls(&self) -> Option<ServerTlsOptions> {
+        let (cert, key) = (self.tls_cert.as_ref()?, self.tls_key.as_ref()?);
+        let tls = ServerTlsOptions::new(cert, key);
+        Some(match &self.tls_client_ca {
+            Some(ca) => tls.with_client_ca(ca),
+            None => tls,
+        })
+    }
+
+    fn cluster_credentials(&self) -> CallCredentials {
+        match (
+            &self.cluster_token,
+            &self.cluster_username,
+            &self.cluster_password,
+        ) {
+            (Some(token), _, _) => CallCredentials::token(token),
+            (None, Some(username), Some(password)) => CallCredentials::password(username, password),
+            _ => CallCredentials::None,
+        }
+    }
+
+    fn cluster_tls(&self) -> Option<ClientTlsOptions> {
+        let tls = ClientTlsOptions::new(self.cluster_tls_ca.as_ref()?);
+        Some(match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => tls.with_identity(cert, key),
+            _ => tls,
+        })
+    }
+}
+
+/// Begin shutting down on SIGTERM or Ctrl-C.
+async fn shutdown_on_signal(lifecycle: Lifecycle) {
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate => {}
+    }
+    tracing::info!("Received shutdown signal");
+    lifecycle.shutdown();
+}
+
+/// Offload segments past the local retention window every `interval` until shutdown.
+/// Topics also offload when they roll, but an idle topic never rolls.
+async fn offload_segments_periodically(
+    core: Arc<flashq_cluster::FlashQ>,
+    interval: std::time::Duration,
+    lifecycle: Lifecycle,
+) {
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {}
+            _ = lifecycle.shutdown_requested() => return,
+        }
+        match core
+            .run_blocking(|core| Ok(core.offload_expired_segments()))
+            .await
+        {
+            Ok(0) => {}
+            Ok(offloaded) => tracing::info!(offloaded, "Offloaded expired segments"),
+            Err(e) => tracing::error!("Segment offload sweep failed: {}", e),
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     use std::time::Duration;
 
-    // Logging via tracing-subscriber with env filter support
-    tracing_subscriber::fmt::init();
-
     let args = Args::parse();
+
+    // Logging via tracing-subscriber with env filter support, plus span export when asked
+    let _otlp = match &args.otlp_endpoint {
+        Some(endpoint) => Some(flashq::telemetry::init_with_otlp(
+            endpoint,
+            "flashq-broker",
+        )?),
+        None => {
+            tracing_subscriber::fmt::init();
+            None
+        }
+    };
     let addr: SocketAddr = format!("{}:{}", args.addr, args.port).parse()?;
 
-    let backend = match args.storage {
+    let mut backend = match args.storage {
         StorageKind::Memory => StorageBackend::new_memory(),
-        StorageKind::File => StorageBackend::new_file_with_path(args.sync.into(), &args.data_dir)?,
+        StorageKind::File => StorageBackend::new_file_with_path(args.sync.into(), &args.data_dir)?
+            .with_compression(args.compression)
+            .with_timestamp_type(args.timestamp_type),
+    };
+    backend = match args.offset_store {
+        OffsetStoreKind::Json => backend,
+        OffsetStoreKind::Sqlite => backend.with_sqlite_offset_store()?,
+        OffsetStoreKind::Topic => backend.with_offsets_topic()?,
     };
+    if let Some(segment_ms) = args.segment_ms {
+        backend = backend.with_segment_ms(segment_ms);
+    }
+    if let Some(max_entries) = args.segment_index_max_entries {
+        backend = backend.with_segment_index_max_entries(max_entries);
+    }
+    if let Some(max_skew_ms) = args.max_timestamp_skew_ms {
+        backend = backend.with_max_timestamp_skew_ms(max_skew_ms);
+    }
+    if let Some(threads) = args.io_threads {
+        backend = backend.with_io_threads(threads);
+    }
+    if let Some(remote_dir) = &args.remote_storage_dir {
+        let store = Arc::new(FsRemoteSegmentStore::new(remote_dir)?);
+        backend = backend.with_tiered_storage(
+            TieredStorageConfig::new(store).with_local_retention_ms(args.local_retention_ms),
+        );
+    }
 
     let core = Arc::new(flashq_cluster::FlashQ::with_storage_backend(backend));
+    let lifecycle = Lifecycle::new();
+    tokio::spawn(shutdown_on_signal(lifecycle.clone()));
+    if args.remote_storage_dir.is_some() {
+        // Sweep often enough for short retention windows without busy-looping on tiny ones
+        let interval = Duration::from_millis(args.local_retention_ms.clamp(1_000, 60_000));
+        tokio::spawn(offload_segments_periodically(
+            core.clone(),
+            interval,
+            lifecycle.clone(),
+        ));
+    }
+    // Listeners that must finish their in-flight requests before storage is synced
+    let mut listeners = Vec::new();
 
     // Create metadata store backend for cluster operations
     let metadata_backend = match args.storage {
@@ -95,29 +369,104 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     // Create metadata store with optional manifest
-    let metadata_store = if let Some(manifest_path) = args.manifest {
-        let manifest = ManifestLoader::from_path(&manifest_path)?;
+    let metadata_store = if let Some(manifest_path) = &args.manifest {
+        let manifest = ManifestLoader::from_path(manifest_path)?;
         metadata_backend.create_with_manifest(manifest)?
     } else {
         metadata_backend.create()?
     };
 
+    #[cfg(feature = "metrics")]
+    if let Some(metrics_addr) = args.metrics_addr {
+        let listener = tokio::net::TcpListener::bind(metrics_addr).await?;
+        let endpoint =
+            flashq_broker::metrics::MetricsEndpoint::install(core.clone(), metadata_store.clone())?;
+        tracing::info!(%metrics_addr, "Serving Prometheus metrics at /metrics");
+        tokio::spawn(async move {
+            if let Err(e) = endpoint.serve(listener).await {
+                tracing::error!("Metrics endpoint failed: {}", e);
+            }
+        });
+    }
+
+    // Shared by the gRPC, REST and Kafka front ends, so a client is limited across all of them
+    let quotas = Arc::new(
+        match args.storage {
+            StorageKind::Memory => QuotaManager::in_memory(),
+            StorageKind::File => QuotaManager::open(&args.data_dir)?,
+        }
+        .with_enforcement(args.quota_enforcement.into()),
+    );
+
+    if let Some(http_addr) = args.http_addr {
+        let listener = tokio::net::TcpListener::bind(http_addr).await?;
+        tracing::info!(%http_addr, "Serving REST API");
+        let core = core.clone();
+        let lifecycle = lifecycle.clone();
+        let quotas = quotas.clone();
+        listeners.push(tokio::spawn(async move {
+            if let Err(e) = flashq_broker::rest::serve(listener, core, lifecycle, quotas).await {
+                tracing::error!("REST API failed: {}", e);
+            }
+        }));
+    }
+
+    if let Some(kafka_addr) = args.kafka_addr {
+        let listener = tokio::net::TcpListener::bind(kafka_addr).await?;
+        let port = listener.local_addr()?.port();
+        let host = args.kafka_advertised_host.clone().unwrap_or_else(|| {
+            if kafka_addr.ip().is_unspecified() {
+                "localhost".to_string()
+            } else {
+                kafka_addr.ip().to_string()
+            }
+        });
+        tracing::info!(%kafka_addr, %host, "Serving Kafka protocol");
+        let kafka = flashq_broker::kafka::KafkaListener::new(
+            core.clone(),
+            args.broker_id as i32,
+            host,
+            port,
+        )
+        .with_lifecycle(lifecycle.clone())
+        .with_quotas(quotas.clone());
+        listeners.push(tokio::spawn(async move {
+            if let Err(e) = kafka.serve(listener).await {
+                tracing::error!("Kafka listener failed: {}", e);
+            }
+        }));
+    }
+
     let broker_id = BrokerId(args.broker_id);
 
     // Create FlashQBroker implementation from the gRPC service
-    let flashq_service = Arc::new(FlashQBroker::new(core.clone()));
+    let flashq_service =
+        Arc::new(FlashQBroker::new(core.clone()).with_lifecycle(lifecycle.clone()));
 
     // Create cluster service with optional cluster client
-    let cluster_service = if let Some(controller_endpoint) = args.cluster_controller {
+    let cluster_service = if let Some(controller_endpoint) = args.cluster_controller.clone() {
         tracing::info!(%controller_endpoint, "Connecting to cluster controller");
 
         // Create cluster client with timeout
         let timeout = Duration::from_secs(args.cluster_timeout);
-        let cluster_client = flashq_cluster::client::ClusterClient::connect_with_timeout(
-            controller_endpoint,
-            timeout,
-        )
-        .await?;
+        let cluster_client = match args.cluster_tls() {
+            Some(tls) => {
+                flashq_cluster::client::ClusterClient::connect_with_tls(
+                    controller_endpoint,
+                    &tls,
+                    Some(timeout),
+                )
+                .await?
+            }
+            None => {
+                flashq_cluster::client::ClusterClient::connect_with_timeout(
+                    controller_endpoint,
+                    timeout,
+                )
+                .await?
+            }
+        }
+        .with_credentials(args.cluster_credentials());
 
         tracing::info!("Successfully connected to cluster controller");
         let service = Arc::new(ClusterServiceImpl::with_client_and_broker(
@@ -143,8 +492,32 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         ))
     };
 
-    tracing::info!(%addr, broker_id = %args.broker_id, "Starting FlashQ gRPC server with cluster support");
+    let auth = match &args.credentials_file {
+        Some(path) => AuthInterceptor::new(Arc::new(CredentialStore::from_path(path)?)),
+        None => AuthInterceptor::disabled(),
+    };
+    let authorizer = if args.enable_acls {
+        match args.storage {
+            StorageKind::Memory => Authorizer::in_memory(),
+            StorageKind::File => Authorizer::open(&args.data_dir)?,
+        }
+        .with_super_users(args.super_users.clone())
+    } else {
+        Authorizer::disabled()
+    };
+    let tls = args.server_tls();
+    tracing::info!(%addr, broker_id = %args.broker_id, tls = tls.is_some(), mutual_tls = args.tls_client_ca.is_some(), auth = args.credentials_file.is_some(), acls = args.enable_acls, "Starting FlashQ gRPC server with cluster support");
     let cluster_server = flashq_cluster::ClusterServer::new(cluster_service);
-    flashq_broker::broker::serve(addr, core, cluster_server).await?;
+    let svc = FlashQBroker::new(core.clone())
+        .with_authorizer(Arc::new(authorizer))
+        .with_quotas(quotas)
+        .with_lifecycle(lifecycle);
+    flashq_broker::broker::serve(addr, svc, cluster_server, tls, auth).await?;
+
+    for listener in listeners {
+        let _ = listener.await;
+    }
+    core.run_blocking(|core| core.sync_all()).await?;
+    tracing::info!("Storage synced; broker stopped");
     Ok(())
 }
@@ -1,14 +1,22 @@
+use flashq_proto::trace_context;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
 use tower_http::trace::TraceLayer;
 
+use flashq_cluster::storage::{CompressionCodec, ReadBounds, StorageError, is_internal_topic};
+
+use crate::acl::{self, Authorizer};
+use crate::auth::{AuthInterceptor, Principal};
 use crate::flashq::v1::admin_server::Admin;
 use crate::flashq::v1::consumer_server::Consumer;
 use crate::flashq::v1::producer_server::Producer;
 use crate::flashq::v1::*;
+use crate::lifecycle::Lifecycle;
+use crate::quota::{self, QuotaManager, QuotaMetric, QuotaSubjects};
+use tonic::service::Interceptor;
 
-mod validation {
+pub(crate) mod validation {
     use tonic::Status;
 
     pub const MAX_KEY_SIZE: usize = 1024;
@@ -38,13 +46,13 @@ mod validation {
 
         // Validate header values
         if let Some(headers) = &record.headers {
-            for (header_key, header_value) in headers {
-                if header_value.len() > MAX_HEADER_VALUE_SIZE {
+            for header in headers {
+                if header.value.len() > MAX_HEADER_VALUE_SIZE {
                     return Err(Box::new(Status::invalid_argument(format!(
                         "Header '{}' value exceeds maximum length of {} characters (got {})",
-                        header_key,
+                        header.key,
                         MAX_HEADER_VALUE_SIZE,
-                        header_value.len()
+                        header.value.len()
                     ))));
                 }
             }
@@ -57,11 +65,75 @@ mod validation {
 #[derive(Clone)]
 pub struct FlashQBroker {
     pub core: Arc<flashq_cluster::FlashQ>,
+    authorizer: Arc<Authorizer>,
+    quotas: Arc<QuotaManager>,
+    lifecycle: Lifecycle,
 }
 
 impl FlashQBroker {
     pub fn new(core: Arc<flashq_cluster::FlashQ>) -> Self {
-        Self { core }
+        Self {
+            core,
+            authorizer: Arc::new(Authorizer::disabled()),
+            quotas: Arc::new(QuotaManager::in_memory()),
+            lifecycle: Lifecycle::new(),
+        }
+    }
+
+    /// Check every call against `authorizer`'s ACLs.
+    pub fn with_authorizer(mut self, authorizer: Arc<Authorizer>) -> Self {
+        self.authorizer = authorizer;
+        self
+    }
+
+    /// Charge produce and fetch calls against `quotas`.
+    pub fn with_quotas(mut self, quotas: Arc<QuotaManager>) -> Self {
+        self.quotas = quotas;
+        self
+    }
+
+    /// Refuse produces and end subscriptions as `lifecycle` drains and shuts down.
+    pub fn with_lifecycle(mut self, lifecycle: Lifecycle) -> Self {
+        self.lifecycle = lifecycle;
+        self
+    }
+
+    pub fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    /// Wait out, or reject, a call from subjects already over a quota on `metrics`.
+    async fn enforce_quotas(
+        &self,
+        subjects: &QuotaSubjects<'_>,
+        metrics: &[QuotaMetric],
+    ) -> Result<(), Box<Status>> {
+        self.quotas
+            .enforce(subjects, metrics)
+            .await
+            .map_err(|violation| Box::new(Status::resource_exhausted(violation.to_string())))
+    }
+
+    fn authorize(
+        &self,
+        principal: &Principal,
+        operation: acl::AclOperation,
+        resource_type: acl::ResourceType,
+        resource_name: &str,
+    ) -> Result<(), Box<Status>> {
+        self.authorizer
+            .authorize(principal, operation, resource_type, resource_name)
+    }
+
+    fn authorize_topic_and_group(
+        &self,
+        principal: &Principal,
+        operation: acl::AclOperation,
+        topic: &str,
+        group_id: &str,
+    ) -> Result<(), Box<Status>> {
+        self.authorize(principal, operation, acl::ResourceType::Topic, topic)?;
+        self.authorize(principal, operation, acl::ResourceType::Group, group_id)
     }
 }
 
@@ -73,18 +145,244 @@ fn to_proto_record(
     validation::validate_record_for_grpc(record)?;
 
     let headers = if include_headers {
-        record.headers.clone().unwrap_or_default()
+        record
+            .headers
+            .iter()
+            .flatten()
+            .map(|h| Header {
+                key: h.key.clone(),
+                value: h.value.clone(),
+            })
+            .collect()
     } else {
-        std::collections::HashMap::new()
+        Vec::new()
     };
 
     Ok(Record {
         key: record.key.clone().unwrap_or_default(),
         value: record.value.clone(),
         headers,
+        timestamp: String::new(),
     })
 }
 
+fn compression_from_proto(value: i32) -> Result<Option<CompressionCodec>, Box<Status>> {
+    match Compression::try_from(value) {
+        Ok(Compression::Unspecified) => Ok(None),
+        Ok(Compression::None) => Ok(Some(CompressionCodec::None)),
+        Ok(Compression::Gzip) => Ok(Some(CompressionCodec::Gzip)),
+        Ok(Compression::Snappy) => Ok(Some(CompressionCodec::Snappy)),
+        Ok(Compression::Lz4) => Ok(Some(CompressionCodec::Lz4)),
+        Ok(Compression::Zstd) => Ok(Some(CompressionCodec::Zstd)),
+        Err(_) => Err(Box::new(Status::invalid_argument(format!(
+            "unknown compression value {value}"
+        )))),
+    }
+}
+
+fn acl_resource_type_from_proto(value: i32) -> Result<Option<acl::ResourceType>, Box<Status>> {
+    match AclResourceType::try_from(value) {
+        Ok(AclResourceType::Unspecified) => Ok(None),
+        Ok(AclResourceType::Topic) => Ok(Some(acl::ResourceType::Topic)),
+        Ok(AclResourceType::Group) => Ok(Some(acl::ResourceType::Group)),
+        Ok(AclResourceType::Cluster) => Ok(Some(acl::ResourceType::Cluster)),
+        Err(_) => Err(Box::new(Status::invalid_argument(format!(
+            "unknown ACL resource type {value}"
+        )))),
+    }
+}
+
+fn acl_pattern_type_from_proto(value: i32) -> Result<Option<acl::PatternType>, Box<Status>> {
+    match AclPatternType::try_from(value) {
+        Ok(AclPatternType::Unspecified) => Ok(None),
+        Ok(AclPatternType::Literal) => Ok(Some(acl::PatternType::Literal)),
+        Ok(AclPatternType::Prefixed) => Ok(Some(acl::PatternType::Prefixed)),
+        Err(_) => Err(Box::new(Status::invalid_argument(format!(
+            "unknown ACL pattern type {value}"
+        )))),
+    }
+}
+
+fn acl_operation_from_proto(value: i32) -> Result<Option<acl::AclOperation>, Box<Status>> {
+    match AclOperation::try_from(value) {
+        Ok(AclOperation::Unspecified) => Ok(None),
+        Ok(AclOperation::All) => Ok(Some(acl::AclOperation::All)),
+        Ok(AclOperation::Read) => Ok(Some(acl::AclOperation::Read)),
+        Ok(AclOperation::Write) => Ok(Some(acl::AclOperation::Write)),
+        Ok(AclOperation::Create) => Ok(Some(acl::AclOperation::Create)),
+        Ok(AclOperation::Delete) => Ok(Some(acl::AclOperation::Delete)),
+        Ok(AclOperation::Describe) => Ok(Some(acl::AclOperation::Describe)),
+        Ok(AclOperation::Alter) => Ok(Some(acl::AclOperation::Alter)),
+        Ok(AclOperation::ClusterAction) => Ok(Some(acl::AclOperation::ClusterAction)),
+        Err(_) => Err(Box::new(Status::invalid_argument(format!(
+            "unknown ACL operation {value}"
+        )))),
+    }
+}
+
+fn acl_permission_from_proto(value: i32) -> Result<Option<acl::AclPermission>, Box<Status>> {
+    match AclPermission::try_from(value) {
+        Ok(AclPermission::Unspecified) => Ok(None),
+        Ok(AclPermission::Allow) => Ok(Some(acl::AclPermission::Allow)),
+        Ok(AclPermission::Deny) => Ok(Some(acl::AclPermission::Deny)),
+        Err(_) => Err(Box::new(Status::invalid_argument(format!(
+            "unknown ACL permission {value}"
+        )))),
+    }
+}
+
+fn acl_binding_from_proto(binding: AclBinding) -> Result<acl::AclBinding, Box<Status>> {
+    let required =
+        |field: &str| Box::new(Status::invalid_argument(format!("ACL {field} is required")));
+    if binding.principal.is_empty() {
+        return Err(required("principal"));
+    }
+    let resource_type = acl_resource_type_from_proto(binding.resource_type)?
+        .ok_or_else(|| required("resource_type"))?;
+    let resource_name = match (resource_type, binding.resource_name.is_empty()) {
+        (acl::ResourceType::Cluster, true) => acl::CLUSTER_RESOURCE_NAME.to_string(),
+        (_, true) => return Err(required("resource_name")),
+        (_, false) => binding.resource_name,
+    };
+    Ok(acl::AclBinding {
+        principal: binding.principal,
+        resource_type,
+        resource_name,
+        pattern_type: acl_pattern_type_from_proto(binding.pattern_type)?
+            .unwrap_or(acl::PatternType::Literal),
+        operation: acl_operation_from_proto(binding.operation)?
+            .ok_or_else(|| required("operation"))?,
+        permission: acl_permission_from_proto(binding.permission)?
+            .ok_or_else(|| required("permission"))?,
+    })
+}
+
+fn acl_binding_to_proto(binding: acl::AclBinding) -> AclBinding {
+    AclBinding {
+        principal: binding.principal,
+        resource_type: match binding.resource_type {
+            acl::ResourceType::Topic => AclResourceType::Topic,
+            acl::ResourceType::Group => AclResourceType::Group,
+            acl::ResourceType::Cluster => AclResourceType::Cluster,
+        } as i32,
+        resource_name: binding.resource_name,
+        pattern_type: match binding.pattern_type {
+            acl::PatternType::Literal => AclPatternType::Literal,
+            acl::PatternType::Prefixed => AclPatternType::Prefixed,
+        } as i32,
+        operation: match binding.operation {
+            acl::AclOperation::All => AclOperation::All,
+            acl::AclOperation::Read => AclOperation::Read,
+            acl::AclOperation::Write => AclOperation::Write,
+            acl::AclOperation::Create => AclOperation::Create,
+            acl::AclOperation::Delete => AclOperation::Delete,
+            acl::AclOperation::Describe => AclOperation::Describe,
+            acl::AclOperation::Alter => AclOperation::Alter,
+            acl::AclOperation::ClusterAction => AclOperation::ClusterAction,
+        } as i32,
+        permission: match binding.permission {
+            acl::AclPermission::Allow => AclPermission::Allow,
+            acl::AclPermission::Deny => AclPermission::Deny,
+        } as i32,
+    }
+}
+
+fn acl_filter_from_proto(filter: Option<AclFilter>) -> Result<acl::AclFilter, Box<Status>> {
+    let filter = filter.unwrap_or_default();
+    let non_empty = |value: String| (!value.is_empty()).then_some(value);
+    Ok(acl::AclFilter {
+        principal: non_empty(filter.principal),
+        resource_type: acl_resource_type_from_proto(filter.resource_type)?,
+        resource_name: non_empty(filter.resource_name),
+        pattern_type: acl_pattern_type_from_proto(filter.pattern_type)?,
+        operation: acl_operation_from_proto(filter.operation)?,
+        permission: acl_permission_from_proto(filter.permission)?,
+    })
+}
+
+/// Fetch bounds from a request; `to_offset == 0` and an empty `to_time` leave that side open.
+fn read_bounds_from_proto(to_offset: u64, to_time: &str) -> Result<ReadBounds, Box<Status>> {
+    let mut bounds = ReadBounds::default();
+    if to_offset > 0 {
+        bounds = bounds.with_to_offset(to_offset);
+    }
+    if !to_time.is_empty() {
+        bounds = bounds.with_to_time(to_time).map_err(|e| {
+            Box::new(Status::invalid_argument(format!(
+                "to_time '{to_time}' is not RFC3339: {e}"
+            )))
+        })?;
+    }
+    Ok(bounds)
+}
+
+/// Client id the caller sent in its call metadata, if any.
+fn client_id<T>(request: &Request<T>) -> Option<String> {
+    request
+        .metadata()
+        .get(flashq_proto::CLIENT_ID_METADATA_KEY)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// Bytes a record counts for against produce and fetch byte-rate quotas.
+pub(crate) fn quota_bytes(record: &flashq_cluster::Record) -> u64 {
+    let key = record.key.as_ref().map_or(0, String::len);
+    let headers: usize = record
+        .headers
+        .iter()
+        .flatten()
+        .map(|h| h.key.len() + h.value.len())
+        .sum();
+    (key + record.value.len() + headers) as u64
+}
+
+fn quota_entity_type_from_proto(value: i32) -> Result<quota::QuotaEntityType, Box<Status>> {
+    match QuotaEntityType::try_from(value) {
+        Ok(QuotaEntityType::Principal) => Ok(quota::QuotaEntityType::Principal),
+        Ok(QuotaEntityType::ClientId) => Ok(quota::QuotaEntityType::ClientId),
+        Ok(QuotaEntityType::Topic) => Ok(quota::QuotaEntityType::Topic),
+        Ok(QuotaEntityType::Unspecified) => Err(Box::new(Status::invalid_argument(
+            "quota entity_type is required",
+        ))),
+        Err(_) => Err(Box::new(Status::invalid_argument(format!(
+            "unknown quota entity type {value}"
+        )))),
+    }
+}
+
+fn quota_entity_from_proto(entity: Option<QuotaEntity>) -> Result<quota::QuotaEntity, Box<Status>> {
+    let entity =
+        entity.ok_or_else(|| Box::new(Status::invalid_argument("quota entity is required")))?;
+    let entity_type = quota_entity_type_from_proto(entity.entity_type)?;
+    if entity.name.is_empty() {
+        return Err(Box::new(Status::invalid_argument(
+            "quota entity name is required",
+        )));
+    }
+    Ok(quota::QuotaEntity::new(entity_type, entity.name))
+}
+
+fn quota_to_proto(entity: quota::QuotaEntity, config: quota::QuotaConfig) -> Quota {
+    let entity_type = match entity.entity_type {
+        quota::QuotaEntityType::Principal => QuotaEntityType::Principal,
+        quota::QuotaEntityType::ClientId => QuotaEntityType::ClientId,
+        quota::QuotaEntityType::Topic => QuotaEntityType::Topic,
+    };
+    Quota {
+        entity: Some(QuotaEntity {
+            entity_type: entity_type as i32,
+            name: entity.name,
+        }),
+        config: Some(QuotaConfig {
+            produce_bytes_per_sec: config.produce_bytes_per_sec,
+            fetch_bytes_per_sec: config.fetch_bytes_per_sec,
+            requests_per_sec: config.requests_per_sec,
+        }),
+    }
+}
+
 fn to_proto_rwo(
     r: &flashq_cluster::RecordWithOffset,
     include_headers: bool,
@@ -102,13 +400,37 @@ impl Producer for FlashQBroker {
         &self,
         request: Request<ProduceRequest>,
     ) -> Result<Response<ProduceResponse>, Status> {
+        let started = std::time::Instant::now();
+        // UNAVAILABLE is retriable, so clients move on to another broker or try again later
+        if !self.lifecycle.accepts_produce() {
+            return Err(Status::unavailable(format!(
+                "Broker is {} and not accepting produce requests",
+                self.lifecycle.state()
+            )));
+        }
+        let principal = Principal::from_request(&request);
+        let client_id = client_id(&request);
         let req = request.into_inner();
         if req.topic.is_empty() {
             return Err(Status::invalid_argument("topic is required"));
         }
+        if is_internal_topic(&req.topic) {
+            return Err(Status::invalid_argument(format!(
+                "topic '{}' is internal and cannot be produced to",
+                req.topic
+            )));
+        }
+        self.authorize(
+            &principal,
+            acl::AclOperation::Write,
+            acl::ResourceType::Topic,
+            &req.topic,
+        )
+        .map_err(|e| *e)?;
         if req.records.is_empty() {
             return Err(Status::invalid_argument("records must be non-empty"));
         }
+        let compression = compression_from_proto(req.compression).map_err(|e| *e)?;
 
         // Map records to core type with validation
         let mut records = Vec::with_capacity(req.records.len());
@@ -138,18 +460,30 @@ impl Producer for FlashQBroker {
                 )));
             }
 
-            for (header_key, header_value) in &rec.headers {
-                if header_value.len() > validation::MAX_HEADER_VALUE_SIZE {
+            for header in &rec.headers {
+                if header.value.len() > validation::MAX_HEADER_VALUE_SIZE {
                     return Err(Status::invalid_argument(format!(
                         "Record at index {} header '{}' value exceeds maximum length of {} characters (got {})",
                         i,
-                        header_key,
+                        header.key,
                         validation::MAX_HEADER_VALUE_SIZE,
-                        header_value.len()
+                        header.value.len()
                     )));
                 }
             }
 
+            let create_time = if rec.timestamp.is_empty() {
+                None
+            } else {
+                if let Err(e) = chrono::DateTime::parse_from_rfc3339(&rec.timestamp) {
+                    return Err(Status::invalid_argument(format!(
+                        "Record at index {i} timestamp '{}' is not RFC3339: {e}",
+                        rec.timestamp
+                    )));
+                }
+                Some(rec.timestamp)
+            };
+
             let key = if rec.key.is_empty() {
                 None
             } else {
@@ -158,19 +492,49 @@ impl Producer for FlashQBroker {
             let headers = if rec.headers.is_empty() {
                 None
             } else {
-                Some(rec.headers)
+                Some(
+                    rec.headers
+                        .into_iter()
+                        .map(|h| flashq_cluster::storage::Header::new(h.key, h.value))
+                        .collect(),
+                )
             };
             records.push(flashq_cluster::Record {
                 key,
                 value: rec.value,
                 headers,
+                create_time,
             });
         }
 
+        let subjects = QuotaSubjects {
+            principal: &principal.name,
+            client_id: client_id.as_deref(),
+            topic: &req.topic,
+        };
+        self.enforce_quotas(
+            &subjects,
+            &[QuotaMetric::Requests, QuotaMetric::ProduceBytes],
+        )
+        .await
+        .map_err(|e| *e)?;
+        let record_count = records.len();
+        let bytes_in = records.iter().map(quota_bytes).sum();
+        self.quotas.record(&subjects, QuotaMetric::Requests, 1);
+        self.quotas
+            .record(&subjects, QuotaMetric::ProduceBytes, bytes_in);
+
         let last = self
             .core
-            .post_records(req.topic.clone(), records)
-            .map_err(|e| Status::internal(format!("produce failed: {e}")))?;
+            .post_records_async(req.topic.clone(), records, compression)
+            .await
+            .map_err(|e| match e {
+                flashq_cluster::storage::FlashQError::Storage(StorageError::InvalidTimestamp(
+                    reason,
+                )) => Status::invalid_argument(format!("produce failed: {reason}")),
+                e => Status::internal(format!("produce failed: {e}")),
+            })?;
+        crate::metrics::record_produce(&req.topic, record_count, bytes_in, started.elapsed());
         // Timestamp: we return "now" in RFC3339 as HTTP does for the last record
         let timestamp = chrono::Utc::now().to_rfc3339();
         Ok(Response::new(ProduceResponse {
@@ -186,12 +550,21 @@ impl Consumer for FlashQBroker {
         &self,
         request: Request<ConsumerGroupId>,
     ) -> Result<Response<ConsumerGroupResponse>, Status> {
+        let principal = Principal::from_request(&request);
         let req = request.into_inner();
         if req.group_id.is_empty() {
             return Err(Status::invalid_argument("group_id is required"));
         }
+        self.authorize(
+            &principal,
+            acl::AclOperation::Read,
+            acl::ResourceType::Group,
+            &req.group_id,
+        )
+        .map_err(|e| *e)?;
         self.core
-            .create_consumer_group(req.group_id.clone())
+            .create_consumer_group_async(req.group_id.clone())
+            .await
             .map_err(|e| Status::internal(format!("create_consumer_group failed: {e}")))?;
         Ok(Response::new(ConsumerGroupResponse {
             group_id: req.group_id,
@@ -202,10 +575,18 @@ impl Consumer for FlashQBroker {
         &self,
         request: Request<ConsumerGroupId>,
     ) -> Result<Response<Empty>, Status> {
+        let principal = Principal::from_request(&request);
         let req = request.into_inner();
         if req.group_id.is_empty() {
             return Err(Status::invalid_argument("group_id is required"));
         }
+        self.authorize(
+            &principal,
+            acl::AclOperation::Delete,
+            acl::ResourceType::Group,
+            &req.group_id,
+        )
+        .map_err(|e| *e)?;
         self.core
             .delete_consumer_group(&req.group_id)
             .map_err(|e| Status::internal(format!("delete_consumer_group failed: {e}")))?;
@@ -216,10 +597,29 @@ impl Consumer for FlashQBroker {
         &self,
         request: Request<FetchByOffsetRequest>,
     ) -> Result<Response<FetchResponse>, Status> {
+        let started = std::time::Instant::now();
+        let principal = Principal::from_request(&request);
+        let client_id = client_id(&request);
         let req = request.into_inner();
         if req.group_id.is_empty() || req.topic.is_empty() {
             return Err(Status::invalid_argument("group_id and topic are required"));
         }
+        self.authorize_topic_and_group(
+            &principal,
+            acl::AclOperation::Read,
+            &req.topic,
+            &req.group_id,
+        )
+        .map_err(|e| *e)?;
+        let subjects = QuotaSubjects {
+            principal: &principal.name,
+            client_id: client_id.as_deref(),
+            topic: &req.topic,
+        };
+        self.enforce_quotas(&subjects, &[QuotaMetric::Requests, QuotaMetric::FetchBytes])
+            .await
+            .map_err(|e| *e)?;
+        self.quotas.record(&subjects, QuotaMetric::Requests, 1);
         // Determine starting offset
         let mut offset = req.from_offset;
         if offset == 0 {
@@ -234,11 +634,16 @@ impl Consumer for FlashQBroker {
             req.max_records as usize
         };
         let include_headers = req.include_headers;
+        let bounds = read_bounds_from_proto(req.to_offset, &req.to_time).map_err(|e| *e)?;
 
         let records = self
             .core
-            .poll_records_from_offset(&req.topic, offset, Some(limit))
+            .poll_records_from_offset_bounded_async(req.topic.clone(), offset, bounds, Some(limit))
+            .await
             .map_err(|e| Status::internal(format!("poll_records_from_offset failed: {e}")))?;
+        let bytes_out = records.iter().map(|r| quota_bytes(&r.record)).sum();
+        self.quotas
+            .record(&subjects, QuotaMetric::FetchBytes, bytes_out);
 
         let next_offset = records
             .last()
@@ -253,6 +658,7 @@ impl Consumer for FlashQBroker {
             .collect();
         let records =
             records.map_err(|e| Status::internal(format!("Record validation failed: {e}")))?;
+        crate::metrics::record_fetch(&req.topic, records.len(), bytes_out, started.elapsed());
 
         Ok(Response::new(FetchResponse {
             records,
@@ -266,22 +672,62 @@ impl Consumer for FlashQBroker {
         &self,
         request: Request<FetchByTimeRequest>,
     ) -> Result<Response<FetchResponse>, Status> {
+        let started = std::time::Instant::now();
+        let principal = Principal::from_request(&request);
+        let client_id = client_id(&request);
         let req = request.into_inner();
         if req.group_id.is_empty() || req.topic.is_empty() || req.from_time.is_empty() {
             return Err(Status::invalid_argument(
                 "group_id, topic and from_time are required",
             ));
         }
+        self.authorize_topic_and_group(
+            &principal,
+            acl::AclOperation::Read,
+            &req.topic,
+            &req.group_id,
+        )
+        .map_err(|e| *e)?;
+        let subjects = QuotaSubjects {
+            principal: &principal.name,
+            client_id: client_id.as_deref(),
+            topic: &req.topic,
+        };
+        self.enforce_quotas(&subjects, &[QuotaMetric::Requests, QuotaMetric::FetchBytes])
+            .await
+            .map_err(|e| *e)?;
+        self.quotas.record(&subjects, QuotaMetric::Requests, 1);
         let limit = if req.max_records == 0 {
             100
         } else {
             req.max_records as usize
         };
         let include_headers = req.include_headers;
+        let bounds = read_bounds_from_proto(req.to_offset, &req.to_time).map_err(|e| *e)?;
+        if let Some(to_time_ms) = bounds.to_time_ms {
+            let from_time = chrono::DateTime::parse_from_rfc3339(&req.from_time).map_err(|e| {
+                Status::invalid_argument(format!(
+                    "from_time '{}' is not RFC3339: {e}",
+                    req.from_time
+                ))
+            })?;
+            if from_time.timestamp_millis() >= to_time_ms as i64 {
+                return Err(Status::invalid_argument("to_time must be after from_time"));
+            }
+        }
         let records = self
             .core
-            .poll_records_from_time(&req.topic, &req.from_time, Some(limit))
+            .poll_records_from_time_bounded_async(
+                req.topic.clone(),
+                req.from_time.clone(),
+                bounds,
+                Some(limit),
+            )
+            .await
             .map_err(|e| Status::internal(format!("poll_records_from_time failed: {e}")))?;
+        let bytes_out = records.iter().map(|r| quota_bytes(&r.record)).sum();
+        self.quotas
+            .record(&subjects, QuotaMetric::FetchBytes, bytes_out);
         let next_offset = records
             .last()
             .map(|r| r.offset.saturating_add(1))
@@ -298,6 +744,7 @@ impl Consumer for FlashQBroker {
             .collect();
         let records =
             records.map_err(|e| Status::internal(format!("Record validation failed: {e}")))?;
+        crate::metrics::record_fetch(&req.topic, records.len(), bytes_out, started.elapsed());
         Ok(Response::new(FetchResponse {
             records,
             next_offset,
@@ -310,12 +757,21 @@ impl Consumer for FlashQBroker {
         &self,
         request: Request<CommitOffsetRequest>,
     ) -> Result<Response<CommitOffsetResponse>, Status> {
+        let principal = Principal::from_request(&request);
         let req = request.into_inner();
         if req.group_id.is_empty() || req.topic.is_empty() {
             return Err(Status::invalid_argument("group_id and topic are required"));
         }
+        self.authorize_topic_and_group(
+            &principal,
+            acl::AclOperation::Read,
+            &req.topic,
+            &req.group_id,
+        )
+        .map_err(|e| *e)?;
         self.core
-            .update_consumer_group_offset(&req.group_id, req.topic.clone(), req.offset)
+            .update_consumer_group_offset_async(req.group_id.clone(), req.topic.clone(), req.offset)
+            .await
             .map_err(|e| Status::internal(format!("update_consumer_group_offset failed: {e}")))?;
         let ts = chrono::Utc::now().to_rfc3339();
         Ok(Response::new(CommitOffsetResponse {
@@ -329,10 +785,18 @@ impl Consumer for FlashQBroker {
         &self,
         request: Request<GetOffsetRequest>,
     ) -> Result<Response<GetOffsetResponse>, Status> {
+        let principal = Principal::from_request(&request);
         let req = request.into_inner();
         if req.group_id.is_empty() || req.topic.is_empty() {
             return Err(Status::invalid_argument("group_id and topic are required"));
         }
+        self.authorize_topic_and_group(
+            &principal,
+            acl::AclOperation::Describe,
+            &req.topic,
+            &req.group_id,
+        )
+        .map_err(|e| *e)?;
         let offset = self
             .core
             .get_consumer_group_offset(&req.group_id, &req.topic)
@@ -350,15 +814,42 @@ impl Consumer for FlashQBroker {
         &self,
         request: Request<FetchByOffsetRequest>,
     ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let principal = Principal::from_request(&request);
+        let client_id = client_id(&request);
         let req = request.into_inner();
         if req.group_id.is_empty() || req.topic.is_empty() {
             return Err(Status::invalid_argument("group_id and topic are required"));
         }
+        self.authorize_topic_and_group(
+            &principal,
+            acl::AclOperation::Read,
+            &req.topic,
+            &req.group_id,
+        )
+        .map_err(|e| *e)?;
+        let bounds = read_bounds_from_proto(req.to_offset, &req.to_time).map_err(|e| *e)?;
+        let subjects = QuotaSubjects {
+            principal: &principal.name,
+            client_id: client_id.as_deref(),
+            topic: &req.topic,
+        };
+        self.enforce_quotas(&subjects, &[QuotaMetric::Requests])
+            .await
+            .map_err(|e| *e)?;
+        self.quotas.record(&subjects, QuotaMetric::Requests, 1);
 
         let (tx, rx) = tokio::sync::mpsc::channel(64);
         let core = self.core.clone();
+        let quotas = self.quotas.clone();
+        let lifecycle = self.lifecycle.clone();
 
         tokio::spawn(async move {
+            let _subscription = crate::metrics::SubscriptionGauge::open(&req.topic);
+            let subjects = QuotaSubjects {
+                principal: &principal.name,
+                client_id: client_id.as_deref(),
+                topic: &req.topic,
+            };
             let mut current = if req.from_offset == 0 {
                 core.get_consumer_group_offset(&req.group_id, &req.topic)
                     .unwrap_or(0)
@@ -372,50 +863,82 @@ impl Consumer for FlashQBroker {
             const INITIAL_RETRY_DELAY: u64 = 200;
             const MAX_RETRY_DELAY: u64 = 5000;
 
-            loop {
-                match core.poll_records_from_offset(&req.topic, current, Some(100)) {
-                    Ok(records) if !records.is_empty() => {
-                        consecutive_errors = 0; // Reset on success
-
-                        for r in records.iter() {
-                            let msg = match to_proto_rwo(r, req.include_headers) {
-                                Ok(msg) => msg,
-                                Err(e) => {
-                                    let _ = tx.send(Err(*e)).await;
-                                    return;
+            // Shutdown ends the stream cleanly: dropping the sender closes it with an OK status
+            let follow = async {
+                loop {
+                    // The stream ends once it has passed the end offset
+                    if bounds.is_past_end(current) {
+                        return;
+                    }
+                    // An open stream is always throttled, never cut off, when over its fetch quota
+                    if let Some(violation) = quotas.check(&subjects, &[QuotaMetric::FetchBytes]) {
+                        tokio::time::sleep(violation.delay).await;
+                    }
+                    let high_water_mark = core.get_high_water_mark(&req.topic);
+                    match core
+                        .poll_records_from_offset_bounded_async(
+                            req.topic.clone(),
+                            current,
+                            bounds,
+                            Some(100),
+                        )
+                        .await
+                    {
+                        Ok(records) if !records.is_empty() => {
+                            consecutive_errors = 0; // Reset on success
+                            let bytes_out = records.iter().map(|r| quota_bytes(&r.record)).sum();
+                            quotas.record(&subjects, QuotaMetric::FetchBytes, bytes_out);
+                            crate::metrics::record_bytes_out(&req.topic, records.len(), bytes_out);
+
+                            for r in records.iter() {
+                                let msg = match to_proto_rwo(r, req.include_headers) {
+                                    Ok(msg) => msg,
+                                    Err(e) => {
+                                        let _ = tx.send(Err(*e)).await;
+                                        return;
+                                    }
+                                };
+                                if tx.send(Ok(msg)).await.is_err() {
+                                    return; // Client disconnected
                                 }
-                            };
-                            if tx.send(Ok(msg)).await.is_err() {
-                                return; // Client disconnected
+                                current = r.offset.saturating_add(1);
                             }
-                            current = r.offset.saturating_add(1);
                         }
-                    }
-                    Ok(_) => {
-                        // No records, wait before polling again
-                        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
-                    }
-                    Err(e) => {
-                        consecutive_errors += 1;
-
-                        if consecutive_errors >= MAX_CONSECUTIVE_ERRORS {
-                            // Circuit breaker triggered
-                            let _ = tx
-                                .send(Err(Status::internal(format!(
-                                    "Too many consecutive errors ({consecutive_errors}): {e}"
-                                ))))
-                                .await;
-                            return;
+                        Ok(_) => {
+                            // Everything below the high-water mark was read and fell outside the
+                            // time bound, so it need not be scanned again
+                            current = current.max(high_water_mark);
+                            // No records, wait before polling again
+                            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
                         }
+                        Err(e) => {
+                            consecutive_errors += 1;
 
-                        // Exponential backoff with jitter
-                        let delay = std::cmp::min(
-                            INITIAL_RETRY_DELAY * 2_u64.pow(consecutive_errors - 1),
-                            MAX_RETRY_DELAY,
-                        );
-                        tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+                            if consecutive_errors >= MAX_CONSECUTIVE_ERRORS {
+                                // Circuit breaker triggered
+                                let _ = tx
+                                    .send(Err(Status::internal(format!(
+                                        "Too many consecutive errors ({consecutive_errors}): {e}"
+                                    ))))
+                                    .await;
+                                return;
+                            }
+
+                            // Exponential backoff with jitter
+                            let delay = std::cmp::min(
+                                INITIAL_RETRY_DELAY * 2_u64.pow(consecutive_errors - 1),
+                                MAX_RETRY_DELAY,
+                            );
+                            tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+                        }
                     }
                 }
+            };
+            tokio::select! {
+                _ = follow => {}
+                _ = lifecycle.shutdown_requested() => {
+                    tracing::debug!(topic = %req.topic, group_id = %req.group_id, "Closing subscription for shutdown");
+                }
             }
         });
 
@@ -429,21 +952,42 @@ impl Consumer for FlashQBroker {
 impl Admin for FlashQBroker {
     async fn list_topics(
         &self,
-        _request: Request<Empty>,
+        request: Request<Empty>,
     ) -> Result<Response<ListTopicsResponse>, Status> {
-        Ok(Response::new(ListTopicsResponse {
-            topics: self.core.get_topics(),
-        }))
+        // Topics the caller may not describe are left out rather than failing the call
+        let principal = Principal::from_request(&request);
+        let topics = self
+            .core
+            .get_topics()
+            .into_iter()
+            .filter(|topic| {
+                self.authorizer.is_allowed(
+                    &principal,
+                    acl::AclOperation::Describe,
+                    acl::ResourceType::Topic,
+                    topic,
+                )
+            })
+            .collect();
+        Ok(Response::new(ListTopicsResponse { topics }))
     }
 
     async fn high_water_mark(
         &self,
         request: Request<HighWaterMarkRequest>,
     ) -> Result<Response<HighWaterMarkResponse>, Status> {
+        let principal = Principal::from_request(&request);
         let req = request.into_inner();
         if req.topic.is_empty() {
             return Err(Status::invalid_argument("topic is required"));
         }
+        self.authorize(
+            &principal,
+            acl::AclOperation::Describe,
+            acl::ResourceType::Topic,
+            &req.topic,
+        )
+        .map_err(|e| *e)?;
         let hwm = self.core.get_high_water_mark(&req.topic);
         Ok(Response::new(HighWaterMarkResponse {
             topic: req.topic,
@@ -451,6 +995,210 @@ impl Admin for FlashQBroker {
         }))
     }
 
+    async fn list_offsets(
+        &self,
+        request: Request<ListOffsetsRequest>,
+    ) -> Result<Response<ListOffsetsResponse>, Status> {
+        let principal = Principal::from_request(&request);
+        let req = request.into_inner();
+        if req.topic.is_empty() {
+            return Err(Status::invalid_argument("topic is required"));
+        }
+        self.authorize(
+            &principal,
+            acl::AclOperation::Describe,
+            acl::ResourceType::Topic,
+            &req.topic,
+        )
+        .map_err(|e| *e)?;
+        let timestamp = if req.timestamp.is_empty() {
+            None
+        } else {
+            chrono::DateTime::parse_from_rfc3339(&req.timestamp).map_err(|e| {
+                Status::invalid_argument(format!(
+                    "timestamp '{}' is not RFC3339: {e}",
+                    req.timestamp
+                ))
+            })?;
+            Some(req.timestamp)
+        };
+        let partitions = req
+            .partitions
+            .into_iter()
+            .map(flashq_cluster::storage::PartitionId::new)
+            .collect();
+        let offsets = self
+            .core
+            .list_offsets_async(req.topic.clone(), partitions, timestamp)
+            .await
+            .map_err(|e| {
+                if e.is_not_found() {
+                    Status::not_found(e.to_string())
+                } else {
+                    Status::internal(format!("list_offsets failed: {e}"))
+                }
+            })?;
+        Ok(Response::new(ListOffsetsResponse {
+            topic: req.topic,
+            partitions: offsets
+                .into_iter()
+                .map(|o| PartitionOffsets {
+                    partition: o.partition.as_u32(),
+                    earliest: o.earliest,
+                    latest: o.latest,
+                    offset_for_timestamp: o.offset_for_timestamp,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn create_acls(
+        &self,
+        request: Request<CreateAclsRequest>,
+    ) -> Result<Response<CreateAclsResponse>, Status> {
+        let principal = Principal::from_request(&request);
+        self.authorize(
+            &principal,
+            acl::AclOperation::Alter,
+            acl::ResourceType::Cluster,
+            acl::CLUSTER_RESOURCE_NAME,
+        )
+        .map_err(|e| *e)?;
+        let req = request.into_inner();
+        if req.acls.is_empty() {
+            return Err(Status::invalid_argument("acls must be non-empty"));
+        }
+        let bindings = req
+            .acls
+            .into_iter()
+            .map(acl_binding_from_proto)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| *e)?;
+        let created = self
+            .authorizer
+            .create_acls(bindings)
+            .map_err(|e| Status::internal(format!("create_acls failed: {e}")))?;
+        Ok(Response::new(CreateAclsResponse {
+            created: created as u32,
+        }))
+    }
+
+    async fn list_acls(
+        &self,
+        request: Request<ListAclsRequest>,
+    ) -> Result<Response<ListAclsResponse>, Status> {
+        let principal = Principal::from_request(&request);
+        self.authorize(
+            &principal,
+            acl::AclOperation::Describe,
+            acl::ResourceType::Cluster,
+            acl::CLUSTER_RESOURCE_NAME,
+        )
+        .map_err(|e| *e)?;
+        let filter = acl_filter_from_proto(request.into_inner().filter).map_err(|e| *e)?;
+        Ok(Response::new(ListAclsResponse {
+            acls: self
+                .authorizer
+                .list_acls(&filter)
+                .into_iter()
+                .map(acl_binding_to_proto)
+                .collect(),
+        }))
+    }
+
+    async fn delete_acls(
+        &self,
+        request: Request<DeleteAclsRequest>,
+    ) -> Result<Response<DeleteAclsResponse>, Status> {
+        let principal = Principal::from_request(&request);
+        self.authorize(
+            &principal,
+            acl::AclOperation::Alter,
+            acl::ResourceType::Cluster,
+            acl::CLUSTER_RESOURCE_NAME,
+        )
+        .map_err(|e| *e)?;
+        let filter = acl_filter_from_proto(request.into_inner().filter).map_err(|e| *e)?;
+        let deleted = self
+            .authorizer
+            .delete_acls(&filter)
+            .map_err(|e| Status::internal(format!("delete_acls failed: {e}")))?;
+        Ok(Response::new(DeleteAclsResponse {
+            deleted: deleted.into_iter().map(acl_binding_to_proto).collect(),
+        }))
+    }
+
+    async fn set_quota(
+        &self,
+        request: Request<SetQuotaRequest>,
+    ) -> Result<Response<Quota>, Status> {
+        let principal = Principal::from_request(&request);
+        self.authorize(
+            &principal,
+            acl::AclOperation::Alter,
+            acl::ResourceType::Cluster,
+            acl::CLUSTER_RESOURCE_NAME,
+        )
+        .map_err(|e| *e)?;
+        let req = request.into_inner();
+        let entity = quota_entity_from_proto(req.entity).map_err(|e| *e)?;
+        let config = req.config.unwrap_or_default();
+        let config = quota::QuotaConfig {
+            produce_bytes_per_sec: config.produce_bytes_per_sec,
+            fetch_bytes_per_sec: config.fetch_bytes_per_sec,
+            requests_per_sec: config.requests_per_sec,
+        };
+        self.quotas
+            .set_quota(entity.clone(), config)
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::InvalidInput => Status::invalid_argument(e.to_string()),
+                _ => Status::internal(format!("set_quota failed: {e}")),
+            })?;
+        Ok(Response::new(quota_to_proto(entity, config)))
+    }
+
+    async fn delete_quota(
+        &self,
+        request: Request<DeleteQuotaRequest>,
+    ) -> Result<Response<DeleteQuotaResponse>, Status> {
+        let principal = Principal::from_request(&request);
+        self.authorize(
+            &principal,
+            acl::AclOperation::Alter,
+            acl::ResourceType::Cluster,
+            acl::CLUSTER_RESOURCE_NAME,
+        )
+        .map_err(|e| *e)?;
+        let entity = quota_entity_from_proto(request.into_inner().entity).map_err(|e| *e)?;
+        let deleted = self
+            .quotas
+            .delete_quota(&entity)
+            .map_err(|e| Status::internal(format!("delete_quota failed: {e}")))?;
+        Ok(Response::new(DeleteQuotaResponse { deleted }))
+    }
+
+    async fn list_quotas(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<ListQuotasResponse>, Status> {
+        let principal = Principal::from_request(&request);
+        self.authorize(
+            &principal,
+            acl::AclOperation::Describe,
+            acl::ResourceType::Cluster,
+            acl::CLUSTER_RESOURCE_NAME,
+        )
+        .map_err(|e| *e)?;
+        Ok(Response::new(ListQuotasResponse {
+            quotas: self
+                .quotas
+                .list_quotas()
+                .into_iter()
+                .map(|(entity, config)| quota_to_proto(entity, config))
+                .collect(),
+        }))
+    }
+
     async fn health(&self, _request: Request<Empty>) -> Result<Response<Empty>, Status> {
         Ok(Response::new(Empty {}))
     }
@@ -487,8 +1235,7 @@ impl flashq_cluster::ClusterBroker for FlashQBroker {
             });
         }
 
-        // FlashQ currently starts at offset 0 for all topics
-        Ok(0)
+        Ok(self.core.get_log_start_offset(topic))
     }
 
     async fn acknowledge_replication(
@@ -510,28 +1257,89 @@ impl flashq_cluster::ClusterBroker for FlashQBroker {
         Ok(())
     }
 
+    async fn step_down(
+        &self,
+        topic: &str,
+        partition: flashq_cluster::types::PartitionId,
+    ) -> Result<(), flashq_cluster::ClusterError> {
+        // For now, FlashQ only supports single partition (partition 0)
+        if partition.0 != 0 {
+            return Err(flashq_cluster::ClusterError::PartitionNotFound {
+                topic: topic.to_string(),
+                partition_id: partition.0,
+            });
+        }
+
+        // Without replication a leader keeps no state of its own, so there is nothing to hand
+        // over; leadership lives in the cluster metadata the resync has already reloaded
+        tracing::info!(topic, partition_id = %partition, "Stepped down as partition leader");
+        Ok(())
+    }
+
+    async fn initiate_drain(&self) -> Result<(), flashq_cluster::ClusterError> {
+        self.lifecycle.drain();
+        Ok(())
+    }
+
     async fn initiate_shutdown(&self) -> Result<(), flashq_cluster::ClusterError> {
-        // TODO: Implement graceful shutdown logic
-        // This should signal the broker to stop accepting new requests and drain existing ones
-        tracing::info!("Graceful shutdown initiated");
+        self.lifecycle.shutdown();
         Ok(())
     }
+
+    fn is_draining(&self) -> bool {
+        self.lifecycle.is_draining()
+    }
 }
 
-/// Run a gRPC server with the FlashQ services on the given address.
+/// Run a gRPC server with `svc`'s services on the given address until its lifecycle begins
+/// shutting down. The server then stops accepting connections and returns once in-flight
+/// calls have finished; subscriptions end on their own.
 pub async fn serve<T: flashq_cluster::ClusterService + 'static>(
     addr: SocketAddr,
-    core: Arc<flashq_cluster::FlashQ>,
+    svc: FlashQBroker,
     cluster_server: flashq_cluster::ClusterServer<T>,
+    tls: Option<flashq_proto::tls::ServerTlsOptions>,
+    auth: AuthInterceptor,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let svc = FlashQBroker::new(core);
-    tonic::transport::Server::builder()
-        .layer(TraceLayer::new_for_http())
-        .add_service(producer_server::ProducerServer::new(svc.clone()))
-        .add_service(consumer_server::ConsumerServer::new(svc.clone()))
-        .add_service(admin_server::AdminServer::new(svc))
-        .add_service(crate::ClusterServer::new(cluster_server))
-        .serve(addr)
+    let authorizer = svc.authorizer.clone();
+    let lifecycle = svc.lifecycle.clone();
+    // Cluster RPCs are broker-to-broker, so each one needs ClusterAction on the cluster
+    let mut cluster_auth = auth.clone();
+    let cluster_interceptor = move |request: Request<()>| {
+        let request = cluster_auth.call(request)?;
+        authorizer
+            .authorize(
+                &Principal::from_request(&request),
+                acl::AclOperation::ClusterAction,
+                acl::ResourceType::Cluster,
+                acl::CLUSTER_RESOURCE_NAME,
+            )
+            .map_err(|e| *e)?;
+        Ok(request)
+    };
+    let mut builder = tonic::transport::Server::builder();
+    if let Some(tls) = tls {
+        builder = builder.tls_config(tls.load()?)?;
+    }
+    builder
+        .layer(TraceLayer::new_for_http().make_span_with(trace_context::server_span))
+        .add_service(producer_server::ProducerServer::with_interceptor(
+            svc.clone(),
+            auth.clone(),
+        ))
+        .add_service(consumer_server::ConsumerServer::with_interceptor(
+            svc.clone(),
+            auth.clone(),
+        ))
+        .add_service(admin_server::AdminServer::with_interceptor(
+            svc,
+            auth.clone(),
+        ))
+        .add_service(crate::ClusterServer::with_interceptor(
+            cluster_server,
+            cluster_interceptor,
+        ))
+        .serve_with_shutdown(addr, lifecycle.shutdown_requested())
         .await?;
     Ok(())
 }
@@ -557,13 +1365,16 @@ mod tests {
                     key: String::new(),
                     value: "a".into(),
                     headers: Default::default(),
+                    timestamp: String::new(),
                 },
                 Record {
                     key: String::new(),
                     value: "b".into(),
                     headers: Default::default(),
+                    timestamp: String::new(),
                 },
             ],
+            ..Default::default()
         };
         let resp = Producer::produce(&svc, Request::new(req))
             .await
@@ -591,6 +1402,8 @@ mod tests {
                 from_offset: 0,
                 max_records: 10,
                 include_headers: true,
+                to_offset: 0,
+                to_time: String::new(),
             }),
         )
         .await
@@ -623,7 +1436,9 @@ mod tests {
                     key: String::new(),
                     value: "x".into(),
                     headers: Default::default(),
+                    timestamp: String::new(),
                 }],
+                ..Default::default()
             }),
         )
         .await
@@ -674,7 +1489,9 @@ mod tests {
                     key: String::new(),
                     value: "r".into(),
                     headers: Default::default(),
+                    timestamp: String::new(),
                 }],
+                ..Default::default()
             }),
         )
         .await
@@ -692,6 +1509,32 @@ mod tests {
         let _ = Admin::health(&svc, Request::new(Empty {})).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_produce_to_internal_topic_is_rejected() {
+        let svc = service();
+        let err = Producer::produce(
+            &svc,
+            Request::new(ProduceRequest {
+                topic: "__consumer_offsets".to_string(),
+                records: vec![Record {
+                    key: String::new(),
+                    value: "x".into(),
+                    headers: Default::default(),
+                    timestamp: String::new(),
+                }],
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        let list = Admin::list_topics(&svc, Request::new(Empty {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(list.topics.is_empty());
+    }
+
     #[tokio::test]
     async fn test_fetch_by_time_unit() {
         let svc = service();
@@ -713,7 +1556,9 @@ mod tests {
                     key: String::new(),
                     value: "tv".into(),
                     headers: Default::default(),
+                    timestamp: String::new(),
                 }],
+                ..Default::default()
             }),
         )
         .await
@@ -726,6 +1571,8 @@ mod tests {
                 from_time: "1970-01-01T00:00:00Z".to_string(),
                 max_records: 10,
                 include_headers: true,
+                to_offset: 0,
+                to_time: String::new(),
             }),
         )
         .await
@@ -0,0 +1,306 @@
+//! Kafka protocol primitive types: big-endian integers, length-prefixed strings, bytes and
+//! arrays, and the zigzag varints used inside record batches. Only the non-flexible
+//! encodings are implemented, since the listener advertises no flexible versions.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ProtocolError(String);
+
+impl ProtocolError {
+    pub(crate) fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+pub(crate) type Result<T> = std::result::Result<T, ProtocolError>;
+
+/// Reads protocol fields from the front of a buffer.
+pub(crate) struct Decoder<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.buf.len() {
+            return Err(ProtocolError::new(format!(
+                "Needed {len} bytes but only {} remain",
+                self.buf.len()
+            )));
+        }
+        let (head, tail) = self.buf.split_at(len);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    fn array_of<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    pub(crate) fn i8(&mut self) -> Result<i8> {
+        Ok(i8::from_be_bytes(self.array_of()?))
+    }
+
+    pub(crate) fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.array_of()?))
+    }
+
+    pub(crate) fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.array_of()?))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.array_of()?))
+    }
+
+    pub(crate) fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_be_bytes(self.array_of()?))
+    }
+
+    pub(crate) fn bool(&mut self) -> Result<bool> {
+        Ok(self.i8()? != 0)
+    }
+
+    pub(crate) fn nullable_string(&mut self) -> Result<Option<String>> {
+        let len = self.i16()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        let bytes = self.take(len as usize)?;
+        String::from_utf8(bytes.to_vec())
+            .map(Some)
+            .map_err(|_| ProtocolError::new("String is not valid UTF-8"))
+    }
+
+    pub(crate) fn string(&mut self) -> Result<String> {
+        self.nullable_string()?
+            .ok_or_else(|| ProtocolError::new("Unexpected null string"))
+    }
+
+    pub(crate) fn nullable_bytes(&mut self) -> Result<Option<&'a [u8]>> {
+        let len = self.i32()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        self.take(len as usize).map(Some)
+    }
+
+    /// An array, or `None` when the length is -1.
+    pub(crate) fn nullable_array<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T>,
+    ) -> Result<Option<Vec<T>>> {
+        let len = self.i32()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        // Each item takes at least one byte, which bounds what a corrupt length can allocate
+        let mut items = Vec::with_capacity((len as usize).min(self.remaining()));
+        for _ in 0..len {
+            items.push(item(self)?);
+        }
+        Ok(Some(items))
+    }
+
+    pub(crate) fn array<T>(&mut self, item: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        Ok(self.nullable_array(item)?.unwrap_or_default())
+    }
+
+    pub(crate) fn varlong(&mut self) -> Result<i64> {
+        let mut value: u64 = 0;
+        for shift in (0..70).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(((value >> 1) as i64) ^ -((value & 1) as i64));
+            }
+        }
+        Err(ProtocolError::new("Varint is longer than 10 bytes"))
+    }
+
+    pub(crate) fn varint(&mut self) -> Result<i32> {
+        i32::try_from(self.varlong()?).map_err(|_| ProtocolError::new("Varint overflows i32"))
+    }
+
+    /// A varint-length-prefixed byte string, as used for record keys, values and headers.
+    pub(crate) fn varint_bytes(&mut self) -> Result<Option<&'a [u8]>> {
+        let len = self.varint()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        self.take(len as usize).map(Some)
+    }
+}
+
+/// Appends protocol fields to a buffer.
+#[derive(Default)]
+pub(crate) struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub(crate) fn raw(&mut self, bytes: &[u8]) -> &mut Self {
+        self.buf.extend_from_slice(bytes);
+        self
+    }
+
+    pub(crate) fn i8(&mut self, v: i8) -> &mut Self {
+        self.raw(&v.to_be_bytes())
+    }
+
+    pub(crate) fn i16(&mut self, v: i16) -> &mut Self {
+        self.raw(&v.to_be_bytes())
+    }
+
+    pub(crate) fn i32(&mut self, v: i32) -> &mut Self {
+        self.raw(&v.to_be_bytes())
+    }
+
+    pub(crate) fn u32(&mut self, v: u32) -> &mut Self {
+        self.raw(&v.to_be_bytes())
+    }
+
+    pub(crate) fn i64(&mut self, v: i64) -> &mut Self {
+        self.raw(&v.to_be_bytes())
+    }
+
+    pub(crate) fn bool(&mut self, v: bool) -> &mut Self {
+        self.i8(v as i8)
+    }
+
+    pub(crate) fn string(&mut self, v: &str) -> &mut Self {
+        self.i16(v.len() as i16).raw(v.as_bytes())
+    }
+
+    pub(crate) fn nullable_string(&mut self, v: Option<&str>) -> &mut Self {
+        match v {
+            Some(v) => self.string(v),
+            None => self.i16(-1),
+        }
+    }
+
+    pub(crate) fn nullable_bytes(&mut self, v: Option<&[u8]>) -> &mut Self {
+        match v {
+            Some(v) => self.i32(v.len() as i32).raw(v),
+            None => self.i32(-1),
+        }
+    }
+
+    pub(crate) fn array<T>(
+        &mut self,
+        items: impl IntoIterator<Item = T, IntoIter: ExactSizeIterator>,
+        mut item: impl FnMut(&mut Self, T),
+    ) -> &mut Self {
+        let items = items.into_iter();
+        self.i32(items.len() as i32);
+        for value in items {
+            item(self, value);
+        }
+        self
+    }
+
+    pub(crate) fn varlong(&mut self, v: i64) -> &mut Self {
+        let mut zigzag = ((v << 1) ^ (v >> 63)) as u64;
+        while zigzag >= 0x80 {
+            self.buf.push((zigzag as u8 & 0x7f) | 0x80);
+            zigzag >>= 7;
+        }
+        self.buf.push(zigzag as u8);
+        self
+    }
+
+    pub(crate) fn varint(&mut self, v: i32) -> &mut Self {
+        self.varlong(i64::from(v))
+    }
+
+    pub(crate) fn varint_bytes(&mut self, v: Option<&[u8]>) -> &mut Self {
+        match v {
+            Some(v) => self.varint(v.len() as i32).raw(v),
+            None => self.varint(-1),
+        }
+    }
+}
+
+/// Kafka error codes the listener returns.
+pub(crate) mod error_code {
+    pub const UNKNOWN_SERVER_ERROR: i16 = -1;
+    pub const NONE: i16 = 0;
+    pub const OFFSET_OUT_OF_RANGE: i16 = 1;
+    pub const CORRUPT_MESSAGE: i16 = 2;
+    pub const UNKNOWN_TOPIC_OR_PARTITION: i16 = 3;
+    pub const NOT_LEADER_OR_FOLLOWER: i16 = 6;
+    pub const INVALID_TOPIC_EXCEPTION: i16 = 17;
+    pub const INVALID_GROUP_ID: i16 = 24;
+    pub const UNSUPPORTED_VERSION: i16 = 35;
+    pub const INVALID_REQUEST: i16 = 42;
+    pub const UNSUPPORTED_COMPRESSION_TYPE: i16 = 76;
+    pub const INVALID_RECORD: i16 = 87;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varlong_round_trips_zigzag_values() {
+        for value in [
+            0,
+            1,
+            -1,
+            63,
+            -64,
+            64,
+            300,
+            i64::from(i32::MAX),
+            i64::MIN,
+            i64::MAX,
+        ] {
+            let mut encoder = Encoder::new();
+            encoder.varlong(value);
+            let bytes = encoder.into_bytes();
+            let mut decoder = Decoder::new(&bytes);
+            assert_eq!(decoder.varlong().unwrap(), value);
+            assert_eq!(decoder.remaining(), 0);
+        }
+        // Zigzag puts small magnitudes of either sign in one byte
+        let mut encoder = Encoder::new();
+        encoder.varint(-1).varint(1);
+        assert_eq!(encoder.into_bytes(), vec![0x01, 0x02]);
+    }
+
+    #[test]
+    fn test_decoder_rejects_truncated_fields() {
+        let mut encoder = Encoder::new();
+        encoder.string("orders");
+        let bytes = encoder.into_bytes();
+        let mut decoder = Decoder::new(&bytes[..4]);
+        assert!(decoder.string().is_err());
+
+        let mut decoder = Decoder::new(&[0xff, 0xff]);
+        assert_eq!(decoder.nullable_string().unwrap(), None);
+    }
+}
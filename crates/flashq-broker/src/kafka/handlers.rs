@@ -0,0 +1,868 @@
+//! Request handlers, each decoding one API's request body and encoding its response body.
+
+use std::time::{Duration, Instant};
+
+use flashq_cluster::storage::{
+    FlashQError, Header, PartitionId, Record, RecordWithOffset, is_internal_topic,
+};
+
+use super::codec::{Decoder, Encoder, ProtocolError, error_code};
+use super::record_batch::{self, BatchError, KafkaRecord};
+use super::{KafkaListener, SUPPORTED_APIS};
+use crate::broker::{quota_bytes, validation};
+use crate::quota::{QuotaMetric, QuotaSubjects};
+
+/// Cluster id reported in metadata responses.
+const CLUSTER_ID: &str = "flashq";
+/// Longest topic name Kafka allows.
+const MAX_TOPIC_NAME_LENGTH: usize = 249;
+/// Most records read for one partition of a fetch.
+const MAX_FETCH_RECORDS: usize = 500;
+/// Longest a fetch waits for `min_bytes`, whatever the client asks for.
+const MAX_FETCH_WAIT: Duration = Duration::from_secs(30);
+/// How often a waiting fetch looks for new records.
+const FETCH_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Authorized operations value meaning "not requested".
+const OPERATIONS_OMITTED: i32 = i32::MIN;
+/// ListOffsets timestamps asking for the latest and earliest offsets.
+const LATEST_TIMESTAMP: i64 = -1;
+const EARLIEST_TIMESTAMP: i64 = -2;
+
+pub(super) fn api_versions(version: i16) -> Encoder {
+    let mut response = Encoder::new();
+    // Newer versions are answered in the v0 format, telling the client to retry with one
+    // listed here
+    let error = if version > 2 {
+        error_code::UNSUPPORTED_VERSION
+    } else {
+        error_code::NONE
+    };
+    response.i16(error);
+    response.array(SUPPORTED_APIS, |r, &(key, min, max)| {
+        r.i16(key).i16(min).i16(max);
+    });
+    if (1..=2).contains(&version) {
+        response.i32(0); // throttle_time_ms
+    }
+    response
+}
+
+struct ProducePartition {
+    index: i32,
+    error_code: i16,
+    base_offset: i64,
+    log_start_offset: i64,
+    error_message: Option<String>,
+}
+
+struct FetchPartition {
+    index: i32,
+    error_code: i16,
+    high_watermark: i64,
+    log_start_offset: i64,
+    records: Vec<u8>,
+    record_count: usize,
+    bytes: u64,
+}
+
+impl FetchPartition {
+    fn empty(index: i32, error_code: i16) -> Self {
+        Self {
+            index,
+            error_code,
+            high_watermark: -1,
+            log_start_offset: -1,
+            records: Vec::new(),
+            record_count: 0,
+            bytes: 0,
+        }
+    }
+}
+
+struct FetchRequestPartition {
+    index: i32,
+    fetch_offset: i64,
+    max_bytes: i32,
+}
+
+impl KafkaListener {
+    pub(super) fn metadata(
+        &self,
+        version: i16,
+        request: &mut Decoder<'_>,
+    ) -> Result<Encoder, ProtocolError> {
+        let topics = if version == 0 {
+            // v0 asks for every topic with an empty list
+            Some(request.array(Decoder::string)?).filter(|topics| !topics.is_empty())
+        } else {
+            request.nullable_array(Decoder::string)?
+        };
+        let allow_auto_topic_creation = version < 4 || request.bool()?;
+
+        let existing = self.core.get_topics();
+        let topics: Vec<(String, i16)> = match topics {
+            None => {
+                let mut all = existing;
+                all.sort();
+                all.into_iter().map(|t| (t, error_code::NONE)).collect()
+            }
+            // FlashQ creates topics on their first produce, so a topic that may be created
+            // is reported as if it already existed
+            Some(names) => names
+                .into_iter()
+                .map(|name| {
+                    let code = if let Some(code) = topic_error(&name) {
+                        code
+                    } else if allow_auto_topic_creation || existing.contains(&name) {
+                        error_code::NONE
+                    } else {
+                        error_code::UNKNOWN_TOPIC_OR_PARTITION
+                    };
+                    (name, code)
+                })
+                .collect(),
+        };
+
+        let mut response = Encoder::new();
+        if version >= 3 {
+            response.i32(0); // throttle_time_ms
+        }
+        response.array([()], |r, ()| {
+            r.i32(self.node_id)
+                .string(&self.host)
+                .i32(i32::from(self.port));
+            if version >= 1 {
+                r.nullable_string(None); // rack
+            }
+        });
+        if version >= 2 {
+            response.nullable_string(Some(CLUSTER_ID));
+        }
+        if version >= 1 {
+            response.i32(self.node_id); // controller_id
+        }
+        response.array(topics, |r, (name, code)| {
+            r.i16(code).string(&name);
+            if version >= 1 {
+                r.bool(false); // is_internal
+            }
+            let partitions = if code == error_code::NONE { 1 } else { 0 };
+            r.array(0..partitions, |r, index| {
+                r.i16(error_code::NONE).i32(index).i32(self.node_id);
+                if version >= 7 {
+                    r.i32(0); // leader_epoch
+                }
+                r.array([self.node_id], |r, node| {
+                    r.i32(node);
+                });
+                r.array([self.node_id], |r, node| {
+                    r.i32(node);
+                });
+                if version >= 5 {
+                    r.array(std::iter::empty::<i32>(), |r, node| {
+                        r.i32(node);
+                    });
+                }
+            });
+            if version >= 8 {
+                r.i32(OPERATIONS_OMITTED);
+            }
+        });
+        if version >= 8 {
+            response.i32(OPERATIONS_OMITTED);
+        }
+        Ok(response)
+    }
+
+    pub(super) async fn produce(
+        &self,
+        version: i16,
+        client_id: Option<&str>,
+        request: &mut Decoder<'_>,
+    ) -> Result<Option<Encoder>, ProtocolError> {
+        let _transactional_id = request.nullable_string()?;
+        let acks = request.i16()?;
+        let _timeout_ms = request.i32()?;
+        let topics = request.array(|d| {
+            let name = d.string()?;
+            let partitions =
+                d.array(|d| Ok((d.i32()?, d.nullable_bytes()?.unwrap_or_default())))?;
+            Ok((name, partitions))
+        })?;
+
+        let throttle_time_ms = self
+            .throttle(
+                client_id,
+                topics.iter().map(|(name, _)| name.as_str()),
+                &[QuotaMetric::Requests, QuotaMetric::ProduceBytes],
+            )
+            .await;
+        let mut results = Vec::with_capacity(topics.len());
+        for (name, partitions) in topics {
+            let subjects = QuotaSubjects::anonymous(client_id, &name);
+            let mut produced = Vec::with_capacity(partitions.len());
+            for (index, data) in partitions {
+                produced.push(self.produce_partition(&subjects, index, data).await);
+            }
+            results.push((name, produced));
+        }
+        if acks == 0 {
+            return Ok(None);
+        }
+
+        let mut response = Encoder::new();
+        response.array(results, |r, (name, partitions)| {
+            r.string(&name);
+            r.array(partitions, |r, p| {
+                r.i32(p.index).i16(p.error_code).i64(p.base_offset);
+                r.i64(-1); // log_append_time_ms
+                if version >= 5 {
+                    r.i64(p.log_start_offset);
+                }
+                if version >= 8 {
+                    r.array(std::iter::empty::<()>(), |_, ()| {}); // record_errors
+                    r.nullable_string(p.error_message.as_deref());
+                }
+            });
+        });
+        response.i32(throttle_time_ms);
+        Ok(Some(response))
+    }
+
+    async fn produce_partition(
+        &self,
+        subjects: &QuotaSubjects<'_>,
+        index: i32,
+        data: &[u8],
+    ) -> ProducePartition {
+        let started = Instant::now();
+        let topic = subjects.topic;
+        let failed = |error_code, message: String| ProducePartition {
+            index,
+            error_code,
+            base_offset: -1,
+            log_start_offset: -1,
+            error_message: Some(message),
+        };
+        if let Some(code) = topic_error(topic) {
+            return failed(code, format!("Topic '{topic}' cannot be produced to"));
+        }
+        if index != 0 {
+            return failed(
+                error_code::UNKNOWN_TOPIC_OR_PARTITION,
+                format!("Topic '{topic}' has only partition 0"),
+            );
+        }
+        // Retriable: the client refreshes metadata and tries again
+        if !self.lifecycle.accepts_produce() {
+            return failed(
+                error_code::NOT_LEADER_OR_FOLLOWER,
+                format!(
+                    "Broker is {} and not accepting produce requests",
+                    self.lifecycle.state()
+                ),
+            );
+        }
+        let records: Vec<Record> = match record_batch::decode_batches(data)
+            .and_then(|records| records.into_iter().map(to_flashq_record).collect())
+        {
+            Ok(records) => records,
+            Err(BatchError { code, message }) => return failed(code, message),
+        };
+        let record_count = records.len();
+        if record_count == 0 {
+            return ProducePartition {
+                index,
+                error_code: error_code::NONE,
+                base_offset: self.core.get_high_water_mark(topic) as i64,
+                log_start_offset: self.core.get_log_start_offset(topic) as i64,
+                error_message: None,
+            };
+        }
+        let bytes_in = records.iter().map(quota_bytes).sum();
+        self.quotas
+            .record(subjects, QuotaMetric::ProduceBytes, bytes_in);
+
+        match self
+            .core
+            .post_records_async(topic.to_string(), records, None)
+            .await
+        {
+            Ok(last_offset) => {
+                crate::metrics::record_produce(topic, record_count, bytes_in, started.elapsed());
+                ProducePartition {
+                    index,
+                    error_code: error_code::NONE,
+                    base_offset: (last_offset + 1 - record_count as u64) as i64,
+                    log_start_offset: self.core.get_log_start_offset(topic) as i64,
+                    error_message: None,
+                }
+            }
+            Err(e) => {
+                tracing::error!(%topic, "Kafka produce failed: {e}");
+                failed(error_code::UNKNOWN_SERVER_ERROR, e.to_string())
+            }
+        }
+    }
+
+    pub(super) async fn fetch(
+        &self,
+        version: i16,
+        client_id: Option<&str>,
+        request: &mut Decoder<'_>,
+    ) -> Result<Encoder, ProtocolError> {
+        let started = Instant::now();
+        let _replica_id = request.i32()?;
+        let max_wait_ms = request.i32()?;
+        let min_bytes = request.i32()?;
+        let max_bytes = request.i32()?;
+        let _isolation_level = request.i8()?;
+        if version >= 7 {
+            let _session_id = request.i32()?;
+            let _session_epoch = request.i32()?;
+        }
+        let topics = request.array(|d| {
+            let name = d.string()?;
+            let partitions = d.array(|d| {
+                let index = d.i32()?;
+                if version >= 9 {
+                    let _current_leader_epoch = d.i32()?;
+                }
+                let fetch_offset = d.i64()?;
+                if version >= 5 {
+                    let _log_start_offset = d.i64()?;
+                }
+                let max_bytes = d.i32()?;
+                Ok(FetchRequestPartition {
+                    index,
+                    fetch_offset,
+                    max_bytes,
+                })
+            })?;
+            Ok((name, partitions))
+        })?;
+        if version >= 7 {
+            let _forgotten_topics = request.array(|d| {
+                d.string()?;
+                d.array(Decoder::i32)
+            })?;
+        }
+        if version >= 11 {
+            let _rack_id = request.string()?;
+        }
+
+        let throttle_time_ms = self
+            .throttle(
+                client_id,
+                topics.iter().map(|(name, _)| name.as_str()),
+                &[QuotaMetric::Requests, QuotaMetric::FetchBytes],
+            )
+            .await;
+
+        // Wait for min_bytes, up to max_wait_ms, answering at once if any partition fails
+        let wait = Duration::from_millis(max_wait_ms.max(0) as u64).min(MAX_FETCH_WAIT);
+        let deadline = Instant::now() + wait;
+        let results = loop {
+            let results = self.read_partitions(&topics, max_bytes).await;
+            let partitions = || results.iter().flat_map(|(_, partitions)| partitions);
+            let bytes: usize = partitions().map(|p| p.records.len()).sum();
+            let failed = partitions().any(|p| p.error_code != error_code::NONE);
+            let now = Instant::now();
+            if failed || bytes >= min_bytes.max(0) as usize || now >= deadline {
+                break results;
+            }
+            tokio::time::sleep(FETCH_POLL_INTERVAL.min(deadline - now)).await;
+        };
+        for (topic, partitions) in &results {
+            let subjects = QuotaSubjects::anonymous(client_id, topic);
+            for p in partitions.iter().filter(|p| p.record_count > 0) {
+                self.quotas
+                    .record(&subjects, QuotaMetric::FetchBytes, p.bytes);
+                crate::metrics::record_fetch(topic, p.record_count, p.bytes, started.elapsed());
+            }
+        }
+
+        let mut response = Encoder::new();
+        response.i32(throttle_time_ms);
+        if version >= 7 {
+            response.i16(error_code::NONE).i32(0); // no fetch session
+        }
+        response.array(results, |r, (topic, partitions)| {
+            r.string(&topic);
+            r.array(partitions, |r, p| {
+                r.i32(p.index)
+                    .i16(p.error_code)
+                    .i64(p.high_watermark)
+                    .i64(p.high_watermark); // last_stable_offset
+                if version >= 5 {
+                    r.i64(p.log_start_offset);
+                }
+                r.array(std::iter::empty::<()>(), |_, ()| {}); // aborted_transactions
+                if version >= 11 {
+                    r.i32(-1); // preferred_read_replica
+                }
+                r.nullable_bytes(Some(&p.records));
+            });
+        });
+        Ok(response)
+    }
+
+    /// Hold a request until every topic it names is back within its quotas on `metrics`,
+    /// then charge each topic one request. Kafka clients expect to be slowed down rather
+    /// than failed, so this throttles whatever the broker's enforcement. Returns the wait in
+    /// milliseconds for the response's `throttle_time_ms`.
+    async fn throttle<'a>(
+        &self,
+        client_id: Option<&'a str>,
+        topics: impl Iterator<Item = &'a str> + Clone,
+        metrics: &[QuotaMetric],
+    ) -> i32 {
+        let delay = topics
+            .clone()
+            .filter_map(|topic| {
+                self.quotas
+                    .check(&QuotaSubjects::anonymous(client_id, topic), metrics)
+            })
+            .map(|violation| violation.delay)
+            .max()
+            .unwrap_or_default();
+        if !delay.is_zero() {
+            tracing::debug!(?client_id, ?delay, "Throttling Kafka request");
+            tokio::time::sleep(delay).await;
+        }
+        for topic in topics {
+            self.quotas.record(
+                &QuotaSubjects::anonymous(client_id, topic),
+                QuotaMetric::Requests,
+                1,
+            );
+        }
+        delay.as_millis().min(i32::MAX as u128) as i32
+    }
+
+    async fn read_partitions(
+        &self,
+        topics: &[(String, Vec<FetchRequestPartition>)],
+        max_bytes: i32,
+    ) -> Vec<(String, Vec<FetchPartition>)> {
+        let mut budget = max_bytes.max(0) as usize;
+        let mut first_records = true;
+        let mut results = Vec::with_capacity(topics.len());
+        for (topic, partitions) in topics {
+            let mut read = Vec::with_capacity(partitions.len());
+            for partition in partitions {
+                let limit = budget.min(partition.max_bytes.max(0) as usize);
+                // The first records of a response are sent even if they exceed the limits,
+                // so a consumer is never stuck behind one large record
+                let result = self
+                    .read_partition(topic, partition, limit, first_records)
+                    .await;
+                if result.record_count > 0 {
+                    first_records = false;
+                    budget = budget.saturating_sub(result.records.len());
+                }
+                read.push(result);
+            }
+            results.push((topic.clone(), read));
+        }
+        results
+    }
+
+    async fn read_partition(
+        &self,
+        topic: &str,
+        partition: &FetchRequestPartition,
+        max_bytes: usize,
+        allow_oversized: bool,
+    ) -> FetchPartition {
+        if topic_error(topic).is_some() || partition.index != 0 {
+            return FetchPartition::empty(partition.index, error_code::UNKNOWN_TOPIC_OR_PARTITION);
+        }
+        let high_watermark = self.core.get_high_water_mark(topic) as i64;
+        let log_start_offset = self.core.get_log_start_offset(topic) as i64;
+        let mut result = FetchPartition {
+            high_watermark,
+            log_start_offset,
+            ..FetchPartition::empty(partition.index, error_code::NONE)
+        };
+        if partition.fetch_offset < log_start_offset || partition.fetch_offset > high_watermark {
+            result.error_code = error_code::OFFSET_OUT_OF_RANGE;
+            return result;
+        }
+        if partition.fetch_offset == high_watermark {
+            return result;
+        }
+
+        let records = match self
+            .core
+            .poll_records_from_offset_async(
+                topic.to_string(),
+                partition.fetch_offset as u64,
+                Some(MAX_FETCH_RECORDS),
+            )
+            .await
+        {
+            Ok(records) => records,
+            Err(FlashQError::TopicNotFound { .. }) => return result,
+            Err(e) => {
+                tracing::error!(%topic, "Kafka fetch failed: {e}");
+                result.error_code = error_code::UNKNOWN_SERVER_ERROR;
+                return result;
+            }
+        };
+
+        // Record sizes in a batch are close to their key, value and header bytes; the batch
+        // header adds about 61 bytes
+        let mut size = 61;
+        let mut batch = Vec::with_capacity(records.len());
+        for record in records {
+            let record_size = quota_bytes(&record.record) as usize + 16;
+            if size + record_size > max_bytes && !(batch.is_empty() && allow_oversized) {
+                break;
+            }
+            size += record_size;
+            result.bytes += quota_bytes(&record.record);
+            batch.push(to_kafka_record(record));
+        }
+        result.record_count = batch.len();
+        result.records = record_batch::encode_batch(&batch);
+        result
+    }
+
+    pub(super) async fn list_offsets(
+        &self,
+        version: i16,
+        request: &mut Decoder<'_>,
+    ) -> Result<Encoder, ProtocolError> {
+        let _replica_id = request.i32()?;
+        if version >= 2 {
+            let _isolation_level = request.i8()?;
+        }
+        let topics = request.array(|d| {
+            let name = d.string()?;
+            let partitions = d.array(|d| {
+                let index = d.i32()?;
+                if version >= 4 {
+                    let _current_leader_epoch = d.i32()?;
+                }
+                Ok((index, d.i64()?))
+            })?;
+            Ok((name, partitions))
+        })?;
+
+        let mut results = Vec::with_capacity(topics.len());
+        for (topic, partitions) in topics {
+            let mut offsets = Vec::with_capacity(partitions.len());
+            for (index, timestamp) in partitions {
+                offsets.push((index, self.offset_for(&topic, index, timestamp).await));
+            }
+            results.push((topic, offsets));
+        }
+
+        let mut response = Encoder::new();
+        if version >= 2 {
+            response.i32(0); // throttle_time_ms
+        }
+        response.array(results, |r, (topic, partitions)| {
+            r.string(&topic);
+            r.array(partitions, |r, (index, (code, timestamp, offset))| {
+                r.i32(index).i16(code).i64(timestamp).i64(offset);
+                if version >= 4 {
+                    r.i32(0); // leader_epoch
+                }
+            });
+        });
+        Ok(response)
+    }
+
+    /// Error code, timestamp and offset answering a ListOffsets query.
+    async fn offset_for(&self, topic: &str, index: i32, timestamp: i64) -> (i16, i64, i64) {
+        if topic_error(topic).is_some() || index != 0 {
+            return (error_code::UNKNOWN_TOPIC_OR_PARTITION, -1, -1);
+        }
+        match timestamp {
+            LATEST_TIMESTAMP => (
+                error_code::NONE,
+                -1,
+                self.core.get_high_water_mark(topic) as i64,
+            ),
+            EARLIEST_TIMESTAMP => (
+                error_code::NONE,
+                -1,
+                self.core.get_log_start_offset(topic) as i64,
+            ),
+            timestamp => {
+                let Some(time) = chrono::DateTime::from_timestamp_millis(timestamp) else {
+                    return (error_code::INVALID_REQUEST, -1, -1);
+                };
+                let time = time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+                let found = self
+                    .core
+                    .list_offsets_async(topic.to_string(), vec![PartitionId::new(0)], Some(time))
+                    .await
+                    .map(|partitions| partitions.first().and_then(|p| p.offset_for_timestamp));
+                let offset = match found {
+                    Ok(Some(offset)) => offset,
+                    Ok(None) | Err(FlashQError::TopicNotFound { .. }) => {
+                        return (error_code::NONE, -1, -1);
+                    }
+                    Err(e) => {
+                        tracing::error!(%topic, "Kafka list offsets failed: {e}");
+                        return (error_code::UNKNOWN_SERVER_ERROR, -1, -1);
+                    }
+                };
+                // Kafka answers with the timestamp of the record found
+                let found_timestamp = self
+                    .core
+                    .poll_records_from_offset_async(topic.to_string(), offset, Some(1))
+                    .await
+                    .ok()
+                    .and_then(|records| records.into_iter().next())
+                    .map_or(-1, |record| timestamp_millis(&record.timestamp));
+                (error_code::NONE, found_timestamp, offset as i64)
+            }
+        }
+    }
+
+    pub(super) fn find_coordinator(
+        &self,
+        version: i16,
+        request: &mut Decoder<'_>,
+    ) -> Result<Encoder, ProtocolError> {
+        let _key = request.string()?;
+        if version >= 1 {
+            let _key_type = request.i8()?;
+        }
+        // This broker coordinates every group
+        let mut response = Encoder::new();
+        if version >= 1 {
+            response.i32(0); // throttle_time_ms
+        }
+        response.i16(error_code::NONE);
+        if version >= 1 {
+            response.nullable_string(None);
+        }
+        response
+            .i32(self.node_id)
+            .string(&self.host)
+            .i32(i32::from(self.port));
+        Ok(response)
+    }
+
+    pub(super) async fn offset_commit(
+        &self,
+        version: i16,
+        request: &mut Decoder<'_>,
+    ) -> Result<Encoder, ProtocolError> {
+        let group_id = request.string()?;
+        let _generation_id = request.i32()?;
+        let _member_id = request.string()?;
+        if version <= 4 {
+            let _retention_time_ms = request.i64()?;
+        }
+        if version >= 7 {
+            let _group_instance_id = request.nullable_string()?;
+        }
+        let topics = request.array(|d| {
+            let name = d.string()?;
+            let partitions = d.array(|d| {
+                let index = d.i32()?;
+                let offset = d.i64()?;
+                if version >= 6 {
+                    let _committed_leader_epoch = d.i32()?;
+                }
+                let _metadata = d.nullable_string()?;
+                Ok((index, offset))
+            })?;
+            Ok((name, partitions))
+        })?;
+
+        // Groups are created by their first commit
+        let group_error = if group_id.is_empty() {
+            Some(error_code::INVALID_GROUP_ID)
+        } else {
+            match self
+                .core
+                .create_consumer_group_async(group_id.clone())
+                .await
+            {
+                Ok(()) | Err(FlashQError::ConsumerGroupAlreadyExists { .. }) => None,
+                Err(e) => {
+                    tracing::error!(%group_id, "Kafka offset commit failed: {e}");
+                    Some(error_code::UNKNOWN_SERVER_ERROR)
+                }
+            }
+        };
+
+        let mut results = Vec::with_capacity(topics.len());
+        for (topic, partitions) in topics {
+            let mut committed = Vec::with_capacity(partitions.len());
+            for (index, offset) in partitions {
+                let code = if let Some(code) = group_error {
+                    code
+                } else if topic_error(&topic).is_some() || index != 0 {
+                    error_code::UNKNOWN_TOPIC_OR_PARTITION
+                } else if offset < 0 {
+                    error_code::OFFSET_OUT_OF_RANGE
+                } else {
+                    match self
+                        .core
+                        .update_consumer_group_offset_async(
+                            group_id.clone(),
+                            topic.clone(),
+                            offset as u64,
+                        )
+                        .await
+                    {
+                        Ok(()) => error_code::NONE,
+                        Err(FlashQError::InvalidOffset { .. }) => error_code::OFFSET_OUT_OF_RANGE,
+                        Err(e) => {
+                            tracing::error!(%group_id, %topic, "Kafka offset commit failed: {e}");
+                            error_code::UNKNOWN_SERVER_ERROR
+                        }
+                    }
+                };
+                committed.push((index, code));
+            }
+            results.push((topic, committed));
+        }
+
+        let mut response = Encoder::new();
+        if version >= 3 {
+            response.i32(0); // throttle_time_ms
+        }
+        response.array(results, |r, (topic, partitions)| {
+            r.string(&topic);
+            r.array(partitions, |r, (index, code)| {
+                r.i32(index).i16(code);
+            });
+        });
+        Ok(response)
+    }
+
+    pub(super) fn offset_fetch(
+        &self,
+        version: i16,
+        request: &mut Decoder<'_>,
+    ) -> Result<Encoder, ProtocolError> {
+        let group_id = request.string()?;
+        let topic_partitions = |d: &mut Decoder<'_>| Ok((d.string()?, d.array(Decoder::i32)?));
+        let topics = if version >= 2 {
+            request.nullable_array(topic_partitions)?
+        } else {
+            Some(request.array(topic_partitions)?)
+        };
+
+        let committed: Vec<(String, PartitionId, u64)> = self
+            .core
+            .committed_offsets()
+            .into_iter()
+            .filter(|c| c.group_id == group_id)
+            .map(|c| (c.topic, c.partition, c.offset))
+            .collect();
+        // A null topic list asks for everything the group has committed
+        let topics = topics.unwrap_or_else(|| {
+            let mut topics: Vec<(String, Vec<i32>)> = Vec::new();
+            for (topic, partition, _) in &committed {
+                let index = partition.as_u32() as i32;
+                match topics.iter_mut().find(|(t, _)| t == topic) {
+                    Some((_, partitions)) => partitions.push(index),
+                    None => topics.push((topic.clone(), vec![index])),
+                }
+            }
+            topics
+        });
+
+        let mut response = Encoder::new();
+        if version >= 3 {
+            response.i32(0); // throttle_time_ms
+        }
+        response.array(topics, |r, (topic, partitions)| {
+            r.string(&topic);
+            r.array(partitions, |r, index| {
+                let offset = committed
+                    .iter()
+                    .find(|(t, p, _)| *t == topic && p.as_u32() as i32 == index)
+                    .map_or(-1, |(_, _, offset)| *offset as i64);
+                r.i32(index).i64(offset);
+                if version >= 5 {
+                    r.i32(-1); // committed_leader_epoch
+                }
+                r.nullable_string(Some("")).i16(error_code::NONE);
+            });
+        });
+        if version >= 2 {
+            response.i16(error_code::NONE);
+        }
+        Ok(response)
+    }
+}
+
+/// The error for a topic name Kafka clients may not use here: names Kafka itself rejects,
+/// and FlashQ's internal topics.
+fn topic_error(topic: &str) -> Option<i16> {
+    let legal = !topic.is_empty()
+        && topic.len() <= MAX_TOPIC_NAME_LENGTH
+        && topic != "."
+        && topic != ".."
+        && topic
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    (!legal || is_internal_topic(topic)).then_some(error_code::INVALID_TOPIC_EXCEPTION)
+}
+
+fn to_flashq_record(record: KafkaRecord) -> Result<Record, BatchError> {
+    let utf8 = |bytes: Vec<u8>, what: &str| {
+        String::from_utf8(bytes).map_err(|_| {
+            BatchError::new(
+                error_code::INVALID_RECORD,
+                format!("Record {what} is not UTF-8"),
+            )
+        })
+    };
+    let key = record.key.map(|key| utf8(key, "key")).transpose()?;
+    let value = record.value.ok_or_else(|| {
+        BatchError::new(
+            error_code::INVALID_RECORD,
+            "Records without a value (tombstones) are not supported",
+        )
+    })?;
+    let value = utf8(value, "value")?;
+    let headers: Vec<Header> = record
+        .headers
+        .into_iter()
+        .map(|(name, value)| Header::new(name, value.unwrap_or_default()))
+        .collect();
+    let headers = (!headers.is_empty()).then_some(headers);
+
+    let mut flashq_record = Record::new(key, value, headers);
+    if let Some(time) =
+        chrono::DateTime::from_timestamp_millis(record.timestamp).filter(|_| record.timestamp >= 0)
+    {
+        flashq_record = flashq_record
+            .with_create_time(time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
+    }
+    validation::validate_record_for_grpc(&flashq_record)
+        .map_err(|status| BatchError::new(error_code::INVALID_RECORD, status.message()))?;
+    Ok(flashq_record)
+}
+
+fn to_kafka_record(record: RecordWithOffset) -> KafkaRecord {
+    KafkaRecord {
+        offset: record.offset as i64,
+        timestamp: timestamp_millis(&record.timestamp),
+        key: record.record.key.map(String::into_bytes),
+        value: Some(record.record.value.into_bytes()),
+        headers: record
+            .record
+            .headers
+            .unwrap_or_default()
+            .into_iter()
+            .map(|header| (header.key, Some(header.value)))
+            .collect(),
+    }
+}
+
+fn timestamp_millis(timestamp: &str) -> i64 {
+    chrono::DateTime::parse_from_rfc3339(timestamp).map_or(-1, |time| time.timestamp_millis())
+}
@@ -0,0 +1,223 @@
+//! Kafka wire-protocol listener.
+//!
+//! Serves a subset of the Kafka binary protocol over FlashQ topics and consumer groups, so
+//! stock Kafka clients can produce to and consume from basic topics: ApiVersions, Metadata,
+//! Produce, Fetch, ListOffsets, FindCoordinator, OffsetCommit and OffsetFetch, each at its
+//! non-flexible versions. Every topic appears as a single partition, 0, led by this broker,
+//! which also coordinates every group. Group membership (JoinGroup, SyncGroup, Heartbeat)
+//! is not implemented, so consumers assign partitions themselves and commit offsets by
+//! group id. Records must carry UTF-8 keys and values in uncompressed v2 batches; producers
+//! should disable idempotence, since InitProducerId is not offered.
+//!
+//! As with the REST gateway, connections are not authenticated. Produces and fetches are
+//! charged to the broker's quotas as the anonymous principal, under the request header's
+//! client id; calls over quota are always held back and report the wait in
+//! `throttle_time_ms`, since Kafka clients expect throttling rather than errors. While the
+//! broker drains, produces fail with NOT_LEADER_OR_FOLLOWER so clients refresh metadata and
+//! retry.
+
+mod codec;
+mod handlers;
+mod record_batch;
+
+use std::io;
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+
+use crate::lifecycle::Lifecycle;
+use crate::quota::QuotaManager;
+use codec::{Decoder, Encoder, ProtocolError};
+
+/// Largest request frame accepted; bigger frames close the connection.
+const MAX_REQUEST_SIZE: usize = 100 * 1024 * 1024;
+
+/// API keys served, with the lowest and highest version of each.
+const SUPPORTED_APIS: &[(i16, i16, i16)] = &[
+    (api_key::PRODUCE, 3, 8),
+    (api_key::FETCH, 4, 11),
+    (api_key::LIST_OFFSETS, 1, 5),
+    (api_key::METADATA, 0, 8),
+    (api_key::OFFSET_COMMIT, 2, 7),
+    (api_key::OFFSET_FETCH, 1, 5),
+    (api_key::FIND_COORDINATOR, 0, 2),
+    (api_key::API_VERSIONS, 0, 2),
+];
+
+mod api_key {
+    pub const PRODUCE: i16 = 0;
+    pub const FETCH: i16 = 1;
+    pub const LIST_OFFSETS: i16 = 2;
+    pub const METADATA: i16 = 3;
+    pub const OFFSET_COMMIT: i16 = 8;
+    pub const OFFSET_FETCH: i16 = 9;
+    pub const FIND_COORDINATOR: i16 = 10;
+    pub const API_VERSIONS: i16 = 18;
+}
+
+/// Serves the Kafka protocol for one broker.
+#[derive(Clone)]
+pub struct KafkaListener {
+    core: Arc<flashq_cluster::FlashQ>,
+    node_id: i32,
+    host: String,
+    port: u16,
+    lifecycle: Lifecycle,
+    quotas: Arc<QuotaManager>,
+}
+
+impl KafkaListener {
+    /// A listener for `core` that tells clients to reach broker `node_id` at `host:port`.
+    pub fn new(
+        core: Arc<flashq_cluster::FlashQ>,
+        node_id: i32,
+        host: impl Into<String>,
+        port: u16,
+    ) -> Self {
+        Self {
+            core,
+            node_id,
+            host: host.into(),
+            port,
+            lifecycle: Lifecycle::new(),
+            quotas: Arc::new(QuotaManager::in_memory()),
+        }
+    }
+
+    /// Refuse produces and stop serving as `lifecycle` drains and shuts down.
+    pub fn with_lifecycle(mut self, lifecycle: Lifecycle) -> Self {
+        self.lifecycle = lifecycle;
+        self
+    }
+
+    /// Charge produce and fetch requests against `quotas`.
+    pub fn with_quotas(mut self, quotas: Arc<QuotaManager>) -> Self {
+        self.quotas = quotas;
+        self
+    }
+
+    /// Accept Kafka connections on `listener` until the lifecycle begins shutting down, then
+    /// return once every connection has answered its in-flight request and closed.
+    pub async fn serve(self, listener: TcpListener) -> io::Result<()> {
+        let mut connections = JoinSet::new();
+        loop {
+            let (stream, peer) = tokio::select! {
+                accepted = listener.accept() => accepted?,
+                _ = self.lifecycle.shutdown_requested() => break,
+                // Reap finished connections so the set does not grow without bound
+                Some(_) = connections.join_next() => continue,
+            };
+            let connection = self.clone();
+            connections.spawn(async move {
+                if let Err(e) = connection.handle_connection(stream).await {
+                    tracing::debug!(%peer, "Kafka connection closed: {e}");
+                }
+            });
+        }
+        drop(listener);
+        while connections.join_next().await.is_some() {}
+        Ok(())
+    }
+
+    /// Answer requests on one connection in the order they arrive, as Kafka clients expect.
+    /// Shutdown closes the connection between requests, never during one.
+    async fn handle_connection(&self, mut stream: TcpStream) -> io::Result<()> {
+        loop {
+            let mut size = [0u8; 4];
+            let read = tokio::select! {
+                read = stream.read_exact(&mut size) => read,
+                _ = self.lifecycle.shutdown_requested() => return Ok(()),
+            };
+            match read {
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e),
+            }
+            let size = i32::from_be_bytes(size);
+            if size < 0 || size as usize > MAX_REQUEST_SIZE {
+                return Err(invalid_data(format!("Request size {size} is out of range")));
+            }
+            let mut frame = vec![0u8; size as usize];
+            stream.read_exact(&mut frame).await?;
+
+            let mut request = Decoder::new(&frame);
+            let header = RequestHeader::decode(&mut request).map_err(invalid_data)?;
+            let body = self
+                .handle_request(&header, &mut request)
+                .await
+                .map_err(|e| {
+                    invalid_data(format!(
+                        "API key {} v{} (client {:?}): {e}",
+                        header.api_key, header.api_version, header.client_id
+                    ))
+                })?;
+            // Produce with acks=0 gets no response at all
+            let Some(body) = body else {
+                continue;
+            };
+
+            let body = body.into_bytes();
+            let mut response = Encoder::new();
+            response
+                .i32(4 + body.len() as i32)
+                .i32(header.correlation_id)
+                .raw(&body);
+            stream.write_all(&response.into_bytes()).await?;
+        }
+    }
+
+    async fn handle_request(
+        &self,
+        header: &RequestHeader,
+        request: &mut Decoder<'_>,
+    ) -> Result<Option<Encoder>, ProtocolError> {
+        let version = header.api_version;
+        let client_id = header.client_id.as_deref();
+        if header.api_key == api_key::API_VERSIONS {
+            return Ok(Some(handlers::api_versions(version)));
+        }
+        let supported = SUPPORTED_APIS
+            .iter()
+            .any(|&(key, min, max)| key == header.api_key && (min..=max).contains(&version));
+        if !supported {
+            return Err(ProtocolError::new("API or version is not supported"));
+        }
+        let response = match header.api_key {
+            api_key::PRODUCE => return self.produce(version, client_id, request).await,
+            api_key::FETCH => self.fetch(version, client_id, request).await?,
+            api_key::LIST_OFFSETS => self.list_offsets(version, request).await?,
+            api_key::METADATA => self.metadata(version, request)?,
+            api_key::OFFSET_COMMIT => self.offset_commit(version, request).await?,
+            api_key::OFFSET_FETCH => self.offset_fetch(version, request)?,
+            api_key::FIND_COORDINATOR => self.find_coordinator(version, request)?,
+            _ => unreachable!("checked against SUPPORTED_APIS"),
+        };
+        Ok(Some(response))
+    }
+}
+
+/// Request header v1, which every non-flexible request uses. Flexible ApiVersions requests
+/// (v3+) start the same way, and their body is never read.
+struct RequestHeader {
+    api_key: i16,
+    api_version: i16,
+    correlation_id: i32,
+    client_id: Option<String>,
+}
+
+impl RequestHeader {
+    fn decode(d: &mut Decoder<'_>) -> Result<Self, ProtocolError> {
+        Ok(Self {
+            api_key: d.i16()?,
+            api_version: d.i16()?,
+            correlation_id: d.i32()?,
+            client_id: d.nullable_string()?,
+        })
+    }
+}
+
+fn invalid_data(message: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
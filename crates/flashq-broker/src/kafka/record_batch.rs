@@ -0,0 +1,297 @@
+//! Kafka record batches (message format v2), the payload of produce requests and fetch
+//! responses.
+
+use super::codec::{Decoder, Encoder, ProtocolError, error_code};
+
+const MAGIC_V2: i8 = 2;
+/// Position of the `crc` field, after base offset, batch length, partition leader epoch and
+/// magic. The CRC covers everything after it.
+const CRC_OFFSET: usize = 8 + 4 + 4 + 1;
+const CRC_END: usize = CRC_OFFSET + 4;
+/// Size of the fields before `batchLength`, which it does not count.
+const LENGTH_PREFIX: usize = 8 + 4;
+
+const COMPRESSION_MASK: i16 = 0x07;
+const TRANSACTIONAL_FLAG: i16 = 0x10;
+const CONTROL_FLAG: i16 = 0x20;
+
+/// One record as carried in a batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct KafkaRecord {
+    pub(crate) offset: i64,
+    /// Milliseconds since the Unix epoch.
+    pub(crate) timestamp: i64,
+    pub(crate) key: Option<Vec<u8>>,
+    pub(crate) value: Option<Vec<u8>>,
+    pub(crate) headers: Vec<(String, Option<Vec<u8>>)>,
+}
+
+/// Why a produced batch was refused, with the Kafka error code to answer with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BatchError {
+    pub(crate) code: i16,
+    pub(crate) message: String,
+}
+
+impl BatchError {
+    pub(crate) fn new(code: i16, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<ProtocolError> for BatchError {
+    fn from(err: ProtocolError) -> Self {
+        Self::new(error_code::CORRUPT_MESSAGE, err.to_string())
+    }
+}
+
+/// Decode every batch in a produce request's record set. Offsets are relative to the start
+/// of the record set, since the broker assigns the real ones.
+pub(crate) fn decode_batches(data: &[u8]) -> Result<Vec<KafkaRecord>, BatchError> {
+    let mut records = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let mut header = Decoder::new(rest);
+        let _base_offset = header.i64()?;
+        let batch_length = header.i32()?;
+        if batch_length < 0 || rest.len() < LENGTH_PREFIX + batch_length as usize {
+            return Err(BatchError::new(
+                error_code::CORRUPT_MESSAGE,
+                "Record batch is truncated",
+            ));
+        }
+        let (batch, tail) = rest.split_at(LENGTH_PREFIX + batch_length as usize);
+        decode_batch(batch, records.len() as i64, &mut records)?;
+        rest = tail;
+    }
+    Ok(records)
+}
+
+fn decode_batch(
+    batch: &[u8],
+    first_offset: i64,
+    records: &mut Vec<KafkaRecord>,
+) -> Result<(), BatchError> {
+    if batch.len() < CRC_END {
+        return Err(BatchError::new(
+            error_code::CORRUPT_MESSAGE,
+            "Record batch is truncated",
+        ));
+    }
+    let mut d = Decoder::new(&batch[CRC_OFFSET - 1..]);
+    let magic = d.i8()?;
+    if magic != MAGIC_V2 {
+        return Err(BatchError::new(
+            error_code::CORRUPT_MESSAGE,
+            format!("Message format v{magic} is not supported; only v2 record batches are"),
+        ));
+    }
+    let crc = d.u32()?;
+    if crc32c::crc32c(&batch[CRC_END..]) != crc {
+        return Err(BatchError::new(
+            error_code::CORRUPT_MESSAGE,
+            "Record batch CRC does not match its contents",
+        ));
+    }
+    let attributes = d.i16()?;
+    if attributes & COMPRESSION_MASK != 0 {
+        return Err(BatchError::new(
+            error_code::UNSUPPORTED_COMPRESSION_TYPE,
+            "Compressed record batches are not supported",
+        ));
+    }
+    if attributes & (TRANSACTIONAL_FLAG | CONTROL_FLAG) != 0 {
+        return Err(BatchError::new(
+            error_code::INVALID_RECORD,
+            "Transactional and control batches are not supported",
+        ));
+    }
+    let _last_offset_delta = d.i32()?;
+    let base_timestamp = d.i64()?;
+    let _max_timestamp = d.i64()?;
+    let _producer_id = d.i64()?;
+    let _producer_epoch = d.i16()?;
+    let _base_sequence = d.i32()?;
+    let count = d.i32()?;
+
+    for _ in 0..count {
+        let length = d.varint()?;
+        if length < 0 {
+            return Err(BatchError::new(
+                error_code::CORRUPT_MESSAGE,
+                "Record has a negative length",
+            ));
+        }
+        let mut r = Decoder::new(d.take(length as usize)?);
+        let _attributes = r.i8()?;
+        let timestamp_delta = r.varlong()?;
+        let offset_delta = r.varint()?;
+        let key = r.varint_bytes()?.map(<[u8]>::to_vec);
+        let value = r.varint_bytes()?.map(<[u8]>::to_vec);
+        let header_count = r.varint()?;
+        // Each header takes at least two bytes, which bounds what a corrupt count can allocate
+        let mut headers = Vec::with_capacity((header_count.max(0) as usize).min(r.remaining()));
+        for _ in 0..header_count {
+            let name = r.varint_bytes()?.unwrap_or_default();
+            let name = String::from_utf8(name.to_vec()).map_err(|_| {
+                BatchError::new(error_code::INVALID_RECORD, "Header key is not UTF-8")
+            })?;
+            headers.push((name, r.varint_bytes()?.map(<[u8]>::to_vec)));
+        }
+        records.push(KafkaRecord {
+            offset: first_offset + i64::from(offset_delta),
+            timestamp: base_timestamp.saturating_add(timestamp_delta),
+            key,
+            value,
+            headers,
+        });
+    }
+    if d.remaining() != 0 {
+        return Err(BatchError::new(
+            error_code::CORRUPT_MESSAGE,
+            "Record batch has bytes after its last record",
+        ));
+    }
+    Ok(())
+}
+
+/// Encode `records`, in offset order, as one uncompressed batch.
+pub(crate) fn encode_batch(records: &[KafkaRecord]) -> Vec<u8> {
+    let Some(first) = records.first() else {
+        return Vec::new();
+    };
+    let base_offset = first.offset;
+    let base_timestamp = first.timestamp;
+    let max_timestamp = records.iter().map(|r| r.timestamp).max().unwrap_or(-1);
+
+    let mut body = Encoder::new();
+    body.i16(0) // attributes: no compression, create time
+        .i32((records[records.len() - 1].offset - base_offset) as i32)
+        .i64(base_timestamp)
+        .i64(max_timestamp)
+        .i64(-1) // producer id
+        .i16(-1) // producer epoch
+        .i32(-1) // base sequence
+        .i32(records.len() as i32);
+    for record in records {
+        let mut r = Encoder::new();
+        r.i8(0)
+            .varlong(record.timestamp - base_timestamp)
+            .varint((record.offset - base_offset) as i32)
+            .varint_bytes(record.key.as_deref())
+            .varint_bytes(record.value.as_deref())
+            .varint(record.headers.len() as i32);
+        for (name, value) in &record.headers {
+            r.varint_bytes(Some(name.as_bytes()))
+                .varint_bytes(value.as_deref());
+        }
+        let r = r.into_bytes();
+        body.varint(r.len() as i32).raw(&r);
+    }
+    frame_batch(base_offset, &body.into_bytes())
+}
+
+/// Wrap an encoded batch body (attributes onwards) in the batch header and CRC.
+fn frame_batch(base_offset: i64, body: &[u8]) -> Vec<u8> {
+    let mut batch = Encoder::new();
+    batch
+        .i64(base_offset)
+        .i32((CRC_END - LENGTH_PREFIX + body.len()) as i32)
+        .i32(0) // partition leader epoch
+        .i8(MAGIC_V2)
+        .u32(crc32c::crc32c(body))
+        .raw(body);
+    batch.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(offset: i64, timestamp: i64, value: &str) -> KafkaRecord {
+        KafkaRecord {
+            offset,
+            timestamp,
+            key: Some(b"user-1".to_vec()),
+            value: Some(value.as_bytes().to_vec()),
+            headers: vec![("source".to_string(), Some(b"web".to_vec()))],
+        }
+    }
+
+    #[test]
+    fn test_batch_round_trips_records() {
+        let records = vec![
+            record(0, 1_700_000_000_000, "a"),
+            record(1, 1_700_000_000_005, "b"),
+        ];
+        let batch = encode_batch(&records);
+        assert_eq!(decode_batches(&batch).unwrap(), records);
+
+        // Two batches in one record set get consecutive relative offsets
+        let mut set = batch.clone();
+        set.extend_from_slice(&batch);
+        let decoded = decode_batches(&set).unwrap();
+        assert_eq!(
+            decoded.iter().map(|r| r.offset).collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_corrupt_and_compressed_batches() {
+        let mut batch = encode_batch(&[record(0, 0, "a")]);
+        let last = batch.len() - 1;
+        batch[last] ^= 0xff;
+        assert_eq!(
+            decode_batches(&batch).unwrap_err().code,
+            error_code::CORRUPT_MESSAGE
+        );
+
+        let mut batch = encode_batch(&[record(0, 0, "a")]);
+        batch[CRC_END + 1] |= 0x01; // gzip
+        let crc = crc32c::crc32c(&batch[CRC_END..]);
+        batch[CRC_OFFSET..CRC_END].copy_from_slice(&crc.to_be_bytes());
+        assert_eq!(
+            decode_batches(&batch).unwrap_err().code,
+            error_code::UNSUPPORTED_COMPRESSION_TYPE
+        );
+
+        let batch = encode_batch(&[record(0, 0, "a")]);
+        assert_eq!(
+            decode_batches(&batch[..batch.len() - 3]).unwrap_err().code,
+            error_code::CORRUPT_MESSAGE
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_header_count() {
+        let mut r = Encoder::new();
+        r.i8(0)
+            .varlong(0)
+            .varint(0)
+            .varint_bytes(None)
+            .varint_bytes(Some(b"a"))
+            .varint(i32::MAX); // header count, with no headers following
+        let r = r.into_bytes();
+        let mut body = Encoder::new();
+        body.i16(0)
+            .i32(0)
+            .i64(0)
+            .i64(0)
+            .i64(-1)
+            .i16(-1)
+            .i32(-1)
+            .i32(1)
+            .varint(r.len() as i32)
+            .raw(&r);
+
+        let batch = frame_batch(0, &body.into_bytes());
+        assert_eq!(
+            decode_batches(&batch).unwrap_err().code,
+            error_code::CORRUPT_MESSAGE
+        );
+    }
+}
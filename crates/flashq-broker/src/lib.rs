@@ -3,7 +3,14 @@
 //! This crate provides the broker services (Producer, Consumer, Admin) that handle
 //! client requests and interact with the FlashQ core.
 
+pub mod acl;
+pub mod auth;
 pub mod broker;
+pub mod kafka;
+pub mod lifecycle;
+pub mod metrics;
+pub mod quota;
+pub mod rest;
 
 // Re-export protocol buffer types from flashq-proto
 pub use flashq_proto::flashq;
@@ -0,0 +1,136 @@
+//! Broker lifecycle: running, draining and shutting down.
+//!
+//! A broker starts out running. A DRAIN directive from the controller moves it to draining,
+//! where produces are refused with a retriable error but fetches and subscriptions carry on.
+//! SIGTERM or a SHUTDOWN directive moves it to shutting down: listeners stop accepting
+//! connections and new produces, in-flight requests finish, subscription streams end, and
+//! the broker fsyncs its storage before exiting. States only move forward.
+
+use std::fmt;
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BrokerState {
+    Running,
+    Draining,
+    ShuttingDown,
+}
+
+impl fmt::Display for BrokerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Running => "running",
+            Self::Draining => "draining",
+            Self::ShuttingDown => "shutting down",
+        })
+    }
+}
+
+/// Shared handle on the broker's state; clones observe and move the same state.
+#[derive(Debug, Clone)]
+pub struct Lifecycle {
+    state: Arc<watch::Sender<BrokerState>>,
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lifecycle {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(watch::Sender::new(BrokerState::Running)),
+        }
+    }
+
+    pub fn state(&self) -> BrokerState {
+        *self.state.borrow()
+    }
+
+    /// Stop taking produces while still serving reads. Returns false if the broker was
+    /// already draining or shutting down.
+    pub fn drain(&self) -> bool {
+        self.advance(BrokerState::Draining)
+    }
+
+    /// Begin shutting down. Returns false if shutdown had already begun.
+    pub fn shutdown(&self) -> bool {
+        self.advance(BrokerState::ShuttingDown)
+    }
+
+    /// Whether the broker has left the running state, as reported in heartbeats.
+    pub fn is_draining(&self) -> bool {
+        self.state() != BrokerState::Running
+    }
+
+    pub fn accepts_produce(&self) -> bool {
+        self.state() == BrokerState::Running
+    }
+
+    /// Resolves once shutdown has begun.
+    pub async fn shutdown_requested(&self) {
+        let mut state = self.state.subscribe();
+        // The sender lives as long as `self`, so waiting cannot fail
+        let _ = state
+            .wait_for(|state| *state == BrokerState::ShuttingDown)
+            .await;
+    }
+
+    fn advance(&self, to: BrokerState) -> bool {
+        self.state.send_if_modified(|state| {
+            if *state >= to {
+                return false;
+            }
+            tracing::info!(from = %state, to = %to, "Broker lifecycle changed");
+            *state = to;
+            true
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_states_only_move_forward() {
+        let lifecycle = Lifecycle::new();
+        assert!(lifecycle.accepts_produce());
+        assert!(!lifecycle.is_draining());
+
+        assert!(lifecycle.drain());
+        assert!(!lifecycle.drain());
+        assert_eq!(lifecycle.state(), BrokerState::Draining);
+        assert!(!lifecycle.accepts_produce());
+        assert!(lifecycle.is_draining());
+
+        assert!(lifecycle.shutdown());
+        assert!(!lifecycle.drain());
+        assert!(!lifecycle.shutdown());
+        assert_eq!(lifecycle.state(), BrokerState::ShuttingDown);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_requested_resolves_for_every_clone() {
+        let lifecycle = Lifecycle::new();
+        let waiter = tokio::spawn({
+            let lifecycle = lifecycle.clone();
+            async move { lifecycle.shutdown_requested().await }
+        });
+        lifecycle.drain();
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        lifecycle.shutdown();
+        tokio::time::timeout(std::time::Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        // Already shutting down: resolves immediately
+        lifecycle.shutdown_requested().await;
+    }
+}
@@ -0,0 +1,371 @@
+//! Broker metrics.
+//!
+//! Request handlers record counters and latencies through the `metrics` facade, which does
+//! nothing until a recorder is installed. Gauges that describe stored state — high-water
+//! marks, consumer lag, segment sizes and broker heartbeats — are read from the broker when
+//! scraped by [`refresh_gauges`]. With the `metrics` feature, [`MetricsEndpoint`] installs a
+//! Prometheus recorder and serves it at `/metrics`; gauges a refresh no longer sets, such as
+//! those of deleted consumer groups, drop out of the exposition shortly after.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use flashq_cluster::MetadataStore;
+use parking_lot::Mutex;
+
+/// Open subscriptions per topic, so every refresh can set their gauge again.
+static OPEN_SUBSCRIPTIONS: LazyLock<Mutex<HashMap<String, usize>>> =
+    LazyLock::new(Default::default);
+
+/// Counts one successful produce call.
+pub(crate) fn record_produce(topic: &str, records: usize, bytes: u64, latency: Duration) {
+    let topic = topic.to_string();
+    ::metrics::counter!("flashq_produce_requests_total", "topic" => topic.clone()).increment(1);
+    ::metrics::counter!("flashq_produce_records_total", "topic" => topic.clone())
+        .increment(records as u64);
+    ::metrics::counter!("flashq_bytes_in_total", "topic" => topic.clone()).increment(bytes);
+    ::metrics::histogram!("flashq_produce_latency_seconds", "topic" => topic).record(latency);
+}
+
+/// Counts one successful fetch call.
+pub(crate) fn record_fetch(topic: &str, records: usize, bytes: u64, latency: Duration) {
+    let topic = topic.to_string();
+    ::metrics::counter!("flashq_fetch_requests_total", "topic" => topic.clone()).increment(1);
+    ::metrics::histogram!("flashq_fetch_latency_seconds", "topic" => topic.clone()).record(latency);
+    record_bytes_out(&topic, records, bytes);
+}
+
+/// Counts records sent to consumers, by fetch or subscription.
+pub(crate) fn record_bytes_out(topic: &str, records: usize, bytes: u64) {
+    let topic = topic.to_string();
+    ::metrics::counter!("flashq_fetch_records_total", "topic" => topic.clone())
+        .increment(records as u64);
+    ::metrics::counter!("flashq_bytes_out_total", "topic" => topic).increment(bytes);
+}
+
+/// Counts an open subscription for as long as it is alive.
+pub(crate) struct SubscriptionGauge {
+    topic: String,
+}
+
+impl SubscriptionGauge {
+    pub(crate) fn open(topic: &str) -> Self {
+        let mut open = OPEN_SUBSCRIPTIONS.lock();
+        let count = open.entry(topic.to_string()).or_default();
+        *count += 1;
+        set_subscriptions_gauge(topic, *count);
+        Self {
+            topic: topic.to_string(),
+        }
+    }
+}
+
+impl Drop for SubscriptionGauge {
+    fn drop(&mut self) {
+        let mut open = OPEN_SUBSCRIPTIONS.lock();
+        let count = open.get_mut(&self.topic).map_or(0, |count| {
+            *count = count.saturating_sub(1);
+            *count
+        });
+        if count == 0 {
+            open.remove(&self.topic);
+        }
+        set_subscriptions_gauge(&self.topic, count);
+    }
+}
+
+fn set_subscriptions_gauge(topic: &str, count: usize) {
+    ::metrics::gauge!("flashq_active_subscriptions", "topic" => topic.to_string())
+        .set(count as f64);
+}
+
+/// Set the gauges that describe stored state: per-partition high-water marks, consumer
+/// group lag, segment counts and bytes per topic, open subscriptions and each broker's
+/// heartbeat age. Reads storage, so async callers should run it off the runtime threads.
+pub fn refresh_gauges(core: &flashq_cluster::FlashQ, metadata_store: &dyn MetadataStore) {
+    for (topic, count) in OPEN_SUBSCRIPTIONS.lock().iter() {
+        set_subscriptions_gauge(topic, *count);
+    }
+
+    let mut high_water_marks = HashMap::new();
+    for topic in core.get_topics() {
+        match core.list_offsets(&topic, &[], None) {
+            Ok(partitions) => {
+                for partition in partitions {
+                    ::metrics::gauge!(
+                        "flashq_partition_high_water_mark",
+                        "topic" => topic.clone(),
+                        "partition" => partition.partition.to_string()
+                    )
+                    .set(partition.latest as f64);
+                    high_water_marks.insert((topic.clone(), partition.partition), partition.latest);
+                }
+            }
+            Err(e) => tracing::warn!(%topic, "Failed to read offsets for metrics: {e}"),
+        }
+        match core.segment_stats(&topic) {
+            Ok(stats) => {
+                for (tier, segments, bytes) in [
+                    ("local", stats.local_segments, stats.local_bytes),
+                    ("remote", stats.remote_segments, stats.remote_bytes),
+                ] {
+                    ::metrics::gauge!("flashq_topic_segments", "topic" => topic.clone(), "tier" => tier)
+                        .set(segments as f64);
+                    ::metrics::gauge!("flashq_topic_segment_bytes", "topic" => topic.clone(), "tier" => tier)
+                        .set(bytes as f64);
+                }
+            }
+            Err(e) => tracing::warn!(%topic, "Failed to read segment stats for metrics: {e}"),
+        }
+    }
+
+    for committed in core.committed_offsets() {
+        let Some(high_water_mark) =
+            high_water_marks.get(&(committed.topic.clone(), committed.partition))
+        else {
+            continue;
+        };
+        ::metrics::gauge!(
+            "flashq_consumer_group_lag",
+            "group" => committed.group_id,
+            "topic" => committed.topic,
+            "partition" => committed.partition.to_string()
+        )
+        .set(high_water_mark.saturating_sub(committed.offset) as f64);
+    }
+
+    match metadata_store.list_brokers_with_status() {
+        Ok(brokers) => {
+            let now = chrono::Utc::now();
+            for (broker, status) in brokers {
+                let age = (now - status.last_heartbeat).num_milliseconds().max(0) as f64 / 1000.0;
+                ::metrics::gauge!(
+                    "flashq_cluster_broker_heartbeat_age_seconds",
+                    "broker" => broker.0.to_string()
+                )
+                .set(age);
+            }
+        }
+        Err(e) => tracing::warn!("Failed to list brokers for metrics: {e}"),
+    }
+}
+
+#[cfg(feature = "metrics")]
+pub use endpoint::MetricsEndpoint;
+
+#[cfg(feature = "metrics")]
+mod endpoint {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use flashq_cluster::MetadataStore;
+    use metrics_exporter_prometheus::{BuildError, PrometheusBuilder, PrometheusHandle};
+    use metrics_util::MetricKindMask;
+
+    /// Latency histogram buckets in seconds, from 100µs to 10s.
+    const LATENCY_BUCKETS: &[f64] = &[
+        0.0001, 0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+    ];
+    /// How long a gauge survives without being set. Every render refreshes the gauges of
+    /// live state first, so only those of deleted topics, groups and brokers expire.
+    const GAUGE_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+    pub(super) fn builder(gauge_idle_timeout: Duration) -> Result<PrometheusBuilder, BuildError> {
+        Ok(PrometheusBuilder::new()
+            .set_buckets(LATENCY_BUCKETS)?
+            .idle_timeout(MetricKindMask::GAUGE, Some(gauge_idle_timeout)))
+    }
+
+    /// Prometheus exposition of the broker's metrics.
+    #[derive(Clone)]
+    pub struct MetricsEndpoint {
+        handle: PrometheusHandle,
+        core: Arc<flashq_cluster::FlashQ>,
+        metadata_store: Arc<dyn MetadataStore>,
+    }
+
+    impl MetricsEndpoint {
+        /// Install the process-wide Prometheus recorder. Fails if a recorder is already
+        /// installed.
+        pub fn install(
+            core: Arc<flashq_cluster::FlashQ>,
+            metadata_store: Arc<dyn MetadataStore>,
+        ) -> Result<Self, BuildError> {
+            let handle = builder(GAUGE_IDLE_TIMEOUT)?.install_recorder()?;
+            Ok(Self {
+                handle,
+                core,
+                metadata_store,
+            })
+        }
+
+        /// Current metrics in the Prometheus text format. The gauges are refreshed on the
+        /// storage I/O pool first, since reading them can block on disk.
+        pub async fn render(&self) -> String {
+            let metadata_store = self.metadata_store.clone();
+            let refreshed = self
+                .core
+                .run_blocking(move |core| {
+                    super::refresh_gauges(core, metadata_store.as_ref());
+                    Ok(())
+                })
+                .await;
+            if let Err(e) = refreshed {
+                tracing::warn!("Failed to refresh metrics gauges: {e}");
+            }
+            self.handle.render()
+        }
+
+        /// Serve `GET /metrics` on `listener` until the task is dropped.
+        pub async fn serve(self, listener: tokio::net::TcpListener) -> std::io::Result<()> {
+            let app = axum::Router::new().route(
+                "/metrics",
+                axum::routing::get(move || {
+                    let endpoint = self.clone();
+                    async move { endpoint.render().await }
+                }),
+            );
+            axum::serve(listener, app).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flashq_cluster::InMemoryMetadataStore;
+    use flashq_cluster::manifest::{BrokerSpec, ClusterManifest};
+    use flashq_cluster::types::BrokerId;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    fn gauge(
+        snapshot: &[(
+            metrics_util::CompositeKey,
+            Option<::metrics::Unit>,
+            Option<::metrics::SharedString>,
+            DebugValue,
+        )],
+        name: &str,
+        labels: &[(&str, &str)],
+    ) -> Option<f64> {
+        snapshot.iter().find_map(|(key, _, _, value)| {
+            let key = key.key();
+            let matches = key.name() == name
+                && labels.iter().all(|(k, v)| {
+                    key.labels()
+                        .any(|label| label.key() == *k && label.value() == *v)
+                });
+            match value {
+                DebugValue::Gauge(v) if matches => Some(v.into_inner()),
+                _ => None,
+            }
+        })
+    }
+
+    #[test]
+    fn test_refresh_gauges_reports_offsets_lag_and_heartbeats() {
+        let core = flashq_cluster::FlashQ::new();
+        let records = (0..5)
+            .map(|i| flashq_cluster::Record::new(None, format!("v{i}"), None))
+            .collect();
+        core.post_records("orders".to_string(), records).unwrap();
+        core.create_consumer_group("billing".to_string()).unwrap();
+        core.update_consumer_group_offset("billing", "orders".to_string(), 2)
+            .unwrap();
+        let metadata_store = InMemoryMetadataStore::new_with_manifest(ClusterManifest {
+            brokers: vec![BrokerSpec {
+                id: BrokerId(7),
+                host: "127.0.0.1".to_string(),
+                port: 6007,
+            }],
+            topics: Default::default(),
+        })
+        .unwrap();
+        metadata_store
+            .record_broker_heartbeat(
+                BrokerId(7),
+                chrono::Utc::now() - chrono::Duration::seconds(30),
+                false,
+            )
+            .unwrap();
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        ::metrics::with_local_recorder(&recorder, || {
+            refresh_gauges(&core, &metadata_store);
+            let _subscription = SubscriptionGauge::open("orders");
+        });
+        let snapshot = snapshotter.snapshot().into_vec();
+
+        assert_eq!(
+            gauge(
+                &snapshot,
+                "flashq_partition_high_water_mark",
+                &[("topic", "orders"), ("partition", "0")]
+            ),
+            Some(5.0)
+        );
+        assert_eq!(
+            gauge(
+                &snapshot,
+                "flashq_consumer_group_lag",
+                &[("group", "billing"), ("topic", "orders")]
+            ),
+            Some(3.0)
+        );
+        assert_eq!(
+            gauge(
+                &snapshot,
+                "flashq_topic_segments",
+                &[("topic", "orders"), ("tier", "local")]
+            ),
+            Some(0.0)
+        );
+        assert_eq!(
+            gauge(
+                &snapshot,
+                "flashq_active_subscriptions",
+                &[("topic", "orders")]
+            ),
+            Some(0.0)
+        );
+        let age = gauge(
+            &snapshot,
+            "flashq_cluster_broker_heartbeat_age_seconds",
+            &[("broker", "7")],
+        )
+        .unwrap();
+        assert!((30.0..60.0).contains(&age), "{age}");
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_gauges_of_deleted_groups_expire() {
+        let core = flashq_cluster::FlashQ::new();
+        core.post_records(
+            "payments".to_string(),
+            vec![flashq_cluster::Record::new(None, "v".to_string(), None)],
+        )
+        .unwrap();
+        core.create_consumer_group("audit".to_string()).unwrap();
+        core.update_consumer_group_offset("audit", "payments".to_string(), 0)
+            .unwrap();
+        let metadata_store =
+            InMemoryMetadataStore::new_with_manifest(ClusterManifest::default()).unwrap();
+        let recorder = endpoint::builder(Duration::from_millis(50))
+            .unwrap()
+            .build_recorder();
+        let handle = recorder.handle();
+
+        ::metrics::with_local_recorder(&recorder, || refresh_gauges(&core, &metadata_store));
+        assert!(handle.render().contains(r#"group="audit""#));
+
+        core.delete_consumer_group("audit").unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        ::metrics::with_local_recorder(&recorder, || refresh_gauges(&core, &metadata_store));
+        let rendered = handle.render();
+        assert!(!rendered.contains(r#"group="audit""#), "{rendered}");
+        assert!(rendered.contains(r#"flashq_partition_high_water_mark{topic="payments""#));
+    }
+}
@@ -0,0 +1,729 @@
+//! Produce, fetch and request-rate quotas.
+//!
+//! A quota caps one entity — a principal, a client id or a topic — at some produce bytes,
+//! fetch bytes and requests per second. Each call is charged to every entity it touches,
+//! and each (entity, metric) pair drains its own token bucket holding one second of
+//! allowance. Usage is recorded after the fact, so a bucket can go into debt; the next call
+//! from that entity waits (or is rejected) until the debt is paid back.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+
+/// Entity name whose quota applies to every entity of its type without one of its own.
+pub const DEFAULT_ENTITY: &str = "*";
+
+const QUOTAS_FILE_NAME: &str = "quotas.json";
+
+/// How often idle buckets are swept out. Buckets hold one second of allowance, so any
+/// untouched this long without debt are full again and can be rebuilt on demand.
+const BUCKET_SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaEntityType {
+    Principal,
+    ClientId,
+    Topic,
+}
+
+impl fmt::Display for QuotaEntityType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Principal => "principal",
+            Self::ClientId => "client id",
+            Self::Topic => "topic",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct QuotaEntity {
+    pub entity_type: QuotaEntityType,
+    pub name: String,
+}
+
+impl QuotaEntity {
+    pub fn new(entity_type: QuotaEntityType, name: impl Into<String>) -> Self {
+        Self {
+            entity_type,
+            name: name.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuotaMetric {
+    ProduceBytes,
+    FetchBytes,
+    Requests,
+}
+
+impl fmt::Display for QuotaMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::ProduceBytes => "produce byte rate",
+            Self::FetchBytes => "fetch byte rate",
+            Self::Requests => "request rate",
+        })
+    }
+}
+
+/// Per-second limits for one entity; `None` leaves that metric unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub produce_bytes_per_sec: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetch_bytes_per_sec: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_sec: Option<u64>,
+}
+
+impl QuotaConfig {
+    fn limit(&self, metric: QuotaMetric) -> Option<u64> {
+        match metric {
+            QuotaMetric::ProduceBytes => self.produce_bytes_per_sec,
+            QuotaMetric::FetchBytes => self.fetch_bytes_per_sec,
+            QuotaMetric::Requests => self.requests_per_sec,
+        }
+    }
+
+    /// The first metric capped at zero. Such a quota could never be met, so it is refused.
+    fn zero_limit(&self) -> Option<QuotaMetric> {
+        [
+            QuotaMetric::ProduceBytes,
+            QuotaMetric::FetchBytes,
+            QuotaMetric::Requests,
+        ]
+        .into_iter()
+        .find(|&metric| self.limit(metric) == Some(0))
+    }
+}
+
+/// What the broker does with a call from an entity over its quota.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QuotaEnforcement {
+    /// Hold the call until the entity is back within its quota.
+    #[default]
+    Throttle,
+    /// Fail the call with `RESOURCE_EXHAUSTED`.
+    Reject,
+}
+
+/// The entities a call is charged to.
+#[derive(Debug, Clone, Copy)]
+pub struct QuotaSubjects<'a> {
+    pub principal: &'a str,
+    pub client_id: Option<&'a str>,
+    pub topic: &'a str,
+}
+
+impl<'a> QuotaSubjects<'a> {
+    /// Subjects of a call through an unauthenticated front end, charged as the anonymous
+    /// principal.
+    pub fn anonymous(client_id: Option<&'a str>, topic: &'a str) -> Self {
+        Self {
+            principal: crate::auth::Principal::ANONYMOUS,
+            client_id,
+            topic,
+        }
+    }
+
+    fn entities(&self) -> impl Iterator<Item = (QuotaEntityType, &str)> {
+        [
+            Some((QuotaEntityType::Principal, self.principal)),
+            self.client_id
+                .map(|client_id| (QuotaEntityType::ClientId, client_id)),
+            Some((QuotaEntityType::Topic, self.topic)),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+/// An entity that is over its quota, and how long until it is back within it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaViolation {
+    pub entity: QuotaEntity,
+    pub metric: QuotaMetric,
+    pub delay: Duration,
+}
+
+impl fmt::Display for QuotaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} quota exceeded for {} '{}'; retry after {} ms",
+            self.metric,
+            self.entity.entity_type,
+            self.entity.name,
+            self.delay.as_millis().max(1)
+        )
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64, now: Instant) -> Self {
+        Self {
+            rate: rate as f64,
+            tokens: rate as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.updated = now;
+    }
+
+    /// Time until the bucket is out of debt.
+    fn debt_delay(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        if self.tokens >= 0.0 || self.rate == 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(-self.tokens / self.rate)
+    }
+
+    fn consume(&mut self, amount: u64, now: Instant) {
+        self.refill(now);
+        self.tokens -= amount as f64;
+    }
+
+    /// Whether the bucket has refilled by `now`, leaving it no different from a new one.
+    fn is_full(&self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens + elapsed * self.rate >= self.rate
+    }
+}
+
+/// Token buckets by (entity, metric). Default quotas give every client id and topic its
+/// own bucket, so full ones are dropped periodically to keep the map bounded.
+#[derive(Debug, Default)]
+struct Buckets {
+    buckets: HashMap<(QuotaEntity, QuotaMetric), TokenBucket>,
+    swept: Option<Instant>,
+}
+
+impl Buckets {
+    fn get(
+        &mut self,
+        entity: QuotaEntity,
+        metric: QuotaMetric,
+        rate: u64,
+        now: Instant,
+    ) -> &mut TokenBucket {
+        self.buckets
+            .entry((entity, metric))
+            .or_insert_with(|| TokenBucket::new(rate, now))
+    }
+
+    fn evict_idle(&mut self, now: Instant) {
+        if self
+            .swept
+            .is_some_and(|swept| now.saturating_duration_since(swept) < BUCKET_SWEEP_INTERVAL)
+        {
+            return;
+        }
+        self.buckets.retain(|_, bucket| !bucket.is_full(now));
+        self.swept = Some(now);
+    }
+
+    fn clear(&mut self) {
+        self.buckets.clear();
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QuotasFile {
+    quotas: Vec<QuotaEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct QuotaEntry {
+    #[serde(flatten)]
+    entity: QuotaEntity,
+    #[serde(flatten)]
+    config: QuotaConfig,
+}
+
+/// Holds quota configuration and the token buckets charged against it.
+#[derive(Debug, Default)]
+pub struct QuotaManager {
+    enforcement: QuotaEnforcement,
+    quotas: RwLock<HashMap<QuotaEntity, QuotaConfig>>,
+    buckets: Mutex<Buckets>,
+    /// File the quotas are persisted to; `None` keeps them in memory only.
+    path: Option<PathBuf>,
+}
+
+impl QuotaManager {
+    /// Quotas kept in memory; none are set initially.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Quotas persisted in `quotas.json` under `data_dir`, loading any saved there.
+    pub fn open(data_dir: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = data_dir.as_ref().join(QUOTAS_FILE_NAME);
+        let quotas = match std::fs::read(&path) {
+            Ok(bytes) => {
+                serde_json::from_slice::<QuotasFile>(&bytes)
+                    .map_err(|e| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!("invalid quota file {}: {e}", path.display()),
+                        )
+                    })?
+                    .quotas
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        if let Some(entry) = quotas
+            .iter()
+            .find(|entry| entry.config.zero_limit().is_some())
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "invalid quota file {}: zero quota for {} '{}'",
+                    path.display(),
+                    entry.entity.entity_type,
+                    entry.entity.name
+                ),
+            ));
+        }
+        Ok(Self {
+            quotas: RwLock::new(
+                quotas
+                    .into_iter()
+                    .map(|entry| (entry.entity, entry.config))
+                    .collect(),
+            ),
+            path: Some(path),
+            ..Self::default()
+        })
+    }
+
+    pub fn with_enforcement(mut self, enforcement: QuotaEnforcement) -> Self {
+        self.enforcement = enforcement;
+        self
+    }
+
+    pub fn enforcement(&self) -> QuotaEnforcement {
+        self.enforcement
+    }
+
+    /// Set `entity`'s limits, replacing any it had. Buckets restart at the new rates.
+    ///
+    /// A limit of zero fails with `InvalidInput`; leave the metric unset for no limit.
+    pub fn set_quota(&self, entity: QuotaEntity, config: QuotaConfig) -> std::io::Result<()> {
+        if let Some(metric) = config.zero_limit() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{metric} quota must be positive; leave it unset for no limit"),
+            ));
+        }
+        let mut quotas = self.quotas.write();
+        let mut updated = quotas.clone();
+        updated.insert(entity, config);
+        self.persist(&updated)?;
+        *quotas = updated;
+        self.buckets.lock().clear();
+        Ok(())
+    }
+
+    /// Remove `entity`'s limits. Returns whether it had any.
+    pub fn delete_quota(&self, entity: &QuotaEntity) -> std::io::Result<bool> {
+        let mut quotas = self.quotas.write();
+        if !quotas.contains_key(entity) {
+            return Ok(false);
+        }
+        let mut updated = quotas.clone();
+        updated.remove(entity);
+        self.persist(&updated)?;
+        *quotas = updated;
+        self.buckets.lock().clear();
+        Ok(true)
+    }
+
+    /// Every configured quota, ordered by entity.
+    pub fn list_quotas(&self) -> Vec<(QuotaEntity, QuotaConfig)> {
+        let mut quotas: Vec<_> = self
+            .quotas
+            .read()
+            .iter()
+            .map(|(entity, config)| (entity.clone(), *config))
+            .collect();
+        quotas.sort_by(|a, b| a.0.cmp(&b.0));
+        quotas
+    }
+
+    /// The longest wait any of `subjects` owes on `metrics`, if one is over its quota.
+    pub fn check(
+        &self,
+        subjects: &QuotaSubjects<'_>,
+        metrics: &[QuotaMetric],
+    ) -> Option<QuotaViolation> {
+        self.check_at(subjects, metrics, Instant::now())
+    }
+
+    /// Wait out a call from subjects already over a quota on `metrics`, or return the
+    /// violation for the caller to refuse the call when enforcement is [`QuotaEnforcement::Reject`].
+    pub async fn enforce(
+        &self,
+        subjects: &QuotaSubjects<'_>,
+        metrics: &[QuotaMetric],
+    ) -> Result<(), QuotaViolation> {
+        let Some(violation) = self.check(subjects, metrics) else {
+            return Ok(());
+        };
+        match self.enforcement {
+            QuotaEnforcement::Throttle => {
+                tracing::debug!(%violation, "Throttling call");
+                tokio::time::sleep(violation.delay).await;
+                Ok(())
+            }
+            QuotaEnforcement::Reject => Err(violation),
+        }
+    }
+
+    /// Charge `amount` of `metric` to every quota-limited entity among `subjects`.
+    pub fn record(&self, subjects: &QuotaSubjects<'_>, metric: QuotaMetric, amount: u64) {
+        self.record_at(subjects, metric, amount, Instant::now())
+    }
+
+    fn check_at(
+        &self,
+        subjects: &QuotaSubjects<'_>,
+        metrics: &[QuotaMetric],
+        now: Instant,
+    ) -> Option<QuotaViolation> {
+        let limits = self.limits(subjects, metrics);
+        if limits.is_empty() {
+            return None;
+        }
+        let mut buckets = self.buckets.lock();
+        buckets.evict_idle(now);
+        limits
+            .into_iter()
+            .filter_map(|(entity, metric, rate)| {
+                let delay = buckets
+                    .get(entity.clone(), metric, rate, now)
+                    .debt_delay(now);
+                (!delay.is_zero()).then_some(QuotaViolation {
+                    entity,
+                    metric,
+                    delay,
+                })
+            })
+            .max_by_key(|violation| violation.delay)
+    }
+
+    fn record_at(
+        &self,
+        subjects: &QuotaSubjects<'_>,
+        metric: QuotaMetric,
+        amount: u64,
+        now: Instant,
+    ) {
+        let limits = self.limits(subjects, &[metric]);
+        if limits.is_empty() {
+            return;
+        }
+        let mut buckets = self.buckets.lock();
+        buckets.evict_idle(now);
+        for (entity, metric, rate) in limits {
+            buckets.get(entity, metric, rate, now).consume(amount, now);
+        }
+    }
+
+    /// The limits that apply to `subjects`, keyed by the concrete entity so entities that
+    /// share a default quota still get separate buckets.
+    fn limits(
+        &self,
+        subjects: &QuotaSubjects<'_>,
+        metrics: &[QuotaMetric],
+    ) -> Vec<(QuotaEntity, QuotaMetric, u64)> {
+        let quotas = self.quotas.read();
+        if quotas.is_empty() {
+            return Vec::new();
+        }
+        let mut limits = Vec::new();
+        for (entity_type, name) in subjects.entities() {
+            let entity = QuotaEntity::new(entity_type, name);
+            let Some(config) = quotas
+                .get(&entity)
+                .or_else(|| quotas.get(&QuotaEntity::new(entity_type, DEFAULT_ENTITY)))
+            else {
+                continue;
+            };
+            for &metric in metrics {
+                if let Some(rate) = config.limit(metric) {
+                    limits.push((entity.clone(), metric, rate));
+                }
+            }
+        }
+        limits
+    }
+
+    fn persist(&self, quotas: &HashMap<QuotaEntity, QuotaConfig>) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut entries: Vec<_> = quotas
+            .iter()
+            .map(|(entity, config)| QuotaEntry {
+                entity: entity.clone(),
+                config: *config,
+            })
+            .collect();
+        entries.sort_by(|a, b| a.entity.cmp(&b.entity));
+        let json = serde_json::to_string_pretty(&QuotasFile { quotas: entries })
+            .map_err(std::io::Error::other)?;
+        let staging = path.with_extension("json.tmp");
+        std::fs::write(&staging, json)?;
+        std::fs::rename(&staging, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subjects<'a>(principal: &'a str, topic: &'a str) -> QuotaSubjects<'a> {
+        QuotaSubjects {
+            principal,
+            client_id: None,
+            topic,
+        }
+    }
+
+    #[test]
+    fn test_bucket_debt_delays_until_paid_back() {
+        let quotas = QuotaManager::in_memory();
+        quotas
+            .set_quota(
+                QuotaEntity::new(QuotaEntityType::Principal, "alice"),
+                QuotaConfig {
+                    produce_bytes_per_sec: Some(1000),
+                    ..QuotaConfig::default()
+                },
+            )
+            .unwrap();
+        let alice = subjects("alice", "events");
+        let start = Instant::now();
+
+        assert_eq!(
+            quotas.check_at(&alice, &[QuotaMetric::ProduceBytes], start),
+            None
+        );
+        quotas.record_at(&alice, QuotaMetric::ProduceBytes, 3000, start);
+
+        let violation = quotas
+            .check_at(&alice, &[QuotaMetric::ProduceBytes], start)
+            .unwrap();
+        assert_eq!(violation.entity.name, "alice");
+        assert_eq!(violation.delay, Duration::from_secs(2));
+
+        let later = start + Duration::from_millis(1500);
+        let violation = quotas
+            .check_at(&alice, &[QuotaMetric::ProduceBytes], later)
+            .unwrap();
+        assert_eq!(violation.delay, Duration::from_millis(500));
+        assert_eq!(
+            quotas.check_at(
+                &alice,
+                &[QuotaMetric::ProduceBytes],
+                start + Duration::from_secs(2)
+            ),
+            None
+        );
+
+        // Other metrics and other principals are not limited
+        assert_eq!(
+            quotas.check_at(&alice, &[QuotaMetric::FetchBytes], start),
+            None
+        );
+        assert_eq!(
+            quotas.check_at(
+                &subjects("bob", "events"),
+                &[QuotaMetric::ProduceBytes],
+                start
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_default_quota_gives_each_entity_its_own_bucket() {
+        let quotas = QuotaManager::in_memory();
+        quotas
+            .set_quota(
+                QuotaEntity::new(QuotaEntityType::Topic, DEFAULT_ENTITY),
+                QuotaConfig {
+                    requests_per_sec: Some(1),
+                    ..QuotaConfig::default()
+                },
+            )
+            .unwrap();
+        quotas
+            .set_quota(
+                QuotaEntity::new(QuotaEntityType::Topic, "firehose"),
+                QuotaConfig::default(),
+            )
+            .unwrap();
+        let now = Instant::now();
+
+        quotas.record_at(&subjects("alice", "a"), QuotaMetric::Requests, 2, now);
+        let violation = quotas
+            .check_at(&subjects("bob", "a"), &[QuotaMetric::Requests], now)
+            .unwrap();
+        assert_eq!(
+            violation.entity,
+            QuotaEntity::new(QuotaEntityType::Topic, "a")
+        );
+        assert_eq!(
+            quotas.check_at(&subjects("alice", "b"), &[QuotaMetric::Requests], now),
+            None
+        );
+
+        // An entity's own quota overrides the default, here lifting the limit
+        quotas.record_at(
+            &subjects("alice", "firehose"),
+            QuotaMetric::Requests,
+            10,
+            now,
+        );
+        assert_eq!(
+            quotas.check_at(
+                &subjects("alice", "firehose"),
+                &[QuotaMetric::Requests],
+                now
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_quotas_persist_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let entity = QuotaEntity::new(QuotaEntityType::ClientId, "loader");
+        let config = QuotaConfig {
+            fetch_bytes_per_sec: Some(4096),
+            requests_per_sec: Some(50),
+            ..QuotaConfig::default()
+        };
+        QuotaManager::open(dir.path())
+            .unwrap()
+            .set_quota(entity.clone(), config)
+            .unwrap();
+
+        let reopened = QuotaManager::open(dir.path()).unwrap();
+        assert_eq!(reopened.list_quotas(), vec![(entity.clone(), config)]);
+        assert!(reopened.delete_quota(&entity).unwrap());
+        assert!(!reopened.delete_quota(&entity).unwrap());
+        assert!(
+            QuotaManager::open(dir.path())
+                .unwrap()
+                .list_quotas()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_failed_persist_leaves_quotas_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let quotas = QuotaManager::open(dir.path().join("missing")).unwrap();
+
+        assert!(
+            quotas
+                .set_quota(
+                    QuotaEntity::new(QuotaEntityType::Principal, "alice"),
+                    QuotaConfig {
+                        requests_per_sec: Some(1),
+                        ..QuotaConfig::default()
+                    },
+                )
+                .is_err()
+        );
+        assert!(quotas.list_quotas().is_empty());
+        let now = Instant::now();
+        quotas.record_at(&subjects("alice", "events"), QuotaMetric::Requests, 5, now);
+        assert_eq!(
+            quotas.check_at(&subjects("alice", "events"), &[QuotaMetric::Requests], now),
+            None
+        );
+    }
+
+    #[test]
+    fn test_zero_rate_is_refused() {
+        let quotas = QuotaManager::in_memory();
+        let err = quotas
+            .set_quota(
+                QuotaEntity::new(QuotaEntityType::Topic, "events"),
+                QuotaConfig {
+                    produce_bytes_per_sec: Some(1000),
+                    requests_per_sec: Some(0),
+                    ..QuotaConfig::default()
+                },
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("request rate"));
+        assert!(quotas.list_quotas().is_empty());
+    }
+
+    #[test]
+    fn test_idle_buckets_are_evicted() {
+        let quotas = QuotaManager::in_memory();
+        quotas
+            .set_quota(
+                QuotaEntity::new(QuotaEntityType::ClientId, DEFAULT_ENTITY),
+                QuotaConfig {
+                    requests_per_sec: Some(10),
+                    ..QuotaConfig::default()
+                },
+            )
+            .unwrap();
+        let start = Instant::now();
+        let client_ids: Vec<String> = (0..100).map(|i| format!("client-{i}")).collect();
+        for client_id in &client_ids {
+            let subjects = QuotaSubjects {
+                principal: "alice",
+                client_id: Some(client_id),
+                topic: "events",
+            };
+            quotas.record_at(&subjects, QuotaMetric::Requests, 1, start);
+        }
+        let in_debt = QuotaSubjects {
+            principal: "alice",
+            client_id: Some("busy"),
+            topic: "events",
+        };
+        quotas.record_at(&in_debt, QuotaMetric::Requests, 30, start);
+        assert_eq!(quotas.buckets.lock().buckets.len(), 101);
+
+        // A second on, the one-off clients have refilled but `busy` is still paying back
+        let later = start + Duration::from_secs(1);
+        let violation = quotas
+            .check_at(&in_debt, &[QuotaMetric::Requests], later)
+            .unwrap();
+        assert_eq!(violation.delay, Duration::from_secs(1));
+        assert_eq!(quotas.buckets.lock().buckets.len(), 1);
+    }
+}
@@ -0,0 +1,883 @@
+//! HTTP/JSON gateway implementing `docs/openapi.yaml` on top of [`FlashQ`].
+//!
+//! The broker serves it with `--http-addr`, sharing its queue with the gRPC services. Records
+//! are JSON with string header values; fetches do not move a group's committed offset, and
+//! `GET .../record/stream` follows a topic as Server-Sent Events. The gateway does no
+//! authentication or ACL checks, so the broker refuses to run it alongside credentials or
+//! ACLs. Its calls are charged to the broker's quotas as the anonymous principal with no
+//! client id; rejected calls get 429 with `Retry-After`. Produces are refused with 503 once
+//! the broker's [`Lifecycle`] starts draining, and streams end when it shuts down.
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::Router;
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use flashq::error::HttpError;
+use flashq::{FlashQ, FlashQError, Header, Record, RecordWithOffset, is_internal_topic};
+use serde::{Deserialize, Serialize};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::broker::{quota_bytes, validation};
+use crate::lifecycle::Lifecycle;
+use crate::quota::{QuotaManager, QuotaMetric, QuotaSubjects, QuotaViolation};
+
+/// Most records one produce request may carry.
+const MAX_BATCH_RECORDS: usize = 1000;
+/// Most records one fetch may return, and the default when `max_records` is left out.
+const MAX_FETCH_RECORDS: usize = 10_000;
+const DEFAULT_FETCH_RECORDS: usize = 100;
+/// Longest topic name or consumer group id.
+const MAX_NAME_LENGTH: usize = 255;
+/// Longest commit metadata string.
+const MAX_COMMIT_METADATA_LENGTH: usize = 1024;
+/// How long an idle stream waits before polling the topic again.
+const STREAM_POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// `Retry-After` seconds sent with produces refused while draining.
+const RETRY_AFTER_SECS: &str = "5";
+
+/// Routes of the REST API, serving `core`.
+pub fn router(core: Arc<FlashQ>) -> Router {
+    router_with_lifecycle(core, Lifecycle::new())
+}
+
+/// Routes of the REST API, serving `core` and following the broker's `lifecycle`.
+pub fn router_with_lifecycle(core: Arc<FlashQ>, lifecycle: Lifecycle) -> Router {
+    router_with_quotas(core, lifecycle, Arc::new(QuotaManager::in_memory()))
+}
+
+/// Routes of the REST API, serving `core`, following `lifecycle` and charging `quotas`.
+pub fn router_with_quotas(
+    core: Arc<FlashQ>,
+    lifecycle: Lifecycle,
+    quotas: Arc<QuotaManager>,
+) -> Router {
+    let gateway = Gateway {
+        core,
+        started: Instant::now(),
+        lifecycle,
+        quotas,
+    };
+    Router::new()
+        .route("/health", get(health))
+        .route("/topics", get(list_topics))
+        .route("/topic/:topic/record", post(produce))
+        .route(
+            "/consumer/:group_id",
+            post(create_consumer_group).delete(delete_consumer_group),
+        )
+        .route(
+            "/consumer/:group_id/topic/:topic/record/offset",
+            get(fetch_by_offset),
+        )
+        .route(
+            "/consumer/:group_id/topic/:topic/record/time",
+            get(fetch_by_time),
+        )
+        .route(
+            "/consumer/:group_id/topic/:topic/record/stream",
+            get(stream_records),
+        )
+        .route(
+            "/consumer/:group_id/topic/:topic/offset",
+            post(commit_offset).get(get_committed_offset),
+        )
+        .fallback(not_found)
+        .with_state(gateway)
+}
+
+/// Serve the REST API for `core` on `listener` until `lifecycle` begins shutting down, then
+/// return once in-flight requests have finished.
+pub async fn serve(
+    listener: tokio::net::TcpListener,
+    core: Arc<FlashQ>,
+    lifecycle: Lifecycle,
+    quotas: Arc<QuotaManager>,
+) -> std::io::Result<()> {
+    let shutdown = lifecycle.clone();
+    axum::serve(listener, router_with_quotas(core, lifecycle, quotas))
+        .with_graceful_shutdown(async move { shutdown.shutdown_requested().await })
+        .await
+}
+
+#[derive(Clone)]
+struct Gateway {
+    core: Arc<FlashQ>,
+    started: Instant,
+    lifecycle: Lifecycle,
+    quotas: Arc<QuotaManager>,
+}
+
+// =============================================================================
+// REQUEST AND RESPONSE BODIES
+// =============================================================================
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordJson {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    headers: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Serialize)]
+struct RecordWithOffsetJson {
+    #[serde(flatten)]
+    record: RecordJson,
+    offset: u64,
+    timestamp: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProduceRequest {
+    records: Vec<RecordJson>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProduceResponse {
+    offset: u64,
+    timestamp: String,
+}
+
+#[derive(Debug, Serialize)]
+struct HealthResponse {
+    status: &'static str,
+    version: &'static str,
+    uptime: String,
+    topics_count: usize,
+    consumer_groups_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct TopicsResponse {
+    topics: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ConsumerGroupResponse {
+    group_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FetchByOffsetQuery {
+    max_records: Option<usize>,
+    from_offset: Option<u64>,
+    include_headers: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FetchByTimeQuery {
+    from_time: Option<String>,
+    max_records: Option<usize>,
+    include_headers: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamQuery {
+    from_offset: Option<u64>,
+    include_headers: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+struct FetchResponse {
+    records: Vec<RecordWithOffsetJson>,
+    next_offset: u64,
+    high_water_mark: u64,
+    lag: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OffsetCommitRequest {
+    offset: u64,
+    metadata: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct OffsetCommitResponse {
+    topic: String,
+    committed_offset: u64,
+    timestamp: String,
+}
+
+#[derive(Debug, Serialize)]
+struct OffsetResponse {
+    topic: String,
+    committed_offset: u64,
+    high_water_mark: u64,
+    lag: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: &'static str,
+    message: String,
+}
+
+// =============================================================================
+// HANDLERS
+// =============================================================================
+
+async fn health(State(gateway): State<Gateway>) -> Json<HealthResponse> {
+    Json(HealthResponse {
+        status: "healthy",
+        version: env!("CARGO_PKG_VERSION"),
+        uptime: format_uptime(gateway.started.elapsed()),
+        topics_count: gateway.core.get_topics().len(),
+        consumer_groups_count: gateway.core.get_consumer_groups().len(),
+    })
+}
+
+async fn list_topics(State(gateway): State<Gateway>) -> Json<TopicsResponse> {
+    let mut topics = gateway.core.get_topics();
+    topics.sort();
+    Json(TopicsResponse { topics })
+}
+
+async fn produce(
+    State(gateway): State<Gateway>,
+    Path(topic): Path<String>,
+    body: Result<Json<ProduceRequest>, JsonRejection>,
+) -> Result<Json<ProduceResponse>, ApiError> {
+    let started = Instant::now();
+    if !gateway.lifecycle.accepts_produce() {
+        return Err(ApiError::Unavailable(format!(
+            "Broker is {} and not accepting produce requests",
+            gateway.lifecycle.state()
+        )));
+    }
+    validate_name("topic", &topic)?;
+    if is_internal_topic(&topic) {
+        return Err(invalid(
+            "topic",
+            format!("Topic '{topic}' is internal and cannot be produced to"),
+        ));
+    }
+    let Json(request) = body.map_err(|e| json_rejection("records", e))?;
+    let records = records_from_json(request.records)?;
+    let subjects = QuotaSubjects::anonymous(None, &topic);
+    gateway
+        .quotas
+        .enforce(
+            &subjects,
+            &[QuotaMetric::Requests, QuotaMetric::ProduceBytes],
+        )
+        .await?;
+    let record_count = records.len();
+    let bytes_in = records.iter().map(quota_bytes).sum();
+    gateway.quotas.record(&subjects, QuotaMetric::Requests, 1);
+    gateway
+        .quotas
+        .record(&subjects, QuotaMetric::ProduceBytes, bytes_in);
+
+    let offset = gateway
+        .core
+        .post_records_async(topic.clone(), records, None)
+        .await?;
+    crate::metrics::record_produce(&topic, record_count, bytes_in, started.elapsed());
+    Ok(Json(ProduceResponse {
+        offset,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+async fn create_consumer_group(
+    State(gateway): State<Gateway>,
+    Path(group_id): Path<String>,
+) -> Result<(StatusCode, Json<ConsumerGroupResponse>), ApiError> {
+    validate_name("group-id", &group_id)?;
+    // Creating a group that already exists joins it
+    let status = match gateway
+        .core
+        .create_consumer_group_async(group_id.clone())
+        .await
+    {
+        Ok(()) => StatusCode::CREATED,
+        Err(FlashQError::ConsumerGroupAlreadyExists { .. }) => StatusCode::OK,
+        Err(e) => return Err(e.into()),
+    };
+    Ok((status, Json(ConsumerGroupResponse { group_id })))
+}
+
+async fn delete_consumer_group(
+    State(gateway): State<Gateway>,
+    Path(group_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    validate_name("group-id", &group_id)?;
+    gateway
+        .core
+        .run_blocking(move |core| core.delete_consumer_group(&group_id))
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn fetch_by_offset(
+    State(gateway): State<Gateway>,
+    Path((group_id, topic)): Path<(String, String)>,
+    query: Result<Query<FetchByOffsetQuery>, QueryRejection>,
+) -> Result<Json<FetchResponse>, ApiError> {
+    let started = Instant::now();
+    validate_name("group-id", &group_id)?;
+    validate_name("topic", &topic)?;
+    let Query(query) = query.map_err(query_rejection)?;
+    let limit = fetch_limit(query.max_records)?;
+    charge_fetch_request(&gateway, &topic).await?;
+    let offset = match query.from_offset {
+        Some(offset) => offset,
+        None => gateway.core.get_consumer_group_offset(&group_id, &topic)?,
+    };
+
+    let (poll_group, poll_topic) = (group_id.clone(), topic.clone());
+    let records = gateway
+        .core
+        .run_blocking(move |core| {
+            core.poll_records_for_consumer_group_from_offset(
+                &poll_group,
+                &poll_topic,
+                offset,
+                Some(limit),
+            )
+        })
+        .await?;
+    let next_offset = records
+        .last()
+        .map_or(offset, |r| r.offset.saturating_add(1));
+    let response = fetch_response(
+        &gateway.core,
+        &topic,
+        records,
+        next_offset,
+        query.include_headers,
+    );
+    gateway.quotas.record(
+        &QuotaSubjects::anonymous(None, &topic),
+        QuotaMetric::FetchBytes,
+        response.1,
+    );
+    crate::metrics::record_fetch(
+        &topic,
+        response.0.records.len(),
+        response.1,
+        started.elapsed(),
+    );
+    Ok(Json(response.0))
+}
+
+async fn fetch_by_time(
+    State(gateway): State<Gateway>,
+    Path((group_id, topic)): Path<(String, String)>,
+    query: Result<Query<FetchByTimeQuery>, QueryRejection>,
+) -> Result<Json<FetchResponse>, ApiError> {
+    let started = Instant::now();
+    validate_name("group-id", &group_id)?;
+    validate_name("topic", &topic)?;
+    let Query(query) = query.map_err(query_rejection)?;
+    let limit = fetch_limit(query.max_records)?;
+    let from_time = query
+        .from_time
+        .ok_or_else(|| invalid("from_time", "from_time is required"))?;
+    if let Err(e) = chrono::DateTime::parse_from_rfc3339(&from_time) {
+        return Err(invalid(
+            "from_time",
+            format!("from_time '{from_time}' is not RFC3339: {e}"),
+        ));
+    }
+    charge_fetch_request(&gateway, &topic).await?;
+
+    let (poll_group, poll_topic) = (group_id.clone(), topic.clone());
+    let records = gateway
+        .core
+        .run_blocking(move |core| {
+            core.poll_records_for_consumer_group_from_time(
+                &poll_group,
+                &poll_topic,
+                &from_time,
+                Some(limit),
+            )
+        })
+        .await?;
+    // Nothing at or after the time yet, so the next record to read is the next one written
+    let next_offset = records.last().map_or_else(
+        || gateway.core.get_high_water_mark(&topic),
+        |r| r.offset.saturating_add(1),
+    );
+    let response = fetch_response(
+        &gateway.core,
+        &topic,
+        records,
+        next_offset,
+        query.include_headers,
+    );
+    gateway.quotas.record(
+        &QuotaSubjects::anonymous(None, &topic),
+        QuotaMetric::FetchBytes,
+        response.1,
+    );
+    crate::metrics::record_fetch(
+        &topic,
+        response.0.records.len(),
+        response.1,
+        started.elapsed(),
+    );
+    Ok(Json(response.0))
+}
+
+async fn stream_records(
+    State(gateway): State<Gateway>,
+    Path((group_id, topic)): Path<(String, String)>,
+    query: Result<Query<StreamQuery>, QueryRejection>,
+) -> Result<Sse<ReceiverStream<Result<Event, Infallible>>>, ApiError> {
+    validate_name("group-id", &group_id)?;
+    validate_name("topic", &topic)?;
+    let Query(query) = query.map_err(query_rejection)?;
+    let committed = gateway.core.get_consumer_group_offset(&group_id, &topic)?;
+    if !gateway.core.get_topics().contains(&topic) {
+        return Err(FlashQError::TopicNotFound { topic }.into());
+    }
+    let mut current = query.from_offset.unwrap_or(committed);
+    let include_headers = query.include_headers.unwrap_or(true);
+    let subjects = QuotaSubjects::anonymous(None, &topic);
+    gateway
+        .quotas
+        .enforce(&subjects, &[QuotaMetric::Requests])
+        .await?;
+    gateway.quotas.record(&subjects, QuotaMetric::Requests, 1);
+
+    let (tx, rx) = tokio::sync::mpsc::channel(64);
+    let core = gateway.core.clone();
+    let lifecycle = gateway.lifecycle.clone();
+    let quotas = gateway.quotas.clone();
+    tokio::spawn(async move {
+        let _subscription = crate::metrics::SubscriptionGauge::open(&topic);
+        let subjects = QuotaSubjects::anonymous(None, &topic);
+        // Same circuit breaker as the gRPC Subscribe stream
+        const MAX_CONSECUTIVE_ERRORS: u32 = 5;
+        const MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+        let mut consecutive_errors = 0;
+        let follow = async {
+            loop {
+                // Like a gRPC subscription, an open stream over its fetch quota is throttled
+                if let Some(violation) = quotas.check(&subjects, &[QuotaMetric::FetchBytes]) {
+                    tokio::time::sleep(violation.delay).await;
+                }
+                match core
+                    .poll_records_from_offset_async(
+                        topic.clone(),
+                        current,
+                        Some(DEFAULT_FETCH_RECORDS),
+                    )
+                    .await
+                {
+                    Ok(records) if !records.is_empty() => {
+                        consecutive_errors = 0;
+                        let bytes_out = records.iter().map(|r| quota_bytes(&r.record)).sum();
+                        quotas.record(&subjects, QuotaMetric::FetchBytes, bytes_out);
+                        crate::metrics::record_bytes_out(&topic, records.len(), bytes_out);
+                        for record in records {
+                            current = record.offset.saturating_add(1);
+                            let event = Event::default()
+                                .event("record")
+                                .id(record.offset.to_string())
+                                .json_data(record_to_json(record, include_headers));
+                            let Ok(event) = event else {
+                                continue;
+                            };
+                            if tx.send(Ok(event)).await.is_err() {
+                                return; // Client disconnected
+                            }
+                        }
+                    }
+                    Ok(_) => {
+                        if tx.is_closed() {
+                            return;
+                        }
+                        tokio::time::sleep(STREAM_POLL_INTERVAL).await;
+                    }
+                    Err(e) => {
+                        consecutive_errors += 1;
+                        if consecutive_errors >= MAX_CONSECUTIVE_ERRORS {
+                            let error = ErrorResponse {
+                                error: "internal_error",
+                                message: format!(
+                                    "Too many consecutive errors ({consecutive_errors}): {e}"
+                                ),
+                            };
+                            if let Ok(event) = Event::default().event("error").json_data(error) {
+                                let _ = tx.send(Ok(event)).await;
+                            }
+                            return;
+                        }
+                        let delay = STREAM_POLL_INTERVAL * 2_u32.pow(consecutive_errors - 1);
+                        tokio::time::sleep(delay.min(MAX_RETRY_DELAY)).await;
+                    }
+                }
+            }
+        };
+        // Ending the stream at shutdown lets the server's graceful shutdown complete
+        tokio::select! {
+            _ = follow => {}
+            _ = lifecycle.shutdown_requested() => {}
+        }
+    });
+
+    Ok(Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default()))
+}
+
+async fn commit_offset(
+    State(gateway): State<Gateway>,
+    Path((group_id, topic)): Path<(String, String)>,
+    body: Result<Json<OffsetCommitRequest>, JsonRejection>,
+) -> Result<Json<OffsetCommitResponse>, ApiError> {
+    validate_name("group-id", &group_id)?;
+    validate_name("topic", &topic)?;
+    let Json(request) = body.map_err(|e| json_rejection("offset", e))?;
+    if let Some(metadata) = &request.metadata {
+        if metadata.len() > MAX_COMMIT_METADATA_LENGTH {
+            return Err(invalid(
+                "metadata",
+                format!(
+                    "metadata exceeds maximum length of {MAX_COMMIT_METADATA_LENGTH} characters (got {})",
+                    metadata.len()
+                ),
+            ));
+        }
+    }
+    gateway
+        .core
+        .update_consumer_group_offset_async(group_id, topic.clone(), request.offset)
+        .await?;
+    Ok(Json(OffsetCommitResponse {
+        topic,
+        committed_offset: request.offset,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+async fn get_committed_offset(
+    State(gateway): State<Gateway>,
+    Path((group_id, topic)): Path<(String, String)>,
+) -> Result<Json<OffsetResponse>, ApiError> {
+    validate_name("group-id", &group_id)?;
+    validate_name("topic", &topic)?;
+    let committed_offset = gateway.core.get_consumer_group_offset(&group_id, &topic)?;
+    let high_water_mark = gateway.core.get_high_water_mark(&topic);
+    Ok(Json(OffsetResponse {
+        topic,
+        committed_offset,
+        high_water_mark,
+        lag: high_water_mark.saturating_sub(committed_offset),
+    }))
+}
+
+async fn not_found() -> ApiError {
+    ApiError::NotFound
+}
+
+/// Wait out, or refuse, a fetch from a topic over its quota, then charge the request.
+async fn charge_fetch_request(gateway: &Gateway, topic: &str) -> Result<(), ApiError> {
+    let subjects = QuotaSubjects::anonymous(None, topic);
+    gateway
+        .quotas
+        .enforce(&subjects, &[QuotaMetric::Requests, QuotaMetric::FetchBytes])
+        .await?;
+    gateway.quotas.record(&subjects, QuotaMetric::Requests, 1);
+    Ok(())
+}
+
+// =============================================================================
+// CONVERSIONS AND VALIDATION
+// =============================================================================
+
+/// Build the fetch response, returning it with the record bytes it carries.
+fn fetch_response(
+    core: &FlashQ,
+    topic: &str,
+    records: Vec<RecordWithOffset>,
+    next_offset: u64,
+    include_headers: Option<bool>,
+) -> (FetchResponse, u64) {
+    let include_headers = include_headers.unwrap_or(true);
+    let bytes_out = records.iter().map(|r| quota_bytes(&r.record)).sum();
+    let high_water_mark = core.get_high_water_mark(topic);
+    let response = FetchResponse {
+        records: records
+            .into_iter()
+            .map(|r| record_to_json(r, include_headers))
+            .collect(),
+        next_offset,
+        high_water_mark,
+        lag: high_water_mark.saturating_sub(next_offset),
+    };
+    (response, bytes_out)
+}
+
+fn record_to_json(record: RecordWithOffset, include_headers: bool) -> RecordWithOffsetJson {
+    let headers = match record.record.headers {
+        Some(headers) if include_headers && !headers.is_empty() => Some(
+            flashq::headers_to_map(&headers)
+                .into_iter()
+                .collect::<BTreeMap<_, _>>(),
+        ),
+        _ => None,
+    };
+    RecordWithOffsetJson {
+        record: RecordJson {
+            key: record.record.key,
+            value: record.record.value,
+            headers,
+        },
+        offset: record.offset,
+        timestamp: record.timestamp,
+    }
+}
+
+fn records_from_json(records: Vec<RecordJson>) -> Result<Vec<Record>, ApiError> {
+    if records.is_empty() || records.len() > MAX_BATCH_RECORDS {
+        return Err(invalid(
+            "records",
+            format!(
+                "records must hold between 1 and {MAX_BATCH_RECORDS} records (got {})",
+                records.len()
+            ),
+        ));
+    }
+    records
+        .into_iter()
+        .enumerate()
+        .map(|(i, record)| {
+            let field = format!("records[{i}]");
+            if record.value.is_empty() {
+                return Err(invalid(
+                    &field,
+                    format!("Record at index {i} value cannot be empty"),
+                ));
+            }
+            if let Some(key) = &record.key {
+                if key.len() > validation::MAX_KEY_SIZE {
+                    return Err(invalid(
+                        &field,
+                        format!(
+                            "Record at index {i} key exceeds maximum length of {} characters (got {})",
+                            validation::MAX_KEY_SIZE,
+                            key.len()
+                        ),
+                    ));
+                }
+            }
+            if record.value.len() > validation::MAX_VALUE_SIZE {
+                return Err(invalid(
+                    &field,
+                    format!(
+                        "Record at index {i} value exceeds maximum length of {} bytes (got {})",
+                        validation::MAX_VALUE_SIZE,
+                        record.value.len()
+                    ),
+                ));
+            }
+            let mut headers = Vec::new();
+            for (name, value) in record.headers.into_iter().flatten() {
+                if !is_valid_header_name(&name) {
+                    return Err(invalid(
+                        &field,
+                        format!(
+                            "Record at index {i} header '{name}' must start with a letter and contain only letters, numbers, underscores, and hyphens"
+                        ),
+                    ));
+                }
+                if value.len() > validation::MAX_HEADER_VALUE_SIZE {
+                    return Err(invalid(
+                        &field,
+                        format!(
+                            "Record at index {i} header '{name}' value exceeds maximum length of {} characters (got {})",
+                            validation::MAX_HEADER_VALUE_SIZE,
+                            value.len()
+                        ),
+                    ));
+                }
+                headers.push(Header::new(name, value));
+            }
+            let headers = (!headers.is_empty()).then_some(headers);
+            Ok(Record::new(record.key, record.value, headers))
+        })
+        .collect()
+}
+
+/// Topic names and group ids match `^[a-zA-Z0-9._][a-zA-Z0-9._-]*$` and are at most 255
+/// characters.
+fn validate_name(field: &str, name: &str) -> Result<(), ApiError> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-');
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && !name.starts_with('-')
+        && name.chars().all(allowed);
+    if valid {
+        return Ok(());
+    }
+    let what = if field == "topic" {
+        "Topic name"
+    } else {
+        "Consumer group id"
+    };
+    Err(invalid(
+        field,
+        format!(
+            "{what} must be 1 to {MAX_NAME_LENGTH} characters of letters, numbers, dots, underscores, and hyphens, not starting with a hyphen"
+        ),
+    ))
+}
+
+fn is_valid_header_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+}
+
+fn fetch_limit(max_records: Option<usize>) -> Result<usize, ApiError> {
+    match max_records {
+        None => Ok(DEFAULT_FETCH_RECORDS),
+        Some(limit) if (1..=MAX_FETCH_RECORDS).contains(&limit) => Ok(limit),
+        Some(limit) => Err(invalid(
+            "max_records",
+            format!("max_records must be between 1 and {MAX_FETCH_RECORDS} (got {limit})"),
+        )),
+    }
+}
+
+fn format_uptime(uptime: Duration) -> String {
+    let secs = uptime.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes}m {seconds}s")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+fn invalid(field: &str, message: impl Into<String>) -> ApiError {
+    ApiError::Http(HttpError::Validation {
+        field: field.to_string(),
+        message: message.into(),
+    })
+}
+
+/// Malformed JSON is a bad request; well-formed JSON of the wrong shape fails validation of
+/// `field`.
+fn json_rejection(field: &str, rejection: JsonRejection) -> ApiError {
+    let field = match rejection {
+        JsonRejection::JsonDataError(_) => field,
+        _ => "body",
+    };
+    invalid(field, rejection.body_text())
+}
+
+fn query_rejection(rejection: QueryRejection) -> ApiError {
+    invalid("query", rejection.body_text())
+}
+
+// =============================================================================
+// ERRORS
+// =============================================================================
+
+/// An error response as described by the spec's `ErrorResponse` schema.
+enum ApiError {
+    Http(HttpError),
+    NotFound,
+    /// The broker is draining or shutting down.
+    Unavailable(String),
+    /// A quota is exhausted and the broker rejects calls over quota.
+    QuotaExceeded(QuotaViolation),
+}
+
+impl From<QuotaViolation> for ApiError {
+    fn from(violation: QuotaViolation) -> Self {
+        ApiError::QuotaExceeded(violation)
+    }
+}
+
+impl From<FlashQError> for ApiError {
+    fn from(err: FlashQError) -> Self {
+        ApiError::Http(HttpError::Domain(err))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, error, message) = match self {
+            // Problems with record contents are 422s; bad paths, queries and bodies are 400s
+            ApiError::Http(HttpError::Validation { field, message }) => {
+                if field.starts_with("records") {
+                    (
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "validation_error",
+                        message,
+                    )
+                } else {
+                    (StatusCode::BAD_REQUEST, "invalid_parameter", message)
+                }
+            }
+            ApiError::Http(HttpError::Domain(err)) => {
+                let (status, error) = match &err {
+                    FlashQError::TopicNotFound { .. } => (StatusCode::NOT_FOUND, "topic_not_found"),
+                    FlashQError::ConsumerGroupNotFound { .. } => {
+                        (StatusCode::NOT_FOUND, "group_not_found")
+                    }
+                    FlashQError::InvalidOffset { .. } => {
+                        (StatusCode::BAD_REQUEST, "invalid_offset")
+                    }
+                    err if err.is_client_error() => (StatusCode::BAD_REQUEST, "invalid_request"),
+                    _ => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+                };
+                (status, error, err.to_string())
+            }
+            ApiError::Http(err @ HttpError::Internal { .. }) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+                err.to_string(),
+            ),
+            ApiError::NotFound => (
+                StatusCode::NOT_FOUND,
+                "not_found",
+                "No such endpoint".to_string(),
+            ),
+            ApiError::Unavailable(message) => {
+                let body = ErrorResponse {
+                    error: "broker_unavailable",
+                    message,
+                };
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    [(axum::http::header::RETRY_AFTER, RETRY_AFTER_SECS)],
+                    Json(body),
+                )
+                    .into_response();
+            }
+            ApiError::QuotaExceeded(violation) => {
+                let retry_after = violation.delay.as_secs_f64().ceil().max(1.0) as u64;
+                let body = ErrorResponse {
+                    error: "quota_exceeded",
+                    message: violation.to_string(),
+                };
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(axum::http::header::RETRY_AFTER, retry_after.to_string())],
+                    Json(body),
+                )
+                    .into_response();
+            }
+        };
+        if status.is_server_error() {
+            tracing::error!(%message, "REST request failed");
+        }
+        (status, Json(ErrorResponse { error, message })).into_response()
+    }
+}
@@ -0,0 +1,279 @@
+use crate::test_utilities::{CallCredentials, TestServer};
+use flashq_broker::flashq::v1 as proto;
+use flashq_client::FlashqClient;
+use flashq_cluster::client::ClusterClient;
+use tempfile::TempDir;
+
+fn write_credentials_file(dir: &TempDir) -> std::path::PathBuf {
+    let path = dir.path().join("credentials.json");
+    std::fs::write(
+        &path,
+        r#"{
+            "tokens": [
+                { "principal": "admin", "token": "tok-admin" },
+                { "principal": "payments-service", "token": "tok-payments" },
+                { "principal": "analytics", "token": "tok-analytics" }
+            ]
+        }"#,
+    )
+    .expect("write credentials file");
+    path
+}
+
+async fn start_server(dir: &TempDir) -> TestServer {
+    let path = write_credentials_file(dir);
+    TestServer::start_with_acls(&path, CallCredentials::token("tok-admin"), "admin")
+        .await
+        .expect("start server")
+}
+
+async fn client(srv: &TestServer, token: &str) -> FlashqClient {
+    FlashqClient::connect(format!("http://127.0.0.1:{}", srv.port))
+        .await
+        .expect("connect")
+        .with_credentials(CallCredentials::token(token))
+}
+
+fn allow(
+    principal: &str,
+    resource_type: proto::AclResourceType,
+    resource_name: &str,
+    pattern_type: proto::AclPatternType,
+    operation: proto::AclOperation,
+) -> proto::AclBinding {
+    proto::AclBinding {
+        principal: principal.to_string(),
+        resource_type: resource_type as i32,
+        resource_name: resource_name.to_string(),
+        pattern_type: pattern_type as i32,
+        operation: operation as i32,
+        permission: proto::AclPermission::Allow as i32,
+    }
+}
+
+async fn produce(client: &FlashqClient, topic: &str) -> Result<(), tonic::Status> {
+    client
+        .producer()
+        .produce(proto::ProduceRequest {
+            topic: topic.to_string(),
+            records: vec![proto::Record {
+                key: String::new(),
+                value: "payload".to_string(),
+                headers: Default::default(),
+                timestamp: String::new(),
+            }],
+            ..Default::default()
+        })
+        .await
+        .map(|_| ())
+}
+
+#[tokio::test]
+async fn test_acls_gate_produce_by_topic() {
+    let dir = tempfile::tempdir().unwrap();
+    let srv = start_server(&dir).await;
+    let admin = client(&srv, "tok-admin").await;
+    let payments = client(&srv, "tok-payments").await;
+    let analytics = client(&srv, "tok-analytics").await;
+
+    let created = admin
+        .admin()
+        .create_acls(proto::CreateAclsRequest {
+            acls: vec![allow(
+                "payments-service",
+                proto::AclResourceType::Topic,
+                "payments",
+                proto::AclPatternType::Prefixed,
+                proto::AclOperation::Write,
+            )],
+        })
+        .await
+        .expect("super user creates ACLs")
+        .into_inner();
+    assert_eq!(created.created, 1);
+
+    produce(&payments, "payments").await.expect("allowed");
+    produce(&payments, "payments-eu")
+        .await
+        .expect("prefix allowed");
+
+    let status = produce(&payments, "orders").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    assert!(status.message().contains("topic 'orders'"));
+
+    let status = produce(&analytics, "payments").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    assert!(status.message().contains("analytics"));
+
+    // Write implies Describe, so list_topics shows only the topics the caller may see
+    produce(&admin, "orders")
+        .await
+        .expect("super user produces");
+    let visible = payments
+        .admin()
+        .list_topics(proto::Empty {})
+        .await
+        .unwrap()
+        .into_inner()
+        .topics;
+    assert!(visible.iter().all(|topic| topic.starts_with("payments")));
+    assert_eq!(visible.len(), 2);
+}
+
+#[tokio::test]
+async fn test_acls_require_read_on_topic_and_group() {
+    let dir = tempfile::tempdir().unwrap();
+    let srv = start_server(&dir).await;
+    let admin = client(&srv, "tok-admin").await;
+    let analytics = client(&srv, "tok-analytics").await;
+    produce(&admin, "events").await.unwrap();
+
+    admin
+        .admin()
+        .create_acls(proto::CreateAclsRequest {
+            acls: vec![
+                allow(
+                    "analytics",
+                    proto::AclResourceType::Topic,
+                    "events",
+                    proto::AclPatternType::Literal,
+                    proto::AclOperation::Read,
+                ),
+                allow(
+                    "analytics",
+                    proto::AclResourceType::Group,
+                    "reporting",
+                    proto::AclPatternType::Literal,
+                    proto::AclOperation::Read,
+                ),
+            ],
+        })
+        .await
+        .unwrap();
+
+    let fetch = |group_id: &str| proto::FetchByOffsetRequest {
+        group_id: group_id.to_string(),
+        topic: "events".to_string(),
+        max_records: 10,
+        ..Default::default()
+    };
+    let mut consumer = analytics.consumer();
+    consumer
+        .create_consumer_group(proto::ConsumerGroupId {
+            group_id: "reporting".to_string(),
+        })
+        .await
+        .expect("create allowed group");
+    let fetched = consumer
+        .fetch_by_offset(fetch("reporting"))
+        .await
+        .expect("read allowed topic and group")
+        .into_inner();
+    assert_eq!(fetched.records.len(), 1);
+
+    let status = consumer
+        .fetch_by_offset(fetch("other-group"))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    assert!(status.message().contains("group 'other-group'"));
+
+    let status = produce(&analytics, "events").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn test_acl_admin_rpcs_list_and_delete() {
+    let dir = tempfile::tempdir().unwrap();
+    let srv = start_server(&dir).await;
+    let admin = client(&srv, "tok-admin").await;
+    let payments = client(&srv, "tok-payments").await;
+
+    let binding = allow(
+        "payments-service",
+        proto::AclResourceType::Topic,
+        "payments",
+        proto::AclPatternType::Literal,
+        proto::AclOperation::Write,
+    );
+    let request = proto::CreateAclsRequest {
+        acls: vec![binding.clone()],
+    };
+    admin.admin().create_acls(request.clone()).await.unwrap();
+    let again = admin
+        .admin()
+        .create_acls(request)
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(again.created, 0);
+
+    let status = payments
+        .admin()
+        .list_acls(proto::ListAclsRequest::default())
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+    let filter = proto::AclFilter {
+        principal: "payments-service".to_string(),
+        ..Default::default()
+    };
+    let listed = admin
+        .admin()
+        .list_acls(proto::ListAclsRequest {
+            filter: Some(filter.clone()),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(listed.acls, vec![binding.clone()]);
+
+    let deleted = admin
+        .admin()
+        .delete_acls(proto::DeleteAclsRequest {
+            filter: Some(filter),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(deleted.deleted, vec![binding]);
+
+    let status = produce(&payments, "payments").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+    let status = admin
+        .admin()
+        .create_acls(proto::CreateAclsRequest {
+            acls: vec![proto::AclBinding {
+                principal: "payments-service".to_string(),
+                ..Default::default()
+            }],
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_cluster_service_requires_cluster_action() {
+    let dir = tempfile::tempdir().unwrap();
+    let srv = start_server(&dir).await;
+    let addr = format!("http://127.0.0.1:{}", srv.port);
+
+    let mut payments = ClusterClient::connect(addr.clone())
+        .await
+        .unwrap()
+        .with_credentials(CallCredentials::token("tok-payments"));
+    let err = payments.describe_cluster().await.unwrap_err();
+    assert!(err.to_string().contains("ClusterAction"), "{err}");
+
+    let mut admin = ClusterClient::connect(addr)
+        .await
+        .unwrap()
+        .with_credentials(CallCredentials::token("tok-admin"));
+    admin
+        .describe_cluster()
+        .await
+        .expect("super user describes cluster");
+}
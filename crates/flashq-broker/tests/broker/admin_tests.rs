@@ -17,3 +17,97 @@ async fn test_admin_health_and_topics() {
         .into_inner();
     assert!(topics.topics.is_empty());
 }
+
+#[tokio::test]
+async fn test_list_offsets_reports_earliest_latest_and_time_lookup() {
+    let srv = TestServer::start().await.expect("start server");
+    let addr = format!("http://127.0.0.1:{}", srv.port);
+    let mut producer = proto::producer_client::ProducerClient::connect(addr.clone())
+        .await
+        .expect("connect producer");
+    let mut admin = proto::admin_client::AdminClient::connect(addr)
+        .await
+        .expect("connect admin");
+    let topic = "list-offsets-topic".to_string();
+
+    let records = [
+        "2024-01-01T00:00:10Z",
+        "2024-01-01T00:00:30Z",
+        "2024-01-01T00:00:20Z",
+    ]
+    .into_iter()
+    .map(|timestamp| proto::Record {
+        key: String::new(),
+        value: "v".into(),
+        headers: Default::default(),
+        timestamp: timestamp.into(),
+    })
+    .collect();
+    producer
+        .produce(proto::ProduceRequest {
+            topic: topic.clone(),
+            records,
+            ..Default::default()
+        })
+        .await
+        .expect("produce");
+
+    let resp = admin
+        .list_offsets(proto::ListOffsetsRequest {
+            topic: topic.clone(),
+            partitions: vec![],
+            timestamp: "2024-01-01T00:00:15Z".into(),
+        })
+        .await
+        .expect("list offsets")
+        .into_inner();
+    assert_eq!(resp.topic, topic);
+    assert_eq!(
+        resp.partitions,
+        vec![proto::PartitionOffsets {
+            partition: 0,
+            earliest: 0,
+            latest: 3,
+            offset_for_timestamp: Some(1),
+        }]
+    );
+
+    let resp = admin
+        .list_offsets(proto::ListOffsetsRequest {
+            topic,
+            partitions: vec![0],
+            timestamp: String::new(),
+        })
+        .await
+        .expect("list offsets without timestamp")
+        .into_inner();
+    assert_eq!(resp.partitions[0].offset_for_timestamp, None);
+}
+
+#[tokio::test]
+async fn test_list_offsets_rejects_bad_requests() {
+    let srv = TestServer::start().await.expect("start server");
+    let addr = format!("http://127.0.0.1:{}", srv.port);
+    let mut admin = proto::admin_client::AdminClient::connect(addr)
+        .await
+        .expect("connect admin");
+
+    let missing = admin
+        .list_offsets(proto::ListOffsetsRequest {
+            topic: "no-such-topic".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(missing.code(), tonic::Code::NotFound);
+
+    let bad_time = admin
+        .list_offsets(proto::ListOffsetsRequest {
+            topic: "no-such-topic".into(),
+            timestamp: "yesterday".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(bad_time.code(), tonic::Code::InvalidArgument);
+}
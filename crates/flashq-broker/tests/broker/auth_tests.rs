@@ -0,0 +1,149 @@
+use crate::test_utilities::{CallCredentials, TestServer};
+use flashq_broker::flashq::v1 as proto;
+use flashq_client::FlashqClient;
+use flashq_cluster::client::ClusterClient;
+use flashq_proto::auth::ScramCredential;
+use tempfile::TempDir;
+
+fn write_credentials_file(dir: &TempDir) -> std::path::PathBuf {
+    let scram = ScramCredential::generate("wonderland", 256).expect("derive scram credential");
+    let path = dir.path().join("credentials.json");
+    std::fs::write(
+        &path,
+        format!(
+            r#"{{
+                "tokens": [{{ "principal": "ingest-service", "token": "tok-ingest" }}],
+                "users": [{{ "username": "alice", "scram": "{scram}" }}]
+            }}"#
+        ),
+    )
+    .expect("write credentials file");
+    path
+}
+
+async fn health(addr: &str, credentials: CallCredentials) -> Result<(), tonic::Status> {
+    let client = FlashqClient::connect(addr.to_string())
+        .await
+        .expect("connect")
+        .with_credentials(credentials);
+    client.admin().health(proto::Empty {}).await.map(|_| ())
+}
+
+#[tokio::test]
+async fn test_broker_requires_known_credentials() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_credentials_file(&dir);
+    let srv = TestServer::start_with_credentials(&path, CallCredentials::token("tok-ingest"))
+        .await
+        .expect("start server");
+    let addr = format!("http://127.0.0.1:{}", srv.port);
+
+    for rejected in [
+        CallCredentials::None,
+        CallCredentials::token("tok-unknown"),
+        CallCredentials::password("alice", "looking-glass"),
+        CallCredentials::password("mallory", "wonderland"),
+    ] {
+        let status = health(&addr, rejected).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    }
+
+    health(&addr, CallCredentials::token("tok-ingest"))
+        .await
+        .expect("token accepted");
+    health(&addr, CallCredentials::password("alice", "wonderland"))
+        .await
+        .expect("password accepted");
+}
+
+#[tokio::test]
+async fn test_authenticated_clients_produce_and_fetch() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_credentials_file(&dir);
+    let srv = TestServer::start_with_credentials(&path, CallCredentials::token("tok-ingest"))
+        .await
+        .expect("start server");
+    let addr = format!("http://127.0.0.1:{}", srv.port);
+    let topic = "auth-topic".to_string();
+
+    let ingest = FlashqClient::connect(addr.clone())
+        .await
+        .unwrap()
+        .with_credentials(CallCredentials::token("tok-ingest"));
+    ingest
+        .producer()
+        .produce(proto::ProduceRequest {
+            topic: topic.clone(),
+            records: vec![proto::Record {
+                key: String::new(),
+                value: "hello".to_string(),
+                headers: Default::default(),
+                timestamp: String::new(),
+            }],
+            ..Default::default()
+        })
+        .await
+        .expect("produce with token");
+
+    let alice = FlashqClient::connect(addr.clone())
+        .await
+        .unwrap()
+        .with_credentials(CallCredentials::password("alice", "wonderland"));
+    let mut consumer = alice.consumer();
+    consumer
+        .create_consumer_group(proto::ConsumerGroupId {
+            group_id: "grp-auth".to_string(),
+        })
+        .await
+        .expect("create group with password");
+    let fetched = consumer
+        .fetch_by_offset(proto::FetchByOffsetRequest {
+            group_id: "grp-auth".to_string(),
+            topic: topic.clone(),
+            max_records: 10,
+            include_headers: true,
+            ..Default::default()
+        })
+        .await
+        .expect("fetch with password")
+        .into_inner();
+    assert_eq!(fetched.records.len(), 1);
+
+    let mut anonymous = FlashqClient::connect(addr).await.unwrap().producer();
+    let status = anonymous
+        .produce(proto::ProduceRequest {
+            topic,
+            records: vec![proto::Record {
+                key: String::new(),
+                value: "sneaky".to_string(),
+                headers: Default::default(),
+                timestamp: String::new(),
+            }],
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn test_cluster_service_requires_credentials() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_credentials_file(&dir);
+    let srv = TestServer::start_with_credentials(&path, CallCredentials::token("tok-ingest"))
+        .await
+        .expect("start server");
+    let addr = format!("http://127.0.0.1:{}", srv.port);
+
+    let mut anonymous = ClusterClient::connect(addr.clone()).await.unwrap();
+    assert!(anonymous.describe_cluster().await.is_err());
+
+    let mut broker = ClusterClient::connect(addr)
+        .await
+        .unwrap()
+        .with_credentials(CallCredentials::token("tok-ingest"));
+    broker
+        .describe_cluster()
+        .await
+        .expect("describe cluster with token");
+}
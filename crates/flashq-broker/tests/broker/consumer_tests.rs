@@ -30,7 +30,9 @@ async fn test_offsets_commit_and_get() {
                     key: String::new(),
                     value: format!("r{i}"),
                     headers: Default::default(),
+                    timestamp: String::new(),
                 }],
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -88,7 +90,9 @@ async fn test_fetch_by_time() {
                     key: String::new(),
                     value: format!("tv{i}"),
                     headers: Default::default(),
+                    timestamp: String::new(),
                 }],
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -102,9 +106,166 @@ async fn test_fetch_by_time() {
             from_time: "1970-01-01T00:00:00Z".to_string(),
             max_records: 10,
             include_headers: true,
+            to_offset: 0,
+            to_time: String::new(),
         })
         .await
         .unwrap()
         .into_inner();
     assert!(fetched.records.len() >= 2);
 }
+
+#[tokio::test]
+async fn test_fetch_by_time_uses_producer_timestamps() {
+    let srv = TestServer::start().await.expect("start server");
+    let addr = format!("http://127.0.0.1:{}", srv.port);
+    let mut producer = proto::producer_client::ProducerClient::connect(addr.clone())
+        .await
+        .unwrap();
+    let mut consumer = proto::consumer_client::ConsumerClient::connect(addr)
+        .await
+        .unwrap();
+    consumer
+        .create_consumer_group(proto::ConsumerGroupId {
+            group_id: "event-time-group".into(),
+        })
+        .await
+        .unwrap();
+
+    // Event times go backwards: offset 1 happened before offset 0
+    let records = [
+        ("late", "2024-01-01T00:00:10Z"),
+        ("early", "2024-01-01T00:00:01Z"),
+    ]
+    .into_iter()
+    .map(|(value, timestamp)| proto::Record {
+        key: String::new(),
+        value: value.into(),
+        headers: Default::default(),
+        timestamp: timestamp.into(),
+    })
+    .collect();
+    producer
+        .produce(proto::ProduceRequest {
+            topic: "event-time-topic".into(),
+            records,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let fetched = consumer
+        .fetch_by_time(proto::FetchByTimeRequest {
+            group_id: "event-time-group".into(),
+            topic: "event-time-topic".into(),
+            from_time: "2024-01-01T00:00:05Z".to_string(),
+            max_records: 10,
+            include_headers: false,
+            to_offset: 0,
+            to_time: String::new(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(fetched.records.len(), 1);
+    assert_eq!(fetched.records[0].offset, 0);
+    assert_eq!(fetched.records[0].timestamp, "2024-01-01T00:00:10+00:00");
+}
+
+#[tokio::test]
+async fn test_fetch_stops_at_end_offset_and_time() {
+    let srv = TestServer::start().await.expect("start server");
+    let addr = format!("http://127.0.0.1:{}", srv.port);
+    let mut producer = proto::producer_client::ProducerClient::connect(addr.clone())
+        .await
+        .unwrap();
+    let mut consumer = proto::consumer_client::ConsumerClient::connect(addr)
+        .await
+        .unwrap();
+    consumer
+        .create_consumer_group(proto::ConsumerGroupId {
+            group_id: "bounded-group".into(),
+        })
+        .await
+        .unwrap();
+
+    let records = [
+        "2024-01-01T00:00:10Z",
+        "2024-01-01T00:00:30Z",
+        "2024-01-01T00:00:20Z",
+        "2024-01-01T00:00:40Z",
+    ]
+    .into_iter()
+    .map(|timestamp| proto::Record {
+        key: String::new(),
+        value: timestamp.into(),
+        headers: Default::default(),
+        timestamp: timestamp.into(),
+    })
+    .collect();
+    producer
+        .produce(proto::ProduceRequest {
+            topic: "bounded-topic".into(),
+            records,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let by_offset = consumer
+        .fetch_by_offset(proto::FetchByOffsetRequest {
+            group_id: "bounded-group".into(),
+            topic: "bounded-topic".into(),
+            from_offset: 1,
+            max_records: 10,
+            include_headers: false,
+            to_offset: 3,
+            to_time: String::new(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let offsets: Vec<u64> = by_offset.records.iter().map(|r| r.offset).collect();
+    assert_eq!(offsets, vec![1, 2]);
+    assert_eq!(by_offset.next_offset, 3);
+
+    let by_time = consumer
+        .fetch_by_time(proto::FetchByTimeRequest {
+            group_id: "bounded-group".into(),
+            topic: "bounded-topic".into(),
+            from_time: "2024-01-01T00:00:15Z".into(),
+            max_records: 10,
+            include_headers: false,
+            to_offset: 0,
+            to_time: "2024-01-01T00:00:35Z".into(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let offsets: Vec<u64> = by_time.records.iter().map(|r| r.offset).collect();
+    assert_eq!(offsets, vec![1, 2]);
+
+    let inverted = consumer
+        .fetch_by_time(proto::FetchByTimeRequest {
+            group_id: "bounded-group".into(),
+            topic: "bounded-topic".into(),
+            from_time: "2024-01-01T00:00:35Z".into(),
+            to_time: "2024-01-01T00:00:15Z".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(inverted.code(), tonic::Code::InvalidArgument);
+
+    let malformed = consumer
+        .fetch_by_offset(proto::FetchByOffsetRequest {
+            group_id: "bounded-group".into(),
+            topic: "bounded-topic".into(),
+            to_time: "tomorrow".into(),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(malformed.code(), tonic::Code::InvalidArgument);
+}
@@ -0,0 +1,681 @@
+//! Kafka protocol listener tests, speaking the wire format through a hand-rolled client.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use flashq_broker::kafka::KafkaListener;
+use flashq_broker::lifecycle::Lifecycle;
+use flashq_broker::quota::{QuotaConfig, QuotaEntity, QuotaEntityType, QuotaManager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const NODE_ID: i32 = 7;
+
+const PRODUCE: i16 = 0;
+const FETCH: i16 = 1;
+const LIST_OFFSETS: i16 = 2;
+const METADATA: i16 = 3;
+const OFFSET_COMMIT: i16 = 8;
+const OFFSET_FETCH: i16 = 9;
+const FIND_COORDINATOR: i16 = 10;
+const API_VERSIONS: i16 = 18;
+
+/// Request body builder.
+#[derive(Default)]
+struct Req(Vec<u8>);
+
+impl Req {
+    fn i8(mut self, v: i8) -> Self {
+        self.0.extend_from_slice(&v.to_be_bytes());
+        self
+    }
+    fn i16(mut self, v: i16) -> Self {
+        self.0.extend_from_slice(&v.to_be_bytes());
+        self
+    }
+    fn i32(mut self, v: i32) -> Self {
+        self.0.extend_from_slice(&v.to_be_bytes());
+        self
+    }
+    fn i64(mut self, v: i64) -> Self {
+        self.0.extend_from_slice(&v.to_be_bytes());
+        self
+    }
+    fn string(self, v: &str) -> Self {
+        let mut req = self.i16(v.len() as i16);
+        req.0.extend_from_slice(v.as_bytes());
+        req
+    }
+    fn bytes(self, v: &[u8]) -> Self {
+        let mut req = self.i32(v.len() as i32);
+        req.0.extend_from_slice(v);
+        req
+    }
+    fn varint(mut self, v: i64) -> Self {
+        let mut zigzag = ((v << 1) ^ (v >> 63)) as u64;
+        while zigzag >= 0x80 {
+            self.0.push((zigzag as u8 & 0x7f) | 0x80);
+            zigzag >>= 7;
+        }
+        self.0.push(zigzag as u8);
+        self
+    }
+    fn varint_bytes(self, v: &[u8]) -> Self {
+        let mut req = self.varint(v.len() as i64);
+        req.0.extend_from_slice(v);
+        req
+    }
+}
+
+/// Response body reader.
+struct Resp<'a>(&'a [u8]);
+
+impl Resp<'_> {
+    fn take(&mut self, n: usize) -> &[u8] {
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        head
+    }
+    fn i8(&mut self) -> i8 {
+        self.take(1)[0] as i8
+    }
+    fn i16(&mut self) -> i16 {
+        i16::from_be_bytes(self.take(2).try_into().unwrap())
+    }
+    fn i32(&mut self) -> i32 {
+        i32::from_be_bytes(self.take(4).try_into().unwrap())
+    }
+    fn i64(&mut self) -> i64 {
+        i64::from_be_bytes(self.take(8).try_into().unwrap())
+    }
+    fn string(&mut self) -> Option<String> {
+        let len = self.i16();
+        (len >= 0).then(|| String::from_utf8(self.take(len as usize).to_vec()).unwrap())
+    }
+    fn bytes(&mut self) -> Vec<u8> {
+        let len = self.i32();
+        self.take(len.max(0) as usize).to_vec()
+    }
+    fn varint(&mut self) -> i64 {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.take(1)[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return ((value >> 1) as i64) ^ -((value & 1) as i64);
+            }
+            shift += 7;
+        }
+    }
+    fn varint_bytes(&mut self) -> Option<Vec<u8>> {
+        let len = self.varint();
+        (len >= 0).then(|| self.take(len as usize).to_vec())
+    }
+    fn done(&self) {
+        assert!(self.0.is_empty(), "{} unread response bytes", self.0.len());
+    }
+}
+
+struct TestRecord {
+    offset: i64,
+    timestamp: i64,
+    key: Option<String>,
+    value: String,
+    headers: Vec<(String, Vec<u8>)>,
+}
+
+/// A v2 record batch of `values`, keyed `key-<n>`, with a `source` header.
+fn record_batch(timestamp: i64, values: &[&str]) -> Vec<u8> {
+    let mut body = Req::default()
+        .i16(0) // attributes
+        .i32(values.len() as i32 - 1)
+        .i64(timestamp)
+        .i64(timestamp + values.len() as i64 - 1)
+        .i64(-1)
+        .i16(-1)
+        .i32(-1)
+        .i32(values.len() as i32);
+    for (i, value) in values.iter().enumerate() {
+        let record = Req::default()
+            .i8(0)
+            .varint(i as i64) // timestamp delta
+            .varint(i as i64) // offset delta
+            .varint_bytes(format!("key-{i}").as_bytes())
+            .varint_bytes(value.as_bytes())
+            .varint(1)
+            .varint_bytes(b"source")
+            .varint_bytes(b"kafka-test");
+        body = body.varint(record.0.len() as i64);
+        body.0.extend_from_slice(&record.0);
+    }
+    let mut batch = Req::default()
+        .i64(0)
+        .i32(4 + 1 + 4 + body.0.len() as i32)
+        .i32(-1)
+        .i8(2)
+        .i32(crc32c::crc32c(&body.0) as i32);
+    batch.0.extend_from_slice(&body.0);
+    batch.0
+}
+
+fn decode_record_batches(mut data: &[u8]) -> Vec<TestRecord> {
+    let mut records = Vec::new();
+    while !data.is_empty() {
+        let mut r = Resp(data);
+        let base_offset = r.i64();
+        let length = r.i32() as usize;
+        let _leader_epoch = r.i32();
+        assert_eq!(r.i8(), 2, "magic");
+        let crc = r.i32() as u32;
+        assert_eq!(crc32c::crc32c(&data[21..12 + length]), crc, "batch CRC");
+        let _attributes = r.i16();
+        let _last_offset_delta = r.i32();
+        let base_timestamp = r.i64();
+        let _max_timestamp = r.i64();
+        r.take(8 + 2 + 4);
+        for _ in 0..r.i32() {
+            let _length = r.varint();
+            let _attributes = r.i8();
+            let timestamp = base_timestamp + r.varint();
+            let offset = base_offset + r.varint();
+            let key = r.varint_bytes().map(|k| String::from_utf8(k).unwrap());
+            let value = String::from_utf8(r.varint_bytes().unwrap()).unwrap();
+            let headers = (0..r.varint())
+                .map(|_| {
+                    let name = String::from_utf8(r.varint_bytes().unwrap()).unwrap();
+                    (name, r.varint_bytes().unwrap_or_default())
+                })
+                .collect();
+            records.push(TestRecord {
+                offset,
+                timestamp,
+                key,
+                value,
+                headers,
+            });
+        }
+        data = &data[12 + length..];
+    }
+    records
+}
+
+struct KafkaClient {
+    stream: TcpStream,
+    correlation_id: i32,
+}
+
+impl KafkaClient {
+    async fn connect(port: u16) -> Self {
+        Self {
+            stream: TcpStream::connect(("127.0.0.1", port)).await.unwrap(),
+            correlation_id: 0,
+        }
+    }
+
+    async fn send(&mut self, api_key: i16, version: i16, body: Req) -> i32 {
+        self.correlation_id += 1;
+        let request = Req::default()
+            .i16(api_key)
+            .i16(version)
+            .i32(self.correlation_id)
+            .string("kafka-test");
+        let size = (request.0.len() + body.0.len()) as i32;
+        let mut frame = size.to_be_bytes().to_vec();
+        frame.extend_from_slice(&request.0);
+        frame.extend_from_slice(&body.0);
+        self.stream.write_all(&frame).await.unwrap();
+        self.correlation_id
+    }
+
+    async fn receive(&mut self) -> (i32, Vec<u8>) {
+        let size = self.stream.read_i32().await.unwrap();
+        let mut frame = vec![0; size as usize];
+        self.stream.read_exact(&mut frame).await.unwrap();
+        let correlation_id = i32::from_be_bytes(frame[..4].try_into().unwrap());
+        (correlation_id, frame[4..].to_vec())
+    }
+
+    async fn call(&mut self, api_key: i16, version: i16, body: Req) -> Vec<u8> {
+        let sent = self.send(api_key, version, body).await;
+        let (correlation_id, response) = self.receive().await;
+        assert_eq!(correlation_id, sent);
+        response
+    }
+
+    /// Produce v8 to partition 0, returning the error code and base offset.
+    async fn produce(&mut self, topic: &str, partition: i32, batch: &[u8]) -> (i16, i64) {
+        let (error, base_offset, throttle_time_ms) =
+            self.produce_throttled(topic, partition, batch).await;
+        assert_eq!(throttle_time_ms, 0, "throttle");
+        (error, base_offset)
+    }
+
+    /// Produce v8 like [`Self::produce`], also returning the throttle time.
+    async fn produce_throttled(
+        &mut self,
+        topic: &str,
+        partition: i32,
+        batch: &[u8],
+    ) -> (i16, i64, i32) {
+        let body = Req::default()
+            .i16(-1) // null transactional id
+            .i16(1)
+            .i32(5000)
+            .i32(1)
+            .string(topic)
+            .i32(1)
+            .i32(partition)
+            .bytes(batch);
+        let response = self.call(PRODUCE, 8, body).await;
+        let mut r = Resp(&response);
+        assert_eq!(r.i32(), 1);
+        assert_eq!(r.string().as_deref(), Some(topic));
+        assert_eq!(r.i32(), 1);
+        assert_eq!(r.i32(), partition);
+        let (error, base_offset) = (r.i16(), r.i64());
+        let _log_append_time = r.i64();
+        let _log_start_offset = r.i64();
+        assert_eq!(r.i32(), 0, "record errors");
+        let _error_message = r.string();
+        let throttle_time_ms = r.i32();
+        r.done();
+        (error, base_offset, throttle_time_ms)
+    }
+
+    /// Fetch v11 from partition 0, returning the error code, high watermark and records.
+    async fn fetch(
+        &mut self,
+        topic: &str,
+        offset: i64,
+        max_wait_ms: i32,
+    ) -> (i16, i64, Vec<TestRecord>) {
+        let body = Req::default()
+            .i32(-1)
+            .i32(max_wait_ms)
+            .i32(1)
+            .i32(1 << 20)
+            .i8(0)
+            .i32(0)
+            .i32(-1) // no session
+            .i32(1)
+            .string(topic)
+            .i32(1)
+            .i32(0)
+            .i32(-1)
+            .i64(offset)
+            .i64(-1)
+            .i32(1 << 20)
+            .i32(0) // forgotten topics
+            .string(""); // rack
+        let response = self.call(FETCH, 11, body).await;
+        let mut r = Resp(&response);
+        assert_eq!(r.i32(), 0, "throttle");
+        assert_eq!(r.i16(), 0, "top-level error");
+        assert_eq!(r.i32(), 0, "session id");
+        assert_eq!(r.i32(), 1);
+        assert_eq!(r.string().as_deref(), Some(topic));
+        assert_eq!(r.i32(), 1);
+        assert_eq!(r.i32(), 0);
+        let error = r.i16();
+        let high_watermark = r.i64();
+        assert_eq!(r.i64(), high_watermark, "last stable offset");
+        let _log_start_offset = r.i64();
+        assert_eq!(r.i32(), 0, "aborted transactions");
+        assert_eq!(r.i32(), -1, "preferred read replica");
+        let records = decode_record_batches(&r.bytes());
+        r.done();
+        (error, high_watermark, records)
+    }
+}
+
+async fn start_listener() -> u16 {
+    let core = Arc::new(flashq_cluster::FlashQ::new());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let kafka = KafkaListener::new(core, NODE_ID, "127.0.0.1", port);
+    tokio::spawn(kafka.serve(listener));
+    port
+}
+
+#[tokio::test]
+async fn test_client_id_quota_throttles_produce() {
+    let quotas = Arc::new(QuotaManager::in_memory());
+    quotas
+        .set_quota(
+            QuotaEntity::new(QuotaEntityType::ClientId, "kafka-test"),
+            QuotaConfig {
+                produce_bytes_per_sec: Some(1000),
+                ..QuotaConfig::default()
+            },
+        )
+        .unwrap();
+    let core = Arc::new(flashq_cluster::FlashQ::new());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let kafka = KafkaListener::new(core, NODE_ID, "127.0.0.1", port).with_quotas(quotas);
+    tokio::spawn(kafka.serve(listener));
+    let mut client = KafkaClient::connect(port).await;
+
+    let payload = "x".repeat(2000);
+    let (error, _, throttle_time_ms) = client
+        .produce_throttled("orders", 0, &record_batch(1_700_000_000_000, &[&payload]))
+        .await;
+    assert_eq!((error, throttle_time_ms), (0, 0));
+
+    let started = Instant::now();
+    let (error, base_offset, throttle_time_ms) = client
+        .produce_throttled("orders", 0, &record_batch(1_700_000_000_000, &["y"]))
+        .await;
+    assert_eq!((error, base_offset), (0, 1));
+    assert!(throttle_time_ms >= 700, "throttle {throttle_time_ms} ms");
+    assert!(
+        started.elapsed() >= Duration::from_millis(700),
+        "second produce was not held back: {:?}",
+        started.elapsed()
+    );
+}
+
+#[tokio::test]
+async fn test_api_versions_advertises_supported_apis() {
+    let port = start_listener().await;
+    let mut client = KafkaClient::connect(port).await;
+
+    let response = client.call(API_VERSIONS, 2, Req::default()).await;
+    let mut r = Resp(&response);
+    assert_eq!(r.i16(), 0);
+    let apis: Vec<(i16, i16, i16)> = (0..r.i32()).map(|_| (r.i16(), r.i16(), r.i16())).collect();
+    assert_eq!(r.i32(), 0, "throttle");
+    r.done();
+    for api in [
+        (PRODUCE, 3, 8),
+        (FETCH, 4, 11),
+        (LIST_OFFSETS, 1, 5),
+        (METADATA, 0, 8),
+        (OFFSET_COMMIT, 2, 7),
+        (OFFSET_FETCH, 1, 5),
+        (FIND_COORDINATOR, 0, 2),
+        (API_VERSIONS, 0, 2),
+    ] {
+        assert!(apis.contains(&api), "{api:?} missing from {apis:?}");
+    }
+
+    // Newer clients open with a flexible version and are told to fall back
+    let response = client.call(API_VERSIONS, 3, Req::default()).await;
+    let mut r = Resp(&response);
+    assert_eq!(r.i16(), 35, "UNSUPPORTED_VERSION");
+    assert_eq!(r.i32() as usize, apis.len());
+}
+
+#[tokio::test]
+async fn test_metadata_and_find_coordinator_point_at_listener() {
+    let port = start_listener().await;
+    let mut client = KafkaClient::connect(port).await;
+
+    let body = Req::default()
+        .i32(2)
+        .string("orders")
+        .string("__consumer_offsets")
+        .i8(1) // allow auto topic creation
+        .i8(0)
+        .i8(0);
+    let response = client.call(METADATA, 8, body).await;
+    let mut r = Resp(&response);
+    assert_eq!(r.i32(), 0, "throttle");
+    assert_eq!(r.i32(), 1, "brokers");
+    assert_eq!(r.i32(), NODE_ID);
+    assert_eq!(r.string().as_deref(), Some("127.0.0.1"));
+    assert_eq!(r.i32(), i32::from(port));
+    assert_eq!(r.string(), None, "rack");
+    assert_eq!(r.string().as_deref(), Some("flashq"));
+    assert_eq!(r.i32(), NODE_ID, "controller");
+    assert_eq!(r.i32(), 2, "topics");
+
+    assert_eq!(r.i16(), 0);
+    assert_eq!(r.string().as_deref(), Some("orders"));
+    assert_eq!(r.i8(), 0, "is_internal");
+    assert_eq!(r.i32(), 1, "partitions");
+    assert_eq!((r.i16(), r.i32(), r.i32(), r.i32()), (0, 0, NODE_ID, 0));
+    assert_eq!((r.i32(), r.i32()), (1, NODE_ID), "replicas");
+    assert_eq!((r.i32(), r.i32()), (1, NODE_ID), "isr");
+    assert_eq!(r.i32(), 0, "offline replicas");
+    let _topic_operations = r.i32();
+
+    assert_eq!(r.i16(), 17, "INVALID_TOPIC_EXCEPTION");
+    assert_eq!(r.string().as_deref(), Some("__consumer_offsets"));
+    r.i8();
+    assert_eq!(r.i32(), 0, "partitions");
+    r.i32();
+    r.i32();
+    r.done();
+
+    let response = client
+        .call(FIND_COORDINATOR, 2, Req::default().string("billing").i8(0))
+        .await;
+    let mut r = Resp(&response);
+    assert_eq!(r.i32(), 0, "throttle");
+    assert_eq!(r.i16(), 0);
+    assert_eq!(r.string(), None);
+    assert_eq!(r.i32(), NODE_ID);
+    assert_eq!(r.string().as_deref(), Some("127.0.0.1"));
+    assert_eq!(r.i32(), i32::from(port));
+    r.done();
+}
+
+#[tokio::test]
+async fn test_produce_then_fetch_round_trips_records() {
+    let port = start_listener().await;
+    let mut client = KafkaClient::connect(port).await;
+    let timestamp = 1_700_000_000_000;
+
+    let (error, base_offset) = client
+        .produce("orders", 0, &record_batch(timestamp, &["created", "paid"]))
+        .await;
+    assert_eq!((error, base_offset), (0, 0));
+    let (error, base_offset) = client
+        .produce("orders", 0, &record_batch(timestamp + 10, &["shipped"]))
+        .await;
+    assert_eq!((error, base_offset), (0, 2));
+
+    let (error, high_watermark, records) = client.fetch("orders", 1, 0).await;
+    assert_eq!((error, high_watermark), (0, 3));
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].offset, 1);
+    assert_eq!(records[0].value, "paid");
+    assert_eq!(records[0].key.as_deref(), Some("key-1"));
+    assert_eq!(records[0].timestamp, timestamp + 1);
+    assert_eq!(
+        records[0].headers,
+        vec![("source".to_string(), b"kafka-test".to_vec())]
+    );
+    assert_eq!(
+        (records[1].offset, records[1].value.as_str()),
+        (2, "shipped")
+    );
+
+    let (error, _, records) = client.fetch("orders", 5, 0).await;
+    assert_eq!(error, 1, "OFFSET_OUT_OF_RANGE");
+    assert!(records.is_empty());
+}
+
+#[tokio::test]
+async fn test_fetch_waits_for_new_records() {
+    let port = start_listener().await;
+    let mut consumer = KafkaClient::connect(port).await;
+    let mut producer = KafkaClient::connect(port).await;
+
+    let started = Instant::now();
+    let fetch = tokio::spawn(async move { consumer.fetch("events", 0, 5000).await });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    producer
+        .produce("events", 0, &record_batch(0, &["late"]))
+        .await;
+
+    let (error, _, records) = fetch.await.unwrap();
+    assert_eq!(error, 0);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].value, "late");
+    assert!(started.elapsed() < Duration::from_secs(4));
+}
+
+#[tokio::test]
+async fn test_produce_rejects_bad_batches_and_partitions() {
+    let port = start_listener().await;
+    let mut client = KafkaClient::connect(port).await;
+
+    let mut corrupt = record_batch(0, &["a"]);
+    let last = corrupt.len() - 1;
+    corrupt[last] ^= 0xff;
+    assert_eq!(
+        client.produce("orders", 0, &corrupt).await.0,
+        2,
+        "CORRUPT_MESSAGE"
+    );
+    assert_eq!(
+        client
+            .produce("orders", 1, &record_batch(0, &["a"]))
+            .await
+            .0,
+        3,
+        "UNKNOWN_TOPIC_OR_PARTITION"
+    );
+    assert_eq!(
+        client
+            .produce("bad topic", 0, &record_batch(0, &["a"]))
+            .await
+            .0,
+        17,
+        "INVALID_TOPIC_EXCEPTION"
+    );
+
+    // acks=0 gets no response, so the next response answers the next request
+    let body = Req::default()
+        .i16(-1)
+        .i16(0)
+        .i32(5000)
+        .i32(1)
+        .string("orders")
+        .i32(1)
+        .i32(0)
+        .bytes(&record_batch(0, &["fire-and-forget"]));
+    client.send(PRODUCE, 3, body).await;
+    let next = client.send(API_VERSIONS, 0, Req::default()).await;
+    assert_eq!(client.receive().await.0, next);
+    let (_, high_watermark, _) = client.fetch("orders", 0, 0).await;
+    assert_eq!(high_watermark, 1);
+}
+
+#[tokio::test]
+async fn test_draining_refuses_produce_and_shutdown_closes_connections() {
+    let core = Arc::new(flashq_cluster::FlashQ::new());
+    let lifecycle = Lifecycle::new();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let kafka =
+        KafkaListener::new(core, NODE_ID, "127.0.0.1", port).with_lifecycle(lifecycle.clone());
+    let serving = tokio::spawn(kafka.serve(listener));
+    let mut client = KafkaClient::connect(port).await;
+    assert_eq!(
+        client
+            .produce("orders", 0, &record_batch(0, &["kept"]))
+            .await
+            .0,
+        0
+    );
+
+    lifecycle.drain();
+    assert_eq!(
+        client
+            .produce("orders", 0, &record_batch(0, &["refused"]))
+            .await
+            .0,
+        6,
+        "NOT_LEADER_OR_FOLLOWER"
+    );
+    let (error, high_watermark, records) = client.fetch("orders", 0, 0).await;
+    assert_eq!((error, high_watermark, records.len()), (0, 1, 1));
+
+    lifecycle.shutdown();
+    tokio::time::timeout(Duration::from_secs(5), serving)
+        .await
+        .expect("listener stops")
+        .unwrap()
+        .unwrap();
+    let mut buf = [0u8; 1];
+    assert_eq!(client.stream.read(&mut buf).await.unwrap(), 0, "closed");
+}
+
+#[tokio::test]
+async fn test_list_offsets_and_group_offsets() {
+    let port = start_listener().await;
+    let mut client = KafkaClient::connect(port).await;
+    let timestamp = 1_700_000_000_000;
+    client
+        .produce("orders", 0, &record_batch(timestamp, &["a", "b", "c"]))
+        .await;
+
+    let list = |timestamp: i64| {
+        Req::default()
+            .i32(-1)
+            .i32(1)
+            .string("orders")
+            .i32(1)
+            .i32(0)
+            .i64(timestamp)
+    };
+    for (query, expected) in [
+        (-2, (-1, 0)),
+        (-1, (-1, 3)),
+        (timestamp + 1, (timestamp + 1, 1)),
+    ] {
+        let response = client.call(LIST_OFFSETS, 1, list(query)).await;
+        let mut r = Resp(&response);
+        assert_eq!(r.i32(), 1);
+        assert_eq!(r.string().as_deref(), Some("orders"));
+        assert_eq!(r.i32(), 1);
+        assert_eq!(r.i32(), 0);
+        assert_eq!(r.i16(), 0);
+        assert_eq!((r.i64(), r.i64()), expected, "timestamp {query}");
+        r.done();
+    }
+
+    let commit = Req::default()
+        .string("billing")
+        .i32(-1)
+        .string("")
+        .i64(-1)
+        .i32(1)
+        .string("orders")
+        .i32(1)
+        .i32(0)
+        .i64(2)
+        .string("");
+    let response = client.call(OFFSET_COMMIT, 2, commit).await;
+    let mut r = Resp(&response);
+    assert_eq!(r.i32(), 1);
+    assert_eq!(r.string().as_deref(), Some("orders"));
+    assert_eq!((r.i32(), r.i32(), r.i16()), (1, 0, 0));
+    r.done();
+
+    let fetch = Req::default()
+        .string("billing")
+        .i32(2)
+        .string("orders")
+        .i32(1)
+        .i32(0)
+        .string("payments")
+        .i32(1)
+        .i32(0);
+    let response = client.call(OFFSET_FETCH, 1, fetch).await;
+    let mut r = Resp(&response);
+    assert_eq!(r.i32(), 2);
+    for expected in [2, -1] {
+        r.string();
+        assert_eq!(r.i32(), 1);
+        assert_eq!(r.i32(), 0);
+        assert_eq!(r.i64(), expected);
+        r.string();
+        assert_eq!(r.i16(), 0);
+    }
+    r.done();
+}
@@ -0,0 +1,142 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::test_utilities::{FlashQBroker, TestServer};
+use flashq_broker::flashq::v1 as proto;
+use flashq_broker::flashq::v1::consumer_server::Consumer;
+use flashq_broker::flashq::v1::producer_server::Producer;
+use flashq_client::FlashqClient;
+use flashq_cluster::ClusterBroker;
+use tonic::{Code, Request};
+
+fn record(value: &str) -> proto::Record {
+    proto::Record {
+        key: String::new(),
+        value: value.to_string(),
+        headers: Default::default(),
+        timestamp: String::new(),
+    }
+}
+
+fn produce_request(topic: &str, value: &str) -> proto::ProduceRequest {
+    proto::ProduceRequest {
+        topic: topic.to_string(),
+        records: vec![record(value)],
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_draining_broker_rejects_produce_but_serves_fetch() {
+    let broker = FlashQBroker::new(Arc::new(flashq_cluster::FlashQ::new()));
+    broker
+        .produce(Request::new(produce_request("drain-topic", "before")))
+        .await
+        .unwrap();
+    broker
+        .create_consumer_group(Request::new(proto::ConsumerGroupId {
+            group_id: "drain-group".into(),
+        }))
+        .await
+        .unwrap();
+
+    broker.initiate_drain().await.unwrap();
+    assert!(broker.is_draining());
+
+    let err = broker
+        .produce(Request::new(produce_request("drain-topic", "after")))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::Unavailable);
+
+    let fetched = broker
+        .fetch_by_offset(Request::new(proto::FetchByOffsetRequest {
+            group_id: "drain-group".into(),
+            topic: "drain-topic".into(),
+            from_offset: 0,
+            max_records: 10,
+            include_headers: false,
+            ..Default::default()
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(fetched.records.len(), 1);
+    assert_eq!(fetched.records[0].record.as_ref().unwrap().value, "before");
+}
+
+#[tokio::test]
+async fn test_sigterm_closes_subscriptions_and_persists_data() {
+    let data_dir = tempfile::Builder::new()
+        .prefix("flashq_lifecycle_test_")
+        .tempdir()
+        .unwrap();
+    let mut srv = TestServer::start_with_data_dir(data_dir.path())
+        .await
+        .expect("start server");
+    let client = FlashqClient::connect(format!("http://127.0.0.1:{}", srv.port))
+        .await
+        .unwrap();
+
+    client
+        .producer()
+        .produce(produce_request("lifecycle-topic", "kept"))
+        .await
+        .unwrap();
+    client
+        .consumer()
+        .create_consumer_group(proto::ConsumerGroupId {
+            group_id: "lifecycle-group".into(),
+        })
+        .await
+        .unwrap();
+    let mut stream = client
+        .consumer()
+        .subscribe(proto::FetchByOffsetRequest {
+            group_id: "lifecycle-group".into(),
+            topic: "lifecycle-topic".into(),
+            from_offset: 0,
+            max_records: 10,
+            include_headers: false,
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let first = stream.message().await.unwrap().expect("record");
+    assert_eq!(first.record.unwrap().value, "kept");
+    client
+        .consumer()
+        .commit_offset(proto::CommitOffsetRequest {
+            group_id: "lifecycle-group".into(),
+            topic: "lifecycle-topic".into(),
+            offset: 1,
+        })
+        .await
+        .unwrap();
+
+    let status = srv.terminate().await.expect("broker exits");
+    assert!(status.success(), "broker exited with {status}");
+    // The stream ends with an OK status rather than a transport error
+    let end = tokio::time::timeout(Duration::from_secs(5), stream.message())
+        .await
+        .expect("stream closes");
+    assert!(matches!(end, Ok(None)), "unexpected stream end: {end:?}");
+
+    let srv = TestServer::start_with_data_dir(data_dir.path())
+        .await
+        .expect("restart server");
+    let client = FlashqClient::connect(format!("http://127.0.0.1:{}", srv.port))
+        .await
+        .unwrap();
+    let offset = client
+        .consumer()
+        .get_consumer_group_offset(proto::GetOffsetRequest {
+            group_id: "lifecycle-group".into(),
+            topic: "lifecycle-topic".into(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(offset.offset, 1);
+}
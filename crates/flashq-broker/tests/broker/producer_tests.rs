@@ -16,22 +16,26 @@ async fn test_produce_and_fetch_offset() {
             key: "k1".into(),
             value: "v1".into(),
             headers: Default::default(),
+            timestamp: String::new(),
         },
         proto::Record {
             key: "".into(),
             value: "v2".into(),
             headers: Default::default(),
+            timestamp: String::new(),
         },
         proto::Record {
             key: "k3".into(),
             value: "v3".into(),
             headers: Default::default(),
+            timestamp: String::new(),
         },
     ];
     let resp = producer
         .produce(proto::ProduceRequest {
             topic: topic.clone(),
             records: recs,
+            ..Default::default()
         })
         .await
         .expect("produce")
@@ -57,6 +61,8 @@ async fn test_produce_and_fetch_offset() {
             from_offset: 0, // use committed; initially 0
             max_records: 10,
             include_headers: true,
+            to_offset: 0,
+            to_time: String::new(),
         })
         .await
         .expect("fetch")
@@ -66,3 +72,119 @@ async fn test_produce_and_fetch_offset() {
     assert_eq!(fetched.next_offset, 3);
     assert!(fetched.high_water_mark >= 3);
 }
+
+#[tokio::test]
+async fn test_headers_keep_order_repeats_and_binary_values() {
+    let srv = TestServer::start().await.expect("start server");
+    let addr = format!("http://127.0.0.1:{}", srv.port);
+    let mut producer = proto::producer_client::ProducerClient::connect(addr.clone())
+        .await
+        .expect("connect producer");
+    let headers = vec![
+        proto::Header {
+            key: "trace".into(),
+            value: vec![0x00, 0xff, 0x10],
+        },
+        proto::Header {
+            key: "schema".into(),
+            value: b"7".to_vec(),
+        },
+        proto::Header {
+            key: "trace".into(),
+            value: b"second".to_vec(),
+        },
+    ];
+    producer
+        .produce(proto::ProduceRequest {
+            topic: "headers-topic".into(),
+            records: vec![proto::Record {
+                key: String::new(),
+                value: "v".into(),
+                headers: headers.clone(),
+                timestamp: String::new(),
+            }],
+            ..Default::default()
+        })
+        .await
+        .expect("produce");
+
+    let mut consumer = proto::consumer_client::ConsumerClient::connect(addr)
+        .await
+        .expect("connect consumer");
+    consumer
+        .create_consumer_group(proto::ConsumerGroupId {
+            group_id: "headers-group".into(),
+        })
+        .await
+        .expect("create group");
+    let fetched = consumer
+        .fetch_by_offset(proto::FetchByOffsetRequest {
+            group_id: "headers-group".into(),
+            topic: "headers-topic".into(),
+            from_offset: 0,
+            max_records: 10,
+            include_headers: true,
+            to_offset: 0,
+            to_time: String::new(),
+        })
+        .await
+        .expect("fetch")
+        .into_inner();
+
+    let record = fetched.records[0].record.as_ref().expect("record");
+    assert_eq!(record.headers, headers);
+}
+
+#[tokio::test]
+async fn test_malformed_record_timestamp_is_rejected() {
+    let srv = TestServer::start().await.expect("start server");
+    let addr = format!("http://127.0.0.1:{}", srv.port);
+    let mut producer = proto::producer_client::ProducerClient::connect(addr)
+        .await
+        .expect("connect producer");
+
+    let status = producer
+        .produce(proto::ProduceRequest {
+            topic: "timestamp-topic".into(),
+            records: vec![proto::Record {
+                key: String::new(),
+                value: "v".into(),
+                headers: Default::default(),
+                timestamp: "yesterday".into(),
+            }],
+            ..Default::default()
+        })
+        .await
+        .expect_err("malformed timestamp must be rejected");
+
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    assert!(status.message().contains("index 0"));
+}
+
+#[test]
+fn test_map_form_headers_decode_as_header_list() {
+    use prost::Message;
+
+    // A Record as encoded by clients built against `map<string, string> headers = 3`
+    #[derive(Clone, PartialEq, Message)]
+    struct MapFormRecord {
+        #[prost(string, tag = "2")]
+        value: String,
+        #[prost(btree_map = "string, string", tag = "3")]
+        headers: std::collections::BTreeMap<String, String>,
+    }
+    let legacy = MapFormRecord {
+        value: "v".into(),
+        headers: [("a".to_string(), "1".to_string())].into(),
+    };
+
+    let record = proto::Record::decode(legacy.encode_to_vec().as_slice()).unwrap();
+
+    assert_eq!(
+        record.headers,
+        vec![proto::Header {
+            key: "a".into(),
+            value: b"1".to_vec(),
+        }]
+    );
+}
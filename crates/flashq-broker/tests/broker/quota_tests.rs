@@ -0,0 +1,180 @@
+use std::time::{Duration, Instant};
+
+use crate::test_utilities::TestServer;
+use flashq_broker::flashq::v1 as proto;
+use flashq_client::FlashqClient;
+
+async fn client(srv: &TestServer) -> FlashqClient {
+    FlashqClient::connect(format!("http://127.0.0.1:{}", srv.port))
+        .await
+        .expect("connect")
+}
+
+async fn set_quota(
+    client: &FlashqClient,
+    entity_type: proto::QuotaEntityType,
+    name: &str,
+    config: proto::QuotaConfig,
+) {
+    client
+        .admin()
+        .set_quota(proto::SetQuotaRequest {
+            entity: Some(proto::QuotaEntity {
+                entity_type: entity_type as i32,
+                name: name.to_string(),
+            }),
+            config: Some(config),
+        })
+        .await
+        .expect("set quota");
+}
+
+async fn produce(client: &FlashqClient, topic: &str, value: &str) -> Result<(), tonic::Status> {
+    client
+        .producer()
+        .produce(proto::ProduceRequest {
+            topic: topic.to_string(),
+            records: vec![proto::Record {
+                key: String::new(),
+                value: value.to_string(),
+                headers: Default::default(),
+                timestamp: String::new(),
+            }],
+            ..Default::default()
+        })
+        .await
+        .map(|_| ())
+}
+
+#[tokio::test]
+async fn test_request_quota_rejects_client_id() {
+    let srv = TestServer::start_with_args(&["--quota-enforcement", "reject"])
+        .await
+        .expect("start server");
+    let admin = client(&srv).await;
+    set_quota(
+        &admin,
+        proto::QuotaEntityType::ClientId,
+        "loader",
+        proto::QuotaConfig {
+            requests_per_sec: Some(1),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    let loader = client(&srv).await.with_client_id("loader").unwrap();
+    produce(&loader, "quota-topic", "one").await.expect("first");
+    produce(&loader, "quota-topic", "two")
+        .await
+        .expect("second");
+    let status = produce(&loader, "quota-topic", "three").await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+    assert!(status.message().contains("client id 'loader'"));
+
+    // Calls without the client id are not charged to it
+    produce(&admin, "quota-topic", "other")
+        .await
+        .expect("unlimited client");
+}
+
+#[tokio::test]
+async fn test_produce_byte_quota_throttles_topic() {
+    let srv = TestServer::start().await.expect("start server");
+    let admin = client(&srv).await;
+    set_quota(
+        &admin,
+        proto::QuotaEntityType::Topic,
+        "slow-topic",
+        proto::QuotaConfig {
+            produce_bytes_per_sec: Some(1000),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    let payload = "x".repeat(2000);
+    produce(&admin, "slow-topic", &payload).await.unwrap();
+    let started = Instant::now();
+    produce(&admin, "slow-topic", "y").await.unwrap();
+    assert!(
+        started.elapsed() >= Duration::from_millis(700),
+        "second produce was not throttled: {:?}",
+        started.elapsed()
+    );
+
+    let started = Instant::now();
+    produce(&admin, "fast-topic", &payload).await.unwrap();
+    assert!(started.elapsed() < Duration::from_millis(700));
+}
+
+#[tokio::test]
+async fn test_quota_admin_rpcs_list_and_delete() {
+    let srv = TestServer::start().await.expect("start server");
+    let admin = client(&srv).await;
+    let config = proto::QuotaConfig {
+        fetch_bytes_per_sec: Some(4096),
+        ..Default::default()
+    };
+    set_quota(&admin, proto::QuotaEntityType::Principal, "*", config).await;
+
+    let quotas = admin
+        .admin()
+        .list_quotas(proto::Empty {})
+        .await
+        .unwrap()
+        .into_inner()
+        .quotas;
+    assert_eq!(quotas.len(), 1);
+    assert_eq!(quotas[0].config, Some(config));
+    assert_eq!(quotas[0].entity.as_ref().unwrap().name, "*");
+
+    let entity = quotas[0].entity.clone();
+    let deleted = admin
+        .admin()
+        .delete_quota(proto::DeleteQuotaRequest {
+            entity: entity.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(deleted.deleted);
+    let deleted = admin
+        .admin()
+        .delete_quota(proto::DeleteQuotaRequest { entity })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!deleted.deleted);
+
+    let status = admin
+        .admin()
+        .set_quota(proto::SetQuotaRequest {
+            entity: Some(proto::QuotaEntity {
+                entity_type: proto::QuotaEntityType::Unspecified as i32,
+                name: "alice".to_string(),
+            }),
+            config: None,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    // A zero rate would block the entity outright rather than limit it
+    let status = admin
+        .admin()
+        .set_quota(proto::SetQuotaRequest {
+            entity: Some(proto::QuotaEntity {
+                entity_type: proto::QuotaEntityType::Topic as i32,
+                name: "events".to_string(),
+            }),
+            config: Some(proto::QuotaConfig {
+                requests_per_sec: Some(0),
+                ..Default::default()
+            }),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    assert!(status.message().contains("request rate"));
+}
@@ -0,0 +1,622 @@
+//! Contract tests for the REST gateway: every response is checked against the schema
+//! `docs/openapi.yaml` declares for its path, method and status.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::Router;
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use flashq_broker::lifecycle::Lifecycle;
+use flashq_broker::quota::{
+    QuotaConfig, QuotaEnforcement, QuotaEntity, QuotaEntityType, QuotaManager,
+};
+use http_body_util::BodyExt;
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+const SPEC: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../docs/openapi.yaml"
+));
+
+/// The OpenAPI document, with just enough schema support to check FlashQ's responses.
+struct Contract {
+    spec: Value,
+}
+
+impl Contract {
+    fn load() -> Self {
+        let spec: serde_yaml::Value = serde_yaml::from_str(SPEC).expect("parse openapi.yaml");
+        Self {
+            spec: serde_json::to_value(spec).expect("openapi.yaml as JSON"),
+        }
+    }
+
+    fn resolve<'a>(&'a self, node: &'a Value) -> &'a Value {
+        match node.get("$ref").and_then(Value::as_str) {
+            Some(pointer) => self.resolve(
+                self.spec
+                    .pointer(pointer.trim_start_matches('#'))
+                    .unwrap_or_else(|| panic!("unresolved $ref {pointer}")),
+            ),
+            None => node,
+        }
+    }
+
+    fn operation(&self, method: &Method, path: &str) -> &Value {
+        self.spec["paths"][path]
+            .get(method.as_str().to_lowercase())
+            .unwrap_or_else(|| panic!("{method} {path} is not in the spec"))
+    }
+
+    /// Check `body` against the JSON schema the spec gives for this response.
+    fn check(&self, method: &Method, path: &str, status: StatusCode, body: &Value) {
+        let responses = &self.operation(method, path)["responses"];
+        let response = responses
+            .get(status.as_str())
+            .unwrap_or_else(|| panic!("{method} {path} does not document status {status}"));
+        let response = self.resolve(response);
+        if let Some(schema) = response.pointer("/content/application~1json/schema") {
+            if let Err(e) = self.validate(schema, body, "$", true) {
+                panic!("{method} {path} {status} breaks the contract: {e}\n{body:#}");
+            }
+        }
+    }
+
+    /// Check `value` against `schema`. Objects may only carry documented properties when
+    /// `strict`; the parts of an `allOf` are checked loosely, and the whole strictly.
+    fn validate(
+        &self,
+        schema: &Value,
+        value: &Value,
+        at: &str,
+        strict: bool,
+    ) -> Result<(), String> {
+        let schema = self.resolve(schema);
+        if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+            for part in parts {
+                self.validate(part, value, at, false)?;
+            }
+        }
+        let matches_type = match schema.get("type").and_then(Value::as_str) {
+            Some("object") => value.is_object(),
+            Some("array") => value.is_array(),
+            Some("string") => value.is_string(),
+            Some("integer") => value.is_u64() || value.is_i64(),
+            Some("boolean") => value.is_boolean(),
+            _ => true,
+        };
+        if !matches_type {
+            return Err(format!("{at} is not of type {}", schema["type"]));
+        }
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(value) {
+                return Err(format!("{at} is not one of {allowed:?}"));
+            }
+        }
+        if let (Some(minimum), Some(n)) = (
+            schema.get("minimum").and_then(Value::as_f64),
+            value.as_f64(),
+        ) {
+            if n < minimum {
+                return Err(format!("{at} is below {minimum}"));
+            }
+        }
+        if let Some(object) = value.as_object() {
+            for name in self.required(schema) {
+                if !object.contains_key(&name) {
+                    return Err(format!("{at} is missing required property '{name}'"));
+                }
+            }
+            let documented = self.properties(schema);
+            let extra = self
+                .resolve(schema)
+                .get("additionalProperties")
+                .filter(|extra| extra.is_object());
+            for (name, field) in object {
+                match (documented.get(name.as_str()), extra) {
+                    (Some(property), _) => {
+                        self.validate(property, field, &format!("{at}.{name}"), true)?
+                    }
+                    (None, Some(extra)) => {
+                        self.validate(extra, field, &format!("{at}.{name}"), true)?
+                    }
+                    (None, None) if strict && !documented.is_empty() => {
+                        return Err(format!("{at} has undocumented property '{name}'"));
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+        if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+            for (i, item) in array.iter().enumerate() {
+                self.validate(items, item, &format!("{at}[{i}]"), true)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn required(&self, schema: &Value) -> Vec<String> {
+        let schema = self.resolve(schema);
+        let mut required: Vec<String> = schema["required"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|name| name.as_str().map(str::to_string))
+            .collect();
+        for part in schema["allOf"].as_array().into_iter().flatten() {
+            required.extend(self.required(part));
+        }
+        required
+    }
+
+    fn properties<'a>(&'a self, schema: &'a Value) -> serde_json::Map<String, Value> {
+        let schema = self.resolve(schema);
+        let mut properties = schema["properties"]
+            .as_object()
+            .cloned()
+            .unwrap_or_default();
+        for part in schema["allOf"].as_array().into_iter().flatten() {
+            properties.extend(self.properties(part));
+        }
+        properties
+    }
+}
+
+struct Gateway {
+    app: Router,
+    contract: Contract,
+}
+
+impl Gateway {
+    fn new() -> Self {
+        Self {
+            app: flashq_broker::rest::router(Arc::new(flashq::FlashQ::new())),
+            contract: Contract::load(),
+        }
+    }
+
+    fn with_quotas(quotas: Arc<QuotaManager>) -> Self {
+        Self {
+            app: flashq_broker::rest::router_with_quotas(
+                Arc::new(flashq::FlashQ::new()),
+                Lifecycle::new(),
+                quotas,
+            ),
+            contract: Contract::load(),
+        }
+    }
+
+    fn with_lifecycle(lifecycle: Lifecycle) -> Self {
+        Self {
+            app: flashq_broker::rest::router_with_lifecycle(
+                Arc::new(flashq::FlashQ::new()),
+                lifecycle,
+            ),
+            contract: Contract::load(),
+        }
+    }
+
+    async fn send(&self, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+        let request = Request::builder().method(method).uri(uri);
+        let request = match body {
+            Some(body) => request
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+        let response = self.app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                panic!("{uri} answered {status} with a non-JSON body ({e}): {bytes:?}")
+            })
+        };
+        (status, body)
+    }
+
+    /// Send a request and check the response against the spec's `path` template.
+    async fn call(
+        &self,
+        method: Method,
+        path: &str,
+        uri: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let (status, response) = self.send(method.clone(), uri, body).await;
+        self.contract.check(&method, path, status, &response);
+        (status, response)
+    }
+
+    async fn produce(&self, topic: &str, records: Value) -> (StatusCode, Value) {
+        self.call(
+            Method::POST,
+            "/topic/{topic}/record",
+            &format!("/topic/{topic}/record"),
+            Some(json!({ "records": records })),
+        )
+        .await
+    }
+
+    async fn create_group(&self, group: &str) -> StatusCode {
+        self.call(
+            Method::POST,
+            "/consumer/{group-id}",
+            &format!("/consumer/{group}"),
+            None,
+        )
+        .await
+        .0
+    }
+}
+
+const FETCH_BY_OFFSET: &str = "/consumer/{group-id}/topic/{topic}/record/offset";
+const FETCH_BY_TIME: &str = "/consumer/{group-id}/topic/{topic}/record/time";
+const OFFSET: &str = "/consumer/{group-id}/topic/{topic}/offset";
+
+#[tokio::test]
+async fn test_every_spec_operation_is_served() {
+    let gateway = Gateway::new();
+    let paths = gateway.contract.spec["paths"].as_object().unwrap().clone();
+    for (path, operations) in paths {
+        let uri = path
+            .replace("{topic}", "orders")
+            .replace("{group-id}", "missing-group");
+        for method in operations.as_object().unwrap().keys() {
+            let method = Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+            let (status, body) = gateway.send(method.clone(), &uri, None).await;
+            assert_ne!(status, StatusCode::METHOD_NOT_ALLOWED, "{method} {path}");
+            assert_ne!(body["error"], "not_found", "{method} {path} is not routed");
+        }
+    }
+
+    let (status, body) = gateway.send(Method::GET, "/no/such/endpoint", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"], "not_found");
+}
+
+#[tokio::test]
+async fn test_health_and_topics_match_contract() {
+    let gateway = Gateway::new();
+    gateway.produce("orders", json!([{ "value": "a" }])).await;
+    gateway.create_group("billing").await;
+
+    let (status, health) = gateway.call(Method::GET, "/health", "/health", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(health["status"], "healthy");
+    assert_eq!(health["topics_count"], 1);
+    assert_eq!(health["consumer_groups_count"], 1);
+
+    let (_, topics) = gateway.call(Method::GET, "/topics", "/topics", None).await;
+    assert_eq!(topics["topics"], json!(["orders"]));
+}
+
+#[tokio::test]
+async fn test_produce_and_fetch_by_offset_match_contract() {
+    let gateway = Gateway::new();
+    let (status, produced) = gateway
+        .produce(
+            "orders",
+            json!([
+                { "key": "user-1", "value": "created", "headers": { "source": "web" } },
+                { "value": "paid" },
+                { "value": "shipped" }
+            ]),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(produced["offset"], 2);
+
+    assert_eq!(gateway.create_group("billing").await, StatusCode::CREATED);
+    assert_eq!(gateway.create_group("billing").await, StatusCode::OK);
+
+    let (status, fetched) = gateway
+        .call(
+            Method::GET,
+            FETCH_BY_OFFSET,
+            "/consumer/billing/topic/orders/record/offset?max_records=2",
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let records = fetched["records"].as_array().unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["key"], "user-1");
+    assert_eq!(records[0]["headers"], json!({ "source": "web" }));
+    assert_eq!(records[1]["offset"], 1);
+    assert_eq!(fetched["next_offset"], 2);
+    assert_eq!(fetched["high_water_mark"], 3);
+    assert_eq!(fetched["lag"], 1);
+
+    let (_, fetched) = gateway
+        .call(
+            Method::GET,
+            FETCH_BY_OFFSET,
+            "/consumer/billing/topic/orders/record/offset?from_offset=0&include_headers=false",
+            None,
+        )
+        .await;
+    assert_eq!(fetched["records"].as_array().unwrap().len(), 3);
+    assert!(fetched["records"][0].get("headers").is_none());
+}
+
+#[tokio::test]
+async fn test_fetch_by_time_matches_contract() {
+    let gateway = Gateway::new();
+    gateway.produce("orders", json!([{ "value": "old" }])).await;
+    gateway.create_group("billing").await;
+
+    let (status, fetched) = gateway
+        .call(
+            Method::GET,
+            FETCH_BY_TIME,
+            "/consumer/billing/topic/orders/record/time?from_time=2000-01-01T00:00:00Z",
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fetched["records"][0]["value"], "old");
+    assert_eq!(fetched["next_offset"], 1);
+
+    let (status, error) = gateway
+        .call(
+            Method::GET,
+            FETCH_BY_TIME,
+            "/consumer/billing/topic/orders/record/time?from_time=yesterday",
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_parameter");
+}
+
+#[tokio::test]
+async fn test_offset_commit_and_lookup_match_contract() {
+    let gateway = Gateway::new();
+    gateway
+        .produce("orders", json!([{ "value": "a" }, { "value": "b" }]))
+        .await;
+    gateway.create_group("billing").await;
+
+    let (status, committed) = gateway
+        .call(
+            Method::POST,
+            OFFSET,
+            "/consumer/billing/topic/orders/offset",
+            Some(json!({ "offset": 1, "metadata": "batch 1" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(committed["committed_offset"], 1);
+
+    let (_, offset) = gateway
+        .call(
+            Method::GET,
+            OFFSET,
+            "/consumer/billing/topic/orders/offset",
+            None,
+        )
+        .await;
+    assert_eq!(offset["committed_offset"], 1);
+    assert_eq!(offset["high_water_mark"], 2);
+    assert_eq!(offset["lag"], 1);
+
+    // Fetches resume from the committed offset
+    let (_, fetched) = gateway
+        .call(
+            Method::GET,
+            FETCH_BY_OFFSET,
+            "/consumer/billing/topic/orders/record/offset",
+            None,
+        )
+        .await;
+    assert_eq!(fetched["records"][0]["value"], "b");
+}
+
+#[tokio::test]
+async fn test_errors_match_contract() {
+    let gateway = Gateway::new();
+
+    let (status, error) = gateway.produce("orders", json!([{ "value": "" }])).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error["error"], "validation_error");
+
+    let (status, error) = gateway
+        .produce(
+            "orders",
+            json!([{ "value": "v", "headers": { "1bad": "x" } }]),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(error["message"].as_str().unwrap().contains("1bad"));
+
+    let (status, _) = gateway.produce("orders", json!([])).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, error) = gateway.produce("-orders", json!([{ "value": "v" }])).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["error"], "invalid_parameter");
+
+    let (status, error) = gateway
+        .call(
+            Method::GET,
+            FETCH_BY_OFFSET,
+            "/consumer/billing/topic/orders/record/offset",
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error["error"], "group_not_found");
+
+    gateway.create_group("billing").await;
+    let (status, _) = gateway
+        .call(
+            Method::GET,
+            FETCH_BY_OFFSET,
+            "/consumer/billing/topic/orders/record/offset?max_records=0",
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = gateway
+        .call(
+            Method::DELETE,
+            "/consumer/{group-id}",
+            "/consumer/billing",
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, error) = gateway
+        .call(
+            Method::DELETE,
+            "/consumer/{group-id}",
+            "/consumer/billing",
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(error["error"], "group_not_found");
+}
+
+#[tokio::test]
+async fn test_draining_refuses_produce_but_serves_fetch() {
+    let lifecycle = Lifecycle::new();
+    let gateway = Gateway::with_lifecycle(lifecycle.clone());
+    gateway.produce("orders", json!([{ "value": "a" }])).await;
+    gateway.create_group("billing").await;
+
+    lifecycle.drain();
+    let (status, error) = gateway.produce("orders", json!([{ "value": "b" }])).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(error["error"], "broker_unavailable");
+
+    let (status, fetched) = gateway
+        .call(
+            Method::GET,
+            FETCH_BY_OFFSET,
+            "/consumer/billing/topic/orders/record/offset",
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fetched["records"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_topic_quota_rejects_calls_with_retry_after() {
+    let quotas = Arc::new(QuotaManager::in_memory().with_enforcement(QuotaEnforcement::Reject));
+    quotas
+        .set_quota(
+            QuotaEntity::new(QuotaEntityType::Topic, "orders"),
+            QuotaConfig {
+                requests_per_sec: Some(1),
+                ..QuotaConfig::default()
+            },
+        )
+        .unwrap();
+    let gateway = Gateway::with_quotas(quotas);
+    gateway.create_group("billing").await;
+
+    let (status, _) = gateway.produce("orders", json!([{ "value": "a" }])).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = gateway
+        .call(
+            Method::GET,
+            FETCH_BY_OFFSET,
+            "/consumer/billing/topic/orders/record/offset",
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, error) = gateway.produce("orders", json!([{ "value": "b" }])).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(error["error"], "quota_exceeded");
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .contains("topic 'orders'")
+    );
+
+    let request = Request::get("/consumer/billing/topic/orders/record/offset")
+        .body(Body::empty())
+        .unwrap();
+    let response = gateway.app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["retry-after"], "1");
+
+    // Other topics are not limited
+    let (status, _) = gateway.produce("audit", json!([{ "value": "c" }])).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_stream_sends_records_as_server_sent_events() {
+    let gateway = Gateway::new();
+    gateway
+        .produce("orders", json!([{ "value": "a" }, { "value": "b" }]))
+        .await;
+    gateway.create_group("billing").await;
+
+    let request = Request::get("/consumer/billing/topic/orders/record/stream?from_offset=1")
+        .body(Body::empty())
+        .unwrap();
+    let response = gateway.app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/event-stream",
+        "stream content type"
+    );
+
+    let mut body = response.into_body();
+    let mut received = String::new();
+    let mut events = Vec::new();
+    while events.len() < 2 {
+        if events.len() == 1 && received.is_empty() {
+            gateway.produce("orders", json!([{ "value": "c" }])).await;
+        }
+        let frame = tokio::time::timeout(Duration::from_secs(5), body.frame())
+            .await
+            .expect("event before timeout")
+            .expect("stream open")
+            .unwrap();
+        let Ok(data) = frame.into_data() else {
+            continue;
+        };
+        received.push_str(std::str::from_utf8(&data).unwrap());
+        while let Some(end) = received.find("\n\n") {
+            let event: String = received.drain(..end + 2).collect();
+            if event.starts_with(':') {
+                continue; // Keep-alive comment
+            }
+            events.push(event);
+        }
+    }
+
+    let schema = json!({ "$ref": "#/components/schemas/RecordWithOffset" });
+    for (event, (offset, value)) in events.iter().zip([(1, "b"), (2, "c")]) {
+        let field = |name: &str| {
+            event
+                .lines()
+                .find_map(|line| line.strip_prefix(&format!("{name}:")))
+                .map(str::trim)
+                .unwrap_or_else(|| panic!("event without {name}: {event}"))
+        };
+        assert_eq!(field("event"), "record");
+        assert_eq!(field("id"), offset.to_string());
+        let record: Value = serde_json::from_str(field("data")).unwrap();
+        gateway
+            .contract
+            .validate(&schema, &record, "$", true)
+            .unwrap_or_else(|e| panic!("stream event breaks the contract: {e}"));
+        assert_eq!(record["value"], value);
+    }
+}
@@ -29,7 +29,9 @@ async fn test_memory_backend_basic_operations() {
                 key: String::new(),
                 value: "Memory test record".into(),
                 headers: Default::default(),
+                timestamp: String::new(),
             }],
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -48,6 +50,8 @@ async fn test_memory_backend_basic_operations() {
             from_offset: 0,
             max_records: 1,
             include_headers: true,
+            to_offset: 0,
+            to_time: String::new(),
         })
         .await
         .unwrap()
@@ -80,7 +84,9 @@ async fn test_file_backend_basic_operations() {
                 key: String::new(),
                 value: "File test record".into(),
                 headers: Default::default(),
+                timestamp: String::new(),
             }],
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -99,6 +105,8 @@ async fn test_file_backend_basic_operations() {
             from_offset: 0,
             max_records: 1,
             include_headers: true,
+            to_offset: 0,
+            to_time: String::new(),
         })
         .await
         .unwrap()
@@ -149,7 +157,9 @@ async fn test_file_backend_persistence_across_restarts() {
                     key: String::new(),
                     value: format!("Persistent record {i}"),
                     headers: Default::default(),
+                    timestamp: String::new(),
                 }],
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -161,6 +171,8 @@ async fn test_file_backend_persistence_across_restarts() {
             from_offset: 0,
             max_records: 100,
             include_headers: true,
+            to_offset: 0,
+            to_time: String::new(),
         })
         .await
         .unwrap()
@@ -185,6 +197,8 @@ async fn test_file_backend_persistence_across_restarts() {
             from_offset: 0,
             max_records: 100,
             include_headers: true,
+            to_offset: 0,
+            to_time: String::new(),
         })
         .await
         .unwrap()
@@ -233,7 +247,9 @@ async fn test_consumer_group_persistence_across_restarts() {
                     key: String::new(),
                     value: format!("Consumer record {i}"),
                     headers: Default::default(),
+                    timestamp: String::new(),
                 }],
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -317,11 +333,13 @@ async fn test_memory_vs_file_backend_consistency() {
             key: String::new(),
             value: r.to_string(),
             headers: Default::default(),
+            timestamp: String::new(),
         };
         let _ = prod_mem
             .produce(proto::ProduceRequest {
                 topic: topic.clone(),
                 records: vec![rec.clone()],
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -329,6 +347,7 @@ async fn test_memory_vs_file_backend_consistency() {
             .produce(proto::ProduceRequest {
                 topic: topic.clone(),
                 records: vec![rec],
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -349,6 +368,8 @@ async fn test_memory_vs_file_backend_consistency() {
             from_offset: 0,
             max_records: 100,
             include_headers: true,
+            to_offset: 0,
+            to_time: String::new(),
         })
         .await
         .unwrap()
@@ -368,6 +389,8 @@ async fn test_memory_vs_file_backend_consistency() {
             from_offset: 0,
             max_records: 100,
             include_headers: true,
+            to_offset: 0,
+            to_time: String::new(),
         })
         .await
         .unwrap()
@@ -401,7 +424,9 @@ async fn test_file_backend_creates_data_directory() {
                 key: String::new(),
                 value: "Directory test record".into(),
                 headers: Default::default(),
+                timestamp: String::new(),
             }],
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -415,3 +440,81 @@ async fn test_file_backend_creates_data_directory() {
         panic!("Expected data directory to be set for file backend");
     }
 }
+
+#[tokio::test]
+async fn test_file_backend_produce_with_compression() {
+    let srv = TestServer::start_with_storage("file")
+        .await
+        .expect("start file server");
+    let addr = format!("http://127.0.0.1:{}", srv.port);
+    let topic = unique_topic();
+    let mut producer = proto::producer_client::ProducerClient::connect(addr.clone())
+        .await
+        .unwrap();
+    let mut consumer = proto::consumer_client::ConsumerClient::connect(addr)
+        .await
+        .unwrap();
+
+    for compression in [
+        proto::Compression::Gzip,
+        proto::Compression::Snappy,
+        proto::Compression::Lz4,
+        proto::Compression::Zstd,
+    ] {
+        producer
+            .produce(proto::ProduceRequest {
+                topic: topic.clone(),
+                records: (0..3)
+                    .map(|i| proto::Record {
+                        key: String::new(),
+                        value: format!("{} record {i}", compression.as_str_name()),
+                        headers: Default::default(),
+                        timestamp: String::new(),
+                    })
+                    .collect(),
+                compression: compression.into(),
+            })
+            .await
+            .unwrap();
+    }
+
+    let group = unique_group();
+    consumer
+        .create_consumer_group(proto::ConsumerGroupId {
+            group_id: group.clone(),
+        })
+        .await
+        .unwrap();
+    let fetched = consumer
+        .fetch_by_offset(proto::FetchByOffsetRequest {
+            group_id: group,
+            topic: topic.clone(),
+            from_offset: 0,
+            max_records: 100,
+            include_headers: true,
+            to_offset: 0,
+            to_time: String::new(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(fetched.records.len(), 12);
+    assert_eq!(
+        fetched.records[4].record.as_ref().unwrap().value,
+        "COMPRESSION_SNAPPY record 1"
+    );
+
+    let invalid = producer
+        .produce(proto::ProduceRequest {
+            topic,
+            records: vec![proto::Record {
+                key: String::new(),
+                value: "bad codec".into(),
+                headers: Default::default(),
+                timestamp: String::new(),
+            }],
+            compression: 42,
+        })
+        .await;
+    assert_eq!(invalid.unwrap_err().code(), tonic::Code::InvalidArgument);
+}
@@ -28,6 +28,8 @@ async fn test_subscribe_receives_new_records() {
         from_offset: 0,
         max_records: 100,
         include_headers: true,
+        to_offset: 0,
+        to_time: String::new(),
     };
     let mut stream = consumer.subscribe(req).await.unwrap().into_inner();
 
@@ -39,7 +41,9 @@ async fn test_subscribe_receives_new_records() {
                 key: String::new(),
                 value: "hello-sub".into(),
                 headers: Default::default(),
+                timestamp: String::new(),
             }],
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -55,3 +59,60 @@ async fn test_subscribe_receives_new_records() {
 
     assert_eq!(item.record.unwrap().value, "hello-sub");
 }
+
+#[tokio::test]
+async fn test_subscribe_ends_at_to_offset() {
+    let srv = TestServer::start().await.expect("start server");
+    let addr = format!("http://127.0.0.1:{}", srv.port);
+
+    let mut producer = proto::producer_client::ProducerClient::connect(addr.clone())
+        .await
+        .unwrap();
+    let mut consumer = proto::consumer_client::ConsumerClient::connect(addr)
+        .await
+        .unwrap();
+    consumer
+        .create_consumer_group(proto::ConsumerGroupId {
+            group_id: "grp-sub-bounded".into(),
+        })
+        .await
+        .unwrap();
+    let records = (0..3)
+        .map(|i| proto::Record {
+            key: String::new(),
+            value: format!("r{i}"),
+            headers: Default::default(),
+            timestamp: String::new(),
+        })
+        .collect();
+    producer
+        .produce(proto::ProduceRequest {
+            topic: "sub-bounded-topic".into(),
+            records,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let req = proto::FetchByOffsetRequest {
+        group_id: "grp-sub-bounded".into(),
+        topic: "sub-bounded-topic".into(),
+        include_headers: true,
+        to_offset: 2,
+        ..Default::default()
+    };
+    let mut stream = consumer.subscribe(req).await.unwrap().into_inner();
+
+    // Expect the two records below the end offset, then the end of the stream
+    let received = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        let mut offsets = Vec::new();
+        while let Some(item) = stream.message().await.expect("stream result ok") {
+            offsets.push(item.offset);
+        }
+        offsets
+    })
+    .await
+    .expect("stream ends at to_offset");
+
+    assert_eq!(received, vec![0, 1]);
+}
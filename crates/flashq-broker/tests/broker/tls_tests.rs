@@ -0,0 +1,133 @@
+use crate::test_utilities::{TestCerts, TestServer};
+use flashq_broker::flashq::v1 as proto;
+use flashq_client::FlashqClient;
+use flashq_cluster::client::ClusterClient;
+
+#[tokio::test]
+async fn test_tls_produce_and_fetch_round_trip() {
+    let certs = TestCerts::generate().expect("generate certs");
+    let srv = TestServer::start_with_tls(&certs, false)
+        .await
+        .expect("start tls server");
+    let addr = format!("https://127.0.0.1:{}", srv.port);
+    let topic = "tls-topic".to_string();
+    let group = "grp-tls".to_string();
+
+    let client = FlashqClient::connect_with_tls(addr, &certs.anonymous_client_tls())
+        .await
+        .expect("connect over tls");
+    let mut producer = client.producer();
+    let mut consumer = client.consumer();
+
+    producer
+        .produce(proto::ProduceRequest {
+            topic: topic.clone(),
+            records: vec![proto::Record {
+                key: String::new(),
+                value: "sealed".to_string(),
+                headers: Default::default(),
+                timestamp: String::new(),
+            }],
+            ..Default::default()
+        })
+        .await
+        .expect("produce over tls");
+    consumer
+        .create_consumer_group(proto::ConsumerGroupId {
+            group_id: group.clone(),
+        })
+        .await
+        .expect("create group over tls");
+    let fetched = consumer
+        .fetch_by_offset(proto::FetchByOffsetRequest {
+            group_id: group,
+            topic,
+            max_records: 10,
+            include_headers: true,
+            ..Default::default()
+        })
+        .await
+        .expect("fetch over tls")
+        .into_inner();
+
+    assert_eq!(fetched.records.len(), 1);
+    assert_eq!(
+        fetched.records[0].record.as_ref().unwrap().value,
+        "sealed".to_string()
+    );
+}
+
+#[tokio::test]
+async fn test_tls_broker_rejects_plaintext_clients() {
+    let certs = TestCerts::generate().expect("generate certs");
+    let srv = TestServer::start_with_tls(&certs, false)
+        .await
+        .expect("start tls server");
+
+    let plaintext = async {
+        let client = FlashqClient::connect(format!("http://127.0.0.1:{}", srv.port)).await?;
+        client.admin().health(proto::Empty {}).await?;
+        Ok::<_, Box<dyn std::error::Error>>(())
+    };
+    assert!(plaintext.await.is_err());
+}
+
+#[tokio::test]
+async fn test_mutual_tls_requires_trusted_client_certificate() {
+    let certs = TestCerts::generate().expect("generate certs");
+    let srv = TestServer::start_with_tls(&certs, true)
+        .await
+        .expect("start mtls server");
+    let addr = format!("https://127.0.0.1:{}", srv.port);
+
+    let anonymous = async {
+        let client =
+            FlashqClient::connect_with_tls(addr.clone(), &certs.anonymous_client_tls()).await?;
+        client.admin().health(proto::Empty {}).await?;
+        Ok::<_, Box<dyn std::error::Error>>(())
+    };
+    assert!(anonymous.await.is_err());
+
+    let untrusted = TestCerts::generate().expect("generate untrusted certs");
+    let foreign_identity = certs
+        .anonymous_client_tls()
+        .with_identity(&untrusted.client_cert, &untrusted.client_key);
+    let foreign = async {
+        let client = FlashqClient::connect_with_tls(addr.clone(), &foreign_identity).await?;
+        client.admin().health(proto::Empty {}).await?;
+        Ok::<_, Box<dyn std::error::Error>>(())
+    };
+    assert!(foreign.await.is_err());
+
+    let client = FlashqClient::connect_with_tls(addr, &certs.client_tls())
+        .await
+        .expect("connect with client certificate");
+    client
+        .admin()
+        .health(proto::Empty {})
+        .await
+        .expect("health with client certificate");
+}
+
+#[tokio::test]
+async fn test_cluster_client_connects_over_mutual_tls() {
+    let certs = TestCerts::generate().expect("generate certs");
+    let srv = TestServer::start_with_tls(&certs, true)
+        .await
+        .expect("start mtls server");
+    let addr = format!("https://127.0.0.1:{}", srv.port);
+
+    let mut cluster = ClusterClient::connect_with_tls(addr.clone(), &certs.client_tls(), None)
+        .await
+        .expect("cluster client over tls");
+    cluster
+        .describe_cluster()
+        .await
+        .expect("describe cluster over tls");
+
+    if let Ok(mut plaintext) =
+        ClusterClient::connect(format!("http://127.0.0.1:{}", srv.port)).await
+    {
+        assert!(plaintext.describe_cluster().await.is_err());
+    }
+}
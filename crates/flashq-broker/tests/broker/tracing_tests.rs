@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use crate::test_utilities::{OtlpCollector, TestServer};
+use flashq_broker::flashq::v1 as proto;
+use flashq_client::FlashqClient;
+use flashq_proto::trace_context::{self, OpenTelemetrySpanExt};
+use opentelemetry::trace::{SpanContext, TraceContextExt, TracerProvider};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use tracing::Instrument;
+use tracing_subscriber::layer::SubscriberExt;
+
+/// Give spans on this thread OpenTelemetry ids, as an instrumented client application would.
+fn trace_this_thread() -> tracing::subscriber::DefaultGuard {
+    let provider = SdkTracerProvider::builder().build();
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("flashq-tests")));
+    tracing::subscriber::set_default(subscriber)
+}
+
+fn span_context(span: &tracing::Span) -> SpanContext {
+    span.context().span().span_context().clone()
+}
+
+async fn client(srv: &TestServer) -> FlashqClient {
+    FlashqClient::connect(format!("http://127.0.0.1:{}", srv.port))
+        .await
+        .expect("connect")
+}
+
+fn record(value: &str) -> proto::Record {
+    proto::Record {
+        key: String::new(),
+        value: value.to_string(),
+        headers: Vec::new(),
+        timestamp: String::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_broker_exports_produce_span_under_client_trace() {
+    let collector = OtlpCollector::start().await.expect("start collector");
+    let srv = TestServer::start_with_args(&["--otlp-endpoint", &collector.endpoint])
+        .await
+        .expect("start server");
+    let client = client(&srv).await;
+    let _tracing = trace_this_thread();
+
+    let span = tracing::info_span!("place_order");
+    let parent = span_context(&span);
+    assert!(parent.is_valid());
+    client
+        .producer()
+        .produce(proto::ProduceRequest {
+            topic: "traced".to_string(),
+            records: vec![record("order-1")],
+            ..Default::default()
+        })
+        .instrument(span)
+        .await
+        .expect("produce");
+
+    let exported = collector
+        .wait_for_span(Duration::from_secs(15), |span| {
+            span.trace_id == parent.trace_id().to_bytes()
+        })
+        .await
+        .expect("broker exported a span in the client's trace");
+    assert_eq!(exported.name, "/flashq.v1.Producer/Produce");
+    assert_eq!(exported.parent_span_id, parent.span_id().to_bytes());
+}
+
+#[tokio::test]
+async fn test_record_headers_carry_producer_trace_context() {
+    let srv = TestServer::start().await.expect("start server");
+    let client = client(&srv).await;
+    let _tracing = trace_this_thread();
+
+    let producer_span = tracing::info_span!("publish");
+    let producer = span_context(&producer_span);
+    let mut traced = record("traced");
+    producer_span.in_scope(|| trace_context::inject_headers(&mut traced.headers));
+    assert!(traced.headers.iter().any(|h| h.key == "traceparent"));
+    client
+        .producer()
+        .produce(proto::ProduceRequest {
+            topic: "linked".to_string(),
+            records: vec![traced, record("untraced")],
+            ..Default::default()
+        })
+        .await
+        .expect("produce");
+
+    let mut consumer = client.consumer();
+    consumer
+        .create_consumer_group(proto::ConsumerGroupId {
+            group_id: "tracing".to_string(),
+        })
+        .await
+        .expect("create group");
+    let fetched = consumer
+        .fetch_by_offset(proto::FetchByOffsetRequest {
+            group_id: "tracing".to_string(),
+            topic: "linked".to_string(),
+            max_records: 10,
+            include_headers: true,
+            ..Default::default()
+        })
+        .await
+        .expect("fetch")
+        .into_inner()
+        .records;
+    let headers = |i: usize| fetched[i].record.as_ref().unwrap().headers.clone();
+
+    let extracted = trace_context::extract_headers(&headers(0));
+    assert_eq!(*extracted.span().span_context(), {
+        // Extracted contexts are marked remote
+        SpanContext::new(
+            producer.trace_id(),
+            producer.span_id(),
+            producer.trace_flags(),
+            true,
+            producer.trace_state().clone(),
+        )
+    });
+    assert!(
+        !trace_context::extract_headers(&headers(1))
+            .span()
+            .span_context()
+            .is_valid()
+    );
+    trace_context::link_to_record(&tracing::info_span!("process"), &headers(0));
+}
@@ -17,8 +17,10 @@ async fn test_record_key_size_validation() {
         records: vec![proto::Record {
             key: oversized_key,
             value: "test_value".to_string(),
-            headers: std::collections::HashMap::new(),
+            headers: Vec::new(),
+            timestamp: String::new(),
         }],
+        ..Default::default()
     };
 
     let result = client.produce(request).await;
@@ -47,8 +49,10 @@ async fn test_record_value_size_validation() {
         records: vec![proto::Record {
             key: "test_key".to_string(),
             value: oversized_value,
-            headers: std::collections::HashMap::new(),
+            headers: Vec::new(),
+            timestamp: String::new(),
         }],
+        ..Default::default()
     };
 
     let result = client.produce(request).await;
@@ -72,8 +76,10 @@ async fn test_record_header_size_validation() {
 
     // Test oversized header value (> 1024 chars)
     let oversized_header_value = "y".repeat(1025);
-    let mut headers = std::collections::HashMap::new();
-    headers.insert("test_header".to_string(), oversized_header_value);
+    let headers = vec![proto::Header {
+        key: "test_header".to_string(),
+        value: oversized_header_value.into_bytes(),
+    }];
 
     let request = proto::ProduceRequest {
         topic: "validation_test_topic".to_string(),
@@ -81,7 +87,9 @@ async fn test_record_header_size_validation() {
             key: "test_key".to_string(),
             value: "test_value".to_string(),
             headers,
+            timestamp: String::new(),
         }],
+        ..Default::default()
     };
 
     let result = client.produce(request).await;
@@ -104,8 +112,10 @@ async fn test_valid_record_sizes_pass_validation() {
     // Test maximum allowed sizes that should pass
     let max_key = "x".repeat(1024); // Exactly 1024 chars
     let max_value = "y".repeat(1_048_576); // Exactly 1MB
-    let mut headers = std::collections::HashMap::new();
-    headers.insert("test_header".to_string(), "z".repeat(1024)); // Exactly 1024 chars
+    let headers = vec![proto::Header {
+        key: "test_header".to_string(),
+        value: "z".repeat(1024).into_bytes(), // Exactly 1024 bytes
+    }];
 
     let request = proto::ProduceRequest {
         topic: "validation_test_topic".to_string(),
@@ -113,7 +123,9 @@ async fn test_valid_record_sizes_pass_validation() {
             key: max_key,
             value: max_value,
             headers,
+            timestamp: String::new(),
         }],
+        ..Default::default()
     };
 
     let result = client.produce(request).await;
@@ -6,10 +6,18 @@
 pub mod test_utilities;
 
 mod broker {
+    pub mod acl_tests;
     pub mod admin_tests;
+    pub mod auth_tests;
     pub mod consumer_tests;
+    pub mod kafka_tests;
+    pub mod lifecycle_tests;
     pub mod producer_tests;
+    pub mod quota_tests;
+    pub mod rest_gateway_tests;
     pub mod storage_integration_tests;
     pub mod subscribe_tests;
+    pub mod tls_tests;
+    pub mod tracing_tests;
     pub mod validation_tests;
 }
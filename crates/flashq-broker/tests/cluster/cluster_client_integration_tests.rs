@@ -205,6 +205,8 @@ async fn test_cluster_client_heartbeat_stream_bidirectional_communication() {
             leader_override: None,
         }],
         timestamp: Utc::now().to_rfc3339(),
+        trace_context: Default::default(),
+        is_draining: false,
     };
 
     // Action
@@ -128,6 +128,8 @@ async fn test_broker_heartbeat_updates_high_water_mark() {
             leader_override: None,
         }],
         timestamp: Utc::now().to_rfc3339(),
+        trace_context: Default::default(),
+        is_draining: false,
     };
 
     // Action
@@ -344,6 +346,8 @@ async fn test_cluster_service_heartbeat_persistence() {
                 leader_override: None,
             }],
             timestamp: Utc::now().to_rfc3339(),
+            trace_context: Default::default(),
+            is_draining: false,
         };
 
         cluster_service.handle_heartbeat(request).await.unwrap();
@@ -375,6 +379,8 @@ async fn test_cluster_service_with_multiple_brokers() {
             broker_id,
             partitions: vec![],
             timestamp: Utc::now().to_rfc3339(),
+            trace_context: Default::default(),
+            is_draining: false,
         };
 
         // Action
@@ -1,7 +1,7 @@
 use std::io::Read;
 use std::net::TcpListener;
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::sync::{Arc, Mutex, Once};
 use std::time::Duration;
 
@@ -14,6 +14,8 @@ pub use flashq_cluster::{
     Record, metadata_store::FileMetadataStore, service::ClusterServiceImpl,
     storage::StorageBackend, types::*,
 };
+pub use flashq_proto::auth::CallCredentials;
+pub use flashq_proto::tls::ClientTlsOptions;
 pub use flashq_storage::file::SyncMode;
 
 static SERVER_INIT: Once = Once::new();
@@ -153,9 +155,297 @@ impl TestServer {
         Err("broker-server failed to start".into())
     }
 
+    /// Start a memory-backed broker serving TLS with `certs`, requiring client certificates
+    /// when `mutual` is set. Readiness is checked over TLS with the client identity.
+    #[allow(dead_code)]
+    pub async fn start_with_tls(
+        certs: &TestCerts,
+        mutual: bool,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::start_secured(Some((certs, mutual)), None, CallCredentials::None, &[]).await
+    }
+
+    /// Start a memory-backed broker that authenticates calls against `credentials_file`.
+    /// Readiness is checked with `credentials`.
+    #[allow(dead_code)]
+    pub async fn start_with_credentials(
+        credentials_file: &Path,
+        credentials: CallCredentials,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::start_secured(None, Some(credentials_file), credentials, &[]).await
+    }
+
+    /// Start a memory-backed broker with extra command-line flags.
+    #[allow(dead_code)]
+    pub async fn start_with_args(args: &[&str]) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::start_secured(None, None, CallCredentials::None, args).await
+    }
+
+    /// Start a memory-backed broker that authenticates against `credentials_file` and
+    /// enforces ACLs, with `super_user` exempt. Readiness is checked with `credentials`,
+    /// which should authenticate as the super user.
+    #[allow(dead_code)]
+    pub async fn start_with_acls(
+        credentials_file: &Path,
+        credentials: CallCredentials,
+        super_user: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::start_secured(
+            None,
+            Some(credentials_file),
+            credentials,
+            &["--enable-acls", "--super-user", super_user],
+        )
+        .await
+    }
+
+    async fn start_secured(
+        tls: Option<(&TestCerts, bool)>,
+        credentials_file: Option<&Path>,
+        credentials: CallCredentials,
+        extra_args: &[&str],
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let port = find_available_port()?;
+        let bin = ensure_server_binary()?;
+        let mut args = vec![
+            "--port".to_string(),
+            port.to_string(),
+            "--storage".to_string(),
+            "memory".to_string(),
+        ];
+        if let Some((certs, mutual)) = tls {
+            args.push("--tls-cert".to_string());
+            args.push(certs.server_cert.display().to_string());
+            args.push("--tls-key".to_string());
+            args.push(certs.server_key.display().to_string());
+            if mutual {
+                args.push("--tls-client-ca".to_string());
+                args.push(certs.ca_cert.display().to_string());
+            }
+        }
+        if let Some(path) = credentials_file {
+            args.push("--credentials-file".to_string());
+            args.push(path.display().to_string());
+        }
+        args.extend(extra_args.iter().map(|arg| arg.to_string()));
+        let mut process = Command::new(bin)
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        for _ in 0..30 {
+            if let Ok(Some(status)) = process.try_wait() {
+                if let Some(mut stderr) = process.stderr.take() {
+                    let mut buf = String::new();
+                    let _ = stderr.read_to_string(&mut buf);
+                    eprintln!("broker-server exited early: {status}, stderr: {buf}");
+                }
+                return Err("broker-server exited".into());
+            }
+            let client = match tls {
+                Some((certs, _)) => {
+                    flashq_client::FlashqClient::connect_with_tls(
+                        format!("https://127.0.0.1:{port}"),
+                        &certs.client_tls(),
+                    )
+                    .await
+                }
+                None => {
+                    flashq_client::FlashqClient::connect(format!("http://127.0.0.1:{port}")).await
+                }
+            };
+            if let Ok(client) = client {
+                if client
+                    .with_credentials(credentials.clone())
+                    .admin()
+                    .health(flashq_broker::flashq::v1::Empty {})
+                    .await
+                    .is_ok()
+                {
+                    return Ok(Self {
+                        process,
+                        port,
+                        data_dir: None,
+                    });
+                }
+            }
+            sleep(Duration::from_millis(300)).await;
+        }
+        let _ = process.kill();
+        Err("broker-server failed to start".into())
+    }
+
     pub fn data_dir(&self) -> Option<&Path> {
         self.data_dir.as_deref()
     }
+
+    /// Send SIGTERM and wait up to ten seconds for the broker to exit.
+    #[allow(dead_code)]
+    pub async fn terminate(&mut self) -> Result<ExitStatus, Box<dyn std::error::Error>> {
+        let sent = Command::new("kill")
+            .args(["-TERM", &self.process.id().to_string()])
+            .status()?;
+        if !sent.success() {
+            return Err("failed to send SIGTERM".into());
+        }
+        for _ in 0..100 {
+            if let Some(status) = self.process.try_wait()? {
+                return Ok(status);
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        Err("broker-server did not exit after SIGTERM".into())
+    }
+}
+
+/// In-process stand-in for an OpenTelemetry collector: accepts OTLP/gRPC trace exports at
+/// `endpoint` and keeps every span it receives.
+#[allow(dead_code)]
+pub struct OtlpCollector {
+    pub endpoint: String,
+    spans: Arc<Mutex<Vec<otlp::trace::v1::Span>>>,
+    server: tokio::task::JoinHandle<()>,
+}
+
+mod otlp {
+    pub use opentelemetry_proto::tonic::collector::trace::v1 as collector;
+    pub use opentelemetry_proto::tonic::trace;
+}
+
+#[derive(Clone)]
+struct CollectedSpans(Arc<Mutex<Vec<otlp::trace::v1::Span>>>);
+
+#[tonic::async_trait]
+impl otlp::collector::trace_service_server::TraceService for CollectedSpans {
+    async fn export(
+        &self,
+        request: tonic::Request<otlp::collector::ExportTraceServiceRequest>,
+    ) -> Result<tonic::Response<otlp::collector::ExportTraceServiceResponse>, tonic::Status> {
+        let mut spans = self.0.lock().unwrap();
+        for resource_spans in request.into_inner().resource_spans {
+            for scope_spans in resource_spans.scope_spans {
+                spans.extend(scope_spans.spans);
+            }
+        }
+        Ok(tonic::Response::new(
+            otlp::collector::ExportTraceServiceResponse::default(),
+        ))
+    }
+}
+
+#[allow(dead_code)]
+impl OtlpCollector {
+    pub async fn start() -> Result<Self, Box<dyn std::error::Error>> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let endpoint = format!("http://{}", listener.local_addr()?);
+        let incoming = tonic::transport::server::TcpIncoming::from_listener(listener, true, None)
+            .map_err(|e| e.to_string())?;
+        let spans = Arc::new(Mutex::new(Vec::new()));
+        let service = otlp::collector::trace_service_server::TraceServiceServer::new(
+            CollectedSpans(spans.clone()),
+        );
+        let server = tokio::spawn(async move {
+            let _ = tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming(incoming)
+                .await;
+        });
+        Ok(Self {
+            endpoint,
+            spans,
+            server,
+        })
+    }
+
+    /// Wait up to `timeout` for a received span matching `predicate`.
+    pub async fn wait_for_span(
+        &self,
+        timeout: Duration,
+        predicate: impl Fn(&otlp::trace::v1::Span) -> bool,
+    ) -> Option<otlp::trace::v1::Span> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            if let Some(span) = self.spans.lock().unwrap().iter().find(|s| predicate(s)) {
+                return Some(span.clone());
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return None;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
+impl Drop for OtlpCollector {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// A throwaway CA with a server certificate for localhost/127.0.0.1 and a client
+/// certificate, all written as PEM files into a temp directory.
+#[allow(dead_code)]
+pub struct TestCerts {
+    _dir: TempDir,
+    pub ca_cert: PathBuf,
+    pub server_cert: PathBuf,
+    pub server_key: PathBuf,
+    pub client_cert: PathBuf,
+    pub client_key: PathBuf,
+}
+
+#[allow(dead_code)]
+impl TestCerts {
+    pub fn generate() -> Result<Self, Box<dyn std::error::Error>> {
+        use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair, KeyUsagePurpose};
+
+        let dir = tempfile::Builder::new()
+            .prefix("flashq_tls_test_")
+            .tempdir()?;
+        let write = |name: &str, pem: String| -> std::io::Result<PathBuf> {
+            let path = dir.path().join(name);
+            std::fs::write(&path, pem)?;
+            Ok(path)
+        };
+
+        let ca_key = KeyPair::generate()?;
+        let mut ca_params = CertificateParams::new(Vec::<String>::new())?;
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+        let ca = ca_params.self_signed(&ca_key)?;
+
+        let server_key = KeyPair::generate()?;
+        let server =
+            CertificateParams::new(vec!["localhost".to_string(), "127.0.0.1".to_string()])?
+                .signed_by(&server_key, &ca, &ca_key)?;
+
+        let client_key = KeyPair::generate()?;
+        let client = CertificateParams::new(vec!["flashq-test-client".to_string()])?.signed_by(
+            &client_key,
+            &ca,
+            &ca_key,
+        )?;
+
+        Ok(Self {
+            ca_cert: write("ca.pem", ca.pem())?,
+            server_cert: write("server.pem", server.pem())?,
+            server_key: write("server.key", server_key.serialize_pem())?,
+            client_cert: write("client.pem", client.pem())?,
+            client_key: write("client.key", client_key.serialize_pem())?,
+            _dir: dir,
+        })
+    }
+
+    /// Client settings trusting the test CA, without a client certificate.
+    pub fn anonymous_client_tls(&self) -> ClientTlsOptions {
+        ClientTlsOptions::new(&self.ca_cert).with_domain_name("localhost")
+    }
+
+    /// Client settings trusting the test CA and presenting the test client certificate.
+    pub fn client_tls(&self) -> ClientTlsOptions {
+        self.anonymous_client_tls()
+            .with_identity(&self.client_cert, &self.client_key)
+    }
 }
 
 /// Create a FlashQ broker with file storage and integrated cluster service for testing
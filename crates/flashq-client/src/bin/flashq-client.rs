@@ -1,7 +1,9 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use flashq_client::FlashqClient;
+use flashq_proto::auth::{CallCredentials, DEFAULT_SCRAM_ITERATIONS, ScramCredential};
 use flashq_proto::flashq::v1 as proto;
-use std::collections::HashMap;
+use flashq_proto::tls::ClientTlsOptions;
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "flashq-client", version, author, about = "FlashQ client")]
@@ -10,10 +12,89 @@ struct Cli {
     #[arg(long, default_value = "http://127.0.0.1:50051")]
     addr: String,
 
+    #[command(flatten)]
+    tls: TlsArgs,
+
+    #[command(flatten)]
+    auth: AuthArgs,
+
+    /// Client id sent with every call; brokers key per-client quotas on it
+    #[arg(long, global = true, env = "FLASHQ_CLIENT_ID")]
+    client_id: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+#[derive(Args, Debug)]
+struct TlsArgs {
+    /// PEM CA bundle for verifying the broker; connects over TLS (use an https:// address)
+    #[arg(long, global = true)]
+    tls_ca: Option<PathBuf>,
+    /// PEM client certificate for brokers that require mutual TLS
+    #[arg(long, global = true, requires_all = ["tls_ca", "tls_key"])]
+    tls_cert: Option<PathBuf>,
+    /// PEM private key for --tls-cert
+    #[arg(long, global = true, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+    /// Name to verify the broker certificate against instead of the address host
+    #[arg(long, global = true, requires = "tls_ca")]
+    tls_domain: Option<String>,
+}
+
+impl TlsArgs {
+    fn options(&self) -> Option<ClientTlsOptions> {
+        let mut tls = ClientTlsOptions::new(self.tls_ca.as_ref()?);
+        if let (Some(cert), Some(key)) = (&self.tls_cert, &self.tls_key) {
+            tls = tls.with_identity(cert, key);
+        }
+        if let Some(domain) = &self.tls_domain {
+            tls = tls.with_domain_name(domain);
+        }
+        Some(tls)
+    }
+}
+
+#[derive(Args, Debug)]
+struct AuthArgs {
+    /// API token to authenticate with
+    #[arg(long, global = true, env = "FLASHQ_TOKEN", conflicts_with = "username")]
+    token: Option<String>,
+    /// Username to authenticate with
+    #[arg(long, global = true, requires = "password")]
+    username: Option<String>,
+    /// Password for --username
+    #[arg(long, global = true, env = "FLASHQ_PASSWORD", requires = "username")]
+    password: Option<String>,
+}
+
+impl AuthArgs {
+    fn credentials(&self) -> CallCredentials {
+        match (&self.token, &self.username, &self.password) {
+            (Some(token), _, _) => CallCredentials::token(token),
+            (None, Some(username), Some(password)) => CallCredentials::password(username, password),
+            _ => CallCredentials::None,
+        }
+    }
+}
+
+async fn connect(
+    addr: &str,
+    tls: &TlsArgs,
+    auth: &AuthArgs,
+    client_id: Option<&str>,
+) -> Result<FlashqClient, Box<dyn std::error::Error>> {
+    let client = match tls.options() {
+        Some(options) => FlashqClient::connect_with_tls(addr.to_string(), &options).await?,
+        None => FlashqClient::connect(addr.to_string()).await?,
+    };
+    let client = client.with_credentials(auth.credentials());
+    match client_id {
+        Some(client_id) => client.with_client_id(client_id),
+        None => Ok(client),
+    }
+}
+
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Test connectivity (establish channel)
@@ -36,6 +117,22 @@ enum Commands {
     ListTopics,
     /// Get topic high water mark
     HighWaterMark(HighWaterMarkCmd),
+    /// List earliest, latest and offset-for-timestamp per partition
+    Offsets(OffsetsCmd),
+    /// Add an ACL binding
+    CreateAcl(AclCmd),
+    /// List ACL bindings matching the given fields
+    ListAcls(AclCmd),
+    /// Delete ACL bindings matching the given fields
+    DeleteAcls(AclCmd),
+    /// Set an entity's quota, replacing its current limits
+    SetQuota(SetQuotaCmd),
+    /// Remove an entity's quota
+    DeleteQuota(QuotaEntityCmd),
+    /// List configured quotas
+    ListQuotas,
+    /// Print a SCRAM credential for a broker credentials file (no broker connection)
+    HashPassword(HashPasswordCmd),
     /// Subscribe and print records continuously
     Subscribe(SubscribeCmd),
 }
@@ -50,9 +147,236 @@ struct ProduceCmd {
     /// Optional key applied to all records
     #[arg(long)]
     key: Option<String>,
-    /// Optional headers KEY=VALUE (repeatable)
+    /// Optional headers KEY=VALUE, sent in order (repeatable, keys may repeat)
     #[arg(long = "header")]
     headers: Vec<String>,
+    /// Optional RFC3339 event time applied to all records
+    #[arg(long)]
+    timestamp: Option<String>,
+    /// Batch compression codec (defaults to the topic's codec)
+    #[arg(long, value_enum)]
+    compression: Option<CompressionArg>,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum CompressionArg {
+    None,
+    Gzip,
+    Snappy,
+    Lz4,
+    Zstd,
+}
+
+impl From<CompressionArg> for proto::Compression {
+    fn from(v: CompressionArg) -> Self {
+        match v {
+            CompressionArg::None => proto::Compression::None,
+            CompressionArg::Gzip => proto::Compression::Gzip,
+            CompressionArg::Snappy => proto::Compression::Snappy,
+            CompressionArg::Lz4 => proto::Compression::Lz4,
+            CompressionArg::Zstd => proto::Compression::Zstd,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum AclResourceArg {
+    Topic,
+    Group,
+    Cluster,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum AclPatternArg {
+    Literal,
+    Prefixed,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum AclOperationArg {
+    All,
+    Read,
+    Write,
+    Create,
+    Delete,
+    Describe,
+    Alter,
+    ClusterAction,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum AclPermissionArg {
+    Allow,
+    Deny,
+}
+
+impl From<AclResourceArg> for proto::AclResourceType {
+    fn from(v: AclResourceArg) -> Self {
+        match v {
+            AclResourceArg::Topic => proto::AclResourceType::Topic,
+            AclResourceArg::Group => proto::AclResourceType::Group,
+            AclResourceArg::Cluster => proto::AclResourceType::Cluster,
+        }
+    }
+}
+
+impl From<AclPatternArg> for proto::AclPatternType {
+    fn from(v: AclPatternArg) -> Self {
+        match v {
+            AclPatternArg::Literal => proto::AclPatternType::Literal,
+            AclPatternArg::Prefixed => proto::AclPatternType::Prefixed,
+        }
+    }
+}
+
+impl From<AclOperationArg> for proto::AclOperation {
+    fn from(v: AclOperationArg) -> Self {
+        match v {
+            AclOperationArg::All => proto::AclOperation::All,
+            AclOperationArg::Read => proto::AclOperation::Read,
+            AclOperationArg::Write => proto::AclOperation::Write,
+            AclOperationArg::Create => proto::AclOperation::Create,
+            AclOperationArg::Delete => proto::AclOperation::Delete,
+            AclOperationArg::Describe => proto::AclOperation::Describe,
+            AclOperationArg::Alter => proto::AclOperation::Alter,
+            AclOperationArg::ClusterAction => proto::AclOperation::ClusterAction,
+        }
+    }
+}
+
+impl From<AclPermissionArg> for proto::AclPermission {
+    fn from(v: AclPermissionArg) -> Self {
+        match v {
+            AclPermissionArg::Allow => proto::AclPermission::Allow,
+            AclPermissionArg::Deny => proto::AclPermission::Deny,
+        }
+    }
+}
+
+/// ACL fields; every field is needed to create a binding, and any subset filters list/delete.
+#[derive(Args, Debug)]
+struct AclCmd {
+    /// Principal name, or "*" for every principal
+    #[arg(long)]
+    principal: Option<String>,
+    #[arg(long, value_enum)]
+    resource_type: Option<AclResourceArg>,
+    /// Resource name, or "*" for every resource of the type
+    #[arg(long)]
+    resource_name: Option<String>,
+    #[arg(long, value_enum)]
+    pattern: Option<AclPatternArg>,
+    #[arg(long, value_enum)]
+    operation: Option<AclOperationArg>,
+    #[arg(long, value_enum)]
+    permission: Option<AclPermissionArg>,
+}
+
+impl AclCmd {
+    fn binding(self) -> proto::AclBinding {
+        proto::AclBinding {
+            principal: self.principal.unwrap_or_default(),
+            resource_type: self
+                .resource_type
+                .map_or(0, |v| proto::AclResourceType::from(v) as i32),
+            resource_name: self.resource_name.unwrap_or_default(),
+            pattern_type: self
+                .pattern
+                .map_or(0, |v| proto::AclPatternType::from(v) as i32),
+            operation: self
+                .operation
+                .map_or(0, |v| proto::AclOperation::from(v) as i32),
+            permission: self
+                .permission
+                .map_or(0, |v| proto::AclPermission::from(v) as i32),
+        }
+    }
+
+    fn filter(self) -> proto::AclFilter {
+        let b = self.binding();
+        proto::AclFilter {
+            principal: b.principal,
+            resource_type: b.resource_type,
+            resource_name: b.resource_name,
+            pattern_type: b.pattern_type,
+            operation: b.operation,
+            permission: b.permission,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum QuotaEntityArg {
+    Principal,
+    ClientId,
+    Topic,
+}
+
+impl From<QuotaEntityArg> for proto::QuotaEntityType {
+    fn from(v: QuotaEntityArg) -> Self {
+        match v {
+            QuotaEntityArg::Principal => proto::QuotaEntityType::Principal,
+            QuotaEntityArg::ClientId => proto::QuotaEntityType::ClientId,
+            QuotaEntityArg::Topic => proto::QuotaEntityType::Topic,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct QuotaEntityCmd {
+    #[arg(long, value_enum)]
+    entity_type: QuotaEntityArg,
+    /// Entity name, or "*" for the default of its type
+    #[arg(long)]
+    name: String,
+}
+
+impl QuotaEntityCmd {
+    fn entity(self) -> proto::QuotaEntity {
+        proto::QuotaEntity {
+            entity_type: proto::QuotaEntityType::from(self.entity_type) as i32,
+            name: self.name,
+        }
+    }
+}
+
+/// Limits left out are unlimited.
+#[derive(Args, Debug)]
+struct SetQuotaCmd {
+    #[command(flatten)]
+    entity: QuotaEntityCmd,
+    #[arg(long)]
+    produce_bytes_per_sec: Option<u64>,
+    #[arg(long)]
+    fetch_bytes_per_sec: Option<u64>,
+    #[arg(long)]
+    requests_per_sec: Option<u64>,
+}
+
+fn print_quota(quota: &proto::Quota) {
+    let entity = quota.entity.clone().unwrap_or_default();
+    let config = quota.config.unwrap_or_default();
+    let limit = |value: Option<u64>| value.map_or("unlimited".to_string(), |v| v.to_string());
+    println!(
+        "{}:{} produce_bytes_per_sec: {} fetch_bytes_per_sec: {} requests_per_sec: {}",
+        entity.entity_type().as_str_name(),
+        entity.name,
+        limit(config.produce_bytes_per_sec),
+        limit(config.fetch_bytes_per_sec),
+        limit(config.requests_per_sec)
+    );
+}
+
+fn print_acl(acl: &proto::AclBinding) {
+    println!(
+        "principal: {} resource: {}:{} pattern: {} operation: {} permission: {}",
+        acl.principal,
+        acl.resource_type().as_str_name(),
+        acl.resource_name,
+        acl.pattern_type().as_str_name(),
+        acl.operation().as_str_name(),
+        acl.permission().as_str_name()
+    );
 }
 
 #[derive(Args, Debug)]
@@ -61,6 +385,17 @@ struct GroupCmd {
     group_id: String,
 }
 
+/// Upper bounds shared by the fetch and subscribe commands; both are exclusive.
+#[derive(Args, Debug)]
+struct FetchBoundsArgs {
+    /// Stop before this offset
+    #[arg(long)]
+    to_offset: Option<u64>,
+    /// Leave out records stamped at or after this time (RFC3339)
+    #[arg(long)]
+    to_time: Option<String>,
+}
+
 #[derive(Args, Debug)]
 struct FetchOffsetCmd {
     #[arg(long)]
@@ -74,6 +409,8 @@ struct FetchOffsetCmd {
     max_records: u32,
     #[arg(long, default_value_t = true)]
     include_headers: bool,
+    #[command(flatten)]
+    bounds: FetchBoundsArgs,
 }
 
 #[derive(Args, Debug)]
@@ -89,6 +426,8 @@ struct FetchTimeCmd {
     max_records: u32,
     #[arg(long, default_value_t = true)]
     include_headers: bool,
+    #[command(flatten)]
+    bounds: FetchBoundsArgs,
 }
 
 #[derive(Args, Debug)]
@@ -115,6 +454,26 @@ struct HighWaterMarkCmd {
     topic: String,
 }
 
+#[derive(Args, Debug)]
+struct OffsetsCmd {
+    #[arg(long)]
+    topic: String,
+    /// Partition to report. Repeat for several; omit for all partitions.
+    #[arg(long)]
+    partition: Vec<u32>,
+    /// Also look up the first offset at or after this time (RFC3339)
+    #[arg(long)]
+    timestamp: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct HashPasswordCmd {
+    #[arg(long, env = "FLASHQ_NEW_PASSWORD")]
+    password: String,
+    #[arg(long, default_value_t = DEFAULT_SCRAM_ITERATIONS)]
+    iterations: u32,
+}
+
 #[derive(Args, Debug)]
 struct SubscribeCmd {
     #[arg(long)]
@@ -126,16 +485,19 @@ struct SubscribeCmd {
     from_offset: u64,
     #[arg(long, default_value_t = true)]
     include_headers: bool,
+    #[command(flatten)]
+    bounds: FetchBoundsArgs,
 }
 
-fn parse_headers(pairs: &[String]) -> HashMap<String, String> {
-    let mut out = HashMap::new();
-    for p in pairs {
-        if let Some((k, v)) = p.split_once('=') {
-            out.insert(k.to_string(), v.to_string());
-        }
-    }
-    out
+fn parse_headers(pairs: &[String]) -> Vec<proto::Header> {
+    pairs
+        .iter()
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| proto::Header {
+            key: k.to_string(),
+            value: v.as_bytes().to_vec(),
+        })
+        .collect()
 }
 
 fn print_record(r: &proto::RecordWithOffset) {
@@ -147,7 +509,12 @@ fn print_record(r: &proto::RecordWithOffset) {
             print!(" (key: {})", rec.key);
         }
         if !rec.headers.is_empty() {
-            print!(" (headers: {:?})", rec.headers);
+            let headers: Vec<String> = rec
+                .headers
+                .iter()
+                .map(|h| format!("{}={}", h.key, String::from_utf8_lossy(&h.value)))
+                .collect();
+            print!(" (headers: {})", headers.join(", "));
         }
         println!();
     }
@@ -159,12 +526,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let cli = Cli::parse();
     match cli.command {
+        Commands::HashPassword(args) => {
+            println!(
+                "{}",
+                ScramCredential::generate(&args.password, args.iterations)?
+            );
+        }
         Commands::Connect => {
-            let _clients = FlashqClient::connect(cli.addr).await?;
+            let _clients =
+                connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
             println!("connected");
         }
         Commands::Produce(args) => {
-            let clients = FlashqClient::connect(cli.addr.clone()).await?;
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
             let mut producer = clients.producer();
             let headers = parse_headers(&args.headers);
             let mut records = Vec::with_capacity(args.value.len());
@@ -173,17 +547,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     key: args.key.clone().unwrap_or_default(),
                     value: v,
                     headers: headers.clone(),
+                    timestamp: args.timestamp.clone().unwrap_or_default(),
                 });
             }
+            let compression = args
+                .compression
+                .map(proto::Compression::from)
+                .unwrap_or(proto::Compression::Unspecified);
             let req = proto::ProduceRequest {
                 topic: args.topic,
                 records,
+                compression: compression.into(),
             };
             let resp = producer.produce(req).await?.into_inner();
             println!("offset: {}\ntimestamp: {}", resp.offset, resp.timestamp);
         }
         Commands::CreateGroup(args) => {
-            let clients = FlashqClient::connect(cli.addr.clone()).await?;
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
             let mut consumer = clients.consumer();
             let req = proto::ConsumerGroupId {
                 group_id: args.group_id,
@@ -192,7 +572,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("group_id: {}", resp.group_id);
         }
         Commands::DeleteGroup(args) => {
-            let clients = FlashqClient::connect(cli.addr.clone()).await?;
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
             let mut consumer = clients.consumer();
             let req = proto::ConsumerGroupId {
                 group_id: args.group_id,
@@ -201,7 +581,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("deleted");
         }
         Commands::FetchOffset(args) => {
-            let clients = FlashqClient::connect(cli.addr.clone()).await?;
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
             let mut consumer = clients.consumer();
             let req = proto::FetchByOffsetRequest {
                 group_id: args.group_id,
@@ -209,6 +589,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 from_offset: args.from_offset,
                 max_records: args.max_records,
                 include_headers: args.include_headers,
+                to_offset: args.bounds.to_offset.unwrap_or_default(),
+                to_time: args.bounds.to_time.unwrap_or_default(),
             };
             let resp = consumer.fetch_by_offset(req).await?.into_inner();
             for r in &resp.records {
@@ -220,7 +602,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             );
         }
         Commands::FetchTime(args) => {
-            let clients = FlashqClient::connect(cli.addr.clone()).await?;
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
             let mut consumer = clients.consumer();
             let req = proto::FetchByTimeRequest {
                 group_id: args.group_id,
@@ -228,6 +610,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 from_time: args.from_time,
                 max_records: args.max_records,
                 include_headers: args.include_headers,
+                to_offset: args.bounds.to_offset.unwrap_or_default(),
+                to_time: args.bounds.to_time.unwrap_or_default(),
             };
             let resp = consumer.fetch_by_time(req).await?.into_inner();
             for r in &resp.records {
@@ -239,7 +623,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             );
         }
         Commands::CommitOffset(args) => {
-            let clients = FlashqClient::connect(cli.addr.clone()).await?;
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
             let mut consumer = clients.consumer();
             let req = proto::CommitOffsetRequest {
                 group_id: args.group_id,
@@ -253,7 +637,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             );
         }
         Commands::GetOffset(args) => {
-            let clients = FlashqClient::connect(cli.addr.clone()).await?;
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
             let mut consumer = clients.consumer();
             let req = proto::GetOffsetRequest {
                 group_id: args.group_id,
@@ -266,7 +650,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             );
         }
         Commands::ListTopics => {
-            let clients = FlashqClient::connect(cli.addr.clone()).await?;
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
             let mut admin = clients.admin();
             let resp = admin.list_topics(proto::Empty {}).await?.into_inner();
             for t in resp.topics {
@@ -274,7 +658,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
         Commands::HighWaterMark(args) => {
-            let clients = FlashqClient::connect(cli.addr.clone()).await?;
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
             let mut admin = clients.admin();
             let req = proto::HighWaterMarkRequest { topic: args.topic };
             let resp = admin.high_water_mark(req).await?.into_inner();
@@ -283,8 +667,98 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 resp.topic, resp.high_water_mark
             );
         }
+        Commands::Offsets(args) => {
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
+            let mut admin = clients.admin();
+            let req = proto::ListOffsetsRequest {
+                topic: args.topic,
+                partitions: args.partition,
+                timestamp: args.timestamp.unwrap_or_default(),
+            };
+            let resp = admin.list_offsets(req).await?.into_inner();
+            println!("topic: {}", resp.topic);
+            for p in resp.partitions {
+                let for_timestamp = p
+                    .offset_for_timestamp
+                    .map_or_else(|| "-".to_string(), |offset| offset.to_string());
+                println!(
+                    "partition: {} earliest: {} latest: {} offset_for_timestamp: {}",
+                    p.partition, p.earliest, p.latest, for_timestamp
+                );
+            }
+        }
+        Commands::CreateAcl(args) => {
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
+            let resp = clients
+                .admin()
+                .create_acls(proto::CreateAclsRequest {
+                    acls: vec![args.binding()],
+                })
+                .await?
+                .into_inner();
+            println!("created: {}", resp.created);
+        }
+        Commands::ListAcls(args) => {
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
+            let resp = clients
+                .admin()
+                .list_acls(proto::ListAclsRequest {
+                    filter: Some(args.filter()),
+                })
+                .await?
+                .into_inner();
+            resp.acls.iter().for_each(print_acl);
+        }
+        Commands::DeleteAcls(args) => {
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
+            let resp = clients
+                .admin()
+                .delete_acls(proto::DeleteAclsRequest {
+                    filter: Some(args.filter()),
+                })
+                .await?
+                .into_inner();
+            println!("deleted: {}", resp.deleted.len());
+            resp.deleted.iter().for_each(print_acl);
+        }
+        Commands::SetQuota(args) => {
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
+            let quota = clients
+                .admin()
+                .set_quota(proto::SetQuotaRequest {
+                    entity: Some(args.entity.entity()),
+                    config: Some(proto::QuotaConfig {
+                        produce_bytes_per_sec: args.produce_bytes_per_sec,
+                        fetch_bytes_per_sec: args.fetch_bytes_per_sec,
+                        requests_per_sec: args.requests_per_sec,
+                    }),
+                })
+                .await?
+                .into_inner();
+            print_quota(&quota);
+        }
+        Commands::DeleteQuota(args) => {
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
+            let resp = clients
+                .admin()
+                .delete_quota(proto::DeleteQuotaRequest {
+                    entity: Some(args.entity()),
+                })
+                .await?
+                .into_inner();
+            println!("deleted: {}", resp.deleted);
+        }
+        Commands::ListQuotas => {
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
+            let resp = clients
+                .admin()
+                .list_quotas(proto::Empty {})
+                .await?
+                .into_inner();
+            resp.quotas.iter().for_each(print_quota);
+        }
         Commands::Subscribe(args) => {
-            let clients = FlashqClient::connect(cli.addr.clone()).await?;
+            let clients = connect(&cli.addr, &cli.tls, &cli.auth, cli.client_id.as_deref()).await?;
             let mut consumer = clients.consumer();
             let req = proto::FetchByOffsetRequest {
                 group_id: args.group_id,
@@ -292,6 +766,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 from_offset: args.from_offset,
                 max_records: 100,
                 include_headers: args.include_headers,
+                to_offset: args.bounds.to_offset.unwrap_or_default(),
+                to_time: args.bounds.to_time.unwrap_or_default(),
             };
             let mut stream = consumer.subscribe(req).await?.into_inner();
             while let Some(item) = stream.message().await? {
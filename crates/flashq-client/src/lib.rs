@@ -3,11 +3,42 @@
 //! This crate provides a convenient client wrapper for connecting to FlashQ brokers
 //! and accessing Producer, Consumer, and Admin services.
 
+use flashq_proto::auth::CallCredentials;
+use flashq_proto::tls::ClientTlsOptions;
+use tonic::metadata::AsciiMetadataValue;
+use tonic::service::Interceptor;
+use tonic::service::interceptor::InterceptedService;
 use tonic::transport::{Channel, Endpoint};
+use tonic::{Request, Status};
+
+/// Channel that attaches the client's credentials and client id to every call.
+pub type AuthenticatedChannel = InterceptedService<Channel, CallMetadata>;
+
+/// Metadata a [`FlashqClient`] sends on every call: credentials, the client id, and the
+/// current span's W3C trace context.
+#[derive(Debug, Clone, Default)]
+pub struct CallMetadata {
+    credentials: CallCredentials,
+    client_id: Option<AsciiMetadataValue>,
+}
+
+impl Interceptor for CallMetadata {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        let mut request = self.credentials.call(request)?;
+        if let Some(client_id) = &self.client_id {
+            request
+                .metadata_mut()
+                .insert(flashq_proto::CLIENT_ID_METADATA_KEY, client_id.clone());
+        }
+        flashq_proto::trace_context::inject(request.metadata_mut());
+        Ok(request)
+    }
+}
 
 /// Convenience wrapper that provides typed clients for all services using a shared channel.
 pub struct FlashqClient {
     channel: Channel,
+    metadata: CallMetadata,
 }
 
 impl FlashqClient {
@@ -17,18 +48,63 @@ impl FlashqClient {
         D::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
     {
         let channel = Endpoint::new(dst)?.connect().await?;
-        Ok(Self { channel })
+        Ok(Self::from_channel(channel))
+    }
+
+    /// Connect over TLS, e.g. to "https://127.0.0.1:50051", verifying the broker against the
+    /// CA bundle in `tls` and presenting its client identity when the broker requires one.
+    pub async fn connect_with_tls<D: TryInto<Endpoint>>(
+        dst: D,
+        tls: &ClientTlsOptions,
+    ) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        D::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let channel = Endpoint::new(dst)?
+            .tls_config(tls.load()?)?
+            .connect()
+            .await?;
+        Ok(Self::from_channel(channel))
+    }
+
+    fn from_channel(channel: Channel) -> Self {
+        Self {
+            channel,
+            metadata: CallMetadata::default(),
+        }
+    }
+
+    /// Send `credentials` on every call made through this client's service clients.
+    pub fn with_credentials(mut self, credentials: CallCredentials) -> Self {
+        self.metadata.credentials = credentials;
+        self
+    }
+
+    /// Identify calls as coming from `client_id`, which brokers use to apply per-client
+    /// quotas. Fails if the id is not printable ASCII.
+    pub fn with_client_id(mut self, client_id: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        self.metadata.client_id = Some(client_id.parse()?);
+        Ok(self)
     }
 
-    pub fn producer(&self) -> flashq_proto::producer_client::ProducerClient<Channel> {
-        flashq_proto::producer_client::ProducerClient::new(self.channel.clone())
+    pub fn producer(&self) -> flashq_proto::producer_client::ProducerClient<AuthenticatedChannel> {
+        flashq_proto::producer_client::ProducerClient::with_interceptor(
+            self.channel.clone(),
+            self.metadata.clone(),
+        )
     }
 
-    pub fn consumer(&self) -> flashq_proto::consumer_client::ConsumerClient<Channel> {
-        flashq_proto::consumer_client::ConsumerClient::new(self.channel.clone())
+    pub fn consumer(&self) -> flashq_proto::consumer_client::ConsumerClient<AuthenticatedChannel> {
+        flashq_proto::consumer_client::ConsumerClient::with_interceptor(
+            self.channel.clone(),
+            self.metadata.clone(),
+        )
     }
 
-    pub fn admin(&self) -> flashq_proto::admin_client::AdminClient<Channel> {
-        flashq_proto::admin_client::AdminClient::new(self.channel.clone())
+    pub fn admin(&self) -> flashq_proto::admin_client::AdminClient<AuthenticatedChannel> {
+        flashq_proto::admin_client::AdminClient::with_interceptor(
+            self.channel.clone(),
+            self.metadata.clone(),
+        )
     }
 }
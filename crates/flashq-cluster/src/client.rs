@@ -1,7 +1,10 @@
+use flashq_proto::auth::CallCredentials;
+use flashq_proto::tls::ClientTlsOptions;
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio_stream::StreamExt;
 use tokio_stream::wrappers::ReceiverStream;
+use tonic::service::interceptor::InterceptedService;
 use tonic::transport::{Channel, Endpoint};
 use tonic::{Request, Status};
 
@@ -18,7 +21,8 @@ use crate::proto::{
 /// cluster coordinators, handling connection management and error conversion.
 #[derive(Debug, Clone)]
 pub struct ClusterClient {
-    client: TonicClusterClient<Channel>,
+    channel: Channel,
+    client: TonicClusterClient<InterceptedService<Channel, CallCredentials>>,
 }
 
 impl ClusterClient {
@@ -28,7 +32,7 @@ impl ClusterClient {
         D: TryInto<Endpoint>,
         D::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
     {
-        Self::connect_with_endpoint_config(dst, |endpoint| endpoint).await
+        Self::connect_with_endpoint_config(dst, Ok).await
     }
 
     /// Connect to a cluster service with custom connection timeout.
@@ -37,7 +41,33 @@ impl ClusterClient {
         D: TryInto<Endpoint>,
         D::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
     {
-        Self::connect_with_endpoint_config(dst, |endpoint| endpoint.timeout(timeout)).await
+        Self::connect_with_endpoint_config(dst, |endpoint| Ok(endpoint.timeout(timeout))).await
+    }
+
+    /// Connect to a cluster service over TLS, verifying the server against the CA bundle in
+    /// `tls` and presenting its client identity when one is set.
+    pub async fn connect_with_tls<D>(
+        dst: D,
+        tls: &ClientTlsOptions,
+        timeout: Option<Duration>,
+    ) -> Result<Self, ClusterError>
+    where
+        D: TryInto<Endpoint>,
+        D::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let tls_config = tls
+            .load()
+            .map_err(|e| ClusterError::from_transport_error(e, "Failed to load TLS settings"))?;
+        Self::connect_with_endpoint_config(dst, |endpoint| {
+            let endpoint = endpoint
+                .tls_config(tls_config)
+                .map_err(|e| ClusterError::from_transport_error(e, "Invalid TLS settings"))?;
+            Ok(match timeout {
+                Some(timeout) => endpoint.timeout(timeout),
+                None => endpoint,
+            })
+        })
+        .await
     }
 
     /// Internal helper method to reduce duplication between connect methods.
@@ -45,26 +75,38 @@ impl ClusterClient {
     where
         D: TryInto<Endpoint>,
         D::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
-        F: FnOnce(Endpoint) -> Endpoint,
+        F: FnOnce(Endpoint) -> Result<Endpoint, ClusterError>,
     {
         let endpoint = dst
             .try_into()
             .map_err(|e| ClusterError::from_transport_error(e.into(), "Invalid endpoint"))?;
 
-        let configured_endpoint = config_fn(endpoint);
+        let configured_endpoint = config_fn(endpoint)?;
 
-        let client = TonicClusterClient::connect(configured_endpoint)
+        // Connect the endpoint itself: the generated `connect` rebuilds https endpoints with
+        // default TLS roots, dropping any CA bundle or identity set above.
+        let channel = configured_endpoint
+            .connect()
             .await
             .map_err(|e| ClusterError::from_transport_error(e, "Failed to connect"))?;
 
-        Ok(Self { client })
+        Ok(Self {
+            client: TonicClusterClient::with_interceptor(channel.clone(), CallCredentials::None),
+            channel,
+        })
+    }
+
+    /// Send `credentials` on every call, for clusters whose brokers require authentication.
+    pub fn with_credentials(mut self, credentials: CallCredentials) -> Self {
+        self.client = TonicClusterClient::with_interceptor(self.channel.clone(), credentials);
+        self
     }
 
     /// Get information about the cluster state.
     ///
     /// Returns details about all brokers, topics, and partition assignments.
     pub async fn describe_cluster(&mut self) -> Result<DescribeClusterResponse, ClusterError> {
-        let request = Request::new(DescribeClusterRequest {});
+        let request = traced_request(DescribeClusterRequest {});
 
         let response = self
             .client
@@ -93,7 +135,7 @@ impl ClusterClient {
 
         let response_stream = self
             .client
-            .heartbeat(Request::new(request_stream))
+            .heartbeat(traced_request(request_stream))
             .await
             .map_err(status_to_cluster_error)?
             .into_inner();
@@ -113,7 +155,7 @@ impl ClusterClient {
     ) -> Result<ReportPartitionStatusResponse, ClusterError> {
         let response = self
             .client
-            .report_partition_status(Request::new(request))
+            .report_partition_status(traced_request(request))
             .await
             .map_err(status_to_cluster_error)?;
 
@@ -123,16 +165,25 @@ impl ClusterClient {
     /// Get a mutable reference to the underlying tonic client.
     ///
     /// This allows access to lower-level tonic functionality if needed.
-    pub fn client_mut(&mut self) -> &mut TonicClusterClient<Channel> {
+    pub fn client_mut(
+        &mut self,
+    ) -> &mut TonicClusterClient<InterceptedService<Channel, CallCredentials>> {
         &mut self.client
     }
 
     /// Get a reference to the underlying tonic client.
-    pub fn client(&self) -> &TonicClusterClient<Channel> {
+    pub fn client(&self) -> &TonicClusterClient<InterceptedService<Channel, CallCredentials>> {
         &self.client
     }
 }
 
+/// Wrap `message` in a request carrying the current span's trace context.
+fn traced_request<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    flashq_proto::trace_context::inject(request.metadata_mut());
+    request
+}
+
 /// Convert a tonic Status to a ClusterError.
 fn status_to_cluster_error(status: Status) -> ClusterError {
     match status.code() {
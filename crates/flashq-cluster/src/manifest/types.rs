@@ -1,6 +1,6 @@
 //! Cluster manifest data structures.
 
-use crate::{ClusterError, types::*};
+use crate::{ClusterError, proto::DescribeClusterResponse, types::*};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -78,6 +78,77 @@ impl Default for ClusterManifest {
     }
 }
 
+/// Rebuild a manifest from a controller's cluster description, as followers do on resync.
+///
+/// The description carries no replication factor, so each topic's is taken from its widest
+/// replica set.
+impl TryFrom<DescribeClusterResponse> for ClusterManifest {
+    type Error = ClusterError;
+
+    fn try_from(response: DescribeClusterResponse) -> Result<Self, Self::Error> {
+        let brokers = response
+            .brokers
+            .into_iter()
+            .map(|broker| {
+                let port = u16::try_from(broker.port).map_err(|e| {
+                    ClusterError::from_parse_error(
+                        e,
+                        &format!("port of broker {}", broker.broker_id),
+                    )
+                })?;
+                Ok(BrokerSpec {
+                    id: BrokerId::from(broker.broker_id),
+                    host: broker.host,
+                    port,
+                })
+            })
+            .collect::<Result<Vec<_>, ClusterError>>()?;
+
+        let topics = response
+            .topics
+            .into_iter()
+            .map(|topic| {
+                let widest = topic
+                    .partitions
+                    .iter()
+                    .map(|partition| partition.replicas.len())
+                    .max()
+                    .unwrap_or(0);
+                let replication_factor = u8::try_from(widest).map_err(|e| {
+                    ClusterError::from_parse_error(
+                        e,
+                        &format!("replication factor of topic '{}'", topic.topic),
+                    )
+                })?;
+                let partitions = topic
+                    .partitions
+                    .into_iter()
+                    .map(|partition| PartitionAssignment {
+                        id: PartitionId::new(partition.partition),
+                        leader: BrokerId::from(partition.leader),
+                        replicas: partition.replicas.into_iter().map(BrokerId::from).collect(),
+                        in_sync_replicas: partition
+                            .in_sync_replicas
+                            .into_iter()
+                            .map(BrokerId::from)
+                            .collect(),
+                        epoch: Epoch::from(partition.epoch),
+                    })
+                    .collect();
+                Ok((
+                    topic.topic,
+                    TopicAssignment {
+                        partitions,
+                        replication_factor,
+                    },
+                ))
+            })
+            .collect::<Result<HashMap<_, _>, ClusterError>>()?;
+
+        Ok(Self { brokers, topics })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,4 +233,45 @@ mod tests {
             Err(ClusterError::PartitionNotFound { .. })
         ));
     }
+
+    #[test]
+    fn test_manifest_from_cluster_description() {
+        use crate::proto::{BrokerInfo, PartitionInfo, TopicAssignment as TopicInfo};
+
+        let broker = |broker_id: u32, port: u32| BrokerInfo {
+            broker_id,
+            host: "127.0.0.1".to_string(),
+            port,
+            ..Default::default()
+        };
+        let mut response = DescribeClusterResponse {
+            brokers: vec![broker(1, 6001), broker(2, 6002)],
+            topics: vec![TopicInfo {
+                topic: "orders".to_string(),
+                partitions: vec![PartitionInfo {
+                    topic: "orders".to_string(),
+                    partition: 0,
+                    leader: 1,
+                    replicas: vec![1, 2],
+                    in_sync_replicas: vec![1, 2],
+                    epoch: 4,
+                }],
+            }],
+            controller_id: 1,
+        };
+
+        // Only the replica count survives the trip, so it stands in for the replication factor
+        let mut expected = create_test_manifest();
+        expected
+            .topics
+            .get_mut("orders")
+            .unwrap()
+            .replication_factor = 2;
+        let manifest = ClusterManifest::try_from(response.clone()).unwrap();
+        assert_eq!(manifest, expected);
+
+        response.brokers[1].port = 70000;
+        let result = ClusterManifest::try_from(response);
+        assert!(matches!(result, Err(ClusterError::InvalidManifest { .. })));
+    }
 }
@@ -1,6 +1,8 @@
+use flashq_proto::trace_context::{self, OpenTelemetrySpanExt};
 use std::sync::Arc;
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status, Streaming};
+use tracing::Instrument;
 
 use crate::error::ClusterError;
 use crate::proto::{
@@ -60,7 +62,18 @@ impl<T: ClusterService + 'static> Cluster for ClusterServer<T> {
             while let Some(result) = stream.message().await.transpose() {
                 match result {
                     Ok(heartbeat_request) => {
-                        match cluster_service.handle_heartbeat(heartbeat_request).await {
+                        let span = tracing::info_span!(
+                            "cluster_heartbeat",
+                            broker_id = heartbeat_request.broker_id
+                        );
+                        span.set_parent(trace_context::extract_map(
+                            &heartbeat_request.trace_context,
+                        ));
+                        match cluster_service
+                            .handle_heartbeat(heartbeat_request)
+                            .instrument(span)
+                            .await
+                        {
                             Ok(response) => {
                                 if tx.send(Ok(response)).await.is_err() {
                                     // Client disconnected
@@ -6,10 +6,12 @@
 use async_trait::async_trait;
 use std::collections::HashSet;
 use std::sync::Arc;
+use tracing::Instrument;
 
 use crate::{
     ClusterError,
     client::ClusterClient,
+    manifest::ClusterManifest,
     metadata_store::MetadataStore,
     proto::{
         BrokerDirective, BrokerInfo, BrokerStatus, DescribeClusterResponse, HeartbeatRequest,
@@ -157,6 +159,9 @@ impl ClusterServiceImpl {
     ) -> Result<(), ClusterError> {
         use tokio_stream::StreamExt;
 
+        // Resyncs describe the cluster over their own handle while the stream holds `client`
+        let mut resync_client = client.clone();
+
         // Start the heartbeat stream
         let (sender, mut receiver) = client.start_heartbeat_stream().await?;
         tracing::info!(%broker_id, "Heartbeat stream established with controller");
@@ -174,7 +179,9 @@ impl ClusterServiceImpl {
                         &sender,
                         metadata_store,
                         flashq_broker,
-                    ).await {
+                    )
+                    .instrument(tracing::info_span!("cluster_heartbeat", %broker_id))
+                    .await {
                         Ok(()) => {
                             tracing::trace!(%broker_id, "Heartbeat sent on stream");
                         }
@@ -196,12 +203,47 @@ impl ClusterServiceImpl {
                                 "Received heartbeat response from controller"
                             );
 
-                            if let Err(e) = Self::process_heartbeat_response(
+                            let was_draining = flashq_broker.is_some_and(|broker| broker.is_draining());
+                            let resync = match Self::process_heartbeat_response(
                                 metadata_store,
                                 flashq_broker,
                                 heartbeat_response,
                             ).await {
-                                tracing::error!(%broker_id, error = %e, "Failed to process heartbeat response");
+                                Ok(resync) => resync,
+                                Err(e) => {
+                                    tracing::error!(%broker_id, error = %e, "Failed to process heartbeat response");
+                                    false
+                                }
+                            };
+                            // A failed resync is retried when the next stale heartbeat draws another directive
+                            let resynced = resync && match Self::resync_from_controller(
+                                broker_id,
+                                &mut resync_client,
+                                metadata_store,
+                                flashq_broker,
+                            )
+                            .instrument(tracing::info_span!("cluster_resync", %broker_id))
+                            .await {
+                                Ok(()) => true,
+                                Err(e) => {
+                                    tracing::error!(%broker_id, error = %e, "Failed to resync with controller");
+                                    false
+                                }
+                            };
+                            // Tell the controller straight away once a directive starts a drain, and
+                            // report the refreshed epochs after a resync
+                            let started_draining = !was_draining
+                                && flashq_broker.is_some_and(|broker| broker.is_draining());
+                            if started_draining || resynced {
+                                if let Err(e) = Self::send_heartbeat_on_stream(
+                                    broker_id,
+                                    &sender,
+                                    metadata_store,
+                                    flashq_broker,
+                                )
+                                .await {
+                                    tracing::warn!(%broker_id, error = %e, "Failed to send follow-up heartbeat on stream");
+                                }
                             }
                         }
                         Some(Err(e)) => {
@@ -231,11 +273,15 @@ impl ClusterServiceImpl {
         let partition_heartbeats =
             Self::collect_partition_heartbeats(metadata_store, broker_id, flashq_broker).await;
 
-        // 2. Create and send HeartbeatRequest
+        // 2. Create and send HeartbeatRequest, carrying this heartbeat's trace context
+        let mut trace_context = std::collections::HashMap::new();
+        flashq_proto::trace_context::inject_map(&mut trace_context);
         let request = HeartbeatRequest {
             broker_id: broker_id.into(),
             partitions: partition_heartbeats,
             timestamp: chrono::Utc::now().to_rfc3339(),
+            trace_context,
+            is_draining: flashq_broker.is_some_and(|broker| broker.is_draining()),
         };
 
         tracing::debug!(
@@ -321,11 +367,16 @@ impl ClusterServiceImpl {
     }
 
     /// Process heartbeat response from controller.
+    ///
+    /// Returns whether the controller asked for a resync, which needs the cluster client and
+    /// is left to the heartbeat task. Several stale partitions still mean a single resync.
     async fn process_heartbeat_response(
         metadata_store: &Arc<dyn MetadataStore>,
         flashq_broker: Option<&Arc<dyn ClusterBroker>>,
         response: HeartbeatResponse,
-    ) -> Result<(), ClusterError> {
+    ) -> Result<bool, ClusterError> {
+        let mut resync = false;
+
         // Process epoch updates
         for epoch_update in response.epoch_updates {
             let topic = &epoch_update.topic;
@@ -355,15 +406,17 @@ impl ClusterServiceImpl {
                 }
                 Ok(BrokerDirective::Resync) => {
                     tracing::info!("Received RESYNC directive from controller");
-                    // TODO: Implement resync logic - fetch fresh cluster state
-                    // This would typically involve refreshing partition assignments
-                    // and synchronizing with the controller's view of the cluster
+                    resync = true;
                 }
                 Ok(BrokerDirective::Drain) => {
                     tracing::info!("Received DRAIN directive from controller");
-                    // TODO: Implement drain logic - stop accepting new requests
-                    // This would involve gracefully stopping new producer requests
-                    // while allowing existing operations to complete
+                    if let Some(broker) = flashq_broker {
+                        if let Err(e) = broker.initiate_drain().await {
+                            tracing::error!("Failed to drain broker: {}", e);
+                        }
+                    } else {
+                        tracing::info!("No FlashQ broker instance available for drain");
+                    }
                 }
                 Ok(BrokerDirective::Shutdown) => {
                     tracing::warn!("Received SHUTDOWN directive from controller");
@@ -381,9 +434,57 @@ impl ClusterServiceImpl {
             }
         }
 
+        Ok(resync)
+    }
+
+    /// Replace local cluster metadata with the controller's view.
+    ///
+    /// Fetches the cluster description, reloads the metadata store from it, and has the broker
+    /// step down from any partition it led before but no longer does.
+    async fn resync_from_controller(
+        broker_id: BrokerId,
+        client: &mut ClusterClient,
+        metadata_store: &Arc<dyn MetadataStore>,
+        flashq_broker: Option<&Arc<dyn ClusterBroker>>,
+    ) -> Result<(), ClusterError> {
+        let led_before = Self::led_partitions(metadata_store, broker_id);
+
+        let cluster = client.describe_cluster().await?;
+        metadata_store.load_from_manifest(ClusterManifest::try_from(cluster)?)?;
+
+        let led_after = Self::led_partitions(metadata_store, broker_id);
+        for (topic, partition_id) in led_before.difference(&led_after) {
+            tracing::info!(%topic, %partition_id, "Stepping down as partition leader after resync");
+            if let Some(broker) = flashq_broker {
+                broker.step_down(topic, *partition_id).await?;
+            }
+        }
+
+        tracing::info!(
+            %broker_id,
+            leading = led_after.len(),
+            "Resynced cluster metadata from controller"
+        );
         Ok(())
     }
 
+    /// Partitions this broker leads according to the metadata store.
+    fn led_partitions(
+        metadata_store: &Arc<dyn MetadataStore>,
+        broker_id: BrokerId,
+    ) -> HashSet<(String, PartitionId)> {
+        metadata_store
+            .get_broker_partitions(broker_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(topic, partition_id)| {
+                metadata_store
+                    .get_partition_leader(topic, *partition_id)
+                    .is_ok_and(|leader| leader == broker_id)
+            })
+            .collect()
+    }
+
     /// Report partition status change to the controller.
     pub async fn report_partition_status_to_controller(
         &self,
@@ -517,9 +618,8 @@ impl ClusterService for ClusterServiceImpl {
             .map_err(|e| ClusterError::from_parse_error(e, "parsing heartbeat timestamp"))?
             .with_timezone(&chrono::Utc);
 
-        // Record broker heartbeat (assume not draining unless explicitly specified)
         self.metadata_store
-            .record_broker_heartbeat(broker_id, timestamp, false)?;
+            .record_broker_heartbeat(broker_id, timestamp, request.is_draining)?;
 
         let mut epoch_updates = Vec::new();
         let mut directives = Vec::new();
@@ -717,6 +817,7 @@ mod tests {
     use super::*;
     use crate::manifest::{BrokerSpec, ClusterManifest};
     use crate::metadata_store::InMemoryMetadataStore;
+    use std::sync::atomic::{AtomicBool, Ordering};
 
     fn create_test_service() -> ClusterServiceImpl {
         let metadata_store = Arc::new(InMemoryMetadataStore::new());
@@ -755,6 +856,8 @@ mod tests {
             broker_id: 1,
             partitions: vec![],
             timestamp: chrono::Utc::now().to_rfc3339(),
+            trace_context: Default::default(),
+            is_draining: false,
         };
 
         let response = service.handle_heartbeat(request).await.unwrap();
@@ -762,6 +865,112 @@ mod tests {
         assert_eq!(response.directives.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_heartbeat_reports_draining_broker() {
+        let service = create_test_service();
+        service
+            .metadata_store()
+            .load_from_manifest(ClusterManifest {
+                brokers: vec![BrokerSpec {
+                    id: BrokerId::from(1),
+                    host: "localhost".to_string(),
+                    port: 9092,
+                }],
+                topics: std::collections::HashMap::new(),
+            })
+            .unwrap();
+
+        service
+            .handle_heartbeat(HeartbeatRequest {
+                broker_id: 1,
+                partitions: vec![],
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                trace_context: Default::default(),
+                is_draining: true,
+            })
+            .await
+            .unwrap();
+
+        let cluster = service.describe_cluster().await.unwrap();
+        assert!(cluster.brokers[0].status.as_ref().unwrap().is_draining);
+    }
+
+    /// Records which lifecycle calls a directive triggered.
+    #[derive(Default)]
+    struct LifecycleBroker {
+        drained: AtomicBool,
+        shut_down: AtomicBool,
+    }
+
+    #[async_trait]
+    impl ClusterBroker for LifecycleBroker {
+        async fn get_high_water_mark(&self, _: &str, _: PartitionId) -> Result<u64, ClusterError> {
+            Ok(0)
+        }
+
+        async fn get_log_start_offset(&self, _: &str, _: PartitionId) -> Result<u64, ClusterError> {
+            Ok(0)
+        }
+
+        async fn acknowledge_replication(
+            &self,
+            _: &str,
+            _: PartitionId,
+            _: u64,
+        ) -> Result<(), ClusterError> {
+            Ok(())
+        }
+
+        async fn step_down(&self, _: &str, _: PartitionId) -> Result<(), ClusterError> {
+            Ok(())
+        }
+
+        async fn initiate_drain(&self) -> Result<(), ClusterError> {
+            self.drained.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn initiate_shutdown(&self) -> Result<(), ClusterError> {
+            self.shut_down.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn is_draining(&self) -> bool {
+            self.drained.load(Ordering::SeqCst) || self.shut_down.load(Ordering::SeqCst)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drain_and_shutdown_directives_reach_broker() {
+        let metadata_store: Arc<dyn MetadataStore> = Arc::new(InMemoryMetadataStore::new());
+        let lifecycle = Arc::new(LifecycleBroker::default());
+        let broker: Arc<dyn ClusterBroker> = lifecycle.clone();
+        let response = |directive: BrokerDirective| HeartbeatResponse {
+            epoch_updates: vec![],
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            directives: vec![directive as i32],
+        };
+
+        ClusterServiceImpl::process_heartbeat_response(
+            &metadata_store,
+            Some(&broker),
+            response(BrokerDirective::Drain),
+        )
+        .await
+        .unwrap();
+        assert!(broker.is_draining());
+        assert!(!lifecycle.shut_down.load(Ordering::SeqCst));
+
+        ClusterServiceImpl::process_heartbeat_response(
+            &metadata_store,
+            Some(&broker),
+            response(BrokerDirective::Shutdown),
+        )
+        .await
+        .unwrap();
+        assert!(lifecycle.shut_down.load(Ordering::SeqCst));
+    }
+
     #[tokio::test]
     async fn test_report_partition_status() {
         let service = create_test_service();
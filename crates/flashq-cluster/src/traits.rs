@@ -43,8 +43,17 @@ pub trait ClusterBroker: Send + Sync {
         offset: u64,
     ) -> Result<(), ClusterError>;
 
+    /// Give up leadership of a partition the controller has assigned to another broker.
+    async fn step_down(&self, topic: &str, partition: PartitionId) -> Result<(), ClusterError>;
+
+    /// Stop accepting produces while continuing to serve fetches.
+    async fn initiate_drain(&self) -> Result<(), ClusterError>;
+
     /// Initiate graceful shutdown of the broker.
     async fn initiate_shutdown(&self) -> Result<(), ClusterError>;
+
+    /// Whether the broker is draining or shutting down, reported in its heartbeats.
+    fn is_draining(&self) -> bool;
 }
 
 /// Defines the cluster coordination service interface for broker communication.
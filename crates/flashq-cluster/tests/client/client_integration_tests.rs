@@ -109,6 +109,8 @@ async fn test_client_heartbeat_stream() {
             leader_override: None,
         }],
         timestamp: Utc::now().to_rfc3339(),
+        trace_context: Default::default(),
+        is_draining: false,
     };
 
     sender.send(heartbeat_request).await.unwrap();
@@ -159,6 +161,8 @@ async fn test_client_multiple_heartbeats() {
                 leader_override: None,
             }],
             timestamp: Utc::now().to_rfc3339(),
+            trace_context: Default::default(),
+            is_draining: false,
         };
 
         sender.send(heartbeat_request).await.unwrap();
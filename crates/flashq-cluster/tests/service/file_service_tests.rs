@@ -62,6 +62,8 @@ async fn test_heartbeat_with_file_persistence() {
             leader_override: None,
         }],
         timestamp: Utc::now().to_rfc3339(),
+        trace_context: Default::default(),
+        is_draining: false,
     };
 
     let response = service.handle_heartbeat(request).await.unwrap();
@@ -136,6 +138,8 @@ async fn test_multiple_brokers_heartbeat_with_file_store() {
             broker_id,
             partitions: vec![],
             timestamp: Utc::now().to_rfc3339(),
+            trace_context: Default::default(),
+            is_draining: false,
         };
 
         let response = service.handle_heartbeat(request).await.unwrap();
@@ -177,6 +181,8 @@ async fn test_service_restart_preserves_cluster_state() {
                 broker_id,
                 partitions: vec![],
                 timestamp: Utc::now().to_rfc3339(),
+                trace_context: Default::default(),
+                is_draining: false,
             };
             service.handle_heartbeat(request).await.unwrap();
         }
@@ -252,6 +258,8 @@ async fn test_concurrent_operations_with_file_persistence() {
                         leader_override: None,
                     }],
                     timestamp: Utc::now().to_rfc3339(),
+                    trace_context: Default::default(),
+                    is_draining: false,
                 };
 
                 let _response = service_clone.handle_heartbeat(request).await.unwrap();
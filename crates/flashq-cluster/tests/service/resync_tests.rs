@@ -0,0 +1,119 @@
+//! Integration tests for follower resync against an in-process controller.
+//!
+//! The follower starts from stale partition metadata, draws a RESYNC directive with its
+//! first heartbeat, and must converge on the controller's view of the cluster.
+
+use flashq_cluster::{
+    client::ClusterClient,
+    manifest::ClusterManifest,
+    metadata_store::{InMemoryMetadataStore, MetadataStore},
+    service::ClusterServiceImpl,
+    types::*,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::test_utilities::{
+    MockFlashQBroker, TestServerConfig, create_test_manifest, start_test_server,
+};
+
+const TOPIC: &str = "test-topic";
+
+/// Start a controller whose copy of partition 1 has moved on to `leader` at epoch 3, and a
+/// follower (broker 2) that still believes it leads partition 1 at epoch 1.
+async fn start_stale_follower(
+    leader: BrokerId,
+) -> (
+    Arc<dyn MetadataStore>,
+    Arc<MockFlashQBroker>,
+    tokio::task::JoinHandle<()>,
+) {
+    let mut controller_manifest: ClusterManifest = create_test_manifest(None);
+    let partition = &mut controller_manifest
+        .topics
+        .get_mut(TOPIC)
+        .unwrap()
+        .partitions[1];
+    partition.leader = leader;
+    partition.epoch = Epoch(3);
+    let controller_store =
+        Arc::new(InMemoryMetadataStore::new_with_manifest(controller_manifest).unwrap());
+    let (server_addr, shutdown_handle) = start_test_server(TestServerConfig {
+        service: ClusterServiceImpl::new(controller_store, BrokerId(1)),
+        port: 0,
+    })
+    .await;
+
+    let follower_store: Arc<dyn MetadataStore> =
+        Arc::new(InMemoryMetadataStore::new_with_manifest(create_test_manifest(None)).unwrap());
+    let broker = Arc::new(MockFlashQBroker::new());
+    broker.add_partition(TOPIC, PartitionId::new(0), 100, 0, false);
+    broker.add_partition(TOPIC, PartitionId::new(1), 50, 0, true);
+
+    let follower = ClusterServiceImpl::with_client_and_broker(
+        follower_store.clone(),
+        ClusterClient::connect(server_addr).await.unwrap(),
+        BrokerId(2),
+        broker.clone(),
+    );
+    follower.start_follower_heartbeat_task().await.unwrap();
+
+    (follower_store, broker, shutdown_handle)
+}
+
+/// Wait for the follower's copy of partition 1 to reach the controller's epoch.
+async fn wait_for_resync(store: &Arc<dyn MetadataStore>) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while store
+            .get_partition_epoch(TOPIC, PartitionId::new(1))
+            .unwrap()
+            != Epoch(3)
+        {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("follower resynced with controller");
+}
+
+fn is_leader(broker: &MockFlashQBroker, partition: u32) -> bool {
+    broker.partitions.lock().unwrap()[&(TOPIC.to_string(), PartitionId::new(partition))].2
+}
+
+#[tokio::test]
+async fn test_stale_follower_resyncs_and_steps_down() {
+    let (follower_store, broker, _shutdown_handle) = start_stale_follower(BrokerId(3)).await;
+
+    wait_for_resync(&follower_store).await;
+
+    assert_eq!(
+        follower_store
+            .get_partition_leader(TOPIC, PartitionId::new(1))
+            .unwrap(),
+        BrokerId(3)
+    );
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while is_leader(&broker, 1) {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("follower stepped down from partition 1");
+    // Partition 0 was never led by the follower, so it is left alone
+    assert!(!is_leader(&broker, 0));
+}
+
+#[tokio::test]
+async fn test_resync_keeps_leadership_the_controller_confirms() {
+    let (follower_store, broker, _shutdown_handle) = start_stale_follower(BrokerId(2)).await;
+
+    wait_for_resync(&follower_store).await;
+
+    assert_eq!(
+        follower_store
+            .get_partition_leader(TOPIC, PartitionId::new(1))
+            .unwrap(),
+        BrokerId(2)
+    );
+    assert!(is_leader(&broker, 1));
+}
@@ -7,4 +7,5 @@ mod test_utilities;
 
 mod service {
     pub mod file_service_tests;
+    pub mod resync_tests;
 }
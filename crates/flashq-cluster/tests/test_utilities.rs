@@ -39,6 +39,8 @@ pub struct MockFlashQBroker {
     pub partitions: Mutex<PartitionMap>,
     /// Whether the broker should return errors for testing failure scenarios
     pub should_error: Mutex<bool>,
+    /// Whether a drain or shutdown has been initiated
+    pub draining: Mutex<bool>,
 }
 
 #[allow(dead_code)]
@@ -55,6 +57,7 @@ impl MockFlashQBroker {
         Self {
             partitions: Mutex::new(HashMap::new()),
             should_error: Mutex::new(false),
+            draining: Mutex::new(false),
         }
     }
 
@@ -77,6 +80,7 @@ impl MockFlashQBroker {
         Self {
             partitions: Mutex::new(partitions),
             should_error: Mutex::new(false),
+            draining: Mutex::new(false),
         }
     }
 
@@ -176,6 +180,33 @@ impl ClusterBroker for MockFlashQBroker {
         Ok(())
     }
 
+    async fn step_down(&self, topic: &str, partition: PartitionId) -> Result<(), ClusterError> {
+        if *self.should_error.lock().unwrap() {
+            return Err(ClusterError::Transport {
+                context: "Mock broker error".to_string(),
+                reason: "Simulated failure".to_string(),
+            });
+        }
+
+        let mut partitions = self.partitions.lock().unwrap();
+        if let Some((_hwm, _lso, is_leader)) = partitions.get_mut(&(topic.to_string(), partition)) {
+            *is_leader = false;
+        }
+        Ok(())
+    }
+
+    async fn initiate_drain(&self) -> Result<(), ClusterError> {
+        if *self.should_error.lock().unwrap() {
+            return Err(ClusterError::Transport {
+                context: "Mock broker error".to_string(),
+                reason: "Simulated failure".to_string(),
+            });
+        }
+
+        *self.draining.lock().unwrap() = true;
+        Ok(())
+    }
+
     async fn initiate_shutdown(&self) -> Result<(), ClusterError> {
         if *self.should_error.lock().unwrap() {
             return Err(ClusterError::Transport {
@@ -184,9 +215,13 @@ impl ClusterBroker for MockFlashQBroker {
             });
         }
 
-        // Mock implementation - just return success
+        *self.draining.lock().unwrap() = true;
         Ok(())
     }
+
+    fn is_draining(&self) -> bool {
+        *self.draining.lock().unwrap()
+    }
 }
 
 /// Configuration options for creating test manifests
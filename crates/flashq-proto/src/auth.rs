@@ -0,0 +1,228 @@
+//! Credentials carried on FlashQ gRPC calls.
+//!
+//! Clients send one `authorization` metadata entry per call: `Bearer <token>` for API keys or
+//! `Basic <base64(username:password)>` for password users. Brokers keep password users as
+//! SCRAM-SHA-256 style salted keys, so the credentials file never holds a password.
+
+use std::fmt;
+use std::num::NonZeroU32;
+use std::str::FromStr;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use ring::rand::SecureRandom;
+use ring::{digest, hmac, pbkdf2};
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::{Request, Status};
+
+pub const AUTHORIZATION_METADATA_KEY: &str = "authorization";
+
+/// Iterations used by [`ScramCredential::generate`], matching the SCRAM minimum.
+pub const DEFAULT_SCRAM_ITERATIONS: u32 = 4096;
+
+/// Credentials a client attaches to every call. Implements [`tonic::service::Interceptor`],
+/// so it can wrap any generated client.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub enum CallCredentials {
+    #[default]
+    None,
+    Token(String),
+    Password {
+        username: String,
+        password: String,
+    },
+}
+
+impl CallCredentials {
+    pub fn token(token: impl Into<String>) -> Self {
+        Self::Token(token.into())
+    }
+
+    pub fn password(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self::Password {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Read the credentials a call carries. A call without an `authorization` entry
+    /// yields `CallCredentials::None`.
+    pub fn from_metadata(metadata: &MetadataMap) -> Result<Self, Box<Status>> {
+        let Some(value) = metadata.get(AUTHORIZATION_METADATA_KEY) else {
+            return Ok(Self::None);
+        };
+        let value = value
+            .to_str()
+            .map_err(|_| unauthenticated("authorization metadata is not ASCII"))?;
+        let (scheme, payload) = value
+            .split_once(' ')
+            .ok_or_else(|| unauthenticated("authorization metadata has no scheme"))?;
+        if scheme.eq_ignore_ascii_case("bearer") {
+            return Ok(Self::token(payload.trim()));
+        }
+        if scheme.eq_ignore_ascii_case("basic") {
+            let decoded = BASE64
+                .decode(payload.trim())
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .ok_or_else(|| unauthenticated("malformed basic credentials"))?;
+            let (username, password) = decoded
+                .split_once(':')
+                .ok_or_else(|| unauthenticated("malformed basic credentials"))?;
+            return Ok(Self::password(username, password));
+        }
+        Err(unauthenticated(format!(
+            "unsupported authorization scheme '{scheme}'"
+        )))
+    }
+
+    fn metadata_value(&self) -> Option<String> {
+        match self {
+            Self::None => None,
+            Self::Token(token) => Some(format!("Bearer {token}")),
+            Self::Password { username, password } => Some(format!(
+                "Basic {}",
+                BASE64.encode(format!("{username}:{password}"))
+            )),
+        }
+    }
+}
+
+impl fmt::Debug for CallCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => f.write_str("None"),
+            Self::Token(_) => f.write_str("Token(<redacted>)"),
+            Self::Password { username, .. } => f
+                .debug_struct("Password")
+                .field("username", username)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+impl tonic::service::Interceptor for CallCredentials {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(value) = self.metadata_value() {
+            let value = MetadataValue::try_from(value)
+                .map_err(|_| Status::invalid_argument("credentials are not valid metadata"))?;
+            request
+                .metadata_mut()
+                .insert(AUTHORIZATION_METADATA_KEY, value);
+        }
+        Ok(request)
+    }
+}
+
+/// Salted key material for a password user, in the SCRAM-SHA-256 style: the broker keeps
+/// `SHA-256(HMAC(PBKDF2(password, salt, iterations), "Client Key"))` and never the password.
+///
+/// Written as `iterations=<n>,salt=<base64>,stored_key=<base64>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScramCredential {
+    pub iterations: u32,
+    pub salt: Vec<u8>,
+    pub stored_key: Vec<u8>,
+}
+
+impl ScramCredential {
+    /// Derive a credential for `password` with a fresh random salt.
+    pub fn generate(password: &str, iterations: u32) -> Result<Self, String> {
+        let mut salt = vec![0u8; 16];
+        ring::rand::SystemRandom::new()
+            .fill(&mut salt)
+            .map_err(|_| "failed to generate salt".to_string())?;
+        Self::derive(password, salt, iterations)
+    }
+
+    pub fn derive(password: &str, salt: Vec<u8>, iterations: u32) -> Result<Self, String> {
+        let stored_key = stored_key(password, &salt, iterations)?;
+        Ok(Self {
+            iterations,
+            salt,
+            stored_key,
+        })
+    }
+
+    pub fn verify(&self, password: &str) -> bool {
+        stored_key(password, &self.salt, self.iterations)
+            .is_ok_and(|candidate| constant_time_eq(&candidate, &self.stored_key))
+    }
+}
+
+impl fmt::Display for ScramCredential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "iterations={},salt={},stored_key={}",
+            self.iterations,
+            BASE64.encode(&self.salt),
+            BASE64.encode(&self.stored_key)
+        )
+    }
+}
+
+impl FromStr for ScramCredential {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mut iterations, mut salt, mut stored_key) = (None, None, None);
+        for field in s.split(',') {
+            let (name, value) = field
+                .split_once('=')
+                .ok_or_else(|| format!("expected name=value, got '{field}'"))?;
+            let decode = |value: &str| {
+                BASE64
+                    .decode(value)
+                    .map_err(|e| format!("invalid base64 in {name}: {e}"))
+            };
+            match name.trim() {
+                "iterations" => {
+                    iterations = Some(
+                        value
+                            .parse::<u32>()
+                            .map_err(|e| format!("invalid iterations: {e}"))?,
+                    )
+                }
+                "salt" => salt = Some(decode(value)?),
+                "stored_key" => stored_key = Some(decode(value)?),
+                other => return Err(format!("unknown field '{other}'")),
+            }
+        }
+        let iterations = iterations.ok_or("missing iterations")?;
+        if iterations == 0 {
+            return Err("iterations must be positive".to_string());
+        }
+        Ok(Self {
+            iterations,
+            salt: salt.ok_or("missing salt")?,
+            stored_key: stored_key.ok_or("missing stored_key")?,
+        })
+    }
+}
+
+fn stored_key(password: &str, salt: &[u8], iterations: u32) -> Result<Vec<u8>, String> {
+    let iterations =
+        NonZeroU32::new(iterations).ok_or_else(|| "iterations must be positive".to_string())?;
+    let mut salted = [0u8; digest::SHA256_OUTPUT_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        password.as_bytes(),
+        &mut salted,
+    );
+    let client_key = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, &salted), b"Client Key");
+    Ok(digest::digest(&digest::SHA256, client_key.as_ref())
+        .as_ref()
+        .to_vec())
+}
+
+fn unauthenticated(message: impl Into<String>) -> Box<Status> {
+    Box::new(Status::unauthenticated(message))
+}
+
+/// Compare two byte strings without stopping at the first difference.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
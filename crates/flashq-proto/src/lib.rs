@@ -20,6 +20,13 @@ pub mod cluster {
     }
 }
 
+pub mod auth;
+pub mod tls;
+pub mod trace_context;
+
+/// Call metadata naming the client application, used to key per-client quotas.
+pub const CLIENT_ID_METADATA_KEY: &str = "x-flashq-client-id";
+
 // Re-export broker API types for convenience
 pub use flashq::v1::*;
 
@@ -0,0 +1,104 @@
+//! TLS settings for FlashQ gRPC endpoints.
+//!
+//! Certificates and keys are PEM files read when the settings are turned into tonic
+//! configs, so a bad path surfaces when a server starts or a client connects.
+
+use std::path::{Path, PathBuf};
+
+use tonic::transport::{Certificate, ClientTlsConfig, Identity, ServerTlsConfig};
+
+/// TLS for a serving endpoint, optionally requiring client certificates (mutual TLS).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerTlsOptions {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    /// CA bundle that client certificates must chain to. `None` accepts clients without one.
+    pub client_ca_path: Option<PathBuf>,
+}
+
+impl ServerTlsOptions {
+    pub fn new(cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        Self {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+            client_ca_path: None,
+        }
+    }
+
+    /// Require every client to present a certificate signed by a CA in `ca_path`.
+    pub fn with_client_ca(mut self, ca_path: impl Into<PathBuf>) -> Self {
+        self.client_ca_path = Some(ca_path.into());
+        self
+    }
+
+    pub fn load(&self) -> std::io::Result<ServerTlsConfig> {
+        let identity = load_identity(&self.cert_path, &self.key_path)?;
+        let mut config = ServerTlsConfig::new().identity(identity);
+        if let Some(ca_path) = &self.client_ca_path {
+            config = config.client_ca_root(load_certificate(ca_path)?);
+        }
+        Ok(config)
+    }
+}
+
+/// TLS for a client channel: the CA bundle that server certificates must chain to and,
+/// for servers requiring mutual TLS, the client's own certificate and key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientTlsOptions {
+    pub ca_path: PathBuf,
+    pub identity: Option<(PathBuf, PathBuf)>,
+    /// Name to verify the server certificate against instead of the endpoint's host.
+    pub domain_name: Option<String>,
+}
+
+impl ClientTlsOptions {
+    pub fn new(ca_path: impl Into<PathBuf>) -> Self {
+        Self {
+            ca_path: ca_path.into(),
+            identity: None,
+            domain_name: None,
+        }
+    }
+
+    pub fn with_identity(
+        mut self,
+        cert_path: impl Into<PathBuf>,
+        key_path: impl Into<PathBuf>,
+    ) -> Self {
+        self.identity = Some((cert_path.into(), key_path.into()));
+        self
+    }
+
+    pub fn with_domain_name(mut self, domain_name: impl Into<String>) -> Self {
+        self.domain_name = Some(domain_name.into());
+        self
+    }
+
+    pub fn load(&self) -> std::io::Result<ClientTlsConfig> {
+        let mut config = ClientTlsConfig::new().ca_certificate(load_certificate(&self.ca_path)?);
+        if let Some((cert_path, key_path)) = &self.identity {
+            config = config.identity(load_identity(cert_path, key_path)?);
+        }
+        if let Some(domain_name) = &self.domain_name {
+            config = config.domain_name(domain_name.clone());
+        }
+        Ok(config)
+    }
+}
+
+fn load_certificate(path: &Path) -> std::io::Result<Certificate> {
+    Ok(Certificate::from_pem(read_pem(path)?))
+}
+
+fn load_identity(cert_path: &Path, key_path: &Path) -> std::io::Result<Identity> {
+    Ok(Identity::from_pem(
+        read_pem(cert_path)?,
+        read_pem(key_path)?,
+    ))
+}
+
+fn read_pem(path: &Path) -> std::io::Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| {
+        std::io::Error::new(e.kind(), format!("failed to read {}: {e}", path.display()))
+    })
+}
@@ -0,0 +1,126 @@
+//! W3C trace context carried on FlashQ calls, heartbeats and records.
+//!
+//! Clients add the current span's `traceparent` and `tracestate` to each call's metadata, and
+//! brokers open the span for the call as a child of it, so one trace follows a request from
+//! client to broker. Producers may also write the same entries into record headers, letting a
+//! consumer link its processing span to the span that produced the record.
+//!
+//! Spans only carry a trace id when an OpenTelemetry layer is installed (see
+//! `flashq::telemetry::init_with_otlp`); otherwise nothing is injected and extracted contexts
+//! are empty.
+
+use std::collections::HashMap;
+
+use opentelemetry::Context;
+use opentelemetry::propagation::{Extractor, Injector, TextMapPropagator};
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use tonic::codegen::http;
+use tonic::metadata::{MetadataKey, MetadataMap, MetadataValue};
+use tracing::Span;
+
+pub use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::flashq::v1::Header;
+
+/// Add the current span's trace context to outgoing call metadata.
+pub fn inject(metadata: &mut MetadataMap) {
+    inject_current(&mut MetadataInjector(metadata));
+}
+
+/// Add the current span's trace context to a string map, as sent on cluster heartbeats.
+pub fn inject_map(carrier: &mut HashMap<String, String>) {
+    inject_current(carrier);
+}
+
+/// Write the current span's trace context into record headers, replacing any trace context
+/// already there.
+pub fn inject_headers(headers: &mut Vec<Header>) {
+    let mut carrier = HashMap::new();
+    inject_current(&mut carrier);
+    if carrier.is_empty() {
+        return;
+    }
+    headers.retain(|header| !carrier.contains_key(&header.key));
+    headers.extend(carrier.into_iter().map(|(key, value)| Header {
+        key,
+        value: value.into_bytes(),
+    }));
+}
+
+/// Trace context sent with an incoming call.
+pub fn extract(headers: &http::HeaderMap) -> Context {
+    propagator().extract(&HeaderExtractor(headers))
+}
+
+/// Trace context carried in a string map.
+pub fn extract_map(carrier: &HashMap<String, String>) -> Context {
+    propagator().extract(carrier)
+}
+
+/// Trace context a producer wrote into record headers.
+pub fn extract_headers(headers: &[Header]) -> Context {
+    let carrier: HashMap<String, String> = headers
+        .iter()
+        .filter_map(|header| {
+            let value = std::str::from_utf8(&header.value).ok()?;
+            Some((header.key.to_ascii_lowercase(), value.to_string()))
+        })
+        .collect();
+    extract_map(&carrier)
+}
+
+/// Span for a call a server is handling, parented by the caller's trace context. Suits
+/// `tower_http::trace::TraceLayer::make_span_with` and `tonic::transport::Server::trace_fn`.
+pub fn server_span<B>(request: &http::Request<B>) -> Span {
+    let span = tracing::info_span!(
+        "grpc_request",
+        otel.name = %request.uri().path(),
+        otel.kind = "server",
+    );
+    span.set_parent(extract(request.headers()));
+    span
+}
+
+/// Link `span` to the span that produced a record, if its headers carry trace context.
+pub fn link_to_record(span: &Span, headers: &[Header]) {
+    let context = extract_headers(headers);
+    let producer = context.span().span_context().clone();
+    if producer.is_valid() {
+        span.add_link(producer);
+    }
+}
+
+fn inject_current(injector: &mut dyn Injector) {
+    propagator().inject_context(&Span::current().context(), injector);
+}
+
+fn propagator() -> TraceContextPropagator {
+    TraceContextPropagator::new()
+}
+
+struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+impl Injector for MetadataInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        let (Ok(key), Ok(value)) = (
+            MetadataKey::from_bytes(key.as_bytes()),
+            MetadataValue::try_from(value),
+        ) else {
+            return;
+        };
+        self.0.insert(key, value);
+    }
+}
+
+struct HeaderExtractor<'a>(&'a http::HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
@@ -0,0 +1,204 @@
+//! Reference third-party storage backend for FlashQ, kept in a single redb database file.
+//!
+//! Plug it into a queue with `FlashQ::with_storage_backend(RedbStorage::open(path)?)`. It lives
+//! outside `flashq-storage` on purpose: everything it needs comes through the public
+//! `StorageFactory`, `TopicLog` and `ConsumerOffsetStore` traits.
+//!
+//! Layout, one table each:
+//! - `records`: `(topic, partition, offset)` to the JSON-encoded `RecordWithOffset`
+//! - `partitions`: `(topic, partition)` to `(next_offset, record_count)`, updated in the same
+//!   transaction as the records so the two never disagree after a crash
+//! - `consumer_offsets`: `(group, topic, partition)` to the committed offset
+//! - `consumer_groups`: group ids, so groups without commits are recovered too
+//!
+//! Every append and offset commit is one redb write transaction, which redb makes durable
+//! before `commit` returns. Time-based reads scan the partition since there is no time index.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use flashq_storage::{
+    ConsumerGroup, ConsumerOffsetStore, IoPool, StorageError, StorageErrorSource, StorageFactory,
+    TopicLog,
+};
+use parking_lot::RwLock;
+use redb::{Database, ReadableTable, TableDefinition};
+
+mod offset_store;
+mod topic_log;
+
+pub use offset_store::{RedbConsumerGroup, RedbConsumerOffsetStore};
+pub use topic_log::RedbTopicLog;
+
+pub(crate) const RECORDS: TableDefinition<(&str, u32, u64), &[u8]> =
+    TableDefinition::new("records");
+pub(crate) const PARTITIONS: TableDefinition<(&str, u32), (u64, u64)> =
+    TableDefinition::new("partitions");
+pub(crate) const CONSUMER_OFFSETS: TableDefinition<(&str, &str, u32), u64> =
+    TableDefinition::new("consumer_offsets");
+pub(crate) const CONSUMER_GROUPS: TableDefinition<&str, ()> =
+    TableDefinition::new("consumer_groups");
+
+const DEFAULT_IO_THREADS: usize = 4;
+
+/// `StorageFactory` over one redb database file.
+pub struct RedbStorage {
+    db: Arc<Database>,
+    path: PathBuf,
+    io_threads: usize,
+}
+
+impl std::fmt::Debug for RedbStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedbStorage")
+            .field("path", &self.path)
+            .field("io_threads", &self.io_threads)
+            .finish()
+    }
+}
+
+impl RedbStorage {
+    /// Open the database at `path`, creating it (and its parent directory) if needed. redb
+    /// holds an exclusive lock on the file until the storage is dropped.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, StorageError> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| StorageError::from_io_error(e, "Failed to create redb directory"))?;
+        }
+        let db = Database::create(&path).map_err(|e| StorageError::Unavailable {
+            context: format!("open redb database {}: {e}", path.display()),
+        })?;
+
+        // Create every table up front so read transactions never miss one
+        let txn = db.begin_write().map_err(write_failed("create tables"))?;
+        txn.open_table(RECORDS)
+            .map_err(write_failed("create records table"))?;
+        txn.open_table(PARTITIONS)
+            .map_err(write_failed("create partitions table"))?;
+        txn.open_table(CONSUMER_OFFSETS)
+            .map_err(write_failed("create consumer offsets table"))?;
+        txn.open_table(CONSUMER_GROUPS)
+            .map_err(write_failed("create consumer groups table"))?;
+        txn.commit().map_err(write_failed("create tables"))?;
+
+        Ok(Self {
+            db: Arc::new(db),
+            path,
+            io_threads: DEFAULT_IO_THREADS,
+        })
+    }
+
+    /// Set the number of dedicated I/O threads serving async callers.
+    pub fn with_io_threads(mut self, threads: usize) -> Self {
+        self.io_threads = threads.max(1);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn register_group(&self, group_id: &str) -> Result<(), StorageError> {
+        let txn = self
+            .db
+            .begin_write()
+            .map_err(write_failed("register consumer group"))?;
+        {
+            let mut groups = txn
+                .open_table(CONSUMER_GROUPS)
+                .map_err(write_failed("register consumer group"))?;
+            groups
+                .insert(group_id, ())
+                .map_err(write_failed("register consumer group"))?;
+        }
+        txn.commit()
+            .map_err(write_failed("register consumer group"))
+    }
+}
+
+impl StorageFactory for RedbStorage {
+    fn create_topic_log(
+        &self,
+        topic: &str,
+    ) -> Result<Arc<RwLock<dyn TopicLog + Send + Sync>>, StorageError> {
+        let log = RedbTopicLog::open(Arc::clone(&self.db), topic)?;
+        Ok(Arc::new(RwLock::new(log)))
+    }
+
+    fn create_group(&self, group_id: &str) -> Result<Arc<RwLock<dyn ConsumerGroup>>, StorageError> {
+        self.register_group(group_id)?;
+        let offset_store = RedbConsumerOffsetStore::new(Arc::clone(&self.db), group_id);
+        Ok(Arc::new(RwLock::new(RedbConsumerGroup::new(offset_store))))
+    }
+
+    fn create_offset_store(
+        &self,
+        group_id: &str,
+    ) -> Result<Arc<dyn ConsumerOffsetStore>, StorageError> {
+        self.register_group(group_id)?;
+        Ok(Arc::new(RedbConsumerOffsetStore::new(
+            Arc::clone(&self.db),
+            group_id,
+        )))
+    }
+
+    fn list_topics(&self) -> Result<Vec<String>, StorageError> {
+        let txn = self.db.begin_read().map_err(read_failed("list topics"))?;
+        let partitions = txn
+            .open_table(PARTITIONS)
+            .map_err(read_failed("list topics"))?;
+        let mut topics = BTreeSet::new();
+        for entry in partitions.iter().map_err(read_failed("list topics"))? {
+            let (key, value) = entry.map_err(read_failed("list topics"))?;
+            let (topic, _) = key.value();
+            let (next_offset, _) = value.value();
+            if next_offset > 0 {
+                topics.insert(topic.to_string());
+            }
+        }
+        Ok(topics.into_iter().collect())
+    }
+
+    fn list_consumer_groups(&self) -> Result<Vec<String>, StorageError> {
+        let txn = self
+            .db
+            .begin_read()
+            .map_err(read_failed("list consumer groups"))?;
+        let groups = txn
+            .open_table(CONSUMER_GROUPS)
+            .map_err(read_failed("list consumer groups"))?;
+        groups
+            .iter()
+            .map_err(read_failed("list consumer groups"))?
+            .map(|entry| {
+                entry
+                    .map(|(group_id, _)| group_id.value().to_string())
+                    .map_err(read_failed("list consumer groups"))
+            })
+            .collect()
+    }
+
+    fn io_pool(&self) -> Result<IoPool, StorageError> {
+        IoPool::with_threads(self.io_threads)
+    }
+}
+
+pub(crate) fn read_failed<E: Into<redb::Error>>(
+    context: &'static str,
+) -> impl Fn(E) -> StorageError {
+    move |e| StorageError::ReadFailed {
+        context: context.to_string(),
+        source: Box::new(StorageErrorSource::Custom(e.into().to_string())),
+    }
+}
+
+pub(crate) fn write_failed<E: Into<redb::Error>>(
+    context: &'static str,
+) -> impl Fn(E) -> StorageError {
+    move |e| StorageError::WriteFailed {
+        context: context.to_string(),
+        source: Box::new(StorageErrorSource::Custom(e.into().to_string())),
+    }
+}
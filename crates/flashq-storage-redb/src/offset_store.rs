@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use flashq_storage::{ConsumerGroup, ConsumerOffsetStore, PartitionId, StorageError};
+use redb::{Database, ReadableTable};
+
+use crate::{CONSUMER_OFFSETS, read_failed, write_failed};
+
+/// Committed offsets of one consumer group. redb runs one write transaction at a time, so
+/// the compare and the insert in `persist_snapshot` cannot interleave with another commit.
+pub struct RedbConsumerOffsetStore {
+    db: Arc<Database>,
+    group_id: String,
+}
+
+impl RedbConsumerOffsetStore {
+    pub fn new(db: Arc<Database>, group_id: &str) -> Self {
+        Self {
+            db,
+            group_id: group_id.to_string(),
+        }
+    }
+}
+
+impl ConsumerOffsetStore for RedbConsumerOffsetStore {
+    fn load_snapshot(&self, topic: &str, partition_id: PartitionId) -> Result<u64, StorageError> {
+        let txn = self
+            .db
+            .begin_read()
+            .map_err(read_failed("load offset snapshot"))?;
+        let table = txn
+            .open_table(CONSUMER_OFFSETS)
+            .map_err(read_failed("load offset snapshot"))?;
+        let offset = table
+            .get((self.group_id.as_str(), topic, partition_id.0))
+            .map_err(read_failed("load offset snapshot"))?
+            .map(|offset| offset.value())
+            .unwrap_or(0);
+        Ok(offset)
+    }
+
+    fn persist_snapshot(
+        &self,
+        topic: String,
+        partition_id: PartitionId,
+        offset: u64,
+    ) -> Result<bool, StorageError> {
+        let key = (self.group_id.as_str(), topic.as_str(), partition_id.0);
+        let txn = self
+            .db
+            .begin_write()
+            .map_err(write_failed("persist offset snapshot"))?;
+        {
+            let mut table = txn
+                .open_table(CONSUMER_OFFSETS)
+                .map_err(write_failed("persist offset snapshot"))?;
+            let current = table
+                .get(key)
+                .map_err(write_failed("persist offset snapshot"))?
+                .map(|current| current.value());
+            if current.is_some_and(|current| offset < current) {
+                return Ok(false);
+            }
+            table
+                .insert(key, offset)
+                .map_err(write_failed("persist offset snapshot"))?;
+        }
+        txn.commit()
+            .map_err(write_failed("persist offset snapshot"))?;
+        Ok(true)
+    }
+
+    fn get_all_snapshots(&self) -> Result<HashMap<(String, PartitionId), u64>, StorageError> {
+        let txn = self
+            .db
+            .begin_read()
+            .map_err(read_failed("load offset snapshots"))?;
+        let table = txn
+            .open_table(CONSUMER_OFFSETS)
+            .map_err(read_failed("load offset snapshots"))?;
+        let group_id = self.group_id.as_str();
+        let range = table
+            .range((group_id, "", 0)..)
+            .map_err(read_failed("load offset snapshots"))?;
+
+        let mut snapshots = HashMap::new();
+        for entry in range {
+            let (key, offset) = entry.map_err(read_failed("load offset snapshots"))?;
+            let (group, topic, partition) = key.value();
+            if group != group_id {
+                break;
+            }
+            snapshots.insert(
+                (topic.to_string(), PartitionId::new(partition)),
+                offset.value(),
+            );
+        }
+        Ok(snapshots)
+    }
+
+    fn group_id(&self) -> &str {
+        &self.group_id
+    }
+}
+
+/// `ConsumerGroup` view over a [`RedbConsumerOffsetStore`].
+pub struct RedbConsumerGroup {
+    offset_store: RedbConsumerOffsetStore,
+}
+
+impl RedbConsumerGroup {
+    pub fn new(offset_store: RedbConsumerOffsetStore) -> Self {
+        Self { offset_store }
+    }
+}
+
+impl ConsumerGroup for RedbConsumerGroup {
+    fn offset_store(&self) -> &dyn ConsumerOffsetStore {
+        &self.offset_store
+    }
+}
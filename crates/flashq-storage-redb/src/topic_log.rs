@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use flashq_storage::{
+    PartitionId, ReadBounds, Record, RecordWithOffset, StorageError, TopicConfig, TopicLog,
+    validate_replayed_offsets,
+};
+use redb::Database;
+
+use crate::{PARTITIONS, RECORDS, read_failed, write_failed};
+
+#[derive(Debug, Clone, Copy, Default)]
+struct PartitionState {
+    next_offset: u64,
+    record_count: u64,
+}
+
+/// One topic's records in a redb database. Partition offsets and counts are cached in memory
+/// and written back with every append.
+pub struct RedbTopicLog {
+    db: Arc<Database>,
+    topic: String,
+    partitions: HashMap<PartitionId, PartitionState>,
+    topic_config: TopicConfig,
+}
+
+impl RedbTopicLog {
+    pub fn open(db: Arc<Database>, topic: &str) -> Result<Self, StorageError> {
+        let mut partitions = HashMap::new();
+        {
+            let txn = db.begin_read().map_err(read_failed("open topic log"))?;
+            let table = txn
+                .open_table(PARTITIONS)
+                .map_err(read_failed("open topic log"))?;
+            let range = table
+                .range((topic, 0)..=(topic, u32::MAX))
+                .map_err(read_failed("open topic log"))?;
+            for entry in range {
+                let (key, value) = entry.map_err(read_failed("open topic log"))?;
+                let (_, partition) = key.value();
+                let (next_offset, record_count) = value.value();
+                partitions.insert(
+                    PartitionId::new(partition),
+                    PartitionState {
+                        next_offset,
+                        record_count,
+                    },
+                );
+            }
+        }
+
+        Ok(Self {
+            db,
+            topic: topic.to_string(),
+            partitions,
+            topic_config: TopicConfig::default(),
+        })
+    }
+
+    /// Apply `topic_config`; only its timestamp settings matter here.
+    pub fn with_topic_config(mut self, topic_config: TopicConfig) -> Self {
+        self.topic_config = topic_config;
+        self
+    }
+
+    fn state(&self, partition_id: PartitionId) -> PartitionState {
+        self.partitions
+            .get(&partition_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Write `records`, whose offsets are already assigned, in one transaction.
+    fn write_records(
+        &mut self,
+        partition_id: PartitionId,
+        records: &[RecordWithOffset],
+    ) -> Result<(), StorageError> {
+        let mut state = self.state(partition_id);
+        let txn = self
+            .db
+            .begin_write()
+            .map_err(write_failed("append records"))?;
+        {
+            let mut table = txn
+                .open_table(RECORDS)
+                .map_err(write_failed("append records"))?;
+            for record in records {
+                let encoded = serde_json::to_vec(record)
+                    .map_err(|e| StorageError::from_serialization_error(e, "encode record"))?;
+                table
+                    .insert(
+                        (self.topic.as_str(), partition_id.0, record.offset),
+                        encoded.as_slice(),
+                    )
+                    .map_err(write_failed("append records"))?;
+            }
+            if let Some(last) = records.last() {
+                state.next_offset = last.offset + 1;
+            }
+            state.record_count += records.len() as u64;
+
+            let mut partitions = txn
+                .open_table(PARTITIONS)
+                .map_err(write_failed("append records"))?;
+            partitions
+                .insert(
+                    (self.topic.as_str(), partition_id.0),
+                    (state.next_offset, state.record_count),
+                )
+                .map_err(write_failed("append records"))?;
+        }
+        txn.commit().map_err(write_failed("append records"))?;
+
+        self.partitions.insert(partition_id, state);
+        Ok(())
+    }
+
+    /// Read records of `partition_id` from `from_offset` on, keeping those `keep` accepts,
+    /// until `count` have been collected.
+    fn scan(
+        &self,
+        partition_id: PartitionId,
+        from_offset: u64,
+        bounds: ReadBounds,
+        count: Option<usize>,
+        mut keep: impl FnMut(&RecordWithOffset) -> bool,
+    ) -> Result<Vec<RecordWithOffset>, StorageError> {
+        let max = count.unwrap_or(usize::MAX);
+        let mut out = Vec::new();
+        if max == 0
+            || bounds.is_past_end(from_offset)
+            || !self.partitions.contains_key(&partition_id)
+        {
+            return Ok(out);
+        }
+        // Past the from_offset check, to_offset is at least 1
+        let last_offset = bounds.to_offset.map_or(u64::MAX, |to_offset| to_offset - 1);
+
+        let txn = self.db.begin_read().map_err(read_failed("read records"))?;
+        let table = txn
+            .open_table(RECORDS)
+            .map_err(read_failed("read records"))?;
+        let topic = self.topic.as_str();
+        let range = table
+            .range((topic, partition_id.0, from_offset)..=(topic, partition_id.0, last_offset))
+            .map_err(read_failed("read records"))?;
+        for entry in range {
+            let (_, value) = entry.map_err(read_failed("read records"))?;
+            let record: RecordWithOffset = serde_json::from_slice(value.value())
+                .map_err(|e| StorageError::from_serialization_error(e, "decode record"))?;
+            if keep(&record) && bounds.admits(&record) {
+                out.push(record);
+                if out.len() >= max {
+                    break;
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl TopicLog for RedbTopicLog {
+    fn append_partition(
+        &mut self,
+        partition_id: PartitionId,
+        record: Record,
+    ) -> Result<u64, StorageError> {
+        self.append_batch_partition(partition_id, vec![record])
+    }
+
+    fn append_batch_partition(
+        &mut self,
+        partition_id: PartitionId,
+        mut records: Vec<Record>,
+    ) -> Result<u64, StorageError> {
+        let next_offset = self.state(partition_id).next_offset;
+        if records.is_empty() {
+            return Ok(next_offset);
+        }
+
+        let timestamps = self.topic_config.resolve_timestamps(&mut records)?;
+        let records: Vec<RecordWithOffset> = records
+            .into_iter()
+            .zip(timestamps)
+            .zip(next_offset..)
+            .map(|((record, timestamp), offset)| RecordWithOffset {
+                record,
+                offset,
+                timestamp,
+            })
+            .collect();
+        self.write_records(partition_id, &records)?;
+        Ok(next_offset + records.len() as u64 - 1)
+    }
+
+    fn append_batch_with_offsets_partition(
+        &mut self,
+        partition_id: PartitionId,
+        records: Vec<RecordWithOffset>,
+    ) -> Result<u64, StorageError> {
+        let next_offset = self.state(partition_id).next_offset;
+        validate_replayed_offsets(next_offset, &records)?;
+
+        let Some(last) = records.last().map(|r| r.offset) else {
+            return Ok(next_offset);
+        };
+        self.write_records(partition_id, &records)?;
+        Ok(last)
+    }
+
+    fn read_from_partition_bounded(
+        &self,
+        partition_id: PartitionId,
+        from_offset: u64,
+        bounds: ReadBounds,
+        max_bytes: Option<usize>,
+    ) -> Result<Vec<RecordWithOffset>, StorageError> {
+        self.scan(partition_id, from_offset, bounds, max_bytes, |_| true)
+    }
+
+    fn read_from_partition_timestamp_bounded(
+        &self,
+        partition_id: PartitionId,
+        ts_rfc3339: &str,
+        bounds: ReadBounds,
+        max_bytes: Option<usize>,
+    ) -> Result<Vec<RecordWithOffset>, StorageError> {
+        let target = chrono::DateTime::parse_from_rfc3339(ts_rfc3339).map_err(|e| {
+            StorageError::DataCorruption {
+                context: "parse from_time".to_string(),
+                details: e.to_string(),
+            }
+        })?;
+        self.scan(partition_id, 0, bounds, max_bytes, |record| {
+            chrono::DateTime::parse_from_rfc3339(&record.timestamp).is_ok_and(|ts| ts >= target)
+        })
+    }
+
+    fn partition_ids(&self) -> Vec<PartitionId> {
+        let mut ids: Vec<PartitionId> = self.partitions.keys().copied().collect();
+        ids.sort_by_key(|id| id.0);
+        ids
+    }
+
+    fn partition_len(&self, partition_id: PartitionId) -> usize {
+        self.state(partition_id).record_count as usize
+    }
+
+    fn partition_is_empty(&self, partition_id: PartitionId) -> bool {
+        self.partition_len(partition_id) == 0
+    }
+
+    fn partition_next_offset(&self, partition_id: PartitionId) -> u64 {
+        self.state(partition_id).next_offset
+    }
+}
@@ -0,0 +1,10 @@
+use flashq_storage_redb::RedbStorage;
+use flashq_storage_testkit::{BackendHarness, storage_conformance_tests};
+
+storage_conformance_tests!(
+    redb,
+    BackendHarness::in_temp_dir(
+        |dir| RedbStorage::open(dir.join("flashq.redb")).expect("open redb storage"),
+        true
+    )
+);
@@ -0,0 +1,82 @@
+use flashq::{FlashQ, PartitionId, Record};
+use flashq_storage::StorageFactory;
+use flashq_storage_redb::RedbStorage;
+
+fn record(value: &str) -> Record {
+    Record::new(Some(format!("key-{value}")), value.to_string(), None)
+}
+
+#[test]
+fn test_flashq_recovers_topics_and_groups_from_redb() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("flashq.redb");
+
+    {
+        let queue = FlashQ::with_storage_backend(RedbStorage::open(&path).unwrap());
+        queue
+            .post_records(
+                "orders".to_string(),
+                vec![record("a"), record("b"), record("c")],
+            )
+            .unwrap();
+        queue.create_consumer_group("billing".to_string()).unwrap();
+        queue
+            .update_consumer_group_offset("billing", "orders".to_string(), 2)
+            .unwrap();
+        queue.create_consumer_group("audit".to_string()).unwrap();
+    }
+
+    let queue = FlashQ::with_storage_backend(RedbStorage::open(&path).unwrap());
+    assert_eq!(queue.get_topics(), vec!["orders".to_string()]);
+    assert_eq!(queue.get_high_water_mark("orders"), 3);
+    assert_eq!(
+        queue
+            .get_consumer_group_offset("billing", "orders")
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        queue.get_consumer_group_offset("audit", "orders").unwrap(),
+        0
+    );
+
+    let records = queue.poll_records_from_offset("orders", 2, None).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].record.value, "c");
+    assert_eq!(
+        queue
+            .post_records("orders".to_string(), vec![record("d")])
+            .unwrap(),
+        3
+    );
+}
+
+#[test]
+fn test_second_open_of_the_same_database_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("flashq.redb");
+
+    let storage = RedbStorage::open(&path).unwrap();
+    assert!(RedbStorage::open(&path).is_err());
+    drop(storage);
+    assert!(RedbStorage::open(&path).is_ok());
+}
+
+#[test]
+fn test_empty_topics_are_not_listed() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = RedbStorage::open(dir.path().join("flashq.redb")).unwrap();
+
+    let log = storage.create_topic_log("empty").unwrap();
+    log.write()
+        .append_batch_partition(PartitionId::new(0), Vec::new())
+        .unwrap();
+    storage
+        .create_topic_log("full")
+        .unwrap()
+        .write()
+        .append(record("x"))
+        .unwrap();
+
+    assert_eq!(storage.list_topics().unwrap(), vec!["full".to_string()]);
+}
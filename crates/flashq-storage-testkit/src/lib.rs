@@ -0,0 +1,197 @@
+//! Conformance suite for FlashQ storage backends.
+//!
+//! Every case takes a [`StorageHarness`], which hands out topic logs and consumer offset
+//! stores from the backend under test and can simulate a restart. [`BackendHarness`] covers
+//! any `StorageFactory`, which is all a third-party backend needs to provide; a test file
+//! then generates one test per case with [`storage_conformance_tests!`]:
+//!
+//! ```ignore
+//! flashq_storage_testkit::storage_conformance_tests!(
+//!     my_backend,
+//!     BackendHarness::in_temp_dir(|dir| MyStorage::open(dir).unwrap(), true)
+//! );
+//! ```
+//!
+//! Cases panic with a message naming the broken expectation, so they read like ordinary
+//! assertions in test output.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use flashq_storage::{
+    ConsumerOffsetStore, StorageBackend, StorageError, StorageFactory, SyncMode, TopicLog,
+};
+use parking_lot::RwLock;
+
+pub mod offset_store;
+pub mod topic_log;
+
+/// Access to a storage backend under test.
+pub trait StorageHarness {
+    /// Open the log for `topic`, recovering whatever the backend persisted for it.
+    fn topic_log(
+        &mut self,
+        topic: &str,
+    ) -> Result<Arc<RwLock<dyn TopicLog + Send + Sync>>, StorageError>;
+
+    /// Open the offset store for `group_id`, recovering whatever the backend persisted for it.
+    fn offset_store(
+        &mut self,
+        group_id: &str,
+    ) -> Result<Arc<dyn ConsumerOffsetStore>, StorageError>;
+
+    /// Drop all in-process state, as a broker restart would. Handles returned earlier are
+    /// released by the case before it calls this. Returns false for backends that keep
+    /// nothing across restarts; recovery cases then pass without checking anything.
+    fn restart(&mut self) -> bool;
+}
+
+/// [`StorageHarness`] over any [`StorageFactory`] built by a constructor closure.
+pub struct BackendHarness {
+    make: Box<dyn Fn() -> Box<dyn StorageFactory>>,
+    backend: Option<Box<dyn StorageFactory>>,
+    durable: bool,
+    _data_dir: Option<tempfile::TempDir>,
+}
+
+impl BackendHarness {
+    /// `make` is called once up front and again after every restart. `durable` says
+    /// whether the backends it builds recover state written by earlier ones.
+    pub fn new<F: StorageFactory + 'static>(make: impl Fn() -> F + 'static, durable: bool) -> Self {
+        let make: Box<dyn Fn() -> Box<dyn StorageFactory>> = Box::new(move || Box::new(make()));
+        Self {
+            backend: Some(make()),
+            make,
+            durable,
+            _data_dir: None,
+        }
+    }
+
+    /// Like [`BackendHarness::new`], with `make` given a temporary directory that lives as
+    /// long as the harness.
+    pub fn in_temp_dir<F: StorageFactory + 'static>(
+        make: impl Fn(&Path) -> F + 'static,
+        durable: bool,
+    ) -> Self {
+        let data_dir = tempfile::Builder::new()
+            .prefix("flashq_conformance_")
+            .tempdir()
+            .expect("create temporary data directory");
+        let path = data_dir.path().to_path_buf();
+        let mut harness = Self::new(move || make(&path), durable);
+        harness._data_dir = Some(data_dir);
+        harness
+    }
+
+    pub fn memory() -> Self {
+        Self::new(StorageBackend::new_memory, false)
+    }
+
+    /// File backend in a fresh temporary directory. Segments are kept small so the cases
+    /// cross segment boundaries.
+    pub fn file() -> Self {
+        Self::in_temp_dir(
+            |path| {
+                StorageBackend::new_file_with_config(SyncMode::Immediate, path, 1000, 16 * 1024)
+                    .expect("open file backend")
+            },
+            true,
+        )
+    }
+
+    fn backend(&mut self) -> &dyn StorageFactory {
+        &**self.backend.get_or_insert_with(|| (self.make)())
+    }
+}
+
+impl StorageHarness for BackendHarness {
+    fn topic_log(
+        &mut self,
+        topic: &str,
+    ) -> Result<Arc<RwLock<dyn TopicLog + Send + Sync>>, StorageError> {
+        self.backend().create_topic_log(topic)
+    }
+
+    fn offset_store(
+        &mut self,
+        group_id: &str,
+    ) -> Result<Arc<dyn ConsumerOffsetStore>, StorageError> {
+        self.backend().create_offset_store(group_id)
+    }
+
+    fn restart(&mut self) -> bool {
+        // Release the old backend (and whatever it locks) before opening the new one
+        self.backend = None;
+        self.backend = Some((self.make)());
+        self.durable
+    }
+}
+
+/// Generate a module named `$name` with one `#[test]` per conformance case, each run
+/// against a fresh harness built by `$harness`.
+#[macro_export]
+macro_rules! storage_conformance_tests {
+    ($name:ident, $harness:expr) => {
+        mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::storage_conformance_tests!(@cases $harness;
+                topic_log::offsets_are_sequential_from_zero,
+                topic_log::batch_append_returns_last_offset,
+                topic_log::large_batches_keep_order_and_content,
+                topic_log::reads_respect_offset_and_count,
+                topic_log::compression_override_round_trips,
+                topic_log::time_polling_starts_at_timestamp,
+                topic_log::producer_timestamps_are_kept_out_of_order,
+                topic_log::offsets_are_listed_by_position_and_time,
+                topic_log::bounded_reads_stop_at_end_offset_and_time,
+                topic_log::invalid_timestamp_is_rejected,
+                topic_log::partitions_are_independent,
+                topic_log::replayed_offsets_are_kept,
+                topic_log::recovery_restores_records,
+                topic_log::concurrent_appends_assign_unique_offsets,
+                offset_store::unknown_offsets_load_as_zero,
+                offset_store::persist_snapshot_is_monotonic,
+                offset_store::batched_persists_are_monotonic,
+                offset_store::snapshots_are_tracked_per_partition,
+                offset_store::recovery_restores_snapshots,
+                offset_store::concurrent_persists_never_regress,
+            );
+        }
+    };
+    (@cases $harness:expr; $($module:ident :: $case:ident),* $(,)?) => {
+        $(
+            #[test]
+            fn $case() {
+                let mut harness = $harness;
+                $crate::$module::$case(&mut harness);
+            }
+        )*
+    };
+}
+
+/// Run every case against `harness` in turn. The macro is preferable in test suites since
+/// it reports each case separately.
+pub fn run_all<H: StorageHarness>(harness: &mut H) {
+    topic_log::offsets_are_sequential_from_zero(harness);
+    topic_log::batch_append_returns_last_offset(harness);
+    topic_log::large_batches_keep_order_and_content(harness);
+    topic_log::reads_respect_offset_and_count(harness);
+    topic_log::compression_override_round_trips(harness);
+    topic_log::time_polling_starts_at_timestamp(harness);
+    topic_log::producer_timestamps_are_kept_out_of_order(harness);
+    topic_log::offsets_are_listed_by_position_and_time(harness);
+    topic_log::bounded_reads_stop_at_end_offset_and_time(harness);
+    topic_log::invalid_timestamp_is_rejected(harness);
+    topic_log::partitions_are_independent(harness);
+    topic_log::replayed_offsets_are_kept(harness);
+    topic_log::recovery_restores_records(harness);
+    topic_log::concurrent_appends_assign_unique_offsets(harness);
+    offset_store::unknown_offsets_load_as_zero(harness);
+    offset_store::persist_snapshot_is_monotonic(harness);
+    offset_store::batched_persists_are_monotonic(harness);
+    offset_store::snapshots_are_tracked_per_partition(harness);
+    offset_store::recovery_restores_snapshots(harness);
+    offset_store::concurrent_persists_never_regress(harness);
+}
@@ -0,0 +1,184 @@
+//! Cases for `ConsumerOffsetStore`: per-partition snapshots, monotonic commits and recovery.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+
+use flashq_storage::PartitionId;
+
+use crate::StorageHarness;
+
+pub fn unknown_offsets_load_as_zero<H: StorageHarness>(harness: &mut H) {
+    let store = harness.offset_store("unknown").expect("open offset store");
+
+    assert_eq!(store.group_id(), "unknown");
+    assert_eq!(
+        store.load_snapshot("missing", PartitionId::new(0)).unwrap(),
+        0
+    );
+    assert!(store.get_all_snapshots().unwrap().is_empty());
+}
+
+pub fn persist_snapshot_is_monotonic<H: StorageHarness>(harness: &mut H) {
+    let store = harness
+        .offset_store("monotonic")
+        .expect("open offset store");
+    let partition = PartitionId::new(0);
+
+    assert!(
+        store
+            .persist_snapshot("t".to_string(), partition, 10)
+            .unwrap()
+    );
+    assert!(
+        store
+            .persist_snapshot("t".to_string(), partition, 10)
+            .unwrap(),
+        "committing the current offset again must be accepted"
+    );
+    assert!(
+        store
+            .persist_snapshot("t".to_string(), partition, 15)
+            .unwrap()
+    );
+    assert!(
+        !store
+            .persist_snapshot("t".to_string(), partition, 12)
+            .unwrap(),
+        "an offset below the committed one must be reported as stale"
+    );
+    assert_eq!(
+        store.load_snapshot("t", partition).unwrap(),
+        15,
+        "a stale commit must not move the offset back"
+    );
+}
+
+pub fn batched_persists_are_monotonic<H: StorageHarness>(harness: &mut H) {
+    let store = harness.offset_store("batched").expect("open offset store");
+    store
+        .persist_snapshot("a".to_string(), PartitionId::new(0), 20)
+        .unwrap();
+
+    let applied = store
+        .persist_snapshots(vec![
+            ("a".to_string(), PartitionId::new(0), 5),
+            ("a".to_string(), PartitionId::new(1), 7),
+            ("b".to_string(), PartitionId::new(0), 9),
+        ])
+        .unwrap();
+
+    assert_eq!(
+        applied,
+        vec![false, true, true],
+        "each entry of a batch must report whether it was applied"
+    );
+    assert_eq!(
+        store.load_snapshot("a", PartitionId::new(0)).unwrap(),
+        20,
+        "a stale entry must not move the offset back"
+    );
+    assert_eq!(store.load_snapshot("a", PartitionId::new(1)).unwrap(), 7);
+    assert_eq!(store.load_snapshot("b", PartitionId::new(0)).unwrap(), 9);
+    assert!(store.persist_snapshots(Vec::new()).unwrap().is_empty());
+}
+
+pub fn snapshots_are_tracked_per_partition<H: StorageHarness>(harness: &mut H) {
+    let store = harness
+        .offset_store("per_partition")
+        .expect("open offset store");
+    let other = harness
+        .offset_store("other_group")
+        .expect("open offset store");
+
+    store
+        .persist_snapshot("a".to_string(), PartitionId::new(0), 3)
+        .unwrap();
+    store
+        .persist_snapshot("a".to_string(), PartitionId::new(1), 7)
+        .unwrap();
+    store
+        .persist_snapshot("b".to_string(), PartitionId::new(0), 11)
+        .unwrap();
+    other
+        .persist_snapshot("a".to_string(), PartitionId::new(0), 99)
+        .unwrap();
+
+    assert_eq!(store.load_snapshot("a", PartitionId::new(0)).unwrap(), 3);
+    assert_eq!(store.load_snapshot("a", PartitionId::new(1)).unwrap(), 7);
+    assert_eq!(store.load_snapshot("b", PartitionId::new(1)).unwrap(), 0);
+    assert_eq!(
+        store.get_all_snapshots().unwrap(),
+        HashMap::from([
+            (("a".to_string(), PartitionId::new(0)), 3),
+            (("a".to_string(), PartitionId::new(1)), 7),
+            (("b".to_string(), PartitionId::new(0)), 11),
+        ]),
+        "groups must not see each other's offsets"
+    );
+}
+
+pub fn recovery_restores_snapshots<H: StorageHarness>(harness: &mut H) {
+    {
+        let store = harness.offset_store("durable").expect("open offset store");
+        store
+            .persist_snapshot("t".to_string(), PartitionId::new(0), 42)
+            .unwrap();
+        store
+            .persist_snapshot("t".to_string(), PartitionId::new(3), 8)
+            .unwrap();
+    }
+    if !harness.restart() {
+        return;
+    }
+
+    let store = harness
+        .offset_store("durable")
+        .expect("reopen offset store");
+    assert_eq!(store.load_snapshot("t", PartitionId::new(0)).unwrap(), 42);
+    assert_eq!(store.load_snapshot("t", PartitionId::new(3)).unwrap(), 8);
+    assert!(
+        !store
+            .persist_snapshot("t".to_string(), PartitionId::new(0), 40)
+            .unwrap(),
+        "recovered offsets must still reject stale commits"
+    );
+}
+
+pub fn concurrent_persists_never_regress<H: StorageHarness>(harness: &mut H) {
+    const THREADS: u64 = 8;
+    const COMMITS_PER_THREAD: u64 = 100;
+
+    let store = harness
+        .offset_store("concurrent")
+        .expect("open offset store");
+    let partition = PartitionId::new(0);
+    // Threads interleave their offsets so stale commits race with newer ones
+    let handles: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let store = Arc::clone(&store);
+            thread::spawn(move || {
+                for i in 0..COMMITS_PER_THREAD {
+                    let offset = i * THREADS + t;
+                    store
+                        .persist_snapshot("t".to_string(), partition, offset)
+                        .unwrap();
+                    let committed = store.load_snapshot("t", partition).unwrap();
+                    assert!(
+                        committed >= offset,
+                        "committed offset {committed} regressed below {offset}"
+                    );
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().expect("commit thread panicked");
+    }
+
+    assert_eq!(
+        store.load_snapshot("t", partition).unwrap(),
+        THREADS * COMMITS_PER_THREAD - 1,
+        "the highest committed offset must win"
+    );
+}
@@ -0,0 +1,516 @@
+//! Cases for `TopicLog`: offset assignment, batching, reads, partitions and recovery.
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use flashq_storage::{CompressionCodec, Header, PartitionId, ReadBounds, Record, RecordWithOffset};
+
+use crate::StorageHarness;
+
+fn record(value: &str) -> Record {
+    Record::new(None, value.to_string(), None)
+}
+
+fn records(prefix: &str, count: usize) -> Vec<Record> {
+    (0..count)
+        .map(|i| record(&format!("{prefix}-{i}")))
+        .collect()
+}
+
+fn values(records: &[RecordWithOffset]) -> Vec<&str> {
+    records.iter().map(|r| r.record.value.as_str()).collect()
+}
+
+fn offsets(records: &[RecordWithOffset]) -> Vec<u64> {
+    records.iter().map(|r| r.offset).collect()
+}
+
+pub fn offsets_are_sequential_from_zero<H: StorageHarness>(harness: &mut H) {
+    let log = harness.topic_log("sequential").expect("open topic log");
+    let mut log = log.write();
+
+    assert!(log.is_empty(), "a new topic log must be empty");
+    assert_eq!(
+        log.next_offset(),
+        0,
+        "a new topic log must start at offset 0"
+    );
+    for expected in 0..5 {
+        let offset = log.append(record(&format!("r{expected}"))).unwrap();
+        assert_eq!(offset, expected, "append must return the assigned offset");
+    }
+    assert_eq!(log.len(), 5);
+    assert_eq!(log.next_offset(), 5);
+}
+
+pub fn batch_append_returns_last_offset<H: StorageHarness>(harness: &mut H) {
+    let log = harness
+        .topic_log("batch_last_offset")
+        .expect("open topic log");
+    let mut log = log.write();
+
+    log.append(record("single")).unwrap();
+    let last = log.append_batch(records("batch", 4)).unwrap();
+    assert_eq!(
+        last, 4,
+        "append_batch must return the offset of its last record"
+    );
+    assert_eq!(log.next_offset(), 5);
+
+    let read = log.get_records_from_offset(0, None).unwrap();
+    assert_eq!(offsets(&read), vec![0, 1, 2, 3, 4]);
+    assert_eq!(
+        values(&read),
+        vec!["single", "batch-0", "batch-1", "batch-2", "batch-3"]
+    );
+}
+
+pub fn large_batches_keep_order_and_content<H: StorageHarness>(harness: &mut H) {
+    let log = harness.topic_log("large_batches").expect("open topic log");
+    let mut log = log.write();
+
+    // Large enough to be split into several internal batches and to span segments
+    let batch: Vec<Record> = (0..2_000)
+        .map(|i| {
+            let headers = (i % 3 == 0).then(|| {
+                vec![
+                    Header::new("index", i.to_string()),
+                    Header::new("source", "conformance"),
+                    Header::new("index", vec![0, 0xff, i as u8]),
+                ]
+            });
+            Record::new(
+                (i % 2 == 0).then(|| format!("key-{i}")),
+                format!("value-{i}-{}", "x".repeat(i % 64)),
+                headers,
+            )
+        })
+        .collect();
+
+    let last = log.append_batch(batch.clone()).unwrap();
+    assert_eq!(last, 1_999);
+
+    let read = log.get_records_from_offset(0, None).unwrap();
+    assert_eq!(read.len(), batch.len(), "every record must be readable");
+    for (i, (stored, original)) in read.iter().zip(&batch).enumerate() {
+        assert_eq!(stored.offset, i as u64, "offsets must follow append order");
+        assert_eq!(
+            &stored.record, original,
+            "record {i} must round-trip unchanged"
+        );
+    }
+}
+
+pub fn reads_respect_offset_and_count<H: StorageHarness>(harness: &mut H) {
+    let log = harness.topic_log("bounded_reads").expect("open topic log");
+    let mut log = log.write();
+    log.append_batch(records("r", 10)).unwrap();
+
+    let read = log.get_records_from_offset(3, Some(4)).unwrap();
+    assert_eq!(offsets(&read), vec![3, 4, 5, 6]);
+
+    let read = log.get_records_from_offset(8, Some(100)).unwrap();
+    assert_eq!(
+        offsets(&read),
+        vec![8, 9],
+        "a read must stop at the end of the log"
+    );
+
+    assert!(log.get_records_from_offset(3, Some(0)).unwrap().is_empty());
+    assert!(
+        log.get_records_from_offset(10, None).unwrap().is_empty(),
+        "reading at the next offset must return nothing"
+    );
+    assert!(log.get_records_from_offset(1_000, None).unwrap().is_empty());
+}
+
+pub fn compression_override_round_trips<H: StorageHarness>(harness: &mut H) {
+    let log = harness.topic_log("compression").expect("open topic log");
+    let mut log = log.write();
+
+    let codecs = [
+        CompressionCodec::None,
+        CompressionCodec::Gzip,
+        CompressionCodec::Snappy,
+        CompressionCodec::Lz4,
+        CompressionCodec::Zstd,
+    ];
+    let mut expected = Vec::new();
+    for codec in codecs {
+        let batch = records(&format!("{codec:?}-{}", "payload".repeat(20)), 25);
+        expected.extend(batch.clone());
+        log.append_batch_with_compression(batch, codec).unwrap();
+    }
+
+    let read = log.get_records_from_offset(0, None).unwrap();
+    assert_eq!(read.len(), expected.len());
+    for (stored, original) in read.iter().zip(&expected) {
+        assert_eq!(
+            &stored.record, original,
+            "compressed records must decode unchanged"
+        );
+    }
+}
+
+pub fn time_polling_starts_at_timestamp<H: StorageHarness>(harness: &mut H) {
+    let log = harness.topic_log("time_polling").expect("open topic log");
+    let mut log = log.write();
+
+    log.append_batch(records("before", 3)).unwrap();
+    thread::sleep(Duration::from_millis(20));
+    let cutoff = chrono::Utc::now().to_rfc3339();
+    thread::sleep(Duration::from_millis(20));
+    log.append_batch(records("after", 3)).unwrap();
+
+    let read = log.get_records_from_timestamp(&cutoff, None).unwrap();
+    assert_eq!(
+        values(&read),
+        vec!["after-0", "after-1", "after-2"],
+        "time polling must start at the first record at or after the timestamp"
+    );
+
+    let read = log.get_records_from_timestamp(&cutoff, Some(2)).unwrap();
+    assert_eq!(offsets(&read), vec![3, 4]);
+
+    let all = log
+        .get_records_from_timestamp("1970-01-01T00:00:00Z", None)
+        .unwrap();
+    assert_eq!(all.len(), 6);
+
+    let future = log
+        .get_records_from_timestamp("2999-01-01T00:00:00Z", None)
+        .unwrap();
+    assert!(
+        future.is_empty(),
+        "no record can be newer than the far future"
+    );
+}
+
+pub fn producer_timestamps_are_kept_out_of_order<H: StorageHarness>(harness: &mut H) {
+    let log = harness
+        .topic_log("producer_timestamps")
+        .expect("open topic log");
+    let mut log = log.write();
+
+    // A permutation of 300 seconds, so timestamps jump back and forth across batches
+    let base = chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap();
+    let create_time = |i: u64| base + chrono::Duration::seconds(((i * 7) % 300) as i64);
+    let batch: Vec<Record> = (0..300)
+        .map(|i| record(&format!("r{i}")).with_create_time(create_time(i).to_rfc3339()))
+        .collect();
+    for chunk in batch.chunks(50) {
+        log.append_batch(chunk.to_vec()).unwrap();
+    }
+
+    let stored = log.get_records_from_offset(0, None).unwrap();
+    for record in &stored {
+        let timestamp = chrono::DateTime::parse_from_rfc3339(&record.timestamp).unwrap();
+        assert_eq!(
+            timestamp,
+            create_time(record.offset),
+            "a producer timestamp must be stored as the record's timestamp"
+        );
+        assert_eq!(record.record.create_time, None);
+    }
+
+    let target = create_time(0) + chrono::Duration::seconds(150);
+    let read = log
+        .get_records_from_timestamp(&target.to_rfc3339(), None)
+        .unwrap();
+    let expected: Vec<u64> = (0..300).filter(|&i| create_time(i) >= target).collect();
+    assert_eq!(
+        offsets(&read),
+        expected,
+        "time polling must return every record at or after the timestamp, in offset order"
+    );
+}
+
+pub fn offsets_are_listed_by_position_and_time<H: StorageHarness>(harness: &mut H) {
+    let log = harness.topic_log("list_offsets").expect("open topic log");
+    let mut log = log.write();
+    let partition = PartitionId::new(0);
+
+    let base = chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap();
+    let at = |secs: i64| (base + chrono::Duration::seconds(secs)).to_rfc3339();
+    assert_eq!(log.partition_start_offset(partition).unwrap(), 0);
+    assert_eq!(
+        log.partition_offset_for_timestamp(partition, &at(0))
+            .unwrap(),
+        None
+    );
+
+    let stamped = |secs: &[i64]| -> Vec<Record> {
+        secs.iter()
+            .map(|&s| record(&format!("t{s}")).with_create_time(at(s)))
+            .collect()
+    };
+    log.append_batch(stamped(&[10, 30, 20])).unwrap();
+    log.append_batch(stamped(&[40, 25])).unwrap();
+
+    assert_eq!(log.partition_start_offset(partition).unwrap(), 0);
+    assert_eq!(log.partition_next_offset(partition), 5);
+    let offset_for = |secs: i64| {
+        log.partition_offset_for_timestamp(partition, &at(secs))
+            .unwrap()
+    };
+    assert_eq!(
+        offset_for(5),
+        Some(0),
+        "a time before every record maps to the first"
+    );
+    assert_eq!(
+        offset_for(25),
+        Some(1),
+        "the first offset whose timestamp is at or after the target wins, not the closest"
+    );
+    assert_eq!(offset_for(35), Some(3));
+    assert_eq!(
+        offset_for(50),
+        None,
+        "a time after every record has no offset"
+    );
+}
+
+pub fn bounded_reads_stop_at_end_offset_and_time<H: StorageHarness>(harness: &mut H) {
+    let log = harness.topic_log("bounded_reads").expect("open topic log");
+    let mut log = log.write();
+    let partition = PartitionId::new(0);
+
+    let base = chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap();
+    let at = |secs: i64| (base + chrono::Duration::seconds(secs)).to_rfc3339();
+    let secs = [10, 30, 20, 40, 25, 5, 50];
+    let batch: Vec<Record> = secs
+        .iter()
+        .map(|&s| record(&format!("t{s}")).with_create_time(at(s)))
+        .collect();
+    log.append_batch(batch).unwrap();
+
+    let to_offset = ReadBounds::default().with_to_offset(4);
+    let read = log
+        .read_from_partition_bounded(partition, 1, to_offset, None)
+        .unwrap();
+    assert_eq!(offsets(&read), vec![1, 2, 3], "to_offset is exclusive");
+    let read = log
+        .read_from_partition_bounded(partition, 1, to_offset, Some(2))
+        .unwrap();
+    assert_eq!(offsets(&read), vec![1, 2]);
+    let read = log
+        .read_from_partition_bounded(partition, 4, to_offset, None)
+        .unwrap();
+    assert!(read.is_empty(), "a read starting at to_offset is empty");
+
+    let to_time = ReadBounds::default().with_to_time(&at(30)).unwrap();
+    let read = log
+        .read_from_partition_bounded(partition, 0, to_time, None)
+        .unwrap();
+    assert_eq!(
+        offsets(&read),
+        vec![0, 2, 4, 5],
+        "to_time is exclusive and applies per record, not as a cut-off in offset order"
+    );
+    let read = log
+        .read_from_partition_timestamp_bounded(partition, &at(15), to_time, None)
+        .unwrap();
+    assert_eq!(offsets(&read), vec![2, 4]);
+
+    let both = to_time.with_to_offset(3);
+    let read = log
+        .read_from_partition_timestamp_bounded(partition, &at(0), both, None)
+        .unwrap();
+    assert_eq!(offsets(&read), vec![0, 2]);
+
+    assert!(
+        ReadBounds::default()
+            .with_to_time("not-a-timestamp")
+            .is_err(),
+        "a to_time that is not RFC3339 must be rejected"
+    );
+}
+
+pub fn invalid_timestamp_is_rejected<H: StorageHarness>(harness: &mut H) {
+    let log = harness
+        .topic_log("invalid_timestamp")
+        .expect("open topic log");
+    let mut log = log.write();
+    log.append(record("r")).unwrap();
+
+    assert!(
+        log.get_records_from_timestamp("not-a-timestamp", None)
+            .is_err(),
+        "a timestamp that is not RFC3339 must be rejected"
+    );
+}
+
+pub fn partitions_are_independent<H: StorageHarness>(harness: &mut H) {
+    let log = harness.topic_log("partitions").expect("open topic log");
+    let mut log = log.write();
+    let p0 = PartitionId::new(0);
+    let p1 = PartitionId::new(1);
+    let p4 = PartitionId::new(4);
+
+    assert_eq!(log.append_partition(p1, record("p1-0")).unwrap(), 0);
+    assert_eq!(log.append_batch_partition(p4, records("p4", 3)).unwrap(), 2);
+    assert_eq!(log.append_partition(p1, record("p1-1")).unwrap(), 1);
+    assert_eq!(log.append_partition(p0, record("p0-0")).unwrap(), 0);
+
+    assert_eq!(log.partition_ids(), vec![p0, p1, p4]);
+    assert_eq!(log.partition_len(p1), 2);
+    assert_eq!(log.partition_next_offset(p4), 3);
+    assert!(log.partition_is_empty(PartitionId::new(2)));
+    assert_eq!(log.partition_next_offset(PartitionId::new(2)), 0);
+
+    let read = log.read_from_partition(p1, 0, None).unwrap();
+    assert_eq!(values(&read), vec!["p1-0", "p1-1"]);
+    let read = log.read_from_partition(p4, 1, None).unwrap();
+    assert_eq!(values(&read), vec!["p4-1", "p4-2"]);
+    assert!(
+        log.read_from_partition(PartitionId::new(2), 0, None)
+            .unwrap()
+            .is_empty(),
+        "reading a partition that was never written must return nothing"
+    );
+}
+
+pub fn replayed_offsets_are_kept<H: StorageHarness>(harness: &mut H) {
+    let log = harness.topic_log("replayed").expect("open topic log");
+    let mut log = log.write();
+    let partition = PartitionId::new(0);
+    let replayed = |offset: u64, timestamp: &str| RecordWithOffset {
+        record: record(&format!("replayed-{offset}")),
+        offset,
+        timestamp: timestamp.to_string(),
+    };
+
+    let last = log
+        .append_batch_with_offsets_partition(
+            partition,
+            vec![
+                replayed(5, "2024-01-01T00:00:00Z"),
+                replayed(6, "2024-01-01T00:00:00Z"),
+                replayed(9, "2024-01-02T00:00:00Z"),
+            ],
+        )
+        .unwrap();
+    assert_eq!(last, 9);
+    assert_eq!(log.partition_next_offset(partition), 10);
+
+    let read = log.read_from_partition(partition, 0, None).unwrap();
+    assert_eq!(
+        offsets(&read),
+        vec![5, 6, 9],
+        "replayed offsets must be kept"
+    );
+    let read = log.read_from_partition(partition, 7, None).unwrap();
+    assert_eq!(
+        offsets(&read),
+        vec![9],
+        "a read inside a gap starts after it"
+    );
+
+    let read = log
+        .read_from_partition_timestamp(partition, "2024-01-01T12:00:00Z", None)
+        .unwrap();
+    assert_eq!(offsets(&read), vec![9], "replayed timestamps must be kept");
+
+    assert!(
+        log.append_batch_with_offsets_partition(
+            partition,
+            vec![replayed(9, "2024-01-03T00:00:00Z")]
+        )
+        .is_err(),
+        "an offset that was already assigned must be rejected"
+    );
+    assert!(
+        log.append_batch_with_offsets_partition(
+            partition,
+            vec![
+                replayed(12, "2024-01-03T00:00:00Z"),
+                replayed(11, "2024-01-03T00:00:00Z"),
+            ],
+        )
+        .is_err(),
+        "offsets that do not increase must be rejected"
+    );
+
+    assert_eq!(log.append_partition(partition, record("next")).unwrap(), 10);
+}
+
+pub fn recovery_restores_records<H: StorageHarness>(harness: &mut H) {
+    {
+        let log = harness.topic_log("recovery").expect("open topic log");
+        let mut log = log.write();
+        log.append_batch(records("p0", 300)).unwrap();
+        log.append_batch_partition(PartitionId::new(2), records("p2", 5))
+            .unwrap();
+    }
+    if !harness.restart() {
+        return;
+    }
+
+    let log = harness.topic_log("recovery").expect("reopen topic log");
+    let mut log = log.write();
+    assert_eq!(
+        log.next_offset(),
+        300,
+        "the next offset must survive a restart"
+    );
+    assert_eq!(log.partition_next_offset(PartitionId::new(2)), 5);
+    assert_eq!(
+        log.partition_ids(),
+        vec![PartitionId::new(0), PartitionId::new(2)]
+    );
+
+    let read = log.get_records_from_offset(0, None).unwrap();
+    assert_eq!(read.len(), 300);
+    assert_eq!(read[299].record.value, "p0-299");
+    let read = log
+        .read_from_partition(PartitionId::new(2), 0, None)
+        .unwrap();
+    assert_eq!(values(&read), vec!["p2-0", "p2-1", "p2-2", "p2-3", "p2-4"]);
+
+    assert_eq!(
+        log.append(record("after-restart")).unwrap(),
+        300,
+        "appends after a restart must continue from the recovered offset"
+    );
+}
+
+pub fn concurrent_appends_assign_unique_offsets<H: StorageHarness>(harness: &mut H) {
+    const THREADS: usize = 8;
+    const APPENDS_PER_THREAD: usize = 50;
+
+    let log = harness.topic_log("concurrent").expect("open topic log");
+    let handles: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let log = Arc::clone(&log);
+            thread::spawn(move || {
+                (0..APPENDS_PER_THREAD)
+                    .map(|i| log.write().append(record(&format!("t{t}-{i}"))).unwrap())
+                    .collect::<Vec<u64>>()
+            })
+        })
+        .collect();
+
+    let mut assigned: Vec<u64> = handles
+        .into_iter()
+        .flat_map(|h| h.join().expect("append thread panicked"))
+        .collect();
+    assigned.sort_unstable();
+    let expected: Vec<u64> = (0..(THREADS * APPENDS_PER_THREAD) as u64).collect();
+    assert_eq!(assigned, expected, "every append must get its own offset");
+
+    let read = log.read().get_records_from_offset(0, None).unwrap();
+    assert_eq!(offsets(&read), expected);
+    for t in 0..THREADS {
+        let prefix = format!("t{t}-");
+        let mine: Vec<&str> = values(&read)
+            .into_iter()
+            .filter(|v| v.starts_with(&prefix))
+            .collect();
+        let in_order: Vec<String> = (0..APPENDS_PER_THREAD)
+            .map(|i| format!("{prefix}{i}"))
+            .collect();
+        assert_eq!(mine, in_order, "records from one writer must stay in order");
+    }
+}
@@ -0,0 +1,29 @@
+use flashq_storage::{StorageBackend, SyncMode};
+use flashq_storage_testkit::{BackendHarness, storage_conformance_tests};
+
+storage_conformance_tests!(memory, BackendHarness::memory());
+storage_conformance_tests!(file, BackendHarness::file());
+storage_conformance_tests!(
+    file_sqlite_offsets,
+    BackendHarness::in_temp_dir(
+        |dir| {
+            StorageBackend::new_file_with_config(SyncMode::Immediate, dir, 1000, 16 * 1024)
+                .unwrap()
+                .with_sqlite_offset_store()
+                .unwrap()
+        },
+        true
+    )
+);
+storage_conformance_tests!(
+    file_offsets_topic,
+    BackendHarness::in_temp_dir(
+        |dir| {
+            StorageBackend::new_file_with_config(SyncMode::Immediate, dir, 1000, 16 * 1024)
+                .unwrap()
+                .with_offsets_topic()
+                .unwrap()
+        },
+        true
+    )
+);
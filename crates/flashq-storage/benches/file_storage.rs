@@ -1,7 +1,6 @@
 use divan::{AllocProfiler, Bencher, black_box};
-use flashq::{FlashQ, Record};
+use flashq::{FlashQ, Header, Record};
 use flashq_storage::{StorageBackend, file::SyncMode};
-use std::collections::HashMap;
 use tempfile::TempDir;
 
 #[global_allocator]
@@ -17,11 +16,7 @@ fn create_1kb_record(index: usize) -> Record {
     Record::new(
         Some(format!("key_{index}")),
         payload,
-        Some({
-            let mut headers = HashMap::new();
-            headers.insert("index".to_string(), index.to_string());
-            headers
-        }),
+        Some(vec![Header::new("index", index.to_string())]),
     )
 }
 
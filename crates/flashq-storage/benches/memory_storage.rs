@@ -1,6 +1,5 @@
 use divan::{AllocProfiler, Bencher, black_box};
-use flashq::{FlashQ, Record};
-use std::collections::HashMap;
+use flashq::{FlashQ, Header, Record};
 
 #[global_allocator]
 static ALLOC: AllocProfiler = AllocProfiler::system();
@@ -15,11 +14,7 @@ fn create_1kb_record(index: usize) -> Record {
     Record::new(
         Some(format!("key_{index}")),
         payload,
-        Some({
-            let mut headers = HashMap::new();
-            headers.insert("index".to_string(), index.to_string());
-            headers
-        }),
+        Some(vec![Header::new("index", index.to_string())]),
     )
 }
 
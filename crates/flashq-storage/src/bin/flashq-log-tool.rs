@@ -0,0 +1,254 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand};
+use flashq_storage::StorageError;
+use flashq_storage::backend::DirectoryLock;
+use flashq_storage::file::IndexingConfig;
+use flashq_storage::file::log_tool::{
+    SegmentBatch, dump_segment, find_segment_logs, rebuild_segment_indexes, truncate_partition,
+    verify_path,
+};
+use flashq_storage::file::snapshot::snapshot_data_dir;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "flashq-log-tool",
+    version,
+    author,
+    about = "Inspect and repair a stopped broker's segment files"
+)]
+struct Cli {
+    /// Broker data directory; its .flashq.lock is held while the command runs
+    #[arg(long, default_value = "./data")]
+    data_dir: PathBuf,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Print the batches and records of a .log segment
+    Dump(DumpCmd),
+    /// Check record framing and index consistency of a segment or every segment below a directory
+    Verify(VerifyCmd),
+    /// Rebuild the offset and time indexes of a segment or every segment below a directory
+    RebuildIndex(RebuildIndexCmd),
+    /// Remove every record at or above an offset from a partition
+    Truncate(TruncateCmd),
+    /// Copy the data directory into a new directory a broker can start on, without stopping
+    /// the broker that owns it
+    Snapshot(SnapshotCmd),
+}
+
+#[derive(Args, Debug)]
+struct DumpCmd {
+    /// Segment .log file
+    log_file: PathBuf,
+    /// Also print record values
+    #[arg(long)]
+    print_values: bool,
+}
+
+#[derive(Args, Debug)]
+struct VerifyCmd {
+    /// Segment .log file or directory (defaults to the whole data directory)
+    path: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct RebuildIndexCmd {
+    /// Segment .log file or directory
+    path: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct TruncateCmd {
+    #[arg(long)]
+    topic: String,
+    #[arg(long, default_value_t = 0)]
+    partition: u32,
+    /// First offset to remove
+    #[arg(long)]
+    to_offset: u64,
+}
+
+#[derive(Args, Debug)]
+struct SnapshotCmd {
+    /// Destination directory; must not exist yet or be empty
+    dest: PathBuf,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<ExitCode, StorageError> {
+    if !cli.data_dir.is_dir() {
+        return Err(StorageError::Unavailable {
+            context: format!("data directory {} does not exist", cli.data_dir.display()),
+        });
+    }
+
+    match cli.command {
+        Commands::Dump(cmd) => {
+            let _lock = lock_for_reading(&cli.data_dir)?;
+            dump(&cmd)
+        }
+        Commands::Verify(cmd) => {
+            let _lock = lock_for_reading(&cli.data_dir)?;
+            verify(cmd.path.as_deref().unwrap_or(&cli.data_dir))
+        }
+        Commands::RebuildIndex(cmd) => {
+            let _lock = DirectoryLock::acquire(&cli.data_dir)?;
+            rebuild(&cmd.path)
+        }
+        Commands::Truncate(cmd) => {
+            let _lock = DirectoryLock::acquire(&cli.data_dir)?;
+            let partition_dir = cli
+                .data_dir
+                .join(&cmd.topic)
+                .join(cmd.partition.to_string());
+            let summary =
+                truncate_partition(&partition_dir, cmd.to_offset, IndexingConfig::default())?;
+            for base_offset in &summary.removed_segments {
+                println!("removed segment {base_offset:020}");
+            }
+            if let Some(base_offset) = summary.truncated_segment {
+                println!("truncated segment {base_offset:020}");
+            }
+            println!(
+                "{}/{} next offset: {}",
+                cmd.topic, cmd.partition, summary.next_offset
+            );
+            Ok(ExitCode::SUCCESS)
+        }
+        // Hot by design: only appends race with it, and it never modifies the source
+        Commands::Snapshot(cmd) => snapshot(&cli.data_dir, &cmd.dest),
+    }
+}
+
+/// Reading commands still take the lock so a broker cannot start mid-inspection, but fall
+/// back to reading without it when a live broker holds it.
+fn lock_for_reading(data_dir: &Path) -> Result<Option<DirectoryLock>, StorageError> {
+    match DirectoryLock::acquire(data_dir) {
+        Ok(lock) => Ok(Some(lock)),
+        Err(StorageError::DirectoryLocked { pid, .. }) => {
+            let owner = pid.map_or_else(|| "another process".to_string(), |p| format!("pid {p}"));
+            eprintln!(
+                "warning: {} is locked by {owner}; output may not reflect in-flight writes",
+                data_dir.display()
+            );
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn dump(cmd: &DumpCmd) -> Result<ExitCode, StorageError> {
+    dump_segment(&cmd.log_file, |batch| print_batch(batch, cmd.print_values))?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_batch(batch: &SegmentBatch, print_values: bool) {
+    let header = &batch.header;
+    println!(
+        "batch position: {} base_offset: {} last_offset: {} count: {} codec: {} crc: {} min_ts_ms: {} max_ts_ms: {}",
+        batch.position,
+        header.base_offset,
+        header.last_offset(),
+        header.record_count,
+        header.codec,
+        header.crc,
+        header.min_ts_ms,
+        header.max_ts_ms
+    );
+    for record in &batch.records {
+        let headers: Vec<String> = record
+            .record
+            .headers
+            .iter()
+            .flatten()
+            .map(|h| format!("{}={}", h.key, String::from_utf8_lossy(&h.value)))
+            .collect();
+        print!(
+            "| offset: {} timestamp: {} key: {} headers: [{}]",
+            record.offset,
+            record.timestamp,
+            record.record.key.as_deref().unwrap_or("-"),
+            headers.join(", ")
+        );
+        if print_values {
+            print!(" value: {}", record.record.value);
+        }
+        println!();
+    }
+}
+
+fn verify(path: &Path) -> Result<ExitCode, StorageError> {
+    let reports = verify_path(path)?;
+    let mut failed = 0;
+    for report in &reports {
+        if report.is_clean() {
+            println!(
+                "ok {} ({} batches, {} records)",
+                report.log_path.display(),
+                report.batches,
+                report.records
+            );
+        } else {
+            failed += 1;
+            println!("FAILED {}", report.log_path.display());
+            for issue in &report.issues {
+                println!("  - {issue}");
+            }
+        }
+    }
+    println!("{} segments checked, {failed} with issues", reports.len());
+    Ok(if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn rebuild(path: &Path) -> Result<ExitCode, StorageError> {
+    for log_path in find_segment_logs(path)? {
+        let rebuilt = rebuild_segment_indexes(&log_path, IndexingConfig::default())?;
+        println!(
+            "rebuilt {} ({} offset entries, {} time entries)",
+            log_path.display(),
+            rebuilt.offset_entries,
+            rebuilt.time_entries
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn snapshot(data_dir: &Path, dest: &Path) -> Result<ExitCode, StorageError> {
+    let summary = snapshot_data_dir(data_dir, dest)?;
+    for partition in &summary.partitions {
+        println!(
+            "{}/{} linked {} segments, copied {} active bytes, next offset: {}",
+            partition.topic,
+            partition.partition,
+            partition.linked_segments,
+            partition.active_bytes,
+            partition.next_offset
+        );
+    }
+    println!(
+        "snapshot written to {} ({} consumer groups)",
+        dest.display(),
+        summary.consumer_group_files
+    );
+    Ok(ExitCode::SUCCESS)
+}
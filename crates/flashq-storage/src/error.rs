@@ -28,6 +28,8 @@ pub enum StorageError {
         pid: Option<u32>,
     },
     InvalidTopic(String),
+    /// A producer timestamp that is malformed or too far from the broker clock.
+    InvalidTimestamp(String),
     LockAcquisitionFailed,
 }
 
@@ -65,6 +67,7 @@ impl fmt::Display for StorageError {
                 None => write!(f, "Directory locked in {context}"),
             },
             StorageError::InvalidTopic(topic) => write!(f, "Invalid topic: {topic}"),
+            StorageError::InvalidTimestamp(reason) => write!(f, "Invalid timestamp: {reason}"),
             StorageError::LockAcquisitionFailed => {
                 write!(f, "Failed to acquire exclusive lock on file")
             }
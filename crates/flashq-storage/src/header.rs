@@ -0,0 +1,197 @@
+//! Record headers: an ordered list of (key, bytes) pairs. Keys may repeat and values are
+//! arbitrary bytes, so trace context, schema ids and similar binary metadata fit as-is.
+//!
+//! In JSON (segment payloads, archives) a header is `{"key": "...", "value": "<base64>"}`.
+//! Records written before headers became a list stored them as a `{"key": "value"}` object;
+//! those still decode, each entry becoming one header with the UTF-8 bytes of its value.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Header {
+    pub key: String,
+    pub value: Vec<u8>,
+}
+
+impl Header {
+    pub fn new(key: impl Into<String>, value: impl Into<Vec<u8>>) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+
+    /// The value as UTF-8, if it is valid UTF-8.
+    pub fn value_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.value).ok()
+    }
+}
+
+/// Headers for the old map form, one per entry in key order since a map has none of its own.
+pub fn headers_from_map(map: HashMap<String, String>) -> Vec<Header> {
+    let mut headers: Vec<Header> = map
+        .into_iter()
+        .map(|(key, value)| Header::new(key, value))
+        .collect();
+    headers.sort_by(|a, b| a.key.cmp(&b.key));
+    headers
+}
+
+/// The old map form of `headers`: the last value wins for repeated keys and values that are
+/// not UTF-8 are converted lossily.
+pub fn headers_to_map(headers: &[Header]) -> HashMap<String, String> {
+    headers
+        .iter()
+        .map(|h| {
+            (
+                h.key.clone(),
+                String::from_utf8_lossy(&h.value).into_owned(),
+            )
+        })
+        .collect()
+}
+
+impl Serialize for Header {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut header = serializer.serialize_struct("Header", 2)?;
+        header.serialize_field("key", &self.key)?;
+        header.serialize_field("value", &BASE64.encode(&self.value))?;
+        header.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Header {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Encoded {
+            key: String,
+            value: String,
+        }
+        let encoded = Encoded::deserialize(deserializer)?;
+        let value = BASE64
+            .decode(encoded.value.as_bytes())
+            .map_err(de::Error::custom)?;
+        Ok(Header::new(encoded.key, value))
+    }
+}
+
+/// `deserialize_with` for `Record::headers`: accepts null, a list of headers, or the old
+/// string-to-string object.
+pub(crate) fn deserialize_headers<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<Header>>, D::Error> {
+    struct HeadersVisitor;
+
+    impl<'de> Visitor<'de> for HeadersVisitor {
+        type Value = Option<Vec<Header>>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a list of headers or a map of string headers")
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+            d.deserialize_any(self)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut headers = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(header) = seq.next_element()? {
+                headers.push(header);
+            }
+            Ok(Some(headers))
+        }
+
+        // The old map form; entries keep the order they were written in
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut headers = Vec::with_capacity(map.size_hint().unwrap_or(0));
+            while let Some((key, value)) = map.next_entry::<String, String>()? {
+                headers.push(Header::new(key, value));
+            }
+            Ok(Some(headers))
+        }
+    }
+
+    deserializer.deserialize_option(HeadersVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Record;
+
+    #[test]
+    fn test_headers_round_trip_through_json_in_order() {
+        let record = Record::new(
+            None,
+            "v".to_string(),
+            Some(vec![
+                Header::new("trace", vec![0u8, 159, 146, 150]),
+                Header::new("schema", "7"),
+                Header::new("trace", "second"),
+            ]),
+        );
+
+        let json = serde_json::to_string(&record).unwrap();
+        let decoded: Record = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(decoded, record);
+    }
+
+    #[test]
+    fn test_map_form_headers_still_decode() {
+        let decoded: Record =
+            serde_json::from_str(r#"{"key":null,"value":"v","headers":{"b":"2","a":"1"}}"#)
+                .unwrap();
+
+        assert_eq!(
+            decoded.headers,
+            Some(vec![Header::new("b", "2"), Header::new("a", "1")])
+        );
+    }
+
+    #[test]
+    fn test_missing_or_null_headers_decode_as_none() {
+        let missing: Record = serde_json::from_str(r#"{"key":null,"value":"v"}"#).unwrap();
+        let null: Record =
+            serde_json::from_str(r#"{"key":null,"value":"v","headers":null}"#).unwrap();
+
+        assert_eq!(missing.headers, None);
+        assert_eq!(null.headers, None);
+    }
+
+    #[test]
+    fn test_map_conversions() {
+        let headers = vec![
+            Header::new("a", "1"),
+            Header::new("b", vec![0xff]),
+            Header::new("a", "2"),
+        ];
+
+        let map = headers_to_map(&headers);
+
+        assert_eq!(map["a"], "2", "the last value of a repeated key wins");
+        assert_eq!(map["b"], "\u{fffd}");
+        assert_eq!(
+            headers_from_map(HashMap::from([
+                ("z".to_string(), "1".to_string()),
+                ("a".to_string(), "2".to_string()),
+            ])),
+            vec![Header::new("a", "2"), Header::new("z", "1")]
+        );
+    }
+}
@@ -1,12 +1,22 @@
-use std::collections::HashMap;
-
 pub mod error;
+pub mod header;
 pub mod storage;
 
 pub use error::{StorageError, StorageErrorSource};
+pub use header::{Header, headers_from_map, headers_to_map};
 pub use storage::{
     backend::StorageBackend,
-    r#trait::{ConsumerGroup, ConsumerOffsetStore, PartitionId, TopicLog},
+    compression::CompressionCodec,
+    factory::StorageFactory,
+    io_pool::{IoPool, IoTask},
+    offsets_topic::{CONSUMER_OFFSETS_TOPIC, is_internal_topic},
+    read_bounds::ReadBounds,
+    remote::{FsRemoteSegmentStore, RemoteSegmentStore, TieredStorageConfig},
+    topic_config::{TimestampType, TopicConfig},
+    r#trait::{
+        ConsumerGroup, ConsumerOffsetStore, PartitionId, SegmentStats, TopicLog,
+        validate_replayed_offsets,
+    },
 };
 
 pub mod file {
@@ -27,7 +37,12 @@ pub mod memory {
 pub struct Record {
     pub key: Option<String>,
     pub value: String,
-    pub headers: Option<HashMap<String, String>>,
+    #[serde(default, deserialize_with = "header::deserialize_headers")]
+    pub headers: Option<Vec<Header>>,
+    /// Event time (RFC3339) supplied by the producer. The topic's timestamp type decides
+    /// whether it becomes the stored timestamp; it is not kept in the log either way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub create_time: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -39,17 +54,29 @@ pub struct RecordWithOffset {
 }
 
 impl Record {
-    pub fn new(
-        key: Option<String>,
-        value: String,
-        headers: Option<HashMap<String, String>>,
-    ) -> Self {
+    pub fn new(key: Option<String>, value: String, headers: Option<Vec<Header>>) -> Self {
         Self {
             key,
             value,
             headers,
+            create_time: None,
         }
     }
+
+    pub fn with_create_time(mut self, create_time: impl Into<String>) -> Self {
+        self.create_time = Some(create_time.into());
+        self
+    }
+
+    /// Value of the last header named `key`, the one that wins when headers are read as a map.
+    pub fn header(&self, key: &str) -> Option<&[u8]> {
+        self.headers
+            .iter()
+            .flatten()
+            .rev()
+            .find(|header| header.key == key)
+            .map(|header| header.value.as_slice())
+    }
 }
 
 impl RecordWithOffset {
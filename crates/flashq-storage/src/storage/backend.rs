@@ -1,18 +1,41 @@
 use crate::error::StorageError;
-use crate::storage::file::{FileConsumerGroup, FileConsumerOffsetStore, FileTopicLog};
+use crate::storage::file::snapshot::{SnapshotSummary, snapshot_data_dir};
+use crate::storage::file::{
+    FileConsumerGroup, FileConsumerOffsetStore, FileTopicLog, SqliteConsumerOffsetStore,
+    SqliteOffsetDatabase,
+};
+use crate::storage::offsets_topic::{
+    CONSUMER_OFFSETS_TOPIC, OffsetsTopic, TopicConsumerOffsetStore, is_internal_topic,
+};
 use crate::storage::{
-    ConsumerGroup, ConsumerOffsetStore, InMemoryConsumerGroup, InMemoryConsumerOffsetStore,
-    InMemoryTopicLog, TopicLog,
+    CompressionCodec, ConsumerGroup, ConsumerOffsetStore, InMemoryConsumerGroup,
+    InMemoryConsumerOffsetStore, InMemoryTopicLog, IoPool, StorageFactory, TieredStorageConfig,
+    TimestampType, TopicConfig, TopicLog,
 };
 use fs4::fs_std::FileExt;
 use log::{debug, warn};
 use parking_lot::RwLock;
+use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use sysinfo::{ProcessesToUpdate, System};
 
+/// Where a file backend keeps consumer group offsets.
+#[derive(Debug, Clone, Default)]
+pub enum OffsetStorage {
+    /// One JSON file per group under `consumer_groups/`.
+    #[default]
+    Json,
+    /// A SQLite database shared by every group.
+    Sqlite(Arc<SqliteOffsetDatabase>),
+    /// Records of the internal `__consumer_offsets` topic.
+    Topic(Arc<OffsetsTopic>),
+}
+
+// Built once per broker, so the size of the File variant does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum StorageBackend {
     Memory {
@@ -25,6 +48,11 @@ pub enum StorageBackend {
         segment_size_bytes: u64,
         batch_bytes: usize,
         indexing_config: crate::storage::file::IndexingConfig,
+        topic_config: TopicConfig,
+        topic_overrides: HashMap<String, TopicConfig>,
+        io_threads: usize,
+        tiered_storage: Option<TieredStorageConfig>,
+        offset_storage: OffsetStorage,
         _directory_lock: File,
     },
 }
@@ -79,6 +107,11 @@ impl StorageBackend {
             segment_size_bytes,
             batch_bytes: crate::storage::batching_heuristics::default_batch_bytes(),
             indexing_config: crate::storage::file::IndexingConfig::default(),
+            topic_config: TopicConfig::default(),
+            topic_overrides: HashMap::new(),
+            io_threads: IoPool::default_threads(),
+            tiered_storage: None,
+            offset_storage: OffsetStorage::Json,
             _directory_lock: directory_lock,
         })
     }
@@ -98,6 +131,11 @@ impl StorageBackend {
             segment_size_bytes: DEFAULT_SEGMENT_SIZE,
             batch_bytes,
             indexing_config: crate::storage::file::IndexingConfig::default(),
+            topic_config: TopicConfig::default(),
+            topic_overrides: HashMap::new(),
+            io_threads: IoPool::default_threads(),
+            tiered_storage: None,
+            offset_storage: OffsetStorage::Json,
             _directory_lock: directory_lock,
         })
     }
@@ -118,6 +156,128 @@ impl StorageBackend {
         self
     }
 
+    /// Set the default batch compression codec for file-backed topics; no-op for memory backend.
+    pub fn with_compression(mut self, compression: CompressionCodec) -> Self {
+        if let StorageBackend::File { topic_config, .. } = &mut self {
+            topic_config.compression = compression;
+        }
+        self
+    }
+
+    /// Roll file-backed segments once their first batch is `segment_ms` old; no-op for memory backend.
+    pub fn with_segment_ms(mut self, segment_ms: u64) -> Self {
+        if let StorageBackend::File { topic_config, .. } = &mut self {
+            topic_config.segment_ms = Some(segment_ms);
+        }
+        self
+    }
+
+    /// Roll file-backed segments once their offset index holds `max_entries`; no-op for memory backend.
+    pub fn with_segment_index_max_entries(mut self, max_entries: usize) -> Self {
+        if let StorageBackend::File { topic_config, .. } = &mut self {
+            topic_config.segment_index_max_entries = Some(max_entries);
+        }
+        self
+    }
+
+    /// Choose whether file-backed topics store producer or append timestamps; no-op for memory backend.
+    pub fn with_timestamp_type(mut self, timestamp_type: TimestampType) -> Self {
+        if let StorageBackend::File { topic_config, .. } = &mut self {
+            topic_config.timestamp_type = timestamp_type;
+        }
+        self
+    }
+
+    /// Reject producer timestamps more than `max_skew_ms` from the broker clock on file-backed
+    /// topics; no-op for memory backend.
+    pub fn with_max_timestamp_skew_ms(mut self, max_skew_ms: u64) -> Self {
+        if let StorageBackend::File { topic_config, .. } = &mut self {
+            topic_config.max_timestamp_skew_ms = Some(max_skew_ms);
+        }
+        self
+    }
+
+    /// Override storage settings for a single topic; no-op for memory backend.
+    pub fn with_topic_config(mut self, topic: &str, config: TopicConfig) -> Self {
+        if let StorageBackend::File {
+            topic_overrides, ..
+        } = &mut self
+        {
+            topic_overrides.insert(topic.to_string(), config);
+        }
+        self
+    }
+
+    /// Set the number of dedicated I/O threads serving async callers; no-op for memory backend.
+    pub fn with_io_threads(mut self, threads: usize) -> Self {
+        if let StorageBackend::File { io_threads, .. } = &mut self {
+            *io_threads = threads.max(1);
+        }
+        self
+    }
+
+    /// Offload closed segments of every file-backed topic to a remote store; no-op for memory backend.
+    pub fn with_tiered_storage(mut self, config: TieredStorageConfig) -> Self {
+        if let StorageBackend::File { tiered_storage, .. } = &mut self {
+            *tiered_storage = Some(config);
+        }
+        self
+    }
+
+    /// Keep consumer group offsets in a SQLite database in the data directory instead of one
+    /// JSON file per group, importing any existing JSON files first; no-op for memory backend.
+    pub fn with_sqlite_offset_store(mut self) -> Result<Self, StorageError> {
+        if let StorageBackend::File {
+            sync_mode,
+            data_dir,
+            offset_storage,
+            ..
+        } = &mut self
+        {
+            let db = SqliteOffsetDatabase::open(&*data_dir, *sync_mode)?;
+            db.migrate_json_groups(&*data_dir)?;
+            *offset_storage = OffsetStorage::Sqlite(Arc::new(db));
+        }
+        Ok(self)
+    }
+
+    /// Keep consumer group offsets as records of the internal `__consumer_offsets` topic,
+    /// replaying it now; no-op for memory backend.
+    pub fn with_offsets_topic(mut self) -> Result<Self, StorageError> {
+        if let StorageBackend::File { .. } = self {
+            let log = self.create_topic_log(CONSUMER_OFFSETS_TOPIC)?;
+            let offsets = OffsetsTopic::open(log)?;
+            if let StorageBackend::File { offset_storage, .. } = &mut self {
+                *offset_storage = OffsetStorage::Topic(Arc::new(offsets));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Build the I/O pool async callers use to reach this backend. File storage gets
+    /// dedicated threads; memory storage never blocks and runs inline.
+    pub fn create_io_pool(&self) -> Result<IoPool, StorageError> {
+        match self {
+            StorageBackend::Memory { .. } => Ok(IoPool::inline()),
+            StorageBackend::File { io_threads, .. } => IoPool::with_threads(*io_threads),
+        }
+    }
+
+    /// Effective settings for `topic`: its override if present, otherwise the backend defaults.
+    pub fn topic_config(&self, topic: &str) -> TopicConfig {
+        match self {
+            StorageBackend::Memory { .. } => TopicConfig::default(),
+            StorageBackend::File {
+                topic_config,
+                topic_overrides,
+                ..
+            } => topic_overrides
+                .get(topic)
+                .cloned()
+                .unwrap_or_else(|| topic_config.clone()),
+        }
+    }
+
     pub fn create(
         &self,
         topic: &str,
@@ -132,16 +292,23 @@ impl StorageBackend {
                 segment_size_bytes,
                 batch_bytes,
                 indexing_config,
+                tiered_storage,
                 ..
             } => {
-                let file_log = FileTopicLog::new_with_batch_bytes_and_indexing_config(
+                let mut file_log = FileTopicLog::new_with_batch_bytes_and_indexing_config(
                     topic,
                     *sync_mode,
                     data_dir,
                     *segment_size_bytes,
                     *batch_bytes,
                     indexing_config.clone(),
-                )?;
+                )?
+                .with_topic_config(self.topic_config(topic));
+                if let Some(tiered_storage) = tiered_storage {
+                    file_log = file_log
+                        .with_tiered_storage(tiered_storage.clone())
+                        .map_err(|e| std::io::Error::other(e.to_string()))?;
+                }
                 Ok(Arc::new(RwLock::new(file_log)))
             }
         }
@@ -156,6 +323,7 @@ impl StorageBackend {
                 group_id.to_string(),
             )))),
             StorageBackend::File {
+                offset_storage: OffsetStorage::Json,
                 sync_mode,
                 data_dir,
                 ..
@@ -163,6 +331,12 @@ impl StorageBackend {
                 let consumer_group = FileConsumerGroup::new(group_id, *sync_mode, data_dir)?;
                 Ok(Arc::new(RwLock::new(consumer_group)))
             }
+            StorageBackend::File { .. } => {
+                let offset_store = self.create_consumer_offset_store(group_id)?;
+                Ok(Arc::new(RwLock::new(FileConsumerGroup::with_offset_store(
+                    offset_store,
+                ))))
+            }
         }
     }
 
@@ -175,13 +349,23 @@ impl StorageBackend {
                 group_id.to_string(),
             ))),
             StorageBackend::File {
+                offset_storage,
                 sync_mode,
                 data_dir,
                 ..
-            } => {
-                let offset_store = FileConsumerOffsetStore::new(group_id, *sync_mode, data_dir)?;
-                Ok(Arc::new(offset_store))
-            }
+            } => match offset_storage {
+                OffsetStorage::Json => Ok(Arc::new(FileConsumerOffsetStore::new(
+                    group_id, *sync_mode, data_dir,
+                )?)),
+                OffsetStorage::Sqlite(db) => Ok(Arc::new(SqliteConsumerOffsetStore::new(
+                    Arc::clone(db),
+                    group_id,
+                )?)),
+                OffsetStorage::Topic(offsets) => Ok(Arc::new(TopicConsumerOffsetStore::new(
+                    Arc::clone(offsets),
+                    group_id,
+                )?)),
+            },
         }
     }
 
@@ -213,7 +397,10 @@ impl StorageBackend {
                     if topic_log_entry.file_type()?.is_dir() {
                         if let Some(topic_name) = topic_log_entry.file_name().to_str() {
                             // Skip system directories
-                            if topic_name == "consumer_groups" || topic_name.starts_with('.') {
+                            if topic_name == "consumer_groups"
+                                || topic_name.starts_with('.')
+                                || is_internal_topic(topic_name)
+                            {
                                 tracing::trace!(topic_name, "Skipping system directory");
                                 continue;
                             }
@@ -255,6 +442,119 @@ impl StorageBackend {
             }
         }
     }
+    /// Discover consumer groups with committed offsets in the storage backend
+    pub fn discover_consumer_groups(&self) -> Result<Vec<String>, std::io::Error> {
+        let StorageBackend::File {
+            data_dir,
+            offset_storage,
+            ..
+        } = self
+        else {
+            // Memory storage doesn't persist consumer groups
+            return Ok(Vec::new());
+        };
+        match offset_storage {
+            OffsetStorage::Json => {}
+            OffsetStorage::Sqlite(db) => return db.group_ids().map_err(std::io::Error::other),
+            OffsetStorage::Topic(offsets) => return Ok(offsets.group_ids()),
+        }
+
+        let consumer_groups_dir = data_dir.join("consumer_groups");
+        if !consumer_groups_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut group_ids = Vec::new();
+        for entry in std::fs::read_dir(&consumer_groups_dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                if let Some(group_id) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    group_ids.push(group_id.to_string());
+                }
+            }
+        }
+        group_ids.sort();
+        Ok(group_ids)
+    }
+}
+
+impl StorageFactory for StorageBackend {
+    fn create_topic_log(
+        &self,
+        topic: &str,
+    ) -> Result<Arc<RwLock<dyn TopicLog + Send + Sync>>, StorageError> {
+        self.create(topic)
+            .map_err(|e| StorageError::from_io_error(e, &format!("create topic '{topic}'")))
+    }
+
+    fn create_group(&self, group_id: &str) -> Result<Arc<RwLock<dyn ConsumerGroup>>, StorageError> {
+        self.create_consumer_group(group_id)
+            .map_err(|e| StorageError::Unavailable {
+                context: format!("create consumer group '{group_id}': {e}"),
+            })
+    }
+
+    fn create_offset_store(
+        &self,
+        group_id: &str,
+    ) -> Result<Arc<dyn ConsumerOffsetStore>, StorageError> {
+        self.create_consumer_offset_store(group_id)
+            .map_err(|e| StorageError::Unavailable {
+                context: format!("create offset store for '{group_id}': {e}"),
+            })
+    }
+
+    fn list_topics(&self) -> Result<Vec<String>, StorageError> {
+        self.discover_topics()
+            .map_err(|e| StorageError::from_io_error(e, "Failed to discover topics"))
+    }
+
+    fn list_consumer_groups(&self) -> Result<Vec<String>, StorageError> {
+        self.discover_consumer_groups()
+            .map_err(|e| StorageError::from_io_error(e, "Failed to discover consumer groups"))
+    }
+
+    fn io_pool(&self) -> Result<IoPool, StorageError> {
+        self.create_io_pool()
+    }
+
+    fn snapshot(&self, dest: &Path) -> Result<SnapshotSummary, StorageError> {
+        match self {
+            StorageBackend::File { data_dir, .. } => snapshot_data_dir(data_dir, dest),
+            StorageBackend::Memory { .. } => Err(StorageError::Unavailable {
+                context: "snapshots need a file storage backend".to_string(),
+            }),
+        }
+    }
+}
+
+/// Exclusive hold on a data directory, taken through the same `.flashq.lock` file a file
+/// backend uses. Offline tools take it so they never run against a live broker's files.
+#[derive(Debug)]
+pub struct DirectoryLock {
+    lock_path: PathBuf,
+    _file: File,
+}
+
+impl DirectoryLock {
+    pub fn acquire<P: AsRef<Path>>(data_dir: P) -> Result<Self, StorageError> {
+        let file = acquire_directory_lock(&data_dir)?;
+        Ok(Self {
+            lock_path: data_dir.as_ref().join(".flashq.lock"),
+            _file: file,
+        })
+    }
+}
+
+impl Drop for DirectoryLock {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.lock_path) {
+            warn!("Failed to remove lock file {:?}: {e}", self.lock_path);
+        }
+    }
 }
 
 fn acquire_directory_lock<P: AsRef<Path>>(data_dir: P) -> Result<File, StorageError> {
@@ -382,8 +682,7 @@ fn has_log_files(dir_path: &Path) -> Result<bool, std::io::Error> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Record;
-    use std::collections::HashMap;
+    use crate::{Header, Record};
 
     #[test]
     fn test_storage_backend_memory() {
@@ -432,9 +731,11 @@ mod tests {
     #[test]
     fn test_storage_abstraction_with_headers() {
         let mut storage = InMemoryTopicLog::new();
-        let mut headers = HashMap::new();
-        headers.insert("source".to_string(), "test".to_string());
-        headers.insert("priority".to_string(), "high".to_string());
+        let headers = vec![
+            Header::new("source", "test"),
+            Header::new("priority", "high"),
+            Header::new("source", vec![0xde, 0xad]),
+        ];
 
         let record = Record::new(
             Some("user123".to_string()),
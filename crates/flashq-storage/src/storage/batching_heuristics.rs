@@ -23,8 +23,9 @@ pub fn estimate_record_size(r: &crate::Record) -> usize {
         len += k.len();
     }
     if let Some(h) = &r.headers {
-        for (k, v) in h {
-            len += k.len() + v.len();
+        for header in h {
+            // Values are base64 in the JSON payload
+            len += header.key.len() + header.value.len().div_ceil(3) * 4;
         }
     }
     // JSON/structural overhead and potential escaping room
@@ -0,0 +1,172 @@
+use crate::error::{StorageError, StorageErrorSource};
+use std::io::{Read, Write};
+
+/// Compression codec applied to the inner records of a record batch.
+///
+/// The discriminants are persisted in the batch frame's codec byte and must not change.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionCodec {
+    #[default]
+    None = 0,
+    Gzip = 1,
+    Snappy = 2,
+    Lz4 = 3,
+    Zstd = 4,
+}
+
+impl CompressionCodec {
+    pub fn as_byte(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_byte(byte: u8) -> Result<Self, StorageError> {
+        match byte {
+            0 => Ok(CompressionCodec::None),
+            1 => Ok(CompressionCodec::Gzip),
+            2 => Ok(CompressionCodec::Snappy),
+            3 => Ok(CompressionCodec::Lz4),
+            4 => Ok(CompressionCodec::Zstd),
+            other => Err(StorageError::DataCorruption {
+                context: "record batch codec".to_string(),
+                details: format!("unknown compression codec id {other}"),
+            }),
+        }
+    }
+
+    pub fn compress(self, data: &[u8]) -> Result<Vec<u8>, StorageError> {
+        match self {
+            CompressionCodec::None => Ok(data.to_vec()),
+            CompressionCodec::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder
+                    .write_all(data)
+                    .and_then(|_| encoder.finish())
+                    .map_err(|e| compression_error("gzip compress", e))
+            }
+            CompressionCodec::Snappy => snap::raw::Encoder::new()
+                .compress_vec(data)
+                .map_err(|e| compression_error("snappy compress", e)),
+            CompressionCodec::Lz4 => Ok(lz4_flex::compress_prepend_size(data)),
+            CompressionCodec::Zstd => {
+                zstd::bulk::compress(data, 0).map_err(|e| compression_error("zstd compress", e))
+            }
+        }
+    }
+
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>, StorageError> {
+        match self {
+            CompressionCodec::None => Ok(data.to_vec()),
+            CompressionCodec::Gzip => {
+                let mut out = Vec::new();
+                flate2::read::GzDecoder::new(data)
+                    .read_to_end(&mut out)
+                    .map_err(|e| decompression_error("gzip", e))?;
+                Ok(out)
+            }
+            CompressionCodec::Snappy => snap::raw::Decoder::new()
+                .decompress_vec(data)
+                .map_err(|e| decompression_error("snappy", e)),
+            CompressionCodec::Lz4 => {
+                lz4_flex::decompress_size_prepended(data).map_err(|e| decompression_error("lz4", e))
+            }
+            CompressionCodec::Zstd => {
+                let mut out = Vec::new();
+                zstd::stream::copy_decode(data, &mut out)
+                    .map_err(|e| decompression_error("zstd", e))?;
+                Ok(out)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for CompressionCodec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CompressionCodec::None => "none",
+            CompressionCodec::Gzip => "gzip",
+            CompressionCodec::Snappy => "snappy",
+            CompressionCodec::Lz4 => "lz4",
+            CompressionCodec::Zstd => "zstd",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for CompressionCodec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(CompressionCodec::None),
+            "gzip" => Ok(CompressionCodec::Gzip),
+            "snappy" => Ok(CompressionCodec::Snappy),
+            "lz4" => Ok(CompressionCodec::Lz4),
+            "zstd" => Ok(CompressionCodec::Zstd),
+            other => Err(format!(
+                "unknown compression codec '{other}' (expected none, gzip, snappy, lz4 or zstd)"
+            )),
+        }
+    }
+}
+
+fn compression_error(context: &str, e: impl std::fmt::Display) -> StorageError {
+    StorageError::WriteFailed {
+        context: context.to_string(),
+        source: Box::new(StorageErrorSource::Custom(e.to_string())),
+    }
+}
+
+fn decompression_error(codec: &str, e: impl std::fmt::Display) -> StorageError {
+    StorageError::DataCorruption {
+        context: format!("{codec} decompress"),
+        details: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_CODECS: [CompressionCodec; 5] = [
+        CompressionCodec::None,
+        CompressionCodec::Gzip,
+        CompressionCodec::Snappy,
+        CompressionCodec::Lz4,
+        CompressionCodec::Zstd,
+    ];
+
+    #[test]
+    fn test_codec_roundtrip() {
+        let data = "flashq record payload ".repeat(64).into_bytes();
+        for codec in ALL_CODECS {
+            let compressed = codec.compress(&data).unwrap();
+            if codec != CompressionCodec::None {
+                assert!(compressed.len() < data.len(), "{codec} did not compress");
+            }
+            assert_eq!(codec.decompress(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_codec_byte_and_name_roundtrip() {
+        for codec in ALL_CODECS {
+            assert_eq!(CompressionCodec::from_byte(codec.as_byte()).unwrap(), codec);
+            assert_eq!(
+                codec.to_string().parse::<CompressionCodec>().unwrap(),
+                codec
+            );
+        }
+        assert!(CompressionCodec::from_byte(42).is_err());
+        assert!("brotli".parse::<CompressionCodec>().is_err());
+    }
+
+    #[test]
+    fn test_decompress_garbage_is_corruption() {
+        let result = CompressionCodec::Gzip.decompress(b"not gzip");
+        assert!(matches!(result, Err(StorageError::DataCorruption { .. })));
+    }
+}
@@ -0,0 +1,50 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+use crate::error::StorageError;
+use crate::storage::file::snapshot::SnapshotSummary;
+use crate::storage::{ConsumerGroup, ConsumerOffsetStore, IoPool, TopicLog};
+
+/// Creates and recovers the storage a `FlashQ` runs on.
+///
+/// `StorageBackend` implements this for the built-in memory and file backends. Other crates
+/// implement it to plug in their own storage (an embedded KV store, a database) without
+/// touching FlashQ itself; `flashq-storage-testkit` checks that an implementation behaves
+/// like the built-in ones.
+pub trait StorageFactory: Send + Sync + std::fmt::Debug {
+    /// Open the log for `topic`, loading whatever was stored for it before.
+    fn create_topic_log(
+        &self,
+        topic: &str,
+    ) -> Result<Arc<RwLock<dyn TopicLog + Send + Sync>>, StorageError>;
+
+    /// Open the consumer group `group_id`, loading its committed offsets.
+    fn create_group(&self, group_id: &str) -> Result<Arc<RwLock<dyn ConsumerGroup>>, StorageError>;
+
+    /// Open the offset store for `group_id`, loading its committed offsets.
+    fn create_offset_store(
+        &self,
+        group_id: &str,
+    ) -> Result<Arc<dyn ConsumerOffsetStore>, StorageError>;
+
+    /// Topics that already hold data and should be reopened at startup.
+    fn list_topics(&self) -> Result<Vec<String>, StorageError>;
+
+    /// Consumer groups that already hold offsets and should be reopened at startup.
+    fn list_consumer_groups(&self) -> Result<Vec<String>, StorageError>;
+
+    /// Pool async callers use to run blocking storage calls. The default runs them inline,
+    /// which only suits storage that never blocks.
+    fn io_pool(&self) -> Result<IoPool, StorageError> {
+        Ok(IoPool::inline())
+    }
+
+    /// Copy this storage's data into `dest` while it keeps serving requests.
+    fn snapshot(&self, _dest: &Path) -> Result<SnapshotSummary, StorageError> {
+        Err(StorageError::Unavailable {
+            context: format!("{self:?} does not support snapshots"),
+        })
+    }
+}
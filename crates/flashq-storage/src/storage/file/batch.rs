@@ -0,0 +1,313 @@
+use std::io::{BufReader, Cursor, Read, Seek};
+
+use crate::error::StorageError;
+use crate::storage::compression::CompressionCodec;
+use crate::storage::file::common::{
+    deserialize_record, serialize_record_into_buffer, timestamp_ms,
+};
+use crate::{Record, RecordWithOffset};
+
+// ================================================================================================
+// RECORD BATCH FRAME
+// ================================================================================================
+//
+// [8B base_offset][4B batch_len][1B magic][4B crc32][1B codec][4B record_count]
+// [8B min_ts_ms][8B max_ts_ms][payload]
+//
+// `batch_len` counts every byte after itself. Producer timestamps need not increase with
+// offsets, so the two timestamps bound the batch rather than naming its first and last record.
+// The CRC covers everything after the CRC field.
+// The payload is the codec-compressed concatenation of inner records, each using the
+// per-record layout from `common` ([4B payload][8B offset][8B ts_ms][4B ts_len][ts][json]).
+
+pub const BATCH_MAGIC: u8 = 2;
+pub const BATCH_HEADER_SIZE: u64 = 8 + 4 + BATCH_LENGTH_OVERHEAD as u64;
+const BATCH_LENGTH_OVERHEAD: u32 = 1 + 4 + 1 + 4 + 8 + 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordBatchHeader {
+    pub base_offset: u64,
+    pub batch_len: u32,
+    pub crc: u32,
+    pub codec: CompressionCodec,
+    pub record_count: u32,
+    /// Smallest record timestamp in the batch.
+    pub min_ts_ms: u64,
+    /// Largest record timestamp in the batch.
+    pub max_ts_ms: u64,
+}
+
+impl RecordBatchHeader {
+    pub fn last_offset(&self) -> u64 {
+        self.base_offset + (self.record_count.saturating_sub(1)) as u64
+    }
+
+    /// Total on-disk size of the batch, header included.
+    pub fn frame_size(&self) -> u64 {
+        12 + self.batch_len as u64
+    }
+
+    fn payload_len(&self) -> usize {
+        (self.batch_len - BATCH_LENGTH_OVERHEAD) as usize
+    }
+}
+
+/// Encode `records` as a single batch starting at `base_offset` and append the frame to `buf`.
+/// Every record in the batch is stamped with `timestamp`.
+pub fn encode_batch_into(
+    buf: &mut Vec<u8>,
+    records: &[Record],
+    base_offset: u64,
+    timestamp: &str,
+    codec: CompressionCodec,
+) -> Result<RecordBatchHeader, StorageError> {
+    encode_batch_with(buf, records, base_offset, |_| timestamp, codec)
+}
+
+/// Like [`encode_batch_into`] but stamps each record with its own entry of `timestamps`.
+pub fn encode_stamped_batch_into(
+    buf: &mut Vec<u8>,
+    records: &[Record],
+    timestamps: &[String],
+    base_offset: u64,
+    codec: CompressionCodec,
+) -> Result<RecordBatchHeader, StorageError> {
+    debug_assert_eq!(records.len(), timestamps.len());
+    encode_batch_with(buf, records, base_offset, |i| &timestamps[i], codec)
+}
+
+fn encode_batch_with<'a>(
+    buf: &mut Vec<u8>,
+    records: &[Record],
+    base_offset: u64,
+    timestamp: impl Fn(usize) -> &'a str,
+    codec: CompressionCodec,
+) -> Result<RecordBatchHeader, StorageError> {
+    let mut inner = Vec::with_capacity(records.len().saturating_mul(64));
+    let mut min_ts_ms = u64::MAX;
+    let mut max_ts_ms = 0;
+    for (i, record) in records.iter().enumerate() {
+        let timestamp = timestamp(i);
+        serialize_record_into_buffer(&mut inner, record, base_offset + i as u64, timestamp)?;
+        let ts_ms = timestamp_ms(timestamp);
+        min_ts_ms = min_ts_ms.min(ts_ms);
+        max_ts_ms = max_ts_ms.max(ts_ms);
+    }
+    // An empty batch has no timestamps; store 0 for both
+    let min_ts_ms = min_ts_ms.min(max_ts_ms);
+    let payload = codec.compress(&inner)?;
+    let record_count = records.len() as u32;
+
+    let mut crc_region = Vec::with_capacity(1 + 4 + 8 + 8 + payload.len());
+    crc_region.push(codec.as_byte());
+    crc_region.extend_from_slice(&record_count.to_be_bytes());
+    crc_region.extend_from_slice(&min_ts_ms.to_be_bytes());
+    crc_region.extend_from_slice(&max_ts_ms.to_be_bytes());
+    crc_region.extend_from_slice(&payload);
+    let crc = crc32fast::hash(&crc_region);
+
+    let batch_len = BATCH_LENGTH_OVERHEAD + payload.len() as u32;
+    buf.reserve(12 + batch_len as usize);
+    buf.extend_from_slice(&base_offset.to_be_bytes());
+    buf.extend_from_slice(&batch_len.to_be_bytes());
+    buf.push(BATCH_MAGIC);
+    buf.extend_from_slice(&crc.to_be_bytes());
+    buf.extend_from_slice(&crc_region);
+
+    Ok(RecordBatchHeader {
+        base_offset,
+        batch_len,
+        crc,
+        codec,
+        record_count,
+        min_ts_ms,
+        max_ts_ms,
+    })
+}
+
+/// Convenience wrapper around [`encode_batch_into`] stamping records with the current time.
+pub fn encode_batch(
+    records: &[Record],
+    base_offset: u64,
+    codec: CompressionCodec,
+) -> Result<Vec<u8>, StorageError> {
+    let mut buf = Vec::new();
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    encode_batch_into(&mut buf, records, base_offset, &timestamp, codec)?;
+    Ok(buf)
+}
+
+/// Read a batch header, leaving the reader positioned at the start of the payload.
+pub fn read_batch_header<R: Read>(reader: &mut R) -> Result<RecordBatchHeader, StorageError> {
+    let mut fixed = [0u8; BATCH_HEADER_SIZE as usize];
+    reader
+        .read_exact(&mut fixed)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to read batch header"))?;
+
+    let base_offset = u64::from_be_bytes(fixed[0..8].try_into().unwrap());
+    let batch_len = u32::from_be_bytes(fixed[8..12].try_into().unwrap());
+    let magic = fixed[12];
+    if magic != BATCH_MAGIC {
+        return Err(StorageError::DataCorruption {
+            context: "read batch header".to_string(),
+            details: format!("unexpected batch magic {magic} at base offset {base_offset}"),
+        });
+    }
+    if batch_len < BATCH_LENGTH_OVERHEAD {
+        return Err(StorageError::DataCorruption {
+            context: "read batch header".to_string(),
+            details: format!("batch length {batch_len} shorter than header"),
+        });
+    }
+    let crc = u32::from_be_bytes(fixed[13..17].try_into().unwrap());
+    let codec = CompressionCodec::from_byte(fixed[17])?;
+    let record_count = u32::from_be_bytes(fixed[18..22].try_into().unwrap());
+    let min_ts_ms = u64::from_be_bytes(fixed[22..30].try_into().unwrap());
+    let max_ts_ms = u64::from_be_bytes(fixed[30..38].try_into().unwrap());
+
+    Ok(RecordBatchHeader {
+        base_offset,
+        batch_len,
+        crc,
+        codec,
+        record_count,
+        min_ts_ms,
+        max_ts_ms,
+    })
+}
+
+/// Read, verify and decompress the payload following `header`, returning its records.
+pub fn read_batch_records<R: Read>(
+    reader: &mut R,
+    header: &RecordBatchHeader,
+) -> Result<Vec<RecordWithOffset>, StorageError> {
+    let mut payload = vec![0u8; header.payload_len()];
+    reader
+        .read_exact(&mut payload)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to read batch payload"))?;
+
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&[header.codec.as_byte()]);
+    hasher.update(&header.record_count.to_be_bytes());
+    hasher.update(&header.min_ts_ms.to_be_bytes());
+    hasher.update(&header.max_ts_ms.to_be_bytes());
+    hasher.update(&payload);
+    let actual_crc = hasher.finalize();
+    if actual_crc != header.crc {
+        return Err(StorageError::DataCorruption {
+            context: format!("batch at base offset {}", header.base_offset),
+            details: format!("crc mismatch: expected {}, got {actual_crc}", header.crc),
+        });
+    }
+
+    let inner = header.codec.decompress(&payload)?;
+    let inner_len = inner.len() as u64;
+    let mut inner_reader = BufReader::new(Cursor::new(inner));
+    let mut records = Vec::with_capacity(header.record_count as usize);
+    while inner_reader
+        .stream_position()
+        .map_err(|e| StorageError::from_io_error(e, "Failed to get batch position"))?
+        < inner_len
+    {
+        records.push(deserialize_record(&mut inner_reader)?);
+    }
+
+    if records.len() != header.record_count as usize {
+        return Err(StorageError::DataCorruption {
+            context: format!("batch at base offset {}", header.base_offset),
+            details: format!(
+                "record count mismatch: header says {}, decoded {}",
+                header.record_count,
+                records.len()
+            ),
+        });
+    }
+    Ok(records)
+}
+
+/// Skip the payload following `header` without decompressing it.
+pub fn skip_batch_payload<R: Read + Seek>(
+    reader: &mut BufReader<R>,
+    header: &RecordBatchHeader,
+) -> Result<(), StorageError> {
+    reader
+        .seek_relative(header.payload_len() as i64)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to skip batch payload"))
+}
+
+/// Read the next complete batch from `reader`.
+pub fn read_batch<R: Read>(
+    reader: &mut R,
+) -> Result<(RecordBatchHeader, Vec<RecordWithOffset>), StorageError> {
+    let header = read_batch_header(reader)?;
+    let records = read_batch_records(reader, &header)?;
+    Ok((header, records))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_records(n: usize) -> Vec<Record> {
+        (0..n)
+            .map(|i| Record::new(Some(format!("k{i}")), format!("value-{i}"), None))
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_roundtrip_for_each_codec() {
+        for codec in [
+            CompressionCodec::None,
+            CompressionCodec::Gzip,
+            CompressionCodec::Snappy,
+            CompressionCodec::Lz4,
+            CompressionCodec::Zstd,
+        ] {
+            let records = sample_records(5);
+            let frame = encode_batch(&records, 40, codec).unwrap();
+            let (header, decoded) = read_batch(&mut Cursor::new(&frame)).unwrap();
+
+            assert_eq!(header.codec, codec);
+            assert_eq!(header.base_offset, 40);
+            assert_eq!(header.last_offset(), 44);
+            assert_eq!(header.frame_size(), frame.len() as u64);
+            let offsets: Vec<u64> = decoded.iter().map(|r| r.offset).collect();
+            assert_eq!(offsets, vec![40, 41, 42, 43, 44]);
+            assert_eq!(decoded[3].record, records[3]);
+        }
+    }
+
+    #[test]
+    fn test_header_can_be_skipped_without_decoding() {
+        let mut buf = Vec::new();
+        let ts = chrono::Utc::now().to_rfc3339();
+        encode_batch_into(&mut buf, &sample_records(3), 0, &ts, CompressionCodec::Zstd).unwrap();
+        encode_batch_into(&mut buf, &sample_records(2), 3, &ts, CompressionCodec::None).unwrap();
+
+        let mut reader = BufReader::new(Cursor::new(buf));
+        let first = read_batch_header(&mut reader).unwrap();
+        skip_batch_payload(&mut reader, &first).unwrap();
+        let (second, records) = read_batch(&mut reader).unwrap();
+
+        assert_eq!(second.base_offset, 3);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].offset, 4);
+    }
+
+    #[test]
+    fn test_crc_mismatch_is_detected() {
+        let mut frame = encode_batch(&sample_records(2), 0, CompressionCodec::None).unwrap();
+        let last = frame.len() - 1;
+        frame[last] ^= 0xFF;
+        let result = read_batch(&mut Cursor::new(&frame));
+        assert!(matches!(result, Err(StorageError::DataCorruption { .. })));
+    }
+
+    #[test]
+    fn test_bad_magic_is_detected() {
+        let mut frame = encode_batch(&sample_records(1), 0, CompressionCodec::None).unwrap();
+        frame[12] = 0;
+        let result = read_batch_header(&mut Cursor::new(&frame));
+        assert!(matches!(result, Err(StorageError::DataCorruption { .. })));
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    io::{BufReader, Read, Seek, SeekFrom},
+    io::{BufReader, Read},
     path::Path,
 };
 
@@ -129,11 +129,7 @@ pub fn serialize_record_into_buffer(
     buf.extend_from_slice(&0u32.to_be_bytes()); // payload size placeholder
     buf.extend_from_slice(&offset.to_be_bytes());
     // New: write timestamp_ms into header
-    let ts_ms_i64 = chrono::DateTime::parse_from_rfc3339(timestamp)
-        .map(|dt| dt.timestamp_millis())
-        .unwrap_or(0);
-    let ts_ms = if ts_ms_i64 < 0 { 0 } else { ts_ms_i64 as u64 };
-    buf.extend_from_slice(&ts_ms.to_be_bytes());
+    buf.extend_from_slice(&timestamp_ms(timestamp).to_be_bytes());
     buf.extend_from_slice(&ts_len_u32.to_be_bytes());
     buf.extend_from_slice(ts_bytes);
 
@@ -148,6 +144,13 @@ pub fn serialize_record_into_buffer(
     Ok((buf.len() - start) as u32)
 }
 
+/// Milliseconds since the epoch for an RFC3339 timestamp; unparsable or pre-epoch times map to 0.
+pub fn timestamp_ms(timestamp: &str) -> u64 {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|dt| dt.timestamp_millis().max(0) as u64)
+        .unwrap_or(0)
+}
+
 fn serialize_record_payload(record: &Record) -> Result<Vec<u8>, StorageError> {
     serde_json::to_vec(record).map_err(|e| {
         StorageError::from_serialization_error(e, "Failed to serialize record to JSON")
@@ -177,32 +180,6 @@ fn assemble_record_buffer(
     buffer
 }
 
-// Fast header peek + skip helpers for time-based scanning
-pub fn read_record_header<R: Read + Seek>(
-    reader: &mut BufReader<R>,
-) -> Result<(u32, u64, u64, u32, u64), StorageError> {
-    let record_start = reader
-        .stream_position()
-        .map_err(|e| StorageError::from_io_error(e, "Failed to get record start pos"))?;
-    let payload_size = read_u32(reader, "Failed to read payload size")?;
-    let offset = read_u64(reader, "Failed to read offset")?;
-    let ts_ms = read_u64(reader, "Failed to read timestamp_ms")?;
-    let ts_len = read_u32(reader, "Failed to read timestamp length")?;
-    Ok((payload_size, offset, ts_ms, ts_len, record_start))
-}
-
-pub fn skip_record_after_header<R: Read + Seek>(
-    reader: &mut BufReader<R>,
-    payload_size: u32,
-) -> Result<(), StorageError> {
-    // After peek, we are positioned just after ts_len; remaining = ts bytes + json
-    let to_skip = (payload_size - 4) as i64;
-    reader
-        .seek(SeekFrom::Current(to_skip))
-        .map_err(|e| StorageError::from_io_error(e, "Failed to skip record"))?;
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -244,6 +221,7 @@ mod tests {
             value: json!({ "test": "data" }).to_string(),
             key: None,
             headers: None,
+            create_time: None,
         };
 
         let serialized = serialize_record_payload(&record).unwrap();
@@ -258,6 +236,7 @@ mod tests {
             value: json!({ "a": 1, "b": "hello" }).to_string(),
             key: Some("my-key".to_string()),
             headers: None,
+            create_time: None,
         };
         let offset = 999;
 
@@ -7,7 +7,7 @@ use crate::storage::{
 };
 
 pub struct FileConsumerGroup {
-    offset_store: Arc<FileConsumerOffsetStore>,
+    offset_store: Arc<dyn ConsumerOffsetStore>,
 }
 
 impl FileConsumerGroup {
@@ -26,7 +26,8 @@ impl FileConsumerGroup {
         Self::new(group_id, sync_mode, "./data")
     }
 
-    pub fn with_offset_store(offset_store: Arc<FileConsumerOffsetStore>) -> Self {
+    /// Group over any offset store, such as a `SqliteConsumerOffsetStore`.
+    pub fn with_offset_store(offset_store: Arc<dyn ConsumerOffsetStore>) -> Self {
         FileConsumerGroup { offset_store }
     }
 }
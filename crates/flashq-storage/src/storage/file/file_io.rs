@@ -93,9 +93,14 @@ impl FileIo {
 
     #[tracing::instrument(level = "debug", skip(handle))]
     pub fn synchronize_to_disk(handle: &mut File) -> Result<(), StorageError> {
-        handle
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let result = handle
             .sync_all()
-            .map_err(|e| StorageError::from_io_error(e, "Failed to sync file to disk"))
+            .map_err(|e| StorageError::from_io_error(e, "Failed to sync file to disk"));
+        #[cfg(feature = "metrics")]
+        metrics::histogram!("flashq_storage_fsync_seconds").record(started.elapsed());
+        result
     }
 
     #[tracing::instrument(level = "debug", skip(handle))]
@@ -0,0 +1,290 @@
+use crate::error::StorageError;
+use log::warn;
+use memmap2::MmapMut;
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+/// Storage for `W`-byte index entries laid out back to back, searched in place.
+///
+/// File-backed indexes are pre-allocated to their maximum size and memory-mapped, so opening
+/// one never reads its entries and lookups only touch the pages a binary search visits. Unused
+/// slots are zero; the number of used slots is recovered on open from the zeroed tail.
+pub(crate) struct FixedWidthIndex<const W: usize> {
+    backing: Backing,
+    entries: usize,
+    capacity: usize,
+}
+
+enum Backing {
+    /// Unpersisted index, used for indexes built purely in memory.
+    Heap(Vec<u8>),
+    Mapped {
+        file: File,
+        mmap: MmapMut,
+    },
+}
+
+impl<const W: usize> FixedWidthIndex<W> {
+    pub fn in_memory() -> Self {
+        Self {
+            backing: Backing::Heap(Vec::new()),
+            entries: 0,
+            capacity: usize::MAX,
+        }
+    }
+
+    /// Open (creating if needed) the index file at `path`, pre-allocating it to `max_bytes`.
+    ///
+    /// Files shorter than the pre-allocated size were trimmed when their segment closed, so every
+    /// slot in them is in use. For pre-allocated files `is_used(slot_index, slot)` finds the end
+    /// of the used prefix by binary search. A file whose length is not a whole number of entries
+    /// is discarded; callers rebuild it from the log.
+    pub fn open(
+        path: &Path,
+        max_bytes: u64,
+        is_used: impl Fn(usize, &[u8; W]) -> bool,
+    ) -> Result<Self, StorageError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(|e| StorageError::from_io_error(e, "Failed to open index file"))?;
+
+        let mut len = file
+            .metadata()
+            .map_err(|e| StorageError::from_io_error(e, "Failed to stat index file"))?
+            .len();
+        if len % W as u64 != 0 {
+            warn!(
+                "Index file {} has length {len}, not a multiple of {W}; discarding it",
+                path.display()
+            );
+            set_len(&file, 0)?;
+            len = 0;
+        }
+
+        let preallocated = (max_bytes / W as u64).max(1) * W as u64;
+        let target_len = len.max(preallocated);
+        if target_len > len {
+            set_len(&file, target_len)?;
+        }
+
+        // SAFETY: the file is owned by this index and only mutated through the mapping
+        let mmap = unsafe { MmapMut::map_mut(&file) }
+            .map_err(|e| StorageError::from_io_error(e, "Failed to map index file"))?;
+
+        let mut index = Self {
+            backing: Backing::Mapped { file, mmap },
+            entries: 0,
+            capacity: (target_len / W as u64) as usize,
+        };
+        let slots_on_disk = (len / W as u64) as usize;
+        index.entries = if len < preallocated {
+            slots_on_disk
+        } else {
+            used_prefix_len(&index.bytes()[..slots_on_disk * W], is_used)
+        };
+        Ok(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.entries >= self.capacity
+    }
+
+    pub fn slot(&self, i: usize) -> &[u8; W] {
+        debug_assert!(i < self.entries);
+        self.raw_slot(i)
+    }
+
+    fn raw_slot(&self, i: usize) -> &[u8; W] {
+        self.bytes()[i * W..(i + 1) * W]
+            .try_into()
+            .expect("index slot has fixed width")
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match &self.backing {
+            Backing::Heap(buf) => buf,
+            Backing::Mapped { mmap, .. } => mmap,
+        }
+    }
+
+    /// Index of the first slot for which `key(slot) > target`, i.e. the insertion point.
+    pub fn upper_bound<K: Ord>(&self, target: &K, key: impl Fn(&[u8; W]) -> K) -> usize {
+        let (mut lo, mut hi) = (0, self.entries);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if key(self.slot(mid)) <= *target {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// Insert `slot` at `at`, shifting later entries. Returns false if the index is full.
+    pub fn insert(&mut self, at: usize, slot: [u8; W]) -> bool {
+        if self.is_full() || at > self.entries {
+            return false;
+        }
+        let used = self.entries * W;
+        match &mut self.backing {
+            Backing::Heap(buf) => {
+                buf.splice(at * W..at * W, slot);
+            }
+            Backing::Mapped { mmap, .. } => {
+                mmap.copy_within(at * W..used, (at + 1) * W);
+                mmap[at * W..(at + 1) * W].copy_from_slice(&slot);
+            }
+        }
+        self.entries += 1;
+        true
+    }
+
+    pub fn set(&mut self, at: usize, slot: [u8; W]) {
+        debug_assert!(at < self.entries);
+        match &mut self.backing {
+            Backing::Heap(buf) => buf[at * W..(at + 1) * W].copy_from_slice(&slot),
+            Backing::Mapped { mmap, .. } => mmap[at * W..(at + 1) * W].copy_from_slice(&slot),
+        }
+    }
+
+    /// Drop every entry, zeroing the used slots so they read as free after a reopen.
+    pub fn clear(&mut self) {
+        let used = self.entries * W;
+        match &mut self.backing {
+            Backing::Heap(buf) => buf.clear(),
+            Backing::Mapped { mmap, .. } => mmap[..used].fill(0),
+        }
+        self.entries = 0;
+    }
+
+    pub fn flush(&self) -> Result<(), StorageError> {
+        match &self.backing {
+            Backing::Heap(_) => Ok(()),
+            Backing::Mapped { mmap, .. } => mmap
+                .flush()
+                .map_err(|e| StorageError::from_io_error(e, "Failed to flush index file")),
+        }
+    }
+
+    /// Shrink a pre-allocated file to its used entries. The index accepts no further entries.
+    pub fn trim_to_valid_size(&mut self) -> Result<(), StorageError> {
+        let used = (self.entries * W) as u64;
+        if let Backing::Mapped { file, mmap } = &mut self.backing {
+            if mmap.len() as u64 == used {
+                return Ok(());
+            }
+            mmap.flush()
+                .map_err(|e| StorageError::from_io_error(e, "Failed to flush index file"))?;
+            set_len(file, used)?;
+            // SAFETY: as in `open`; the previous mapping is dropped on assignment
+            *mmap = unsafe { MmapMut::map_mut(&*file) }
+                .map_err(|e| StorageError::from_io_error(e, "Failed to remap index file"))?;
+            self.capacity = self.entries;
+        }
+        Ok(())
+    }
+}
+
+/// Number of leading used slots in `bytes`, found by binary search since unused slots only
+/// ever follow used ones.
+pub(crate) fn used_prefix_len<const W: usize>(
+    bytes: &[u8],
+    is_used: impl Fn(usize, &[u8; W]) -> bool,
+) -> usize {
+    let slot =
+        |i: usize| -> &[u8; W] { bytes[i * W..(i + 1) * W].try_into().expect("fixed width") };
+    let (mut lo, mut hi) = (0, bytes.len() / W);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if is_used(mid, slot(mid)) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+fn set_len(file: &File, len: u64) -> Result<(), StorageError> {
+    file.set_len(len)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to resize index file"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nonzero(_: usize, slot: &[u8; 4]) -> bool {
+        slot.iter().any(|&b| b != 0)
+    }
+
+    #[test]
+    fn test_preallocated_file_recovers_entry_count() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.index");
+
+        {
+            let mut index = FixedWidthIndex::<4>::open(&path, 64, nonzero).unwrap();
+            assert_eq!(index.capacity, 16);
+            for i in 1..=5u32 {
+                assert!(index.insert(index.len(), i.to_be_bytes()));
+            }
+            index.flush().unwrap();
+        }
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 64);
+
+        let index = FixedWidthIndex::<4>::open(&path, 64, nonzero).unwrap();
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.slot(4), &5u32.to_be_bytes());
+    }
+
+    #[test]
+    fn test_trim_then_reopen_keeps_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.index");
+
+        let mut index = FixedWidthIndex::<4>::open(&path, 64, nonzero).unwrap();
+        index.insert(0, 7u32.to_be_bytes());
+        index.insert(1, 9u32.to_be_bytes());
+        index.trim_to_valid_size().unwrap();
+        assert!(index.is_full());
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 8);
+        drop(index);
+
+        let index = FixedWidthIndex::<4>::open(&path, 64, nonzero).unwrap();
+        assert_eq!(index.len(), 2);
+        assert!(!index.is_full());
+    }
+
+    #[test]
+    fn test_insert_shifts_and_rejects_when_full() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.index");
+
+        let mut index = FixedWidthIndex::<4>::open(&path, 8, nonzero).unwrap();
+        assert!(index.insert(0, 2u32.to_be_bytes()));
+        assert!(index.insert(0, 1u32.to_be_bytes()));
+        assert!(!index.insert(2, 3u32.to_be_bytes()));
+        assert_eq!(index.slot(0), &1u32.to_be_bytes());
+        assert_eq!(index.slot(1), &2u32.to_be_bytes());
+    }
+
+    #[test]
+    fn test_misaligned_file_is_discarded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.index");
+        std::fs::write(&path, b"corrupt").unwrap();
+
+        let index = FixedWidthIndex::<4>::open(&path, 64, nonzero).unwrap();
+        assert_eq!(index.len(), 0);
+    }
+}
@@ -1,5 +1,10 @@
 use crate::error::StorageError;
+use crate::storage::file::fixed_index::{FixedWidthIndex, used_prefix_len};
 use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// On-disk width of an offset index entry: [4B relative offset][4B position].
+pub const OFFSET_INDEX_ENTRY_SIZE: usize = 8;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct IndexEntry {
@@ -7,8 +12,10 @@ pub struct IndexEntry {
     pub position: u32,
 }
 
+/// Sparse offset-to-position index, kept as fixed-width entries sorted by offset.
 pub struct SparseIndex {
-    entries: Vec<IndexEntry>, // sorted by offset
+    base_offset: u64,
+    slots: FixedWidthIndex<OFFSET_INDEX_ENTRY_SIZE>,
 }
 
 impl Default for SparseIndex {
@@ -18,41 +25,96 @@ impl Default for SparseIndex {
 }
 
 impl SparseIndex {
+    /// An index held only in memory, relative to offset 0.
     pub fn new() -> Self {
         Self {
-            entries: Vec::new(),
+            base_offset: 0,
+            slots: FixedWidthIndex::in_memory(),
         }
     }
 
-    pub fn add_entry(&mut self, entry: IndexEntry) {
-        match self
-            .entries
-            .binary_search_by_key(&entry.offset, |e| e.offset)
-        {
-            Ok(_) => {}
-            Err(pos) => {
-                self.entries.insert(pos, entry);
-            }
+    /// Open the index file at `path`, pre-allocated to `max_index_bytes` and searched in place.
+    pub fn open(path: &Path, base_offset: u64, max_index_bytes: u64) -> Result<Self, StorageError> {
+        let slots = FixedWidthIndex::open(path, max_index_bytes, is_used_slot)?;
+        Ok(Self { base_offset, slots })
+    }
+
+    /// Read the used entries of the index file at `path` into memory without modifying it.
+    pub fn load(path: &Path, base_offset: u64) -> Result<Self, StorageError> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| StorageError::from_io_error(e, "Failed to read index file"))?;
+        let mut index = Self::new();
+        index.base_offset = base_offset;
+        let used = used_prefix_len(&bytes, is_used_slot);
+        for chunk in bytes.chunks_exact(OFFSET_INDEX_ENTRY_SIZE).take(used) {
+            let (relative_offset, position) = decode_slot(chunk.try_into().expect("exact chunk"));
+            index.add_entry(IndexEntry {
+                offset: base_offset + relative_offset as u64,
+                position,
+            });
         }
+        Ok(index)
     }
 
-    pub fn find_position_for_offset(&self, target_offset: u64) -> Option<u32> {
-        if self.entries.is_empty() {
-            return Some(0); // Start from beginning if no index
+    fn entry(&self, i: usize) -> IndexEntry {
+        let (relative_offset, position) = decode_slot(self.slots.slot(i));
+        IndexEntry {
+            offset: self.base_offset + relative_offset as u64,
+            position,
         }
+    }
 
-        match self
-            .entries
-            .binary_search_by_key(&target_offset, |entry| entry.offset)
-        {
-            Ok(idx) => Some(self.entries[idx].position), // Exact match
-            Err(idx) => {
-                if idx == 0 {
-                    Some(0) // Target is before first indexed offset
-                } else {
-                    Some(self.entries[idx - 1].position) // Start from previous entry
-                }
-            }
+    /// Entries in offset order.
+    pub fn entries(&self) -> impl Iterator<Item = IndexEntry> + '_ {
+        (0..self.slots.len()).map(|i| self.entry(i))
+    }
+
+    fn encode(&self, entry: &IndexEntry) -> Option<[u8; OFFSET_INDEX_ENTRY_SIZE]> {
+        let relative_offset = u32::try_from(entry.offset.checked_sub(self.base_offset)?).ok()?;
+        let mut slot = [0u8; OFFSET_INDEX_ENTRY_SIZE];
+        slot[..4].copy_from_slice(&relative_offset.to_be_bytes());
+        slot[4..].copy_from_slice(&entry.position.to_be_bytes());
+        Some(slot)
+    }
+
+    /// Insert an entry in offset order; duplicates are ignored, as are entries that do not fit
+    /// once the index is full.
+    pub fn add_entry(&mut self, entry: IndexEntry) {
+        let Some(slot) = self.encode(&entry) else {
+            log::warn!(
+                "Offset {} cannot be indexed relative to base {}",
+                entry.offset,
+                self.base_offset
+            );
+            return;
+        };
+        let at = self.slots.upper_bound(&entry.offset, |s| {
+            self.base_offset + decode_slot(s).0 as u64
+        });
+        if at > 0 && self.entry(at - 1).offset == entry.offset {
+            return;
+        }
+        if !self.slots.insert(at, slot) {
+            log::debug!(
+                "Offset index full; skipping entry for offset {}",
+                entry.offset
+            );
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.slots.is_full()
+    }
+
+    pub fn find_position_for_offset(&self, target_offset: u64) -> Option<u32> {
+        // Start from the beginning if the target precedes every entry (or the index is empty)
+        let at = self.slots.upper_bound(&target_offset, |s| {
+            self.base_offset + decode_slot(s).0 as u64
+        });
+        if at == 0 {
+            Some(0)
+        } else {
+            Some(self.entry(at - 1).position)
         }
     }
 
@@ -60,24 +122,51 @@ impl SparseIndex {
     /// This allows rounding down an approximate byte position to a known offset index anchor.
     /// Returns Some(0) if the index is empty or the target precedes the first entry.
     pub fn find_floor_position_for_position(&self, target_pos: u32) -> Option<u32> {
-        if self.entries.is_empty() {
-            return Some(0);
+        // Entries are appended in offset order, which is monotonic with file position.
+        let at = self.slots.upper_bound(&target_pos, |s| decode_slot(s).1);
+        if at == 0 {
+            Some(0)
+        } else {
+            Some(self.entry(at - 1).position)
         }
+    }
 
-        // Entries are appended in offset order, which is monotonic with file position.
-        match self
-            .entries
-            .binary_search_by_key(&target_pos, |entry| entry.position)
-        {
-            Ok(idx) => Some(self.entries[idx].position),
-            Err(idx) => {
-                if idx == 0 {
-                    Some(0)
-                } else {
-                    Some(self.entries[idx - 1].position)
-                }
-            }
+    /// Cheap consistency check run when a segment is opened: entries must be ordered and must
+    /// not point past the end of the log.
+    pub fn sanity_check(&self, log_len: u64) -> Result<(), StorageError> {
+        let Some(last) = self.last_entry() else {
+            return Ok(());
+        };
+        let first = self.entry(0);
+        if last.offset < first.offset || last.position < first.position {
+            return Err(StorageError::DataCorruption {
+                context: "offset index sanity check".to_string(),
+                details: format!("entries out of order: first {first:?}, last {last:?}"),
+            });
+        }
+        if last.position as u64 >= log_len {
+            return Err(StorageError::DataCorruption {
+                context: "offset index sanity check".to_string(),
+                details: format!(
+                    "last entry position {} is beyond log length {log_len}",
+                    last.position
+                ),
+            });
         }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+    }
+
+    pub fn flush(&self) -> Result<(), StorageError> {
+        self.slots.flush()
+    }
+
+    /// Shrink the file to the used entries once its segment stops taking writes.
+    pub fn trim_to_valid_size(&mut self) -> Result<(), StorageError> {
+        self.slots.trim_to_valid_size()
     }
 
     pub fn serialize_entry(&self, entry: &IndexEntry, base_offset: u64) -> Vec<u8> {
@@ -109,7 +198,7 @@ impl SparseIndex {
         Ok(())
     }
 
-    /// Read index from file and populate the sparse index
+    /// Read index entries from a stream and populate the sparse index
     /// An optional `max_entries` bound can be provided to prevent excessive allocations
     /// in case of a corrupted or unexpectedly large file. If the bound is exceeded,
     /// returns a DataCorruption error.
@@ -119,12 +208,13 @@ impl SparseIndex {
         base_offset: u64,
         max_entries: Option<usize>,
     ) -> Result<(), StorageError> {
-        self.entries.clear();
+        self.clear();
+        self.base_offset = base_offset;
 
         let max_allowed = max_entries.unwrap_or(usize::MAX);
         let mut count: usize = 0;
 
-        let mut buffer = [0u8; 8]; // 4 bytes offset + 4 bytes position
+        let mut buffer = [0u8; OFFSET_INDEX_ENTRY_SIZE];
 
         while reader.read_exact(&mut buffer).is_ok() {
             if count >= max_allowed {
@@ -134,12 +224,9 @@ impl SparseIndex {
                 });
             }
 
-            let relative_offset = u32::from_be_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
-            let position = u32::from_be_bytes([buffer[4], buffer[5], buffer[6], buffer[7]]);
-
-            let absolute_offset = base_offset + relative_offset as u64;
-            self.entries.push(IndexEntry {
-                offset: absolute_offset,
+            let (relative_offset, position) = decode_slot(&buffer);
+            self.add_entry(IndexEntry {
+                offset: base_offset + relative_offset as u64,
                 position,
             });
             count += 1;
@@ -148,15 +235,28 @@ impl SparseIndex {
         Ok(())
     }
 
-    pub fn last_entry(&self) -> Option<&IndexEntry> {
-        self.entries.last()
+    pub fn last_entry(&self) -> Option<IndexEntry> {
+        self.slots.len().checked_sub(1).map(|i| self.entry(i))
     }
 
     pub fn entry_count(&self) -> usize {
-        self.entries.len()
+        self.slots.len()
     }
 }
 
+/// Relative offsets strictly increase, so only the first slot may legitimately be zero.
+fn is_used_slot(i: usize, slot: &[u8; OFFSET_INDEX_ENTRY_SIZE]) -> bool {
+    let (relative_offset, position) = decode_slot(slot);
+    relative_offset != 0 || (i == 0 && position != 0)
+}
+
+fn decode_slot(slot: &[u8; OFFSET_INDEX_ENTRY_SIZE]) -> (u32, u32) {
+    (
+        u32::from_be_bytes([slot[0], slot[1], slot[2], slot[3]]),
+        u32::from_be_bytes([slot[4], slot[5], slot[6], slot[7]]),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -165,7 +265,7 @@ mod tests {
     #[test]
     fn test_new_index_is_empty() {
         let index = SparseIndex::new();
-        assert!(index.entries.is_empty());
+        assert_eq!(index.entry_count(), 0);
         assert_eq!(index.last_entry(), None);
     }
 
@@ -185,10 +285,10 @@ mod tests {
             position: 150,
         });
 
-        assert_eq!(index.entries.len(), 3);
-        assert_eq!(index.entries[0].offset, 5);
-        assert_eq!(index.entries[1].offset, 10);
-        assert_eq!(index.entries[2].offset, 15);
+        assert_eq!(index.entry_count(), 3);
+        assert_eq!(index.entry(0).offset, 5);
+        assert_eq!(index.entry(1).offset, 10);
+        assert_eq!(index.entry(2).offset, 15);
     }
 
     #[test]
@@ -203,8 +303,8 @@ mod tests {
             position: 200,
         });
 
-        assert_eq!(index.entries.len(), 1);
-        assert_eq!(index.entries[0].position, 100);
+        assert_eq!(index.entry_count(), 1);
+        assert_eq!(index.entry(0).position, 100);
     }
 
     #[test]
@@ -261,7 +361,7 @@ mod tests {
         });
         assert_eq!(
             index.last_entry(),
-            Some(&IndexEntry {
+            Some(IndexEntry {
                 offset: 10,
                 position: 100
             })
@@ -272,7 +372,7 @@ mod tests {
         });
         assert_eq!(
             index.last_entry(),
-            Some(&IndexEntry {
+            Some(IndexEntry {
                 offset: 20,
                 position: 200
             })
@@ -310,9 +410,9 @@ mod tests {
             .read_from_file(&mut reader, base_offset, None)
             .unwrap();
 
-        assert_eq!(new_index.entries.len(), 2);
-        assert_eq!(new_index.entries[0], entry1);
-        assert_eq!(new_index.entries[1], entry2);
+        assert_eq!(new_index.entry_count(), 2);
+        assert_eq!(new_index.entry(0), entry1);
+        assert_eq!(new_index.entry(1), entry2);
     }
 
     #[test]
@@ -0,0 +1,439 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::RecordWithOffset;
+use crate::error::StorageError;
+use crate::storage::file::batch::{
+    BATCH_HEADER_SIZE, RecordBatchHeader, encode_stamped_batch_into, read_batch_header,
+    read_batch_records,
+};
+use crate::storage::file::file_io::FileIo;
+use crate::storage::file::index::SparseIndex;
+use crate::storage::file::remote_tier::REMOTE_CACHE_DIR;
+use crate::storage::file::segment_manager::{extract_offset_from_log_file, get_segment_offsets};
+use crate::storage::file::time_index::SparseTimeIndex;
+use crate::storage::file::{IndexingConfig, LogSegment, SyncMode};
+
+// ================================================================================================
+// OFFLINE SEGMENT TOOLING
+// ================================================================================================
+//
+// Inspection and repair of segment files backing `flashq-log-tool`. Nothing here coordinates
+// with a running broker; callers hold the data directory's `DirectoryLock` first.
+
+/// A decoded record batch and where it starts in its `.log` file.
+#[derive(Debug, Clone)]
+pub struct SegmentBatch {
+    pub position: u64,
+    pub header: RecordBatchHeader,
+    pub records: Vec<RecordWithOffset>,
+}
+
+/// Result of checking one segment's framing and indexes against its log.
+#[derive(Debug, Clone, Default)]
+pub struct SegmentReport {
+    pub log_path: PathBuf,
+    pub batches: usize,
+    pub records: u64,
+    /// One past the last readable offset, if the segment holds any records.
+    pub next_offset: Option<u64>,
+    pub issues: Vec<String>,
+}
+
+impl SegmentReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Entry counts of freshly rebuilt indexes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RebuiltIndexes {
+    pub offset_entries: usize,
+    pub time_entries: usize,
+}
+
+/// What `truncate_partition` changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TruncateSummary {
+    /// Base offsets of segments deleted outright.
+    pub removed_segments: Vec<u64>,
+    /// Base offset of the segment whose tail was cut, if any.
+    pub truncated_segment: Option<u64>,
+    /// Next offset the partition will assign once reopened.
+    pub next_offset: u64,
+}
+
+pub(crate) struct BatchWalk {
+    pub(crate) valid_bytes: u64,
+    pub(crate) log_bytes: u64,
+    pub(crate) error: Option<StorageError>,
+}
+
+/// Decode batches from the start of `log_path` until EOF, the first unreadable batch, or
+/// `visit` returning false. Decoding failures are reported in the walk rather than returned.
+pub(crate) fn walk_batches(
+    log_path: &Path,
+    mut visit: impl FnMut(SegmentBatch) -> bool,
+) -> Result<BatchWalk, StorageError> {
+    let log_file = File::open(log_path)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to open log file"))?;
+    let log_bytes = log_file
+        .metadata()
+        .map_err(|e| StorageError::from_io_error(e, "Failed to get log file metadata"))?
+        .len();
+    let mut reader = BufReader::new(log_file);
+
+    let mut position = 0;
+    let corrupt = |position: u64, details: String| StorageError::DataCorruption {
+        context: format!("batch at position {position}"),
+        details,
+    };
+    let error = loop {
+        if position >= log_bytes {
+            break None;
+        }
+        if log_bytes - position < BATCH_HEADER_SIZE {
+            break Some(corrupt(
+                position,
+                format!(
+                    "{} trailing bytes do not hold a header",
+                    log_bytes - position
+                ),
+            ));
+        }
+        let header = match read_batch_header(&mut reader) {
+            Ok(header) => header,
+            Err(e) => break Some(e),
+        };
+        if position + header.frame_size() > log_bytes {
+            break Some(corrupt(
+                position,
+                format!(
+                    "batch of {} bytes runs past end of log ({log_bytes} bytes)",
+                    header.frame_size()
+                ),
+            ));
+        }
+        let records = match read_batch_records(&mut reader, &header) {
+            Ok(records) => records,
+            Err(e) => break Some(e),
+        };
+        if let Some((i, record)) = records
+            .iter()
+            .enumerate()
+            .find(|(i, r)| r.offset != header.base_offset + *i as u64)
+        {
+            break Some(corrupt(
+                position,
+                format!(
+                    "record {i} has offset {} in batch based at {}",
+                    record.offset, header.base_offset
+                ),
+            ));
+        }
+
+        let frame_size = header.frame_size();
+        let keep_going = visit(SegmentBatch {
+            position,
+            header,
+            records,
+        });
+        position += frame_size;
+        if !keep_going {
+            break None;
+        }
+    };
+
+    Ok(BatchWalk {
+        valid_bytes: position,
+        log_bytes,
+        error,
+    })
+}
+
+/// Pass every batch of `log_path` to `visit` in log order, failing on the first unreadable one.
+pub fn dump_segment(
+    log_path: &Path,
+    mut visit: impl FnMut(&SegmentBatch),
+) -> Result<(), StorageError> {
+    let walk = walk_batches(log_path, |batch| {
+        visit(&batch);
+        true
+    })?;
+    walk.error.map_or(Ok(()), Err)
+}
+
+/// Check record framing, CRCs and offset continuity of a segment, then check that every
+/// offset and time index entry points at the start of a batch with matching offset or timestamp.
+pub fn verify_segment(log_path: &Path) -> Result<SegmentReport, StorageError> {
+    let mut report = SegmentReport {
+        log_path: log_path.to_path_buf(),
+        ..SegmentReport::default()
+    };
+    let Some(base_offset) = extract_offset_from_log_file(log_path) else {
+        report
+            .issues
+            .push("file name is not a segment base offset".to_string());
+        return Ok(report);
+    };
+
+    // Batch start position -> (base offset, time index key)
+    let mut batch_starts: HashMap<u64, (u64, u64)> = HashMap::new();
+    let mut max_ts_ms: Option<u64> = None;
+    let mut expected_offset = base_offset;
+    let mut records = 0;
+    let mut issues = Vec::new();
+    let walk = walk_batches(log_path, |batch| {
+        records += batch.records.len() as u64;
+        let header = &batch.header;
+        if header.base_offset != expected_offset {
+            issues.push(format!(
+                "batch at position {} starts at offset {}, expected {expected_offset}",
+                batch.position, header.base_offset
+            ));
+        }
+        expected_offset = header.last_offset() + 1;
+        let ts_ms = max_ts_ms.map_or(header.min_ts_ms, |max| max.max(header.min_ts_ms));
+        max_ts_ms = Some(ts_ms.max(header.max_ts_ms));
+        batch_starts.insert(batch.position, (header.base_offset, ts_ms));
+        true
+    })?;
+    report.issues = issues;
+    report.batches = batch_starts.len();
+    report.records = records;
+    report.next_offset = (report.batches > 0).then_some(expected_offset);
+    if let Some(error) = walk.error {
+        report.issues.push(format!(
+            "{error}; {} of {} bytes readable",
+            walk.valid_bytes, walk.log_bytes
+        ));
+    }
+
+    let index_path = log_path.with_extension("index");
+    if index_path.exists() {
+        for entry in SparseIndex::load(&index_path, base_offset)?.entries() {
+            match batch_starts.get(&(entry.position as u64)) {
+                Some(&(offset, _)) if offset == entry.offset => {}
+                Some(&(offset, _)) => report.issues.push(format!(
+                    "index maps offset {} to position {}, which holds offset {offset}",
+                    entry.offset, entry.position
+                )),
+                None => report.issues.push(format!(
+                    "index maps offset {} to position {}, which is not a batch start",
+                    entry.offset, entry.position
+                )),
+            }
+        }
+    } else {
+        report.issues.push("offset index is missing".to_string());
+    }
+
+    let time_index_path = log_path.with_extension("timeindex");
+    if time_index_path.exists() {
+        for entry in SparseTimeIndex::load(&time_index_path)?.entries() {
+            match batch_starts.get(&(entry.position as u64)) {
+                Some(&(_, ts_ms)) if ts_ms == entry.timestamp_ms => {}
+                Some(&(_, ts_ms)) => report.issues.push(format!(
+                    "time index maps {} ms to position {}, which should be keyed {ts_ms} ms",
+                    entry.timestamp_ms, entry.position
+                )),
+                None => report.issues.push(format!(
+                    "time index maps {} ms to position {}, which is not a batch start",
+                    entry.timestamp_ms, entry.position
+                )),
+            }
+        }
+    } else {
+        report.issues.push("time index is missing".to_string());
+    }
+
+    Ok(report)
+}
+
+/// Verify a single `.log` file or every segment below a directory, in path order.
+pub fn verify_path(path: &Path) -> Result<Vec<SegmentReport>, StorageError> {
+    find_segment_logs(path)?
+        .iter()
+        .map(|p| verify_segment(p))
+        .collect()
+}
+
+/// `path` itself if it is a segment `.log` file, otherwise every segment below it in path order.
+pub fn find_segment_logs(path: &Path) -> Result<Vec<PathBuf>, StorageError> {
+    let mut log_paths = Vec::new();
+    collect_log_files(path, &mut log_paths)?;
+    log_paths.sort();
+    Ok(log_paths)
+}
+
+fn collect_log_files(path: &Path, out: &mut Vec<PathBuf>) -> Result<(), StorageError> {
+    if path.is_file() {
+        if extract_offset_from_log_file(path).is_some() {
+            out.push(path.to_path_buf());
+        }
+        return Ok(());
+    }
+    let entries = std::fs::read_dir(path)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to read directory"))?;
+    for entry in entries.flatten() {
+        let entry_path = entry.path();
+        // Fetched remote segments are disposable copies
+        if entry_path.is_dir() && entry.file_name() == REMOTE_CACHE_DIR {
+            continue;
+        }
+        collect_log_files(&entry_path, out)?;
+    }
+    Ok(())
+}
+
+/// Discard and rebuild both indexes of the segment at `log_path` from its log. Indexes of
+/// segments that are no longer the newest in their partition are trimmed afterwards.
+pub fn rebuild_segment_indexes(
+    log_path: &Path,
+    indexing_config: IndexingConfig,
+) -> Result<RebuiltIndexes, StorageError> {
+    let base_offset = segment_base_offset(log_path)?;
+    let mut segment = LogSegment::recover(
+        base_offset,
+        log_path.with_extension(""),
+        SyncMode::Immediate,
+        indexing_config,
+    )?;
+    segment.rebuild_offset_index_from_log()?;
+    segment.rebuild_time_index_from_log()?;
+    segment.sync()?;
+
+    let partition_dir = log_path.parent().unwrap_or(Path::new("."));
+    let newest = get_segment_offsets(&partition_dir.to_path_buf())?
+        .last()
+        .copied();
+    if newest != Some(base_offset) {
+        segment.trim_indexes()?;
+    }
+
+    Ok(RebuiltIndexes {
+        offset_entries: segment.index_entry_count(),
+        time_entries: segment.time_index_entry_count(),
+    })
+}
+
+/// Remove every record at or above `to_offset` from the partition in `partition_dir`.
+///
+/// Later segments are deleted, the segment holding `to_offset` is cut at that offset (a batch
+/// straddling it is rewritten with only its earlier records) and its indexes are rebuilt.
+/// Anything after an unreadable batch in that segment is dropped as well.
+pub fn truncate_partition(
+    partition_dir: &Path,
+    to_offset: u64,
+    indexing_config: IndexingConfig,
+) -> Result<TruncateSummary, StorageError> {
+    let segment_offsets = get_segment_offsets(&partition_dir.to_path_buf())?;
+    let mut summary = TruncateSummary::default();
+
+    for &base_offset in segment_offsets.iter().filter(|&&b| b >= to_offset) {
+        let base_path = partition_dir.join(format!("{base_offset:020}"));
+        for extension in ["log", "index", "timeindex"] {
+            let path = base_path.with_extension(extension);
+            if let Err(e) = std::fs::remove_file(&path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    return Err(StorageError::from_io_error(
+                        e,
+                        "Failed to remove segment file",
+                    ));
+                }
+            }
+        }
+        summary.removed_segments.push(base_offset);
+    }
+
+    let Some(&base_offset) = segment_offsets.iter().rev().find(|&&b| b < to_offset) else {
+        return Ok(summary);
+    };
+    let log_path = partition_dir.join(format!("{base_offset:020}.log"));
+
+    let mut cut: Option<(u64, Option<SegmentBatch>)> = None;
+    let mut last_kept = None;
+    let walk = walk_batches(&log_path, |batch| {
+        if batch.header.last_offset() < to_offset {
+            last_kept = Some(batch.header.last_offset());
+            return true;
+        }
+        let position = batch.position;
+        let straddles = batch.header.base_offset < to_offset;
+        cut = Some((position, straddles.then_some(batch)));
+        false
+    })?;
+    let (cut_position, straddling) = match cut {
+        Some(cut) => cut,
+        // The segment ends before `to_offset`; only an unreadable tail is dropped
+        None => (walk.valid_bytes, None),
+    };
+
+    if cut_position < walk.log_bytes || straddling.is_some() {
+        let mut log_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&log_path)
+            .map_err(|e| StorageError::from_io_error(e, "Failed to open log file"))?;
+        log_file
+            .set_len(cut_position)
+            .map_err(|e| StorageError::from_io_error(e, "Failed to truncate log file"))?;
+        log_file
+            .seek(SeekFrom::End(0))
+            .map_err(|e| StorageError::from_io_error(e, "Failed to seek log file"))?;
+
+        if let Some(batch) = straddling {
+            let keep = (to_offset - batch.header.base_offset) as usize;
+            let (records, timestamps): (Vec<_>, Vec<_>) = batch.records[..keep]
+                .iter()
+                .map(|r| (r.record.clone(), r.timestamp.clone()))
+                .unzip();
+            let mut frame = Vec::new();
+            encode_stamped_batch_into(
+                &mut frame,
+                &records,
+                &timestamps,
+                batch.header.base_offset,
+                batch.header.codec,
+            )?;
+            FileIo::append_data_to_end(&mut log_file, &frame).map_err(|e| {
+                StorageError::from_io_error(
+                    std::io::Error::other(e.to_string()),
+                    "Failed to rewrite truncated batch",
+                )
+            })?;
+            last_kept = Some(to_offset - 1);
+        }
+        FileIo::synchronize_to_disk(&mut log_file).map_err(|e| {
+            StorageError::from_io_error(
+                std::io::Error::other(e.to_string()),
+                "Failed to sync log file",
+            )
+        })?;
+
+        // Stale index files would pass the sanity check if they stay below the new length
+        let _ = std::fs::remove_file(log_path.with_extension("index"));
+        let _ = std::fs::remove_file(log_path.with_extension("timeindex"));
+        rebuild_segment_indexes(&log_path, indexing_config)?;
+        summary.truncated_segment = Some(base_offset);
+    }
+
+    // Segments are contiguous, so an emptied segment continues where the previous one ended
+    let has_earlier_segments = segment_offsets.first() != Some(&base_offset);
+    summary.next_offset = match last_kept {
+        Some(offset) => offset + 1,
+        None if has_earlier_segments => base_offset,
+        None => 0,
+    };
+    Ok(summary)
+}
+
+fn segment_base_offset(log_path: &Path) -> Result<u64, StorageError> {
+    extract_offset_from_log_file(log_path).ok_or_else(|| StorageError::DataCorruption {
+        context: format!("segment {}", log_path.display()),
+        details: "file name is not a segment base offset".to_string(),
+    })
+}
@@ -1,18 +1,27 @@
+pub mod batch;
 pub mod common;
 pub mod consumer_group;
 pub mod file_io;
+pub(crate) mod fixed_index;
 pub mod index;
+pub mod log_tool;
 pub mod offset_store;
+pub mod remote_tier;
 pub mod segment;
 pub mod segment_manager;
+pub mod snapshot;
+pub mod sqlite_offset_store;
 pub mod time_index;
 pub mod topic_log;
 
+pub use batch::RecordBatchHeader;
 pub use common::SyncMode;
 pub use consumer_group::FileConsumerGroup;
 pub use file_io::FileIo;
 pub use index::{IndexEntry, SparseIndex};
 pub use offset_store::FileConsumerOffsetStore;
+pub use remote_tier::RemoteSegmentMetadata;
 pub use segment::{IndexingConfig, LogSegment};
 pub use segment_manager::SegmentManager;
+pub use sqlite_offset_store::{SqliteConsumerOffsetStore, SqliteOffsetDatabase};
 pub use topic_log::FileTopicLog;
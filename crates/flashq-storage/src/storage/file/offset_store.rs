@@ -70,6 +70,13 @@ impl FileConsumerOffsetStore {
         Ok(consumer_groups_dir.join(format!("{group_id}.json")))
     }
 
+    /// Offsets stored in a group's JSON file; empty if the file is missing or unreadable.
+    pub(crate) fn read_snapshot_file(
+        file_path: &Path,
+    ) -> Result<HashMap<(String, PartitionId), u64>, std::io::Error> {
+        Self::load_snapshots_from_disk(file_path)
+    }
+
     fn load_snapshots_from_disk(
         file_path: &Path,
     ) -> Result<HashMap<(String, PartitionId), u64>, std::io::Error> {
@@ -124,8 +131,17 @@ impl FileConsumerOffsetStore {
     }
 
     fn persist_to_disk(&self) -> Result<(), std::io::Error> {
-        let snapshots = self.snapshots.read();
-        let serializable_data = Self::convert_to_serializable_format(&snapshots, &self.group_id)?;
+        let snapshots = self.snapshots.write();
+        self.write_snapshots(&snapshots)
+    }
+
+    /// Callers hold the snapshot write lock so concurrent persists never interleave their
+    /// truncate-and-write of the file.
+    fn write_snapshots(
+        &self,
+        snapshots: &HashMap<(String, PartitionId), u64>,
+    ) -> Result<(), std::io::Error> {
+        let serializable_data = Self::convert_to_serializable_format(snapshots, &self.group_id)?;
         self.write_to_file(&serializable_data)
     }
 
@@ -150,12 +166,16 @@ impl FileConsumerOffsetStore {
     }
 
     fn write_to_file(&self, json_data: &str) -> Result<(), std::io::Error> {
+        self.write_to_file_with_sync(json_data, self.sync_mode == SyncMode::Immediate)
+    }
+
+    fn write_to_file_with_sync(&self, json_data: &str, sync: bool) -> Result<(), std::io::Error> {
         let mut file_handle = FileIo::create_with_write_truncate_permissions(&self.file_path)
             .map_err(std::io::Error::other)?;
         FileIo::write_data_at_offset(&mut file_handle, json_data.as_bytes(), 0)
             .map_err(std::io::Error::other)?;
 
-        if self.sync_mode == SyncMode::Immediate {
+        if sync {
             FileIo::synchronize_to_disk(&mut file_handle).map_err(std::io::Error::other)?;
         }
 
@@ -169,19 +189,6 @@ impl FileConsumerOffsetStore {
             .copied()
             .unwrap_or(0)
     }
-
-    fn should_persist_offset(
-        &self,
-        topic: &str,
-        partition_id: PartitionId,
-        new_offset: u64,
-    ) -> bool {
-        new_offset >= self.get_current_offset(topic, partition_id)
-    }
-
-    fn update_snapshot_in_memory(&self, topic: String, partition_id: PartitionId, offset: u64) {
-        self.snapshots.write().insert((topic, partition_id), offset);
-    }
 }
 
 impl ConsumerOffsetStore for FileConsumerOffsetStore {
@@ -195,12 +202,15 @@ impl ConsumerOffsetStore for FileConsumerOffsetStore {
         partition_id: PartitionId,
         offset: u64,
     ) -> Result<bool, StorageError> {
-        if !self.should_persist_offset(&topic, partition_id, offset) {
+        // Compare, update and write under one lock so a concurrent lower commit cannot land last
+        let mut snapshots = self.snapshots.write();
+        let key = (topic, partition_id);
+        if snapshots.get(&key).is_some_and(|&current| offset < current) {
             return Ok(false);
         }
 
-        self.update_snapshot_in_memory(topic, partition_id, offset);
-        self.persist_to_disk()
+        snapshots.insert(key, offset);
+        self.write_snapshots(&snapshots)
             .map_err(|e| StorageError::from_io_error(e, "Failed to persist offset snapshot"))?;
 
         Ok(true)
@@ -213,4 +223,12 @@ impl ConsumerOffsetStore for FileConsumerOffsetStore {
     fn group_id(&self) -> &str {
         &self.group_id
     }
+
+    /// Rewrite the snapshot file and fsync it, under the write lock like every persist.
+    fn sync(&self) -> Result<(), StorageError> {
+        let snapshots = self.snapshots.write();
+        Self::convert_to_serializable_format(&snapshots, &self.group_id)
+            .and_then(|json_data| self.write_to_file_with_sync(&json_data, true))
+            .map_err(|e| StorageError::from_io_error(e, "Failed to sync consumer offsets"))
+    }
 }
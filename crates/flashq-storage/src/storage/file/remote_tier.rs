@@ -0,0 +1,249 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+use log::{debug, info, warn};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::StorageError;
+use crate::storage::file::common::ensure_directory_exists;
+use crate::storage::file::{IndexingConfig, LogSegment, SyncMode};
+use crate::storage::remote::TieredStorageConfig;
+
+pub const REMOTE_METADATA_FILE: &str = "remote_segments.json";
+pub const REMOTE_CACHE_DIR: &str = "remote_cache";
+
+const SEGMENT_EXTENSIONS: [&str; 3] = ["log", "index", "timeindex"];
+
+/// Description of a segment that lives in the remote store rather than on local disk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteSegmentMetadata {
+    pub base_offset: u64,
+    pub max_offset: u64,
+    pub min_ts_ms: Option<u64>,
+    pub max_ts_ms: Option<u64>,
+    pub size_bytes: u64,
+    pub uploaded_at_ms: u64,
+}
+
+impl RemoteSegmentMetadata {
+    pub fn record_count(&self) -> usize {
+        (self.max_offset - self.base_offset + 1) as usize
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RemoteLogMetadata {
+    segments: Vec<RemoteSegmentMetadata>,
+}
+
+/// Remote half of a partition: which segments were offloaded, plus an LRU cache of the
+/// ones fetched back for reads.
+pub struct RemoteTier {
+    config: TieredStorageConfig,
+    key_prefix: String,
+    metadata_path: PathBuf,
+    cache_dir: PathBuf,
+    segments: BTreeMap<u64, RemoteSegmentMetadata>,
+    cache: Mutex<SegmentCache>,
+    indexing_config: IndexingConfig,
+}
+
+#[derive(Default)]
+struct SegmentCache {
+    segments: HashMap<u64, LogSegment>,
+    lru: VecDeque<u64>,
+}
+
+impl RemoteTier {
+    /// Load the partition's remote metadata and start with an empty fetch cache.
+    #[tracing::instrument(level = "info", skip(config, indexing_config), fields(partition_dir = %partition_dir.display()))]
+    pub fn open(
+        config: TieredStorageConfig,
+        key_prefix: String,
+        partition_dir: &Path,
+        indexing_config: IndexingConfig,
+    ) -> Result<Self, StorageError> {
+        let metadata_path = partition_dir.join(REMOTE_METADATA_FILE);
+        let segments = load_metadata(&metadata_path)?;
+
+        // Cached copies are disposable; start clean so stale fetches never shadow the store
+        let cache_dir = partition_dir.join(REMOTE_CACHE_DIR);
+        if cache_dir.exists() {
+            std::fs::remove_dir_all(&cache_dir).map_err(|e| {
+                StorageError::from_io_error(e, "Failed to clear remote segment cache")
+            })?;
+        }
+        ensure_directory_exists(&cache_dir)
+            .map_err(|e| StorageError::from_io_error(e, "Failed to create remote segment cache"))?;
+
+        info!(
+            "Opened remote tier {key_prefix} with {} offloaded segments",
+            segments.len()
+        );
+
+        Ok(Self {
+            config,
+            key_prefix,
+            metadata_path,
+            cache_dir,
+            segments,
+            cache: Mutex::new(SegmentCache::default()),
+            indexing_config,
+        })
+    }
+
+    pub fn local_retention_ms(&self) -> u64 {
+        self.config.local_retention_ms
+    }
+
+    pub fn segments(&self) -> impl Iterator<Item = &RemoteSegmentMetadata> {
+        self.segments.values()
+    }
+
+    /// Upload a closed segment and record it in the partition's remote metadata. The caller
+    /// owns removing the local copy once this returns.
+    #[tracing::instrument(level = "info", skip(self, segment), fields(prefix = %self.key_prefix, base_offset = segment.base_offset))]
+    pub fn upload(&mut self, segment: &mut LogSegment) -> Result<(), StorageError> {
+        let Some(max_offset) = segment.max_offset else {
+            return Ok(());
+        };
+        segment.sync()?;
+
+        let local_paths = [
+            &segment.log_path,
+            &segment.index_path,
+            &segment.time_index_path,
+        ];
+        for (path, extension) in local_paths.into_iter().zip(SEGMENT_EXTENSIONS) {
+            let key = self.object_key(segment.base_offset, extension);
+            self.config.store.put(&key, path)?;
+        }
+
+        let metadata = RemoteSegmentMetadata {
+            base_offset: segment.base_offset,
+            max_offset,
+            min_ts_ms: segment.min_ts_ms,
+            max_ts_ms: segment.max_ts_ms,
+            size_bytes: segment.size_bytes()?,
+            uploaded_at_ms: chrono::Utc::now().timestamp_millis().max(0) as u64,
+        };
+        self.segments.insert(segment.base_offset, metadata);
+        self.persist_metadata()
+    }
+
+    /// Run `read` against the remote segment starting at `base_offset`, fetching it into the
+    /// local cache first if needed.
+    pub fn with_segment<R>(
+        &self,
+        base_offset: u64,
+        read: impl FnOnce(&LogSegment) -> R,
+    ) -> Result<R, StorageError> {
+        let mut cache = self.cache.lock();
+
+        if let Entry::Vacant(entry) = cache.segments.entry(base_offset) {
+            entry.insert(self.fetch(base_offset)?);
+        }
+        cache.lru.retain(|&cached| cached != base_offset);
+        cache.lru.push_back(base_offset);
+
+        // The segment being read is most recent, so eviction never removes it
+        while cache.segments.len() > self.config.cache_segments.max(1) {
+            let Some(evicted) = cache.lru.pop_front() else {
+                break;
+            };
+            if let Some(segment) = cache.segments.remove(&evicted) {
+                debug!("Evicting cached remote segment {evicted}");
+                remove_segment_files(&segment);
+            }
+        }
+
+        let segment = cache
+            .segments
+            .get(&base_offset)
+            .expect("remote segment was just cached");
+        Ok(read(segment))
+    }
+
+    fn fetch(&self, base_offset: u64) -> Result<LogSegment, StorageError> {
+        debug!(
+            "Fetching remote segment {}/{base_offset:020}",
+            self.key_prefix
+        );
+        let base_path = self.cache_dir.join(format!("{base_offset:020}"));
+
+        for extension in SEGMENT_EXTENSIONS {
+            let key = self.object_key(base_offset, extension);
+            let dest = base_path.with_extension(extension);
+            match self.config.store.get(&key, &dest) {
+                Ok(()) => {}
+                // Indexes are rebuilt from the log on recovery if they cannot be fetched
+                Err(e) if extension != "log" => {
+                    warn!("Failed to fetch {key}, index will be rebuilt: {e}");
+                    let _ = std::fs::remove_file(&dest);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut segment = LogSegment::recover(
+            base_offset,
+            base_path,
+            SyncMode::None,
+            self.indexing_config.clone(),
+        )?;
+        segment.trim_indexes()?;
+        Ok(segment)
+    }
+
+    fn object_key(&self, base_offset: u64, extension: &str) -> String {
+        format!("{}/{base_offset:020}.{extension}", self.key_prefix)
+    }
+
+    fn persist_metadata(&self) -> Result<(), StorageError> {
+        let data = RemoteLogMetadata {
+            segments: self.segments.values().cloned().collect(),
+        };
+        let json = serde_json::to_string_pretty(&data).map_err(|e| {
+            StorageError::from_serialization_error(e, "Failed to serialize remote metadata")
+        })?;
+
+        let staging = self.metadata_path.with_extension("json.tmp");
+        std::fs::write(&staging, json)
+            .map_err(|e| StorageError::from_io_error(e, "Failed to write remote metadata"))?;
+        std::fs::rename(&staging, &self.metadata_path)
+            .map_err(|e| StorageError::from_io_error(e, "Failed to replace remote metadata"))
+    }
+}
+
+fn load_metadata(path: &Path) -> Result<BTreeMap<u64, RemoteSegmentMetadata>, StorageError> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to read remote metadata"))?;
+    let data: RemoteLogMetadata = serde_json::from_str(&contents).map_err(|e| {
+        StorageError::from_serialization_error(e, "Failed to parse remote metadata")
+    })?;
+    Ok(data
+        .segments
+        .into_iter()
+        .map(|segment| (segment.base_offset, segment))
+        .collect())
+}
+
+/// Delete a segment's log and index files, ignoring ones that are already gone.
+pub fn remove_segment_files(segment: &LogSegment) {
+    for path in [
+        &segment.log_path,
+        &segment.index_path,
+        &segment.time_index_path,
+    ] {
+        if let Err(e) = std::fs::remove_file(path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove segment file {}: {e}", path.display());
+            }
+        }
+    }
+}
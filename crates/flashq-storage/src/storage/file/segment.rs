@@ -1,14 +1,17 @@
-use crate::Record;
 use crate::error::StorageError;
-use crate::storage::file::common::{
-    SyncMode, deserialize_record, serialize_record, serialize_record_into_buffer,
+use crate::storage::compression::CompressionCodec;
+use crate::storage::file::batch::{
+    BATCH_HEADER_SIZE, BATCH_MAGIC, RecordBatchHeader, encode_batch_into,
+    encode_stamped_batch_into, read_batch_header, skip_batch_payload,
 };
+use crate::storage::file::common::{SyncMode, deserialize_record};
 use crate::storage::file::file_io::FileIo;
 use crate::storage::file::index::{IndexEntry, SparseIndex};
 use crate::storage::file::time_index::{SparseTimeIndex, TimeIndexEntry};
+use crate::{Record, RecordWithOffset};
 use log::warn;
 use std::fs::File;
-use std::io::{BufReader, Seek, SeekFrom};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
@@ -16,6 +19,8 @@ pub struct IndexingConfig {
     pub index_interval_bytes: u32,
     pub index_interval_records: u32,
     pub time_seek_back_bytes: u32,
+    /// Size each offset and time index file is pre-allocated to while its segment is active.
+    pub max_index_bytes: u64,
 }
 
 impl Default for IndexingConfig {
@@ -24,6 +29,7 @@ impl Default for IndexingConfig {
             index_interval_bytes: 4096,
             index_interval_records: 100,
             time_seek_back_bytes: 4096, // sensible default equals interval bytes
+            max_index_bytes: 10 * 1024 * 1024,
         }
     }
 }
@@ -35,11 +41,7 @@ pub struct LogSegment {
     pub index_path: PathBuf,
     pub time_index_path: PathBuf,
     log_file: File,
-    index_file: File,
-    index_buffer: Vec<u8>,
     index: SparseIndex,
-    time_index_file: File,
-    time_index_buffer: Vec<u8>,
     time_index: SparseTimeIndex,
     bytes_since_last_index: u32,
     records_since_last_index: u32,
@@ -47,6 +49,8 @@ pub struct LogSegment {
     indexing_config: IndexingConfig,
     pub min_ts_ms: Option<u64>,
     pub max_ts_ms: Option<u64>,
+    /// Largest timestamp in the first batch written to the segment; drives age-based rolling.
+    pub first_ts_ms: Option<u64>,
 }
 
 impl LogSegment {
@@ -70,40 +74,19 @@ impl LogSegment {
         })?;
         tracing::info!("Successfully created log file: {}", log_path.display());
 
-        // Use StdFileIO for index file operations
-        tracing::debug!("Creating index file: {}", index_path.display());
-        let index_file =
-            FileIo::create_with_append_and_read_permissions(&index_path).map_err(|e| {
-                tracing::error!(
-                    "Failed to create index file {}: {}",
-                    index_path.display(),
-                    e
-                );
-                StorageError::from_io_error(
-                    std::io::Error::other(e.to_string()),
-                    "Failed to open index file",
-                )
+        // Index files are pre-allocated and memory-mapped; entries are written in place
+        let index = SparseIndex::open(&index_path, base_offset, indexing_config.max_index_bytes)
+            .inspect_err(|e| {
+                tracing::error!("Failed to open index file {}: {}", index_path.display(), e)
             })?;
-        tracing::info!("Successfully created index file: {}", index_path.display());
-
-        // Prepare time index alongside
-        tracing::debug!("Creating time index file: {}", time_index_path.display());
-        let time_index_file = FileIo::create_with_append_and_read_permissions(&time_index_path)
-            .map_err(|e| {
-                tracing::error!(
-                    "Failed to create time index file {}: {}",
-                    time_index_path.display(),
-                    e
-                );
-                StorageError::from_io_error(
-                    std::io::Error::other(e.to_string()),
-                    "Failed to open time index file",
-                )
-            })?;
-        tracing::info!(
-            "Successfully created time index file: {}",
-            time_index_path.display()
-        );
+        let time_index = SparseTimeIndex::open(&time_index_path, indexing_config.max_index_bytes)
+            .inspect_err(|e| {
+            tracing::error!(
+                "Failed to open time index file {}: {}",
+                time_index_path.display(),
+                e
+            )
+        })?;
 
         Ok(LogSegment {
             base_offset,
@@ -111,19 +94,16 @@ impl LogSegment {
             log_path,
             index_path,
             log_file,
-            index_file,
-            index_buffer: Vec::new(),
-            index: SparseIndex::new(),
+            index,
             time_index_path,
-            time_index_file,
-            time_index_buffer: Vec::new(),
-            time_index: SparseTimeIndex::new(),
+            time_index,
             bytes_since_last_index: 0,
             records_since_last_index: 0,
             sync_mode,
             indexing_config,
             min_ts_ms: None,
             max_ts_ms: None,
+            first_ts_ms: None,
         })
     }
 
@@ -146,143 +126,85 @@ impl LogSegment {
             time_index_path.display()
         );
 
+        if migrate_legacy_log(&log_path, &index_path, &time_index_path)? {
+            tracing::info!(
+                "Converted legacy record log {} to batch format",
+                log_path.display()
+            );
+        }
+
+        // Opening creates missing index files, so note which ones have to be rebuilt first
+        let index_existed = index_path.exists();
+        let time_index_existed = time_index_path.exists();
+
         let mut segment = Self::new(
             base_offset,
-            log_path.clone(),
-            index_path.clone(),
-            time_index_path.clone(),
+            log_path,
+            index_path,
+            time_index_path,
             sync_mode,
             indexing_config,
         )?;
 
-        // Check if index file exists and try to read it
-        if segment.index_path.exists() {
-            let index_size = std::fs::metadata(&segment.index_path)
-                .map(|m| m.len())
-                .unwrap_or(0);
-            tracing::info!("Index file exists with size: {} bytes", index_size);
-
-            if index_size == 0 {
-                tracing::warn!("Index file exists but is empty (0 bytes)");
-            }
+        let log_len = std::fs::metadata(&segment.log_path)
+            .map(|m| m.len())
+            .unwrap_or(0);
 
-            let index_file = std::fs::File::open(&segment.index_path).map_err(|e| {
-                tracing::error!("Failed to open index file for reading: {}", e);
-                StorageError::from_io_error(e, "Failed to open index file for reading")
-            })?;
-            let mut index_reader = BufReader::new(index_file);
-            // Bound the maximum number of entries we accept from an index file to
-            // avoid excessive allocations from corrupted or malformed files. On error,
-            // rebuild the offset index from the log to degrade gracefully.
-            match segment
-                .index
-                .read_from_file(&mut index_reader, base_offset, Some(1_000_000))
-            {
-                Ok(()) => {
-                    let entries_read = segment.index.entry_count();
-                    tracing::info!("Successfully read {} entries from index file", entries_read);
-                }
-                Err(err) => {
-                    tracing::warn!(
-                        "Offset index read failed ({}); rebuilding from log {:?}",
-                        err,
-                        segment.log_path
-                    );
-                    segment.rebuild_offset_index_from_log()?;
-                }
-            }
-
-            // If offset index appears empty while log has data, rebuild it (treat as corrupt)
-            let log_len = match std::fs::metadata(&segment.log_path) {
-                Ok(m) => m.len(),
-                Err(_) => 0,
-            };
-            if segment.index.last_entry().is_none() && log_len > 0 {
-                tracing::warn!(
-                    "Offset index at {:?} appears empty (0 entries) while log has {} bytes; rebuilding from log {:?}",
-                    segment.index_path,
-                    log_len,
-                    segment.log_path
-                );
-                segment.rebuild_offset_index_from_log()?;
-            }
-        } else {
+        if !index_existed {
             tracing::warn!(
                 "Index file does not exist: {}",
                 segment.index_path.display()
             );
-            // Missing offset index: build from the log
             segment.rebuild_offset_index_from_log()?;
-        }
-
-        // Check if time index file exists and try to read it
-        if segment.time_index_path.exists() {
-            let time_index_size = std::fs::metadata(&segment.time_index_path)
-                .map(|m| m.len())
-                .unwrap_or(0);
-            tracing::info!(
-                "Time index file exists with size: {} bytes",
-                time_index_size
+        } else if let Err(err) = segment.index.sanity_check(log_len) {
+            tracing::warn!(
+                "Offset index failed sanity check ({}); rebuilding from log {:?}",
+                err,
+                segment.log_path
             );
+            segment.rebuild_offset_index_from_log()?;
+        } else if segment.index.last_entry().is_none() && log_len > 0 {
+            // An empty index over a non-empty log is treated as corrupt
+            tracing::warn!(
+                "Offset index at {:?} appears empty (0 entries) while log has {} bytes; rebuilding from log {:?}",
+                segment.index_path,
+                log_len,
+                segment.log_path
+            );
+            segment.rebuild_offset_index_from_log()?;
+        }
 
-            if time_index_size == 0 {
-                tracing::warn!("Time index file exists but is empty (0 bytes)");
-            }
-
-            let time_index_file = std::fs::File::open(&segment.time_index_path).map_err(|e| {
-                tracing::error!("Failed to open time index file for reading: {}", e);
-                StorageError::from_io_error(e, "Failed to open time index file for reading")
-            })?;
-            let mut time_index_reader = BufReader::new(time_index_file);
-            // Read with a sane bound; if it fails, rebuild the time index from log.
-            match segment
-                .time_index
-                .read_from_file(&mut time_index_reader, Some(1_000_000))
-            {
-                Ok(()) => {
-                    let entries_read = segment.time_index.entry_count();
-                    tracing::info!(
-                        "Successfully read {} entries from time index file",
-                        entries_read
-                    );
-                }
-                Err(err) => {
-                    tracing::warn!(
-                        "Time index read failed ({}); rebuilding from log {:?}",
-                        err,
-                        segment.log_path
-                    );
-                    segment.rebuild_time_index_from_log()?;
-                }
-            }
-
-            // If time index appears empty while log has data, rebuild it (treat as corrupt)
-            let log_len = match std::fs::metadata(&segment.log_path) {
-                Ok(m) => m.len(),
-                Err(_) => 0,
-            };
-            if segment.time_index.last_entry().is_none() && log_len > 0 {
-                tracing::warn!(
-                    "Time index at {:?} appears empty (0 entries) while log has {} bytes; rebuilding from log {:?}",
-                    segment.time_index_path,
-                    log_len,
-                    segment.log_path
-                );
-                segment.rebuild_time_index_from_log()?;
-            }
-        } else {
+        if !time_index_existed {
             tracing::warn!(
                 "Time index file does not exist: {}",
                 segment.time_index_path.display()
             );
-            // Missing time index: build from the log
+            segment.rebuild_time_index_from_log()?;
+        } else if let Err(err) = segment.time_index.sanity_check(log_len) {
+            tracing::warn!(
+                "Time index failed sanity check ({}); rebuilding from log {:?}",
+                err,
+                segment.log_path
+            );
+            segment.rebuild_time_index_from_log()?;
+        } else if segment.time_index.last_entry().is_none() && log_len > 0 {
+            tracing::warn!(
+                "Time index at {:?} appears empty (0 entries) while log has {} bytes; rebuilding from log {:?}",
+                segment.time_index_path,
+                log_len,
+                segment.log_path
+            );
             segment.rebuild_time_index_from_log()?;
         }
 
-        // Initialize max_ts_ms from time index if available (min_ts_ms optional)
-        segment.max_ts_ms = segment.time_index.last_entry().map(|e| e.timestamp_ms);
-
-        segment.max_offset = determine_max_offset(&segment.log_path, &segment.index)?;
+        // Initialize max_ts_ms from the time index and the batches after the last anchor
+        let (max_offset, tail_max_ts_ms) = determine_log_tail(&segment.log_path, &segment.index)?;
+        segment.max_offset = max_offset;
+        segment.first_ts_ms = read_first_batch_ts_ms(&segment.log_path)?;
+        segment.max_ts_ms = match (segment.time_index.last_entry(), tail_max_ts_ms) {
+            (Some(entry), Some(tail)) => Some(entry.timestamp_ms.max(tail)),
+            (entry, tail) => entry.map(|e| e.timestamp_ms).or(tail),
+        };
 
         tracing::info!(
             "Segment recovery completed - max_offset: {:?}, index entries: {}, time index entries: {}",
@@ -296,252 +218,177 @@ impl LogSegment {
 
     #[tracing::instrument(level = "debug", skip(self, record), fields(offset))]
     pub fn append_record(&mut self, record: &Record, offset: u64) -> Result<(), StorageError> {
-        let serialized = serialize_record(record, offset)?;
-        let start_position = self.write_record_to_log(&serialized)?;
-
-        self.update_metadata(offset, serialized.len() as u32);
-        // Best-effort timestamp for single append; may differ slightly from serialized value
-        let ts_ms = chrono::Utc::now().timestamp_millis();
-        let ts_ms = if ts_ms < 0 { 0 } else { ts_ms as u64 };
-
-        // Maintain cached min/max timestamps for pruning
-        self.min_ts_ms = Some(self.min_ts_ms.map_or(ts_ms, |v| v.min(ts_ms)));
-        self.max_ts_ms = Some(self.max_ts_ms.map_or(ts_ms, |v| v.max(ts_ms)));
-
-        tracing::trace!(
-            "Record appended - offset: {}, bytes_since_last_index: {}, records_since_last_index: {}, should_add_index: {}",
-            offset,
-            self.bytes_since_last_index,
-            self.records_since_last_index,
-            self.should_add_index_entry()
-        );
-
-        if self.should_add_index_entry() {
-            tracing::info!(
-                "Adding index entry - offset: {}, position: {}, bytes_accumulated: {}, records_accumulated: {}",
-                offset,
-                start_position,
-                self.bytes_since_last_index,
-                self.records_since_last_index
-            );
+        self.append_batch(std::slice::from_ref(record), offset, CompressionCodec::None)
+            .map(|_| ())
+    }
 
-            // Offset index update
-            let index_entry = IndexEntry {
-                offset,
-                position: start_position,
-            };
-            self.index.add_entry(index_entry.clone());
-            let serialized_entry = self.index.serialize_entry(&index_entry, self.base_offset);
-            tracing::debug!(
-                "Adding {} bytes to index buffer (current buffer size: {})",
-                serialized_entry.len(),
-                self.index_buffer.len()
-            );
-            self.index_buffer.extend_from_slice(&serialized_entry);
-
-            if self.index_buffer.len() >= self.indexing_config.index_interval_bytes as usize {
-                tracing::info!(
-                    "Index buffer reached threshold ({}), flushing",
-                    self.index_buffer.len()
-                );
-                self.flush_index_buffer()?;
-            }
+    /// Append multiple records as a single uncompressed batch.
+    pub fn append_records_bulk(
+        &mut self,
+        records: &[Record],
+        start_offset: u64,
+    ) -> Result<u64, StorageError> {
+        self.append_batch(records, start_offset, CompressionCodec::None)
+    }
 
-            // Time index update (avoid writing duplicates for identical timestamps)
-            let write_time_entry = match self.time_index.last_entry() {
-                Some(last) => last.timestamp_ms != ts_ms,
-                None => true,
-            };
-            if write_time_entry {
-                tracing::info!(
-                    "Adding time index entry - timestamp: {}, position: {}",
-                    ts_ms,
-                    start_position
-                );
-                let tentry = TimeIndexEntry {
-                    timestamp_ms: ts_ms,
-                    position: start_position,
-                };
-                self.time_index.add_entry(tentry.clone());
-                tracing::debug!(
-                    "Adding {} bytes to time index buffer (current buffer size: {})",
-                    8 + 4,
-                    self.time_index_buffer.len()
-                ); // 8 bytes timestamp + 4 bytes position
-                self.time_index_buffer
-                    .extend_from_slice(&tentry.timestamp_ms.to_be_bytes());
-                self.time_index_buffer
-                    .extend_from_slice(&tentry.position.to_be_bytes());
-                if self.time_index_buffer.len()
-                    >= self.indexing_config.index_interval_bytes as usize
-                {
-                    tracing::info!(
-                        "Time index buffer reached threshold ({}), flushing",
-                        self.time_index_buffer.len()
-                    );
-                    self.flush_time_index_buffer()?;
-                }
-            } else {
-                tracing::debug!(
-                    "Skipping time index entry - timestamp {} already exists",
-                    ts_ms
-                );
-            }
+    /// Append `records` as one record batch compressed with `codec` and write it in a single
+    /// I/O operation. Index entries, when due, point at the start of the batch.
+    #[tracing::instrument(level = "debug", skip(self, records), fields(count = records.len(), start_offset, %codec))]
+    pub fn append_batch(
+        &mut self,
+        records: &[Record],
+        start_offset: u64,
+        codec: CompressionCodec,
+    ) -> Result<u64, StorageError> {
+        // Use a single timestamp for the whole batch
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        self.append_batch_at(records, start_offset, &timestamp, codec)
+    }
 
-            self.bytes_since_last_index = 0;
-            self.records_since_last_index = 0;
-        }
-        self.sync_files_if_needed()
+    /// Like [`append_batch`](Self::append_batch) but stamps the batch with `timestamp`
+    /// instead of the current time. Used when replaying records that already carry one.
+    #[tracing::instrument(level = "debug", skip(self, records), fields(count = records.len(), start_offset, %codec))]
+    pub fn append_batch_at(
+        &mut self,
+        records: &[Record],
+        start_offset: u64,
+        timestamp: &str,
+        codec: CompressionCodec,
+    ) -> Result<u64, StorageError> {
+        self.append_encoded(records.len(), |buf| {
+            encode_batch_into(buf, records, start_offset, timestamp, codec)
+        })
     }
 
-    /// Append multiple records in a single I/O operation by coalescing
-    /// serialized bytes into one contiguous buffer and writing once.
-    #[tracing::instrument(level = "debug", skip(self, records), fields(count = records.len(), start_offset))]
-    pub fn append_records_bulk(
+    /// Like [`append_batch`](Self::append_batch) but stamps each record with its own entry of
+    /// `timestamps`, which need not be in order.
+    #[tracing::instrument(level = "debug", skip(self, records, timestamps), fields(count = records.len(), start_offset, %codec))]
+    pub fn append_stamped_batch(
         &mut self,
         records: &[Record],
+        timestamps: &[String],
         start_offset: u64,
+        codec: CompressionCodec,
     ) -> Result<u64, StorageError> {
-        if records.is_empty() {
+        self.append_encoded(records.len(), |buf| {
+            encode_stamped_batch_into(buf, records, timestamps, start_offset, codec)
+        })
+    }
+
+    fn append_encoded(
+        &mut self,
+        record_count: usize,
+        encode: impl FnOnce(&mut Vec<u8>) -> Result<RecordBatchHeader, StorageError>,
+    ) -> Result<u64, StorageError> {
+        if record_count == 0 {
             return Err(StorageError::WriteFailed {
-                context: "append_records_bulk: empty input".to_string(),
+                context: "append_batch: empty input".to_string(),
                 source: Box::new(crate::error::StorageErrorSource::Custom(
                     "invalid input: no records".to_string(),
                 )),
             });
         }
 
-        // Serialize all records into a single buffer and remember each record's
-        // starting position within the buffer as well as its size.
-        let mut buf: Vec<u8> = Vec::with_capacity(records.len().saturating_mul(64));
-        let mut rel_positions: Vec<u32> = Vec::with_capacity(records.len());
-        let mut sizes: Vec<u32> = Vec::with_capacity(records.len());
+        let mut buf: Vec<u8> = Vec::new();
+        let header = encode(&mut buf)?;
+        let time_index_ts_ms = time_index_key(self.max_ts_ms, &header);
 
-        let mut next_offset = start_offset;
-        let timestamp = chrono::Utc::now().to_rfc3339();
-        let ts_ms_i64 = chrono::DateTime::parse_from_rfc3339(&timestamp)
-            .map(|dt| dt.timestamp_millis())
-            .unwrap_or_else(|_| chrono::Utc::now().timestamp_millis());
-        let ts_ms = if ts_ms_i64 < 0 { 0 } else { ts_ms_i64 as u64 };
+        let start_position = self.write_batch_to_log(&buf)?;
+
+        self.first_ts_ms.get_or_insert(header.max_ts_ms);
         // Maintain cached min/max timestamps for pruning
-        self.min_ts_ms = Some(self.min_ts_ms.map_or(ts_ms, |v| v.min(ts_ms)));
-        self.max_ts_ms = Some(self.max_ts_ms.map_or(ts_ms, |v| v.max(ts_ms)));
-        for record in records {
-            let before = buf.len();
-            // Use a single timestamp for the whole batch
-            let rec_size = serialize_record_into_buffer(&mut buf, record, next_offset, &timestamp)?;
-            rel_positions.push(before as u32);
-            sizes.push(rec_size);
-            next_offset += 1;
-        }
+        self.min_ts_ms = Some(
+            self.min_ts_ms
+                .map_or(header.min_ts_ms, |v| v.min(header.min_ts_ms)),
+        );
+        self.max_ts_ms = Some(
+            self.max_ts_ms
+                .map_or(header.max_ts_ms, |v| v.max(header.max_ts_ms)),
+        );
+        self.update_metadata(&header);
 
-        // Single write to the log file; this returns the absolute start position in the file.
-        let start_position_abs =
-            FileIo::append_data_to_end(&mut self.log_file, &buf).map_err(|e| {
-                StorageError::from_io_error(
-                    std::io::Error::other(e.to_string()),
-                    "Failed bulk append",
-                )
-            })? as u32;
-
-        // Update metadata and sparse index incrementally for each record.
-        let mut current_offset = start_offset;
-        for (idx, rec_size) in sizes.iter().enumerate() {
-            let rec_pos_abs = start_position_abs + rel_positions[idx];
-            self.update_metadata(current_offset, *rec_size);
-
-            if self.should_add_index_entry() {
-                let index_entry = IndexEntry {
-                    offset: current_offset,
-                    position: rec_pos_abs,
-                };
-                self.index.add_entry(index_entry.clone());
-                let serialized_entry = self.index.serialize_entry(&index_entry, self.base_offset);
-                self.index_buffer.extend_from_slice(&serialized_entry);
-                if self.index_buffer.len() >= self.indexing_config.index_interval_bytes as usize {
-                    self.flush_index_buffer()?;
-                }
-                self.bytes_since_last_index = 0;
-                self.records_since_last_index = 0;
-
-                // Time index: add only if timestamp changed from last entry to avoid heavy duplicates
-                let write_time_entry = match self.time_index.last_entry() {
-                    Some(last) => last.timestamp_ms != ts_ms,
-                    None => true,
-                };
-                if write_time_entry {
-                    let tentry = TimeIndexEntry {
-                        timestamp_ms: ts_ms,
-                        position: rec_pos_abs,
-                    };
-                    self.time_index.add_entry(tentry.clone());
-                    self.time_index_buffer
-                        .extend_from_slice(&tentry.timestamp_ms.to_be_bytes());
-                    self.time_index_buffer
-                        .extend_from_slice(&tentry.position.to_be_bytes());
-                    if self.time_index_buffer.len()
-                        >= self.indexing_config.index_interval_bytes as usize
-                    {
-                        self.flush_time_index_buffer()?;
-                    }
-                }
-            }
+        tracing::trace!(
+            "Batch appended - offsets: {}-{}, bytes_since_last_index: {}, records_since_last_index: {}",
+            header.base_offset,
+            header.last_offset(),
+            self.bytes_since_last_index,
+            self.records_since_last_index
+        );
 
-            current_offset += 1;
+        if self.should_add_index_entry() {
+            self.add_index_entries(&header, time_index_ts_ms, start_position)?;
+            self.bytes_since_last_index = 0;
+            self.records_since_last_index = 0;
         }
 
         // Sync if needed (Immediate mode flushes index and fsyncs files)
         self.sync_files_if_needed()?;
 
-        Ok(current_offset - 1)
+        Ok(header.last_offset())
+    }
+
+    fn add_index_entries(
+        &mut self,
+        header: &RecordBatchHeader,
+        ts_ms: u64,
+        position: u32,
+    ) -> Result<(), StorageError> {
+        tracing::debug!(
+            "Adding index entry - offset: {}, position: {}",
+            header.base_offset,
+            position
+        );
+        let index_entry = IndexEntry {
+            offset: header.base_offset,
+            position,
+        };
+        self.index.add_entry(index_entry);
+
+        // Time index: add only if timestamp changed from last entry to avoid heavy duplicates
+        let write_time_entry = match self.time_index.last_entry() {
+            Some(last) => last.timestamp_ms != ts_ms,
+            None => true,
+        };
+        if write_time_entry {
+            let tentry = TimeIndexEntry {
+                timestamp_ms: ts_ms,
+                position,
+            };
+            self.time_index.add_entry(tentry);
+        } else {
+            tracing::debug!(
+                "Skipping time index entry - timestamp {} already exists",
+                ts_ms
+            );
+        }
+        Ok(())
     }
 
-    #[tracing::instrument(level = "debug", skip(self, serialized_record), fields(len = serialized_record.len()))]
-    fn write_record_to_log(&mut self, serialized_record: &[u8]) -> Result<u32, StorageError> {
-        // Use StdFileIO append method
-        let start_position = FileIo::append_data_to_end(&mut self.log_file, serialized_record)
-            .map_err(|e| {
-                StorageError::from_io_error(
-                    std::io::Error::other(e.to_string()),
-                    "Failed to append record to log file",
-                )
-            })? as u32;
+    #[tracing::instrument(level = "debug", skip(self, batch), fields(len = batch.len()))]
+    fn write_batch_to_log(&mut self, batch: &[u8]) -> Result<u32, StorageError> {
+        let start_position = FileIo::append_data_to_end(&mut self.log_file, batch).map_err(|e| {
+            StorageError::from_io_error(
+                std::io::Error::other(e.to_string()),
+                "Failed to append batch to log file",
+            )
+        })? as u32;
 
         Ok(start_position)
     }
 
-    fn update_metadata(&mut self, offset: u64, record_size: u32) {
-        self.max_offset = Some(offset);
-        self.bytes_since_last_index += record_size;
-        self.records_since_last_index += 1;
+    fn update_metadata(&mut self, header: &RecordBatchHeader) {
+        self.max_offset = Some(header.last_offset());
+        self.bytes_since_last_index = self
+            .bytes_since_last_index
+            .saturating_add(header.frame_size() as u32);
+        self.records_since_last_index = self
+            .records_since_last_index
+            .saturating_add(header.record_count);
     }
 
-    // removed: update_index_if_needed (rolled into append paths)
-
     fn sync_files_if_needed(&mut self) -> Result<(), StorageError> {
         if matches!(self.sync_mode, SyncMode::Immediate) {
-            self.flush_index_buffer()?;
-            self.flush_time_index_buffer()?;
-
-            FileIo::synchronize_to_disk(&mut self.log_file).map_err(|e| {
-                StorageError::from_io_error(
-                    std::io::Error::other(e.to_string()),
-                    "Failed to sync log file",
-                )
-            })?;
-
-            FileIo::synchronize_to_disk(&mut self.index_file).map_err(|e| {
-                StorageError::from_io_error(
-                    std::io::Error::other(e.to_string()),
-                    "Failed to sync index file",
-                )
-            })?;
-            FileIo::synchronize_to_disk(&mut self.time_index_file).map_err(|e| {
-                StorageError::from_io_error(
-                    std::io::Error::other(e.to_string()),
-                    "Failed to sync time index file",
-                )
-            })?;
+            self.sync()?;
         }
         Ok(())
     }
@@ -567,75 +414,32 @@ impl LogSegment {
         should_add
     }
 
-    #[tracing::instrument(level = "debug", skip(self), fields(bytes = self.index_buffer.len(), index_path = %self.index_path.display()))]
-    fn flush_index_buffer(&mut self) -> Result<(), StorageError> {
-        if self.index_buffer.is_empty() {
-            tracing::debug!("Index buffer empty, skipping flush");
-            return Ok(());
-        }
-
-        tracing::info!("Flushing {} bytes to index file", self.index_buffer.len());
-
-        FileIo::append_data_to_end(&mut self.index_file, &self.index_buffer).map_err(|e| {
-            tracing::error!(
-                "Failed to write {} bytes to index file: {}",
-                self.index_buffer.len(),
-                e
-            );
-            StorageError::from_io_error(
-                std::io::Error::other(e.to_string()),
-                "Failed to write to index file",
-            )
-        })?;
-
-        tracing::info!(
-            "Successfully flushed {} bytes to index file",
-            self.index_buffer.len()
-        );
-        self.index_buffer.clear();
-        Ok(())
+    pub fn index_entry_count(&self) -> usize {
+        self.index.entry_count()
     }
 
-    #[tracing::instrument(level = "debug", skip(self), fields(bytes = self.time_index_buffer.len(), time_index_path = %self.time_index_path.display()))]
-    fn flush_time_index_buffer(&mut self) -> Result<(), StorageError> {
-        if self.time_index_buffer.is_empty() {
-            tracing::debug!("Time index buffer empty, skipping flush");
-            return Ok(());
-        }
-
-        tracing::info!(
-            "Flushing {} bytes to time index file",
-            self.time_index_buffer.len()
-        );
-
-        FileIo::append_data_to_end(&mut self.time_index_file, &self.time_index_buffer).map_err(
-            |e| {
-                tracing::error!(
-                    "Failed to write {} bytes to time index file: {}",
-                    self.time_index_buffer.len(),
-                    e
-                );
-                StorageError::from_io_error(
-                    std::io::Error::other(e.to_string()),
-                    "Failed to write to time index file",
-                )
-            },
-        )?;
+    pub fn time_index_entry_count(&self) -> usize {
+        self.time_index.entry_count()
+    }
 
-        tracing::info!(
-            "Successfully flushed {} bytes to time index file",
-            self.time_index_buffer.len()
-        );
-        self.time_index_buffer.clear();
-        Ok(())
+    /// True once either index has used up its pre-allocated space.
+    pub fn is_index_full(&self) -> bool {
+        self.index.is_full() || self.time_index.is_full()
     }
 
     pub fn find_position_for_offset(&self, offset: u64) -> Option<u32> {
         self.index.find_position_for_offset(offset)
     }
 
+    /// Position to start scanning from for records with a timestamp >= `ts_ms`; no record
+    /// before it has a timestamp that large.
     pub fn find_position_for_timestamp(&self, ts_ms: u64) -> Option<u32> {
-        self.time_index.find_position_for_timestamp(ts_ms)
+        // An entry only bounds the records before its batch, so it must sit strictly below the
+        // target for none of them to match it
+        match ts_ms.checked_sub(1) {
+            Some(below) => self.time_index.find_position_for_timestamp(below),
+            None => Some(0),
+        }
     }
 
     /// Find the nearest offset index anchor at or before the given file position.
@@ -644,7 +448,7 @@ impl LogSegment {
     }
 
     #[tracing::instrument(level = "debug", skip(self))]
-    pub fn size_bytes(&mut self) -> Result<u64, StorageError> {
+    pub fn size_bytes(&self) -> Result<u64, StorageError> {
         FileIo::get_file_size(&self.log_file).map_err(|e| {
             StorageError::from_io_error(
                 std::io::Error::other(e.to_string()),
@@ -671,196 +475,297 @@ impl LogSegment {
 
     #[tracing::instrument(level = "debug", skip(self))]
     pub fn sync(&mut self) -> Result<(), StorageError> {
-        self.flush_index_buffer()?;
-        self.flush_time_index_buffer()?;
-
         FileIo::synchronize_to_disk(&mut self.log_file).map_err(|e| {
             StorageError::from_io_error(
                 std::io::Error::other(e.to_string()),
                 "Failed to sync log file",
             )
         })?;
-        FileIo::synchronize_to_disk(&mut self.index_file).map_err(|e| {
-            StorageError::from_io_error(
-                std::io::Error::other(e.to_string()),
-                "Failed to sync index file",
-            )
-        })?;
-        FileIo::synchronize_to_disk(&mut self.time_index_file).map_err(|e| {
-            StorageError::from_io_error(
-                std::io::Error::other(e.to_string()),
-                "Failed to sync time index file",
-            )
-        })?;
+        self.index.flush()?;
+        self.time_index.flush()?;
         Ok(())
     }
 
-    #[tracing::instrument(level = "info", skip(self))]
-    fn rebuild_time_index_from_log(&mut self) -> Result<(), StorageError> {
-        self.time_index = SparseTimeIndex::new();
-        self.time_index_buffer.clear();
+    /// Shrink both index files to their used entries. Called once the segment is closed for
+    /// appends; a later `recover` reopens them at the pre-allocated size again.
+    #[tracing::instrument(level = "debug", skip(self), fields(base_offset = self.base_offset))]
+    pub fn trim_indexes(&mut self) -> Result<(), StorageError> {
+        self.index.trim_to_valid_size()?;
+        self.time_index.trim_to_valid_size()
+    }
 
-        // Open the log file and iterate records from the beginning
-        let mut log_file = std::fs::File::open(&self.log_path).map_err(|e| {
-            StorageError::from_io_error(e, "Failed to open log for time index rebuild")
-        })?;
-        let mut reader = BufReader::new(&mut log_file);
-        reader.seek(SeekFrom::Start(0)).map_err(|e| {
-            StorageError::from_io_error(e, "Failed to seek log for time index rebuild")
-        })?;
+    #[tracing::instrument(level = "info", skip(self))]
+    pub fn rebuild_time_index_from_log(&mut self) -> Result<(), StorageError> {
+        self.time_index.clear();
 
+        let mut entries: Vec<TimeIndexEntry> = Vec::new();
         let mut bytes_since: u32 = 0;
         let mut records_since: u32 = 0;
         let mut last_ts_ms: Option<u64> = None;
+        let mut max_ts_ms: Option<u64> = None;
 
-        loop {
-            let start_pos = reader
-                .stream_position()
-                .map_err(|e| StorageError::from_io_error(e, "Failed to get stream position"))?;
-            match deserialize_record(&mut reader) {
-                Ok(r) => {
-                    let end_pos = reader.stream_position().map_err(|e| {
-                        StorageError::from_io_error(e, "Failed to get stream position after read")
-                    })?;
-                    let sz = (end_pos - start_pos) as u32;
-                    bytes_since = bytes_since.saturating_add(sz);
-                    records_since = records_since.saturating_add(1);
-
-                    let ts_ms_i64 = chrono::DateTime::parse_from_rfc3339(&r.timestamp)
-                        .map(|dt| dt.timestamp_millis())
-                        .unwrap_or(0);
-                    let ts_ms = if ts_ms_i64 < 0 { 0 } else { ts_ms_i64 as u64 };
-
-                    if bytes_since >= self.indexing_config.index_interval_bytes
-                        || records_since >= self.indexing_config.index_interval_records
-                    {
-                        let should_write = match last_ts_ms {
-                            Some(v) => v != ts_ms,
-                            None => true,
-                        };
-                        if should_write {
-                            let pos_u32 = start_pos as u32;
-                            let entry = TimeIndexEntry {
-                                timestamp_ms: ts_ms,
-                                position: pos_u32,
-                            };
-                            self.time_index.add_entry(entry.clone());
-                            self.time_index_buffer
-                                .extend_from_slice(&entry.timestamp_ms.to_be_bytes());
-                            self.time_index_buffer
-                                .extend_from_slice(&entry.position.to_be_bytes());
-                            if self.time_index_buffer.len()
-                                >= self.indexing_config.index_interval_bytes as usize
-                            {
-                                self.flush_time_index_buffer()?;
-                            }
-                            last_ts_ms = Some(ts_ms);
-                        }
-                        bytes_since = 0;
-                        records_since = 0;
-                    }
+        scan_batch_headers(&self.log_path, 0, |position, header| {
+            bytes_since = bytes_since.saturating_add(header.frame_size() as u32);
+            records_since = records_since.saturating_add(header.record_count);
+            let ts_ms = time_index_key(max_ts_ms, header);
+            max_ts_ms = Some(max_ts_ms.map_or(header.max_ts_ms, |m| m.max(header.max_ts_ms)));
+
+            if bytes_since >= self.indexing_config.index_interval_bytes
+                || records_since >= self.indexing_config.index_interval_records
+            {
+                if last_ts_ms != Some(ts_ms) {
+                    entries.push(TimeIndexEntry {
+                        timestamp_ms: ts_ms,
+                        position: position as u32,
+                    });
+                    last_ts_ms = Some(ts_ms);
                 }
-                Err(_) => break, // EOF or read error; best-effort rebuild
+                bytes_since = 0;
+                records_since = 0;
             }
-        }
+        })?;
 
-        self.flush_time_index_buffer()?;
-        Ok(())
+        for entry in entries {
+            self.time_index.add_entry(entry);
+        }
+        self.time_index.flush()
     }
 
     #[tracing::instrument(level = "info", skip(self))]
-    fn rebuild_offset_index_from_log(&mut self) -> Result<(), StorageError> {
-        self.index = SparseIndex::new();
-        self.index_buffer.clear();
-
-        // Open the log file and iterate records from the beginning
-        let mut log_file = std::fs::File::open(&self.log_path)
-            .map_err(|e| StorageError::from_io_error(e, "Failed to open log for index rebuild"))?;
-        let mut reader = BufReader::new(&mut log_file);
-        reader
-            .seek(SeekFrom::Start(0))
-            .map_err(|e| StorageError::from_io_error(e, "Failed to seek log for index rebuild"))?;
+    pub fn rebuild_offset_index_from_log(&mut self) -> Result<(), StorageError> {
+        self.index.clear();
 
+        let mut entries: Vec<IndexEntry> = Vec::new();
         let mut bytes_since: u32 = 0;
         let mut records_since: u32 = 0;
 
-        loop {
-            let start_pos = reader
-                .stream_position()
-                .map_err(|e| StorageError::from_io_error(e, "Failed to get stream position"))?;
-            match deserialize_record(&mut reader) {
-                Ok(r) => {
-                    let end_pos = reader.stream_position().map_err(|e| {
-                        StorageError::from_io_error(e, "Failed to get stream position after read")
-                    })?;
-                    let sz = (end_pos - start_pos) as u32;
-                    bytes_since = bytes_since.saturating_add(sz);
-                    records_since = records_since.saturating_add(1);
-
-                    if bytes_since >= self.indexing_config.index_interval_bytes
-                        || records_since >= self.indexing_config.index_interval_records
-                    {
-                        let entry = IndexEntry {
-                            offset: r.offset,
-                            position: start_pos as u32,
-                        };
-                        self.index.add_entry(entry.clone());
-                        let serialized_entry = self.index.serialize_entry(&entry, self.base_offset);
-                        self.index_buffer.extend_from_slice(&serialized_entry);
-                        if self.index_buffer.len()
-                            >= self.indexing_config.index_interval_bytes as usize
-                        {
-                            self.flush_index_buffer()?;
-                        }
-                        bytes_since = 0;
-                        records_since = 0;
-                    }
-                }
-                Err(_) => break, // EOF or read error; best-effort rebuild
+        scan_batch_headers(&self.log_path, 0, |position, header| {
+            bytes_since = bytes_since.saturating_add(header.frame_size() as u32);
+            records_since = records_since.saturating_add(header.record_count);
+
+            if bytes_since >= self.indexing_config.index_interval_bytes
+                || records_since >= self.indexing_config.index_interval_records
+            {
+                entries.push(IndexEntry {
+                    offset: header.base_offset,
+                    position: position as u32,
+                });
+                bytes_since = 0;
+                records_since = 0;
             }
-        }
+        })?;
 
-        self.flush_index_buffer()?;
-        Ok(())
+        for entry in entries {
+            self.index.add_entry(entry);
+        }
+        self.index.flush()
     }
 }
 
-fn determine_max_offset(
-    log_path: &PathBuf,
-    index: &SparseIndex,
-) -> Result<Option<u64>, StorageError> {
+/// Walk batch headers from `start_pos` without decoding payloads, stopping at EOF or the
+/// first unreadable batch (best-effort, like the record readers).
+fn scan_batch_headers<F>(log_path: &PathBuf, start_pos: u64, mut f: F) -> Result<(), StorageError>
+where
+    F: FnMut(u64, &RecordBatchHeader),
+{
     let log_file = match File::open(log_path) {
         Ok(file) => file,
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
         Err(e) => return Err(StorageError::from_io_error(e, "Failed to open log file")),
     };
-
-    if log_file
+    let log_len = log_file
         .metadata()
         .map_err(|e| StorageError::from_io_error(e, "Failed to get log file metadata"))?
-        .len()
-        == 0
-    {
-        return Ok(None);
-    }
+        .len();
 
-    let start_pos = index.last_entry().map_or(0, |entry| entry.position as u64);
     let mut reader = BufReader::new(log_file);
     reader
         .seek(SeekFrom::Start(start_pos))
         .map_err(|e| StorageError::from_io_error(e, "Failed to seek in log file"))?;
 
-    let last_known_offset = index.last_entry().map(|e| e.offset);
+    let mut position = start_pos;
+    while let Ok(header) = read_batch_header(&mut reader) {
+        // A torn write leaves a header whose payload runs past the end of the file
+        if position + header.frame_size() > log_len {
+            break;
+        }
+        f(position, &header);
+        if skip_batch_payload(&mut reader, &header).is_err() {
+            break;
+        }
+        position += header.frame_size();
+    }
+    Ok(())
+}
 
-    Ok(scan_for_max_offset(&mut reader, last_known_offset))
+/// Rewrite a log still in the per-record format that predates batch framing, returning whether
+/// it did. Each record becomes a batch of one keeping its offset and timestamp; the old index
+/// files point at positions that no longer hold, so they are removed to be rebuilt.
+fn migrate_legacy_log(
+    log_path: &PathBuf,
+    index_path: &PathBuf,
+    time_index_path: &PathBuf,
+) -> Result<bool, StorageError> {
+    let log_file = match File::open(log_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(StorageError::from_io_error(e, "Failed to open log file")),
+    };
+    let log_len = log_file
+        .metadata()
+        .map_err(|e| StorageError::from_io_error(e, "Failed to get log file metadata"))?
+        .len();
+    // Anything shorter than a batch header is a torn first write, left to the batch scanner.
+    // Legacy records carry the high byte of their millisecond timestamp where batches keep
+    // their magic, and that byte stays 0 for any real timestamp.
+    let mut reader = BufReader::new(log_file);
+    if log_len < BATCH_HEADER_SIZE {
+        return Ok(false);
+    }
+    let mut head = [0u8; BATCH_HEADER_SIZE as usize];
+    reader
+        .read_exact(&mut head)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to read log header"))?;
+    if head[12] == BATCH_MAGIC {
+        return Ok(false);
+    }
+    reader
+        .seek(SeekFrom::Start(0))
+        .map_err(|e| StorageError::from_io_error(e, "Failed to seek in log file"))?;
+
+    let migrating_path = log_path.with_extension("log.migrating");
+    let migrating_file = File::create(&migrating_path)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to create migrated log file"))?;
+    let mut writer = BufWriter::new(migrating_file);
+    let mut batch = Vec::new();
+    let mut converted = 0usize;
+    let mut position = 0u64;
+    while position < log_len {
+        let record = match read_legacy_record(&mut reader, log_len - position) {
+            Ok((record, frame_len)) => {
+                position += frame_len;
+                record
+            }
+            // Like the old reader, a torn final record is dropped
+            Err(e) if converted > 0 => {
+                warn!("Dropping unreadable tail of legacy log {log_path:?}: {e}");
+                break;
+            }
+            Err(e) => {
+                drop(writer);
+                let _ = std::fs::remove_file(&migrating_path);
+                return Err(StorageError::DataCorruption {
+                    context: format!("recover segment {}", log_path.display()),
+                    details: format!("unsupported segment format: {e}"),
+                });
+            }
+        };
+        batch.clear();
+        encode_stamped_batch_into(
+            &mut batch,
+            std::slice::from_ref(&record.record),
+            std::slice::from_ref(&record.timestamp),
+            record.offset,
+            CompressionCodec::None,
+        )?;
+        writer
+            .write_all(&batch)
+            .map_err(|e| StorageError::from_io_error(e, "Failed to write migrated log"))?;
+        converted += 1;
+    }
+
+    let migrating_file = writer
+        .into_inner()
+        .map_err(|e| StorageError::from_io_error(e.into_error(), "Failed to flush migrated log"))?;
+    migrating_file
+        .sync_all()
+        .map_err(|e| StorageError::from_io_error(e, "Failed to sync migrated log"))?;
+    std::fs::rename(&migrating_path, log_path)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to replace legacy log"))?;
+    for stale in [index_path, time_index_path] {
+        match std::fs::remove_file(stale) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(StorageError::from_io_error(
+                    e,
+                    "Failed to remove legacy index",
+                ));
+            }
+        }
+    }
+    tracing::debug!(
+        records = converted,
+        "Migrated legacy log {}",
+        log_path.display()
+    );
+    Ok(true)
 }
 
-fn scan_for_max_offset(
-    reader: &mut BufReader<File>,
-    mut last_valid_offset: Option<u64>,
-) -> Option<u64> {
-    while let Ok(record_with_offset) = deserialize_record(reader) {
-        last_valid_offset = Some(record_with_offset.offset);
+/// Read one legacy record, checking its length fields against the `remaining` bytes of the
+/// log before anything is allocated. Returns the record and its size on disk.
+fn read_legacy_record<R: Read>(
+    reader: &mut R,
+    remaining: u64,
+) -> Result<(RecordWithOffset, u64), StorageError> {
+    let mut header = [0u8; 24];
+    reader
+        .read_exact(&mut header)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to read legacy record header"))?;
+    let payload_size = u32::from_be_bytes(header[0..4].try_into().unwrap());
+    let timestamp_len = u32::from_be_bytes(header[20..24].try_into().unwrap());
+    let frame_len = 20 + payload_size as u64;
+    if frame_len > remaining || timestamp_len > payload_size.saturating_sub(4) {
+        return Err(StorageError::DataCorruption {
+            context: "read legacy record".to_string(),
+            details: format!(
+                "record of {frame_len} bytes with a {timestamp_len}-byte timestamp does not fit the remaining {remaining} bytes"
+            ),
+        });
     }
-    last_valid_offset
+
+    let mut frame = header.to_vec();
+    frame.resize(frame_len as usize, 0);
+    reader
+        .read_exact(&mut frame[24..])
+        .map_err(|e| StorageError::from_io_error(e, "Failed to read legacy record"))?;
+    let record = deserialize_record(&mut BufReader::new(frame.as_slice()))?;
+    Ok((record, frame_len))
+}
+
+/// Time index key for a batch: the largest timestamp before it, or its own smallest if that is
+/// larger. Keys never decrease even when producer timestamps do, and for in-order timestamps
+/// this is simply the batch's first timestamp.
+fn time_index_key(max_ts_ms_before: Option<u64>, header: &RecordBatchHeader) -> u64 {
+    max_ts_ms_before.map_or(header.min_ts_ms, |max| max.max(header.min_ts_ms))
+}
+
+/// Largest timestamp of the first batch in the log, reading only its header.
+fn read_first_batch_ts_ms(log_path: &PathBuf) -> Result<Option<u64>, StorageError> {
+    let log_file = match File::open(log_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(StorageError::from_io_error(e, "Failed to open log file")),
+    };
+    Ok(read_batch_header(&mut BufReader::new(log_file))
+        .ok()
+        .map(|header| header.max_ts_ms))
+}
+
+/// Determine the last offset and latest batch timestamp in the log by scanning forward from the
+/// last offset index anchor.
+fn determine_log_tail(
+    log_path: &PathBuf,
+    index: &SparseIndex,
+) -> Result<(Option<u64>, Option<u64>), StorageError> {
+    let start_pos = index.last_entry().map_or(0, |entry| entry.position as u64);
+    let mut max_offset = None;
+    let mut max_ts_ms: Option<u64> = None;
+
+    scan_batch_headers(log_path, start_pos, |_, header| {
+        max_offset = Some(header.last_offset());
+        max_ts_ms = Some(max_ts_ms.map_or(header.max_ts_ms, |v| v.max(header.max_ts_ms)));
+    })?;
+
+    Ok((max_offset, max_ts_ms))
 }
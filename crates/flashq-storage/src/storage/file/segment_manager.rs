@@ -5,12 +5,14 @@ use std::{collections::BTreeMap, io::BufReader};
 
 use crate::RecordWithOffset;
 use crate::error::StorageError;
-use crate::storage::file::common::{
-    deserialize_record, read_record_header, skip_record_after_header,
-};
+use crate::storage::ReadBounds;
+use crate::storage::file::batch::{read_batch_header, read_batch_records, skip_batch_payload};
+use crate::storage::file::common::timestamp_ms;
+use crate::storage::file::remote_tier::{RemoteSegmentMetadata, RemoteTier, remove_segment_files};
 use crate::storage::file::{IndexingConfig, LogSegment, SyncMode};
+use crate::storage::remote::TieredStorageConfig;
 
-use log::warn;
+use log::{debug, info, warn};
 
 /// Manager for multiple log segments, implementing segment rolling
 pub struct SegmentManager {
@@ -20,6 +22,9 @@ pub struct SegmentManager {
     segment_size_bytes: u64,
     sync_mode: SyncMode,
     indexing_config: IndexingConfig,
+    segment_ms: Option<u64>,
+    index_max_entries: Option<usize>,
+    remote: Option<RemoteTier>,
 }
 
 impl SegmentManager {
@@ -37,9 +42,93 @@ impl SegmentManager {
             segment_size_bytes,
             sync_mode,
             indexing_config,
+            segment_ms: None,
+            index_max_entries: None,
+            remote: None,
         }
     }
 
+    /// Also roll the active segment once its first batch is `segment_ms` old or its offset
+    /// index holds `index_max_entries` entries. `None` disables the respective limit.
+    pub fn set_roll_limits(&mut self, segment_ms: Option<u64>, index_max_entries: Option<usize>) {
+        self.segment_ms = segment_ms;
+        self.index_max_entries = index_max_entries;
+    }
+
+    /// Attach a remote tier. Closed segments past the local retention window are offloaded
+    /// on roll, and reads before the local log start are served from the remote store.
+    pub fn enable_tiered_storage(
+        &mut self,
+        config: TieredStorageConfig,
+        key_prefix: String,
+    ) -> Result<(), StorageError> {
+        self.remote = Some(RemoteTier::open(
+            config,
+            key_prefix,
+            &self.base_dir,
+            self.indexing_config.clone(),
+        )?);
+        Ok(())
+    }
+
+    /// Lowest base offset still held on local disk.
+    pub fn local_log_start_offset(&self) -> Option<u64> {
+        self.all_segments().map(|s| s.base_offset).min()
+    }
+
+    /// Offloaded segments that precede the local log, in offset order.
+    pub fn remote_segments(&self) -> Vec<&RemoteSegmentMetadata> {
+        let local_start = self.local_log_start_offset().unwrap_or(u64::MAX);
+        match &self.remote {
+            Some(remote) => remote
+                .segments()
+                .filter(|s| s.base_offset < local_start)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Upload closed segments whose newest record is older than the local retention window,
+    /// then drop them from local disk. Returns how many segments were offloaded.
+    #[tracing::instrument(level = "info", skip(self), fields(base_dir = %self.base_dir.display()))]
+    pub fn offload_expired_segments(&mut self, now_ms: u64) -> Result<usize, StorageError> {
+        let Some(remote) = self.remote.as_mut() else {
+            return Ok(0);
+        };
+        let retention_ms = remote.local_retention_ms();
+
+        let expired: Vec<u64> = self
+            .segments
+            .values()
+            .filter(|s| {
+                s.max_ts_ms
+                    .is_some_and(|ts| ts.saturating_add(retention_ms) <= now_ms)
+            })
+            .map(|s| s.base_offset)
+            .collect();
+
+        let mut offloaded = 0;
+        for base_offset in expired {
+            let Some(mut segment) = self.segments.remove(&base_offset) else {
+                continue;
+            };
+            if let Err(e) = remote.upload(&mut segment) {
+                self.segments.insert(base_offset, segment);
+                return Err(e);
+            }
+            remove_segment_files(&segment);
+            offloaded += 1;
+        }
+
+        if offloaded > 0 {
+            info!(
+                "Offloaded {offloaded} segments from {} to remote storage",
+                self.base_dir.display()
+            );
+        }
+        Ok(offloaded)
+    }
+
     pub fn find_segment_for_offset(&self, offset: u64) -> Option<&LogSegment> {
         if let Some(active) = &self.active_segment {
             if active.contains_offset(offset) {
@@ -88,27 +177,83 @@ impl SegmentManager {
     }
 
     /// Streaming read that keeps a single reader per segment and iterates sequentially across segments
-    #[tracing::instrument(level = "debug", skip(self), fields(offset, count = ?count))]
     pub fn read_records_streaming(
         &self,
         offset: u64,
         count: Option<usize>,
+    ) -> Result<Vec<RecordWithOffset>, StorageError> {
+        self.read_records_streaming_bounded(offset, ReadBounds::default(), count)
+    }
+
+    /// [`read_records_streaming`](Self::read_records_streaming) that stops at the first batch
+    /// past `bounds.to_offset` and skips batches stamped entirely at or after `bounds.to_time_ms`.
+    #[tracing::instrument(level = "debug", skip(self), fields(offset, count = ?count))]
+    pub fn read_records_streaming_bounded(
+        &self,
+        offset: u64,
+        bounds: ReadBounds,
+        count: Option<usize>,
     ) -> Result<Vec<RecordWithOffset>, StorageError> {
         let max_records = count.unwrap_or(usize::MAX);
-        if max_records == 0 {
+        if max_records == 0 || bounds.is_past_end(offset) {
             return Ok(Vec::new());
         }
 
-        let segments_sorted_by_offset = self.get_segments_sorted_by_offset();
-        let start_idx = self.find_starting_segment_index(&segments_sorted_by_offset, offset)?;
-
         let mut results: Vec<RecordWithOffset> = Vec::new();
         let mut need_offset = offset;
 
-        for segment in &segments_sorted_by_offset[start_idx..] {
+        // Offsets before the local log start are served from the remote tier
+        for remote_segment in self.remote_segments() {
             if results.len() >= max_records {
+                return Ok(results);
+            }
+            if bounds.is_past_end(remote_segment.base_offset) {
+                return Ok(results);
+            }
+            if remote_segment.max_offset < need_offset
+                || remote_segment
+                    .min_ts_ms
+                    .is_some_and(|min_ts| !bounds.admits_time(min_ts))
+            {
+                continue;
+            }
+            self.read_remote_segment(remote_segment.base_offset, |segment| {
+                let file_pos = self.calculate_file_position_for_segment(segment, need_offset);
+                match create_segment_reader(segment, file_pos) {
+                    Ok(mut reader) => collect_records_into(
+                        &mut reader,
+                        need_offset,
+                        bounds,
+                        max_records,
+                        &mut results,
+                    ),
+                    Err(e) => log_read_error(&e),
+                }
+            })?;
+            if let Some(last) = results.last() {
+                need_offset = last.offset + 1;
+            }
+        }
+
+        let segments_sorted_by_offset = self.get_segments_sorted_by_offset();
+        // No segment at or after the offset means it is past the end of the log, which
+        // reads as empty like a caught-up consumer polling at the next offset
+        let Ok(start_idx) =
+            self.find_starting_segment_index(&segments_sorted_by_offset, need_offset)
+        else {
+            return Ok(results);
+        };
+
+        for segment in &segments_sorted_by_offset[start_idx..] {
+            if results.len() >= max_records || bounds.is_past_end(segment.base_offset) {
                 break;
             }
+            if segment
+                .min_ts_ms
+                .is_some_and(|min_ts| !bounds.admits_time(min_ts))
+            {
+                continue;
+            }
 
             let file_pos = self.calculate_file_position_for_segment(segment, need_offset);
             let mut reader = match create_segment_reader(segment, file_pos) {
@@ -119,16 +264,7 @@ impl SegmentManager {
                 }
             };
 
-            while results.len() < max_records {
-                match deserialize_record(&mut reader) {
-                    Ok(r) => {
-                        if r.offset >= need_offset {
-                            results.push(r);
-                        }
-                    }
-                    Err(_) => break, // next segment
-                }
-            }
+            collect_records_into(&mut reader, need_offset, bounds, max_records, &mut results);
 
             if let Some(last) = results.last() {
                 need_offset = last.offset + 1;
@@ -138,13 +274,27 @@ impl SegmentManager {
         Ok(results)
     }
 
-    /// Streaming read starting from the first record whose timestamp is >= `ts_rfc3339`.
-    /// Uses each segment's sparse time index to compute a near position and then streams forward.
-    #[tracing::instrument(level = "debug", skip(self, ts_rfc3339), fields(ts = %ts_rfc3339, count = ?count))]
+    /// Streaming read of the records whose timestamp is >= `ts_rfc3339`, in offset order.
+    /// Producer timestamps may go backwards, so earlier records after the first match are
+    /// skipped rather than returned. Uses each segment's sparse time index to compute a near
+    /// position and then streams forward.
     pub fn read_records_from_timestamp(
         &self,
         ts_rfc3339: &str,
         count: Option<usize>,
+    ) -> Result<Vec<RecordWithOffset>, StorageError> {
+        self.read_records_from_timestamp_bounded(ts_rfc3339, ReadBounds::default(), count)
+    }
+
+    /// [`read_records_from_timestamp`](Self::read_records_from_timestamp) limited to `bounds`.
+    /// Scanning ends at the first batch past `bounds.to_offset`; batches and segments stamped
+    /// entirely at or after `bounds.to_time_ms` are skipped without decoding.
+    #[tracing::instrument(level = "debug", skip(self, ts_rfc3339), fields(ts = %ts_rfc3339, count = ?count))]
+    pub fn read_records_from_timestamp_bounded(
+        &self,
+        ts_rfc3339: &str,
+        bounds: ReadBounds,
+        count: Option<usize>,
     ) -> Result<Vec<RecordWithOffset>, StorageError> {
         let max_records = count.unwrap_or(usize::MAX);
         if max_records == 0 {
@@ -152,11 +302,43 @@ impl SegmentManager {
         }
 
         let target_ts_ms = Self::parse_target_ts_ms(ts_rfc3339)?;
-        let segments_sorted_by_offset = self.get_segments_sorted_by_offset();
         let mut results: Vec<RecordWithOffset> = Vec::new();
 
-        for segment in &segments_sorted_by_offset {
+        for remote_segment in self.remote_segments() {
             if results.len() >= max_records {
+                return Ok(results);
+            }
+            if bounds.is_past_end(remote_segment.base_offset) {
+                return Ok(results);
+            }
+            if remote_segment
+                .max_ts_ms
+                .is_some_and(|max_ts| max_ts < target_ts_ms)
+                || remote_segment
+                    .min_ts_ms
+                    .is_some_and(|min_ts| !bounds.admits_time(min_ts))
+            {
+                continue;
+            }
+            self.read_remote_segment(remote_segment.base_offset, |segment| {
+                let start_pos = self.compute_time_seek_start_pos(segment, target_ts_ms);
+                match create_segment_reader(segment, start_pos) {
+                    Ok(mut reader) => self.stream_records_from_pos(
+                        &mut reader,
+                        target_ts_ms,
+                        bounds,
+                        max_records,
+                        &mut results,
+                    ),
+                    Err(e) => log_read_error(&e),
+                }
+            })?;
+        }
+
+        let segments_sorted_by_offset = self.get_segments_sorted_by_offset();
+
+        for segment in &segments_sorted_by_offset {
+            if results.len() >= max_records || bounds.is_past_end(segment.base_offset) {
                 break;
             }
 
@@ -166,6 +348,12 @@ impl SegmentManager {
                     continue;
                 }
             }
+            if segment
+                .min_ts_ms
+                .is_some_and(|min_ts| !bounds.admits_time(min_ts))
+            {
+                continue;
+            }
 
             let start_pos = self.compute_time_seek_start_pos(segment, target_ts_ms);
 
@@ -177,12 +365,64 @@ impl SegmentManager {
                 }
             };
 
-            self.stream_records_from_pos(&mut reader, target_ts_ms, max_records, &mut results);
+            self.stream_records_from_pos(
+                &mut reader,
+                target_ts_ms,
+                bounds,
+                max_records,
+                &mut results,
+            );
         }
 
         Ok(results)
     }
 
+    /// Lowest offset held in either tier, `None` when there are no segments at all.
+    pub fn log_start_offset(&self) -> Option<u64> {
+        self.remote_segments()
+            .first()
+            .map(|s| s.base_offset)
+            .or_else(|| self.local_log_start_offset())
+    }
+
+    /// Offset of the first record, in offset order, whose timestamp is at or after
+    /// `ts_rfc3339`. When the first segment that can hold one has a minimum timestamp at or
+    /// after the target, its base offset is the answer and no records are read.
+    pub fn offset_for_timestamp(&self, ts_rfc3339: &str) -> Result<Option<u64>, StorageError> {
+        let target_ts_ms = Self::parse_target_ts_ms(ts_rfc3339)?;
+        let remote = self
+            .remote_segments()
+            .into_iter()
+            .map(|s| (s.base_offset, s.min_ts_ms, s.max_ts_ms));
+        let local = self
+            .get_segments_sorted_by_offset()
+            .into_iter()
+            .map(|s| (s.base_offset, s.min_ts_ms, s.max_ts_ms));
+        let candidate = remote
+            .chain(local)
+            .find(|(_, _, max_ts)| max_ts.is_none_or(|max_ts| max_ts >= target_ts_ms));
+
+        match candidate {
+            None => Ok(None),
+            Some((base_offset, Some(min_ts), _)) if min_ts >= target_ts_ms => Ok(Some(base_offset)),
+            Some(_) => Ok(self
+                .read_records_from_timestamp(ts_rfc3339, Some(1))?
+                .first()
+                .map(|record| record.offset)),
+        }
+    }
+
+    fn read_remote_segment(
+        &self,
+        base_offset: u64,
+        read: impl FnOnce(&LogSegment),
+    ) -> Result<(), StorageError> {
+        match &self.remote {
+            Some(remote) => remote.with_segment(base_offset, read),
+            None => Ok(()),
+        }
+    }
+
     #[inline]
     fn parse_target_ts_ms(ts_rfc3339: &str) -> Result<u64, StorageError> {
         let ts_ms_i64 = chrono::DateTime::parse_from_rfc3339(ts_rfc3339)
@@ -218,34 +458,48 @@ impl SegmentManager {
         &self,
         reader: &mut BufReader<File>,
         target_ts_ms: u64,
+        bounds: ReadBounds,
         max_records: usize,
         results: &mut Vec<RecordWithOffset>,
     ) {
         while results.len() < max_records {
-            match read_record_header(reader) {
-                Ok((payload_size, _off, ts_ms, _ts_len, record_start)) => {
-                    if ts_ms < target_ts_ms {
-                        // Fast skip without JSON parsing
-                        if let Err(e) = skip_record_after_header(reader, payload_size) {
-                            log_read_error(&e);
-                            break;
-                        }
-                        continue;
-                    }
-                    // Need this record fully: rewind and deserialize once
-                    if let Err(e) = reader.seek(SeekFrom::Start(record_start)) {
-                        log_read_error(&StorageError::from_io_error(
-                            e,
-                            "Failed to seek to record start",
-                        ));
-                        break;
-                    }
-                    match deserialize_record(reader) {
-                        Ok(r) => results.push(r),
-                        Err(_) => break,
-                    }
-                }
+            let header = match read_batch_header(reader) {
+                Ok(header) => header,
                 Err(_) => break,
+            };
+            if bounds.is_past_end(header.base_offset) {
+                break;
+            }
+            if header.max_ts_ms < target_ts_ms || !bounds.admits_time(header.min_ts_ms) {
+                // Fast skip without decompressing or JSON parsing
+                if let Err(e) = skip_batch_payload(reader, &header) {
+                    log_read_error(&e);
+                    break;
+                }
+                continue;
+            }
+            let records = match read_batch_records(reader, &header) {
+                Ok(records) => records,
+                Err(e) => {
+                    log_read_error(&e);
+                    break;
+                }
+            };
+            for record in records {
+                if results.len() >= max_records {
+                    break;
+                }
+                if bounds.is_past_end(record.offset) {
+                    break;
+                }
+                // Only a batch straddling the target or the time bound needs per-record timestamps
+                let after_target =
+                    header.min_ts_ms >= target_ts_ms || record_ts_ms(&record) >= target_ts_ms;
+                let before_bound = bounds.admits_time(header.max_ts_ms)
+                    || bounds.admits_time(record_ts_ms(&record));
+                if after_target && before_bound {
+                    results.push(record);
+                }
             }
         }
     }
@@ -298,19 +552,55 @@ impl SegmentManager {
     }
 
     pub fn should_roll_segment(&mut self) -> bool {
-        if let Some(active) = &mut self.active_segment {
-            if let Ok(size) = active.size_bytes() {
-                return size >= self.segment_size_bytes;
+        let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+        self.should_roll_segment_at(now_ms)
+    }
+
+    /// Whether the active segment has hit its size, index or age limit as of `now_ms`.
+    pub fn should_roll_segment_at(&mut self, now_ms: u64) -> bool {
+        let Some(active) = &mut self.active_segment else {
+            return false;
+        };
+        if active
+            .size_bytes()
+            .is_ok_and(|size| size >= self.segment_size_bytes)
+        {
+            return true;
+        }
+        if active.is_index_full()
+            || self
+                .index_max_entries
+                .is_some_and(|max| active.index_entry_count() >= max)
+        {
+            debug!(
+                "Active segment {} reached its index limit",
+                active.base_offset
+            );
+            return true;
+        }
+        match (self.segment_ms, active.first_ts_ms) {
+            (Some(segment_ms), Some(first_ts_ms)) => {
+                now_ms.saturating_sub(first_ts_ms) >= segment_ms
             }
+            _ => false,
         }
-        false
     }
 
     #[tracing::instrument(level = "info", skip(self), fields(next_offset))]
     pub fn roll_to_new_segment(&mut self, next_offset: u64) -> Result<(), StorageError> {
+        // Trim in place so a failure leaves the segment active rather than dropping it
+        if let Some(active) = self.active_segment.as_mut() {
+            active.trim_indexes()?;
+        }
         if let Some(active) = self.active_segment.take() {
-            let base_offset = active.base_offset;
-            self.segments.insert(base_offset, active);
+            self.segments.insert(active.base_offset, active);
+        }
+
+        if self.remote.is_some() {
+            let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+            if let Err(e) = self.offload_expired_segments(now_ms) {
+                warn!("Failed to offload closed segments to remote storage: {e}");
+            }
         }
 
         let log_path = self.base_dir.join(format!("{next_offset:020}.log"));
@@ -330,6 +620,19 @@ impl SegmentManager {
         Ok(())
     }
 
+    /// Continue the log at `next_offset`, leaving the offsets between the current end and it
+    /// unassigned. An active segment that never took a record is replaced instead of closed,
+    /// so the skipped range does not leave an empty segment behind.
+    pub fn skip_to_offset(&mut self, next_offset: u64) -> Result<(), StorageError> {
+        if let Some(active) = self
+            .active_segment
+            .take_if(|active| active.record_count() == 0)
+        {
+            remove_segment_files(&active);
+        }
+        self.roll_to_new_segment(next_offset)
+    }
+
     pub fn active_segment_mut(&mut self) -> Option<&mut LogSegment> {
         self.active_segment.as_mut()
     }
@@ -358,12 +661,16 @@ impl SegmentManager {
             let log_path = base_path.with_extension("log");
 
             if log_path.exists() {
-                let segment = LogSegment::recover(
+                let mut segment = LogSegment::recover(
                     base_offset,
                     base_path,
                     self.sync_mode,
                     self.indexing_config.clone(),
                 )?;
+                // Only the latest segment keeps taking appends
+                if Some(&base_offset) != segment_offsets.last() {
+                    segment.trim_indexes()?;
+                }
                 self.segments.insert(base_offset, segment);
             }
         }
@@ -379,7 +686,7 @@ impl SegmentManager {
     }
 }
 
-fn get_segment_offsets(segment_directory: &PathBuf) -> Result<Vec<u64>, StorageError> {
+pub(crate) fn get_segment_offsets(segment_directory: &PathBuf) -> Result<Vec<u64>, StorageError> {
     let directory_entries = std::fs::read_dir(segment_directory)
         .map_err(|e| StorageError::from_io_error(e, "Failed to read segment directory"))?;
 
@@ -398,7 +705,7 @@ fn get_segment_offsets(segment_directory: &PathBuf) -> Result<Vec<u64>, StorageE
     Ok(discovered_offsets)
 }
 
-fn extract_offset_from_log_file(file_path: &std::path::Path) -> Option<u64> {
+pub(crate) fn extract_offset_from_log_file(file_path: &std::path::Path) -> Option<u64> {
     let file_name = file_path.file_name()?.to_str()?;
 
     if !file_name.ends_with(".log") {
@@ -429,22 +736,69 @@ fn collect_records(
     maximum_records: usize,
 ) -> Vec<RecordWithOffset> {
     let mut collected_records = Vec::new();
+    collect_records_into(
+        segment_reader,
+        minimum_offset,
+        ReadBounds::default(),
+        maximum_records,
+        &mut collected_records,
+    );
+    collected_records
+}
 
+/// Decode batches from the reader, appending records at or after `minimum_offset` and within
+/// `bounds` until `maximum_records` have been collected. Batches entirely below the offset or
+/// stamped entirely past the time bound are skipped without being decompressed; reading stops
+/// at the first batch past the offset bound.
+fn collect_records_into(
+    segment_reader: &mut BufReader<File>,
+    minimum_offset: u64,
+    bounds: ReadBounds,
+    maximum_records: usize,
+    collected_records: &mut Vec<RecordWithOffset>,
+) {
     while collected_records.len() < maximum_records {
-        match deserialize_record(segment_reader) {
-            Ok(record_with_offset) => {
-                if record_with_offset.offset >= minimum_offset {
-                    collected_records.push(record_with_offset);
-                }
+        let header = match read_batch_header(segment_reader) {
+            Ok(header) => header,
+            Err(error) => {
+                log_read_error(&error);
+                break;
+            }
+        };
+        if bounds.is_past_end(header.base_offset) {
+            break;
+        }
+        if header.last_offset() < minimum_offset || !bounds.admits_time(header.min_ts_ms) {
+            if let Err(error) = skip_batch_payload(segment_reader, &header) {
+                log_read_error(&error);
+                break;
+            }
+            continue;
+        }
+        match read_batch_records(segment_reader, &header) {
+            Ok(records) => {
+                let remaining = maximum_records - collected_records.len();
+                collected_records.extend(
+                    records
+                        .into_iter()
+                        .filter(|r| r.offset >= minimum_offset && !bounds.is_past_end(r.offset))
+                        .filter(|r| {
+                            bounds.admits_time(header.max_ts_ms)
+                                || bounds.admits_time(record_ts_ms(r))
+                        })
+                        .take(remaining),
+                );
             }
-            Err(deserialization_error) => {
-                log_read_error(&deserialization_error);
+            Err(error) => {
+                log_read_error(&error);
                 break;
             }
         }
     }
+}
 
-    collected_records
+fn record_ts_ms(record: &RecordWithOffset) -> u64 {
+    timestamp_ms(&record.timestamp)
 }
 
 fn log_read_error(storage_error: &StorageError) {
@@ -511,6 +865,7 @@ mod tests {
             index_interval_bytes: 64,
             index_interval_records: 1,
             time_seek_back_bytes: 64,
+            ..Default::default()
         };
         let mut segment = LogSegment::new(
             0,
@@ -528,17 +883,16 @@ mod tests {
             .collect();
         let _last = segment.append_records_bulk(&recs, 0).unwrap();
 
-        // Extract the timestamp of the first record
-        use crate::storage::file::common::read_record_header;
+        // Extract the timestamp of the first batch
         let mut reader = super::create_segment_reader(&segment, 0).unwrap();
-        let (_payload, _off, target_ts_ms, _tslen, _start) =
-            read_record_header(&mut reader).unwrap();
+        let target_ts_ms = read_batch_header(&mut reader).unwrap().min_ts_ms;
 
         // Manager config: set a small backseek to test logic
         let mgr_idx_cfg = IndexingConfig {
             index_interval_bytes: 128,
             index_interval_records: 100,
             time_seek_back_bytes: 32,
+            ..Default::default()
         };
         let mgr = SegmentManager::new(base.clone(), 1024 * 1024, SyncMode::Immediate, mgr_idx_cfg);
 
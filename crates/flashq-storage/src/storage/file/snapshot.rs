@@ -0,0 +1,225 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use log::{debug, info};
+
+use crate::error::StorageError;
+use crate::storage::file::common::ensure_directory_exists;
+use crate::storage::file::log_tool::walk_batches;
+use crate::storage::file::remote_tier::{REMOTE_CACHE_DIR, REMOTE_METADATA_FILE};
+use crate::storage::file::segment_manager::get_segment_offsets;
+use crate::storage::file::sqlite_offset_store::{SQLITE_OFFSETS_FILE, copy_offset_database};
+use crate::storage::offsets_topic::is_internal_topic;
+
+// ================================================================================================
+// HOT SNAPSHOTS
+// ================================================================================================
+//
+// A snapshot is a data directory that a broker can be started on directly. Closed segments are
+// immutable, so they are hard-linked (copied when the destination is on another filesystem).
+// The active segment is still taking appends: its log is copied up to the last complete batch
+// present when the copy starts, and its indexes are left out for recovery to rebuild.
+//
+// Consumer group offsets are copied before any segment, so no committed offset in the snapshot
+// can point past the records it holds. The SQLite offset database, when present, is copied
+// with `VACUUM INTO` rather than file by file so the copy is one consistent transaction.
+
+/// What `snapshot_data_dir` captured for one partition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionSnapshot {
+    pub topic: String,
+    pub partition: u32,
+    /// Closed segments hard-linked (or copied) into the snapshot.
+    pub linked_segments: usize,
+    /// Bytes of the active segment's log copied into the snapshot.
+    pub active_bytes: u64,
+    /// Next offset the snapshot's partition will assign once opened.
+    pub next_offset: u64,
+}
+
+/// What `snapshot_data_dir` wrote.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotSummary {
+    pub partitions: Vec<PartitionSnapshot>,
+    /// Consumer group JSON files copied, counting the SQLite offset database as one.
+    pub consumer_group_files: usize,
+}
+
+/// Take a snapshot of `data_dir` into `dest`, which must not exist yet or be empty. Safe to
+/// run against the data directory of a live broker.
+#[tracing::instrument(level = "info", fields(data_dir = %data_dir.display(), dest = %dest.display()))]
+pub fn snapshot_data_dir(data_dir: &Path, dest: &Path) -> Result<SnapshotSummary, StorageError> {
+    if dest
+        .read_dir()
+        .is_ok_and(|mut entries| entries.next().is_some())
+    {
+        return Err(StorageError::Unavailable {
+            context: format!("snapshot destination {} is not empty", dest.display()),
+        });
+    }
+    ensure_directory_exists(dest)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to create snapshot directory"))?;
+
+    let mut summary = SnapshotSummary {
+        consumer_group_files: copy_consumer_groups(data_dir, dest)?,
+        ..Default::default()
+    };
+
+    // Offsets kept in an internal topic are captured ahead of the data topics, like the files
+    let mut topics = list_dirs(data_dir)?;
+    topics.sort_by_key(|(topic, _)| !is_internal_topic(topic));
+    for (topic, topic_dir) in topics {
+        if topic == "consumer_groups" || topic.starts_with('.') {
+            continue;
+        }
+        for (partition_name, partition_dir) in list_dirs(&topic_dir)? {
+            let Ok(partition) = partition_name.parse::<u32>() else {
+                continue;
+            };
+            let dest_dir = dest.join(&topic).join(&partition_name);
+            let mut snapshot = snapshot_partition(&partition_dir, &dest_dir)?;
+            snapshot.topic = topic.clone();
+            snapshot.partition = partition;
+            summary.partitions.push(snapshot);
+        }
+    }
+
+    info!(
+        "Snapshot of {} written to {}: {} partitions, {} consumer groups",
+        data_dir.display(),
+        dest.display(),
+        summary.partitions.len(),
+        summary.consumer_group_files
+    );
+    Ok(summary)
+}
+
+fn snapshot_partition(
+    partition_dir: &Path,
+    dest_dir: &Path,
+) -> Result<PartitionSnapshot, StorageError> {
+    ensure_directory_exists(dest_dir)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to create snapshot partition"))?;
+
+    let remote_metadata = partition_dir.join(REMOTE_METADATA_FILE);
+    if remote_metadata.exists() {
+        std::fs::copy(&remote_metadata, dest_dir.join(REMOTE_METADATA_FILE))
+            .map_err(|e| StorageError::from_io_error(e, "Failed to copy remote metadata"))?;
+    }
+
+    let mut snapshot = PartitionSnapshot {
+        topic: String::new(),
+        partition: 0,
+        linked_segments: 0,
+        active_bytes: 0,
+        next_offset: 0,
+    };
+    let offsets = get_segment_offsets(&partition_dir.to_path_buf())?;
+    let Some((&active_offset, closed)) = offsets.split_last() else {
+        return Ok(snapshot);
+    };
+
+    for &base_offset in closed {
+        for extension in ["log", "index", "timeindex"] {
+            let name = format!("{base_offset:020}.{extension}");
+            let source = partition_dir.join(&name);
+            if source.exists() {
+                link_or_copy(&source, &dest_dir.join(&name))?;
+            }
+        }
+        snapshot.linked_segments += 1;
+    }
+
+    let log_name = format!("{active_offset:020}.log");
+    let log_path = partition_dir.join(&log_name);
+    let mut next_offset = None;
+    let walk = walk_batches(&log_path, |batch| {
+        next_offset = Some(batch.header.last_offset() + 1);
+        true
+    })?;
+    if walk.valid_bytes < walk.log_bytes {
+        debug!(
+            "Cutting active segment {} at {} of {} bytes",
+            log_path.display(),
+            walk.valid_bytes,
+            walk.log_bytes
+        );
+    }
+    copy_prefix(&log_path, &dest_dir.join(&log_name), walk.valid_bytes)?;
+    snapshot.active_bytes = walk.valid_bytes;
+    snapshot.next_offset = next_offset.unwrap_or(active_offset);
+    Ok(snapshot)
+}
+
+fn copy_consumer_groups(data_dir: &Path, dest: &Path) -> Result<usize, StorageError> {
+    let mut copied = 0;
+    let offset_db = data_dir.join(SQLITE_OFFSETS_FILE);
+    if offset_db.is_file() {
+        copy_offset_database(&offset_db, &dest.join(SQLITE_OFFSETS_FILE))?;
+        copied += 1;
+    }
+
+    let source_dir = data_dir.join("consumer_groups");
+    if !source_dir.is_dir() {
+        return Ok(copied);
+    }
+    let dest_dir = dest.join("consumer_groups");
+    ensure_directory_exists(&dest_dir)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to create consumer group snapshot"))?;
+
+    for entry in std::fs::read_dir(&source_dir)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to read consumer groups"))?
+    {
+        let entry =
+            entry.map_err(|e| StorageError::from_io_error(e, "Failed to read consumer groups"))?;
+        if entry.path().is_file() {
+            std::fs::copy(entry.path(), dest_dir.join(entry.file_name()))
+                .map_err(|e| StorageError::from_io_error(e, "Failed to copy consumer group"))?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+/// Subdirectories of `dir` as (name, path), sorted by name. The remote segment cache is
+/// skipped since its contents are refetched on demand.
+fn list_dirs(dir: &Path) -> Result<Vec<(String, std::path::PathBuf)>, StorageError> {
+    let mut dirs = Vec::new();
+    for entry in std::fs::read_dir(dir)
+        .map_err(|e| StorageError::from_io_error(e, &format!("Failed to read {}", dir.display())))?
+    {
+        let entry = entry.map_err(|e| {
+            StorageError::from_io_error(e, &format!("Failed to read {}", dir.display()))
+        })?;
+        let path = entry.path();
+        if let (true, Some(name)) = (path.is_dir(), entry.file_name().to_str()) {
+            if name != REMOTE_CACHE_DIR {
+                dirs.push((name.to_string(), path));
+            }
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+fn link_or_copy(source: &Path, dest: &Path) -> Result<(), StorageError> {
+    if std::fs::hard_link(source, dest).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(source, dest).map(|_| ()).map_err(|e| {
+        StorageError::from_io_error(e, &format!("Failed to copy {}", source.display()))
+    })
+}
+
+fn copy_prefix(source: &Path, dest: &Path, len: u64) -> Result<(), StorageError> {
+    let source_file = File::open(source)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to open active segment"))?;
+    let mut dest_file = File::create(dest)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to create snapshot segment"))?;
+    std::io::copy(&mut source_file.take(len), &mut dest_file)
+        .map_err(|e| StorageError::from_io_error(e, "Failed to copy active segment"))?;
+    dest_file
+        .sync_all()
+        .map_err(|e| StorageError::from_io_error(e, "Failed to sync snapshot segment"))
+}
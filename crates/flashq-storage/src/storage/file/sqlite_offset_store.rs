@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use log::{info, warn};
+use parking_lot::Mutex;
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::error::{StorageError, StorageErrorSource};
+use crate::storage::{
+    ConsumerOffsetStore, PartitionId,
+    file::{FileConsumerOffsetStore, SyncMode},
+};
+
+/// Name of the offset database inside the data directory.
+pub const SQLITE_OFFSETS_FILE: &str = "consumer_offsets.db";
+
+/// Suffix given to `consumer_groups/*.json` files once their offsets are imported.
+const MIGRATED_SUFFIX: &str = "migrated";
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS consumer_groups (
+        group_id TEXT PRIMARY KEY
+    ) WITHOUT ROWID;
+    CREATE TABLE IF NOT EXISTS consumer_offsets (
+        group_id TEXT NOT NULL,
+        topic TEXT NOT NULL,
+        partition INTEGER NOT NULL,
+        committed_offset INTEGER NOT NULL,
+        PRIMARY KEY (group_id, topic, partition)
+    ) WITHOUT ROWID;
+";
+
+// The WHERE clause makes the upsert a no-op for stale offsets, so the monotonic check and
+// the write are one statement and no lock is needed around them.
+const UPSERT_OFFSET: &str = "
+    INSERT INTO consumer_offsets (group_id, topic, partition, committed_offset)
+    VALUES (?1, ?2, ?3, ?4)
+    ON CONFLICT (group_id, topic, partition) DO UPDATE
+    SET committed_offset = excluded.committed_offset
+    WHERE excluded.committed_offset >= consumer_offsets.committed_offset
+";
+
+const REGISTER_GROUP: &str = "INSERT OR IGNORE INTO consumer_groups (group_id) VALUES (?1)";
+
+/// The data directory's SQLite offset database, shared by the offset stores of every
+/// consumer group. Commits from any number of groups can go into one transaction.
+pub struct SqliteOffsetDatabase {
+    conn: Mutex<Connection>,
+    path: PathBuf,
+}
+
+impl std::fmt::Debug for SqliteOffsetDatabase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteOffsetDatabase")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl SqliteOffsetDatabase {
+    /// Open (or create) the offset database in `data_dir`. `SyncMode::Immediate` makes every
+    /// commit wait for fsync; other modes survive a process crash but may lose the last
+    /// commits on power loss.
+    #[tracing::instrument(level = "info", skip_all, fields(data_dir = %data_dir.as_ref().display()))]
+    pub fn open<P: AsRef<Path>>(data_dir: P, sync_mode: SyncMode) -> Result<Self, StorageError> {
+        let path = data_dir.as_ref().join(SQLITE_OFFSETS_FILE);
+        let conn = Connection::open(&path).map_err(sqlite_error("open offset database"))?;
+
+        let synchronous = match sync_mode {
+            SyncMode::Immediate => "FULL",
+            SyncMode::None | SyncMode::Periodic => "NORMAL",
+        };
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(sqlite_error("enable WAL journal"))?;
+        conn.pragma_update(None, "synchronous", synchronous)
+            .map_err(sqlite_error("set synchronous mode"))?;
+        conn.execute_batch(SCHEMA)
+            .map_err(sqlite_error("create offset tables"))?;
+
+        info!("Opened consumer offset database {}", path.display());
+        Ok(Self {
+            conn: Mutex::new(conn),
+            path,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Import the offsets of every `consumer_groups/*.json` file under `data_dir`, then
+    /// rename each file to `<group>.json.migrated` so it is not imported again. Offsets go
+    /// through the monotonic upsert, so running this twice never moves a group back.
+    /// Returns the number of groups imported.
+    #[tracing::instrument(level = "info", skip_all, fields(data_dir = %data_dir.as_ref().display()))]
+    pub fn migrate_json_groups<P: AsRef<Path>>(&self, data_dir: P) -> Result<usize, StorageError> {
+        let groups_dir = data_dir.as_ref().join("consumer_groups");
+        if !groups_dir.is_dir() {
+            return Ok(0);
+        }
+
+        let mut json_files: Vec<PathBuf> = std::fs::read_dir(&groups_dir)
+            .map_err(|e| StorageError::from_io_error(e, "Failed to read consumer groups"))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        json_files.sort();
+
+        let mut migrated = 0;
+        for json_path in json_files {
+            let Some(group_id) = json_path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let snapshots = FileConsumerOffsetStore::read_snapshot_file(&json_path)
+                .map_err(|e| StorageError::from_io_error(e, "Failed to read consumer group"))?;
+
+            let commits: Vec<_> = snapshots
+                .into_iter()
+                .map(|((topic, partition_id), offset)| (group_id, topic, partition_id, offset))
+                .collect();
+            self.register_group(group_id)?;
+            self.persist_batch(&commits)?;
+
+            let mut migrated_path = json_path.clone().into_os_string();
+            migrated_path.push(format!(".{MIGRATED_SUFFIX}"));
+            if let Err(e) = std::fs::rename(&json_path, &migrated_path) {
+                // The import is idempotent, so a leftover file is only re-read next start
+                warn!("Failed to rename migrated {}: {e}", json_path.display());
+            }
+            info!(
+                "Migrated {} offsets of consumer group {group_id} into {}",
+                commits.len(),
+                self.path.display()
+            );
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+
+    /// Consumer groups known to the database, sorted.
+    pub fn group_ids(&self) -> Result<Vec<String>, StorageError> {
+        let conn = self.conn.lock();
+        let mut stmt = conn
+            .prepare_cached("SELECT group_id FROM consumer_groups ORDER BY group_id")
+            .map_err(sqlite_error("list consumer groups"))?;
+        stmt.query_map([], |row| row.get(0))
+            .and_then(|rows| rows.collect())
+            .map_err(sqlite_error("list consumer groups"))
+    }
+
+    /// Checkpoint the write-ahead log into the database file, fsyncing both.
+    pub fn sync(&self) -> Result<(), StorageError> {
+        self.conn
+            .lock()
+            .execute_batch("PRAGMA wal_checkpoint(FULL)")
+            .map_err(sqlite_error("checkpoint offset database"))
+    }
+
+    fn register_group(&self, group_id: &str) -> Result<(), StorageError> {
+        self.conn
+            .lock()
+            .prepare_cached(REGISTER_GROUP)
+            .and_then(|mut stmt| stmt.execute(params![group_id]))
+            .map(|_| ())
+            .map_err(sqlite_error("register consumer group"))
+    }
+
+    /// Commit `(group, topic, partition, offset)` entries in one transaction. Each entry
+    /// follows the monotonic rule; the result says which ones were applied.
+    pub fn persist_batch<G: AsRef<str>>(
+        &self,
+        commits: &[(G, String, PartitionId, u64)],
+    ) -> Result<Vec<bool>, StorageError> {
+        let mut conn = self.conn.lock();
+        let txn = conn
+            .transaction()
+            .map_err(sqlite_error("begin offset commit"))?;
+        let mut applied = Vec::with_capacity(commits.len());
+        {
+            let mut stmt = txn
+                .prepare_cached(UPSERT_OFFSET)
+                .map_err(sqlite_error("prepare offset commit"))?;
+            for (group_id, topic, partition_id, offset) in commits {
+                let changed = stmt
+                    .execute(params![group_id.as_ref(), topic, partition_id.0, offset])
+                    .map_err(sqlite_error("persist offset snapshot"))?;
+                applied.push(changed > 0);
+            }
+        }
+        txn.commit().map_err(sqlite_error("commit offsets"))?;
+        Ok(applied)
+    }
+}
+
+/// `ConsumerOffsetStore` for one group in a [`SqliteOffsetDatabase`].
+pub struct SqliteConsumerOffsetStore {
+    db: Arc<SqliteOffsetDatabase>,
+    group_id: String,
+}
+
+impl SqliteConsumerOffsetStore {
+    /// Open the store for `group_id`, registering the group so it is recovered on restart
+    /// even before its first commit.
+    pub fn new(db: Arc<SqliteOffsetDatabase>, group_id: &str) -> Result<Self, StorageError> {
+        db.register_group(group_id)?;
+        Ok(Self {
+            db,
+            group_id: group_id.to_string(),
+        })
+    }
+}
+
+impl ConsumerOffsetStore for SqliteConsumerOffsetStore {
+    fn load_snapshot(&self, topic: &str, partition_id: PartitionId) -> Result<u64, StorageError> {
+        let conn = self.db.conn.lock();
+        let offset: Option<u64> = conn
+            .prepare_cached(
+                "SELECT committed_offset FROM consumer_offsets
+                 WHERE group_id = ?1 AND topic = ?2 AND partition = ?3",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_row(params![self.group_id, topic, partition_id.0], |row| {
+                    row.get(0)
+                })
+                .optional()
+            })
+            .map_err(sqlite_error("load offset snapshot"))?;
+        Ok(offset.unwrap_or(0))
+    }
+
+    fn persist_snapshot(
+        &self,
+        topic: String,
+        partition_id: PartitionId,
+        offset: u64,
+    ) -> Result<bool, StorageError> {
+        let applied =
+            self.db
+                .persist_batch(&[(self.group_id.as_str(), topic, partition_id, offset)])?;
+        Ok(applied[0])
+    }
+
+    fn persist_snapshots(
+        &self,
+        snapshots: Vec<(String, PartitionId, u64)>,
+    ) -> Result<Vec<bool>, StorageError> {
+        let commits: Vec<_> = snapshots
+            .into_iter()
+            .map(|(topic, partition_id, offset)| {
+                (self.group_id.as_str(), topic, partition_id, offset)
+            })
+            .collect();
+        self.db.persist_batch(&commits)
+    }
+
+    fn get_all_snapshots(&self) -> Result<HashMap<(String, PartitionId), u64>, StorageError> {
+        let conn = self.db.conn.lock();
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT topic, partition, committed_offset FROM consumer_offsets
+                 WHERE group_id = ?1",
+            )
+            .map_err(sqlite_error("load offset snapshots"))?;
+        stmt.query_map(params![self.group_id], |row| {
+            Ok(((row.get(0)?, PartitionId(row.get(1)?)), row.get(2)?))
+        })
+        .and_then(|rows| rows.collect())
+        .map_err(sqlite_error("load offset snapshots"))
+    }
+
+    fn group_id(&self) -> &str {
+        &self.group_id
+    }
+
+    fn sync(&self) -> Result<(), StorageError> {
+        self.db.sync()
+    }
+}
+
+/// Copy the offset database at `source` to `dest` with `VACUUM INTO`, which reads one
+/// consistent view even while another connection is committing.
+pub(crate) fn copy_offset_database(source: &Path, dest: &Path) -> Result<(), StorageError> {
+    let conn = Connection::open_with_flags(source, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(sqlite_error("open offset database for snapshot"))?;
+    conn.execute("VACUUM INTO ?1", params![dest.to_string_lossy()])
+        .map(|_| ())
+        .map_err(sqlite_error("copy offset database"))
+}
+
+fn sqlite_error(context: &'static str) -> impl Fn(rusqlite::Error) -> StorageError {
+    move |e| StorageError::WriteFailed {
+        context: context.to_string(),
+        source: Box::new(StorageErrorSource::Custom(e.to_string())),
+    }
+}
@@ -1,5 +1,10 @@
 use crate::error::StorageError;
+use crate::storage::file::fixed_index::{FixedWidthIndex, used_prefix_len};
 use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// On-disk width of a time index entry: [8B timestamp_ms][4B position].
+pub const TIME_INDEX_ENTRY_SIZE: usize = 12;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct TimeIndexEntry {
@@ -9,7 +14,7 @@ pub struct TimeIndexEntry {
 
 /// Sparse time index maintained per segment, sorted by `timestamp_ms`.
 pub struct SparseTimeIndex {
-    entries: Vec<TimeIndexEntry>,
+    slots: FixedWidthIndex<TIME_INDEX_ENTRY_SIZE>,
 }
 
 impl Default for SparseTimeIndex {
@@ -19,27 +24,76 @@ impl Default for SparseTimeIndex {
 }
 
 impl SparseTimeIndex {
+    /// A time index held only in memory.
     pub fn new() -> Self {
         Self {
-            entries: Vec::new(),
+            slots: FixedWidthIndex::in_memory(),
+        }
+    }
+
+    /// Open the time index file at `path`, pre-allocated to `max_index_bytes`.
+    pub fn open(path: &Path, max_index_bytes: u64) -> Result<Self, StorageError> {
+        let slots =
+            FixedWidthIndex::open(path, max_index_bytes, |_, slot| decode_slot(slot).0 != 0)?;
+        Ok(Self { slots })
+    }
+
+    /// Read the used entries of the time index file at `path` into memory without modifying it.
+    pub fn load(path: &Path) -> Result<Self, StorageError> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| StorageError::from_io_error(e, "Failed to read time index file"))?;
+        let mut index = Self::new();
+        let used = used_prefix_len(&bytes, |_, slot| decode_slot(slot).0 != 0);
+        for chunk in bytes.chunks_exact(TIME_INDEX_ENTRY_SIZE).take(used) {
+            let (timestamp_ms, position) = decode_slot(chunk.try_into().expect("exact chunk"));
+            index.add_entry(TimeIndexEntry {
+                timestamp_ms,
+                position,
+            });
+        }
+        Ok(index)
+    }
+
+    /// Entries in timestamp order.
+    pub fn entries(&self) -> impl Iterator<Item = TimeIndexEntry> + '_ {
+        (0..self.slots.len()).map(|i| self.entry(i))
+    }
+
+    fn entry(&self, i: usize) -> TimeIndexEntry {
+        let (timestamp_ms, position) = decode_slot(self.slots.slot(i));
+        TimeIndexEntry {
+            timestamp_ms,
+            position,
         }
     }
 
     /// Insert a new entry while maintaining sorted order by timestamp.
     /// For duplicate timestamps, keep the earliest position (min position).
+    /// Entries that do not fit once the index is full are dropped.
     pub fn add_entry(&mut self, entry: TimeIndexEntry) {
-        match self
-            .entries
-            .binary_search_by_key(&entry.timestamp_ms, |e| e.timestamp_ms)
-        {
-            Ok(idx) => {
+        let at = self
+            .slots
+            .upper_bound(&entry.timestamp_ms, |s| decode_slot(s).0);
+        if at > 0 {
+            let existing = self.entry(at - 1);
+            if existing.timestamp_ms == entry.timestamp_ms {
                 // Duplicate timestamp: keep the earliest position
-                if entry.position < self.entries[idx].position {
-                    self.entries[idx].position = entry.position;
+                if entry.position < existing.position {
+                    self.slots.set(at - 1, encode_slot(&entry));
                 }
+                return;
             }
-            Err(pos) => self.entries.insert(pos, entry),
         }
+        if !self.slots.insert(at, encode_slot(&entry)) {
+            log::debug!(
+                "Time index full; skipping entry for timestamp {}",
+                entry.timestamp_ms
+            );
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.slots.is_full()
     }
 
     /// Find a starting file position for the given timestamp (in ms).
@@ -48,23 +102,47 @@ impl SparseTimeIndex {
     /// - Some(position) of the exact timestamp entry if found
     /// - Some(position) of the closest preceding entry otherwise
     pub fn find_position_for_timestamp(&self, ts_ms: u64) -> Option<u32> {
-        if self.entries.is_empty() {
-            return Some(0);
+        let at = self.slots.upper_bound(&ts_ms, |s| decode_slot(s).0);
+        if at == 0 {
+            Some(0)
+        } else {
+            Some(self.entry(at - 1).position)
         }
+    }
 
-        match self
-            .entries
-            .binary_search_by_key(&ts_ms, |entry| entry.timestamp_ms)
-        {
-            Ok(idx) => Some(self.entries[idx].position),
-            Err(idx) => {
-                if idx == 0 {
-                    Some(0)
-                } else {
-                    Some(self.entries[idx - 1].position)
-                }
-            }
+    /// Cheap consistency check run when a segment is opened: timestamps must be ordered and
+    /// positions must fall inside the log.
+    pub fn sanity_check(&self, log_len: u64) -> Result<(), StorageError> {
+        let Some(last) = self.last_entry() else {
+            return Ok(());
+        };
+        let first = self.entry(0);
+        if last.timestamp_ms < first.timestamp_ms {
+            return Err(StorageError::DataCorruption {
+                context: "time index sanity check".to_string(),
+                details: format!("entries out of order: first {first:?}, last {last:?}"),
+            });
         }
+        if first.position as u64 >= log_len || last.position as u64 >= log_len {
+            return Err(StorageError::DataCorruption {
+                context: "time index sanity check".to_string(),
+                details: format!("entry positions exceed log length {log_len}"),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+    }
+
+    pub fn flush(&self) -> Result<(), StorageError> {
+        self.slots.flush()
+    }
+
+    /// Shrink the file to the used entries once its segment stops taking writes.
+    pub fn trim_to_valid_size(&mut self) -> Result<(), StorageError> {
+        self.slots.trim_to_valid_size()
     }
 
     /// Serialize and write a single entry to file (big-endian):
@@ -87,7 +165,7 @@ impl SparseTimeIndex {
         Ok(())
     }
 
-    /// Read the time index from file and populate the sparse index.
+    /// Read time index entries from a stream and populate the sparse index.
     /// An optional `max_entries` bound can be provided to prevent excessive allocations
     /// in case of a corrupted or unexpectedly large file. If the bound is exceeded,
     /// returns a DataCorruption error.
@@ -96,12 +174,12 @@ impl SparseTimeIndex {
         reader: &mut BufReader<R>,
         max_entries: Option<usize>,
     ) -> Result<(), StorageError> {
-        self.entries.clear();
+        self.clear();
 
         let max_allowed = max_entries.unwrap_or(usize::MAX);
         let mut count: usize = 0;
 
-        let mut buffer = [0u8; 12]; // 8 bytes timestamp_ms + 4 bytes position
+        let mut buffer = [0u8; TIME_INDEX_ENTRY_SIZE];
         while reader.read_exact(&mut buffer).is_ok() {
             if count >= max_allowed {
                 return Err(StorageError::DataCorruption {
@@ -110,13 +188,9 @@ impl SparseTimeIndex {
                 });
             }
 
-            let ts_ms = u64::from_be_bytes([
-                buffer[0], buffer[1], buffer[2], buffer[3], buffer[4], buffer[5], buffer[6],
-                buffer[7],
-            ]);
-            let position = u32::from_be_bytes([buffer[8], buffer[9], buffer[10], buffer[11]]);
-            self.entries.push(TimeIndexEntry {
-                timestamp_ms: ts_ms,
+            let (timestamp_ms, position) = decode_slot(&buffer);
+            self.add_entry(TimeIndexEntry {
+                timestamp_ms,
                 position,
             });
             count += 1;
@@ -125,20 +199,36 @@ impl SparseTimeIndex {
         Ok(())
     }
 
-    pub fn last_entry(&self) -> Option<&TimeIndexEntry> {
-        self.entries.last()
+    pub fn last_entry(&self) -> Option<TimeIndexEntry> {
+        self.slots.len().checked_sub(1).map(|i| self.entry(i))
     }
 
     pub fn entry_count(&self) -> usize {
-        self.entries.len()
+        self.slots.len()
     }
 
     #[cfg(test)]
     fn len(&self) -> usize {
-        self.entries.len()
+        self.slots.len()
     }
 }
 
+fn encode_slot(entry: &TimeIndexEntry) -> [u8; TIME_INDEX_ENTRY_SIZE] {
+    let mut slot = [0u8; TIME_INDEX_ENTRY_SIZE];
+    slot[..8].copy_from_slice(&entry.timestamp_ms.to_be_bytes());
+    slot[8..].copy_from_slice(&entry.position.to_be_bytes());
+    slot
+}
+
+fn decode_slot(slot: &[u8; TIME_INDEX_ENTRY_SIZE]) -> (u64, u32) {
+    let mut ts = [0u8; 8];
+    ts.copy_from_slice(&slot[..8]);
+    (
+        u64::from_be_bytes(ts),
+        u32::from_be_bytes([slot[8], slot[9], slot[10], slot[11]]),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,7 +265,7 @@ mod tests {
         assert_eq!(idx.len(), 3);
         assert_eq!(
             idx.last_entry(),
-            Some(&TimeIndexEntry {
+            Some(TimeIndexEntry {
                 timestamp_ms: 20,
                 position: 200
             })
@@ -236,7 +326,7 @@ mod tests {
         idx2.read_from_file(&mut reader, None).unwrap();
 
         assert_eq!(idx2.len(), 2);
-        assert_eq!(idx2.last_entry(), Some(&entry2));
+        assert_eq!(idx2.last_entry(), Some(entry2));
         assert_eq!(idx2.find_position_for_timestamp(1500), Some(10)); // previous entry position
         assert_eq!(idx2.find_position_for_timestamp(2000), Some(20));
     }
@@ -1,7 +1,11 @@
 use crate::error::StorageError;
+use crate::storage::ReadBounds;
+use crate::storage::compression::CompressionCodec;
 use crate::storage::file::common::ensure_directory_exists;
 use crate::storage::file::{IndexingConfig, SegmentManager, SyncMode};
-use crate::storage::r#trait::{PartitionId, TopicLog};
+use crate::storage::remote::TieredStorageConfig;
+use crate::storage::topic_config::TopicConfig;
+use crate::storage::r#trait::{PartitionId, SegmentStats, TopicLog, validate_replayed_offsets};
 use crate::{Record, RecordWithOffset};
 use log::{debug, error, info, trace, warn};
 use std::collections::HashMap;
@@ -16,6 +20,8 @@ pub struct FileTopicLog {
     segment_size_bytes: u64,
     batch_bytes: usize,
     indexing_config: IndexingConfig,
+    topic_config: TopicConfig,
+    tiered_storage: Option<TieredStorageConfig>,
 }
 
 pub struct PartitionData {
@@ -77,12 +83,69 @@ impl FileTopicLog {
             segment_size_bytes,
             batch_bytes,
             indexing_config,
+            topic_config: TopicConfig::default(),
+            tiered_storage: None,
         };
 
         log.recover_all_partitions()?;
         Ok(log)
     }
 
+    /// Apply per-topic settings such as the default batch compression codec.
+    pub fn with_topic_config(mut self, topic_config: TopicConfig) -> Self {
+        for partition_data in self.partitions.values_mut() {
+            partition_data.segment_manager.set_roll_limits(
+                topic_config.segment_ms,
+                topic_config.segment_index_max_entries,
+            );
+        }
+        self.topic_config = topic_config;
+        self
+    }
+
+    /// Offload closed segments to a remote store, attaching it to every recovered partition
+    /// and to partitions created later.
+    pub fn with_tiered_storage(
+        mut self,
+        tiered_storage: TieredStorageConfig,
+    ) -> Result<Self, StorageError> {
+        let partition_ids: Vec<PartitionId> = self.partitions.keys().copied().collect();
+        for partition_id in partition_ids {
+            let key_prefix = self.remote_key_prefix(partition_id);
+            let partition_data = self.partitions.get_mut(&partition_id).unwrap();
+            partition_data
+                .segment_manager
+                .enable_tiered_storage(tiered_storage.clone(), key_prefix)?;
+            (partition_data.next_offset, partition_data.record_count) =
+                Self::calculate_metadata_from_segments(&partition_data.segment_manager);
+        }
+        self.tiered_storage = Some(tiered_storage);
+        Ok(self)
+    }
+
+    /// Offload every partition's closed segments that have aged past the local retention
+    /// window. Returns the number of segments moved to the remote store.
+    pub fn offload_expired_segments(&mut self) -> Result<usize, StorageError> {
+        let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+        let mut offloaded = 0;
+        for partition_data in self.partitions.values_mut() {
+            offloaded += partition_data
+                .segment_manager
+                .offload_expired_segments(now_ms)?;
+        }
+        Ok(offloaded)
+    }
+
+    /// Lowest offset of `partition_id` still held on local disk.
+    pub fn local_log_start_offset(&self, partition_id: PartitionId) -> Option<u64> {
+        self.find_partition(partition_id)
+            .and_then(|p| p.segment_manager.local_log_start_offset())
+    }
+
+    fn remote_key_prefix(&self, partition_id: PartitionId) -> String {
+        format!("{}/{}", self.topic, partition_id)
+    }
+
     fn setup_topic_directory<P: AsRef<Path>>(
         data_dir: P,
         topic: &str,
@@ -192,7 +255,7 @@ impl FileTopicLog {
             std::io::Error::other(format!("Partition recovery failed: {e}"))
         })?;
 
-        let (next_offset, record_count) = self.calculate_metadata_from_segments(&segment_manager);
+        let (next_offset, record_count) = Self::calculate_metadata_from_segments(&segment_manager);
 
         info!(
             "Recovered partition {}: next_offset={}, record_count={}",
@@ -212,18 +275,28 @@ impl FileTopicLog {
     }
 
     fn create_segment_manager(&self, partition_dir: &std::path::Path) -> SegmentManager {
-        SegmentManager::new(
+        let mut segment_manager = SegmentManager::new(
             partition_dir.to_path_buf(),
             self.segment_size_bytes,
             self.sync_mode,
             self.indexing_config.clone(),
-        )
+        );
+        segment_manager.set_roll_limits(
+            self.topic_config.segment_ms,
+            self.topic_config.segment_index_max_entries,
+        );
+        segment_manager
     }
 
-    fn calculate_metadata_from_segments(&self, segment_manager: &SegmentManager) -> (u64, usize) {
+    fn calculate_metadata_from_segments(segment_manager: &SegmentManager) -> (u64, usize) {
         let mut total_records = 0;
         let mut highest_offset = 0;
 
+        for remote_segment in segment_manager.remote_segments() {
+            total_records += remote_segment.record_count();
+            highest_offset = highest_offset.max(remote_segment.max_offset);
+        }
+
         for segment in segment_manager.all_segments() {
             let segment_record_count = segment.record_count();
             total_records += segment_record_count;
@@ -280,6 +353,12 @@ impl FileTopicLog {
                 ),
             )
         })?;
+        if let Some(tiered_storage) = &self.tiered_storage {
+            segment_manager.enable_tiered_storage(
+                tiered_storage.clone(),
+                self.remote_key_prefix(partition_id),
+            )?;
+        }
 
         let (next_offset, total_records) = self.calculate_partition_state(&mut segment_manager)?;
 
@@ -313,7 +392,7 @@ impl FileTopicLog {
         &self,
         segment_manager: &mut SegmentManager,
     ) -> Result<(u64, usize), StorageError> {
-        let (next_offset, total_records) = self.calculate_metadata_from_segments(segment_manager);
+        let (next_offset, total_records) = Self::calculate_metadata_from_segments(segment_manager);
 
         if segment_manager.active_segment_mut().is_none() {
             segment_manager.roll_to_new_segment(next_offset)?;
@@ -331,6 +410,8 @@ impl FileTopicLog {
         &mut self,
         partition_id: PartitionId,
         records: &[Record],
+        timestamps: &[String],
+        compression: CompressionCodec,
     ) -> Result<u64, StorageError> {
         let partition_data = self.get_or_create_partition(partition_id)?;
 
@@ -359,7 +440,8 @@ impl FileTopicLog {
             })?;
 
         let start_offset = partition_data.next_offset;
-        let last_offset = active_segment.append_records_bulk(records, start_offset)?;
+        let last_offset =
+            active_segment.append_stamped_batch(records, timestamps, start_offset, compression)?;
         let appended_count = (last_offset - start_offset + 1) as usize;
 
         partition_data.next_offset += appended_count as u64;
@@ -372,6 +454,110 @@ impl FileTopicLog {
 
         Ok(last_offset)
     }
+
+    /// Split `records` into batches of roughly `batch_bytes` and write each as one record batch.
+    /// Timestamps are resolved for the whole input first, so a rejected one writes nothing.
+    fn write_records_in_batches(
+        &mut self,
+        partition_id: PartitionId,
+        mut records: Vec<Record>,
+        compression: CompressionCodec,
+    ) -> Result<u64, StorageError> {
+        if records.is_empty() {
+            return Ok(self.get_or_create_partition(partition_id)?.next_offset);
+        }
+        let timestamps = self.topic_config.resolve_timestamps(&mut records)?;
+
+        let mut last_offset = 0;
+        let mut start = 0;
+
+        for i in 0..records.len() {
+            // Check if we should flush this batch
+            let should_flush = {
+                if i <= start {
+                    false
+                } else {
+                    let accumulated_size: usize = records[start..i]
+                        .iter()
+                        .map(crate::storage::batching_heuristics::estimate_record_size)
+                        .sum();
+
+                    let next_record_size =
+                        crate::storage::batching_heuristics::estimate_record_size(&records[i]);
+
+                    accumulated_size > 0 && accumulated_size + next_record_size > self.batch_bytes
+                }
+            };
+
+            if should_flush {
+                last_offset = self.write_batch_to_partition(
+                    partition_id,
+                    &records[start..i],
+                    &timestamps[start..i],
+                    compression,
+                )?;
+                start = i;
+            }
+        }
+
+        if start < records.len() {
+            last_offset = self.write_batch_to_partition(
+                partition_id,
+                &records[start..],
+                &timestamps[start..],
+                compression,
+            )?;
+        }
+
+        Ok(last_offset)
+    }
+
+    /// Write replayed records as one batch per run of consecutive offsets sharing a
+    /// timestamp, so every batch keeps the time it was originally stamped with.
+    fn write_replayed_records(
+        &mut self,
+        partition_id: PartitionId,
+        records: &[RecordWithOffset],
+        compression: CompressionCodec,
+    ) -> Result<u64, StorageError> {
+        let partition_data = self.get_or_create_partition(partition_id)?;
+        validate_replayed_offsets(partition_data.next_offset, records)?;
+
+        let mut last_offset = partition_data.next_offset;
+        for run in records.chunk_by(|a, b| b.offset == a.offset + 1 && b.timestamp == a.timestamp) {
+            let start_offset = run[0].offset;
+            let segment_manager = &mut partition_data.segment_manager;
+            if start_offset > partition_data.next_offset {
+                debug!(
+                    "Skipping partition {} from offset {} to {start_offset}",
+                    partition_id.0, partition_data.next_offset
+                );
+                segment_manager.skip_to_offset(start_offset)?;
+            } else if segment_manager.should_roll_segment() {
+                info!("Rolling to new segment for partition {}", partition_id.0);
+                segment_manager.roll_to_new_segment(start_offset)?;
+            }
+
+            let active_segment = segment_manager.active_segment_mut().ok_or_else(|| {
+                StorageError::from_io_error(
+                    std::io::Error::other("No active segment"),
+                    "No active segment available for replayed records",
+                )
+            })?;
+
+            let batch: Vec<Record> = run.iter().map(|r| r.record.clone()).collect();
+            last_offset = active_segment.append_batch_at(
+                &batch,
+                start_offset,
+                &run[0].timestamp,
+                compression,
+            )?;
+            partition_data.next_offset = last_offset + 1;
+            partition_data.record_count += run.len();
+        }
+
+        Ok(last_offset)
+    }
 }
 
 impl TopicLog for FileTopicLog {
@@ -379,8 +565,12 @@ impl TopicLog for FileTopicLog {
     fn append_partition(
         &mut self,
         partition_id: PartitionId,
-        record: Record,
+        mut record: Record,
     ) -> Result<u64, StorageError> {
+        let timestamp = self
+            .topic_config
+            .resolve_timestamp(&mut record, chrono::Utc::now())?;
+
         // Get the initial state we need
         let next_offset = {
             let partition_data = self.get_or_create_partition(partition_id)?;
@@ -392,6 +582,8 @@ impl TopicLog for FileTopicLog {
             partition_id.0, next_offset
         );
 
+        let compression = self.topic_config.compression;
+
         // Now work with a fresh mutable borrow
         let partition_data = self.get_or_create_partition(partition_id)?;
 
@@ -412,7 +604,12 @@ impl TopicLog for FileTopicLog {
                 )
             })?;
 
-        active_segment.append_record(&record, next_offset)?;
+        active_segment.append_batch_at(
+            std::slice::from_ref(&record),
+            next_offset,
+            &timestamp,
+            compression,
+        )?;
         partition_data.next_offset += 1;
         partition_data.record_count += 1;
 
@@ -430,72 +627,66 @@ impl TopicLog for FileTopicLog {
         partition_id: PartitionId,
         records: Vec<Record>,
     ) -> Result<u64, StorageError> {
-        if records.is_empty() {
-            return Ok(self.get_or_create_partition(partition_id)?.next_offset);
-        }
-
-        let mut last_offset = 0;
-        let mut start = 0;
-
-        for i in 0..records.len() {
-            // Check if we should flush this batch
-            let should_flush = {
-                if i <= start {
-                    false
-                } else {
-                    let accumulated_size: usize = records[start..i]
-                        .iter()
-                        .map(crate::storage::batching_heuristics::estimate_record_size)
-                        .sum();
-
-                    let next_record_size =
-                        crate::storage::batching_heuristics::estimate_record_size(&records[i]);
-
-                    accumulated_size > 0 && accumulated_size + next_record_size > self.batch_bytes
-                }
-            };
-
-            if should_flush {
-                last_offset = self.write_batch_to_partition(partition_id, &records[start..i])?;
-                start = i;
-            }
-        }
+        let compression = self.topic_config.compression;
+        self.write_records_in_batches(partition_id, records, compression)
+    }
 
-        if start < records.len() {
-            last_offset = self.write_batch_to_partition(partition_id, &records[start..])?;
-        }
+    #[tracing::instrument(level = "debug", skip_all, fields(topic = %self.topic, partition = %partition_id.0, count = records.len(), %compression), name = "append_batch")]
+    fn append_batch_partition_with_compression(
+        &mut self,
+        partition_id: PartitionId,
+        records: Vec<Record>,
+        compression: CompressionCodec,
+    ) -> Result<u64, StorageError> {
+        self.write_records_in_batches(partition_id, records, compression)
+    }
 
-        Ok(last_offset)
+    #[tracing::instrument(level = "debug", skip_all, fields(topic = %self.topic, partition = %partition_id.0, count = records.len()), name = "append_with_offsets")]
+    fn append_batch_with_offsets_partition(
+        &mut self,
+        partition_id: PartitionId,
+        records: Vec<RecordWithOffset>,
+    ) -> Result<u64, StorageError> {
+        let compression = self.topic_config.compression;
+        self.write_replayed_records(partition_id, &records, compression)
     }
 
-    fn read_from_partition(
+    fn read_from_partition_bounded(
         &self,
         partition_id: PartitionId,
         from_offset: u64,
+        bounds: ReadBounds,
         max_bytes: Option<usize>,
     ) -> Result<Vec<RecordWithOffset>, StorageError> {
         match self.find_partition(partition_id) {
             Some(partition_data) => partition_data
                 .segment_manager
-                .read_records_streaming(from_offset, max_bytes),
+                .read_records_streaming_bounded(from_offset, bounds, max_bytes),
             None => Ok(Vec::new()),
         }
     }
 
-    fn read_from_partition_timestamp(
+    fn read_from_partition_timestamp_bounded(
         &self,
         partition_id: PartitionId,
         ts_rfc3339: &str,
+        bounds: ReadBounds,
         max_bytes: Option<usize>,
     ) -> Result<Vec<RecordWithOffset>, StorageError> {
         match self.find_partition(partition_id) {
             Some(partition_data) => partition_data
                 .segment_manager
-                .read_records_from_timestamp(ts_rfc3339, max_bytes),
+                .read_records_from_timestamp_bounded(ts_rfc3339, bounds, max_bytes),
             None => Ok(Vec::new()),
         }
     }
 
+    fn partition_ids(&self) -> Vec<PartitionId> {
+        let mut ids: Vec<PartitionId> = self.partitions.keys().copied().collect();
+        ids.sort_by_key(|id| id.0);
+        ids
+    }
+
     fn partition_len(&self, partition_id: PartitionId) -> usize {
         self.find_partition(partition_id)
             .map(|p| p.record_count)
@@ -513,4 +704,49 @@ impl TopicLog for FileTopicLog {
             .map(|p| p.next_offset)
             .unwrap_or(0)
     }
+
+    fn partition_start_offset(&self, partition_id: PartitionId) -> Result<u64, StorageError> {
+        Ok(self
+            .find_partition(partition_id)
+            .map(|p| {
+                p.segment_manager
+                    .log_start_offset()
+                    .map_or(p.next_offset, |start| start.min(p.next_offset))
+            })
+            .unwrap_or(0))
+    }
+
+    fn partition_offset_for_timestamp(
+        &self,
+        partition_id: PartitionId,
+        ts_rfc3339: &str,
+    ) -> Result<Option<u64>, StorageError> {
+        match self.find_partition(partition_id) {
+            Some(partition) => partition.segment_manager.offset_for_timestamp(ts_rfc3339),
+            None => Ok(None),
+        }
+    }
+
+    fn segment_stats(&self) -> Result<SegmentStats, StorageError> {
+        let mut stats = SegmentStats::default();
+        for partition in self.partitions.values() {
+            for segment in partition.segment_manager.all_segments() {
+                stats.local_segments += 1;
+                stats.local_bytes += segment.size_bytes()?;
+            }
+            for segment in partition.segment_manager.remote_segments() {
+                stats.remote_segments += 1;
+                stats.remote_bytes += segment.size_bytes;
+            }
+        }
+        Ok(stats)
+    }
+
+    fn sync(&mut self) -> Result<(), StorageError> {
+        self.sync_all_partitions()
+    }
+
+    fn offload_expired_segments(&mut self) -> Result<usize, StorageError> {
+        FileTopicLog::offload_expired_segments(self)
+    }
 }
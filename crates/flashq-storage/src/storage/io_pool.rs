@@ -0,0 +1,187 @@
+use crate::error::StorageError;
+use futures_channel::oneshot;
+use log::{debug, error};
+use parking_lot::Mutex;
+use std::future::Future;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::task::{Context, Poll};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Executes blocking storage work so async callers never touch the disk on their own threads.
+///
+/// The `Threads` flavour hands jobs to a fixed set of dedicated I/O threads over a channel and
+/// resolves an [`IoTask`] when the job completes. The `Inline` flavour runs jobs on the caller's
+/// thread; it suits backends that never block, such as in-memory storage.
+#[derive(Clone)]
+pub struct IoPool {
+    inner: Arc<IoPoolInner>,
+}
+
+enum IoPoolInner {
+    Inline,
+    Threads {
+        sender: Mutex<mpsc::Sender<Job>>,
+        threads: usize,
+    },
+}
+
+impl std::fmt::Debug for IoPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.inner.as_ref() {
+            IoPoolInner::Inline => f.write_str("IoPool::Inline"),
+            IoPoolInner::Threads { threads, .. } => write!(f, "IoPool::Threads({threads})"),
+        }
+    }
+}
+
+impl IoPool {
+    /// A pool that runs every job immediately on the calling thread.
+    pub fn inline() -> Self {
+        Self {
+            inner: Arc::new(IoPoolInner::Inline),
+        }
+    }
+
+    /// Spawn `threads` dedicated I/O worker threads (at least one).
+    pub fn with_threads(threads: usize) -> Result<Self, StorageError> {
+        let threads = threads.max(1);
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for id in 0..threads {
+            let receiver = receiver.clone();
+            std::thread::Builder::new()
+                .name(format!("flashq-io-{id}"))
+                .spawn(move || io_worker_loop(receiver))
+                .map_err(|e| StorageError::from_io_error(e, "Failed to spawn I/O worker thread"))?;
+        }
+        debug!("Started I/O pool with {threads} worker threads");
+
+        Ok(Self {
+            inner: Arc::new(IoPoolInner::Threads {
+                sender: Mutex::new(sender),
+                threads,
+            }),
+        })
+    }
+
+    /// Default worker count: one per available core, capped to keep file handle churn modest.
+    pub fn default_threads() -> usize {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4)
+            .clamp(2, 16)
+    }
+
+    pub fn is_inline(&self) -> bool {
+        matches!(self.inner.as_ref(), IoPoolInner::Inline)
+    }
+
+    /// Run `job` on the pool and return a future resolving to its result.
+    pub fn run<F, T>(&self, job: F) -> IoTask<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        match self.inner.as_ref() {
+            IoPoolInner::Inline => IoTask {
+                state: IoTaskState::Ready(Some(job())),
+            },
+            IoPoolInner::Threads { sender, .. } => {
+                let (tx, rx) = oneshot::channel();
+                let wrapped: Job = Box::new(move || {
+                    // A dropped receiver means the caller gave up; the result is discarded.
+                    let _ = tx.send(job());
+                });
+                if sender.lock().send(wrapped).is_err() {
+                    error!("I/O pool channel closed; rejecting job");
+                }
+                IoTask {
+                    state: IoTaskState::Pending(rx),
+                }
+            }
+        }
+    }
+}
+
+fn io_worker_loop(receiver: Arc<Mutex<mpsc::Receiver<Job>>>) {
+    loop {
+        let job = match receiver.lock().recv() {
+            Ok(job) => job,
+            // All senders dropped: the pool is gone
+            Err(_) => break,
+        };
+        if catch_unwind(AssertUnwindSafe(job)).is_err() {
+            error!("I/O job panicked; worker continues");
+        }
+    }
+}
+
+/// Future returned by [`IoPool::run`].
+///
+/// Resolves to `StorageError::Unavailable` if the job panicked or the pool shut down first.
+pub struct IoTask<T> {
+    state: IoTaskState<T>,
+}
+
+enum IoTaskState<T> {
+    Ready(Option<T>),
+    Pending(oneshot::Receiver<T>),
+}
+
+// The result is only ever moved out, never pinned in place.
+impl<T> Unpin for IoTask<T> {}
+
+impl<T> Future for IoTask<T> {
+    type Output = Result<T, StorageError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match &mut self.get_mut().state {
+            IoTaskState::Ready(value) => {
+                Poll::Ready(value.take().ok_or_else(|| StorageError::Unavailable {
+                    context: "I/O task polled after completion".to_string(),
+                }))
+            }
+            IoTaskState::Pending(rx) => Pin::new(rx).poll(cx).map(|result| {
+                result.map_err(|_| StorageError::Unavailable {
+                    context: "I/O job did not complete (panicked or pool shut down)".to_string(),
+                })
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_inline_pool_runs_on_caller_thread() {
+        let pool = IoPool::inline();
+        let caller = std::thread::current().id();
+        let ran_on = pool.run(move || std::thread::current().id()).await.unwrap();
+        assert_eq!(ran_on, caller);
+    }
+
+    #[tokio::test]
+    async fn test_thread_pool_runs_off_caller_thread() {
+        let pool = IoPool::with_threads(2).unwrap();
+        let name = pool
+            .run(|| std::thread::current().name().map(str::to_string))
+            .await
+            .unwrap();
+        assert!(name.unwrap().starts_with("flashq-io-"));
+    }
+
+    #[tokio::test]
+    async fn test_panicking_job_reports_unavailable_and_pool_survives() {
+        let pool = IoPool::with_threads(1).unwrap();
+        let failed = pool.run(|| -> u32 { panic!("boom") }).await;
+        assert!(matches!(failed, Err(StorageError::Unavailable { .. })));
+        assert_eq!(pool.run(|| 7).await.unwrap(), 7);
+    }
+}
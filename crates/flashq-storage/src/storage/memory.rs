@@ -1,4 +1,5 @@
-use super::{ConsumerGroup, ConsumerOffsetStore, PartitionId, TopicLog};
+use super::r#trait::validate_replayed_offsets;
+use super::{ConsumerGroup, ConsumerOffsetStore, PartitionId, ReadBounds, TopicConfig, TopicLog};
 use crate::error::StorageError;
 use crate::{Record, RecordWithOffset};
 use parking_lot::RwLock;
@@ -9,6 +10,7 @@ use std::sync::Arc;
 pub struct InMemoryTopicLog {
     partitions: HashMap<PartitionId, PartitionData>,
     batch_bytes: usize,
+    topic_config: TopicConfig,
 }
 
 #[derive(Debug, Clone)]
@@ -22,6 +24,7 @@ impl InMemoryTopicLog {
         InMemoryTopicLog {
             partitions: HashMap::new(),
             batch_bytes: crate::storage::batching_heuristics::default_batch_bytes(),
+            topic_config: TopicConfig::default(),
         }
     }
 
@@ -29,9 +32,16 @@ impl InMemoryTopicLog {
         InMemoryTopicLog {
             partitions: HashMap::new(),
             batch_bytes,
+            topic_config: TopicConfig::default(),
         }
     }
 
+    /// Apply `topic_config`; only its timestamp settings matter in memory.
+    pub fn with_topic_config(mut self, topic_config: TopicConfig) -> Self {
+        self.topic_config = topic_config;
+        self
+    }
+
     fn get_or_create_partition(&mut self, partition_id: PartitionId) -> &mut PartitionData {
         self.partitions
             .entry(partition_id)
@@ -50,12 +60,18 @@ impl TopicLog for InMemoryTopicLog {
     fn append_partition(
         &mut self,
         partition_id: PartitionId,
-        record: Record,
+        mut record: Record,
     ) -> Result<u64, StorageError> {
+        let timestamp = self
+            .topic_config
+            .resolve_timestamp(&mut record, chrono::Utc::now())?;
         let partition_data = self.get_or_create_partition(partition_id);
         let current_offset = partition_data.next_offset;
-        let record_with_offset = RecordWithOffset::from_record(record, current_offset);
-        partition_data.records.push(record_with_offset);
+        partition_data.records.push(RecordWithOffset {
+            record,
+            offset: current_offset,
+            timestamp,
+        });
         partition_data.next_offset += 1;
         Ok(current_offset)
     }
@@ -63,12 +79,13 @@ impl TopicLog for InMemoryTopicLog {
     fn append_batch_partition(
         &mut self,
         partition_id: PartitionId,
-        records: Vec<Record>,
+        mut records: Vec<Record>,
     ) -> Result<u64, StorageError> {
         if records.is_empty() {
             let partition_data = self.get_or_create_partition(partition_id);
             return Ok(partition_data.next_offset);
         }
+        let timestamps = self.topic_config.resolve_timestamps(&mut records)?;
 
         let batch_bytes = self.batch_bytes;
         let partition_data = self.get_or_create_partition(partition_id);
@@ -92,13 +109,12 @@ impl TopicLog for InMemoryTopicLog {
                 break;
             }
             partition_data.records.reserve(batch_len);
-            let batch_ts = chrono::Utc::now().to_rfc3339();
             let mut offset = partition_data.next_offset;
-            for r in records[start..end].iter().cloned() {
+            for (r, timestamp) in records[start..end].iter().zip(&timestamps[start..end]) {
                 let rwo = RecordWithOffset {
-                    record: r,
+                    record: r.clone(),
                     offset,
-                    timestamp: batch_ts.clone(),
+                    timestamp: timestamp.clone(),
                 };
                 partition_data.records.push(rwo);
                 last = offset;
@@ -110,42 +126,63 @@ impl TopicLog for InMemoryTopicLog {
         Ok(last)
     }
 
-    fn read_from_partition(
+    fn append_batch_with_offsets_partition(
+        &mut self,
+        partition_id: PartitionId,
+        records: Vec<RecordWithOffset>,
+    ) -> Result<u64, StorageError> {
+        let partition_data = self.get_or_create_partition(partition_id);
+        validate_replayed_offsets(partition_data.next_offset, &records)?;
+
+        let Some(last) = records.last().map(|r| r.offset) else {
+            return Ok(partition_data.next_offset);
+        };
+        partition_data.records.extend(records);
+        partition_data.next_offset = last + 1;
+        Ok(last)
+    }
+
+    fn read_from_partition_bounded(
         &self,
         partition_id: PartitionId,
         from_offset: u64,
+        bounds: ReadBounds,
         max_bytes: Option<usize>,
     ) -> Result<Vec<RecordWithOffset>, StorageError> {
         match self.get_partition(partition_id) {
             Some(partition_data) => {
-                let start_index =
-                    from_offset
-                        .try_into()
-                        .map_err(|_| StorageError::DataCorruption {
-                            context: "memory storage".to_string(),
-                            details: format!(
-                                "offset {from_offset} too large to convert to array index"
-                            ),
-                        })?;
-
-                if start_index >= partition_data.records.len() {
+                // Replayed records may leave offset gaps, so search rather than index
+                let records = &partition_data.records;
+                let start_index = records.partition_point(|r| r.offset < from_offset);
+                let end_index = match bounds.to_offset {
+                    Some(to_offset) => records.partition_point(|r| r.offset < to_offset),
+                    None => records.len(),
+                };
+
+                if start_index >= end_index {
                     return Ok(Vec::new());
                 }
-                let slice = &partition_data.records[start_index..];
-                let limited = match max_bytes {
-                    Some(limit) => &slice[..limit.min(slice.len())],
-                    None => slice,
-                };
-                Ok(limited.to_vec())
+                let slice = &records[start_index..end_index];
+                let max = max_bytes.unwrap_or(usize::MAX);
+                if bounds.to_time_ms.is_none() {
+                    return Ok(slice[..max.min(slice.len())].to_vec());
+                }
+                Ok(slice
+                    .iter()
+                    .filter(|r| bounds.admits(r))
+                    .take(max)
+                    .cloned()
+                    .collect())
             }
             None => Ok(Vec::new()),
         }
     }
 
-    fn read_from_partition_timestamp(
+    fn read_from_partition_timestamp_bounded(
         &self,
         partition_id: PartitionId,
         ts_rfc3339: &str,
+        bounds: ReadBounds,
         max_bytes: Option<usize>,
     ) -> Result<Vec<RecordWithOffset>, StorageError> {
         match self.get_partition(partition_id) {
@@ -164,8 +201,11 @@ impl TopicLog for InMemoryTopicLog {
                 }
 
                 for rwo in &partition_data.records {
+                    if bounds.is_past_end(rwo.offset) {
+                        break;
+                    }
                     if let Ok(ts_rec) = chrono::DateTime::parse_from_rfc3339(&rwo.timestamp) {
-                        if ts_rec >= ts_target {
+                        if ts_rec >= ts_target && bounds.admits(rwo) {
                             out.push(rwo.clone());
                             if out.len() >= max {
                                 break;
@@ -180,6 +220,12 @@ impl TopicLog for InMemoryTopicLog {
         }
     }
 
+    fn partition_ids(&self) -> Vec<PartitionId> {
+        let mut ids: Vec<PartitionId> = self.partitions.keys().copied().collect();
+        ids.sort_by_key(|id| id.0);
+        ids
+    }
+
     fn partition_len(&self, partition_id: PartitionId) -> usize {
         self.get_partition(partition_id)
             .map(|p| p.records.len())
@@ -317,6 +363,37 @@ mod tests {
         assert_eq!(records[0].offset, offset1);
         assert_eq!(records[1].offset, offset2);
     }
+
+    #[test]
+    fn test_topic_log_replayed_offsets_leave_gaps() {
+        let mut log = InMemoryTopicLog::new();
+        let replayed = |offset: u64| RecordWithOffset {
+            record: Record::new(None, format!("msg{offset}"), None),
+            offset,
+            timestamp: "2024-01-01T00:00:00+00:00".to_string(),
+        };
+
+        let last = log
+            .append_batch_with_offsets_partition(PartitionId(0), vec![replayed(10), replayed(12)])
+            .unwrap();
+
+        assert_eq!(last, 12);
+        assert_eq!(log.next_offset(), 13);
+        assert_eq!(
+            log.append(Record::new(None, "live".into(), None)).unwrap(),
+            13
+        );
+        let records = log.get_records_from_offset(11, None).unwrap();
+        assert_eq!(
+            records.iter().map(|r| r.offset).collect::<Vec<_>>(),
+            vec![12, 13]
+        );
+        assert_eq!(records[0].timestamp, "2024-01-01T00:00:00+00:00");
+        assert!(
+            log.append_batch_with_offsets_partition(PartitionId(0), vec![replayed(5)])
+                .is_err()
+        );
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -363,19 +440,6 @@ impl InMemoryConsumerOffsetStore {
             .copied()
             .unwrap_or(0)
     }
-
-    fn should_persist_offset(
-        &self,
-        topic: &str,
-        partition_id: PartitionId,
-        new_offset: u64,
-    ) -> bool {
-        new_offset >= self.get_current_offset(topic, partition_id)
-    }
-
-    fn update_snapshot(&self, topic: String, partition_id: PartitionId, offset: u64) {
-        self.snapshots.write().insert((topic, partition_id), offset);
-    }
 }
 
 impl ConsumerOffsetStore for InMemoryConsumerOffsetStore {
@@ -389,12 +453,14 @@ impl ConsumerOffsetStore for InMemoryConsumerOffsetStore {
         partition_id: PartitionId,
         offset: u64,
     ) -> Result<bool, StorageError> {
-        if self.should_persist_offset(&topic, partition_id, offset) {
-            self.update_snapshot(topic, partition_id, offset);
-            Ok(true)
-        } else {
-            Ok(false)
+        // Compare and update under one lock so a concurrent lower commit cannot land last
+        let mut snapshots = self.snapshots.write();
+        let key = (topic, partition_id);
+        if snapshots.get(&key).is_some_and(|&current| offset < current) {
+            return Ok(false);
         }
+        snapshots.insert(key, offset);
+        Ok(true)
     }
 
     fn get_all_snapshots(&self) -> Result<HashMap<(String, PartitionId), u64>, StorageError> {
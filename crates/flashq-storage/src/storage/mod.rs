@@ -1,9 +1,27 @@
 pub mod backend;
 pub mod batching_heuristics;
+pub mod compression;
+pub mod factory;
 pub mod file;
+pub mod io_pool;
 pub mod memory;
+pub mod offsets_topic;
+pub mod read_bounds;
+pub mod remote;
+pub mod topic_config;
 pub mod r#trait;
 
-pub use backend::StorageBackend;
+pub use backend::{OffsetStorage, StorageBackend};
+pub use compression::CompressionCodec;
+pub use factory::StorageFactory;
+pub use io_pool::{IoPool, IoTask};
 pub use memory::{InMemoryConsumerGroup, InMemoryConsumerOffsetStore, InMemoryTopicLog};
-pub use r#trait::{ConsumerGroup, ConsumerOffsetStore, PartitionId, TopicLog};
+pub use offsets_topic::{
+    CONSUMER_OFFSETS_TOPIC, OffsetsTopic, TopicConsumerOffsetStore, is_internal_topic,
+};
+pub use read_bounds::ReadBounds;
+pub use remote::{FsRemoteSegmentStore, RemoteSegmentStore, TieredStorageConfig};
+pub use topic_config::{TimestampType, TopicConfig};
+pub use r#trait::{
+    ConsumerGroup, ConsumerOffsetStore, PartitionId, TopicLog, validate_replayed_offsets,
+};
@@ -0,0 +1,264 @@
+//! Consumer offsets kept as records of the internal `__consumer_offsets` topic.
+//!
+//! Every commit is appended to partition 0 of an ordinary `TopicLog`, so offsets get the same
+//! segments, sync modes and recovery as data. Each record is keyed by
+//! `{"group", "topic", "partition"}` with the offset as its value; a record without topic and
+//! partition registers a group that has not committed yet. Keys are stable, so only the newest
+//! record per key matters, which is what log compaction will rely on. Opening the store
+//! replays the log into an in-memory map that serves every read.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use log::{info, warn};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::Record;
+use crate::error::StorageError;
+use crate::storage::{ConsumerOffsetStore, PartitionId, TopicLog};
+
+/// Name of the internal topic holding committed offsets.
+pub const CONSUMER_OFFSETS_TOPIC: &str = "__consumer_offsets";
+
+/// Records are appended to, and replayed from, this partition only.
+const OFFSETS_PARTITION: PartitionId = PartitionId(0);
+
+const REPLAY_CHUNK: usize = 1000;
+
+/// Internal topics are named with a leading `__`; they are not listed or produced to by clients.
+pub fn is_internal_topic(topic: &str) -> bool {
+    topic.starts_with("__")
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OffsetKey {
+    group: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    topic: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    partition: Option<u32>,
+}
+
+type OffsetMap = HashMap<(String, String, PartitionId), u64>;
+
+#[derive(Debug, Default)]
+struct OffsetsState {
+    groups: BTreeSet<String>,
+    offsets: OffsetMap,
+}
+
+/// The `__consumer_offsets` log and the offsets replayed from it, shared by the offset stores
+/// of every consumer group.
+pub struct OffsetsTopic {
+    log: Arc<RwLock<dyn TopicLog + Send + Sync>>,
+    // Held for writing across the monotonic check and the append, so commits hit the log in
+    // the order they were accepted
+    state: RwLock<OffsetsState>,
+}
+
+impl std::fmt::Debug for OffsetsTopic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.read();
+        f.debug_struct("OffsetsTopic")
+            .field("groups", &state.groups.len())
+            .field("offsets", &state.offsets.len())
+            .finish()
+    }
+}
+
+impl OffsetsTopic {
+    /// Replay `log` and serve offsets from it. Records that do not decode are skipped with a
+    /// warning rather than failing startup.
+    #[tracing::instrument(level = "info", skip_all)]
+    pub fn open(log: Arc<RwLock<dyn TopicLog + Send + Sync>>) -> Result<Self, StorageError> {
+        let mut state = OffsetsState::default();
+        let mut next = 0;
+        let mut replayed = 0;
+        loop {
+            let records =
+                log.read()
+                    .read_from_partition(OFFSETS_PARTITION, next, Some(REPLAY_CHUNK))?;
+            let Some(last) = records.last() else {
+                break;
+            };
+            next = last.offset + 1;
+            replayed += records.len();
+            for record in records {
+                if let Err(reason) = apply(&mut state, &record.record) {
+                    warn!(
+                        "Skipping {CONSUMER_OFFSETS_TOPIC} record at offset {}: {reason}",
+                        record.offset
+                    );
+                }
+            }
+        }
+
+        info!(
+            "Replayed {replayed} {CONSUMER_OFFSETS_TOPIC} records: {} groups, {} offsets",
+            state.groups.len(),
+            state.offsets.len()
+        );
+        Ok(Self {
+            log,
+            state: RwLock::new(state),
+        })
+    }
+
+    /// Fsync the `__consumer_offsets` log.
+    pub fn sync(&self) -> Result<(), StorageError> {
+        self.log.write().sync()
+    }
+
+    /// Consumer groups registered in the topic, sorted.
+    pub fn group_ids(&self) -> Vec<String> {
+        self.state.read().groups.iter().cloned().collect()
+    }
+
+    fn register_group(&self, group_id: &str) -> Result<(), StorageError> {
+        let mut state = self.state.write();
+        if state.groups.contains(group_id) {
+            return Ok(());
+        }
+        let key = OffsetKey {
+            group: group_id.to_string(),
+            topic: None,
+            partition: None,
+        };
+        self.log
+            .write()
+            .append_partition(OFFSETS_PARTITION, encode(&key, String::new())?)?;
+        state.groups.insert(group_id.to_string());
+        Ok(())
+    }
+
+    /// Commit `(group, topic, partition, offset)` entries as one appended batch. Each entry
+    /// follows the monotonic rule; the result says which ones were applied.
+    pub fn persist_batch<G: AsRef<str>>(
+        &self,
+        commits: &[(G, String, PartitionId, u64)],
+    ) -> Result<Vec<bool>, StorageError> {
+        let mut state = self.state.write();
+        let mut accepted: OffsetMap = HashMap::new();
+        let mut records = Vec::new();
+        let mut applied = Vec::with_capacity(commits.len());
+        for (group_id, topic, partition_id, offset) in commits {
+            let slot = (group_id.as_ref().to_string(), topic.clone(), *partition_id);
+            let current = accepted.get(&slot).or_else(|| state.offsets.get(&slot));
+            if current.is_some_and(|current| offset < current) {
+                applied.push(false);
+                continue;
+            }
+            let key = OffsetKey {
+                group: slot.0.clone(),
+                topic: Some(topic.clone()),
+                partition: Some(partition_id.0),
+            };
+            records.push(encode(&key, offset.to_string())?);
+            accepted.insert(slot, *offset);
+            applied.push(true);
+        }
+
+        if !records.is_empty() {
+            self.log
+                .write()
+                .append_batch_partition(OFFSETS_PARTITION, records)?;
+        }
+        for ((group_id, topic, partition_id), offset) in accepted {
+            state.groups.insert(group_id.clone());
+            state
+                .offsets
+                .insert((group_id, topic, partition_id), offset);
+        }
+        Ok(applied)
+    }
+}
+
+fn encode(key: &OffsetKey, value: String) -> Result<Record, StorageError> {
+    let key = serde_json::to_string(key)
+        .map_err(|e| StorageError::from_serialization_error(e, "encode offset key"))?;
+    Ok(Record::new(Some(key), value, None))
+}
+
+fn apply(state: &mut OffsetsState, record: &Record) -> Result<(), String> {
+    let key = record.key.as_deref().ok_or("record has no key")?;
+    let key: OffsetKey = serde_json::from_str(key).map_err(|e| e.to_string())?;
+    state.groups.insert(key.group.clone());
+    if let (Some(topic), Some(partition)) = (key.topic, key.partition) {
+        let offset = record.value.parse::<u64>().map_err(|e| e.to_string())?;
+        // Appends were checked against the committed offset, so the newest record wins
+        state
+            .offsets
+            .insert((key.group, topic, PartitionId::new(partition)), offset);
+    }
+    Ok(())
+}
+
+/// `ConsumerOffsetStore` for one group in an [`OffsetsTopic`].
+pub struct TopicConsumerOffsetStore {
+    offsets: Arc<OffsetsTopic>,
+    group_id: String,
+}
+
+impl TopicConsumerOffsetStore {
+    /// Open the store for `group_id`, registering the group in the topic so it is recovered on
+    /// restart even before its first commit.
+    pub fn new(offsets: Arc<OffsetsTopic>, group_id: &str) -> Result<Self, StorageError> {
+        offsets.register_group(group_id)?;
+        Ok(Self {
+            offsets,
+            group_id: group_id.to_string(),
+        })
+    }
+}
+
+impl ConsumerOffsetStore for TopicConsumerOffsetStore {
+    fn load_snapshot(&self, topic: &str, partition_id: PartitionId) -> Result<u64, StorageError> {
+        let state = self.offsets.state.read();
+        let slot = (self.group_id.clone(), topic.to_string(), partition_id);
+        Ok(state.offsets.get(&slot).copied().unwrap_or(0))
+    }
+
+    fn persist_snapshot(
+        &self,
+        topic: String,
+        partition_id: PartitionId,
+        offset: u64,
+    ) -> Result<bool, StorageError> {
+        let applied =
+            self.offsets
+                .persist_batch(&[(self.group_id.as_str(), topic, partition_id, offset)])?;
+        Ok(applied[0])
+    }
+
+    fn persist_snapshots(
+        &self,
+        snapshots: Vec<(String, PartitionId, u64)>,
+    ) -> Result<Vec<bool>, StorageError> {
+        let commits: Vec<_> = snapshots
+            .into_iter()
+            .map(|(topic, partition_id, offset)| {
+                (self.group_id.as_str(), topic, partition_id, offset)
+            })
+            .collect();
+        self.offsets.persist_batch(&commits)
+    }
+
+    fn get_all_snapshots(&self) -> Result<HashMap<(String, PartitionId), u64>, StorageError> {
+        let state = self.offsets.state.read();
+        Ok(state
+            .offsets
+            .iter()
+            .filter(|((group_id, _, _), _)| *group_id == self.group_id)
+            .map(|((_, topic, partition_id), offset)| ((topic.clone(), *partition_id), *offset))
+            .collect())
+    }
+
+    fn group_id(&self) -> &str {
+        &self.group_id
+    }
+
+    fn sync(&self) -> Result<(), StorageError> {
+        self.offsets.sync()
+    }
+}
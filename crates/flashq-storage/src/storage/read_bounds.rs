@@ -0,0 +1,54 @@
+use crate::RecordWithOffset;
+use crate::error::StorageError;
+
+/// Upper bounds on a read. Both are exclusive: records at or past `to_offset`, or stamped at
+/// or after `to_time_ms`, are not returned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReadBounds {
+    pub to_offset: Option<u64>,
+    /// Milliseconds since the epoch.
+    pub to_time_ms: Option<u64>,
+}
+
+impl ReadBounds {
+    pub fn with_to_offset(mut self, to_offset: u64) -> Self {
+        self.to_offset = Some(to_offset);
+        self
+    }
+
+    pub fn with_to_time_ms(mut self, to_time_ms: u64) -> Self {
+        self.to_time_ms = Some(to_time_ms);
+        self
+    }
+
+    /// Bound the read at an RFC3339 time; pre-epoch times bound at the epoch.
+    pub fn with_to_time(self, to_time_rfc3339: &str) -> Result<Self, StorageError> {
+        let to_time = chrono::DateTime::parse_from_rfc3339(to_time_rfc3339).map_err(|e| {
+            StorageError::DataCorruption {
+                context: "parse to_time".to_string(),
+                details: e.to_string(),
+            }
+        })?;
+        Ok(self.with_to_time_ms(to_time.timestamp_millis().max(0) as u64))
+    }
+
+    /// Whether `offset` and every offset after it lie past `to_offset`.
+    pub fn is_past_end(&self, offset: u64) -> bool {
+        self.to_offset.is_some_and(|to_offset| offset >= to_offset)
+    }
+
+    /// Whether a record stamped at `ts_ms` is before `to_time_ms`.
+    pub fn admits_time(&self, ts_ms: u64) -> bool {
+        self.to_time_ms.is_none_or(|to_time_ms| ts_ms < to_time_ms)
+    }
+
+    /// Whether `record` lies within both bounds. Unparsable timestamps fall outside a time bound.
+    pub fn admits(&self, record: &RecordWithOffset) -> bool {
+        if self.is_past_end(record.offset) {
+            return false;
+        }
+        self.to_time_ms.is_none()
+            || chrono::DateTime::parse_from_rfc3339(&record.timestamp)
+                .is_ok_and(|ts| self.admits_time(ts.timestamp_millis().max(0) as u64))
+    }
+}
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+
+use crate::Record;
+use crate::error::StorageError;
+use crate::storage::compression::CompressionCodec;
+
+/// Which time a topic stores as a record's timestamp.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum TimestampType {
+    /// The producer's timestamp when it sends one, the broker's append time otherwise.
+    #[default]
+    CreateTime,
+    /// Always the broker's append time; producer timestamps are ignored.
+    LogAppendTime,
+}
+
+impl std::fmt::Display for TimestampType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            TimestampType::CreateTime => "CreateTime",
+            TimestampType::LogAppendTime => "LogAppendTime",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for TimestampType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "createtime" => Ok(TimestampType::CreateTime),
+            "logappendtime" => Ok(TimestampType::LogAppendTime),
+            other => Err(format!(
+                "unknown timestamp type '{other}' (expected CreateTime or LogAppendTime)"
+            )),
+        }
+    }
+}
+
+/// Per-topic storage settings applied when a topic log is created.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TopicConfig {
+    /// Codec used for record batches when the producer does not request one.
+    pub compression: CompressionCodec,
+    /// Roll the active segment once its first batch is this many milliseconds old.
+    #[serde(default)]
+    pub segment_ms: Option<u64>,
+    /// Roll the active segment once its offset index holds this many entries.
+    #[serde(default)]
+    pub segment_index_max_entries: Option<usize>,
+    /// Whether producer timestamps or the append time are stored (`timestamp.type`).
+    #[serde(default)]
+    pub timestamp_type: TimestampType,
+    /// Reject producer timestamps further than this many milliseconds from the broker clock.
+    /// Only checked for [`TimestampType::CreateTime`].
+    #[serde(default)]
+    pub max_timestamp_skew_ms: Option<u64>,
+}
+
+impl TopicConfig {
+    pub fn with_compression(mut self, compression: CompressionCodec) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn with_segment_ms(mut self, segment_ms: u64) -> Self {
+        self.segment_ms = Some(segment_ms);
+        self
+    }
+
+    pub fn with_segment_index_max_entries(mut self, max_entries: usize) -> Self {
+        self.segment_index_max_entries = Some(max_entries);
+        self
+    }
+
+    pub fn with_timestamp_type(mut self, timestamp_type: TimestampType) -> Self {
+        self.timestamp_type = timestamp_type;
+        self
+    }
+
+    pub fn with_max_timestamp_skew_ms(mut self, max_skew_ms: u64) -> Self {
+        self.max_timestamp_skew_ms = Some(max_skew_ms);
+        self
+    }
+
+    /// Take the producer timestamp off `record` and return the RFC3339 timestamp to store
+    /// for it when appended at `append_time`.
+    pub fn resolve_timestamp(
+        &self,
+        record: &mut Record,
+        append_time: DateTime<Utc>,
+    ) -> Result<String, StorageError> {
+        let Some(create_time) = record.create_time.take() else {
+            return Ok(append_time.to_rfc3339());
+        };
+        let create_time = DateTime::parse_from_rfc3339(&create_time)
+            .map_err(|e| {
+                StorageError::InvalidTimestamp(format!("'{create_time}' is not RFC3339: {e}"))
+            })?
+            .with_timezone(&Utc);
+        if self.timestamp_type == TimestampType::LogAppendTime {
+            return Ok(append_time.to_rfc3339());
+        }
+        if let Some(max_skew_ms) = self.max_timestamp_skew_ms {
+            let skew_ms = (create_time - append_time)
+                .num_milliseconds()
+                .unsigned_abs();
+            if skew_ms > max_skew_ms {
+                return Err(StorageError::InvalidTimestamp(format!(
+                    "{} is {skew_ms}ms from the broker clock, more than the allowed {max_skew_ms}ms",
+                    create_time.to_rfc3339()
+                )));
+            }
+        }
+        Ok(create_time.to_rfc3339())
+    }
+
+    /// [`resolve_timestamp`](Self::resolve_timestamp) for a batch appended now. Every record is
+    /// checked before any timestamp is returned, so a bad one rejects the whole batch.
+    pub fn resolve_timestamps(&self, records: &mut [Record]) -> Result<Vec<String>, StorageError> {
+        let append_time = Utc::now();
+        records
+            .iter_mut()
+            .map(|record| self.resolve_timestamp(record, append_time))
+            .collect()
+    }
+}
@@ -1,10 +1,22 @@
 use crate::error::StorageError;
+use crate::storage::compression::CompressionCodec;
+use crate::storage::read_bounds::ReadBounds;
 use crate::{Record, RecordWithOffset};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct PartitionId(pub u32);
 
+/// Segments a topic holds across its partitions, on local disk and offloaded to the remote
+/// tier. Backends without segments report zeroes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SegmentStats {
+    pub local_segments: usize,
+    pub local_bytes: u64,
+    pub remote_segments: usize,
+    pub remote_bytes: u64,
+}
+
 impl PartitionId {
     pub fn new(id: u32) -> Self {
         Self(id)
@@ -42,6 +54,14 @@ pub trait TopicLog: Send + Sync {
         self.append_batch_partition(PartitionId::new(0), records)
     }
 
+    fn append_batch_with_compression(
+        &mut self,
+        records: Vec<Record>,
+        compression: CompressionCodec,
+    ) -> Result<u64, StorageError> {
+        self.append_batch_partition_with_compression(PartitionId::new(0), records, compression)
+    }
+
     fn get_records_from_offset(
         &self,
         offset: u64,
@@ -88,23 +108,138 @@ pub trait TopicLog: Send + Sync {
         Ok(last)
     }
 
+    /// Append a batch using `compression` instead of the topic's configured codec.
+    /// Backends that do not compress ignore the codec.
+    fn append_batch_partition_with_compression(
+        &mut self,
+        partition_id: PartitionId,
+        records: Vec<Record>,
+        _compression: CompressionCodec,
+    ) -> Result<u64, StorageError> {
+        self.append_batch_partition(partition_id, records)
+    }
+
+    /// Append records that already carry an offset and timestamp, keeping both. Offsets must
+    /// increase and start at or above the partition's next offset; a gap leaves those offsets
+    /// unassigned. Returns the last offset written.
+    fn append_batch_with_offsets_partition(
+        &mut self,
+        partition_id: PartitionId,
+        records: Vec<RecordWithOffset>,
+    ) -> Result<u64, StorageError>;
+
     fn read_from_partition(
         &self,
         partition_id: PartitionId,
         from_offset: u64,
         max_bytes: Option<usize>,
-    ) -> Result<Vec<RecordWithOffset>, StorageError>;
+    ) -> Result<Vec<RecordWithOffset>, StorageError> {
+        self.read_from_partition_bounded(
+            partition_id,
+            from_offset,
+            ReadBounds::default(),
+            max_bytes,
+        )
+    }
 
     fn read_from_partition_timestamp(
         &self,
         partition_id: PartitionId,
         ts_rfc3339: &str,
         max_bytes: Option<usize>,
+    ) -> Result<Vec<RecordWithOffset>, StorageError> {
+        self.read_from_partition_timestamp_bounded(
+            partition_id,
+            ts_rfc3339,
+            ReadBounds::default(),
+            max_bytes,
+        )
+    }
+
+    /// Read from `from_offset` in offset order, leaving out records outside `bounds`.
+    /// Backends stop scanning once the offset bound is reached.
+    fn read_from_partition_bounded(
+        &self,
+        partition_id: PartitionId,
+        from_offset: u64,
+        bounds: ReadBounds,
+        max_bytes: Option<usize>,
+    ) -> Result<Vec<RecordWithOffset>, StorageError>;
+
+    /// Read the records stamped at or after `ts_rfc3339`, in offset order, leaving out
+    /// records outside `bounds`.
+    fn read_from_partition_timestamp_bounded(
+        &self,
+        partition_id: PartitionId,
+        ts_rfc3339: &str,
+        bounds: ReadBounds,
+        max_bytes: Option<usize>,
     ) -> Result<Vec<RecordWithOffset>, StorageError>;
 
+    /// Partitions of this topic that exist, in ascending order.
+    fn partition_ids(&self) -> Vec<PartitionId>;
+
     fn partition_len(&self, partition_id: PartitionId) -> usize;
     fn partition_is_empty(&self, partition_id: PartitionId) -> bool;
     fn partition_next_offset(&self, partition_id: PartitionId) -> u64;
+
+    /// Lowest offset the partition still holds, or its next offset when it holds no records.
+    fn partition_start_offset(&self, partition_id: PartitionId) -> Result<u64, StorageError> {
+        let first = self.read_from_partition(partition_id, 0, Some(1))?;
+        Ok(first.first().map_or_else(
+            || self.partition_next_offset(partition_id),
+            |record| record.offset,
+        ))
+    }
+
+    /// Offset of the first record, in offset order, whose timestamp is at or after
+    /// `ts_rfc3339`. `None` when no such record exists.
+    fn partition_offset_for_timestamp(
+        &self,
+        partition_id: PartitionId,
+        ts_rfc3339: &str,
+    ) -> Result<Option<u64>, StorageError> {
+        let first = self.read_from_partition_timestamp(partition_id, ts_rfc3339, Some(1))?;
+        Ok(first.first().map(|record| record.offset))
+    }
+
+    fn segment_stats(&self) -> Result<SegmentStats, StorageError> {
+        Ok(SegmentStats::default())
+    }
+
+    /// Force every appended record to stable storage, whatever the sync mode. No-op for
+    /// backends that are not durable or that sync on every write.
+    fn sync(&mut self) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    /// Move closed segments past the local retention window to the remote tier, returning
+    /// how many were moved. No-op for backends without tiered storage.
+    fn offload_expired_segments(&mut self) -> Result<usize, StorageError> {
+        Ok(0)
+    }
+}
+
+/// Check that `records` can be appended with their own offsets to a partition whose next
+/// offset is `next_offset`. Backends call this from `append_batch_with_offsets_partition`.
+pub fn validate_replayed_offsets(
+    next_offset: u64,
+    records: &[RecordWithOffset],
+) -> Result<(), StorageError> {
+    let mut expected_min = next_offset;
+    for record in records {
+        if record.offset < expected_min {
+            return Err(StorageError::WriteFailed {
+                context: "append records with offsets".to_string(),
+                source: Box::new(crate::error::StorageErrorSource::Custom(format!(
+                    "offset {} is below the next writable offset {expected_min}",
+                    record.offset
+                ))),
+            });
+        }
+        expected_min = record.offset + 1;
+    }
+    Ok(())
 }
 
 pub trait ConsumerGroup: Send + Sync {
@@ -178,10 +313,28 @@ pub trait ConsumerOffsetStore: Send + Sync {
         offset: u64,
     ) -> Result<bool, StorageError>;
 
+    /// Persist several snapshots, each under the same monotonic rule as `persist_snapshot`,
+    /// and return one applied/stale flag per snapshot. Stores that can write them in one
+    /// transaction override this.
+    fn persist_snapshots(
+        &self,
+        snapshots: Vec<(String, PartitionId, u64)>,
+    ) -> Result<Vec<bool>, StorageError> {
+        snapshots
+            .into_iter()
+            .map(|(topic, partition_id, offset)| self.persist_snapshot(topic, partition_id, offset))
+            .collect()
+    }
+
     /// Get all offset snapshots for this consumer group.
     /// Returns map of (topic, partition) -> offset.
     fn get_all_snapshots(&self) -> Result<HashMap<(String, PartitionId), u64>, StorageError>;
 
     /// Get the consumer group ID.
     fn group_id(&self) -> &str;
+
+    /// Force every persisted snapshot to stable storage, whatever the sync mode.
+    fn sync(&self) -> Result<(), StorageError> {
+        Ok(())
+    }
 }
@@ -0,0 +1,183 @@
+use super::test_utilities::*;
+use flashq::Record;
+use flashq_storage::file::{FileTopicLog, IndexingConfig};
+use flashq_storage::{CompressionCodec, StorageBackend, TopicConfig, TopicLog};
+use test_log::test;
+
+const CODECS: [CompressionCodec; 5] = [
+    CompressionCodec::None,
+    CompressionCodec::Gzip,
+    CompressionCodec::Snappy,
+    CompressionCodec::Lz4,
+    CompressionCodec::Zstd,
+];
+
+fn repetitive_records(count: usize) -> Vec<Record> {
+    (0..count)
+        .map(|i| {
+            Record::new(
+                Some(format!("key-{i}")),
+                format!(
+                    "{{\"order\":{i},\"status\":\"pending\",\"note\":\"{}\"}}",
+                    "a".repeat(200)
+                ),
+                None,
+            )
+        })
+        .collect()
+}
+
+fn open_log(config: &TestConfig, compression: CompressionCodec) -> FileTopicLog {
+    FileTopicLog::new(
+        &config.topic_name,
+        config.sync_mode,
+        config.temp_dir_path(),
+        config.segment_size,
+    )
+    .unwrap()
+    .with_topic_config(TopicConfig::default().with_compression(compression))
+}
+
+fn log_file_size(config: &TestConfig) -> u64 {
+    let path = config
+        .temp_dir_path()
+        .join(&config.topic_name)
+        .join("0")
+        .join("00000000000000000000.log");
+    std::fs::metadata(path).unwrap().len()
+}
+
+#[test]
+fn test_compressed_batches_roundtrip_for_each_codec() {
+    for codec in CODECS {
+        let config = TestConfig::new(&format!("compressed_roundtrip_{codec}"));
+        let mut log = open_log(&config, codec);
+        let records = repetitive_records(50);
+
+        let last = log.append_batch(records.clone()).unwrap();
+        assert_eq!(last, 49);
+
+        let read = log.get_records_from_offset(0, None).unwrap();
+        assert_eq!(read.len(), 50, "codec {codec}");
+        for (i, r) in read.iter().enumerate() {
+            assert_eq!(r.offset, i as u64);
+            assert_eq!(r.record, records[i]);
+        }
+
+        let from_middle = log.get_records_from_offset(23, Some(5)).unwrap();
+        let offsets: Vec<u64> = from_middle.iter().map(|r| r.offset).collect();
+        assert_eq!(offsets, vec![23, 24, 25, 26, 27], "codec {codec}");
+    }
+}
+
+#[test]
+fn test_compression_reduces_segment_size() {
+    let plain = TestConfig::new("size_plain");
+    let mut plain_log = open_log(&plain, CompressionCodec::None);
+    plain_log.append_batch(repetitive_records(100)).unwrap();
+
+    let zstd = TestConfig::new("size_zstd");
+    let mut zstd_log = open_log(&zstd, CompressionCodec::Zstd);
+    zstd_log.append_batch(repetitive_records(100)).unwrap();
+
+    assert!(log_file_size(&zstd) * 4 < log_file_size(&plain));
+}
+
+#[test]
+fn test_per_request_codec_overrides_topic_default() {
+    let config = TestConfig::new("codec_override");
+    let mut log = open_log(&config, CompressionCodec::None);
+
+    log.append_batch(repetitive_records(10)).unwrap();
+    log.append_batch_with_compression(repetitive_records(10), CompressionCodec::Gzip)
+        .unwrap();
+    log.append(Record::new(None, "single".to_string(), None))
+        .unwrap();
+
+    let read = log.get_records_from_offset(0, None).unwrap();
+    assert_eq!(read.len(), 21);
+    assert_eq!(read[15].offset, 15);
+    assert_eq!(read[20].record.value, "single");
+}
+
+#[test]
+fn test_compressed_log_recovers_and_rebuilds_indexes() {
+    let config = TestConfig::new("compressed_recovery");
+    let indexing = IndexingConfig {
+        index_interval_bytes: 64,
+        index_interval_records: 1,
+        time_seek_back_bytes: 64,
+        ..Default::default()
+    };
+    let topic_config = TopicConfig::default().with_compression(CompressionCodec::Lz4);
+
+    {
+        let mut log = FileTopicLog::new_with_batch_bytes_and_indexing_config(
+            &config.topic_name,
+            config.sync_mode,
+            config.temp_dir_path(),
+            config.segment_size,
+            1024,
+            indexing.clone(),
+        )
+        .unwrap()
+        .with_topic_config(topic_config.clone());
+        log.append_batch(repetitive_records(40)).unwrap();
+        log.sync().unwrap();
+    }
+
+    // Drop both indexes so recovery has to rebuild them from batch headers
+    let partition_dir = config.temp_dir_path().join(&config.topic_name).join("0");
+    std::fs::remove_file(partition_dir.join("00000000000000000000.index")).unwrap();
+    std::fs::remove_file(partition_dir.join("00000000000000000000.timeindex")).unwrap();
+
+    let log = FileTopicLog::new_with_batch_bytes_and_indexing_config(
+        &config.topic_name,
+        config.sync_mode,
+        config.temp_dir_path(),
+        config.segment_size,
+        1024,
+        indexing,
+    )
+    .unwrap()
+    .with_topic_config(topic_config);
+
+    assert_eq!(log.next_offset(), 40);
+    let tail = log.get_records_from_offset(37, None).unwrap();
+    let offsets: Vec<u64> = tail.iter().map(|r| r.offset).collect();
+    assert_eq!(offsets, vec![37, 38, 39]);
+
+    let first_ts = log.get_records_from_offset(0, Some(1)).unwrap()[0]
+        .timestamp
+        .clone();
+    let by_time = log.get_records_from_timestamp(&first_ts, None).unwrap();
+    assert_eq!(by_time.len(), 40);
+}
+
+#[test]
+fn test_backend_applies_topic_compression() {
+    let config = TestConfig::new("backend_topic_compression");
+    let backend = StorageBackend::new_file_with_path(config.sync_mode, config.temp_dir_path())
+        .unwrap()
+        .with_compression(CompressionCodec::Snappy)
+        .with_topic_config(
+            "raw",
+            TopicConfig::default().with_compression(CompressionCodec::None),
+        );
+
+    assert_eq!(
+        backend.topic_config("orders").compression,
+        CompressionCodec::Snappy
+    );
+    assert_eq!(
+        backend.topic_config("raw").compression,
+        CompressionCodec::None
+    );
+
+    let log = backend.create("orders").unwrap();
+    log.write().append_batch(repetitive_records(5)).unwrap();
+    assert_eq!(
+        log.read().get_records_from_offset(0, None).unwrap().len(),
+        5
+    );
+}
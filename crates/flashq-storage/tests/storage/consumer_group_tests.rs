@@ -158,10 +158,15 @@ fn test_empty_index_file_warning() {
     let partition_dir = topic_dir.join("0");
     fs::create_dir_all(&partition_dir).unwrap();
 
-    // Create a log file with properly serialized record data
+    // Create a log file with a properly encoded record batch
     let log_path = partition_dir.join("00000000000000000000.log");
     let record = flashq::Record::new(None, "test_value".to_string(), None);
-    let serialized = flashq_storage::file::common::serialize_record(&record, 0).unwrap();
+    let serialized = flashq_storage::file::batch::encode_batch(
+        &[record],
+        0,
+        flashq_storage::CompressionCodec::None,
+    )
+    .unwrap();
     fs::write(&log_path, serialized).unwrap();
 
     // Create empty index files (this should trigger the warning)
@@ -167,6 +167,30 @@ fn test_segment_rolling() {
     assert_eq!(middle_records[19].offset, 44);
 }
 
+#[test]
+fn test_segment_stats_counts_local_segments() {
+    let config = TestConfig::new("segment_stats");
+    let mut log = FileTopicLog::new(
+        &config.topic_name,
+        config.sync_mode,
+        config.temp_dir_path(),
+        10 * 1024,
+    )
+    .unwrap();
+    assert_eq!(log.segment_stats().unwrap().local_bytes, 0);
+
+    for i in 0..30 {
+        let record = Record::new(Some(format!("key_{i}")), "x".repeat(1024), None);
+        log.append(record).unwrap();
+    }
+
+    let stats = log.segment_stats().unwrap();
+    assert!(stats.local_segments > 1, "{stats:?}");
+    assert!(stats.local_bytes >= 30 * 1024, "{stats:?}");
+    assert_eq!(stats.remote_segments, 0);
+    assert_eq!(stats.remote_bytes, 0);
+}
+
 #[test]
 fn test_segment_boundary_crossing() {
     // Setup - Use very small segments to force frequent rolling
@@ -206,7 +230,6 @@ fn test_segment_boundary_crossing() {
 fn test_flashq_large_file_benchmark_scenario() {
     use flashq::FlashQ;
     use flashq_storage::{StorageBackend, file::SyncMode};
-    use std::collections::HashMap;
 
     // Setup: Create FlashQ with file storage and helper function
     let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
@@ -217,8 +240,7 @@ fn test_flashq_large_file_benchmark_scenario() {
 
     let create_1kb_record = |index: usize| {
         let payload = "x".repeat(1024);
-        let mut headers = HashMap::new();
-        headers.insert("index".to_string(), index.to_string());
+        let headers = vec![flashq::Header::new("index", index.to_string())];
         flashq::Record::new(Some(format!("key_{index}")), payload, Some(headers))
     };
 
@@ -260,3 +282,248 @@ fn test_flashq_large_file_benchmark_scenario() {
         "First record should be at offset 900"
     );
 }
+
+fn count_segment_logs(config: &TestConfig, topic: &str) -> usize {
+    std::fs::read_dir(config.temp_dir_path().join(topic).join("0"))
+        .unwrap()
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "log"))
+        .count()
+}
+
+#[test]
+fn test_topic_config_rolls_segments_by_index_entries() {
+    let config = TestConfig::new("topic_roll_index_entries");
+    let indexing = flashq_storage::file::IndexingConfig {
+        index_interval_records: 1,
+        ..Default::default()
+    };
+    let mut log = FileTopicLog::new_with_batch_bytes_and_indexing_config(
+        &config.topic_name,
+        config.sync_mode,
+        config.temp_dir_path(),
+        config.segment_size,
+        1024,
+        indexing,
+    )
+    .unwrap()
+    .with_topic_config(flashq_storage::TopicConfig::default().with_segment_index_max_entries(2));
+
+    for i in 0..6 {
+        log.append(Record::new(None, format!("value-{i}"), None))
+            .unwrap();
+    }
+
+    assert_eq!(count_segment_logs(&config, &config.topic_name), 3);
+    let offsets: Vec<u64> = log
+        .get_records_from_offset(0, None)
+        .unwrap()
+        .iter()
+        .map(|r| r.offset)
+        .collect();
+    assert_eq!(offsets, (0..6).collect::<Vec<u64>>());
+}
+
+#[test]
+fn test_backend_segment_ms_rolls_idle_topics() {
+    let config = TestConfig::new("topic_roll_segment_ms");
+    let backend = flashq_storage::StorageBackend::new_file_with_config(
+        config.sync_mode,
+        config.temp_dir_path(),
+        1000,
+        config.segment_size,
+    )
+    .unwrap()
+    .with_segment_ms(20)
+    .with_topic_config("pinned", flashq_storage::TopicConfig::default());
+    assert_eq!(backend.topic_config("events").segment_ms, Some(20));
+    assert_eq!(backend.topic_config("pinned").segment_ms, None);
+
+    let events = backend.create("events").unwrap();
+    let pinned = backend.create("pinned").unwrap();
+    for log in [&events, &pinned] {
+        log.write()
+            .append(Record::new(None, "first".to_string(), None))
+            .unwrap();
+    }
+    std::thread::sleep(std::time::Duration::from_millis(40));
+    for log in [&events, &pinned] {
+        log.write()
+            .append(Record::new(None, "second".to_string(), None))
+            .unwrap();
+    }
+
+    assert_eq!(count_segment_logs(&config, "events"), 2);
+    assert_eq!(count_segment_logs(&config, "pinned"), 1);
+}
+
+fn replayed(offset: u64, timestamp: &str) -> flashq::RecordWithOffset {
+    flashq::RecordWithOffset {
+        record: Record::new(
+            Some(format!("key-{offset}")),
+            format!("value-{offset}"),
+            None,
+        ),
+        offset,
+        timestamp: timestamp.to_string(),
+    }
+}
+
+#[test]
+fn test_append_with_offsets_keeps_offsets_and_timestamps() {
+    let config = TestConfig::new("replay_offsets");
+    let early = "2024-01-01T00:00:00+00:00";
+    let late = "2024-01-02T00:00:00+00:00";
+    let records = vec![
+        replayed(100, early),
+        replayed(101, early),
+        replayed(102, late),
+        replayed(110, late),
+    ];
+
+    {
+        let mut log = FileTopicLog::new(
+            &config.topic_name,
+            config.sync_mode,
+            config.temp_dir_path(),
+            config.segment_size,
+        )
+        .unwrap();
+        let last = log
+            .append_batch_with_offsets_partition(flashq_storage::PartitionId(0), records.clone())
+            .unwrap();
+        assert_eq!(last, 110);
+        assert_eq!(log.next_offset(), 111);
+        assert_eq!(
+            log.append(Record::new(None, "live".into(), None)).unwrap(),
+            111
+        );
+    }
+
+    // The skipped ranges each start a segment rather than leaving an empty one behind
+    assert_eq!(count_segment_logs(&config, &config.topic_name), 2);
+
+    let log = FileTopicLog::new(
+        &config.topic_name,
+        config.sync_mode,
+        config.temp_dir_path(),
+        config.segment_size,
+    )
+    .unwrap();
+    assert_eq!(log.next_offset(), 112);
+    let read = log.get_records_from_offset(0, None).unwrap();
+    assert_eq!(&read[..4], &records[..]);
+    assert_eq!(read[4].offset, 111);
+    assert_eq!(
+        log.get_records_from_offset(105, Some(1)).unwrap()[0].offset,
+        110
+    );
+    let from_late = log.get_records_from_timestamp(late, None).unwrap();
+    assert_eq!(from_late[0].offset, 102);
+}
+
+#[test]
+fn test_append_with_offsets_rejects_offsets_already_assigned() {
+    let config = TestConfig::new("replay_offsets_reject");
+    let mut log = FileTopicLog::new(
+        &config.topic_name,
+        config.sync_mode,
+        config.temp_dir_path(),
+        config.segment_size,
+    )
+    .unwrap();
+    for i in 0..3 {
+        log.append(Record::new(None, format!("value-{i}"), None))
+            .unwrap();
+    }
+
+    let ts = "2024-01-01T00:00:00+00:00";
+    let partition = flashq_storage::PartitionId(0);
+    assert!(
+        log.append_batch_with_offsets_partition(partition, vec![replayed(2, ts)])
+            .is_err()
+    );
+    assert!(
+        log.append_batch_with_offsets_partition(partition, vec![replayed(5, ts), replayed(4, ts)])
+            .is_err()
+    );
+    assert_eq!(log.next_offset(), 3);
+}
+
+/// Encode a record the way logs were written before batch framing:
+/// [4B payload][8B offset][8B ts_ms][4B ts_len][ts][json].
+fn legacy_record_bytes(record: &Record, offset: u64, timestamp: &str) -> Vec<u8> {
+    let json = serde_json::to_vec(record).unwrap();
+    let ts_ms = chrono::DateTime::parse_from_rfc3339(timestamp)
+        .unwrap()
+        .timestamp_millis() as u64;
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&(4 + timestamp.len() as u32 + json.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&offset.to_be_bytes());
+    bytes.extend_from_slice(&ts_ms.to_be_bytes());
+    bytes.extend_from_slice(&(timestamp.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(timestamp.as_bytes());
+    bytes.extend_from_slice(&json);
+    bytes
+}
+
+#[test]
+fn test_recovers_legacy_record_format_segment() {
+    let config = TestConfig::new("legacy_segment");
+    let partition_dir = config.temp_dir_path().join(&config.topic_name).join("0");
+    std::fs::create_dir_all(&partition_dir).unwrap();
+    let base_path = partition_dir.join("00000000000000000000");
+
+    let timestamp = "2024-05-01T12:00:00+00:00";
+    let legacy: Vec<Record> = (0..3)
+        .map(|i| Record::new(Some(format!("key-{i}")), format!("value-{i}"), None))
+        .collect();
+    let mut log_bytes = Vec::new();
+    for (offset, record) in legacy.iter().enumerate() {
+        log_bytes.extend(legacy_record_bytes(record, offset as u64, timestamp));
+    }
+    // A torn final record is dropped, as the old reader did
+    log_bytes.extend_from_slice(&[0, 0, 0, 99, 0, 0]);
+    std::fs::write(base_path.with_extension("log"), log_bytes).unwrap();
+    // Index positions from the old layout must not survive the conversion
+    std::fs::write(
+        base_path.with_extension("index"),
+        [0u8, 0, 0, 2, 0, 0, 0, 200],
+    )
+    .unwrap();
+
+    let mut log = FileTopicLog::new(
+        &config.topic_name,
+        config.sync_mode,
+        config.temp_dir_path(),
+        config.segment_size,
+    )
+    .unwrap();
+    assert_eq!(log.next_offset(), 3);
+
+    let records = log.get_records_from_offset(0, None).unwrap();
+    assert_eq!(records.len(), 3);
+    for (offset, record) in records.iter().enumerate() {
+        assert_eq!(record.offset, offset as u64);
+        assert_eq!(record.record, legacy[offset]);
+        assert_eq!(record.timestamp, timestamp);
+    }
+    assert_eq!(log.get_records_from_offset(2, None).unwrap()[0].offset, 2);
+
+    let offset = log
+        .append(Record::new(None, "after-upgrade".to_string(), None))
+        .unwrap();
+    assert_eq!(offset, 3);
+    drop(log);
+
+    let log = FileTopicLog::new(
+        &config.topic_name,
+        config.sync_mode,
+        config.temp_dir_path(),
+        config.segment_size,
+    )
+    .unwrap();
+    let records = log.get_records_from_offset(0, None).unwrap();
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[3].record.value, "after-upgrade");
+}
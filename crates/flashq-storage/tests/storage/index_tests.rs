@@ -127,7 +127,7 @@ fn test_save_index_to_file() {
     let mut writer = std::io::BufWriter::new(&mut file);
 
     if let Some(entry) = index.last_entry() {
-        let result = index.write_entry_to_file(&mut writer, entry, 0);
+        let result = index.write_entry_to_file(&mut writer, &entry, 0);
         assert!(result.is_ok());
     }
 
@@ -309,3 +309,63 @@ fn test_large_index_performance() {
     assert_eq!(index.entry_count(), 1000);
     assert_eq!(index.find_position_for_offset(5000), Some(50000));
 }
+
+#[test]
+fn test_open_preallocates_and_finds_entries_in_place() {
+    let (_temp_dir, file_path) = create_temp_file();
+
+    {
+        let mut index = SparseIndex::open(&file_path, 1000, 1024).unwrap();
+        assert_eq!(fs::metadata(&file_path).unwrap().len(), 1024);
+        for i in 0..10u64 {
+            index.add_entry(IndexEntry {
+                offset: 1000 + i * 10,
+                position: (i * 100) as u32 + 1,
+            });
+        }
+        index.flush().unwrap();
+    }
+
+    let index = SparseIndex::open(&file_path, 1000, 1024).unwrap();
+    assert_eq!(index.entry_count(), 10);
+    assert_eq!(index.find_position_for_offset(1055), Some(501));
+    assert!(index.sanity_check(1_000).is_ok());
+    assert!(index.sanity_check(500).is_err());
+}
+
+#[test]
+fn test_open_fills_up_and_reports_full() {
+    let (_temp_dir, file_path) = create_temp_file();
+    let mut index = SparseIndex::open(&file_path, 0, 16).unwrap();
+
+    for i in 1..=3u64 {
+        index.add_entry(IndexEntry {
+            offset: i,
+            position: i as u32,
+        });
+    }
+
+    assert!(index.is_full());
+    assert_eq!(index.entry_count(), 2);
+}
+
+#[test]
+fn test_open_is_not_bounded_by_entry_count() {
+    let (_temp_dir, file_path) = create_temp_file();
+    let entries = 1_100_000u64;
+
+    {
+        let mut index = SparseIndex::open(&file_path, 0, entries * 8).unwrap();
+        for i in 1..=entries {
+            index.add_entry(IndexEntry {
+                offset: i,
+                position: i as u32,
+            });
+        }
+        index.trim_to_valid_size().unwrap();
+    }
+
+    let index = SparseIndex::open(&file_path, 0, 1024).unwrap();
+    assert_eq!(index.entry_count(), entries as usize);
+    assert_eq!(index.find_position_for_offset(777_777), Some(777_777));
+}
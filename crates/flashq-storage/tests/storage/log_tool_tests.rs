@@ -0,0 +1,263 @@
+use super::test_utilities::*;
+use flashq::Record;
+use flashq_storage::backend::DirectoryLock;
+use flashq_storage::file::log_tool::{
+    dump_segment, find_segment_logs, rebuild_segment_indexes, truncate_partition, verify_path,
+    verify_segment,
+};
+use flashq_storage::file::{FileTopicLog, IndexingConfig};
+use flashq_storage::{CompressionCodec, StorageBackend, StorageError, TopicConfig, TopicLog};
+use std::path::{Path, PathBuf};
+use test_log::test;
+
+const SMALL_SEGMENT: u64 = 1024;
+
+fn indexing_config() -> IndexingConfig {
+    IndexingConfig {
+        index_interval_bytes: 64,
+        index_interval_records: 1,
+        time_seek_back_bytes: 64,
+        ..Default::default()
+    }
+}
+
+fn record(i: u64) -> Record {
+    Record::new(
+        Some(format!("key-{i}")),
+        format!("{{\"n\":{i}}}"),
+        Some(vec![flashq::Header::new("source", "test")]),
+    )
+}
+
+fn open_log(config: &TestConfig) -> FileTopicLog {
+    FileTopicLog::new_with_batch_bytes_and_indexing_config(
+        &config.topic_name,
+        config.sync_mode,
+        config.temp_dir_path(),
+        SMALL_SEGMENT,
+        64 * 1024, // keep each append below in one batch
+        indexing_config(),
+    )
+    .unwrap()
+    .with_topic_config(TopicConfig::default().with_compression(CompressionCodec::Lz4))
+}
+
+/// Write `count` records in batches of five and return the partition directory.
+fn populate(config: &TestConfig, count: u64) -> PathBuf {
+    let mut log = open_log(config);
+    for start in (0..count).step_by(5) {
+        let records = (start..(start + 5).min(count)).map(record).collect();
+        log.append_batch(records).unwrap();
+    }
+    log.sync().unwrap();
+    config.temp_dir_path().join(&config.topic_name).join("0")
+}
+
+fn first_segment(partition_dir: &Path) -> PathBuf {
+    find_segment_logs(partition_dir).unwrap().remove(0)
+}
+
+#[test]
+fn test_dump_prints_every_record_of_a_segment() {
+    let config = TestConfig::new("log_tool_dump");
+    let partition_dir = populate(&config, 12);
+
+    let mut batches = 0;
+    let mut records = Vec::new();
+    for log_path in find_segment_logs(&partition_dir).unwrap() {
+        dump_segment(&log_path, |batch| {
+            batches += 1;
+            assert_eq!(batch.header.codec, CompressionCodec::Lz4);
+            records.extend(batch.records.iter().cloned());
+        })
+        .unwrap();
+    }
+
+    assert!(batches >= 3);
+    let offsets: Vec<u64> = records.iter().map(|r| r.offset).collect();
+    assert_eq!(offsets, (0..12).collect::<Vec<u64>>());
+    assert_eq!(records[7].record, record(7));
+}
+
+#[test]
+fn test_verify_accepts_healthy_partition() {
+    let config = TestConfig::new("log_tool_verify_ok");
+    let partition_dir = populate(&config, 40);
+
+    let reports = verify_path(config.temp_dir_path()).unwrap();
+    assert!(reports.len() > 1, "expected several segments");
+    assert!(reports.iter().all(|r| r.is_clean()), "{reports:?}");
+    assert_eq!(reports.iter().map(|r| r.records).sum::<u64>(), 40);
+    assert_eq!(
+        find_segment_logs(&partition_dir).unwrap().len(),
+        reports.len()
+    );
+}
+
+#[test]
+fn test_verify_reports_corrupted_batch_and_bad_index_entries() {
+    let config = TestConfig::new("log_tool_verify_bad");
+    let partition_dir = populate(&config, 10);
+    let log_path = first_segment(&partition_dir);
+
+    // Flip a payload byte in the last batch
+    let mut bytes = std::fs::read(&log_path).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xFF;
+    std::fs::write(&log_path, bytes).unwrap();
+
+    let report = verify_segment(&log_path).unwrap();
+    assert!(!report.is_clean());
+    assert!(report.issues.iter().any(|i| i.contains("crc mismatch")));
+    // The index still points at the now unreadable batch
+    assert!(
+        report
+            .issues
+            .iter()
+            .any(|i| i.contains("not a batch start"))
+    );
+}
+
+#[test]
+fn test_rebuild_index_repairs_corrupted_indexes() {
+    let config = TestConfig::new("log_tool_rebuild");
+    let partition_dir = populate(&config, 10);
+    let log_path = first_segment(&partition_dir);
+
+    let index_path = log_path.with_extension("index");
+    let mut bytes = std::fs::read(&index_path).unwrap();
+    bytes[4..8].copy_from_slice(&3u32.to_be_bytes());
+    std::fs::write(&index_path, bytes).unwrap();
+    std::fs::remove_file(log_path.with_extension("timeindex")).unwrap();
+    assert!(!verify_segment(&log_path).unwrap().is_clean());
+
+    let rebuilt = rebuild_segment_indexes(&log_path, indexing_config()).unwrap();
+    assert!(rebuilt.offset_entries > 0);
+    assert!(rebuilt.time_entries > 0);
+
+    let report = verify_segment(&log_path).unwrap();
+    assert!(report.is_clean(), "{report:?}");
+}
+
+#[test]
+fn test_truncate_rewrites_straddling_batch_and_drops_later_segments() {
+    let config = TestConfig::new("log_tool_truncate");
+    let partition_dir = populate(&config, 40);
+    let segments_before = find_segment_logs(&partition_dir).unwrap().len();
+
+    let summary = truncate_partition(&partition_dir, 7, indexing_config()).unwrap();
+    assert_eq!(summary.next_offset, 7);
+    // Batches hold offsets 5..=9, so offset 7 falls inside one
+    assert!(summary.truncated_segment.is_some_and(|base| base <= 5));
+    assert!(summary.removed_segments.iter().all(|&base| base >= 7));
+    assert_eq!(
+        find_segment_logs(&partition_dir).unwrap().len(),
+        segments_before - summary.removed_segments.len()
+    );
+    assert!(
+        verify_path(&partition_dir)
+            .unwrap()
+            .iter()
+            .all(|r| r.is_clean())
+    );
+
+    let mut log = open_log(&config);
+    assert_eq!(log.next_offset(), 7);
+    let offsets: Vec<u64> = log
+        .get_records_from_offset(0, None)
+        .unwrap()
+        .iter()
+        .map(|r| r.offset)
+        .collect();
+    assert_eq!(offsets, (0..7).collect::<Vec<u64>>());
+    assert_eq!(log.append(record(7)).unwrap(), 7);
+}
+
+#[test]
+fn test_truncate_keeps_per_record_timestamps_of_straddling_batch() {
+    let config = TestConfig::new("log_tool_truncate_timestamps");
+    let create_times = [
+        "2024-05-01T12:00:03+00:00",
+        "2024-05-01T12:00:01+00:00",
+        "2024-05-01T12:00:04+00:00",
+        "2024-05-01T12:00:00+00:00",
+        "2024-05-01T12:00:02+00:00",
+    ];
+    let mut log = open_log(&config);
+    let records = create_times
+        .iter()
+        .enumerate()
+        .map(|(i, time)| record(i as u64).with_create_time(*time))
+        .collect();
+    log.append_batch(records).unwrap();
+    let before: Vec<String> = log
+        .get_records_from_offset(0, None)
+        .unwrap()
+        .into_iter()
+        .map(|r| r.timestamp)
+        .collect();
+    drop(log);
+    let partition_dir = config.temp_dir_path().join(&config.topic_name).join("0");
+
+    let summary = truncate_partition(&partition_dir, 3, indexing_config()).unwrap();
+    assert_eq!(summary.next_offset, 3);
+
+    let log = open_log(&config);
+    let after: Vec<String> = log
+        .get_records_from_offset(0, None)
+        .unwrap()
+        .into_iter()
+        .map(|r| r.timestamp)
+        .collect();
+    assert_eq!(after, before[..3]);
+
+    // The rewritten batch is bounded by the kept records' own timestamps
+    let mut bounds = Vec::new();
+    dump_segment(&first_segment(&partition_dir), |batch| {
+        bounds.push((batch.header.min_ts_ms, batch.header.max_ts_ms));
+    })
+    .unwrap();
+    let ms = |time: &str| {
+        chrono::DateTime::parse_from_rfc3339(time)
+            .unwrap()
+            .timestamp_millis() as u64
+    };
+    assert_eq!(bounds, [(ms(create_times[1]), ms(create_times[2]))]);
+}
+
+#[test]
+fn test_truncate_drops_unreadable_tail() {
+    let config = TestConfig::new("log_tool_truncate_tail");
+    let partition_dir = populate(&config, 5);
+    let log_path = first_segment(&partition_dir);
+
+    let valid_len = std::fs::metadata(&log_path).unwrap().len();
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&log_path)
+        .unwrap();
+    std::io::Write::write_all(&mut file, b"torn write").unwrap();
+    drop(file);
+
+    let summary = truncate_partition(&partition_dir, 100, indexing_config()).unwrap();
+    assert_eq!(summary.next_offset, 5);
+    assert_eq!(std::fs::metadata(&log_path).unwrap().len(), valid_len);
+}
+
+#[test]
+fn test_directory_lock_excludes_running_backend() {
+    let config = TestConfig::new("log_tool_lock");
+    let data_dir = config.temp_dir_path().join("data");
+
+    let backend = StorageBackend::new_file_with_path(config.sync_mode, &data_dir).unwrap();
+    assert!(matches!(
+        DirectoryLock::acquire(&data_dir),
+        Err(StorageError::DirectoryLocked { .. })
+    ));
+    drop(backend);
+
+    let lock = DirectoryLock::acquire(&data_dir).unwrap();
+    assert!(StorageBackend::new_file_with_path(config.sync_mode, &data_dir).is_err());
+    drop(lock);
+    assert!(!data_dir.join(".flashq.lock").exists());
+}
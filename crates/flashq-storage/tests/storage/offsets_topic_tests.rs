@@ -0,0 +1,141 @@
+use super::test_utilities::*;
+use flashq::{FlashQ, Record};
+use flashq_storage::memory::InMemoryTopicLog;
+use flashq_storage::storage::{OffsetsTopic, TopicConsumerOffsetStore};
+use flashq_storage::{
+    CONSUMER_OFFSETS_TOPIC, ConsumerOffsetStore, PartitionId, StorageBackend, StorageFactory,
+    SyncMode, TopicLog,
+};
+use parking_lot::RwLock;
+use std::path::Path;
+use std::sync::Arc;
+use test_log::test;
+
+fn topic_backend(data_dir: &Path) -> StorageBackend {
+    StorageBackend::new_file_with_path(SyncMode::Immediate, data_dir)
+        .unwrap()
+        .with_offsets_topic()
+        .unwrap()
+}
+
+#[test]
+fn test_commits_are_appended_to_internal_topic() {
+    let config = TestConfig::new("offsets_topic_append");
+    let backend = topic_backend(config.temp_dir_path());
+    let store = backend.create_consumer_offset_store("orders").unwrap();
+
+    store
+        .persist_snapshot("a".to_string(), PartitionId::new(0), 4)
+        .unwrap();
+    assert!(
+        !store
+            .persist_snapshot("a".to_string(), PartitionId::new(0), 2)
+            .unwrap()
+    );
+
+    let log = backend.create_topic_log(CONSUMER_OFFSETS_TOPIC).unwrap();
+    let records = log
+        .read()
+        .read_from_partition(PartitionId::new(0), 0, None)
+        .unwrap();
+    assert_eq!(
+        records.len(),
+        2,
+        "one registration and one commit; the stale commit must not be appended"
+    );
+    assert_eq!(records[1].record.value, "4");
+}
+
+#[test]
+fn test_offsets_are_replayed_on_restart() {
+    let config = TestConfig::new("offsets_topic_replay");
+    let data_dir = config.temp_dir_path();
+    {
+        let backend = topic_backend(data_dir);
+        let store = backend.create_consumer_offset_store("orders").unwrap();
+        for offset in [1, 5, 9] {
+            store
+                .persist_snapshot("a".to_string(), PartitionId::new(1), offset)
+                .unwrap();
+        }
+        backend.create_consumer_offset_store("idle").unwrap();
+    }
+
+    let backend = topic_backend(data_dir);
+
+    assert_eq!(
+        backend.discover_consumer_groups().unwrap(),
+        vec!["idle".to_string(), "orders".to_string()]
+    );
+    let store = backend.create_consumer_offset_store("orders").unwrap();
+    assert_eq!(store.load_snapshot("a", PartitionId::new(1)).unwrap(), 9);
+    assert!(
+        !data_dir.join("consumer_groups").exists(),
+        "the topic store must not write JSON offset files"
+    );
+}
+
+#[test]
+fn test_internal_topic_is_hidden_from_topic_listings() {
+    let config = TestConfig::new("offsets_topic_hidden");
+    let data_dir = config.temp_dir_path();
+    {
+        let queue = FlashQ::with_storage_backend(topic_backend(data_dir));
+        queue
+            .post_records(
+                "orders".to_string(),
+                vec![Record::new(None, "v".to_string(), None)],
+            )
+            .unwrap();
+        queue.create_consumer_group("workers".to_string()).unwrap();
+        queue
+            .update_consumer_group_offset("workers", "orders".to_string(), 1)
+            .unwrap();
+        assert_eq!(queue.get_topics(), vec!["orders".to_string()]);
+    }
+
+    let backend = topic_backend(data_dir);
+    assert!(data_dir.join(CONSUMER_OFFSETS_TOPIC).is_dir());
+    assert_eq!(
+        backend.discover_topics().unwrap(),
+        vec!["orders".to_string()]
+    );
+
+    let queue = FlashQ::with_storage_backend(backend);
+    assert_eq!(queue.get_topics(), vec!["orders".to_string()]);
+    assert_eq!(
+        queue
+            .get_consumer_group_offset("workers", "orders")
+            .unwrap(),
+        1
+    );
+}
+
+#[test]
+fn test_replay_skips_undecodable_records() {
+    let log: Arc<RwLock<dyn TopicLog + Send + Sync>> =
+        Arc::new(RwLock::new(InMemoryTopicLog::new()));
+    {
+        let offsets = Arc::new(OffsetsTopic::open(Arc::clone(&log)).unwrap());
+        let store = TopicConsumerOffsetStore::new(offsets, "orders").unwrap();
+        store
+            .persist_snapshot("a".to_string(), PartitionId::new(0), 3)
+            .unwrap();
+    }
+    log.write()
+        .append(Record::new(
+            Some("not json".to_string()),
+            "7".to_string(),
+            None,
+        ))
+        .unwrap();
+    log.write()
+        .append(Record::new(None, "7".to_string(), None))
+        .unwrap();
+
+    let offsets = Arc::new(OffsetsTopic::open(log).unwrap());
+
+    assert_eq!(offsets.group_ids(), vec!["orders".to_string()]);
+    let store = TopicConsumerOffsetStore::new(offsets, "orders").unwrap();
+    assert_eq!(store.load_snapshot("a", PartitionId::new(0)).unwrap(), 3);
+}
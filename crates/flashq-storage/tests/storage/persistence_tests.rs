@@ -1,6 +1,6 @@
 use super::test_utilities::*;
 use flashq::{FlashQ, Record};
-use flashq_storage::file::FileTopicLog;
+use flashq_storage::file::{FileTopicLog, SyncMode};
 use flashq_storage::{StorageBackend, TopicLog};
 use test_log::test;
 
@@ -78,6 +78,46 @@ fn test_flashq_persistence_across_instances() {
     assert_eq!(records[1].offset, 1);
 }
 
+#[test]
+fn test_flashq_sync_all_persists_unsynced_writes() {
+    let config = TestConfig::new("flashq_sync_all");
+    let topic_name = config.topic_name.clone();
+    let temp_dir = config.temp_dir_path().to_path_buf();
+
+    {
+        let queue = FlashQ::with_storage_backend(
+            StorageBackend::new_file_with_path(SyncMode::None, temp_dir.clone()).unwrap(),
+        );
+        queue
+            .post_records(
+                topic_name.clone(),
+                vec![
+                    Record::new(None, "first".to_string(), None),
+                    Record::new(None, "second".to_string(), None),
+                ],
+            )
+            .unwrap();
+        queue.create_consumer_group("billing".to_string()).unwrap();
+        queue
+            .update_consumer_group_offset("billing", topic_name.clone(), 1)
+            .unwrap();
+
+        queue.sync_all().unwrap();
+    }
+    let new_queue = FlashQ::with_storage_backend(
+        StorageBackend::new_file_with_path(SyncMode::None, temp_dir.clone()).unwrap(),
+    );
+
+    let records = new_queue.poll_records(&topic_name, None).unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(
+        new_queue
+            .get_consumer_group_offset("billing", &topic_name)
+            .unwrap(),
+        1
+    );
+}
+
 #[test]
 fn test_offset_continuation_after_recovery() {
     // Setup
@@ -38,6 +38,7 @@ fn test_segment_rolling() {
         value: "a".repeat(100).to_string(),
         key: None,
         headers: None,
+        create_time: None,
     };
 
     {
@@ -60,3 +61,145 @@ fn test_segment_rolling() {
     let active_segment = sm.active_segment_mut().unwrap();
     assert_eq!(active_segment.base_offset, 2);
 }
+
+#[test]
+fn test_roll_trims_closed_segment_indexes() {
+    let config = TestConfig::new("sm_roll_trims_indexes");
+    let base_dir = config.temp_dir_path().to_path_buf();
+    let indexing_config = IndexingConfig {
+        index_interval_records: 1,
+        max_index_bytes: 4096,
+        ..IndexingConfig::default()
+    };
+    let mut sm = SegmentManager::new(base_dir.clone(), 1024, SyncMode::Immediate, indexing_config);
+    sm.roll_to_new_segment(0).unwrap();
+
+    for offset in 0..3 {
+        let record = Record::new(None, format!("value-{offset}"), None);
+        sm.active_segment_mut()
+            .unwrap()
+            .append_record(&record, offset)
+            .unwrap();
+    }
+    sm.roll_to_new_segment(3).unwrap();
+
+    let closed_index = base_dir.join(format!("{:020}.index", 0));
+    let active_index = base_dir.join(format!("{:020}.index", 3));
+    assert!(std::fs::metadata(closed_index).unwrap().len() <= 3 * 8);
+    assert_eq!(std::fs::metadata(active_index).unwrap().len(), 4096);
+}
+
+#[test]
+fn test_should_roll_when_active_segment_is_older_than_segment_ms() {
+    let config = TestConfig::new("sm_roll_segment_ms");
+    let mut sm = SegmentManager::new(
+        config.temp_dir_path().to_path_buf(),
+        1024 * 1024,
+        SyncMode::Immediate,
+        IndexingConfig::default(),
+    );
+    sm.set_roll_limits(Some(60_000), None);
+    sm.roll_to_new_segment(0).unwrap();
+
+    // An empty segment has no first timestamp and never rolls by age
+    assert!(!sm.should_roll_segment_at(u64::MAX));
+
+    let record = Record::new(None, "value".to_string(), None);
+    sm.active_segment_mut()
+        .unwrap()
+        .append_record(&record, 0)
+        .unwrap();
+    let first_ts_ms = sm.active_segment_mut().unwrap().first_ts_ms.unwrap();
+
+    assert!(!sm.should_roll_segment_at(first_ts_ms + 59_999));
+    assert!(sm.should_roll_segment_at(first_ts_ms + 60_000));
+}
+
+#[test]
+fn test_first_timestamp_survives_recovery() {
+    let config = TestConfig::new("sm_first_ts_recovery");
+    let base_dir = config.temp_dir_path().to_path_buf();
+
+    let first_ts_ms = {
+        let mut sm = SegmentManager::new(
+            base_dir.clone(),
+            1024 * 1024,
+            SyncMode::Immediate,
+            IndexingConfig::default(),
+        );
+        sm.roll_to_new_segment(0).unwrap();
+        let active = sm.active_segment_mut().unwrap();
+        for offset in 0..3 {
+            let record = Record::new(None, format!("value-{offset}"), None);
+            active.append_record(&record, offset).unwrap();
+        }
+        active.first_ts_ms.unwrap()
+    };
+
+    let mut sm = SegmentManager::new(
+        base_dir,
+        1024 * 1024,
+        SyncMode::Immediate,
+        IndexingConfig::default(),
+    );
+    sm.set_roll_limits(Some(1_000), None);
+    sm.recover_from_directory().unwrap();
+
+    assert_eq!(
+        sm.active_segment_mut().unwrap().first_ts_ms,
+        Some(first_ts_ms)
+    );
+    assert!(sm.should_roll_segment_at(first_ts_ms + 1_000));
+}
+
+#[test]
+fn test_should_roll_when_index_reaches_max_entries() {
+    let config = TestConfig::new("sm_roll_index_entries");
+    let indexing_config = IndexingConfig {
+        index_interval_records: 1,
+        ..IndexingConfig::default()
+    };
+    let mut sm = SegmentManager::new(
+        config.temp_dir_path().to_path_buf(),
+        1024 * 1024,
+        SyncMode::Immediate,
+        indexing_config,
+    );
+    sm.set_roll_limits(None, Some(3));
+    sm.roll_to_new_segment(0).unwrap();
+
+    for offset in 0..3 {
+        assert!(!sm.should_roll_segment());
+        let record = Record::new(None, format!("value-{offset}"), None);
+        sm.active_segment_mut()
+            .unwrap()
+            .append_record(&record, offset)
+            .unwrap();
+    }
+    assert!(sm.should_roll_segment());
+}
+
+#[test]
+fn test_should_roll_when_index_file_is_full() {
+    let config = TestConfig::new("sm_roll_index_full");
+    let indexing_config = IndexingConfig {
+        index_interval_records: 1,
+        max_index_bytes: 12, // room for a single entry in either index
+        ..IndexingConfig::default()
+    };
+    let mut sm = SegmentManager::new(
+        config.temp_dir_path().to_path_buf(),
+        1024 * 1024,
+        SyncMode::Immediate,
+        indexing_config,
+    );
+    sm.roll_to_new_segment(0).unwrap();
+    assert!(!sm.should_roll_segment());
+
+    let record = Record::new(None, "value".to_string(), None);
+    sm.active_segment_mut()
+        .unwrap()
+        .append_record(&record, 0)
+        .unwrap();
+    assert!(sm.should_roll_segment());
+}
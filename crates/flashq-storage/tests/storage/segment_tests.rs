@@ -54,11 +54,13 @@ fn test_append_and_recover() {
         value: json!("record1").to_string(),
         key: None,
         headers: None,
+        create_time: None,
     };
     let record2 = Record {
         value: json!("record2").to_string(),
         key: None,
         headers: None,
+        create_time: None,
     };
 
     segment.append_record(&record1, 100).unwrap();
@@ -73,3 +75,111 @@ fn test_append_and_recover() {
     assert_eq!(recovered_segment.max_offset, Some(101));
     assert_eq!(recovered_segment.record_count(), 2);
 }
+
+fn dense_indexing_config() -> IndexingConfig {
+    IndexingConfig {
+        index_interval_bytes: 64,
+        index_interval_records: 1,
+        time_seek_back_bytes: 64,
+        max_index_bytes: 4096,
+    }
+}
+
+fn file_len(path: &std::path::Path) -> u64 {
+    std::fs::metadata(path).unwrap().len()
+}
+
+#[test]
+fn test_index_files_are_preallocated_and_trimmed() {
+    let config = TestConfig::new("segment_index_prealloc");
+    let base_path = config.temp_dir_path().join("00000000000000000000");
+    let index_path = base_path.with_extension("index");
+    let time_index_path = base_path.with_extension("timeindex");
+
+    let mut segment = LogSegment::new(
+        0,
+        base_path.with_extension("log"),
+        index_path.clone(),
+        time_index_path.clone(),
+        SyncMode::Immediate,
+        dense_indexing_config(),
+    )
+    .unwrap();
+    assert_eq!(file_len(&index_path), 4096);
+    assert_eq!(file_len(&time_index_path), 4092); // whole 12-byte entries
+
+    for i in 0..10 {
+        let record = Record::new(None, format!("value-{i}"), None);
+        segment.append_record(&record, i).unwrap();
+    }
+    assert_eq!(file_len(&index_path), 4096);
+
+    segment.trim_indexes().unwrap();
+    let index_len = file_len(&index_path);
+    assert!(index_len > 0 && index_len <= 10 * 8);
+    assert_eq!(index_len % 8, 0);
+    assert_eq!(file_len(&time_index_path) % 12, 0);
+    let position = segment.find_position_for_offset(9).unwrap();
+    drop(segment);
+
+    let recovered =
+        LogSegment::recover(0, base_path, SyncMode::Immediate, dense_indexing_config()).unwrap();
+    assert_eq!(recovered.max_offset, Some(9));
+    assert_eq!(recovered.find_position_for_offset(9), Some(position));
+}
+
+#[test]
+fn test_recover_rebuilds_index_failing_sanity_check() {
+    let config = TestConfig::new("segment_index_sanity");
+    let base_path = config.temp_dir_path().join("00000000000000000000");
+    let index_path = base_path.with_extension("index");
+
+    let mut segment = LogSegment::new(
+        0,
+        base_path.with_extension("log"),
+        index_path.clone(),
+        base_path.with_extension("timeindex"),
+        SyncMode::Immediate,
+        dense_indexing_config(),
+    )
+    .unwrap();
+    for i in 0..5 {
+        let record = Record::new(None, format!("value-{i}"), None);
+        segment.append_record(&record, i).unwrap();
+    }
+    let expected = segment.find_position_for_offset(4);
+    let log_len = segment.size_bytes().unwrap();
+    drop(segment);
+
+    // Point the last entry past the end of the log
+    let mut bytes = std::fs::read(&index_path).unwrap();
+    bytes[4 * 8 + 4..4 * 8 + 8].copy_from_slice(&u32::MAX.to_be_bytes());
+    std::fs::write(&index_path, bytes).unwrap();
+
+    let recovered =
+        LogSegment::recover(0, base_path, SyncMode::Immediate, dense_indexing_config()).unwrap();
+    assert_eq!(recovered.find_position_for_offset(4), expected);
+    assert!((recovered.find_position_for_offset(4).unwrap() as u64) < log_len);
+    assert_eq!(recovered.max_offset, Some(4));
+}
+
+#[test]
+fn test_recover_rejects_unrecognised_segment_format() {
+    let config = TestConfig::new("segment_unknown_format");
+    let base_path = config.temp_dir_path().join("00000000000000000000");
+    std::fs::write(base_path.with_extension("log"), [0xffu8; 64]).unwrap();
+
+    let result = LogSegment::recover(
+        0,
+        base_path.clone(),
+        SyncMode::Immediate,
+        IndexingConfig::default(),
+    );
+    let err = result.err().expect("unreadable segment is rejected");
+    assert!(
+        err.to_string().contains("unsupported segment format"),
+        "unexpected error: {err}"
+    );
+    // The original bytes are left in place
+    assert_eq!(file_len(&base_path.with_extension("log")), 64);
+}
@@ -0,0 +1,145 @@
+use super::test_utilities::*;
+use flashq::Record;
+use flashq_storage::file::FileTopicLog;
+use flashq_storage::file::snapshot::snapshot_data_dir;
+use flashq_storage::{StorageBackend, StorageError, TopicLog};
+use std::io::Write;
+use std::path::Path;
+use test_log::test;
+
+const SMALL_SEGMENT: u64 = 512;
+
+fn open_log(topic: &str, data_dir: &Path) -> FileTopicLog {
+    FileTopicLog::new(
+        topic,
+        flashq_storage::SyncMode::Immediate,
+        data_dir,
+        SMALL_SEGMENT,
+    )
+    .unwrap()
+}
+
+fn append_values(log: &mut FileTopicLog, range: std::ops::Range<u64>) {
+    for i in range {
+        log.append(Record::new(
+            None,
+            format!("value-{i}-{}", big_val(64)),
+            None,
+        ))
+        .unwrap();
+    }
+}
+
+fn is_hard_linked(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).unwrap().nlink() > 1
+}
+
+#[test]
+fn test_snapshot_links_closed_segments_and_copies_active_one() {
+    let config = TestConfig::new("snapshot_live");
+    let dest = create_test_dir("snapshot_live_dest");
+    let dest_dir = dest.path().join("snap");
+    let mut log = open_log(&config.topic_name, config.temp_dir_path());
+    append_values(&mut log, 0..20);
+
+    let summary = snapshot_data_dir(config.temp_dir_path(), &dest_dir).unwrap();
+
+    // Appends after the snapshot must not show up in it
+    append_values(&mut log, 20..25);
+
+    assert_eq!(summary.partitions.len(), 1);
+    let partition = &summary.partitions[0];
+    assert_eq!(partition.topic, config.topic_name);
+    assert_eq!(partition.next_offset, 20);
+    assert!(partition.linked_segments >= 1);
+
+    let snap_partition = dest_dir.join(&config.topic_name).join("0");
+    let first_log = snap_partition.join(format!("{:020}.log", 0));
+    assert!(is_hard_linked(&first_log));
+
+    let restored = open_log(&config.topic_name, &dest_dir);
+    assert_eq!(restored.next_offset(), 20);
+    let records = restored.get_records_from_offset(0, None).unwrap();
+    assert_eq!(records.len(), 20);
+    assert!(records[19].record.value.starts_with("value-19-"));
+    assert_eq!(log.next_offset(), 25);
+}
+
+#[test]
+fn test_snapshot_cuts_active_segment_at_last_complete_batch() {
+    let config = TestConfig::new("snapshot_torn");
+    let dest = create_test_dir("snapshot_torn_dest");
+    let mut log = open_log(&config.topic_name, config.temp_dir_path());
+    append_values(&mut log, 0..3);
+    drop(log);
+
+    // Simulate a batch that is only partly written when the snapshot runs
+    let partition_dir = config.temp_dir_path().join(&config.topic_name).join("0");
+    let active = std::fs::read_dir(&partition_dir)
+        .unwrap()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "log"))
+        .max()
+        .unwrap();
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&active)
+        .unwrap();
+    file.write_all(&[0, 0, 0, 0, 0, 0, 0, 3, 0, 0]).unwrap();
+
+    let summary = snapshot_data_dir(config.temp_dir_path(), &dest.path().join("snap")).unwrap();
+
+    assert_eq!(summary.partitions[0].next_offset, 3);
+    let restored = open_log(&config.topic_name, &dest.path().join("snap"));
+    assert_eq!(restored.get_records_from_offset(0, None).unwrap().len(), 3);
+}
+
+#[test]
+fn test_snapshot_copies_consumer_groups_and_opens_as_data_dir() {
+    let config = TestConfig::new("snapshot_groups");
+    let dest = create_test_dir("snapshot_groups_dest");
+    let dest_dir = dest.path().join("snap");
+    let group_id = create_test_consumer_group("snapshot");
+
+    let backend =
+        StorageBackend::new_file_with_path(config.sync_mode, config.temp_dir_path()).unwrap();
+    let topic = backend.create(&config.topic_name).unwrap();
+    for i in 0..5 {
+        topic
+            .write()
+            .append(Record::new(None, format!("value-{i}"), None))
+            .unwrap();
+    }
+    let group = backend.create_consumer_group(&group_id).unwrap();
+    group.write().set_offset(config.topic_name.clone(), 4);
+
+    let summary = snapshot_data_dir(config.temp_dir_path(), &dest_dir).unwrap();
+    assert_eq!(summary.consumer_group_files, 1);
+    drop(group);
+    drop(topic);
+    drop(backend);
+
+    let restored = StorageBackend::new_file_with_path(config.sync_mode, &dest_dir).unwrap();
+    assert_eq!(
+        restored.discover_topics().unwrap(),
+        vec![config.topic_name.clone()]
+    );
+    let topic = restored.create(&config.topic_name).unwrap();
+    assert_eq!(topic.read().next_offset(), 5);
+    let group = restored.create_consumer_group(&group_id).unwrap();
+    assert_eq!(group.read().get_offset(&config.topic_name), 4);
+}
+
+#[test]
+fn test_snapshot_refuses_non_empty_destination() {
+    let config = TestConfig::new("snapshot_dest");
+    let dest = create_test_dir("snapshot_dest_busy");
+    std::fs::write(dest.path().join("existing"), b"keep me").unwrap();
+
+    let result = snapshot_data_dir(config.temp_dir_path(), dest.path());
+
+    assert!(matches!(result, Err(StorageError::Unavailable { .. })));
+    assert!(dest.path().join("existing").exists());
+}
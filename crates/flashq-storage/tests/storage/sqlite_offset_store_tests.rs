@@ -0,0 +1,171 @@
+use super::test_utilities::*;
+use flashq::{FlashQ, Record};
+use flashq_storage::file::SqliteOffsetDatabase;
+use flashq_storage::file::snapshot::snapshot_data_dir;
+use flashq_storage::{PartitionId, StorageBackend, SyncMode};
+use std::path::Path;
+use test_log::test;
+
+fn sqlite_backend(data_dir: &Path) -> StorageBackend {
+    StorageBackend::new_file_with_path(SyncMode::Immediate, data_dir)
+        .unwrap()
+        .with_sqlite_offset_store()
+        .unwrap()
+}
+
+fn commit_json_offsets(data_dir: &Path, group_id: &str, offsets: &[(&str, u32, u64)]) {
+    let backend = StorageBackend::new_file_with_path(SyncMode::Immediate, data_dir).unwrap();
+    let store = backend.create_consumer_offset_store(group_id).unwrap();
+    for (topic, partition, offset) in offsets {
+        store
+            .persist_snapshot(topic.to_string(), PartitionId::new(*partition), *offset)
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_migration_imports_json_groups_and_renames_files() {
+    let config = TestConfig::new("sqlite_migrate");
+    let data_dir = config.temp_dir_path();
+    commit_json_offsets(data_dir, "orders", &[("a", 0, 12), ("a", 1, 4)]);
+    commit_json_offsets(data_dir, "billing", &[("b", 0, 30)]);
+
+    let backend = sqlite_backend(data_dir);
+
+    let groups_dir = data_dir.join("consumer_groups");
+    assert!(!groups_dir.join("orders.json").exists());
+    assert!(groups_dir.join("orders.json.migrated").exists());
+    assert!(groups_dir.join("billing.json.migrated").exists());
+    assert_eq!(
+        backend.discover_consumer_groups().unwrap(),
+        vec!["billing".to_string(), "orders".to_string()]
+    );
+
+    let store = backend.create_consumer_offset_store("orders").unwrap();
+    assert_eq!(store.load_snapshot("a", PartitionId::new(0)).unwrap(), 12);
+    assert_eq!(store.load_snapshot("a", PartitionId::new(1)).unwrap(), 4);
+    let store = backend.create_consumer_offset_store("billing").unwrap();
+    assert_eq!(store.load_snapshot("b", PartitionId::new(0)).unwrap(), 30);
+}
+
+#[test]
+fn test_migration_never_moves_offsets_back() {
+    let config = TestConfig::new("sqlite_migrate_stale");
+    let data_dir = config.temp_dir_path();
+    {
+        let backend = sqlite_backend(data_dir);
+        let store = backend.create_consumer_offset_store("orders").unwrap();
+        store
+            .persist_snapshot("a".to_string(), PartitionId::new(0), 50)
+            .unwrap();
+    }
+    // A JSON file left behind by a broker started without the SQLite store
+    commit_json_offsets(data_dir, "orders", &[("a", 0, 20), ("a", 1, 8)]);
+
+    let backend = sqlite_backend(data_dir);
+    let store = backend.create_consumer_offset_store("orders").unwrap();
+
+    assert_eq!(store.load_snapshot("a", PartitionId::new(0)).unwrap(), 50);
+    assert_eq!(store.load_snapshot("a", PartitionId::new(1)).unwrap(), 8);
+}
+
+#[test]
+fn test_batch_commits_span_groups_in_one_transaction() {
+    let config = TestConfig::new("sqlite_batch");
+    let db = SqliteOffsetDatabase::open(config.temp_dir_path(), SyncMode::Immediate).unwrap();
+
+    let applied = db
+        .persist_batch(&[
+            ("g1", "t".to_string(), PartitionId::new(0), 10),
+            ("g2", "t".to_string(), PartitionId::new(0), 3),
+            ("g1", "t".to_string(), PartitionId::new(0), 7),
+        ])
+        .unwrap();
+
+    assert_eq!(applied, vec![true, true, false]);
+}
+
+#[test]
+fn test_offsets_survive_restart() {
+    let config = TestConfig::new("sqlite_restart");
+    let data_dir = config.temp_dir_path();
+    {
+        let backend = sqlite_backend(data_dir);
+        let store = backend.create_consumer_offset_store("orders").unwrap();
+        store
+            .persist_snapshots(vec![
+                ("a".to_string(), PartitionId::new(0), 5),
+                ("a".to_string(), PartitionId::new(2), 9),
+            ])
+            .unwrap();
+    }
+
+    let backend = sqlite_backend(data_dir);
+    let store = backend.create_consumer_offset_store("orders").unwrap();
+
+    assert_eq!(store.get_all_snapshots().unwrap().len(), 2);
+    assert_eq!(store.load_snapshot("a", PartitionId::new(2)).unwrap(), 9);
+    assert!(
+        !data_dir
+            .join("consumer_groups")
+            .join("orders.json")
+            .exists(),
+        "the SQLite store must not write JSON offset files"
+    );
+}
+
+#[test]
+fn test_flashq_recovers_groups_from_sqlite_store() {
+    let config = TestConfig::new("sqlite_flashq");
+    let data_dir = config.temp_dir_path();
+    {
+        let queue = FlashQ::with_storage_backend(sqlite_backend(data_dir));
+        queue
+            .post_records(
+                "orders".to_string(),
+                (0..5)
+                    .map(|i| Record::new(None, format!("v{i}"), None))
+                    .collect(),
+            )
+            .unwrap();
+        queue.create_consumer_group("idle".to_string()).unwrap();
+        queue.create_consumer_group("workers".to_string()).unwrap();
+        queue
+            .update_consumer_group_offset("workers", "orders".to_string(), 3)
+            .unwrap();
+    }
+
+    let queue = FlashQ::with_storage_backend(sqlite_backend(data_dir));
+
+    assert_eq!(
+        queue
+            .get_consumer_group_offset("workers", "orders")
+            .unwrap(),
+        3
+    );
+    assert_eq!(
+        queue.get_consumer_group_offset("idle", "orders").unwrap(),
+        0
+    );
+}
+
+#[test]
+fn test_snapshot_copies_offset_database() {
+    let config = TestConfig::new("sqlite_snapshot");
+    let data_dir = config.temp_dir_path();
+    let backend = sqlite_backend(data_dir);
+    backend
+        .create_consumer_offset_store("orders")
+        .unwrap()
+        .persist_snapshot("a".to_string(), PartitionId::new(0), 6)
+        .unwrap();
+    let dest = create_test_dir("sqlite_snapshot_dest");
+
+    let summary = snapshot_data_dir(data_dir, dest.path()).unwrap();
+    drop(backend);
+
+    assert_eq!(summary.consumer_group_files, 1);
+    let restored = sqlite_backend(dest.path());
+    let store = restored.create_consumer_offset_store("orders").unwrap();
+    assert_eq!(store.load_snapshot("a", PartitionId::new(0)).unwrap(), 6);
+}
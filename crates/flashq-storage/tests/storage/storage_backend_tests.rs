@@ -1,7 +1,8 @@
 use super::test_utilities::*;
 use flashq::{FlashQ, Record};
-use flashq_storage::StorageBackend;
 use flashq_storage::file::SyncMode;
+use flashq_storage::{StorageBackend, StorageFactory};
+use std::sync::Arc;
 use test_log::test;
 
 #[test]
@@ -123,3 +124,96 @@ fn test_file_storage_vs_memory_storage_interface() {
         file_group.read().get_offset("any_topic")
     );
 }
+
+#[test]
+fn test_io_pool_matches_backend_kind() {
+    let config = TestConfig::new("io_pool_kind");
+    let memory_backend = StorageBackend::new_memory().with_io_threads(4);
+    let file_backend = StorageBackend::new_file_with_path(config.sync_mode, config.temp_dir_path())
+        .unwrap()
+        .with_io_threads(2);
+
+    assert!(memory_backend.create_io_pool().unwrap().is_inline());
+    assert!(!file_backend.create_io_pool().unwrap().is_inline());
+}
+
+#[test]
+fn test_factory_lists_persisted_topics_and_groups() {
+    let config = TestConfig::new("factory_listing");
+    let topic_name = config.topic_name.clone();
+    let file_backend =
+        StorageBackend::new_file_with_path(config.sync_mode, config.temp_dir_path()).unwrap();
+    let memory_backend = StorageBackend::new_memory();
+
+    for backend in [&file_backend, &memory_backend] {
+        let log = backend.create_topic_log(&topic_name).unwrap();
+        log.write()
+            .append(Record::new(None, "value".to_string(), None))
+            .unwrap();
+        backend.create_group("group_b").unwrap();
+        backend.create_group("group_a").unwrap();
+    }
+
+    assert_eq!(file_backend.list_topics().unwrap(), vec![topic_name]);
+    assert_eq!(
+        file_backend.list_consumer_groups().unwrap(),
+        vec!["group_a".to_string(), "group_b".to_string()]
+    );
+    assert!(memory_backend.list_topics().unwrap().is_empty());
+    assert!(memory_backend.list_consumer_groups().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_async_api_runs_file_io_off_caller_thread() {
+    let config = TestConfig::new("async_file_io");
+    let topic_name = config.topic_name.clone();
+    let group_id = create_test_consumer_group("async_io");
+    let queue = Arc::new(FlashQ::with_storage_backend(
+        StorageBackend::new_file_with_path(config.sync_mode, config.temp_dir_path())
+            .unwrap()
+            .with_io_threads(2),
+    ));
+
+    let records = (0..10)
+        .map(|i| Record::new(None, format!("value_{i}"), None))
+        .collect();
+    let last = queue
+        .post_records_async(topic_name.clone(), records, None)
+        .await
+        .unwrap();
+    assert_eq!(last, 9);
+
+    let polled = queue
+        .poll_records_from_offset_async(topic_name.clone(), 4, Some(3))
+        .await
+        .unwrap();
+    let offsets: Vec<u64> = polled.iter().map(|r| r.offset).collect();
+    assert_eq!(offsets, vec![4, 5, 6]);
+
+    let by_time = queue
+        .poll_records_from_time_async(topic_name.clone(), polled[0].timestamp.clone(), None)
+        .await
+        .unwrap();
+    assert!(by_time.len() >= 6);
+
+    queue
+        .create_consumer_group_async(group_id.clone())
+        .await
+        .unwrap();
+    queue
+        .update_consumer_group_offset_async(group_id.clone(), topic_name.clone(), 7)
+        .await
+        .unwrap();
+    assert_eq!(
+        queue
+            .get_consumer_group_offset(&group_id, &topic_name)
+            .unwrap(),
+        7
+    );
+
+    let worker = queue
+        .run_blocking(|_| Ok(std::thread::current().name().map(str::to_string)))
+        .await
+        .unwrap();
+    assert!(worker.unwrap().starts_with("flashq-io-"));
+}
@@ -0,0 +1,249 @@
+use super::test_utilities::*;
+use flashq::{FlashQ, Record};
+use flashq_storage::file::FileTopicLog;
+use flashq_storage::{
+    FsRemoteSegmentStore, PartitionId, StorageBackend, TieredStorageConfig, TopicLog,
+};
+use std::path::Path;
+use std::sync::Arc;
+use test_log::test;
+
+const SMALL_SEGMENT: u64 = 512;
+
+fn record(i: usize) -> Record {
+    Record::new(
+        Some(format!("key-{i}")),
+        format!("{{\"n\":{i},\"pad\":\"{}\"}}", big_val(100)),
+        None,
+    )
+}
+
+fn tiered_config(remote_root: &Path, local_retention_ms: u64) -> TieredStorageConfig {
+    TieredStorageConfig::new(Arc::new(FsRemoteSegmentStore::new(remote_root).unwrap()))
+        .with_local_retention_ms(local_retention_ms)
+        .with_cache_segments(2)
+}
+
+fn open_log(config: &TestConfig, tiered: TieredStorageConfig) -> FileTopicLog {
+    FileTopicLog::new(
+        &config.topic_name,
+        config.sync_mode,
+        config.temp_dir_path(),
+        SMALL_SEGMENT,
+    )
+    .unwrap()
+    .with_tiered_storage(tiered)
+    .unwrap()
+}
+
+fn count_files(dir: &Path, extension: &str) -> usize {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().extension().is_some_and(|ext| ext == extension))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+#[test]
+fn test_closed_segments_are_offloaded_after_retention() {
+    let config = TestConfig::new("tiered_offload");
+    let remote_root = config.temp_dir_path().join("remote");
+    let mut log = open_log(&config, tiered_config(&remote_root, 0));
+
+    for i in 0..30 {
+        log.append(record(i)).unwrap();
+    }
+
+    let partition_dir = config.temp_dir_path().join(&config.topic_name).join("0");
+    let remote_dir = remote_root.join(&config.topic_name).join("0");
+
+    // Only the active segment stays local; every closed one is in the remote store
+    assert_eq!(count_files(&partition_dir, "log"), 1);
+    assert!(count_files(&remote_dir, "log") > 1);
+    assert_eq!(
+        count_files(&remote_dir, "log"),
+        count_files(&remote_dir, "timeindex")
+    );
+    assert!(partition_dir.join("remote_segments.json").exists());
+    assert!(log.local_log_start_offset(PartitionId(0)).unwrap() > 0);
+}
+
+#[test]
+fn test_reads_before_local_log_start_fetch_remote_segments() {
+    let config = TestConfig::new("tiered_read");
+    let remote_root = config.temp_dir_path().join("remote");
+    let mut log = open_log(&config, tiered_config(&remote_root, 0));
+
+    for i in 0..30 {
+        log.append(record(i)).unwrap();
+    }
+
+    let all = log.get_records_from_offset(0, None).unwrap();
+    let offsets: Vec<u64> = all.iter().map(|r| r.offset).collect();
+    assert_eq!(offsets, (0..30).collect::<Vec<u64>>());
+    assert_eq!(all[3].record, record(3));
+
+    let middle = log.get_records_from_offset(5, Some(4)).unwrap();
+    let offsets: Vec<u64> = middle.iter().map(|r| r.offset).collect();
+    assert_eq!(offsets, vec![5, 6, 7, 8]);
+
+    let first_ts = all[0].timestamp.clone();
+    let by_time = log.get_records_from_timestamp(&first_ts, None).unwrap();
+    assert_eq!(by_time.len(), 30);
+    assert_eq!(by_time[0].offset, 0);
+
+    // Only a bounded number of fetched segments remain cached on disk
+    let cache_dir = config
+        .temp_dir_path()
+        .join(&config.topic_name)
+        .join("0")
+        .join("remote_cache");
+    assert!(count_files(&cache_dir, "log") <= 2);
+}
+
+#[test]
+fn test_list_offsets_span_remote_and_local_segments() {
+    let config = TestConfig::new("tiered_list_offsets");
+    let remote_root = config.temp_dir_path().join("remote");
+    let mut log = open_log(&config, tiered_config(&remote_root, 0));
+
+    for i in 0..30 {
+        log.append(record(i)).unwrap();
+    }
+    let partition = PartitionId(0);
+    let all = log.get_records_from_offset(0, None).unwrap();
+
+    // The log starts in the remote tier, not at the local log start
+    assert!(log.local_log_start_offset(partition).unwrap() > 0);
+    assert_eq!(log.partition_start_offset(partition).unwrap(), 0);
+
+    let first_ts = all[0].timestamp.clone();
+    assert_eq!(
+        log.partition_offset_for_timestamp(partition, &first_ts)
+            .unwrap(),
+        Some(0)
+    );
+    let last = all.last().unwrap();
+    let parse = |ts: &str| chrono::DateTime::parse_from_rfc3339(ts).unwrap();
+    let expected = all
+        .iter()
+        .find(|r| parse(&r.timestamp) >= parse(&last.timestamp))
+        .map(|r| r.offset);
+    assert_eq!(
+        log.partition_offset_for_timestamp(partition, &last.timestamp)
+            .unwrap(),
+        expected
+    );
+    let later = (parse(&last.timestamp) + chrono::Duration::seconds(60)).to_rfc3339();
+    assert_eq!(
+        log.partition_offset_for_timestamp(partition, &later)
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_remote_metadata_survives_restart() {
+    let config = TestConfig::new("tiered_restart");
+    let remote_root = config.temp_dir_path().join("remote");
+
+    {
+        let mut log = open_log(&config, tiered_config(&remote_root, 0));
+        for i in 0..30 {
+            log.append(record(i)).unwrap();
+        }
+        log.sync().unwrap();
+    }
+
+    let mut log = open_log(&config, tiered_config(&remote_root, 0));
+    assert_eq!(log.next_offset(), 30);
+    assert_eq!(log.len(), 30);
+
+    let head = log.get_records_from_offset(0, Some(3)).unwrap();
+    let offsets: Vec<u64> = head.iter().map(|r| r.offset).collect();
+    assert_eq!(offsets, vec![0, 1, 2]);
+
+    assert_eq!(log.append(record(30)).unwrap(), 30);
+}
+
+#[test]
+fn test_segments_within_retention_stay_local() {
+    let config = TestConfig::new("tiered_retained");
+    let remote_root = config.temp_dir_path().join("remote");
+    let mut log = open_log(&config, tiered_config(&remote_root, 60 * 60 * 1000));
+
+    for i in 0..30 {
+        log.append(record(i)).unwrap();
+    }
+
+    assert_eq!(log.offload_expired_segments().unwrap(), 0);
+    assert_eq!(log.local_log_start_offset(PartitionId(0)), Some(0));
+    let remote_dir = remote_root.join(&config.topic_name).join("0");
+    assert_eq!(count_files(&remote_dir, "log"), 0);
+}
+
+#[test]
+fn test_backend_attaches_tiered_storage_to_topics() {
+    let config = TestConfig::new("tiered_backend");
+    let remote_root = config.temp_dir_path().join("remote");
+    let backend = StorageBackend::new_file_with_config(
+        config.sync_mode,
+        config.temp_dir_path().join("data"),
+        1000,
+        SMALL_SEGMENT,
+    )
+    .unwrap()
+    .with_tiered_storage(tiered_config(&remote_root, 0));
+
+    let log = backend.create("orders").unwrap();
+    for i in 0..30 {
+        log.write().append(record(i)).unwrap();
+    }
+
+    assert!(count_files(&remote_root.join("orders").join("0"), "log") > 0);
+    assert_eq!(
+        log.read().get_records_from_offset(0, None).unwrap().len(),
+        30
+    );
+}
+
+#[test]
+fn test_idle_topic_offloads_when_swept() {
+    let config = TestConfig::new("tiered_sweep");
+    let remote_root = config.temp_dir_path().join("remote");
+    let backend = StorageBackend::new_file_with_config(
+        config.sync_mode,
+        config.temp_dir_path().join("data"),
+        1000,
+        SMALL_SEGMENT,
+    )
+    .unwrap()
+    .with_tiered_storage(tiered_config(&remote_root, 200));
+    let queue = FlashQ::with_storage_backend(backend);
+
+    for i in 0..30 {
+        queue
+            .post_records("orders".to_string(), vec![record(i)])
+            .unwrap();
+    }
+    let remote_dir = remote_root.join("orders").join("0");
+    assert_eq!(queue.offload_expired_segments(), 0);
+    assert_eq!(count_files(&remote_dir, "log"), 0);
+
+    // No further appends, so no roll: only the sweep moves the now-expired segments
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    let offloaded = queue.offload_expired_segments();
+    assert!(offloaded > 0);
+    assert_eq!(count_files(&remote_dir, "log"), offloaded);
+    assert_eq!(queue.offload_expired_segments(), 0);
+    assert_eq!(
+        queue
+            .poll_records_from_offset("orders", 0, None)
+            .unwrap()
+            .len(),
+        30
+    );
+}
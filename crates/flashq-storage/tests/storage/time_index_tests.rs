@@ -127,7 +127,7 @@ fn test_save_time_index_to_file() {
     let mut writer = std::io::BufWriter::new(&mut file);
 
     if let Some(entry) = index.last_entry() {
-        let result = index.write_entry_to_file(&mut writer, entry);
+        let result = index.write_entry_to_file(&mut writer, &entry);
         assert!(result.is_ok());
     }
 
@@ -182,7 +182,7 @@ fn test_save_load_time_index_round_trip() {
 
     if let Some(entry) = original_index.last_entry() {
         original_index
-            .write_entry_to_file(&mut writer, entry)
+            .write_entry_to_file(&mut writer, &entry)
             .expect("Failed to write entry");
     }
     drop(writer);
@@ -2,9 +2,9 @@ use super::test_utilities::*;
 use chrono::{DateTime, Duration, FixedOffset};
 use flashq::Record;
 use flashq::RecordWithOffset;
-use flashq_storage::TopicLog;
 use flashq_storage::backend::StorageBackend;
-use flashq_storage::file::{FileTopicLog, SyncMode};
+use flashq_storage::file::{FileTopicLog, IndexingConfig, SyncMode};
+use flashq_storage::{PartitionId, ReadBounds, StorageError, TimestampType, TopicConfig, TopicLog};
 use std::thread;
 use std::time::Duration as StdDuration;
 use test_log::test;
@@ -316,3 +316,164 @@ fn spans_segments_returns_only_second_batch_offsets() {
     assert_eq!(got[0].offset, start2);
     assert_eq!(got.last().unwrap().offset, start2 + (n2 as u64) - 1);
 }
+
+fn producer_time(i: u64) -> DateTime<FixedOffset> {
+    // A permutation of 200 seconds, so timestamps go backwards between and within batches
+    DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap()
+        + Duration::seconds(((i * 7) % 200) as i64)
+}
+
+fn open_indexed_log(config: &TestConfig, topic_config: TopicConfig) -> FileTopicLog {
+    let indexing = IndexingConfig {
+        index_interval_bytes: 64,
+        index_interval_records: 1,
+        time_seek_back_bytes: 64,
+        ..Default::default()
+    };
+    FileTopicLog::new_with_batch_bytes_and_indexing_config(
+        &config.topic_name,
+        config.sync_mode,
+        config.temp_dir_path(),
+        config.segment_size,
+        1024,
+        indexing,
+    )
+    .unwrap()
+    .with_topic_config(topic_config)
+}
+
+#[test]
+fn out_of_order_producer_timestamps_are_found_before_and_after_index_rebuild() {
+    // Setup: every batch is indexed and carries producer timestamps that are not monotonic
+    let config = TestConfig::new("time_poll_out_of_order");
+    let target = producer_time(0) + Duration::seconds(100);
+    let expected: Vec<u64> = (0..200).filter(|&i| producer_time(i) >= target).collect();
+    {
+        let mut log = open_indexed_log(&config, TopicConfig::default());
+        for start in (0..200).step_by(5) {
+            let batch = (start..start + 5)
+                .map(|i| {
+                    Record::new(None, format!("r{i}"), None)
+                        .with_create_time(producer_time(i).to_rfc3339())
+                })
+                .collect();
+            log.append_batch(batch).unwrap();
+        }
+
+        // Action: poll while the time index is the one built on append
+        let got = log
+            .get_records_from_timestamp(&target.to_rfc3339(), None)
+            .unwrap();
+
+        // Expectation: every record at or after the target, none before it
+        let offsets: Vec<u64> = got.iter().map(|r| r.offset).collect();
+        assert_eq!(offsets, expected);
+        log.sync().unwrap();
+    }
+
+    // Action: rebuild the time index from the log on reopen and poll again
+    let partition_dir = config.temp_dir_path().join(&config.topic_name).join("0");
+    std::fs::remove_file(partition_dir.join("00000000000000000000.timeindex")).unwrap();
+    let log = open_indexed_log(&config, TopicConfig::default());
+    let got = log
+        .get_records_from_timestamp(&target.to_rfc3339(), None)
+        .unwrap();
+
+    // Expectation: the rebuilt index gives the same answer
+    let offsets: Vec<u64> = got.iter().map(|r| r.offset).collect();
+    assert_eq!(offsets, expected);
+}
+
+#[test]
+fn time_range_and_end_offset_bound_out_of_order_reads() {
+    // Setup: indexed batches whose producer timestamps are not monotonic
+    let config = TestConfig::new("time_poll_bounded");
+    let mut log = open_indexed_log(&config, TopicConfig::default());
+    for start in (0..200).step_by(5) {
+        let batch = (start..start + 5)
+            .map(|i| {
+                Record::new(None, format!("r{i}"), None)
+                    .with_create_time(producer_time(i).to_rfc3339())
+            })
+            .collect();
+        log.append_batch(batch).unwrap();
+    }
+    let from = producer_time(0) + Duration::seconds(50);
+    let to = producer_time(0) + Duration::seconds(120);
+    let bounds = ReadBounds::default()
+        .with_to_time(&to.to_rfc3339())
+        .unwrap()
+        .with_to_offset(150);
+
+    // Action: read the time range and the offset range with both bounds
+    let by_time = log
+        .read_from_partition_timestamp_bounded(PartitionId(0), &from.to_rfc3339(), bounds, None)
+        .unwrap();
+    let by_offset = log
+        .read_from_partition_bounded(PartitionId(0), 20, bounds, None)
+        .unwrap();
+
+    // Expectation: only records inside [from, to) and below offset 150, in offset order
+    let in_time = |i: &u64| producer_time(*i) < to;
+    let expected: Vec<u64> = (0..150)
+        .filter(|&i| producer_time(i) >= from)
+        .filter(in_time)
+        .collect();
+    assert_eq!(
+        by_time.iter().map(|r| r.offset).collect::<Vec<_>>(),
+        expected
+    );
+    let expected: Vec<u64> = (20..150).filter(in_time).collect();
+    assert_eq!(
+        by_offset.iter().map(|r| r.offset).collect::<Vec<_>>(),
+        expected
+    );
+}
+
+#[test]
+fn log_append_time_ignores_producer_timestamps() {
+    // Setup: a LogAppendTime topic and a record claiming to be from 2001
+    let config = TestConfig::new("time_poll_log_append");
+    let mut log = open_indexed_log(
+        &config,
+        TopicConfig::default().with_timestamp_type(TimestampType::LogAppendTime),
+    );
+    let before = chrono::Utc::now();
+
+    // Action: append it
+    log.append(Record::new(None, "r".to_string(), None).with_create_time("2001-01-01T00:00:00Z"))
+        .unwrap();
+
+    // Expectation: the broker clock is stored instead
+    let stored = log.get_records_from_offset(0, None).unwrap();
+    let timestamp = DateTime::parse_from_rfc3339(&stored[0].timestamp).unwrap();
+    assert!(timestamp >= before);
+    assert_eq!(stored[0].record.create_time, None);
+}
+
+#[test]
+fn skewed_producer_timestamp_rejects_the_whole_batch() {
+    // Setup: a topic allowing one minute of skew and a batch with one record an hour old
+    let config = TestConfig::new("time_poll_skew");
+    let mut log = open_indexed_log(
+        &config,
+        TopicConfig::default().with_max_timestamp_skew_ms(60_000),
+    );
+    let now = chrono::Utc::now();
+    let batch = vec![
+        Record::new(None, "fresh".to_string(), None).with_create_time(now.to_rfc3339()),
+        Record::new(None, "stale".to_string(), None)
+            .with_create_time((now - Duration::hours(1)).to_rfc3339()),
+    ];
+
+    // Action: append the batch, then a record without a timestamp
+    let result = log.append_batch(batch);
+    log.append(Record::new(None, "plain".to_string(), None))
+        .unwrap();
+
+    // Expectation: nothing from the rejected batch was written
+    assert!(matches!(result, Err(StorageError::InvalidTimestamp(_))));
+    let stored = log.get_records_from_offset(0, None).unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].record.value, "plain");
+}
@@ -0,0 +1,984 @@
+//! Topic export and import.
+//!
+//! An archive holds one topic: a header, the exported records of each partition, then the
+//! committed offsets of every consumer group on that topic. Two encodings are supported:
+//!
+//! - **NDJSON**: one JSON object per line, tagged by `type` (`header`, `record`, `group_offset`).
+//!   Easy to inspect and to produce from other systems.
+//! - **Binary**: an 8-byte magic followed by `[1B kind][4B len][body]` frames. Records travel
+//!   as storage record batches (CRC-checked, optionally compressed), one per run of
+//!   consecutive offsets sharing a timestamp.
+//!
+//! Import detects the encoding from the first bytes of the archive.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::Arc;
+
+use chrono::{DateTime, FixedOffset};
+use flashq_storage::file::batch::{encode_batch_into, read_batch};
+use flashq_storage::file::snapshot::SnapshotSummary;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    CompressionCodec, FlashQ, FlashQError, PartitionId, Record, RecordWithOffset, TopicLog, debug,
+    info,
+};
+
+/// Leading bytes of a binary archive.
+pub const BINARY_ARCHIVE_MAGIC: &[u8; 8] = b"FQARCHV1";
+const ARCHIVE_VERSION: u32 = 1;
+
+const FRAME_HEADER: u8 = 1;
+const FRAME_RECORDS: u8 = 2;
+const FRAME_GROUP_OFFSET: u8 = 3;
+
+/// Records read from the topic, and written on import, per storage call.
+const CHUNK_RECORDS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    #[default]
+    Ndjson,
+    Binary,
+}
+
+impl std::str::FromStr for ArchiveFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ndjson" | "json" => Ok(ArchiveFormat::Ndjson),
+            "binary" | "bin" => Ok(ArchiveFormat::Binary),
+            other => Err(format!("unknown archive format '{other}'")),
+        }
+    }
+}
+
+/// Which part of a topic to export and how to encode it.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub format: ArchiveFormat,
+    /// Codec for record batches in binary archives; ignored for NDJSON.
+    pub compression: CompressionCodec,
+    /// Partitions to export; all of the topic's partitions when unset.
+    pub partitions: Option<Vec<PartitionId>>,
+    /// First offset to export.
+    pub from_offset: Option<u64>,
+    /// Offset to stop before.
+    pub to_offset: Option<u64>,
+    /// Earliest record timestamp to export (RFC3339).
+    pub from_time: Option<String>,
+    /// Record timestamp to stop before (RFC3339).
+    pub to_time: Option<String>,
+    pub include_consumer_groups: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            format: ArchiveFormat::default(),
+            compression: CompressionCodec::None,
+            partitions: None,
+            from_offset: None,
+            to_offset: None,
+            from_time: None,
+            to_time: None,
+            include_consumer_groups: true,
+        }
+    }
+}
+
+impl ExportOptions {
+    pub fn with_format(mut self, format: ArchiveFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: CompressionCodec) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn with_partitions(mut self, partitions: Vec<PartitionId>) -> Self {
+        self.partitions = Some(partitions);
+        self
+    }
+
+    pub fn with_offset_range(mut self, from_offset: Option<u64>, to_offset: Option<u64>) -> Self {
+        self.from_offset = from_offset;
+        self.to_offset = to_offset;
+        self
+    }
+
+    pub fn with_time_range(mut self, from_time: Option<String>, to_time: Option<String>) -> Self {
+        self.from_time = from_time;
+        self.to_time = to_time;
+        self
+    }
+
+    pub fn without_consumer_groups(mut self) -> Self {
+        self.include_consumer_groups = false;
+        self
+    }
+}
+
+/// How to replay an archive.
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    /// Topic to import into; the archived topic name when unset.
+    pub topic: Option<String>,
+    /// Keep each record's original offset. The target partitions must not have reached them.
+    pub preserve_offsets: bool,
+    /// Keep each record's original timestamp instead of stamping it on import.
+    pub preserve_timestamps: bool,
+    pub restore_consumer_groups: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            topic: None,
+            preserve_offsets: false,
+            preserve_timestamps: false,
+            restore_consumer_groups: true,
+        }
+    }
+}
+
+impl ImportOptions {
+    pub fn into_topic(mut self, topic: impl Into<String>) -> Self {
+        self.topic = Some(topic.into());
+        self
+    }
+
+    pub fn preserve_offsets(mut self) -> Self {
+        self.preserve_offsets = true;
+        self
+    }
+
+    pub fn preserve_timestamps(mut self) -> Self {
+        self.preserve_timestamps = true;
+        self
+    }
+
+    pub fn without_consumer_groups(mut self) -> Self {
+        self.restore_consumer_groups = false;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub partitions: usize,
+    pub records: u64,
+    pub group_offsets: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub topic: String,
+    pub records: u64,
+    pub group_offsets: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ArchiveHeader {
+    version: u32,
+    topic: String,
+    partitions: Vec<u32>,
+    exported_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct GroupOffset {
+    group_id: String,
+    partition: u32,
+    offset: u64,
+}
+
+/// One NDJSON line.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum NdjsonEntry {
+    Header(ArchiveHeader),
+    Record {
+        partition: u32,
+        #[serde(flatten)]
+        record: RecordWithOffset,
+    },
+    GroupOffset(GroupOffset),
+}
+
+enum ArchiveItem {
+    Header(ArchiveHeader),
+    Records(u32, Vec<RecordWithOffset>),
+    GroupOffset(GroupOffset),
+}
+
+fn archive_error(reason: impl Into<String>) -> FlashQError {
+    FlashQError::InvalidArchive {
+        reason: reason.into(),
+    }
+}
+
+fn io_error(e: std::io::Error) -> FlashQError {
+    archive_error(format!("I/O error: {e}"))
+}
+
+fn parse_time(value: &str, field: &str) -> Result<DateTime<FixedOffset>, FlashQError> {
+    DateTime::parse_from_rfc3339(value)
+        .map_err(|e| archive_error(format!("invalid {field} '{value}': {e}")))
+}
+
+// =============================================================================
+// ENCODING
+// =============================================================================
+
+struct ArchiveWriter<W: Write> {
+    out: W,
+    format: ArchiveFormat,
+    compression: CompressionCodec,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    fn new(
+        mut out: W,
+        format: ArchiveFormat,
+        compression: CompressionCodec,
+    ) -> Result<Self, FlashQError> {
+        if format == ArchiveFormat::Binary {
+            out.write_all(BINARY_ARCHIVE_MAGIC).map_err(io_error)?;
+        }
+        Ok(Self {
+            out,
+            format,
+            compression,
+        })
+    }
+
+    fn write_header(&mut self, header: ArchiveHeader) -> Result<(), FlashQError> {
+        match self.format {
+            ArchiveFormat::Ndjson => self.write_line(&NdjsonEntry::Header(header)),
+            ArchiveFormat::Binary => self.write_json_frame(FRAME_HEADER, &header),
+        }
+    }
+
+    fn write_records(
+        &mut self,
+        partition: u32,
+        records: &[RecordWithOffset],
+    ) -> Result<(), FlashQError> {
+        match self.format {
+            ArchiveFormat::Ndjson => {
+                for record in records {
+                    self.write_line(&NdjsonEntry::Record {
+                        partition,
+                        record: record.clone(),
+                    })?;
+                }
+                Ok(())
+            }
+            ArchiveFormat::Binary => {
+                for run in
+                    records.chunk_by(|a, b| b.offset == a.offset + 1 && b.timestamp == a.timestamp)
+                {
+                    let batch: Vec<Record> = run.iter().map(|r| r.record.clone()).collect();
+                    let mut body = partition.to_be_bytes().to_vec();
+                    encode_batch_into(
+                        &mut body,
+                        &batch,
+                        run[0].offset,
+                        &run[0].timestamp,
+                        self.compression,
+                    )?;
+                    self.write_frame(FRAME_RECORDS, &body)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn write_group_offset(&mut self, offset: GroupOffset) -> Result<(), FlashQError> {
+        match self.format {
+            ArchiveFormat::Ndjson => self.write_line(&NdjsonEntry::GroupOffset(offset)),
+            ArchiveFormat::Binary => self.write_json_frame(FRAME_GROUP_OFFSET, &offset),
+        }
+    }
+
+    fn finish(mut self) -> Result<(), FlashQError> {
+        self.out.flush().map_err(io_error)
+    }
+
+    fn write_line(&mut self, entry: &NdjsonEntry) -> Result<(), FlashQError> {
+        serde_json::to_writer(&mut self.out, entry)
+            .map_err(|e| archive_error(format!("failed to encode entry: {e}")))?;
+        self.out.write_all(b"\n").map_err(io_error)
+    }
+
+    fn write_json_frame<T: Serialize>(&mut self, kind: u8, value: &T) -> Result<(), FlashQError> {
+        let body = serde_json::to_vec(value)
+            .map_err(|e| archive_error(format!("failed to encode entry: {e}")))?;
+        self.write_frame(kind, &body)
+    }
+
+    fn write_frame(&mut self, kind: u8, body: &[u8]) -> Result<(), FlashQError> {
+        self.out.write_all(&[kind]).map_err(io_error)?;
+        self.out
+            .write_all(&(body.len() as u32).to_be_bytes())
+            .map_err(io_error)?;
+        self.out.write_all(body).map_err(io_error)
+    }
+}
+
+// =============================================================================
+// DECODING
+// =============================================================================
+
+struct ArchiveReader<R: BufRead> {
+    input: R,
+    format: ArchiveFormat,
+    line: String,
+}
+
+impl<R: BufRead> ArchiveReader<R> {
+    fn new(mut input: R) -> Result<Self, FlashQError> {
+        let format = if input
+            .fill_buf()
+            .map_err(io_error)?
+            .starts_with(BINARY_ARCHIVE_MAGIC)
+        {
+            input.consume(BINARY_ARCHIVE_MAGIC.len());
+            ArchiveFormat::Binary
+        } else {
+            ArchiveFormat::Ndjson
+        };
+        Ok(Self {
+            input,
+            format,
+            line: String::new(),
+        })
+    }
+
+    fn next_item(&mut self) -> Result<Option<ArchiveItem>, FlashQError> {
+        match self.format {
+            ArchiveFormat::Ndjson => self.next_line(),
+            ArchiveFormat::Binary => self.next_frame(),
+        }
+    }
+
+    fn next_line(&mut self) -> Result<Option<ArchiveItem>, FlashQError> {
+        loop {
+            self.line.clear();
+            if self.input.read_line(&mut self.line).map_err(io_error)? == 0 {
+                return Ok(None);
+            }
+            if self.line.trim().is_empty() {
+                continue;
+            }
+            let entry: NdjsonEntry = serde_json::from_str(&self.line)
+                .map_err(|e| archive_error(format!("malformed archive line: {e}")))?;
+            return Ok(Some(match entry {
+                NdjsonEntry::Header(header) => ArchiveItem::Header(header),
+                NdjsonEntry::Record { partition, record } => {
+                    ArchiveItem::Records(partition, vec![record])
+                }
+                NdjsonEntry::GroupOffset(offset) => ArchiveItem::GroupOffset(offset),
+            }));
+        }
+    }
+
+    fn next_frame(&mut self) -> Result<Option<ArchiveItem>, FlashQError> {
+        let mut kind = [0u8; 1];
+        if self.input.read(&mut kind).map_err(io_error)? == 0 {
+            return Ok(None);
+        }
+        let mut len = [0u8; 4];
+        self.input
+            .read_exact(&mut len)
+            .map_err(|e| archive_error(format!("truncated frame header: {e}")))?;
+        // The length is untrusted, so let the body grow with what the input actually holds
+        let len = u32::from_be_bytes(len) as u64;
+        let mut body = Vec::new();
+        (&mut self.input)
+            .take(len)
+            .read_to_end(&mut body)
+            .map_err(io_error)?;
+        if body.len() as u64 != len {
+            return Err(archive_error(format!(
+                "truncated frame: expected {len} bytes, found {}",
+                body.len()
+            )));
+        }
+
+        match kind[0] {
+            FRAME_HEADER => Ok(Some(ArchiveItem::Header(decode_json_frame(&body)?))),
+            FRAME_GROUP_OFFSET => Ok(Some(ArchiveItem::GroupOffset(decode_json_frame(&body)?))),
+            FRAME_RECORDS => {
+                let Some((partition, batch)) = body.split_first_chunk::<4>() else {
+                    return Err(archive_error(format!(
+                        "truncated records frame: {} bytes is too short for a partition id",
+                        body.len()
+                    )));
+                };
+                let (_, records) = read_batch(&mut &batch[..])?;
+                Ok(Some(ArchiveItem::Records(
+                    u32::from_be_bytes(*partition),
+                    records,
+                )))
+            }
+            other => Err(archive_error(format!("unknown frame kind {other}"))),
+        }
+    }
+}
+
+fn decode_json_frame<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<T, FlashQError> {
+    serde_json::from_slice(body).map_err(|e| archive_error(format!("malformed archive frame: {e}")))
+}
+
+/// Offsets a partition's records were imported at, kept as runs so committed consumer
+/// offsets can be moved along with the records they point at.
+#[derive(Default)]
+struct OffsetTranslation {
+    /// (original first offset, imported first offset, run length), in import order.
+    runs: Vec<(u64, u64, u64)>,
+}
+
+impl OffsetTranslation {
+    fn record(&mut self, original: u64, imported: u64) {
+        if let Some((orig_start, new_start, len)) = self.runs.last_mut() {
+            if *orig_start + *len == original && *new_start + *len == imported {
+                *len += 1;
+                return;
+            }
+        }
+        self.runs.push((original, imported, 1));
+    }
+
+    /// The imported offset of the first record at or after `original`, or `end` if every
+    /// imported record precedes it.
+    fn translate(&self, original: u64, end: u64) -> u64 {
+        self.runs
+            .iter()
+            .find(|(orig_start, _, len)| orig_start + len > original)
+            .map(|&(orig_start, new_start, _)| new_start + original.saturating_sub(orig_start))
+            .unwrap_or(end)
+    }
+}
+
+// =============================================================================
+// FLASHQ API
+// =============================================================================
+
+impl FlashQ {
+    /// Write `topic` to `writer` as an archive according to `options`.
+    #[tracing::instrument(level = "info", skip(self, options, writer), fields(topic = %topic, format = ?options.format))]
+    pub fn export_topic<W: Write>(
+        &self,
+        topic: &str,
+        options: &ExportOptions,
+        writer: W,
+    ) -> Result<ExportSummary, FlashQError> {
+        let topic_log = self.topic_log(topic)?;
+        let to_time = options
+            .to_time
+            .as_deref()
+            .map(|t| parse_time(t, "to_time"))
+            .transpose()?;
+        if let Some(from_time) = options.from_time.as_deref() {
+            parse_time(from_time, "from_time")?;
+        }
+
+        let partitions = match &options.partitions {
+            Some(partitions) => partitions.clone(),
+            None => topic_log.read().partition_ids(),
+        };
+
+        let mut out = ArchiveWriter::new(writer, options.format, options.compression)?;
+        out.write_header(ArchiveHeader {
+            version: ARCHIVE_VERSION,
+            topic: topic.to_string(),
+            partitions: partitions.iter().map(|p| p.0).collect(),
+            exported_at: chrono::Utc::now().to_rfc3339(),
+        })?;
+
+        let mut summary = ExportSummary {
+            partitions: partitions.len(),
+            ..Default::default()
+        };
+        for &partition_id in &partitions {
+            let (start, end) = {
+                let log = topic_log.read();
+                let mut start = options.from_offset.unwrap_or(0);
+                if let Some(from_time) = options.from_time.as_deref() {
+                    match log
+                        .read_from_partition_timestamp(partition_id, from_time, Some(1))?
+                        .first()
+                    {
+                        Some(first) => start = start.max(first.offset),
+                        None => continue,
+                    }
+                }
+                let end = options
+                    .to_offset
+                    .map_or(log.partition_next_offset(partition_id), |to| {
+                        to.min(log.partition_next_offset(partition_id))
+                    });
+                (start, end)
+            };
+
+            let mut offset = start;
+            while offset < end {
+                let chunk = topic_log.read().read_from_partition(
+                    partition_id,
+                    offset,
+                    Some(CHUNK_RECORDS),
+                )?;
+                let Some(last) = chunk.last() else {
+                    break;
+                };
+                offset = last.offset + 1;
+
+                let selected: Vec<RecordWithOffset> = chunk
+                    .into_iter()
+                    .filter(|r| r.offset < end)
+                    .filter(|r| match &to_time {
+                        Some(to_time) => {
+                            DateTime::parse_from_rfc3339(&r.timestamp).is_ok_and(|ts| ts < *to_time)
+                        }
+                        None => true,
+                    })
+                    .collect();
+                summary.records += selected.len() as u64;
+                out.write_records(partition_id.0, &selected)?;
+            }
+        }
+
+        if options.include_consumer_groups {
+            for group in self.consumer_groups.iter() {
+                let group = group.value().read();
+                let mut offsets: Vec<GroupOffset> = group
+                    .get_all_offsets_partitioned()
+                    .into_iter()
+                    .filter(|((t, p), _)| t == topic && partitions.contains(p))
+                    .map(|((_, p), offset)| GroupOffset {
+                        group_id: group.group_id().to_string(),
+                        partition: p.0,
+                        offset,
+                    })
+                    .collect();
+                offsets.sort_by_key(|o| o.partition);
+                for offset in offsets {
+                    out.write_group_offset(offset)?;
+                    summary.group_offsets += 1;
+                }
+            }
+        }
+
+        out.finish()?;
+        info!(
+            "Exported {} records from {} partitions of '{topic}'",
+            summary.records, summary.partitions
+        );
+        Ok(summary)
+    }
+
+    /// Replay an archive written by [`export_topic`](Self::export_topic) into this queue.
+    #[tracing::instrument(level = "info", skip(self, options, reader))]
+    pub fn import_archive<R: Read>(
+        &self,
+        reader: R,
+        options: &ImportOptions,
+    ) -> Result<ImportSummary, FlashQError> {
+        let mut input = ArchiveReader::new(BufReader::new(reader))?;
+        let header = match input.next_item()? {
+            Some(ArchiveItem::Header(header)) => header,
+            _ => return Err(archive_error("archive does not start with a header")),
+        };
+        if header.version > ARCHIVE_VERSION {
+            return Err(archive_error(format!(
+                "unsupported archive version {}",
+                header.version
+            )));
+        }
+
+        let topic = options.topic.clone().unwrap_or(header.topic);
+        let topic_log = self.get_or_create_topic_log(&topic)?;
+        let mut summary = ImportSummary {
+            topic: topic.clone(),
+            ..Default::default()
+        };
+
+        let mut pending: Vec<(u32, Vec<RecordWithOffset>)> = Vec::new();
+        let mut translations: std::collections::HashMap<u32, OffsetTranslation> =
+            Default::default();
+        let mut group_offsets = Vec::new();
+        while let Some(item) = input.next_item()? {
+            match item {
+                ArchiveItem::Header(_) => return Err(archive_error("duplicate archive header")),
+                ArchiveItem::Records(partition, records) => {
+                    summary.records += records.len() as u64;
+                    let buffered = match pending.iter_mut().find(|(p, _)| *p == partition) {
+                        Some((_, buffered)) => buffered,
+                        None => {
+                            pending.push((partition, Vec::new()));
+                            &mut pending.last_mut().unwrap().1
+                        }
+                    };
+                    buffered.extend(records);
+                    if buffered.len() >= CHUNK_RECORDS {
+                        let batch = std::mem::take(buffered);
+                        let translation = translations.entry(partition).or_default();
+                        replay_records(&topic_log, partition, batch, options, translation)?;
+                    }
+                }
+                ArchiveItem::GroupOffset(offset) => group_offsets.push(offset),
+            }
+        }
+        for (partition, batch) in pending {
+            let translation = translations.entry(partition).or_default();
+            replay_records(&topic_log, partition, batch, options, translation)?;
+        }
+
+        if options.restore_consumer_groups {
+            for GroupOffset {
+                group_id,
+                partition,
+                offset,
+            } in group_offsets
+            {
+                let partition_id = PartitionId::new(partition);
+                let offset = if options.preserve_offsets {
+                    offset
+                } else {
+                    let end = topic_log.read().partition_next_offset(partition_id);
+                    translations
+                        .get(&partition)
+                        .map_or(end, |translation| translation.translate(offset, end))
+                };
+                let group = self.get_or_create_consumer_group(&group_id)?;
+                group
+                    .write()
+                    .set_offset_partition(topic.clone(), partition_id, offset);
+                summary.group_offsets += 1;
+            }
+        }
+
+        info!(
+            "Imported {} records and {} consumer group offsets into '{topic}'",
+            summary.records, summary.group_offsets
+        );
+        Ok(summary)
+    }
+
+    /// Copy the storage backend's data into `dest` while this queue keeps running. For the
+    /// file backend see [`flashq_storage::file::snapshot::snapshot_data_dir`] for what the
+    /// snapshot contains; backends without snapshot support return `Unavailable`.
+    #[tracing::instrument(level = "info", skip(self, dest), fields(dest = %dest.as_ref().display()))]
+    pub fn snapshot<P: AsRef<std::path::Path>>(
+        &self,
+        dest: P,
+    ) -> Result<SnapshotSummary, FlashQError> {
+        Ok(self.storage_backend.snapshot(dest.as_ref())?)
+    }
+
+    fn topic_log(&self, topic: &str) -> Result<Arc<RwLock<dyn TopicLog>>, FlashQError> {
+        self.topics
+            .get(topic)
+            .map(|entry| Arc::clone(entry.value()))
+            .ok_or_else(|| FlashQError::TopicNotFound {
+                topic: topic.to_string(),
+            })
+    }
+
+    fn get_or_create_topic_log(
+        &self,
+        topic: &str,
+    ) -> Result<Arc<RwLock<dyn TopicLog>>, FlashQError> {
+        if let Some(entry) = self.topics.get(topic) {
+            return Ok(Arc::clone(entry.value()));
+        }
+        let created = self.storage_backend.create_topic_log(topic)?;
+        Ok(Arc::clone(
+            self.topics
+                .entry(topic.to_string())
+                .or_insert(created)
+                .value(),
+        ))
+    }
+
+    fn get_or_create_consumer_group(
+        &self,
+        group_id: &str,
+    ) -> Result<Arc<RwLock<dyn crate::ConsumerGroup>>, FlashQError> {
+        match self.create_consumer_group(group_id.to_string()) {
+            Ok(()) | Err(FlashQError::ConsumerGroupAlreadyExists { .. }) => {}
+            Err(e) => return Err(e),
+        }
+        self.consumer_groups
+            .get(group_id)
+            .map(|entry| Arc::clone(entry.value()))
+            .ok_or_else(|| FlashQError::ConsumerGroupNotFound {
+                group_id: group_id.to_string(),
+            })
+    }
+}
+
+/// Append one buffered chunk of archived records to `partition`, keeping offsets and
+/// timestamps as `options` asks.
+fn replay_records(
+    topic_log: &RwLock<dyn TopicLog>,
+    partition: u32,
+    mut records: Vec<RecordWithOffset>,
+    options: &ImportOptions,
+    translation: &mut OffsetTranslation,
+) -> Result<(), FlashQError> {
+    let partition_id = PartitionId::new(partition);
+    let mut log = topic_log.write();
+    debug!(
+        "Replaying {} records into partition {partition}",
+        records.len()
+    );
+
+    if !options.preserve_offsets && !options.preserve_timestamps {
+        let first = log.partition_next_offset(partition_id);
+        for (i, record) in records.iter().enumerate() {
+            translation.record(record.offset, first + i as u64);
+        }
+        log.append_batch_partition(
+            partition_id,
+            records.into_iter().map(|r| r.record).collect(),
+        )?;
+        return Ok(());
+    }
+
+    if !options.preserve_timestamps {
+        let now = chrono::Utc::now().to_rfc3339();
+        for record in &mut records {
+            record.timestamp = now.clone();
+        }
+    }
+    if !options.preserve_offsets {
+        let first = log.partition_next_offset(partition_id);
+        for (i, record) in records.iter_mut().enumerate() {
+            translation.record(record.offset, first + i as u64);
+            record.offset = first + i as u64;
+        }
+    }
+    log.append_batch_with_offsets_partition(partition_id, records)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Header;
+
+    fn record(i: u64) -> Record {
+        Record::new(
+            Some(format!("key-{i}")),
+            format!("value-{i}"),
+            Some(vec![
+                Header::new("n", i.to_string()),
+                Header::new("raw", vec![0xff]),
+            ]),
+        )
+    }
+
+    fn queue_with_records(topic: &str, count: u64) -> FlashQ {
+        let queue = FlashQ::new();
+        queue
+            .post_records(topic.to_string(), (0..count).map(record).collect())
+            .unwrap();
+        queue
+    }
+
+    fn export(queue: &FlashQ, topic: &str, options: &ExportOptions) -> Vec<u8> {
+        let mut archive = Vec::new();
+        queue.export_topic(topic, options, &mut archive).unwrap();
+        archive
+    }
+
+    #[test]
+    fn ndjson_round_trip_restores_records_and_group_offsets() {
+        let source = queue_with_records("orders", 10);
+        source.create_consumer_group("billing".to_string()).unwrap();
+        source
+            .update_consumer_group_offset("billing", "orders".to_string(), 6)
+            .unwrap();
+
+        let archive = export(&source, "orders", &ExportOptions::default());
+        let text = String::from_utf8(archive.clone()).unwrap();
+        assert_eq!(text.lines().count(), 12);
+        assert!(text.starts_with("{\"type\":\"header\""));
+
+        let target = FlashQ::new();
+        let summary = target
+            .import_archive(archive.as_slice(), &ImportOptions::default())
+            .unwrap();
+
+        assert_eq!(summary.records, 10);
+        assert_eq!(summary.group_offsets, 1);
+        let records = target.poll_records("orders", None).unwrap();
+        assert_eq!(records.len(), 10);
+        assert_eq!(records[4].record, record(4));
+        assert_eq!(
+            target
+                .get_consumer_group_offset("billing", "orders")
+                .unwrap(),
+            6
+        );
+    }
+
+    #[test]
+    fn binary_range_import_preserves_offsets_and_timestamps() {
+        let source = queue_with_records("orders", 10);
+        let options = ExportOptions::default()
+            .with_format(ArchiveFormat::Binary)
+            .with_compression(CompressionCodec::Lz4)
+            .with_offset_range(Some(3), Some(8));
+        let archive = export(&source, "orders", &options);
+        assert!(archive.starts_with(BINARY_ARCHIVE_MAGIC));
+
+        let target = FlashQ::new();
+        let options = ImportOptions::default()
+            .into_topic("orders-copy")
+            .preserve_offsets()
+            .preserve_timestamps();
+        target.import_archive(archive.as_slice(), &options).unwrap();
+
+        let original = source
+            .poll_records_from_offset("orders", 3, Some(5))
+            .unwrap();
+        let imported = target.poll_records("orders-copy", None).unwrap();
+        assert_eq!(imported, original);
+        assert_eq!(target.get_high_water_mark("orders-copy"), 8);
+    }
+
+    #[test]
+    fn import_into_populated_topic_moves_group_offsets_with_records() {
+        let source = queue_with_records("orders", 10);
+        source.create_consumer_group("billing".to_string()).unwrap();
+        source
+            .update_consumer_group_offset("billing", "orders".to_string(), 7)
+            .unwrap();
+        let archive = export(
+            &source,
+            "orders",
+            &ExportOptions::default().with_offset_range(Some(5), None),
+        );
+
+        let target = queue_with_records("orders", 3);
+        target
+            .import_archive(archive.as_slice(), &ImportOptions::default())
+            .unwrap();
+
+        // Source offsets 5..10 land at 3..8, so the group's offset 7 becomes 5
+        let imported = target.poll_records_from_offset("orders", 3, None).unwrap();
+        assert_eq!(imported[0].record, record(5));
+        assert_eq!(
+            target
+                .get_consumer_group_offset("billing", "orders")
+                .unwrap(),
+            5
+        );
+    }
+
+    #[test]
+    fn preserving_offsets_below_the_target_end_is_rejected() {
+        let source = queue_with_records("orders", 4);
+        let archive = export(&source, "orders", &ExportOptions::default());
+        let target = queue_with_records("orders", 2);
+
+        let result = target.import_archive(
+            archive.as_slice(),
+            &ImportOptions::default().preserve_offsets(),
+        );
+
+        assert!(matches!(result, Err(FlashQError::Storage(_))));
+        assert_eq!(target.get_high_water_mark("orders"), 2);
+    }
+
+    #[test]
+    fn time_range_export_selects_records_by_timestamp() {
+        let queue = queue_with_records("orders", 3);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let cutoff = chrono::Utc::now().to_rfc3339();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        queue
+            .post_records("orders".to_string(), (3..5).map(record).collect())
+            .unwrap();
+
+        let before = ExportOptions::default().with_time_range(None, Some(cutoff.clone()));
+        let after = ExportOptions::default().with_time_range(Some(cutoff), None);
+        let mut sink = Vec::new();
+
+        assert_eq!(
+            queue
+                .export_topic("orders", &before, &mut sink)
+                .unwrap()
+                .records,
+            3
+        );
+        assert_eq!(
+            queue
+                .export_topic("orders", &after, &mut sink)
+                .unwrap()
+                .records,
+            2
+        );
+    }
+
+    #[test]
+    fn malformed_archives_are_reported() {
+        let queue = FlashQ::new();
+
+        let no_header = queue.import_archive(
+            &b"{\"type\":\"group_offset\",\"group_id\":\"g\",\"partition\":0,\"offset\":1}\n"[..],
+            &ImportOptions::default(),
+        );
+        let garbage = queue.import_archive(&b"not json"[..], &ImportOptions::default());
+
+        assert!(matches!(no_header, Err(FlashQError::InvalidArchive { .. })));
+        assert!(matches!(garbage, Err(FlashQError::InvalidArchive { .. })));
+    }
+
+    #[test]
+    fn malformed_binary_frames_are_reported() {
+        let queue = FlashQ::new();
+        let frame = |kind: u8, len: u32, body: &[u8]| {
+            let mut archive = BINARY_ARCHIVE_MAGIC.to_vec();
+            archive.push(kind);
+            archive.extend_from_slice(&len.to_be_bytes());
+            archive.extend_from_slice(body);
+            archive
+        };
+        let reason = |archive: Vec<u8>| match queue
+            .import_archive(archive.as_slice(), &ImportOptions::default())
+        {
+            Err(FlashQError::InvalidArchive { reason }) => reason,
+            other => panic!("expected an invalid archive, got {other:?}"),
+        };
+
+        // A length far beyond the input is reported rather than allocated up front
+        let oversized = reason(frame(FRAME_HEADER, u32::MAX, b"{}"));
+        assert!(oversized.starts_with("truncated frame"), "{oversized}");
+
+        let short_records = reason(frame(FRAME_RECORDS, 2, &[0, 0]));
+        assert!(
+            short_records.starts_with("truncated records frame"),
+            "{short_records}"
+        );
+
+        let unknown = reason(frame(9, 0, &[]));
+        assert_eq!(unknown, "unknown frame kind 9");
+    }
+
+    #[test]
+    fn snapshot_needs_a_file_backend() {
+        let queue = FlashQ::new();
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(queue.snapshot(dir.path().join("snap")).is_err());
+    }
+}
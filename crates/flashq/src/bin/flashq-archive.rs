@@ -0,0 +1,157 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand};
+use flashq::archive::{ArchiveFormat, ExportOptions, ImportOptions};
+use flashq::{CompressionCodec, FlashQ, FlashQError, PartitionId, StorageBackend};
+use flashq_storage::SyncMode;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "flashq-archive",
+    version,
+    author,
+    about = "Export topics to portable archives and import them into a data directory"
+)]
+struct Cli {
+    /// Broker data directory; the broker must be stopped while this runs
+    #[arg(long, default_value = "./data")]
+    data_dir: PathBuf,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Write a topic, or a range of it, to an archive
+    Export(ExportCmd),
+    /// Replay an archive into the data directory
+    Import(ImportCmd),
+}
+
+#[derive(Args, Debug)]
+struct ExportCmd {
+    #[arg(long)]
+    topic: String,
+    /// Archive file to write; stdout when omitted
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// ndjson or binary
+    #[arg(long, default_value = "ndjson")]
+    format: ArchiveFormat,
+    /// Batch codec for binary archives: none, gzip, snappy, lz4 or zstd
+    #[arg(long, default_value = "none")]
+    compression: CompressionCodec,
+    /// Partition to export; repeat for several (default: all)
+    #[arg(long = "partition")]
+    partitions: Vec<u32>,
+    #[arg(long)]
+    from_offset: Option<u64>,
+    /// First offset not to export
+    #[arg(long)]
+    to_offset: Option<u64>,
+    /// RFC3339 timestamp of the earliest record to export
+    #[arg(long)]
+    from_time: Option<String>,
+    /// RFC3339 timestamp records must be older than
+    #[arg(long)]
+    to_time: Option<String>,
+    /// Leave consumer group offsets out of the archive
+    #[arg(long)]
+    no_consumer_groups: bool,
+}
+
+#[derive(Args, Debug)]
+struct ImportCmd {
+    /// Archive file to read
+    input: PathBuf,
+    /// Import into this topic instead of the archived one
+    #[arg(long)]
+    topic: Option<String>,
+    #[arg(long)]
+    preserve_offsets: bool,
+    #[arg(long)]
+    preserve_timestamps: bool,
+    /// Skip restoring consumer group offsets
+    #[arg(long)]
+    no_consumer_groups: bool,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), FlashQError> {
+    let backend = StorageBackend::new_file_with_path(SyncMode::Immediate, &cli.data_dir)?;
+    let queue = FlashQ::with_storage_backend(backend);
+
+    match cli.command {
+        Commands::Export(cmd) => {
+            let mut options = ExportOptions::default()
+                .with_format(cmd.format)
+                .with_compression(cmd.compression)
+                .with_offset_range(cmd.from_offset, cmd.to_offset)
+                .with_time_range(cmd.from_time, cmd.to_time);
+            if !cmd.partitions.is_empty() {
+                options = options
+                    .with_partitions(cmd.partitions.into_iter().map(PartitionId::new).collect());
+            }
+            if cmd.no_consumer_groups {
+                options = options.without_consumer_groups();
+            }
+
+            let out: Box<dyn Write> = match &cmd.output {
+                Some(path) => Box::new(File::create(path).map_err(|e| {
+                    FlashQError::Storage(flashq_storage::StorageError::from_io_error(
+                        e,
+                        &format!("create {}", path.display()),
+                    ))
+                })?),
+                None => Box::new(std::io::stdout().lock()),
+            };
+            let summary = queue.export_topic(&cmd.topic, &options, BufWriter::new(out))?;
+            eprintln!(
+                "exported {} records from {} partitions and {} consumer group offsets",
+                summary.records, summary.partitions, summary.group_offsets
+            );
+        }
+        Commands::Import(cmd) => {
+            let mut options = ImportOptions::default();
+            if let Some(topic) = cmd.topic {
+                options = options.into_topic(topic);
+            }
+            if cmd.preserve_offsets {
+                options = options.preserve_offsets();
+            }
+            if cmd.preserve_timestamps {
+                options = options.preserve_timestamps();
+            }
+            if cmd.no_consumer_groups {
+                options = options.without_consumer_groups();
+            }
+
+            let input = File::open(&cmd.input).map_err(|e| {
+                FlashQError::Storage(flashq_storage::StorageError::from_io_error(
+                    e,
+                    &format!("open {}", cmd.input.display()),
+                ))
+            })?;
+            let summary = queue.import_archive(input, &options)?;
+            eprintln!(
+                "imported {} records and {} consumer group offsets into '{}'",
+                summary.records, summary.group_offsets, summary.topic
+            );
+        }
+    }
+    Ok(())
+}
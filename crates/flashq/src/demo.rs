@@ -71,6 +71,7 @@ fn post_record_interactive(queue: &FlashQ, topics_created: &mut HashMap<String,
         key: None,
         value: content.clone(),
         headers: None,
+        create_time: None,
     };
 
     match queue.post_records(topic.clone(), vec![record]) {
@@ -143,8 +144,12 @@ fn poll_records_interactive(queue: &FlashQ) {
                         && !headers.is_empty()
                     {
                         println!("  🏷️  Headers:");
-                        for (header_key, header_value) in headers {
-                            println!("    {header_key}: \"{header_value}\"");
+                        for header in headers {
+                            println!(
+                                "    {}: \"{}\"",
+                                header.key,
+                                String::from_utf8_lossy(&header.value)
+                            );
                         }
                     }
 
@@ -202,6 +207,7 @@ fn run_demo(queue: &FlashQ, topics_created: &mut HashMap<String, usize>) {
             key: None,
             value: content.to_string(),
             headers: None,
+            create_time: None,
         };
         match queue.post_records(demo_topic.clone(), vec![record]) {
             Ok(record_id) => {
@@ -21,6 +21,9 @@ pub enum FlashQError {
         topic: String,
         max_offset: u64,
     },
+    InvalidArchive {
+        reason: String,
+    },
     Storage(StorageError),
 }
 
@@ -54,6 +57,7 @@ impl fmt::Display for FlashQError {
                     "Invalid offset {offset} for topic '{topic}', max offset is {max_offset}"
                 )
             }
+            FlashQError::InvalidArchive { reason } => write!(f, "Invalid archive: {reason}"),
             FlashQError::Storage(err) => write!(f, "Storage error: {err}"),
         }
     }
@@ -92,6 +96,7 @@ impl FlashQError {
                 | FlashQError::ConsumerGroupAlreadyExists { .. }
                 | FlashQError::ConsumerGroupCreationFailed { .. }
                 | FlashQError::InvalidOffset { .. }
+                | FlashQError::InvalidArchive { .. }
         )
     }
 }
@@ -3,14 +3,16 @@ use dashmap::mapref::entry::Entry::{Occupied, Vacant};
 use parking_lot::RwLock;
 use std::sync::Arc;
 
+pub mod archive;
 pub mod demo;
 pub mod error;
 pub mod telemetry;
 
 pub use error::FlashQError;
 pub use flashq_storage::{
-    ConsumerGroup, ConsumerOffsetStore, PartitionId, Record, RecordWithOffset, StorageBackend,
-    TopicLog,
+    CompressionCodec, ConsumerGroup, ConsumerOffsetStore, Header, IoPool, PartitionId, ReadBounds,
+    Record, RecordWithOffset, SegmentStats, StorageBackend, StorageError, StorageFactory,
+    TimestampType, TopicConfig, TopicLog, headers_from_map, headers_to_map, is_internal_topic,
 };
 
 pub use log::{debug, error, info, trace, warn};
@@ -19,10 +21,33 @@ pub use log::{debug, error, info, trace, warn};
 // QUEUE COMPONENTS
 // =============================================================================
 
+/// Offsets of one partition as reported by [`FlashQ::list_offsets`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionOffsets {
+    pub partition: PartitionId,
+    /// Log start offset: the lowest offset still held.
+    pub earliest: u64,
+    /// High-water mark: the offset the next record will get.
+    pub latest: u64,
+    /// First offset whose record timestamp is at or after the requested time.
+    pub offset_for_timestamp: Option<u64>,
+}
+
+/// A consumer group's committed offset for one partition, as reported by
+/// [`FlashQ::committed_offsets`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommittedOffset {
+    pub group_id: String,
+    pub topic: String,
+    pub partition: PartitionId,
+    pub offset: u64,
+}
+
 pub struct FlashQ {
     topics: Arc<DashMap<String, Arc<RwLock<dyn TopicLog>>>>,
     consumer_groups: Arc<DashMap<String, Arc<RwLock<dyn ConsumerGroup>>>>,
-    storage_backend: StorageBackend,
+    storage_backend: Box<dyn StorageFactory>,
+    io_pool: IoPool,
 }
 
 impl Default for FlashQ {
@@ -40,19 +65,25 @@ impl FlashQ {
 
     #[tracing::instrument(level = "info", skip(storage_backend))]
 
-    pub fn with_storage_backend(storage_backend: StorageBackend) -> Self {
+    pub fn with_storage_backend(storage_backend: impl StorageFactory + 'static) -> Self {
         debug!(
             "Creating FlashQ with storage backend: {:?}",
             storage_backend
         );
 
+        let io_pool = storage_backend.io_pool().unwrap_or_else(|e| {
+            warn!("Failed to start I/O pool, running storage calls inline: {e}");
+            IoPool::inline()
+        });
+
         let queue = FlashQ {
             topics: Arc::new(DashMap::new()),
             consumer_groups: Arc::new(DashMap::new()),
-            storage_backend,
+            storage_backend: Box::new(storage_backend),
+            io_pool,
         };
 
-        // For file backends, recover existing topics and consumer groups from disk
+        // Reopen whatever topics and consumer groups the backend already holds
         debug!("Starting topic recovery...");
         queue.recover_existing_topics().unwrap_or_else(|e| {
             warn!("Failed to recover existing topics: {e}");
@@ -75,16 +106,30 @@ impl FlashQ {
     #[tracing::instrument(level = "debug", skip(self, records), fields(topic = %topic, count = records.len()))]
 
     pub fn post_records(&self, topic: String, records: Vec<Record>) -> Result<u64, FlashQError> {
+        self.post_records_with_compression(topic, records, None)
+    }
+
+    /// Post records, optionally overriding the topic's batch compression codec.
+    #[tracing::instrument(level = "debug", skip(self, records), fields(topic = %topic, count = records.len(), compression = ?compression))]
+
+    pub fn post_records_with_compression(
+        &self,
+        topic: String,
+        records: Vec<Record>,
+        compression: Option<CompressionCodec>,
+    ) -> Result<u64, FlashQError> {
         let topic_log = self.topics.entry(topic.clone()).or_insert_with(|| {
             self.storage_backend
-                .create(&topic)
+                .create_topic_log(&topic)
                 .expect("Failed to create storage backend")
         });
 
         let mut topic_log_locked = topic_log.value().write();
-        let last = topic_log_locked
-            .append_batch(records)
-            .map_err(FlashQError::from)?;
+        let last = match compression {
+            Some(codec) => topic_log_locked.append_batch_with_compression(records, codec),
+            None => topic_log_locked.append_batch(records),
+        }
+        .map_err(FlashQError::from)?;
         Ok(last)
     }
 
@@ -98,19 +143,31 @@ impl FlashQ {
         self.poll_records_from_offset(topic, 0, count)
     }
 
+    pub fn poll_records_from_offset(
+        &self,
+        topic: &str,
+        offset: u64,
+        count: Option<usize>,
+    ) -> Result<Vec<RecordWithOffset>, FlashQError> {
+        self.poll_records_from_offset_bounded(topic, offset, ReadBounds::default(), count)
+    }
+
+    /// Poll from `offset`, stopping before `bounds.to_offset` and leaving out records stamped
+    /// at or after `bounds.to_time_ms`.
     #[tracing::instrument(level = "debug", skip(self), fields(topic = %topic, offset, count = ?count))]
 
-    pub fn poll_records_from_offset(
+    pub fn poll_records_from_offset_bounded(
         &self,
         topic: &str,
         offset: u64,
+        bounds: ReadBounds,
         count: Option<usize>,
     ) -> Result<Vec<RecordWithOffset>, FlashQError> {
         match self.topics.get(topic) {
             Some(topic_log) => topic_log
                 .value()
                 .read()
-                .get_records_from_offset(offset, count)
+                .read_from_partition_bounded(PartitionId::new(0), offset, bounds, count)
                 .map_err(FlashQError::from),
             None => Err(FlashQError::TopicNotFound {
                 topic: topic.to_string(),
@@ -118,19 +175,36 @@ impl FlashQ {
         }
     }
 
+    pub fn poll_records_from_time(
+        &self,
+        topic: &str,
+        from_time: &str,
+        count: Option<usize>,
+    ) -> Result<Vec<RecordWithOffset>, FlashQError> {
+        self.poll_records_from_time_bounded(topic, from_time, ReadBounds::default(), count)
+    }
+
+    /// Poll the records stamped at or after `from_time`, limited to `bounds`. With
+    /// `bounds.to_time_ms` set this returns the records in `[from_time, to_time)`.
     #[tracing::instrument(level = "debug", skip(self), fields(topic = %topic, from_time, count = ?count))]
 
-    pub fn poll_records_from_time(
+    pub fn poll_records_from_time_bounded(
         &self,
         topic: &str,
         from_time: &str,
+        bounds: ReadBounds,
         count: Option<usize>,
     ) -> Result<Vec<RecordWithOffset>, FlashQError> {
         match self.topics.get(topic) {
             Some(topic_log) => topic_log
                 .value()
                 .read()
-                .get_records_from_timestamp(from_time, count)
+                .read_from_partition_timestamp_bounded(
+                    PartitionId::new(0),
+                    from_time,
+                    bounds,
+                    count,
+                )
                 .map_err(FlashQError::from),
             None => Err(FlashQError::TopicNotFound {
                 topic: topic.to_string(),
@@ -144,13 +218,12 @@ impl FlashQ {
         match self.consumer_groups.entry(group_id.clone()) {
             Occupied(_) => Err(FlashQError::ConsumerGroupAlreadyExists { group_id }),
             Vacant(entry) => {
-                let consumer_group = self
-                    .storage_backend
-                    .create_consumer_group(&group_id)
-                    .map_err(|e| FlashQError::ConsumerGroupCreationFailed {
+                let consumer_group = self.storage_backend.create_group(&group_id).map_err(|e| {
+                    FlashQError::ConsumerGroupCreationFailed {
                         group_id: group_id.clone(),
                         reason: e.to_string(),
-                    })?;
+                    }
+                })?;
                 entry.insert(consumer_group);
                 Ok(())
             }
@@ -254,6 +327,110 @@ impl FlashQ {
         self.poll_records_from_time(topic, from_time, count)
     }
 
+    // =========================================================================
+    // ASYNC API
+    // =========================================================================
+    //
+    // These wrappers move the blocking storage calls onto the backend's I/O pool
+    // so async callers (the gRPC broker) never block their reactor on disk I/O.
+
+    /// Run `job` against this queue on the I/O pool.
+    pub async fn run_blocking<F, T>(self: &Arc<Self>, job: F) -> Result<T, FlashQError>
+    where
+        F: FnOnce(&FlashQ) -> Result<T, FlashQError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let queue = Arc::clone(self);
+        self.io_pool
+            .run(move || job(&queue))
+            .await
+            .map_err(FlashQError::from)?
+    }
+
+    pub async fn post_records_async(
+        self: &Arc<Self>,
+        topic: String,
+        records: Vec<Record>,
+        compression: Option<CompressionCodec>,
+    ) -> Result<u64, FlashQError> {
+        self.run_blocking(move |q| q.post_records_with_compression(topic, records, compression))
+            .await
+    }
+
+    pub async fn poll_records_from_offset_async(
+        self: &Arc<Self>,
+        topic: String,
+        offset: u64,
+        count: Option<usize>,
+    ) -> Result<Vec<RecordWithOffset>, FlashQError> {
+        self.poll_records_from_offset_bounded_async(topic, offset, ReadBounds::default(), count)
+            .await
+    }
+
+    pub async fn poll_records_from_offset_bounded_async(
+        self: &Arc<Self>,
+        topic: String,
+        offset: u64,
+        bounds: ReadBounds,
+        count: Option<usize>,
+    ) -> Result<Vec<RecordWithOffset>, FlashQError> {
+        self.run_blocking(move |q| {
+            q.poll_records_from_offset_bounded(&topic, offset, bounds, count)
+        })
+        .await
+    }
+
+    pub async fn poll_records_from_time_async(
+        self: &Arc<Self>,
+        topic: String,
+        from_time: String,
+        count: Option<usize>,
+    ) -> Result<Vec<RecordWithOffset>, FlashQError> {
+        self.poll_records_from_time_bounded_async(topic, from_time, ReadBounds::default(), count)
+            .await
+    }
+
+    pub async fn poll_records_from_time_bounded_async(
+        self: &Arc<Self>,
+        topic: String,
+        from_time: String,
+        bounds: ReadBounds,
+        count: Option<usize>,
+    ) -> Result<Vec<RecordWithOffset>, FlashQError> {
+        self.run_blocking(move |q| {
+            q.poll_records_from_time_bounded(&topic, &from_time, bounds, count)
+        })
+        .await
+    }
+
+    pub async fn create_consumer_group_async(
+        self: &Arc<Self>,
+        group_id: String,
+    ) -> Result<(), FlashQError> {
+        self.run_blocking(move |q| q.create_consumer_group(group_id))
+            .await
+    }
+
+    pub async fn update_consumer_group_offset_async(
+        self: &Arc<Self>,
+        group_id: String,
+        topic: String,
+        offset: u64,
+    ) -> Result<(), FlashQError> {
+        self.run_blocking(move |q| q.update_consumer_group_offset(&group_id, topic, offset))
+            .await
+    }
+
+    pub async fn list_offsets_async(
+        self: &Arc<Self>,
+        topic: String,
+        partitions: Vec<PartitionId>,
+        timestamp: Option<String>,
+    ) -> Result<Vec<PartitionOffsets>, FlashQError> {
+        self.run_blocking(move |q| q.list_offsets(&topic, &partitions, timestamp.as_deref()))
+            .await
+    }
+
     pub fn get_high_water_mark(&self, topic: &str) -> u64 {
         match self.topics.get(topic) {
             Some(topic_log) => topic_log.value().read().next_offset(),
@@ -261,21 +438,144 @@ impl FlashQ {
         }
     }
 
+    pub fn get_log_start_offset(&self, topic: &str) -> u64 {
+        let Some(topic_log) = self.topics.get(topic) else {
+            return 0;
+        };
+        let log = topic_log.value().read();
+        log.partition_start_offset(PartitionId::new(0))
+            .unwrap_or_else(|e| {
+                warn!("Failed to read log start offset for topic '{topic}': {e}");
+                0
+            })
+    }
+
+    /// Earliest and latest offsets of each requested partition (every partition when
+    /// `partitions` is empty), plus the first offset at or after `timestamp` when one is given.
+    #[tracing::instrument(level = "debug", skip(self), fields(topic = %topic))]
+    pub fn list_offsets(
+        &self,
+        topic: &str,
+        partitions: &[PartitionId],
+        timestamp: Option<&str>,
+    ) -> Result<Vec<PartitionOffsets>, FlashQError> {
+        let topic_log = self
+            .topics
+            .get(topic)
+            .ok_or_else(|| FlashQError::TopicNotFound {
+                topic: topic.to_string(),
+            })?;
+        let log = topic_log.value().read();
+        let partitions = if partitions.is_empty() {
+            log.partition_ids()
+        } else {
+            partitions.to_vec()
+        };
+
+        partitions
+            .into_iter()
+            .map(|partition| {
+                let offset_for_timestamp = match timestamp {
+                    Some(ts) => log.partition_offset_for_timestamp(partition, ts)?,
+                    None => None,
+                };
+                Ok(PartitionOffsets {
+                    partition,
+                    earliest: log.partition_start_offset(partition)?,
+                    latest: log.partition_next_offset(partition),
+                    offset_for_timestamp,
+                })
+            })
+            .collect()
+    }
+
+    /// Segment counts and sizes for `topic`; zeroes for unknown topics and backends without
+    /// segments.
+    pub fn segment_stats(&self, topic: &str) -> Result<SegmentStats, FlashQError> {
+        match self.topics.get(topic) {
+            Some(topic_log) => Ok(topic_log.value().read().segment_stats()?),
+            None => Ok(SegmentStats::default()),
+        }
+    }
+
+    /// Every committed offset of every consumer group.
+    pub fn committed_offsets(&self) -> Vec<CommittedOffset> {
+        self.consumer_groups
+            .iter()
+            .flat_map(|entry| {
+                let group_id = entry.key().clone();
+                entry
+                    .value()
+                    .read()
+                    .get_all_offsets_partitioned()
+                    .into_iter()
+                    .map(move |((topic, partition), offset)| CommittedOffset {
+                        group_id: group_id.clone(),
+                        topic,
+                        partition,
+                        offset,
+                    })
+            })
+            .collect()
+    }
+
+    /// Topics clients can see; internal topics such as `__consumer_offsets` are left out.
     pub fn get_topics(&self) -> Vec<String> {
         self.topics
+            .iter()
+            .map(|entry| entry.key().clone())
+            .filter(|topic| !is_internal_topic(topic))
+            .collect()
+    }
+
+    /// Ids of the consumer groups that exist.
+    pub fn get_consumer_groups(&self) -> Vec<String> {
+        self.consumer_groups
             .iter()
             .map(|entry| entry.key().clone())
             .collect()
     }
 
+    /// Fsync every topic log and consumer offset store, regardless of the configured sync
+    /// mode, so nothing acknowledged is lost when the process exits.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub fn sync_all(&self) -> Result<(), FlashQError> {
+        for entry in self.topics.iter() {
+            entry.value().write().sync()?;
+        }
+        for entry in self.consumer_groups.iter() {
+            entry.value().read().offset_store().sync()?;
+        }
+        Ok(())
+    }
+
+    /// Offload every topic's closed segments that have aged past the local retention window.
+    /// Segments are otherwise only offloaded when a topic rolls, so idle topics rely on this
+    /// being called periodically. A topic that fails is logged and skipped. Returns how many
+    /// segments were moved.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub fn offload_expired_segments(&self) -> usize {
+        self.topics
+            .iter()
+            .map(
+                |entry| match entry.value().write().offload_expired_segments() {
+                    Ok(offloaded) => offloaded,
+                    Err(e) => {
+                        warn!("Failed to offload segments of topic '{}': {e}", entry.key());
+                        0
+                    }
+                },
+            )
+            .sum()
+    }
+
     #[tracing::instrument(level = "info", skip(self))]
 
-    /// Recover existing topics from disk for file storage backends
+    /// Recover the topics the storage backend already holds
     fn recover_existing_topics(&self) -> Result<(), Box<dyn std::error::Error>> {
-        // Discover existing topics using the storage backend
         let topic_names = self
             .storage_backend
-            .discover_topics()
+            .list_topics()
             .map_err(|e| format!("Failed to discover topics: {e}"))?;
 
         debug!(
@@ -286,7 +586,7 @@ impl FlashQ {
 
         // Create TopicLog instances for discovered topics
         for topic_name in topic_names {
-            if let Ok(topic_log) = self.storage_backend.create(&topic_name) {
+            if let Ok(topic_log) = self.storage_backend.create_topic_log(&topic_name) {
                 debug!("Successfully recovered topic: {topic_name}");
                 self.topics.insert(topic_name, topic_log);
             } else {
@@ -299,43 +599,11 @@ impl FlashQ {
 
     #[tracing::instrument(level = "info", skip(self))]
 
-    /// Recover existing consumer groups from disk for file storage backends  
+    /// Recover the consumer groups the storage backend already holds
     fn recover_existing_consumer_groups(&self) -> Result<(), Box<dyn std::error::Error>> {
-        use std::fs;
-
-        // Only recover for file storage backends
-        let data_dir = match &self.storage_backend {
-            StorageBackend::File { data_dir, .. } => data_dir.clone(),
-            StorageBackend::Memory { .. } => return Ok(()), // No recovery needed for memory
-        };
-
-        let consumer_groups_dir = data_dir.join("consumer_groups");
-
-        // Check if consumer groups directory exists
-        if !consumer_groups_dir.exists() {
-            return Ok(());
-        }
-
-        // Scan for .json files (consumer group files)
-        let entries = fs::read_dir(&consumer_groups_dir)?;
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
-
-            if let Some(extension) = path.extension() {
-                if extension == "json" {
-                    if let Some(file_name) = path.file_stem() {
-                        if let Some(group_id) = file_name.to_str() {
-                            // Create ConsumerGroup for this group
-                            if let Ok(consumer_group) =
-                                self.storage_backend.create_consumer_group(group_id)
-                            {
-                                self.consumer_groups
-                                    .insert(group_id.to_string(), consumer_group);
-                            }
-                        }
-                    }
-                }
+        for group_id in self.storage_backend.list_consumer_groups()? {
+            if let Ok(consumer_group) = self.storage_backend.create_group(&group_id) {
+                self.consumer_groups.insert(group_id, consumer_group);
             }
         }
 
@@ -1,3 +1,8 @@
+use opentelemetry::trace::{TraceError, TracerProvider};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
 use tracing_log::LogTracer;
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
@@ -21,6 +26,63 @@ pub fn init() {
         .try_init();
 }
 
+/// Initialize tracing as [`init`] does, and also export spans over OTLP/gRPC to `endpoint`
+/// (e.g. "http://127.0.0.1:4317") under `service_name`.
+///
+/// Spans are batched and sent from a background thread through a gRPC channel that runs on
+/// the current Tokio runtime, so this must be called from within one. W3C trace context is
+/// installed as the global propagator. Keep the returned guard alive for as long as spans
+/// should be exported; dropping it flushes what is still buffered.
+pub fn init_with_otlp(endpoint: &str, service_name: &str) -> Result<OtlpGuard, TraceError> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(service_name.to_string())
+                .build(),
+        )
+        .build();
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let _ = LogTracer::init();
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let fmt_layer = fmt::layer().with_target(true).compact();
+    let otel_layer = tracing_opentelemetry::layer().with_tracer(provider.tracer("flashq"));
+    let _ = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt_layer)
+        .with(otel_layer)
+        .try_init();
+
+    Ok(OtlpGuard { provider })
+}
+
+/// Keeps the OTLP exporter from [`init_with_otlp`] running.
+pub struct OtlpGuard {
+    provider: SdkTracerProvider,
+}
+
+impl OtlpGuard {
+    /// Export all finished spans now rather than on the next batch interval.
+    pub fn flush(&self) {
+        if let Err(e) = self.provider.force_flush() {
+            tracing::warn!("Failed to flush OTLP spans: {e}");
+        }
+    }
+}
+
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("Failed to shut down OTLP exporter: {e}");
+        }
+    }
+}
+
 /// Initialize a no-op tracing subscriber for benchmarks.
 ///
 /// This sets up a subscriber that discards all events, ensuring zero overhead